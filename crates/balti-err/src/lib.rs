@@ -1,51 +1,533 @@
+use std::{
+    cell::RefCell,
+    future::{Future, poll_fn},
+    panic::Location,
+    pin::pin,
+    sync::Arc,
+};
+
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Broad category of an error, for callers that react differently per case
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorKind {
+    #[default]
+    Other,
+    /// Key lies outside the prefix the remote is scoped to
+    OutOfScope,
+    /// Item was deliberately left alone, it counts as skipped in a [`BatchReport`]
+    Skipped,
+    /// Bucket lives in another region than the remote is configured for
+    WrongRegion,
+    /// Temporary credentials of the remote ran out, they have to be replaced
+    ExpiredCredentials,
+    /// Provider doesn't implement the request
+    Unsupported,
+    /// Credentials aren't allowed to make the request
+    AccessDenied,
+    /// Endpoint didn't answer: the connection failed, timed out or a gateway in front
+    /// of it gave up
+    Unreachable,
+    /// Provider refused the request because a quota or usage cap of the account ran out
+    QuotaExceeded,
+    /// Item's batch paused before it went through, it counts as paused in a
+    /// [`BatchReport`] and can be resumed
+    Paused,
+    /// Destination is taken and the request wasn't allowed to overwrite it
+    AlreadyExists,
+    /// Object doesn't have the ETag the request was conditional on anymore
+    Modified,
+    /// Object isn't there anymore, deleted since it was listed
+    NotFound,
+}
+
 #[allow(warnings)]
 #[derive(Debug, Clone)]
 pub struct AppError {
     pub status: u16,
     pub message: String,
     pub req_id: String,
+    pub kind: ErrorKind,
+    /// What was being done when it failed, set where the error leaves `balti-s3`
+    pub context: Option<ErrorContext>,
+    /// Where the error was made, logged along with it
+    pub location: &'static Location<'static>,
+}
+
+/// Operation an error came out of and the remote it ran against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Like `list_objects`
+    pub op: &'static str,
+    pub remote: Arc<str>,
+    /// Id of the user operation the request ran for, see [`in_operation`]
+    pub operation: Option<Arc<str>>,
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} on {}", self.op, self.remote)
+    }
 }
 
 impl AppError {
     #[track_caller]
     pub fn message(message: impl Into<String>) -> Self {
-        let message = message.into();
-
-        let location = std::panic::Location::caller();
-        tracing::error!(
-            "Error [{}:{}:{}]: {}",
-            location.file(),
-            location.line(),
-            location.column(),
-            message,
-        );
-
         Self {
             status: 400,
-            message,
+            message: message.into(),
             req_id: "".into(),
+            kind: ErrorKind::Other,
+            context: None,
+            location: Location::caller(),
         }
     }
 
     #[track_caller]
     pub fn err(err: impl std::error::Error) -> Self {
-        let source = err.source();
-        let location = std::panic::Location::caller();
-        tracing::error!(
-            "Error [{}:{}:{}]: {} - {:?}",
-            location.file(),
-            location.line(),
-            location.column(),
-            err,
-            source,
-        );
-
         Self {
             status: 500,
             message: format!("{}", err),
             req_id: "".into(),
+            kind: ErrorKind::Other,
+            context: None,
+            location: Location::caller(),
+        }
+    }
+
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Labels the error with the operation and remote it came out of, replacing an
+    /// inner label. The user operation polled, or else the inner one, stays along.
+    pub fn with_context(mut self, op: &'static str, remote: &str) -> Self {
+        let inner = self.context.take().and_then(|context| context.operation);
+        self.context = Some(ErrorContext {
+            op,
+            remote: remote.into(),
+            operation: current_operation().or(inner),
+        });
+        self
+    }
+
+    /// Logs the error once it reaches the user, with `op`, `remote`, `operation` and
+    /// the `req_id` when there are. Skipped and paused items didn't fail, they log as
+    /// info.
+    pub fn log(&self) {
+        let op = self.context.as_ref().map(|context| context.op);
+        let remote = self.context.as_ref().map(|context| context.remote.as_ref());
+        let operation = self
+            .context
+            .as_ref()
+            .and_then(|context| context.operation.as_deref());
+        let req_id = (!self.req_id.is_empty()).then_some(self.req_id.as_str());
+        let location = self.location;
+        let message = match &self.context {
+            Some(context) => format!("Error in {context}: {}", self.message),
+            None => format!("Error: {}", self.message),
+        };
+        match self.kind {
+            ErrorKind::Skipped | ErrorKind::Paused => {
+                tracing::info!(op, remote, operation, req_id, %location, "{message}")
+            }
+            _ => tracing::error!(op, remote, operation, req_id, %location, "{message}"),
+        }
+    }
+}
+
+thread_local! {
+    static OPERATION: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+/// Runs `request` as part of the user operation `id`: errors labelled while it's
+/// polled carry the id, wherever the runtime polls it from
+pub async fn in_operation<F: Future>(id: Arc<str>, request: F) -> F::Output {
+    let mut request = pin!(request);
+    poll_fn(|cx| {
+        let outer = OPERATION.replace(Some(id.clone()));
+        let poll = request.as_mut().poll(cx);
+        OPERATION.set(outer);
+        poll
+    })
+    .await
+}
+
+/// Id of the user operation being polled, when there is one
+pub fn current_operation() -> Option<Arc<str>> {
+    OPERATION.with_borrow(|operation| operation.clone())
+}
+
+pub trait ResultExt {
+    /// [`AppError::with_context`] for the error of a result
+    fn with_context(self, op: &'static str, remote: &str) -> Self;
+}
+
+impl<T> ResultExt for AppResult<T> {
+    fn with_context(self, op: &'static str, remote: &str) -> Self {
+        self.map_err(|err| err.with_context(op, remote))
+    }
+}
+
+/// Per key outcome of an operation run over many objects
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub items: Vec<(Arc<str>, AppResult<()>)>,
+}
+
+impl BatchReport {
+    pub fn push(&mut self, key: Arc<str>, result: AppResult<()>) {
+        self.items.push((key, result));
+    }
+
+    /// [`AppError::log`] for every item that didn't go through
+    pub fn log(&self) {
+        for (_, result) in self.items.iter() {
+            if let Err(err) = result {
+                err.log();
+            }
+        }
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .count()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|(_, result)| matches!(result, Err(err) if err.kind == ErrorKind::Skipped))
+            .count()
+    }
+
+    pub fn paused(&self) -> usize {
+        self.paused_keys().count()
+    }
+
+    /// Items left for when the batch resumes
+    pub fn paused_keys(&self) -> impl Iterator<Item = &Arc<str>> {
+        self.items.iter().filter_map(|(key, result)| match result {
+            Err(err) if err.kind == ErrorKind::Paused => Some(key),
+            _ => None,
+        })
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failures().count()
+    }
+
+    /// Items that errored, skipped and paused ones excluded
+    pub fn failures(&self) -> impl Iterator<Item = (&Arc<str>, &AppError)> {
+        self.items.iter().filter_map(|(key, result)| match result {
+            Err(err) if !matches!(err.kind, ErrorKind::Skipped | ErrorKind::Paused) => {
+                Some((key, err))
+            }
+            _ => None,
+        })
+    }
+
+    /// Every item went through, nothing skipped or failed
+    pub fn is_clean(&self) -> bool {
+        self.items.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// One line summary of the counts, e.g. `3 succeeded, 1 skipped, 2 failed`. Paused
+    /// items are only mentioned when there are any.
+    pub fn summary(&self) -> String {
+        let summary = format!(
+            "{} succeeded, {} skipped, {} failed",
+            self.succeeded(),
+            self.skipped(),
+            self.failed()
+        );
+        match self.paused() {
+            0 => summary,
+            paused => format!("{summary}, {paused} paused"),
+        }
+    }
+
+    /// Plain text report with one line per item, for copying out of the app
+    pub fn report(&self) -> String {
+        let mut report = self.summary();
+        for (key, result) in self.items.iter() {
+            let line = match result {
+                Ok(_) => format!("OK      {key}"),
+                Err(err) if err.kind == ErrorKind::Skipped => {
+                    format!("SKIPPED {key}: {}", err.message)
+                }
+                Err(err) if err.kind == ErrorKind::Paused => {
+                    format!("PAUSED  {key}: {}", err.message)
+                }
+                Err(err) => format!("FAILED  {key}: {}", err.message),
+            };
+            report.push('\n');
+            report.push_str(&line);
+        }
+        report
+    }
+
+    /// `key,status,error` rows with a header, for a spreadsheet
+    pub fn csv(&self) -> String {
+        let mut csv = String::from("key,status,error\n");
+        for (key, result) in self.items.iter() {
+            let (status, error) = match result {
+                Ok(_) => ("ok", ""),
+                Err(err) if err.kind == ErrorKind::Skipped => ("skipped", err.message.as_str()),
+                Err(err) if err.kind == ErrorKind::Paused => ("paused", err.message.as_str()),
+                Err(err) => ("failed", err.message.as_str()),
+            };
+            csv.push_str(&format!(
+                "{},{status},{}\n",
+                csv_field(key),
+                csv_field(error)
+            ));
+        }
+        csv
+    }
+}
+
+/// Quotes `value` when it has a comma, quote or line break, doubling its quotes
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Keys a request over many objects went through for, and the ones it didn't with why.
+/// Unlike a [`BatchReport`] it has no skipped or paused items of its own.
+#[derive(Debug, Clone, Default)]
+pub struct BatchResult {
+    pub succeeded: Vec<Arc<str>>,
+    pub failed: Vec<(Arc<str>, AppError)>,
+}
+
+impl BatchResult {
+    /// Every key failed with `err`, like when the request as a whole did
+    pub fn failed_with(keys: impl IntoIterator<Item = Arc<str>>, err: &AppError) -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: keys.into_iter().map(|key| (key, err.clone())).collect(),
+        }
+    }
+
+    pub fn append(&mut self, other: BatchResult) {
+        self.succeeded.extend(other.succeeded);
+        self.failed.extend(other.failed);
+    }
+
+    /// Outcome per key, succeeded ones first, to push onto a [`BatchReport`]
+    pub fn into_items(self) -> impl Iterator<Item = (Arc<str>, AppResult<()>)> {
+        let succeeded = self.succeeded.into_iter().map(|key| (key, Ok(())));
+        let failed = self.failed.into_iter().map(|(key, err)| (key, Err(err)));
+        succeeded.chain(failed)
+    }
+}
+
+impl Extend<(Arc<str>, AppResult<()>)> for BatchReport {
+    fn extend<I: IntoIterator<Item = (Arc<str>, AppResult<()>)>>(&mut self, iter: I) {
+        self.items.extend(iter);
+    }
+}
+
+impl FromIterator<(Arc<str>, AppResult<()>)> for BatchReport {
+    fn from_iter<I: IntoIterator<Item = (Arc<str>, AppResult<()>)>>(iter: I) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    /// Log output collected by a test subscriber
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
         }
     }
+
+    fn logged(run: impl FnOnce()) -> String {
+        let logs = Logs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, run);
+        String::from_utf8(logs.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn attaches_context() {
+        let result: AppResult<()> = Err(AppError::message("Access Denied"));
+        let err = result.with_context("list_objects", "prod").unwrap_err();
+
+        assert_eq!(
+            err.context,
+            Some(ErrorContext {
+                op: "list_objects",
+                remote: "prod".into(),
+                operation: None,
+            })
+        );
+        assert_eq!(err.context.unwrap().to_string(), "list_objects on prod");
+        assert_eq!(err.message, "Access Denied");
+
+        let ok: AppResult<u8> = Ok(1);
+        assert_eq!(ok.with_context("list_objects", "prod").unwrap(), 1);
+    }
+
+    #[test]
+    fn labels_errors_with_the_operation_polled() {
+        let request = in_operation("19a2-3".into(), async {
+            AppError::message("Access Denied").with_context("delete_file", "prod")
+        });
+        let mut request = pin!(request);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        let std::task::Poll::Ready(err) = request.as_mut().poll(&mut cx) else {
+            panic!("request didn't finish");
+        };
+        assert_eq!(err.context.unwrap().operation.as_deref(), Some("19a2-3"));
+        assert_eq!(current_operation(), None);
+
+        let logs = logged(|| {
+            let mut err = AppError::message("Access Denied").with_context("delete_file", "prod");
+            err.context.as_mut().unwrap().operation = Some("19a2-3".into());
+            err.log();
+        });
+        assert!(logs.contains("operation=\"19a2-3\""), "{logs}");
+    }
+
+    #[test]
+    fn outer_context_wins() {
+        let err = AppError::message("Access Denied")
+            .with_context("list_objects", "prod")
+            .with_context("object_exists", "backup");
+        assert_eq!(err.context.unwrap().to_string(), "object_exists on backup");
+    }
+
+    #[test]
+    fn logs_context() {
+        let logs = logged(|| {
+            AppError::message("Access Denied")
+                .with_context("delete_file", "prod")
+                .log();
+        });
+        let line = logs
+            .lines()
+            .find(|line| line.contains("delete_file on prod"))
+            .expect("context wasn't logged");
+        assert!(line.contains("Access Denied"), "{line}");
+        assert!(line.contains("op=\"delete_file\""), "{line}");
+        assert!(line.contains("remote=\"prod\""), "{line}");
+        assert!(!line.contains("req_id"), "{line}");
+
+        let logs = logged(|| {
+            let mut err = AppError::message("Slow Down");
+            err.req_id = "4442587FB7D0A2F9".into();
+            err.with_context("put_object", "prod").log();
+        });
+        assert!(logs.contains("req_id=\"4442587FB7D0A2F9\""), "{logs}");
+    }
+
+    #[test]
+    fn logs_once_where_surfaced() {
+        let logs = logged(|| {
+            let _ = AppError::message("Access Denied")
+                .with_context("head_object", "prod")
+                .with_context("object_exists", "prod");
+        });
+        assert_eq!(logs, "");
+
+        let logs = logged(|| {
+            BatchReport::from_iter([
+                (
+                    Arc::from("a.txt"),
+                    Err(AppError::message("taken").with_kind(ErrorKind::Skipped)),
+                ),
+                (
+                    Arc::from("b.txt"),
+                    Err(AppError::message("storage cap exceeded").with_kind(ErrorKind::Paused)),
+                ),
+                (Arc::from("c.txt"), Err(AppError::message("Access Denied"))),
+            ])
+            .log();
+        });
+        let levels = logs
+            .lines()
+            .map(|line| line.split_whitespace().nth(1).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(levels, ["INFO", "INFO", "ERROR"], "{logs}");
+        assert!(logs.contains("src/lib.rs"), "{logs}");
+    }
+
+    #[test]
+    fn counts_paused_items_apart_from_failures() {
+        let report = BatchReport::from_iter([
+            (Arc::from("a.txt"), Ok(())),
+            (
+                Arc::from("b.txt"),
+                Err(AppError::message("storage cap exceeded").with_kind(ErrorKind::Paused)),
+            ),
+            (
+                Arc::from("c.txt"),
+                Err(AppError::message("Access Denied").with_kind(ErrorKind::AccessDenied)),
+            ),
+        ]);
+
+        assert_eq!(report.paused(), 1);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(
+            report
+                .paused_keys()
+                .map(AsRef::as_ref)
+                .collect::<Vec<&str>>(),
+            ["b.txt"]
+        );
+        assert_eq!(
+            report.report(),
+            "1 succeeded, 0 skipped, 1 failed, 1 paused\n\
+             OK      a.txt\n\
+             PAUSED  b.txt: storage cap exceeded\n\
+             FAILED  c.txt: Access Denied"
+        );
+    }
+
+    #[test]
+    fn exports_reports_as_csv() {
+        let report = BatchReport::from_iter([
+            (Arc::from("a.txt"), Ok(())),
+            (
+                Arc::from("b, final.txt"),
+                Err(AppError::message("\"b, final.txt\" is empty")),
+            ),
+        ]);
+
+        assert_eq!(
+            report.csv(),
+            "key,status,error\n\
+             a.txt,ok,\n\
+             \"b, final.txt\",failed,\"\"\"b, final.txt\"\" is empty\"\n"
+        );
+    }
 }
@@ -0,0 +1,312 @@
+//! Fills a bucket with synthetic objects for developing and benchmarking pagination,
+//! the listing and bulk operations, and removes them again.
+//!
+//! ```text
+//! cargo run -p balti-s3 --example fixtures -- populate fixtures/ --count 50000 --depth 3 --sizes mixed --seed 7
+//! cargo run -p balti-s3 --example fixtures -- cleanup fixtures/
+//! ```
+//!
+//! The remote comes from `BALTI_ENDPOINT`, `BALTI_BUCKET`, `BALTI_REGION` (`us-east-1`
+//! when unset), `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` for
+//! temporary keys. Runs with the same seed plan the same keys and bodies, keys already
+//! in the bucket are skipped so an interrupted run picks up where it stopped.
+
+use std::{collections::HashSet, path::Path, sync::Arc, time::Instant};
+
+use balti_err::{AppError, AppResult};
+use balti_s3::{__S3Object, __S3Remote, MAX_PAGE_SIZE, S3Config, S3Remote};
+use futures::StreamExt;
+
+const USAGE: &str = "\
+usage: fixtures populate <prefix> [--count N] [--depth N] [--sizes tiny|mixed|large] [--seed N] [--concurrency N]
+       fixtures cleanup <prefix>";
+
+/// Folders per level objects are spread over
+const FOLDER_FANOUT: u64 = 8;
+
+/// Objects between two progress lines
+const PROGRESS_EVERY: usize = 500;
+
+#[derive(Debug, Clone, Copy)]
+enum Sizes {
+    /// Up to 4 KiB, for listing and bulk operations
+    Tiny,
+    /// Mostly small with a tail up to 16 MiB, like a typical bucket
+    Mixed,
+    /// 1 to 64 MiB, for transfers
+    Large,
+}
+
+impl Sizes {
+    fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "tiny" => Ok(Self::Tiny),
+            "mixed" => Ok(Self::Mixed),
+            "large" => Ok(Self::Large),
+            _ => Err(AppError::message(format!(
+                "Unknown size distribution \"{value}\""
+            ))),
+        }
+    }
+
+    fn pick(&self, rng: &mut Rng) -> u64 {
+        const KIB: u64 = 1024;
+        const MIB: u64 = 1024 * KIB;
+        match self {
+            Self::Tiny => rng.below(4 * KIB),
+            Self::Mixed => match rng.below(100) {
+                0 => MIB + rng.below(15 * MIB),
+                1..10 => 64 * KIB + rng.below(MIB),
+                _ => rng.below(64 * KIB),
+            },
+            Self::Large => MIB + rng.below(63 * MIB),
+        }
+    }
+}
+
+/// SplitMix64, plenty for spreading fixtures and stable across platforms
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound.max(1)
+    }
+}
+
+struct Fixture {
+    key: String,
+    size: u64,
+    /// Seeds the body, so reruns upload the same bytes
+    seed: u64,
+}
+
+struct Populate {
+    prefix: String,
+    count: usize,
+    depth: u64,
+    sizes: Sizes,
+    seed: u64,
+    concurrency: usize,
+}
+
+/// Keys and sizes of the fixtures, the same for the same parameters
+fn plan(params: &Populate) -> Vec<Fixture> {
+    let mut rng = Rng(params.seed);
+    (0..params.count)
+        .map(|i| {
+            let depth = rng.below(params.depth + 1);
+            let folders = (0..depth)
+                .map(|level| format!("dir-{level}-{}/", rng.below(FOLDER_FANOUT)))
+                .collect::<String>();
+            Fixture {
+                key: format!("{}{folders}object-{i:07}.bin", params.prefix),
+                size: params.sizes.pick(&mut rng),
+                seed: rng.next(),
+            }
+        })
+        .collect()
+}
+
+fn body(fixture: &Fixture) -> Vec<u8> {
+    let mut rng = Rng(fixture.seed);
+    let mut body = Vec::with_capacity(fixture.size as usize);
+    while body.len() < fixture.size as usize {
+        body.extend_from_slice(&rng.next().to_le_bytes());
+    }
+    body.truncate(fixture.size as usize);
+    body
+}
+
+fn remote_from_env() -> AppResult<S3Remote> {
+    let var = |name: &str| {
+        std::env::var(name).map_err(|_| AppError::message(format!("{name} isn't set")))
+    };
+    let config = S3Config {
+        access_key_id: var("AWS_ACCESS_KEY_ID")?.into(),
+        secret_access_key: var("AWS_SECRET_ACCESS_KEY")?.into(),
+        session_token: var("AWS_SESSION_TOKEN").ok().map(Into::into),
+        region: var("BALTI_REGION")
+            .unwrap_or_else(|_| "us-east-1".to_owned())
+            .into(),
+        endpoint: var("BALTI_ENDPOINT")?.into(),
+        bucket_name: var("BALTI_BUCKET")?.into(),
+        page_size: None,
+        root_prefix: None,
+        headers: Vec::new(),
+        proxy: None,
+        ca_bundle: None,
+        insecure_tls: false,
+        timeout_secs: None,
+        credentials_expiry: None,
+        upload_defaults: Default::default(),
+    };
+    Ok(__S3Remote::new(
+        "fixtures".into(),
+        config,
+        None,
+        Arc::default(),
+    ))
+}
+
+/// Every key under `prefix`
+async fn existing_keys(remote: &S3Remote, prefix: &str) -> AppResult<HashSet<Arc<str>>> {
+    let mut keys = HashSet::new();
+    let mut token = None::<Arc<str>>;
+    loop {
+        let page = balti_s3::list_objects_recursive(
+            remote.clone(),
+            prefix,
+            MAX_PAGE_SIZE,
+            token.as_deref(),
+        )
+        .await?;
+        keys.extend(
+            page.objects
+                .iter()
+                .filter_map(|object| match object.as_ref() {
+                    __S3Object::File { key, .. } => Some(key.clone()),
+                    __S3Object::Folder(_) => None,
+                }),
+        );
+        token = page.next_token;
+        if token.is_none() {
+            return Ok(keys);
+        }
+    }
+}
+
+/// Uploads through a temp file, the way the app uploads
+async fn upload(remote: S3Remote, dir: &Path, fixture: Fixture) -> AppResult<()> {
+    let path = dir.join(fixture.key.replace('/', "_"));
+    tokio::fs::write(&path, body(&fixture))
+        .await
+        .map_err(|err| AppError::err(err))?;
+    let result = balti_s3::upload_file(remote, &fixture.key, &path, None).await;
+    let _ = tokio::fs::remove_file(&path).await;
+    result
+}
+
+async fn populate(remote: S3Remote, params: Populate) -> AppResult<()> {
+    let existing = existing_keys(&remote, &params.prefix).await?;
+    let fixtures = plan(&params)
+        .into_iter()
+        .filter(|fixture| !existing.contains(fixture.key.as_str()))
+        .collect::<Vec<_>>();
+    let total = fixtures.len();
+    let bytes = fixtures.iter().map(|fixture| fixture.size).sum::<u64>();
+    eprintln!(
+        "uploading {total} objects ({bytes} bytes), {} already there",
+        params.count - total
+    );
+
+    let dir = std::env::temp_dir().join(format!("balti-fixtures-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|err| AppError::err(err))?;
+
+    let started = Instant::now();
+    let mut done = 0;
+    let mut failed = 0;
+    let mut uploads = futures::stream::iter(fixtures)
+        .map(|fixture| upload(remote.clone(), &dir, fixture))
+        .buffer_unordered(params.concurrency.max(1));
+    while let Some(result) = uploads.next().await {
+        done += 1;
+        if let Err(err) = result {
+            failed += 1;
+            eprintln!("upload failed: {}", err.message);
+        }
+        if done % PROGRESS_EVERY == 0 || done == total {
+            eprintln!(
+                "{done}/{total} in {:.1}s, {failed} failed",
+                started.elapsed().as_secs_f64()
+            );
+        }
+    }
+
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    match failed {
+        0 => Ok(()),
+        failed => Err(AppError::message(format!(
+            "{failed} uploads failed, run again to retry them"
+        ))),
+    }
+}
+
+async fn cleanup(remote: S3Remote, prefix: &str) -> AppResult<()> {
+    let mut removed = 0;
+    loop {
+        // each round deletes up to a page of keys
+        let page = balti_s3::list_objects_recursive(remote.clone(), prefix, 1, None).await?;
+        if page.objects.is_empty() {
+            break;
+        }
+        balti_s3::delete_folder(remote.clone(), prefix).await?;
+        removed += 1;
+        eprintln!("deleted round {removed}");
+    }
+    eprintln!("{prefix} is empty");
+    Ok(())
+}
+
+fn parse_populate(prefix: String, mut args: impl Iterator<Item = String>) -> AppResult<Populate> {
+    let mut params = Populate {
+        prefix,
+        count: 1000,
+        depth: 2,
+        sizes: Sizes::Tiny,
+        seed: 1,
+        concurrency: 16,
+    };
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| AppError::message(format!("{flag} needs a value")))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| AppError::message(format!("{flag} takes a number")))
+        };
+        match flag.as_str() {
+            "--count" => params.count = number()? as usize,
+            "--depth" => params.depth = number()?,
+            "--sizes" => params.sizes = Sizes::parse(&value)?,
+            "--seed" => params.seed = number()?,
+            "--concurrency" => params.concurrency = number()? as usize,
+            _ => return Err(AppError::message(format!("Unknown option {flag}"))),
+        }
+    }
+    Ok(params)
+}
+
+/// Prefixes are folders, `fixtures` and `fixtures/` mean the same
+fn folder(prefix: String) -> String {
+    format!("{}/", prefix.trim_matches('/'))
+}
+
+async fn run(mut args: impl Iterator<Item = String>) -> AppResult<()> {
+    match (args.next().as_deref(), args.next()) {
+        (Some("populate"), Some(prefix)) => {
+            let params = parse_populate(folder(prefix), args)?;
+            populate(remote_from_env()?, params).await
+        }
+        (Some("cleanup"), Some(prefix)) => cleanup(remote_from_env()?, &folder(prefix)).await,
+        _ => Err(AppError::message(USAGE)),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = run(std::env::args().skip(1)).await {
+        eprintln!("{}", err.message);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,424 @@
+use std::{collections::HashMap, sync::Arc};
+
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use balti_err::{AppError, AppResult, BatchResult, ErrorKind};
+
+use crate::{ACCESS_DENIED_CODE, MAX_PAGE_SIZE, S3Remote, labelled};
+
+/// Keys a single `DeleteObjects` request takes at most
+pub const MAX_DELETE_BATCH: usize = 1000;
+/// Passes over a folder before giving up on it, each one lists it again for keys
+/// written while the previous one was deleting
+const MAX_DELETE_PASSES: usize = 5;
+/// Failed keys named in the error, the rest are only counted
+const MAX_FAILURES_SHOWN: usize = 5;
+
+/// Keys of a listing page, with the token of the next page when there is one
+struct KeyPage {
+    keys: Vec<String>,
+    next_token: Option<String>,
+}
+
+/// Deletes every object below `key`, a page at a time. Keys written while it runs get
+/// listed again and deleted too. Objects the provider refused to delete are named in
+/// the error.
+pub async fn delete_folder(remote: S3Remote, key: &str) -> AppResult<()> {
+    labelled("delete_folder", &remote, async {
+        remote.check_scope(key)?;
+
+        let failures = delete_listed(
+            |token| list_keys(&remote, key, token),
+            |keys| delete_keys(&remote, keys),
+        )
+        .await?;
+        let failures = failures
+            .into_iter()
+            .map(|(key, err)| (key, err.message))
+            .collect();
+        match failures_message(key, failures) {
+            Some(message) => Err(AppError::message(message)),
+            None => Ok(()),
+        }
+    })
+    .await
+}
+
+/// Deletes the objects at `keys` with as few requests as it takes, a failed one only
+/// failing its own keys. Missing keys count as deleted, like S3 has them.
+pub async fn delete_many(remote: S3Remote, keys: Vec<Arc<str>>) -> BatchResult {
+    let mut out_of_scope = Vec::new();
+    let keys = keys
+        .into_iter()
+        .filter(|key| match remote.check_scope(key) {
+            Ok(_) => true,
+            Err(err) => {
+                out_of_scope.push((key.clone(), err));
+                false
+            }
+        })
+        .collect();
+
+    let mut result = delete_batched(keys, |keys| {
+        labelled("delete_many", &remote, delete_keys(&remote, keys))
+    })
+    .await;
+    result.failed.extend(out_of_scope);
+    result
+}
+
+/// Deletes `keys` through `delete` in batches it takes, collecting the outcome per key
+async fn delete_batched<D>(
+    keys: Vec<Arc<str>>,
+    mut delete: impl FnMut(Vec<String>) -> D,
+) -> BatchResult
+where
+    D: Future<Output = AppResult<Vec<(String, AppError)>>>,
+{
+    let mut result = BatchResult::default();
+    for batch in keys.chunks(MAX_DELETE_BATCH) {
+        let request = batch.iter().map(|key| key.to_string()).collect();
+        match delete(request).await {
+            Ok(failed) => {
+                let mut failed = failed.into_iter().collect::<HashMap<_, _>>();
+                for key in batch {
+                    match failed.remove(key.as_ref()) {
+                        Some(err) => result.failed.push((key.clone(), err)),
+                        None => result.succeeded.push(key.clone()),
+                    }
+                }
+            }
+            Err(err) => result.append(BatchResult::failed_with(batch.iter().cloned(), &err)),
+        }
+    }
+    result
+}
+
+async fn list_keys(remote: &S3Remote, prefix: &str, token: Option<String>) -> AppResult<KeyPage> {
+    let response = remote
+        .client
+        .list_objects_v2()
+        .bucket(remote.bucket_name.as_ref())
+        .prefix(prefix)
+        .max_keys(MAX_PAGE_SIZE)
+        .set_continuation_token(token)
+        .send()
+        .await
+        .map_err(|err| remote.request_err(err))?;
+
+    Ok(KeyPage {
+        keys: response
+            .contents()
+            .iter()
+            .filter_map(|object| object.key().map(str::to_owned))
+            .collect(),
+        // some providers flag the last page as truncated, without a token to go on
+        next_token: response
+            .next_continuation_token
+            .filter(|_| response.is_truncated == Some(true)),
+    })
+}
+
+/// Deletes up to [`MAX_DELETE_BATCH`] keys, returning the ones that failed with why
+async fn delete_keys(remote: &S3Remote, keys: Vec<String>) -> AppResult<Vec<(String, AppError)>> {
+    let objects = keys
+        .into_iter()
+        .map(|key| {
+            ObjectIdentifier::builder()
+                .key(key)
+                .build()
+                .map_err(|err| AppError::err(err))
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    let delete = Delete::builder()
+        .set_objects(Some(objects))
+        // only the failures come back
+        .quiet(true)
+        .build()
+        .map_err(|err| AppError::err(err))?;
+
+    let output = remote
+        .client
+        .delete_objects()
+        .bucket(remote.bucket_name.as_ref())
+        .delete(delete)
+        .send()
+        .await
+        .map_err(|err| remote.request_err(err))?;
+
+    Ok(output
+        .errors()
+        .iter()
+        .map(|err| {
+            let reason = match (err.code(), err.message()) {
+                (Some(code), Some(message)) => format!("{code}: {message}"),
+                (code, message) => code.or(message).unwrap_or("unknown error").to_owned(),
+            };
+            let kind = match err.code() {
+                Some(ACCESS_DENIED_CODE) => ErrorKind::AccessDenied,
+                _ => ErrorKind::Other,
+            };
+            let key = err.key().unwrap_or_default().to_owned();
+            (key, AppError::message(reason).with_kind(kind))
+        })
+        .collect())
+}
+
+/// Deletes whatever `list` turns up through `delete`, in batches. A pass that deleted
+/// anything is followed by another from the first page, which only ends the deletion
+/// once it finds nothing but keys that already failed. Returns those by key, with why.
+async fn delete_listed<L, D, F>(
+    mut list: impl FnMut(Option<String>) -> L,
+    mut delete: impl FnMut(Vec<String>) -> D,
+) -> AppResult<Vec<(String, F)>>
+where
+    L: Future<Output = AppResult<KeyPage>>,
+    D: Future<Output = AppResult<Vec<(String, F)>>>,
+{
+    let mut failures = HashMap::<String, F>::new();
+
+    for _ in 0..MAX_DELETE_PASSES {
+        let mut deleted = 0;
+        let mut token = None;
+        loop {
+            let page = list(token).await?;
+            let keys = page
+                .keys
+                .into_iter()
+                .filter(|key| !failures.contains_key(key))
+                .collect::<Vec<_>>();
+
+            for batch in keys.chunks(MAX_DELETE_BATCH) {
+                // errors for keys this batch didn't send, or twice for one, aren't its failures
+                let failed = delete(batch.to_vec())
+                    .await?
+                    .into_iter()
+                    .filter(|(key, _)| batch.contains(key))
+                    .collect::<HashMap<_, _>>();
+                deleted += batch.len() - failed.len();
+                failures.extend(failed);
+            }
+
+            match page.next_token {
+                Some(next) => token = Some(next),
+                None => break,
+            }
+        }
+
+        if deleted == 0 {
+            let mut failures = failures.into_iter().collect::<Vec<_>>();
+            failures.sort_by(|(a, _), (b, _)| a.cmp(b));
+            return Ok(failures);
+        }
+    }
+
+    Err(AppError::message(
+        "Objects kept appearing in the folder while it was being deleted, try again once \
+         nothing writes to it",
+    ))
+}
+
+/// What went wrong deleting below `prefix`, `None` when nothing did
+fn failures_message(prefix: &str, failures: Vec<(String, String)>) -> Option<String> {
+    if failures.is_empty() {
+        return None;
+    }
+
+    let mut shown = failures
+        .iter()
+        .take(MAX_FAILURES_SHOWN)
+        .map(|(key, reason)| format!("\"{key}\" ({reason})"))
+        .collect::<Vec<_>>();
+    if failures.len() > MAX_FAILURES_SHOWN {
+        shown.push(format!("and {} more", failures.len() - MAX_FAILURES_SHOWN));
+    }
+    Some(format!(
+        "Couldn't delete {} of the objects below \"{prefix}\": {}",
+        failures.len(),
+        shown.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeSet};
+
+    use super::*;
+
+    /// Bucket in memory, listed in pages like S3 does
+    struct FakeBucket {
+        keys: RefCell<BTreeSet<String>>,
+        page_size: usize,
+        lists: RefCell<usize>,
+    }
+
+    impl FakeBucket {
+        fn new(count: usize, page_size: usize) -> Self {
+            Self {
+                keys: RefCell::new((0..count).map(|i| format!("logs/{i:05}")).collect()),
+                page_size,
+                lists: RefCell::new(0),
+            }
+        }
+
+        /// Pages start after the key the token names, like a continuation token does
+        fn list(&self, token: Option<String>) -> KeyPage {
+            *self.lists.borrow_mut() += 1;
+            let keys = self.keys.borrow();
+            let page = keys
+                .iter()
+                .filter(|key| token.as_ref().is_none_or(|after| *key > after))
+                .take(self.page_size)
+                .cloned()
+                .collect::<Vec<_>>();
+            let more = page
+                .last()
+                .is_some_and(|last| keys.range(last.clone()..).nth(1).is_some());
+            KeyPage {
+                next_token: more.then(|| page.last().cloned()).flatten(),
+                keys: page,
+            }
+        }
+
+        fn delete(&self, keys: Vec<String>, refuse: &str) -> Vec<(String, String)> {
+            assert!(keys.len() <= MAX_DELETE_BATCH);
+            let mut failed = Vec::new();
+            for key in keys {
+                if key.ends_with(refuse) {
+                    failed.push((key, "AccessDenied: Access Denied".to_owned()));
+                } else {
+                    self.keys.borrow_mut().remove(&key);
+                }
+            }
+            failed
+        }
+    }
+
+    fn run(bucket: &FakeBucket, refuse: &str) -> AppResult<Vec<(String, String)>> {
+        futures::executor::block_on(delete_listed(
+            |token| std::future::ready(Ok(bucket.list(token))),
+            |keys| std::future::ready(Ok(bucket.delete(keys, refuse))),
+        ))
+    }
+
+    #[test]
+    fn deletes_past_a_page() {
+        for count in [999, 1000, 1001, 2500] {
+            let bucket = FakeBucket::new(count, MAX_PAGE_SIZE as usize);
+            assert_eq!(run(&bucket, "none").unwrap(), []);
+            assert!(bucket.keys.borrow().is_empty(), "{count} keys");
+        }
+
+        // exactly a page is one pass, then one listing that finds nothing
+        let bucket = FakeBucket::new(1000, 1000);
+        run(&bucket, "none").unwrap();
+        assert_eq!(*bucket.lists.borrow(), 2);
+    }
+
+    #[test]
+    fn deletes_keys_written_meanwhile() {
+        let bucket = FakeBucket::new(1500, 1000);
+        let written = RefCell::new(false);
+        let failures = futures::executor::block_on(delete_listed(
+            |token| std::future::ready(Ok(bucket.list(token))),
+            |keys| {
+                // lands before the page the next token continues from
+                if !written.replace(true) {
+                    bucket
+                        .keys
+                        .borrow_mut()
+                        .insert("logs/00000-late".to_owned());
+                }
+                std::future::ready(Ok(bucket.delete(keys, "none")))
+            },
+        ))
+        .unwrap();
+
+        assert_eq!(failures, []);
+        assert!(bucket.keys.borrow().is_empty());
+    }
+
+    #[test]
+    fn collects_refused_keys() {
+        let bucket = FakeBucket::new(1200, 1000);
+        let failures = run(&bucket, "7").unwrap();
+        assert_eq!(failures.len(), 120);
+        assert_eq!(bucket.keys.borrow().len(), 120);
+
+        let message = failures_message("logs/", failures).unwrap();
+        assert!(message.starts_with(
+            "Couldn't delete 120 of the objects below \"logs/\": \"logs/00007\" (AccessDenied: \
+             Access Denied), "
+        ));
+        assert!(message.ends_with(", and 115 more"));
+        assert_eq!(failures_message("logs/", Vec::new()), None);
+    }
+
+    #[test]
+    fn ignores_errors_for_keys_not_sent() {
+        let bucket = FakeBucket::new(3, 1000);
+        let failures = futures::executor::block_on(delete_listed(
+            |token| std::future::ready(Ok(bucket.list(token))),
+            |keys| {
+                let mut failed = bucket.delete(keys, "1");
+                failed.push(("logs/00001".to_owned(), "InternalError".to_owned()));
+                failed.push(("elsewhere".to_owned(), "NoSuchKey".to_owned()));
+                failed.push(("".to_owned(), "MalformedXML".to_owned()));
+                std::future::ready(Ok(failed))
+            },
+        ))
+        .unwrap();
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "logs/00001");
+        assert_eq!(
+            *bucket.keys.borrow(),
+            BTreeSet::from(["logs/00001".to_owned()])
+        );
+    }
+
+    #[test]
+    fn deletes_many_in_batches() {
+        let bucket = FakeBucket::new(2500, 1000);
+        let keys = bucket
+            .keys
+            .borrow()
+            .iter()
+            .map(|key| Arc::from(key.as_str()))
+            .collect();
+        let batches = RefCell::new(0);
+        let result = futures::executor::block_on(delete_batched(keys, |keys| {
+            *batches.borrow_mut() += 1;
+            // the second request fails as a whole
+            let result = match *batches.borrow() {
+                2 => Err(AppError::message("connection reset")),
+                _ => Ok(bucket
+                    .delete(keys, "7")
+                    .into_iter()
+                    .map(|(key, reason)| {
+                        (
+                            key,
+                            AppError::message(reason).with_kind(ErrorKind::AccessDenied),
+                        )
+                    })
+                    .collect()),
+            };
+            std::future::ready(result)
+        }));
+
+        assert_eq!(*batches.borrow(), 3);
+        assert_eq!(result.succeeded.len(), 1350);
+        assert_eq!(result.failed.len(), 1150);
+        assert_eq!(bucket.keys.borrow().len(), 1150);
+
+        let (key, err) = &result.failed[0];
+        assert_eq!(
+            (key.as_ref(), err.kind),
+            ("logs/00007", ErrorKind::AccessDenied)
+        );
+        let (key, err) = &result.failed[100];
+        assert_eq!(
+            (key.as_ref(), err.message.as_str()),
+            ("logs/01000", "connection reset")
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use std::sync::Arc;
+
+use crate::{EndpointUrl, S3Config, is_aws_region};
+
+/// Whose bucket a pasted location points into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteMatch {
+    /// Name of the configured remote holding the key
+    Remote(Arc<str>),
+    /// A bucket no configured remote covers the key of, with the region and endpoint
+    /// when the URL names them
+    Bucket {
+        bucket: String,
+        region: Option<String>,
+        endpoint: Option<String>,
+    },
+    /// A bare key, relative to whichever remote is browsed
+    Current,
+}
+
+/// Bucket and key a URL names, before matching it against the remotes
+struct Located {
+    bucket: String,
+    key: String,
+    region: Option<String>,
+    /// `scheme://host[:port]` of URLs that aren't AWS specific
+    endpoint: Option<String>,
+}
+
+/// Figures out where a pasted string points: `s3://bucket/key`, virtual-hosted or
+/// path-style object URLs of AWS or of a remote's endpoint, AWS console URLs, or a
+/// bare key. Keys come back URL-decoded, `None` when the input is none of these.
+pub fn parse_object_locator<'a>(
+    input: &str,
+    remotes: impl IntoIterator<Item = (&'a Arc<str>, &'a S3Config)>,
+) -> Option<(RemoteMatch, String)> {
+    let input = input.trim();
+    if input.is_empty() || input.contains(['\n', '\r']) {
+        return None;
+    }
+
+    let Some((scheme, rest)) = input.split_once("://") else {
+        let key = input.trim_start_matches('/');
+        return Some((RemoteMatch::Current, key.to_owned()));
+    };
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let remotes = remotes.into_iter().collect::<Vec<_>>();
+    let located = match scheme.to_ascii_lowercase().as_str() {
+        "s3" => {
+            let (bucket, key) = location.split_once('/').unwrap_or((location, ""));
+            Located {
+                bucket: bucket.to_owned(),
+                key: percent_decode(key, false)?,
+                region: None,
+                endpoint: None,
+            }
+        }
+        "http" | "https" => {
+            let (authority, path) = location.split_at(location.find('/').unwrap_or(location.len()));
+            let host = authority
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            if host.ends_with("console.aws.amazon.com") {
+                from_console(path, query)?
+            } else if host.ends_with(".amazonaws.com") || host.ends_with(".amazonaws.com.cn") {
+                from_aws_host(&host, path)?
+            } else {
+                let url = format!("{scheme}://{authority}");
+                from_endpoint(&url, path, &remotes)?
+            }
+        }
+        _ => return None,
+    };
+    if located.bucket.is_empty() {
+        return None;
+    }
+
+    let remote = remotes
+        .iter()
+        .filter(|(_, config)| config.bucket_name.as_ref() == located.bucket)
+        .filter(|(_, config)| {
+            located
+                .endpoint
+                .as_deref()
+                .is_none_or(|endpoint| same_host(&config.endpoint, endpoint, &located.bucket))
+        })
+        .filter(|(_, config)| {
+            config
+                .root_prefix
+                .as_deref()
+                .is_none_or(|root| located.key.starts_with(root))
+        })
+        // the remote scoped closest to the key
+        .max_by_key(|(_, config)| config.root_prefix.as_deref().map_or(0, str::len));
+
+    let remote = match remote {
+        Some((remote_name, _)) => RemoteMatch::Remote(Arc::clone(remote_name)),
+        None => RemoteMatch::Bucket {
+            bucket: located.bucket,
+            region: located.region,
+            endpoint: located.endpoint,
+        },
+    };
+    Some((remote, located.key))
+}
+
+/// `/s3/buckets/<bucket>?prefix=a/b/` and `/s3/object/<bucket>?prefix=a/b.txt` pages
+fn from_console(path: &str, query: &str) -> Option<Located> {
+    let bucket = path
+        .strip_prefix("/s3/buckets/")
+        .or_else(|| path.strip_prefix("/s3/object/"))?
+        .trim_end_matches('/');
+    if bucket.contains('/') {
+        return None;
+    }
+
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| percent_decode(value, true))
+    };
+
+    Some(Located {
+        bucket: bucket.to_owned(),
+        key: param("prefix").unwrap_or_default(),
+        region: param("region").filter(|region| is_aws_region(region)),
+        endpoint: None,
+    })
+}
+
+/// `bucket.s3.region.amazonaws.com/key`, legacy `bucket.s3-region.…` and path-style
+/// `s3.region.amazonaws.com/bucket/key` hosts
+fn from_aws_host(host: &str, path: &str) -> Option<Located> {
+    let labels = host.split('.').collect::<Vec<_>>();
+    let at = labels
+        .iter()
+        .position(|label| *label == "s3" || label.strip_prefix("s3-").is_some_and(is_aws_region))?;
+    let region = labels[at..]
+        .iter()
+        .map(|label| label.strip_prefix("s3-").unwrap_or(label))
+        .find(|label| is_aws_region(label))
+        .map(str::to_owned);
+
+    let path = path.trim_start_matches('/');
+    let (bucket, key) = if at > 0 {
+        (labels[..at].join("."), path)
+    } else {
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        (bucket.to_owned(), key)
+    };
+
+    Some(Located {
+        bucket,
+        key: percent_decode(key, false)?,
+        region,
+        endpoint: None,
+    })
+}
+
+/// URLs of an S3 compatible endpoint, virtual-hosted on a remote's endpoint or
+/// path-style, where the first segment below the endpoint's own path is the bucket
+fn from_endpoint(url: &str, path: &str, remotes: &[(&Arc<str>, &S3Config)]) -> Option<Located> {
+    let parsed = EndpointUrl::parse(url).ok()?;
+    let host = parsed.host.to_ascii_lowercase();
+
+    let virtual_hosted = remotes.iter().find_map(|(_, config)| {
+        let endpoint = EndpointUrl::parse(&config.endpoint).ok()?;
+        let bucket = host.strip_suffix(&format!(".{}", endpoint.host.to_ascii_lowercase()))?;
+        (bucket.eq_ignore_ascii_case(&config.bucket_name) && parsed.port == endpoint.port)
+            .then(|| (config.bucket_name.to_string(), path))
+    });
+    let (bucket, key) = match virtual_hosted {
+        Some((bucket, key)) => (bucket, key.trim_start_matches('/')),
+        None => {
+            // gateways can mount S3 below a path of their own
+            let mounted = remotes
+                .iter()
+                .filter_map(|(_, config)| EndpointUrl::parse(&config.endpoint).ok())
+                .filter(|endpoint| {
+                    endpoint.host.eq_ignore_ascii_case(&host) && endpoint.port == parsed.port
+                })
+                .map(|endpoint| endpoint.path)
+                .find(|mount| !mount.is_empty() && path.starts_with(&format!("{mount}/")));
+            let path = path[mounted.map_or(0, |mount| mount.len())..].trim_start_matches('/');
+            let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+            (bucket.to_owned(), key)
+        }
+    };
+
+    Some(Located {
+        bucket,
+        key: percent_decode(key, false)?,
+        region: None,
+        endpoint: Some(url.to_owned()),
+    })
+}
+
+/// Whether the remote's endpoint serves `url`, directly or with the bucket as subdomain
+fn same_host(endpoint: &str, url: &str, bucket: &str) -> bool {
+    let (Ok(endpoint), Ok(url)) = (EndpointUrl::parse(endpoint), EndpointUrl::parse(url)) else {
+        return false;
+    };
+    endpoint.port == url.port
+        && (endpoint.host.eq_ignore_ascii_case(&url.host)
+            || url
+                .host
+                .eq_ignore_ascii_case(&format!("{bucket}.{}", endpoint.host)))
+}
+
+/// Decodes `%XX` escapes, and `+` as a space in query values. `None` when the escapes
+/// don't make valid UTF-8.
+fn percent_decode(input: &str, plus_as_space: bool) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut at = 0;
+    while at < bytes.len() {
+        let byte = match bytes[at] {
+            b'%' if at + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[at + 1..at + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        at += 2;
+                        byte
+                    }
+                    None => b'%',
+                }
+            }
+            b'+' if plus_as_space => b' ',
+            byte => byte,
+        };
+        decoded.push(byte);
+        at += 1;
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(endpoint: &str, bucket_name: &str, root_prefix: Option<&str>) -> S3Config {
+        S3Config {
+            bucket_name: bucket_name.into(),
+            root_prefix: root_prefix.map(Arc::from),
+            ..S3Config::test(endpoint)
+        }
+    }
+
+    fn remotes() -> Vec<(Arc<str>, S3Config)> {
+        vec![
+            (
+                "aws".into(),
+                config("https://s3.eu-west-1.amazonaws.com", "acme-data", None),
+            ),
+            (
+                "aws-reports".into(),
+                config(
+                    "https://s3.eu-west-1.amazonaws.com",
+                    "acme-data",
+                    Some("reports/"),
+                ),
+            ),
+            (
+                "minio".into(),
+                config("http://192.168.1.50:9000", "media", None),
+            ),
+            (
+                "gateway".into(),
+                config("https://gateway.corp/s3", "archive", None),
+            ),
+            (
+                "r2".into(),
+                config("https://r2.example.com", "assets", None),
+            ),
+        ]
+    }
+
+    fn locate(input: &str) -> Option<(RemoteMatch, String)> {
+        let remotes = remotes();
+        parse_object_locator(input, remotes.iter().map(|(name, config)| (name, config)))
+    }
+
+    fn remote(name: &str, key: &str) -> Option<(RemoteMatch, String)> {
+        Some((RemoteMatch::Remote(name.into()), key.to_owned()))
+    }
+
+    fn bucket(bucket: &str, region: Option<&str>, endpoint: Option<&str>) -> RemoteMatch {
+        RemoteMatch::Bucket {
+            bucket: bucket.to_owned(),
+            region: region.map(str::to_owned),
+            endpoint: endpoint.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn parses_s3_uris() {
+        assert_eq!(
+            locate("s3://acme-data/logs/a.txt"),
+            remote("aws", "logs/a.txt")
+        );
+        assert_eq!(locate(" S3://acme-data "), remote("aws", ""));
+        assert_eq!(
+            locate("s3://acme-data/reports/2024/q1.csv"),
+            remote("aws-reports", "reports/2024/q1.csv")
+        );
+        assert_eq!(
+            locate("s3://acme-data/my%20file%2B1.txt"),
+            remote("aws", "my file+1.txt")
+        );
+        assert_eq!(
+            locate("s3://other/a/b/"),
+            Some((bucket("other", None, None), "a/b/".to_owned()))
+        );
+        assert_eq!(locate("s3:///a.txt"), None);
+    }
+
+    #[test]
+    fn parses_aws_object_urls() {
+        assert_eq!(
+            locate("https://acme-data.s3.eu-west-1.amazonaws.com/logs/a+b.txt"),
+            remote("aws", "logs/a+b.txt")
+        );
+        assert_eq!(
+            locate("https://acme-data.s3-eu-west-1.amazonaws.com/logs/"),
+            remote("aws", "logs/")
+        );
+        assert_eq!(
+            locate("https://s3.eu-west-1.amazonaws.com/acme-data/logs/a.txt?X-Amz-Expires=60"),
+            remote("aws", "logs/a.txt")
+        );
+        assert_eq!(
+            locate("https://my.dotted.bucket.s3.amazonaws.com/k%C3%A9y.txt#top"),
+            Some((bucket("my.dotted.bucket", None, None), "kéy.txt".to_owned()))
+        );
+        assert_eq!(
+            locate("https://other.s3.ap-south-1.amazonaws.com/"),
+            Some((bucket("other", Some("ap-south-1"), None), String::new()))
+        );
+        assert_eq!(locate("https://ec2.amazonaws.com/acme-data/a.txt"), None);
+    }
+
+    #[test]
+    fn parses_console_urls() {
+        assert_eq!(
+            locate(
+                "https://eu-west-1.console.aws.amazon.com/s3/buckets/acme-data?region=eu-west-1&bucketType=general&prefix=logs/2024+q1/&showversions=false"
+            ),
+            remote("aws", "logs/2024 q1/")
+        );
+        assert_eq!(
+            locate(
+                "https://s3.console.aws.amazon.com/s3/object/other?region=us-west-2&prefix=a%2Fb.txt"
+            ),
+            Some((
+                bucket("other", Some("us-west-2"), None),
+                "a/b.txt".to_owned()
+            ))
+        );
+        assert_eq!(
+            locate("https://s3.console.aws.amazon.com/s3/home?region=us-west-2"),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_urls_of_configured_endpoints() {
+        assert_eq!(
+            locate("http://192.168.1.50:9000/media/videos/clip.mp4"),
+            remote("minio", "videos/clip.mp4")
+        );
+        assert_eq!(
+            locate("https://gateway.corp/s3/archive/2023/"),
+            remote("gateway", "2023/")
+        );
+        assert_eq!(
+            locate("https://assets.r2.example.com/img/logo.png"),
+            remote("r2", "img/logo.png")
+        );
+        assert_eq!(
+            locate("http://192.168.1.50:9001/media/a.txt"),
+            Some((
+                bucket("media", None, Some("http://192.168.1.50:9001")),
+                "a.txt".to_owned()
+            ))
+        );
+        assert_eq!(
+            locate("https://files.example.org/photos/cat.jpg"),
+            Some((
+                bucket("photos", None, Some("https://files.example.org")),
+                "cat.jpg".to_owned()
+            ))
+        );
+        assert_eq!(locate("ftp://files.example.org/photos/cat.jpg"), None);
+    }
+
+    #[test]
+    fn takes_bare_keys_as_is() {
+        assert_eq!(
+            locate("/logs/2024/a b.txt"),
+            Some((RemoteMatch::Current, "logs/2024/a b.txt".to_owned()))
+        );
+        assert_eq!(
+            locate("logs/%41"),
+            Some((RemoteMatch::Current, "logs/%41".to_owned()))
+        );
+        assert_eq!(locate("  "), None);
+        assert_eq!(locate("a.txt\nb.txt"), None);
+    }
+}
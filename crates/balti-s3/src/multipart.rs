@@ -0,0 +1,352 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use aws_sdk_s3::{
+    error::DisplayErrorContext,
+    primitives::{ByteStream, Length},
+    types::{CompletedMultipartUpload, CompletedPart},
+};
+use balti_err::{AppError, AppResult};
+use futures::StreamExt;
+
+use crate::{
+    S3Remote, UploadDefaults, labelled,
+    progress::{OnProgress, ProgressCounter},
+    upload_file,
+};
+
+/// Files this big or larger upload in parts, smaller ones with a single request
+pub const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+/// Smallest part S3 takes, only the last part may be shorter
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+/// Part size uploads go in unless told otherwise, grown for files S3 needs fewer parts of
+pub const PART_SIZE: u64 = 8 * 1024 * 1024;
+/// Most parts an upload may have
+const MAX_PARTS: u64 = 10_000;
+/// Parts uploading at once, each streams its own range of the file
+const PART_CONCURRENCY: usize = 4;
+
+/// Range of the file a part uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Part {
+    /// From 1, as S3 counts them
+    number: i32,
+    offset: u64,
+    length: u64,
+}
+
+/// Splits `size` bytes into parts of `part_size`, grown to fit S3's limits
+fn parts(size: u64, part_size: u64) -> Vec<Part> {
+    let part_size = part_size.max(MIN_PART_SIZE).max(size.div_ceil(MAX_PARTS));
+
+    (0..size.div_ceil(part_size))
+        .map(|i| Part {
+            number: i as i32 + 1,
+            offset: i * part_size,
+            length: part_size.min(size - i * part_size),
+        })
+        .collect()
+}
+
+/// Uploads the file at `from_path` in parts of `part_size` bytes, a few at a time.
+/// Files below [`MULTIPART_THRESHOLD`] go up with [`upload_file`] instead. A failed
+/// upload gets aborted, so its parts don't linger in the bucket. Uploads with
+/// `options`, or the remote's upload defaults when `None`.
+pub async fn upload_file_multipart(
+    remote: S3Remote,
+    to_key: &str,
+    from_path: &Path,
+    part_size: u64,
+    options: Option<&UploadDefaults>,
+) -> AppResult<()> {
+    upload_file_with_progress(remote, to_key, from_path, part_size, options, &|_, _| {}).await
+}
+
+/// Like [`upload_file_multipart`], reporting the bytes uploaded. Parts count once they
+/// completed, a file small enough for a single request once it's up.
+pub async fn upload_file_with_progress(
+    remote: S3Remote,
+    to_key: &str,
+    from_path: &Path,
+    part_size: u64,
+    options: Option<&UploadDefaults>,
+    on_progress: OnProgress<'_>,
+) -> AppResult<()> {
+    let size = std::fs::metadata(from_path)
+        .map_err(|err| AppError::err(err))?
+        .len();
+    let mut counter = ProgressCounter::new(Some(size), on_progress);
+    if size < MULTIPART_THRESHOLD {
+        upload_file(remote, to_key, &from_path.to_path_buf(), options).await?;
+        counter.add(size);
+        counter.finish();
+        return Ok(());
+    }
+
+    labelled("upload_file_multipart", &remote, async {
+        remote.check_scope(to_key)?;
+        let options = options.unwrap_or(&remote.config.upload_defaults);
+
+        let upload_id = remote
+            .client
+            .create_multipart_upload()
+            .bucket(remote.bucket_name.as_ref())
+            .key(to_key)
+            .set_storage_class(options.storage_class())
+            .set_server_side_encryption(options.encryption())
+            .set_ssekms_key_id(options.kms_key_id.as_deref().map(str::to_owned))
+            .set_acl(options.acl())
+            .set_content_type(options.content_type(to_key).map(str::to_owned))
+            .send()
+            .await
+            .map_err(|err| remote.request_err(err))?
+            .upload_id
+            .ok_or_else(|| AppError::message("The provider didn't start the multipart upload"))?;
+
+        let result = match upload_parts(
+            &remote,
+            to_key,
+            from_path,
+            &upload_id,
+            size,
+            part_size,
+            &mut counter,
+        )
+        .await
+        {
+            Ok(parts) => remote
+                .client
+                .complete_multipart_upload()
+                .bucket(remote.bucket_name.as_ref())
+                .key(to_key)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|err| remote.request_err(err)),
+            Err(err) => Err(err),
+        };
+
+        if result.is_err()
+            && let Err(err) = remote
+                .client
+                .abort_multipart_upload()
+                .bucket(remote.bucket_name.as_ref())
+                .key(to_key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+        {
+            tracing::warn!(
+                "Couldn't abort upload {upload_id} of {to_key} on {}: {}",
+                remote.remote_name,
+                DisplayErrorContext(&err)
+            );
+        }
+        result
+    })
+    .await
+}
+
+/// Uploads every part, the completed ones in order when all of them made it
+async fn upload_parts(
+    remote: &S3Remote,
+    to_key: &str,
+    from_path: &Path,
+    upload_id: &str,
+    size: u64,
+    part_size: u64,
+    counter: &mut ProgressCounter<'_>,
+) -> AppResult<Vec<CompletedPart>> {
+    upload_each_part(parts(size, part_size), counter, |part| {
+        upload_part(remote, to_key, from_path, upload_id, part)
+    })
+    .await
+}
+
+/// Uploads `parts` through `upload`, a few at a time. No part starts once one failed,
+/// the ones already running still finish so the error names them all.
+async fn upload_each_part<U>(
+    parts: Vec<Part>,
+    counter: &mut ProgressCounter<'_>,
+    upload: impl Fn(Part) -> U,
+) -> AppResult<Vec<CompletedPart>>
+where
+    U: Future<Output = AppResult<CompletedPart>>,
+{
+    let total = parts.len();
+    let failing = AtomicBool::new(false);
+
+    let uploads = parts.into_iter().map(|part| {
+        let upload = upload(part);
+        async move { (part, upload.await) }
+    });
+    let mut uploads = futures::stream::iter(uploads)
+        .take_while(|_| std::future::ready(!failing.load(Ordering::Relaxed)))
+        .buffer_unordered(PART_CONCURRENCY);
+
+    let (mut completed, mut failed) = (Vec::new(), Vec::new());
+    while let Some((part, result)) = uploads.next().await {
+        match result {
+            Ok(uploaded) => {
+                counter.add(part.length);
+                completed.push(uploaded);
+            }
+            Err(err) => {
+                failing.store(true, Ordering::Relaxed);
+                failed.push((part.number, err));
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(failed_parts(failed, total));
+    }
+
+    completed.sort_by_key(|part| part.part_number);
+    Ok(completed)
+}
+
+async fn upload_part(
+    remote: &S3Remote,
+    to_key: &str,
+    from_path: &Path,
+    upload_id: &str,
+    part: Part,
+) -> AppResult<CompletedPart> {
+    let body = ByteStream::read_from()
+        .path(from_path)
+        .offset(part.offset)
+        .length(Length::Exact(part.length))
+        .build()
+        .await
+        .map_err(|err| AppError::err(err))?;
+
+    let uploaded = remote
+        .client
+        .upload_part()
+        .bucket(remote.bucket_name.as_ref())
+        .key(to_key)
+        .upload_id(upload_id)
+        .part_number(part.number)
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| remote.request_err(err))?;
+
+    Ok(CompletedPart::builder()
+        .part_number(part.number)
+        .set_e_tag(uploaded.e_tag)
+        .build())
+}
+
+/// One error for the parts that failed, naming them. Keeps the kind of the first
+/// failure so quota and access errors are still told apart.
+fn failed_parts(mut failed: Vec<(i32, AppError)>, total: usize) -> AppError {
+    failed.sort_by_key(|(number, _)| *number);
+    let numbers = failed
+        .iter()
+        .map(|(number, _)| number.to_string())
+        .collect::<Vec<_>>();
+
+    let (_, first) = &failed[0];
+    AppError::message(format!(
+        "{} of {total} parts failed to upload ({}): {}",
+        failed.len(),
+        numbers.join(", "),
+        first.message
+    ))
+    .with_kind(first.kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use balti_err::ErrorKind;
+
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn splits_files_into_parts() {
+        let split = parts(20 * MIB, 8 * MIB);
+        assert_eq!(
+            split,
+            [
+                Part {
+                    number: 1,
+                    offset: 0,
+                    length: 8 * MIB
+                },
+                Part {
+                    number: 2,
+                    offset: 8 * MIB,
+                    length: 8 * MIB
+                },
+                Part {
+                    number: 3,
+                    offset: 16 * MIB,
+                    length: 4 * MIB
+                },
+            ]
+        );
+
+        // parts never go below the minimum, nor past the most an upload may have
+        assert_eq!(parts(20 * MIB, MIB).len(), 4);
+        let huge = parts(100_000 * MIB, 8 * MIB);
+        assert_eq!(huge.len() as u64, MAX_PARTS);
+        assert_eq!(
+            huge.iter().map(|part| part.length).sum::<u64>(),
+            100_000 * MIB
+        );
+        assert_eq!(parts(16 * MIB, 16 * MIB).len(), 1);
+    }
+
+    #[test]
+    fn stops_starting_parts_after_a_failure() {
+        let parts = parts(100 * MIB, 5 * MIB);
+        assert_eq!(parts.len(), 20);
+
+        let started = Cell::new(0);
+        let mut counter = ProgressCounter::new(Some(100 * MIB), &|_, _| {});
+        let result = futures::executor::block_on(upload_each_part(parts, &mut counter, |part| {
+            started.set(started.get() + 1);
+            std::future::ready(match part.number {
+                2 => Err(AppError::message("slow down")),
+                number => Ok(CompletedPart::builder().part_number(number).build()),
+            })
+        }));
+
+        // the parts running alongside the failed one finish, the rest never start
+        assert!(
+            started.get() < 2 * PART_CONCURRENCY,
+            "{} parts started",
+            started.get()
+        );
+        let err = result.unwrap_err();
+        assert_eq!(err.message, "1 of 20 parts failed to upload (2): slow down");
+    }
+
+    #[test]
+    fn names_the_failed_parts() {
+        let failed = vec![
+            (4, AppError::message("timeout")),
+            (
+                2,
+                AppError::message("quota").with_kind(ErrorKind::QuotaExceeded),
+            ),
+        ];
+
+        let err = failed_parts(failed, 4);
+        assert_eq!(err.message, "2 of 4 parts failed to upload (2, 4): quota");
+        assert_eq!(err.kind, ErrorKind::QuotaExceeded);
+    }
+}
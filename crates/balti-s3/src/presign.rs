@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use aws_sdk_s3::presigning::PresigningConfig;
+use balti_err::{AppError, AppResult};
+
+use crate::{S3Remote, labelled};
+
+/// Longest a presigned URL can stay valid, SigV4 refuses anything past a week
+pub const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Characters RFC 5987 lets through unencoded in an extended parameter value
+fn is_attr_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte)
+}
+
+/// `Content-Disposition` value that makes browsers save the object as `file_name`
+/// rather than show it. Names outside plain ASCII also go in `filename*` (RFC 6266),
+/// with a lossy ASCII `filename` for clients that don't read it.
+pub fn attachment_disposition(file_name: &str) -> String {
+    let fallback = file_name
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    if fallback == file_name {
+        return format!("attachment; filename=\"{fallback}\"");
+    }
+
+    let encoded = file_name
+        .bytes()
+        .map(|byte| {
+            if is_attr_char(byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{byte:02X}")
+            }
+        })
+        .collect::<String>();
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+/// URL that gets the object at `key` without credentials for `expires_in`. With a
+/// `content_disposition` the response carries it in place of the stored one, see
+/// [`attachment_disposition`].
+pub async fn presign_get(
+    remote: S3Remote,
+    key: &str,
+    expires_in: Duration,
+    content_disposition: Option<&str>,
+) -> AppResult<String> {
+    labelled("presign_get", &remote, async {
+        remote.check_scope(key)?;
+        if expires_in.is_zero() || expires_in > MAX_PRESIGN_EXPIRY {
+            return Err(AppError::message(
+                "Presigned links expire after at most 7 days",
+            ));
+        }
+
+        let config = PresigningConfig::expires_in(expires_in).map_err(|err| AppError::err(err))?;
+        let request = remote
+            .client
+            .get_object()
+            .bucket(remote.bucket_name.as_ref())
+            .key(key)
+            .set_response_content_disposition(content_disposition.map(str::to_owned))
+            .presigned(config)
+            .await
+            .map_err(|err| remote.request_err(err))?;
+        Ok(request.uri().to_owned())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{__S3Remote, S3Config};
+
+    #[test]
+    fn encodes_download_names() {
+        assert_eq!(
+            attachment_disposition("report.csv"),
+            "attachment; filename=\"report.csv\""
+        );
+        // spaces are fine quoted
+        assert_eq!(
+            attachment_disposition("Q1 report.pdf"),
+            "attachment; filename=\"Q1 report.pdf\""
+        );
+        assert_eq!(
+            attachment_disposition("résumé 2024.pdf"),
+            "attachment; filename=\"r_sum_ 2024.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9%202024.pdf"
+        );
+        assert_eq!(
+            attachment_disposition("日本.txt"),
+            "attachment; filename=\"__.txt\"; filename*=UTF-8''%E6%97%A5%E6%9C%AC.txt"
+        );
+        assert_eq!(
+            attachment_disposition("a\"b\\c.txt"),
+            "attachment; filename=\"a_b_c.txt\"; filename*=UTF-8''a%22b%5Cc.txt"
+        );
+    }
+
+    fn remote(session_token: Option<&str>) -> S3Remote {
+        __S3Remote::new(
+            "prod".into(),
+            S3Config {
+                session_token: session_token.map(Into::into),
+                ..S3Config::test("http://localhost:9000")
+            },
+            None,
+            Arc::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn presigns_with_a_download_name() {
+        let remote = remote(None);
+        let hour = Duration::from_secs(3600);
+
+        let disposition = attachment_disposition("résumé 2024.pdf");
+        let url = presign_get(remote.clone(), "cv/r.pdf", hour, Some(&disposition))
+            .await
+            .unwrap();
+        assert!(url.starts_with("http://localhost:9000/bucket/cv/r.pdf?"));
+        assert!(url.contains("X-Amz-Expires=3600"));
+        // the header value is encoded once more as a query parameter
+        assert!(url.contains(
+            "response-content-disposition=attachment%3B%20filename%3D%22r_sum_%202024.pdf%22"
+        ));
+        assert!(url.contains("%25C3%25A9"));
+
+        let url = presign_get(remote.clone(), "cv/r.pdf", hour, None)
+            .await
+            .unwrap();
+        assert!(!url.contains("response-content-disposition"));
+        assert!(!url.contains("X-Amz-Security-Token"));
+
+        let week = MAX_PRESIGN_EXPIRY + Duration::from_secs(1);
+        assert!(presign_get(remote, "cv/r.pdf", week, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn signs_the_session_token_into_urls() {
+        let remote = remote(Some("FwoGZXIvYXdzEB4a"));
+        let url = presign_get(remote, "cv/r.pdf", Duration::from_secs(60), None)
+            .await
+            .unwrap();
+        assert!(url.contains("X-Amz-Security-Token=FwoGZXIvYXdzEB4a"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::{fs, io::Write, path::Path};
+
+use balti_err::{AppError, AppResult};
+
+use crate::{S3Remote, labelled};
+
+/// Gets how far a transfer is, as bytes moved so far and the total. The total is `None`
+/// when the provider didn't send a length.
+pub type OnProgress<'a> = &'a (dyn Fn(u64, Option<u64>) + Send + Sync);
+
+/// Bytes moved between two reports, so a fast transfer doesn't flood the callback
+pub const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// Running byte count of a transfer, reported every [`PROGRESS_STEP_BYTES`]
+pub(crate) struct ProgressCounter<'a> {
+    done: u64,
+    unreported: u64,
+    total: Option<u64>,
+    on_progress: OnProgress<'a>,
+}
+
+impl<'a> ProgressCounter<'a> {
+    /// Starts at zero, reporting it right away
+    pub(crate) fn new(total: Option<u64>, on_progress: OnProgress<'a>) -> Self {
+        on_progress(0, total);
+        Self {
+            done: 0,
+            unreported: 0,
+            total,
+            on_progress,
+        }
+    }
+
+    pub(crate) fn add(&mut self, bytes: u64) {
+        self.done += bytes;
+        self.unreported += bytes;
+        if self.unreported >= PROGRESS_STEP_BYTES {
+            self.report();
+        }
+    }
+
+    /// Reports what's left unreported, once the transfer ends
+    pub(crate) fn finish(mut self) -> u64 {
+        if self.unreported > 0 {
+            self.report();
+        }
+        self.done
+    }
+
+    fn report(&mut self) {
+        self.unreported = 0;
+        (self.on_progress)(self.done, self.total);
+    }
+}
+
+/// Downloads the object at `key` to `to_path`, reporting the bytes written as they come
+/// in. A failed download removes the partial file. Returns the bytes written.
+pub async fn download_file_with_progress(
+    remote: S3Remote,
+    key: &str,
+    to_path: &Path,
+    on_progress: OnProgress<'_>,
+) -> AppResult<u64> {
+    labelled("download_file", &remote, async {
+        remote.check_scope(key)?;
+
+        let output = remote
+            .client
+            .get_object()
+            .bucket(remote.bucket_name.as_ref())
+            .key(key)
+            .send()
+            .await
+            .map_err(|err| remote.request_err(err))?;
+        let total = output
+            .content_length
+            .filter(|length| *length >= 0)
+            .map(|length| length as u64);
+
+        let mut file = fs::File::create(to_path).map_err(|err| AppError::err(err))?;
+        let mut body = output.body;
+        let mut counter = ProgressCounter::new(total, on_progress);
+        let written = async {
+            while let Some(chunk) = body.try_next().await.map_err(|err| AppError::err(err))? {
+                file.write_all(&chunk).map_err(|err| AppError::err(err))?;
+                counter.add(chunk.len() as u64);
+            }
+            AppResult::Ok(())
+        }
+        .await;
+        let done = counter.finish();
+
+        if let Err(err) = written {
+            drop(file);
+            let _ = fs::remove_file(to_path);
+            return Err(err);
+        }
+        Ok(done)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn reports_in_steps() {
+        let reports = Mutex::new(Vec::new());
+        let on_progress =
+            |done: u64, total: Option<u64>| reports.lock().unwrap().push((done, total));
+
+        let mut counter = ProgressCounter::new(Some(3 * PROGRESS_STEP_BYTES), &on_progress);
+        counter.add(PROGRESS_STEP_BYTES / 2);
+        counter.add(PROGRESS_STEP_BYTES / 2);
+        counter.add(PROGRESS_STEP_BYTES);
+        counter.add(10);
+        assert_eq!(counter.finish(), 2 * PROGRESS_STEP_BYTES + 10);
+
+        let total = Some(3 * PROGRESS_STEP_BYTES);
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                (0, total),
+                (PROGRESS_STEP_BYTES, total),
+                (2 * PROGRESS_STEP_BYTES, total),
+                (2 * PROGRESS_STEP_BYTES + 10, total),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_without_a_length() {
+        let reports = Mutex::new(Vec::new());
+        let on_progress =
+            |done: u64, total: Option<u64>| reports.lock().unwrap().push((done, total));
+
+        let mut counter = ProgressCounter::new(None, &on_progress);
+        counter.add(PROGRESS_STEP_BYTES + 1);
+        // nothing left unreported
+        assert_eq!(counter.finish(), PROGRESS_STEP_BYTES + 1);
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [(0, None), (PROGRESS_STEP_BYTES + 1, None)]
+        );
+    }
+}
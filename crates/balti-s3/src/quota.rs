@@ -0,0 +1,97 @@
+use crate::EndpointUrl;
+
+/// Error a provider answers with once a quota or usage cap of the account ran out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaRule {
+    /// Host suffix of the provider's endpoints like `backblazeb2.com`, `None` for any
+    pub host: Option<&'static str>,
+    pub code: &'static str,
+    /// Text the message contains, for providers answering with a generic code
+    pub message: Option<&'static str>,
+}
+
+/// Quota errors of known providers. Provider specific ones are scoped to its endpoints
+/// by `host`, add a provider's rules here as they turn up.
+pub const QUOTA_RULES: &[QuotaRule] = &[
+    // Ceph RGW, Wasabi and others with bucket or user quotas
+    QuotaRule {
+        host: None,
+        code: "QuotaExceeded",
+        message: None,
+    },
+    // MinIO bucket quotas
+    QuotaRule {
+        host: None,
+        code: "XMinioAdminBucketQuotaExceeded",
+        message: None,
+    },
+    // B2 denies requests past the storage, download and transaction caps
+    QuotaRule {
+        host: Some("backblazeb2.com"),
+        code: "AccessDenied",
+        message: Some("cap exceeded"),
+    },
+];
+
+/// Whether an error `code` with `message` from `endpoint` means a quota ran out
+pub fn is_quota_error(endpoint: &str, code: &str, message: &str) -> bool {
+    let host = EndpointUrl::parse(endpoint)
+        .map(|endpoint| endpoint.host.to_ascii_lowercase())
+        .unwrap_or_default();
+    let message = message.to_ascii_lowercase();
+
+    QUOTA_RULES.iter().any(|rule| {
+        rule.code == code
+            && rule
+                .host
+                .is_none_or(|suffix| host == suffix || host.ends_with(&format!(".{suffix}")))
+            && rule.message.is_none_or(|text| message.contains(text))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const B2: &str = "https://s3.us-west-004.backblazeb2.com";
+
+    #[test]
+    fn matches_generic_quota_codes() {
+        assert!(is_quota_error(
+            "https://s3.wasabisys.com",
+            "QuotaExceeded",
+            ""
+        ));
+        assert!(is_quota_error(
+            "http://192.168.1.50:9000",
+            "XMinioAdminBucketQuotaExceeded",
+            "Bucket quota exceeded"
+        ));
+        assert!(!is_quota_error("http://192.168.1.50:9000", "SlowDown", ""));
+    }
+
+    #[test]
+    fn scopes_provider_rules_to_their_endpoints() {
+        assert!(is_quota_error(
+            B2,
+            "AccessDenied",
+            "Cannot upload files, storage cap exceeded."
+        ));
+        assert!(is_quota_error(
+            B2,
+            "AccessDenied",
+            "Transaction cap exceeded, see the Caps & Alerts page"
+        ));
+        assert!(!is_quota_error(B2, "AccessDenied", "Access Denied"));
+        assert!(!is_quota_error(
+            "https://s3.example.com",
+            "AccessDenied",
+            "storage cap exceeded"
+        ));
+        assert!(!is_quota_error(
+            "https://notbackblazeb2.com",
+            "AccessDenied",
+            "storage cap exceeded"
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use balti_err::AppResult;
+
+use crate::{MAX_PAGE_SIZE, ObjectPage, S3Object, S3Remote, labelled, list_page};
+
+/// Hits a search collects before it stops, past that the query should be narrowed
+pub const MAX_SEARCH_MATCHES: usize = 1000;
+
+/// Whether `key` below `prefix` has `query` in its path past the prefix, ignoring case.
+/// `query` is expected lowercased already.
+fn key_matches(prefix: &str, key: &str, query: &str) -> bool {
+    let path = key.strip_prefix(prefix).unwrap_or(key);
+    !path.is_empty() && path.to_lowercase().contains(query)
+}
+
+/// Finds every file below `prefix`, in any folder under it, whose path past the prefix
+/// contains `query` ignoring case. Matches keep their full keys and are handed to
+/// `on_matches` a listing page at a time. Stops once `on_matches` returns false or after
+/// [`MAX_SEARCH_MATCHES`].
+pub async fn search_objects(
+    remote: S3Remote,
+    prefix: &str,
+    query: &str,
+    on_matches: impl FnMut(Vec<S3Object>) -> bool,
+) -> AppResult<()> {
+    labelled(
+        "search_objects",
+        &remote,
+        search_listed(
+            |token| {
+                let remote = remote.clone();
+                async move {
+                    list_page(remote, prefix, None, MAX_PAGE_SIZE, token.as_deref()).await
+                }
+            },
+            prefix,
+            query,
+            on_matches,
+        ),
+    )
+    .await
+}
+
+/// Pages through `list` past `prefix` for keys matching `query`, see [`search_objects`]
+async fn search_listed<F: Future<Output = AppResult<ObjectPage>>>(
+    mut list: impl FnMut(Option<Arc<str>>) -> F,
+    prefix: &str,
+    query: &str,
+    mut on_matches: impl FnMut(Vec<S3Object>) -> bool,
+) -> AppResult<()> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Ok(());
+    }
+
+    let mut token: Option<Arc<str>> = None;
+    let mut found = 0;
+    loop {
+        let page = list(token.take()).await?;
+        let matches = page
+            .objects
+            .into_iter()
+            .filter(|object| !object.is_folder() && !object.is_marker())
+            .filter(|object| key_matches(prefix, object.key(), &query))
+            .take(MAX_SEARCH_MATCHES - found)
+            .collect::<Vec<_>>();
+        found += matches.len();
+
+        if !matches.is_empty() && !on_matches(matches) {
+            return Ok(());
+        }
+        match page.next_token {
+            Some(next) if found < MAX_SEARCH_MATCHES => token = Some(next),
+            _ => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::__S3Object;
+
+    use super::*;
+
+    fn file(key: &str) -> S3Object {
+        Arc::new(__S3Object::file(key.into(), 1, None, None))
+    }
+
+    #[test]
+    fn matches_past_the_prefix_ignoring_case() {
+        assert!(key_matches("photos/", "photos/2024/Beach.JPG", "beach"));
+        assert!(key_matches("photos/", "photos/2024/beach.jpg", "2024/b"));
+        // the prefix itself doesn't count
+        assert!(!key_matches("photos/", "photos/2024/beach.jpg", "photos"));
+        assert!(!key_matches("photos/", "photos/", ""));
+    }
+
+    #[tokio::test]
+    async fn searches_every_page() {
+        let pages = [
+            vec![
+                file("docs/Report.pdf"),
+                file("docs/__fd.dat"),
+                file("docs/notes.txt"),
+            ],
+            vec![file("docs/2024/report-final.pdf"), file("docs/2024/a.png")],
+        ];
+        let mut found = Vec::new();
+        search_listed(
+            |token| {
+                let (objects, next_token) = match token {
+                    None => (pages[0].clone(), Some("2".into())),
+                    Some(_) => (pages[1].clone(), None),
+                };
+                async move {
+                    Ok(ObjectPage {
+                        objects,
+                        next_token,
+                    })
+                }
+            },
+            "docs/",
+            " REPORT ",
+            |matches| {
+                found.extend(matches.iter().map(|object| object.key().to_string()));
+                true
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(found, ["docs/Report.pdf", "docs/2024/report-final.pdf"]);
+
+        // stopping after the first page doesn't list the second
+        let mut lists = 0;
+        search_listed(
+            |_token| {
+                lists += 1;
+                let objects = pages[0].clone();
+                async move {
+                    Ok(ObjectPage {
+                        objects,
+                        next_token: Some("2".into()),
+                    })
+                }
+            },
+            "docs/",
+            "notes",
+            |_matches| false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(lists, 1);
+    }
+}
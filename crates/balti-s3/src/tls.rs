@@ -0,0 +1,290 @@
+use std::{
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use aws_smithy_http_client::tls::{TlsContext, TrustStore};
+use aws_smithy_runtime_api::client::{
+    http::{
+        HttpConnector, HttpConnectorFuture, SharedHttpClient, SharedHttpConnector, http_client_fn,
+    },
+    orchestrator::{HttpRequest, HttpResponse},
+    result::ConnectorError,
+};
+use aws_smithy_types::{base64, body::SdkBody};
+use balti_err::{AppError, AppResult};
+use http::{HeaderValue, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::{
+        legacy::{
+            Client,
+            connect::{HttpConnector as TcpConnector, proxy::Tunnel},
+        },
+        proxy::matcher::Matcher,
+    },
+    rt::TokioExecutor,
+};
+use rustls::{
+    ClientConfig, DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, aws_lc_rs, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use tower_service::Service;
+
+use crate::ProxySettings;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Trusts the certificates in the PEM bundle on top of the system roots
+pub(crate) fn tls_context(ca_bundle: Option<&str>) -> AppResult<TlsContext> {
+    let Some(path) = ca_bundle.filter(|p| !p.trim().is_empty()) else {
+        return Ok(TlsContext::default());
+    };
+
+    let pem = read_ca_bundle(Path::new(path))?;
+    TlsContext::builder()
+        .with_trust_store(
+            TrustStore::empty()
+                .with_native_roots(true)
+                .with_pem_certificate(pem),
+        )
+        .build()
+        .map_err(|err| AppError::message(format!("Invalid CA bundle \"{path}\": {err}")))
+}
+
+/// Reads the bundle, making sure it holds at least one PEM certificate
+pub fn read_ca_bundle(path: &Path) -> AppResult<Vec<u8>> {
+    let pem = std::fs::read(path).map_err(|err| {
+        AppError::message(format!(
+            "Can't read CA bundle \"{}\": {err}",
+            path.display()
+        ))
+    })?;
+
+    if !String::from_utf8_lossy(&pem).contains("-----BEGIN CERTIFICATE-----") {
+        return Err(AppError::message(format!(
+            "\"{}\" has no PEM certificates",
+            path.display()
+        )));
+    }
+    Ok(pem)
+}
+
+/// Whether a connection error comes from the server's certificate failing verification
+pub fn is_certificate_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    ["invalid peer certificate", "unknownissuer"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+/// Proxy the insecure client tunnels its connections through, with CONNECT
+#[derive(Clone)]
+pub(crate) struct ProxyRoute {
+    matcher: Arc<Matcher>,
+    auth: Option<HeaderValue>,
+}
+
+impl ProxyRoute {
+    /// Route through the configured proxy, skipping its `no_proxy` hosts
+    pub(crate) fn configured(settings: &ProxySettings) -> AppResult<Self> {
+        if settings.url.starts_with("https://") {
+            return Err(AppError::message(
+                "Insecure TLS can't connect to a proxy over TLS, use the proxy's http:// address",
+            ));
+        }
+
+        let mut matcher = Matcher::builder().all(settings.url.as_ref());
+        if let Some(no_proxy) = settings.no_proxy.as_deref() {
+            // `*.internal` is written `.internal` here, both match the subdomains
+            let no_proxy = no_proxy
+                .split(',')
+                .map(|host| host.trim().trim_start_matches('*'))
+                .collect::<Vec<_>>()
+                .join(",");
+            matcher = matcher.no(no_proxy);
+        }
+        let auth = match settings.username.as_deref().filter(|u| !u.is_empty()) {
+            Some(username) => {
+                let password = settings.password.as_deref().unwrap_or_default();
+                let credentials = base64::encode(format!("{username}:{password}"));
+                let auth = HeaderValue::from_str(&format!("Basic {credentials}"))
+                    .map_err(|_| AppError::message("Proxy credentials can't go in a header"))?;
+                Some(auth)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            matcher: Arc::new(matcher.build()),
+            auth,
+        })
+    }
+
+    /// Route through the proxy the environment sets, like curl reads it
+    pub(crate) fn from_env() -> Self {
+        Self {
+            matcher: Arc::new(Matcher::from_env()),
+            auth: None,
+        }
+    }
+}
+
+/// HTTPS client that accepts any server certificate, for lab setups only. Connections
+/// go through `proxy` when it covers the host, and wait as long as the timeouts of the
+/// SDK's connector settings allow.
+pub(crate) fn insecure_http_client(proxy: Option<ProxyRoute>) -> AppResult<SharedHttpClient> {
+    let provider = Arc::new(aws_lc_rs::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|err| AppError::err(err))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+        .with_no_client_auth();
+
+    Ok(http_client_fn(move |settings, _components| {
+        let mut tcp = TcpConnector::new();
+        tcp.enforce_http(false);
+        tcp.set_connect_timeout(settings.connect_timeout());
+        let https = HttpsConnectorBuilder::new()
+            .with_tls_config(config.clone())
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(ProxyConnector {
+                tcp,
+                proxy: proxy.clone(),
+            });
+
+        SharedHttpConnector::new(InsecureConnector {
+            client: Client::builder(TokioExecutor::new()).build(https),
+            read_timeout: settings.read_timeout(),
+        })
+    }))
+}
+
+/// Connects to the host directly, or through a tunnel when the proxy covers it
+#[derive(Clone)]
+struct ProxyConnector {
+    tcp: TcpConnector,
+    proxy: Option<ProxyRoute>,
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = <TcpConnector as Service<Uri>>::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.tcp.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let route = self
+            .proxy
+            .as_ref()
+            .and_then(|route| Some((route.matcher.intercept(&dst)?, route.auth.clone())));
+        let Some((intercept, auth)) = route else {
+            let connecting = self.tcp.call(dst);
+            return Box::pin(async move { connecting.await.map_err(Into::into) });
+        };
+
+        let mut tunnel = Tunnel::new(intercept.uri().clone(), self.tcp.clone());
+        // credentials of the environment's proxy come in its url
+        if let Some(auth) = auth.or_else(|| intercept.basic_auth().cloned()) {
+            tunnel = tunnel.with_auth(auth);
+        }
+        Box::pin(async move { tunnel.call(dst).await.map_err(Into::into) })
+    }
+}
+
+#[derive(Clone)]
+struct InsecureConnector {
+    client: Client<HttpsConnector<ProxyConnector>, SdkBody>,
+    /// Longest wait for the response to start, `None` waits as long as it takes
+    read_timeout: Option<Duration>,
+}
+
+impl std::fmt::Debug for InsecureConnector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InsecureConnector")
+            .field("read_timeout", &self.read_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpConnector for InsecureConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let (client, read_timeout) = (self.client.clone(), self.read_timeout);
+        HttpConnectorFuture::new(async move {
+            let request = request
+                .try_into_http1x()
+                .map_err(|err| ConnectorError::user(err.into()))?;
+            let response = client.request(request);
+            let response = match read_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, response)
+                    .await
+                    .map_err(|err| ConnectorError::timeout(err.into()))?,
+                None => response.await,
+            }
+            .map_err(|err| ConnectorError::io(err.into()))?;
+
+            HttpResponse::try_from(response.map(SdkBody::from_body_1_x))
+                .map_err(|err| ConnectorError::other(err.into(), None))
+        })
+    }
+}
+
+/// Skips certificate checks, handshake signatures are still verified
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
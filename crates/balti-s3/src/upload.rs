@@ -0,0 +1,336 @@
+use std::{path::PathBuf, sync::Arc};
+
+use aws_sdk_s3::types::{ObjectCannedAcl, ServerSideEncryption, StorageClass};
+use balti_err::{AppError, AppResult, BatchResult};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{PART_SIZE, S3Remote, upload_file_multipart};
+
+/// Files [`upload_many`] uploads at once, unless told otherwise
+pub const UPLOAD_CONCURRENCY: usize = 8;
+
+/// Gets how many files of a batch are done uploading, failed ones included, and how
+/// many it has
+pub type OnUploaded<'a> = &'a (dyn Fn(usize, usize) + Send + Sync);
+
+/// Encryptions an upload can ask for, the others are managed by the provider
+pub const ENCRYPTIONS: [&str; 2] = ["AES256", "aws:kms"];
+
+/// Content types by lowercased file extension, the rest upload as S3's
+/// `binary/octet-stream`
+const CONTENT_TYPES: [(&str, &str); 32] = [
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("wasm", "application/wasm"),
+    ("parquet", "application/vnd.apache.parquet"),
+];
+
+/// How uploads to a remote are written, unless an upload brings its own. Saved as
+/// the remote's `upload_defaults` table, every field optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadDefaults {
+    /// Like `STANDARD_IA`, the bucket's default when `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<Arc<str>>,
+    /// One of [`ENCRYPTIONS`], the bucket's default when `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<Arc<str>>,
+    /// Key `aws:kms` encrypts with, the account's S3 key when `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kms_key_id: Option<Arc<str>>,
+    /// Canned ACL like `public-read`, the bucket's default when `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acl: Option<Arc<str>>,
+    /// Sets the content type from the file's extension
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub detect_content_type: bool,
+}
+
+impl UploadDefaults {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Storage classes differ between providers, only their shape is checked.
+    /// Encryptions and ACLs have to be ones S3 knows.
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(class) = self.storage_class.as_deref()
+            && !class
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(AppError::message(format!(
+                "Invalid storage class \"{class}\", like STANDARD_IA"
+            )));
+        }
+        if let Some(encryption) = self.encryption.as_deref()
+            && !ENCRYPTIONS.contains(&encryption)
+        {
+            return Err(AppError::message(format!(
+                "Unknown encryption \"{encryption}\", one of {}",
+                ENCRYPTIONS.join(", ")
+            )));
+        }
+        if self.kms_key_id.is_some() && self.encryption.as_deref() != Some("aws:kms") {
+            return Err(AppError::message("A KMS key needs the aws:kms encryption"));
+        }
+        if let Some(acl) = self.acl.as_deref()
+            && !ObjectCannedAcl::values().contains(&acl)
+        {
+            return Err(AppError::message(format!(
+                "Unknown ACL \"{acl}\", one of {}",
+                ObjectCannedAcl::values().join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn storage_class(&self) -> Option<StorageClass> {
+        self.storage_class.as_deref().map(StorageClass::from)
+    }
+
+    pub(crate) fn encryption(&self) -> Option<ServerSideEncryption> {
+        self.encryption.as_deref().map(ServerSideEncryption::from)
+    }
+
+    pub(crate) fn acl(&self) -> Option<ObjectCannedAcl> {
+        self.acl.as_deref().map(ObjectCannedAcl::from)
+    }
+
+    /// Content type `key` uploads with, `None` leaves it to the provider
+    pub(crate) fn content_type(&self, key: &str) -> Option<&'static str> {
+        self.detect_content_type
+            .then(|| content_type_for(key))
+            .flatten()
+    }
+}
+
+/// Content type of `key` by its extension, `None` when it's not a known one
+pub fn content_type_for(key: &str) -> Option<&'static str> {
+    let name = key.rsplit('/').next().unwrap_or(key);
+    let (_, extension) = name.rsplit_once('.')?;
+    let extension = extension.to_ascii_lowercase();
+    CONTENT_TYPES
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, content_type)| *content_type)
+}
+
+/// Uploads each file to its key with `options`, or the remote's upload defaults when
+/// `None`, `concurrency` at once and large ones in parts of [`PART_SIZE`]. A failed
+/// upload only fails its own key, the others go on.
+pub async fn upload_many(
+    remote: S3Remote,
+    items: Vec<(Arc<str>, PathBuf)>,
+    concurrency: usize,
+    options: Option<&UploadDefaults>,
+    on_uploaded: Option<OnUploaded<'_>>,
+) -> BatchResult {
+    upload_each(items, concurrency, on_uploaded, |key, path| {
+        let remote = remote.clone();
+        async move { upload_file_multipart(remote, &key, &path, PART_SIZE, options).await }
+    })
+    .await
+}
+
+/// Uploads `items` through `upload`, `concurrency` at once, collecting the outcome per key
+async fn upload_each<U>(
+    items: Vec<(Arc<str>, PathBuf)>,
+    concurrency: usize,
+    on_uploaded: Option<OnUploaded<'_>>,
+    upload: impl Fn(Arc<str>, PathBuf) -> U,
+) -> BatchResult
+where
+    U: Future<Output = AppResult<()>>,
+{
+    let total = items.len();
+    let mut uploads = futures::stream::iter(items)
+        .map(|(key, path)| {
+            let upload = upload(key.clone(), path);
+            async move { (key, upload.await) }
+        })
+        .buffer_unordered(concurrency.max(1));
+
+    let mut result = BatchResult::default();
+    while let Some((key, outcome)) = uploads.next().await {
+        match outcome {
+            Ok(()) => result.succeeded.push(key),
+            Err(err) => result.failed.push((key, err)),
+        }
+        if let Some(on_uploaded) = on_uploaded {
+            on_uploaded(result.succeeded.len() + result.failed.len(), total);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        sync::Mutex,
+        task::{Context, Poll},
+    };
+
+    use super::*;
+
+    #[test]
+    fn detects_content_types() {
+        assert_eq!(content_type_for("site/index.HTML"), Some("text/html"));
+        assert_eq!(content_type_for("a.tar.gz"), Some("application/gzip"));
+        assert_eq!(content_type_for("v1.2/README"), None);
+        assert_eq!(content_type_for("data.bin"), None);
+
+        let defaults = UploadDefaults::default();
+        assert_eq!(defaults.content_type("logo.png"), None);
+        let defaults = UploadDefaults {
+            detect_content_type: true,
+            ..Default::default()
+        };
+        assert_eq!(defaults.content_type("logo.png"), Some("image/png"));
+    }
+
+    #[test]
+    fn validates_upload_defaults() {
+        let archive = UploadDefaults {
+            storage_class: Some("STANDARD_IA".into()),
+            encryption: Some("aws:kms".into()),
+            kms_key_id: Some("alias/archive".into()),
+            ..Default::default()
+        };
+        assert!(archive.validate().is_ok());
+        let cdn = UploadDefaults {
+            acl: Some("public-read".into()),
+            detect_content_type: true,
+            ..Default::default()
+        };
+        assert!(cdn.validate().is_ok());
+
+        for invalid in [
+            UploadDefaults {
+                storage_class: Some("standard ia".into()),
+                ..Default::default()
+            },
+            UploadDefaults {
+                encryption: Some("rot13".into()),
+                ..Default::default()
+            },
+            UploadDefaults {
+                kms_key_id: Some("alias/archive".into()),
+                ..Default::default()
+            },
+            UploadDefaults {
+                acl: Some("everyone".into()),
+                ..Default::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{invalid:?}");
+        }
+    }
+
+    /// Lets the other uploads run before finishing, like a request in flight does
+    async fn in_flight() {
+        let mut waited = false;
+        std::future::poll_fn(|cx: &mut Context| {
+            if waited {
+                return Poll::Ready(());
+            }
+            waited = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await
+    }
+
+    fn items(count: usize) -> Vec<(Arc<str>, PathBuf)> {
+        (0..count)
+            .map(|i| {
+                (
+                    Arc::from(format!("photos/{i:02}.jpg")),
+                    PathBuf::from(format!("{i:02}.jpg")),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn uploads_many_at_bounded_concurrency() {
+        let running = Cell::new(0);
+        let most_running = Cell::new(0);
+        let progress = Mutex::new(Vec::new());
+        let on_uploaded = |done, total| progress.lock().unwrap().push((done, total));
+
+        let result = futures::executor::block_on(upload_each(
+            items(20),
+            3,
+            Some(&on_uploaded),
+            |key, _path| {
+                let (running, most_running) = (&running, &most_running);
+                async move {
+                    running.set(running.get() + 1);
+                    most_running.set(most_running.get().max(running.get()));
+                    in_flight().await;
+                    running.set(running.get() - 1);
+                    if key.ends_with("7.jpg") {
+                        Err(AppError::message("connection reset"))
+                    } else {
+                        Ok(())
+                    }
+                }
+            },
+        ));
+
+        assert_eq!(most_running.get(), 3);
+        assert_eq!(result.succeeded.len(), 18);
+        // a failure doesn't stop the uploads after it
+        let mut failed = result
+            .failed
+            .iter()
+            .map(|(key, _)| key.as_ref())
+            .collect::<Vec<_>>();
+        failed.sort();
+        assert_eq!(failed, ["photos/07.jpg", "photos/17.jpg"]);
+        assert_eq!(
+            progress.into_inner().unwrap(),
+            (1..=20).map(|done| (done, 20)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn uploads_one_at_a_time_at_least() {
+        let result = futures::executor::block_on(upload_each(items(2), 0, None, |_, _| {
+            std::future::ready(Ok(()))
+        }));
+        assert_eq!(result.succeeded.len(), 2);
+    }
+}
@@ -0,0 +1,481 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use balti_err::{AppError, AppResult};
+
+use crate::{FOLDER_MARKER, MAX_PAGE_SIZE, ObjectKind, S3Object, S3Remote, labelled, list_objects};
+
+/// Files and folders of a local tree, by their path below where it's read from with
+/// `/` between names
+#[derive(Debug, Default, Clone)]
+pub struct LocalTree {
+    files: BTreeMap<String, PathBuf>,
+    /// Every folder, empty ones too
+    dirs: BTreeSet<String>,
+}
+
+impl LocalTree {
+    /// Reads the folder at `root`, which lands under its own name. Symlinked folders
+    /// aren't followed, so a link back up the tree can't loop.
+    pub fn read(root: &Path) -> AppResult<Self> {
+        let name = root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| not_utf8(root))?;
+
+        let mut tree = Self::default();
+        tree.read_dir(root, name.to_owned())?;
+        Ok(tree)
+    }
+
+    fn read_dir(&mut self, dir: &Path, path: String) -> AppResult<()> {
+        let read_err = |err: std::io::Error| {
+            AppError::message(format!("Can't read \"{}\": {err}", dir.display()))
+        };
+
+        for entry in fs::read_dir(dir).map_err(read_err)? {
+            let entry = entry.map_err(read_err)?;
+            let local = entry.path();
+            let name = entry.file_name();
+            let name = name.to_str().ok_or_else(|| not_utf8(&local))?;
+            let child = format!("{path}/{name}");
+
+            if entry.file_type().map_err(read_err)?.is_dir() {
+                self.read_dir(&local, child)?;
+            } else if local.is_file() {
+                self.files.insert(child, local);
+            }
+        }
+
+        self.dirs.insert(path);
+        Ok(())
+    }
+
+    /// Files and folders by the folder they're in, read once rather than per folder
+    fn children(&self) -> Children<'_> {
+        let mut children = Children::default();
+        for (path, local) in &self.files {
+            children
+                .files
+                .entry(parent(path))
+                .or_default()
+                .push((file_name(path), local));
+        }
+        for path in self.dirs.iter().filter(|path| !path.is_empty()) {
+            children.dirs.entry(parent(path)).or_default().push(path);
+        }
+        children
+    }
+}
+
+/// What a [`LocalTree`] has right below each of its folders, in name order
+#[derive(Default)]
+struct Children<'a> {
+    /// Names of the files, with where each is
+    files: BTreeMap<&'a str, Vec<(&'a str, &'a PathBuf)>>,
+    /// Paths of the folders
+    dirs: BTreeMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> Children<'a> {
+    fn files_in(&self, dir: &str) -> &[(&'a str, &'a PathBuf)] {
+        self.files.get(dir).map_or(&[], Vec::as_slice)
+    }
+
+    fn dirs_in(&self, dir: &str) -> &[&'a str] {
+        self.dirs.get(dir).map_or(&[], Vec::as_slice)
+    }
+}
+
+fn not_utf8(path: &Path) -> AppError {
+    AppError::message(format!(
+        "Can't upload \"{}\", its name isn't valid UTF-8",
+        path.display()
+    ))
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Names right below a prefix, as a shallow listing of it has them
+#[derive(Debug, Default, Clone)]
+pub struct Level {
+    folders: BTreeSet<String>,
+    files: BTreeSet<String>,
+    /// Whether a [`ObjectKind::FolderMarker`] keeps the folder alive
+    marked: bool,
+}
+
+impl Level {
+    /// Adds a page of the listing of `prefix`
+    pub fn extend(&mut self, prefix: &str, objects: &[S3Object]) {
+        for object in objects {
+            let Some(name) = object.key().strip_prefix(prefix) else {
+                continue;
+            };
+            match object.kind() {
+                ObjectKind::FolderPrefix => {
+                    self.folders.insert(name.trim_end_matches('/').to_owned());
+                }
+                ObjectKind::FolderMarker => {
+                    self.marked = true;
+                }
+                ObjectKind::RealFile => {
+                    self.files.insert(name.to_owned());
+                }
+            }
+        }
+    }
+}
+
+/// Where a local tree lands below a prefix, worked out before anything uploads
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UploadPlan {
+    /// Keys of the folder markers to write first, parents before their children
+    pub markers: Vec<String>,
+    /// Keys the local files upload to, with where each comes from
+    pub uploads: Vec<(String, PathBuf)>,
+    /// Keys of files an upload replaces
+    pub overwrites: Vec<String>,
+    /// Names already taken by the other kind, nothing uploads to or below them
+    pub collisions: Vec<Collision>,
+}
+
+/// A local name the destination has as the other of file and folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Collision {
+    /// A local file where a folder of that name is
+    FileOnFolder(String),
+    /// A local folder where a file of that name is
+    FolderOnFile(String),
+}
+
+impl Collision {
+    pub fn key(&self) -> &str {
+        match self {
+            Collision::FileOnFolder(key) | Collision::FolderOnFile(key) => key,
+        }
+    }
+}
+
+/// How far planning got with the levels listed so far
+#[derive(Debug, PartialEq, Eq)]
+pub enum Scan {
+    /// These prefixes have to be listed before the plan is complete
+    List(Vec<String>),
+    Ready(UploadPlan),
+}
+
+/// Plans uploading `tree` below `to_prefix`, which is empty or ends in `/`, from the
+/// shallow listings in `listed` by prefix. Only folders that exist on both sides need
+/// listing, anything below a new folder is new as well.
+pub fn scan(tree: &LocalTree, to_prefix: &str, listed: &HashMap<String, Level>) -> Scan {
+    let Some(level) = listed.get(to_prefix) else {
+        return Scan::List(vec![to_prefix.to_owned()]);
+    };
+
+    let mut plan = UploadPlan::default();
+    let mut needed = Vec::new();
+    visit(
+        &tree.children(),
+        listed,
+        "",
+        to_prefix,
+        Some(level),
+        &mut plan,
+        &mut needed,
+    );
+
+    if needed.is_empty() {
+        Scan::Ready(plan)
+    } else {
+        Scan::List(needed)
+    }
+}
+
+/// Plans the local folder `dir` landing at `prefix`, where `level` is `None` when
+/// nothing exists there yet
+fn visit(
+    tree: &Children,
+    listed: &HashMap<String, Level>,
+    dir: &str,
+    prefix: &str,
+    level: Option<&Level>,
+    plan: &mut UploadPlan,
+    needed: &mut Vec<String>,
+) {
+    for &(name, local) in tree.files_in(dir) {
+        let key = format!("{prefix}{name}");
+        match level {
+            Some(level) if level.folders.contains(name) => {
+                plan.collisions.push(Collision::FileOnFolder(key));
+                continue;
+            }
+            Some(level) if level.files.contains(name) => plan.overwrites.push(key.clone()),
+            _ => {}
+        }
+        plan.uploads.push((key, local.clone()));
+    }
+
+    for &path in tree.dirs_in(dir) {
+        let name = file_name(path);
+        let child = format!("{prefix}{name}/");
+        let child_level = match level {
+            Some(level) if level.files.contains(name) => {
+                plan.collisions
+                    .push(Collision::FolderOnFile(format!("{prefix}{name}")));
+                continue;
+            }
+            Some(level) if level.folders.contains(name) => match listed.get(&child) {
+                Some(child_level) => Some(child_level),
+                None => {
+                    needed.push(child);
+                    continue;
+                }
+            },
+            _ => None,
+        };
+
+        if !child_level.is_some_and(|level| level.marked) {
+            plan.markers.push(format!("{child}{FOLDER_MARKER}"));
+        }
+        visit(tree, listed, path, &child, child_level, plan, needed);
+    }
+}
+
+/// Plans uploading the folder at `from_dir` below `to_prefix`, listing each level of
+/// the destination it goes through at most once
+pub async fn plan_upload_dir(
+    remote: S3Remote,
+    from_dir: &Path,
+    to_prefix: &str,
+) -> AppResult<UploadPlan> {
+    labelled("plan_upload_dir", &remote, async {
+        remote.check_scope(to_prefix)?;
+        let tree = LocalTree::read(from_dir)?;
+
+        prescan(&tree, to_prefix, |prefix| {
+            list_level(remote.clone(), prefix)
+        })
+        .await
+    })
+    .await
+}
+
+/// Lists what `scan` asks for through `list` until it has a plan
+async fn prescan<F>(
+    tree: &LocalTree,
+    to_prefix: &str,
+    mut list: impl FnMut(String) -> F,
+) -> AppResult<UploadPlan>
+where
+    F: Future<Output = AppResult<Level>>,
+{
+    let mut listed = HashMap::new();
+    loop {
+        match scan(tree, to_prefix, &listed) {
+            Scan::Ready(plan) => return Ok(plan),
+            Scan::List(prefixes) => {
+                for prefix in prefixes {
+                    let level = list(prefix.clone()).await?;
+                    listed.insert(prefix, level);
+                }
+            }
+        }
+    }
+}
+
+async fn list_level(remote: S3Remote, prefix: String) -> AppResult<Level> {
+    let mut level = Level::default();
+    let mut token: Option<Arc<str>> = None;
+    loop {
+        let page = list_objects(remote.clone(), &prefix, MAX_PAGE_SIZE, token.as_deref()).await?;
+        level.extend(&prefix, &page.objects);
+
+        match page.next_token {
+            Some(next) => token = Some(next),
+            None => return Ok(level),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::__S3Object;
+
+    /// Bucket in memory that answers shallow listings and counts them
+    struct FakeStore {
+        keys: BTreeSet<String>,
+        lists: RefCell<Vec<String>>,
+    }
+
+    impl FakeStore {
+        fn new(keys: &[&str]) -> Self {
+            Self {
+                keys: keys.iter().map(|key| key.to_string()).collect(),
+                lists: RefCell::default(),
+            }
+        }
+
+        fn list(&self, prefix: String) -> Level {
+            let mut objects = Vec::new();
+            let mut folders = BTreeSet::new();
+            for key in self.keys.iter().filter(|key| key.starts_with(&prefix)) {
+                match key[prefix.len()..].split_once('/') {
+                    Some((name, _)) => {
+                        folders.insert(format!("{prefix}{name}/"));
+                    }
+                    None => objects.push(Arc::new(__S3Object::file(
+                        key.as_str().into(),
+                        0,
+                        None,
+                        None,
+                    ))),
+                }
+            }
+            objects.extend(
+                folders
+                    .into_iter()
+                    .map(|folder| Arc::new(__S3Object::Folder(folder.into()))),
+            );
+
+            let mut level = Level::default();
+            level.extend(&prefix, &objects);
+            self.lists.borrow_mut().push(prefix);
+            level
+        }
+    }
+
+    fn tree(files: &[&str], empty_dirs: &[&str]) -> LocalTree {
+        let mut tree = LocalTree::default();
+        for path in files.iter().chain(empty_dirs) {
+            let mut dir = parent(path);
+            while !dir.is_empty() {
+                tree.dirs.insert(dir.to_owned());
+                dir = parent(dir);
+            }
+        }
+        tree.files = files
+            .iter()
+            .map(|path| (path.to_string(), PathBuf::from("/local").join(path)))
+            .collect();
+        tree.dirs
+            .extend(empty_dirs.iter().map(|dir| dir.to_string()));
+        tree
+    }
+
+    fn plan(store: &FakeStore, tree: &LocalTree, to_prefix: &str) -> UploadPlan {
+        futures::executor::block_on(prescan(tree, to_prefix, |prefix| {
+            std::future::ready(Ok(store.list(prefix)))
+        }))
+        .unwrap()
+    }
+
+    fn keys(plan: &UploadPlan) -> Vec<&str> {
+        plan.uploads.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    #[test]
+    fn only_lists_folders_on_both_sides() {
+        let store = FakeStore::new(&[
+            "backup/photos/__fd.dat",
+            "backup/photos/2023/a.jpg",
+            "backup/notes.txt",
+        ]);
+        let tree = tree(
+            &[
+                "photos/2023/b.jpg",
+                "photos/2024/jan/c.jpg",
+                "photos/2024/feb/d.jpg",
+            ],
+            &["photos/empty"],
+        );
+
+        let plan = plan(&store, &tree, "backup/");
+        assert_eq!(
+            *store.lists.borrow(),
+            ["backup/", "backup/photos/", "backup/photos/2023/"]
+        );
+        assert_eq!(
+            keys(&plan),
+            [
+                "backup/photos/2023/b.jpg",
+                "backup/photos/2024/feb/d.jpg",
+                "backup/photos/2024/jan/c.jpg",
+            ]
+        );
+        // folders that only exist through the keys below them get a marker too
+        assert_eq!(
+            plan.markers,
+            [
+                "backup/photos/2023/__fd.dat",
+                "backup/photos/2024/__fd.dat",
+                "backup/photos/2024/feb/__fd.dat",
+                "backup/photos/2024/jan/__fd.dat",
+                "backup/photos/empty/__fd.dat",
+            ]
+        );
+        assert!(plan.collisions.is_empty());
+        assert!(plan.overwrites.is_empty());
+    }
+
+    #[test]
+    fn uploads_into_an_empty_bucket() {
+        let store = FakeStore::new(&[]);
+        let plan = plan(
+            &store,
+            &tree(&["site/index.html", "site/css/a.css"], &[]),
+            "",
+        );
+
+        assert_eq!(*store.lists.borrow(), [""]);
+        assert_eq!(keys(&plan), ["site/index.html", "site/css/a.css"]);
+        assert_eq!(plan.markers, ["site/__fd.dat", "site/css/__fd.dat"]);
+    }
+
+    #[test]
+    fn finds_collisions_before_uploading() {
+        let store = FakeStore::new(&[
+            "site/css",
+            "site/index.html/old.html",
+            "site/about.html",
+            "site/js/",
+            "site/js/app.js",
+        ]);
+        let tree = tree(
+            &[
+                "site/css/a.css",
+                "site/index.html",
+                "site/about.html",
+                "site/js/app.js",
+            ],
+            &[],
+        );
+
+        let plan = plan(&store, &tree, "");
+        assert_eq!(
+            plan.collisions,
+            [
+                Collision::FileOnFolder("site/index.html".into()),
+                Collision::FolderOnFile("site/css".into()),
+            ]
+        );
+        // nothing goes into or below a collision
+        assert_eq!(keys(&plan), ["site/about.html", "site/js/app.js"]);
+        assert_eq!(plan.overwrites, ["site/about.html", "site/js/app.js"]);
+        // the empty object named like the folder already keeps it alive
+        assert_eq!(plan.markers, ["site/__fd.dat"]);
+    }
+}
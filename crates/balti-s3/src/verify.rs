@@ -0,0 +1,141 @@
+use aws_sdk_s3::types::ChecksumMode;
+use balti_err::{AppError, AppResult};
+
+use crate::{ObjectKind, S3Remote, head_object, labelled};
+
+/// Bytes a sampled check reads from each end of an object
+pub const SAMPLE_BYTES: u64 = 64 * 1024;
+/// Largest object a full check reads by default, bigger ones get sampled
+pub const DEFAULT_FULL_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// How much of an object a verification reads, each tier does what the one before does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerifyTier {
+    /// Existence and size from a `HEAD`
+    #[default]
+    Head,
+    /// Reads the first and last [`SAMPLE_BYTES`]
+    Sampled,
+    /// Reads the whole object, checked against its stored checksum when it has one
+    Full,
+}
+
+/// What's wrong with an object the listing showed as `listed` bytes, given the size a
+/// `HEAD` reports. Empty objects are only expected for folder markers.
+fn size_problem(key: &str, listed: i64, size: i64) -> Option<String> {
+    if size != listed {
+        return Some(format!(
+            "\"{key}\" was listed as {listed} bytes but has {size}"
+        ));
+    }
+    let is_marker = ObjectKind::of_key(key, size) == ObjectKind::FolderMarker;
+    (size == 0 && !is_marker).then(|| format!("\"{key}\" is empty"))
+}
+
+/// Inclusive byte ranges a sampled check reads, the whole object when the ends overlap
+fn sample_ranges(size: u64) -> Vec<(u64, u64)> {
+    match size {
+        0 => Vec::new(),
+        size if size <= 2 * SAMPLE_BYTES => vec![(0, size - 1)],
+        size => vec![(0, SAMPLE_BYTES - 1), (size - SAMPLE_BYTES, size - 1)],
+    }
+}
+
+/// Checks the object at `key` is there and readable as far as `tier` goes. Objects
+/// above `full_limit` bytes get sampled rather than read in full.
+pub async fn verify_object(
+    remote: S3Remote,
+    key: &str,
+    listed_size: i64,
+    tier: VerifyTier,
+    full_limit: u64,
+) -> AppResult<()> {
+    labelled("verify_object", &remote, async {
+        let meta = head_object(remote.clone(), key).await?;
+        if let Some(problem) = size_problem(key, listed_size, meta.size) {
+            return Err(AppError::message(problem));
+        }
+
+        let size = meta.size.max(0) as u64;
+        match tier {
+            VerifyTier::Head => Ok(()),
+            VerifyTier::Full if size <= full_limit => read_range(&remote, key, None, size).await,
+            VerifyTier::Sampled | VerifyTier::Full => {
+                for (start, end) in sample_ranges(size) {
+                    read_range(&remote, key, Some((start, end)), end - start + 1).await?;
+                }
+                Ok(())
+            }
+        }
+    })
+    .await
+}
+
+/// Reads `range` of the object, or all of it with its checksum validated, and checks
+/// `expected` bytes came back
+async fn read_range(
+    remote: &S3Remote,
+    key: &str,
+    range: Option<(u64, u64)>,
+    expected: u64,
+) -> AppResult<()> {
+    remote.check_scope(key)?;
+
+    let output = remote
+        .client
+        .get_object()
+        .bucket(remote.bucket_name.as_ref())
+        .key(key)
+        .set_range(range.map(|(start, end)| format!("bytes={start}-{end}")))
+        .set_checksum_mode(range.is_none().then_some(ChecksumMode::Enabled))
+        .send()
+        .await
+        .map_err(|err| remote.request_err(err))?;
+
+    let mut body = output.body;
+    let mut read = 0;
+    while let Some(chunk) = body
+        .try_next()
+        .await
+        .map_err(|err| AppError::message(format!("\"{key}\" couldn't be read: {err}")))?
+    {
+        read += chunk.len() as u64;
+    }
+
+    if read != expected {
+        return Err(AppError::message(format!(
+            "\"{key}\" returned {read} of {expected} bytes"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_size_problems() {
+        assert_eq!(size_problem("a/report.csv", 10, 10), None);
+        assert_eq!(size_problem("a/", 0, 0), None);
+        assert_eq!(size_problem("a/__fd.dat", 0, 0), None);
+        assert_eq!(
+            size_problem("a/report.csv", 0, 0).as_deref(),
+            Some("\"a/report.csv\" is empty")
+        );
+        assert!(size_problem("a/report.csv", 10, 12).is_some());
+    }
+
+    #[test]
+    fn samples_both_ends() {
+        assert!(sample_ranges(0).is_empty());
+        assert_eq!(sample_ranges(10), [(0, 9)]);
+        assert_eq!(sample_ranges(2 * SAMPLE_BYTES), [(0, 2 * SAMPLE_BYTES - 1)]);
+
+        let size = 10 * SAMPLE_BYTES;
+        assert_eq!(
+            sample_ranges(size),
+            [(0, SAMPLE_BYTES - 1), (size - SAMPLE_BYTES, size - 1)]
+        );
+    }
+}
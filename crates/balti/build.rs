@@ -1,9 +1,30 @@
-use std::process::Command;
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::Value;
 
 fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
     let version = std::env::var("CARGO_PKG_VERSION")?;
     println!("cargo:rustc-env=BALTI_VERSION={version}");
 
+    // reproducible builds pin the date
+    let build_timestamp = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch.parse()?,
+        Err(_) => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    println!("cargo:rustc-env=BALTI_BUILD_TIMESTAMP={build_timestamp}");
+
+    let out_dir = std::env::var("OUT_DIR")?;
+    let licenses = third_party_licenses().unwrap_or_else(|err| {
+        println!("cargo:warning=Failed to list third-party licenses: {err}");
+        String::new()
+    });
+    std::fs::write(Path::new(&out_dir).join("licenses.tsv"), licenses)?;
+
     if let Ok(output) = Command::new("git").args(["rev-parse", "HEAD"]).output()
         && output.status.success()
     {
@@ -16,3 +37,85 @@ fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
         Err(std::env::VarError::NotPresent.into())
     }
 }
+
+/// Crates linked into the app with their license, a `name\tversion\tlicense` line each.
+/// Follows the normal dependencies of this package for the target being built.
+fn third_party_licenses() -> Result<String, Box<dyn std::error::Error + 'static>> {
+    let cargo = std::env::var("CARGO")?;
+    let manifest = Path::new(&std::env::var("CARGO_MANIFEST_DIR")?).join("Cargo.toml");
+    let target = std::env::var("TARGET")?;
+    let output = Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--filter-platform"])
+        .arg(&target)
+        .arg("--manifest-path")
+        .arg(&manifest)
+        .output()?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into_owned().into());
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout)?;
+
+    let packages = metadata["packages"]
+        .as_array()
+        .ok_or("metadata has no packages")?
+        .iter()
+        .filter_map(|package| Some((package["id"].as_str()?, package)))
+        .collect::<HashMap<_, _>>();
+    let nodes = metadata["resolve"]["nodes"]
+        .as_array()
+        .ok_or("metadata has no resolve graph")?
+        .iter()
+        .filter_map(|node| Some((node["id"].as_str()?, node)))
+        .collect::<HashMap<_, _>>();
+    let members = metadata["workspace_members"]
+        .as_array()
+        .ok_or("metadata has no workspace members")?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<BTreeSet<_>>();
+    let root = metadata["resolve"]["root"]
+        .as_str()
+        .ok_or("metadata has no root package")?;
+
+    let mut linked = BTreeSet::new();
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        let Some(node) = nodes.get(id) else {
+            continue;
+        };
+        let deps = node["deps"].as_array().into_iter().flatten();
+        for dep in deps {
+            // build and dev dependencies don't end up in the binary
+            let normal = dep["dep_kinds"]
+                .as_array()
+                .is_some_and(|kinds| kinds.iter().any(|kind| kind["kind"].is_null()));
+            if let Some(dep_id) = dep["pkg"].as_str()
+                && normal
+                && linked.insert(dep_id)
+            {
+                pending.push(dep_id);
+            }
+        }
+    }
+
+    let mut lines = linked
+        .into_iter()
+        .filter(|id| !members.contains(id))
+        .filter_map(|id| {
+            let package = packages.get(id)?;
+            let license = package["license"]
+                .as_str()
+                .or_else(|| package["license_file"].as_str().map(|_| "see license file"))
+                .unwrap_or("unknown");
+            Some(format!(
+                "{}\t{}\t{}",
+                package["name"].as_str()?,
+                package["version"].as_str()?,
+                license
+            ))
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.dedup();
+    Ok(lines.join("\n"))
+}
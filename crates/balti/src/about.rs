@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+
+use crate::{config, util};
+
+/// Crates linked into the app, a `name\tversion\tlicense` line each from the build script
+const LICENSES: &str = include_str!(concat!(env!("OUT_DIR"), "/licenses.tsv"));
+
+/// Third-party crate built into the app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// SPDX expression like `MIT OR Apache-2.0`
+    pub license: &'static str,
+}
+
+pub fn dependencies() -> Vec<Dependency> {
+    parse_dependencies(LICENSES)
+}
+
+fn parse_dependencies(list: &'static str) -> Vec<Dependency> {
+    list.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(Dependency {
+                name: fields.next().filter(|name| !name.is_empty())?,
+                version: fields.next()?,
+                license: fields.next()?,
+            })
+        })
+        .collect()
+}
+
+/// When this binary was built, `None` if the build script's timestamp doesn't parse
+pub fn build_date() -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(config::BALTI_BUILD_TIMESTAMP.parse().ok()?, 0)
+}
+
+/// What a bug report needs to know about this install
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    pub version: &'static str,
+    pub commit: &'static str,
+    pub build_date: Option<DateTime<Utc>>,
+    pub os: String,
+    pub config_dir: PathBuf,
+    pub log_file: Option<PathBuf>,
+}
+
+impl Diagnostics {
+    /// Note: This reads the OS version, only call from background threads
+    pub fn collect() -> Self {
+        Self {
+            version: config::BALTI_VERSION,
+            commit: config::BALTI_COMMIT_SHA,
+            build_date: build_date(),
+            os: format!("{} {}", util::os_name(), util::os_version()),
+            config_dir: config::config_dir().clone(),
+            log_file: config::log_file_path().cloned(),
+        }
+    }
+
+    /// Plain text block for a bug report. Left untranslated, it's read by the maintainers.
+    pub fn report(&self) -> String {
+        let build_date = self
+            .build_date
+            .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| "unknown".to_owned());
+        let log_file = self
+            .log_file
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "none".to_owned());
+
+        format!(
+            "Balti {}\nCommit: {}\nBuilt: {}\nOS: {}\nConfig: {}\nLog: {}",
+            self.version,
+            self.commit,
+            build_date,
+            self.os,
+            self.config_dir.display(),
+            log_file
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_license_list() {
+        let list = "anyhow\t1.0.98\tMIT OR Apache-2.0\nbroken line\n\t1\tMIT\nzip\t8.6.0\tMIT\n";
+        assert_eq!(
+            parse_dependencies(list),
+            [
+                Dependency {
+                    name: "anyhow",
+                    version: "1.0.98",
+                    license: "MIT OR Apache-2.0"
+                },
+                Dependency {
+                    name: "zip",
+                    version: "8.6.0",
+                    license: "MIT"
+                }
+            ]
+        );
+        assert!(parse_dependencies("").is_empty());
+    }
+
+    #[test]
+    fn formats_the_report() {
+        let mut diagnostics = Diagnostics {
+            version: "0.1.0",
+            commit: "abc123",
+            build_date: DateTime::from_timestamp(1_760_000_000, 0),
+            os: "Linux wayland ubuntu 24.04".into(),
+            config_dir: PathBuf::from("/home/me/.config/balti"),
+            log_file: Some(PathBuf::from("/home/me/.config/balti/logs/balti.log")),
+        };
+        assert_eq!(
+            diagnostics.report(),
+            "Balti 0.1.0\nCommit: abc123\nBuilt: 2025-10-09 08:53 UTC\n\
+             OS: Linux wayland ubuntu 24.04\nConfig: /home/me/.config/balti\n\
+             Log: /home/me/.config/balti/logs/balti.log"
+        );
+
+        diagnostics.build_date = None;
+        diagnostics.log_file = None;
+        assert!(diagnostics.report().contains("Built: unknown\n"));
+        assert!(diagnostics.report().ends_with("Log: none"));
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use balti_err::{AppError, AppResult};
+use balti_s3::{__S3Object, MAX_PAGE_SIZE, S3Remote};
+use futures::channel::mpsc::UnboundedSender;
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+/// Archives above this size ask for confirmation before downloading
+pub const LARGE_ARCHIVE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Don't flood the UI with an update per chunk
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// Archive snapshots are at least this far apart, each one rewrites its directory
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Object that ends up as a file inside the archive
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub key: Arc<str>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArchiveProgress {
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// What [`write_zip`] reports while it runs
+#[derive(Debug)]
+pub enum ZipUpdate {
+    Progress(ArchiveProgress),
+    /// The first `completed` entries are in the archive as of `snapshot`
+    Snapshot {
+        completed: usize,
+        snapshot: ZipSnapshot,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipOutcome {
+    Done,
+    /// Stopped at the object budget, the last snapshot has everything written
+    Paused,
+}
+
+/// Lists a page of the prefix recursively, skipping folder markers and folder placeholder
+/// keys. Returns the token of the next page, `None` after the last one.
+pub async fn list_page(
+    remote: S3Remote,
+    prefix: &str,
+    token: Option<&str>,
+) -> AppResult<(Vec<ArchiveEntry>, Option<Arc<str>>)> {
+    let page = balti_s3::list_objects_recursive(remote, prefix, MAX_PAGE_SIZE, token).await?;
+
+    let entries = page
+        .objects
+        .iter()
+        .filter_map(|object| match object.as_ref() {
+            __S3Object::File {
+                key,
+                size,
+                marker: false,
+                ..
+            } if !key.ends_with('/') => Some(ArchiveEntry {
+                key: key.clone(),
+                size: (*size).max(0) as u64,
+            }),
+            _ => None,
+        })
+        .collect();
+    Ok((entries, page.next_token))
+}
+
+pub fn total_size(entries: &[ArchiveEntry]) -> u64 {
+    entries.iter().map(|e| e.size).sum()
+}
+
+/// Name of the object's file in the archive, relative to `prefix`
+pub fn entry_name<'a>(prefix: &str, key: &'a str) -> &'a str {
+    key.strip_prefix(balti_s3::listed_prefix(prefix))
+        .unwrap_or(key)
+        .trim_start_matches('/')
+}
+
+/// How a zip looked at a snapshot: everything before `entries_len` is kept as the archive
+/// grows, only the central directory after it gets overwritten. Putting `directory` back
+/// there restores the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipSnapshot {
+    pub entries_len: u64,
+    pub directory: Vec<u8>,
+}
+
+/// Zip being written, it can be closed into a valid archive after any entry and picked
+/// up from there later
+pub struct ResumableZip {
+    writer: ZipWriter<File>,
+    dest: PathBuf,
+}
+
+impl ResumableZip {
+    pub fn create(dest: &Path) -> AppResult<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dest)
+            .map_err(|err| AppError::err(err))?;
+        Ok(Self {
+            writer: ZipWriter::new(file),
+            dest: dest.to_owned(),
+        })
+    }
+
+    /// Puts the archive back the way `snapshot` left it. `None` unless it then holds
+    /// exactly `expected`, checked by name and size without reading the bodies.
+    pub fn restore(
+        dest: &Path,
+        snapshot: &ZipSnapshot,
+        prefix: &str,
+        expected: &[ArchiveEntry],
+    ) -> AppResult<Option<Self>> {
+        let mut file = match OpenOptions::new().read(true).write(true).open(dest) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(AppError::err(err)),
+        };
+        let len = file.metadata().map_err(|err| AppError::err(err))?.len();
+        if len < snapshot.entries_len {
+            return Ok(None);
+        }
+
+        file.set_len(snapshot.entries_len)
+            .and_then(|_| file.seek(SeekFrom::End(0)))
+            .and_then(|_| file.write_all(&snapshot.directory))
+            .map_err(|err| AppError::err(err))?;
+
+        let Ok(mut archive) = ZipArchive::new(file) else {
+            return Ok(None);
+        };
+        if archive.len() != expected.len()
+            || archive.central_directory_start() != snapshot.entries_len
+        {
+            return Ok(None);
+        }
+        for (i, entry) in expected.iter().enumerate() {
+            // reads the local header too, so the entry's data is where the directory says
+            let matches = archive.by_index_raw(i).is_ok_and(|file| {
+                file.name() == entry_name(prefix, &entry.key) && file.size() == entry.size
+            });
+            if !matches {
+                return Ok(None);
+            }
+        }
+
+        let writer =
+            ZipWriter::new_append(archive.into_inner()).map_err(|err| AppError::err(err))?;
+        Ok(Some(Self {
+            writer,
+            dest: dest.to_owned(),
+        }))
+    }
+
+    /// Closes the archive as it is and reopens it for the next entries
+    pub fn snapshot(self) -> AppResult<(Self, ZipSnapshot)> {
+        let archive = self
+            .writer
+            .finish_into_readable()
+            .map_err(|err| AppError::err(err))?;
+        let entries_len = archive.central_directory_start();
+
+        let mut file = archive.into_inner();
+        let mut directory = Vec::new();
+        file.seek(SeekFrom::Start(entries_len))
+            .and_then(|_| file.read_to_end(&mut directory))
+            .and_then(|_| file.sync_data())
+            .map_err(|err| AppError::err(err))?;
+
+        let writer = ZipWriter::new_append(file).map_err(|err| AppError::err(err))?;
+        Ok((
+            Self {
+                writer,
+                dest: self.dest,
+            },
+            ZipSnapshot {
+                entries_len,
+                directory,
+            },
+        ))
+    }
+
+    pub fn finish(self) -> AppResult<()> {
+        self.writer.finish().map_err(|err| AppError::err(err))?;
+        Ok(())
+    }
+}
+
+/// Streams the bodies of the entries after the first `completed` straight into `zip`,
+/// named relative to `prefix`. Stops early with a snapshot once `budget` objects are
+/// written, unless it's `0`, and snapshots every so often for resuming after a failure.
+pub async fn write_zip(
+    remote: S3Remote,
+    prefix: Arc<str>,
+    entries: Vec<ArchiveEntry>,
+    mut zip: ResumableZip,
+    completed: usize,
+    budget: usize,
+    updates: UnboundedSender<ZipUpdate>,
+) -> AppResult<ZipOutcome> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    let mut state = ArchiveProgress {
+        files_done: completed,
+        files_total: entries.len(),
+        bytes_done: total_size(&entries[..completed]),
+        bytes_total: total_size(&entries),
+    };
+    let _ = updates.unbounded_send(ZipUpdate::Progress(state));
+    let mut last_snapshot = Instant::now();
+
+    for (i, entry) in entries.iter().enumerate().skip(completed) {
+        if budget > 0 && i - completed == budget {
+            let (_zip, snapshot) = zip.snapshot()?;
+            let _ = updates.unbounded_send(ZipUpdate::Snapshot {
+                completed: i,
+                snapshot,
+            });
+            return Ok(ZipOutcome::Paused);
+        }
+        if last_snapshot.elapsed() >= SNAPSHOT_INTERVAL {
+            let (reopened, snapshot) = zip.snapshot()?;
+            zip = reopened;
+            last_snapshot = Instant::now();
+            let _ = updates.unbounded_send(ZipUpdate::Snapshot {
+                completed: i,
+                snapshot,
+            });
+        }
+
+        zip.writer
+            .start_file(entry_name(&prefix, &entry.key), options)
+            .map_err(|err| AppError::err(err))?;
+
+        let mut body = balti_s3::download_file(remote.clone(), &entry.key, &zip.dest).await?;
+        let mut unreported = 0;
+        while let Some(chunk) = body.try_next().await.map_err(|err| AppError::err(err))? {
+            zip.writer
+                .write_all(&chunk)
+                .map_err(|err| AppError::err(err))?;
+
+            state.bytes_done += chunk.len() as u64;
+            unreported += chunk.len() as u64;
+            if unreported >= PROGRESS_STEP_BYTES {
+                unreported = 0;
+                let _ = updates.unbounded_send(ZipUpdate::Progress(state));
+            }
+        }
+
+        state.files_done += 1;
+        let _ = updates.unbounded_send(ZipUpdate::Progress(state));
+    }
+
+    zip.finish()?;
+    Ok(ZipOutcome::Done)
+}
+
+/// Removes the file on drop unless it was kept
+pub struct PartialFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl PartialFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, keep: false }
+    }
+
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        if let Err(err) = fs::remove_file(&self.path) {
+            tracing::warn!("Failed to remove partial file {:?}: {}", self.path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_archive(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("balti-archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    fn add(zip: &mut ResumableZip, name: &str, body: &[u8]) {
+        zip.writer
+            .start_file(name, SimpleFileOptions::default())
+            .unwrap();
+        zip.writer.write_all(body).unwrap();
+    }
+
+    fn entry(key: &str, size: u64) -> ArchiveEntry {
+        ArchiveEntry {
+            key: Arc::from(key),
+            size,
+        }
+    }
+
+    fn contents(dest: &Path) -> Vec<(String, String)> {
+        let mut archive = ZipArchive::new(File::open(dest).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut file = archive.by_index(i).unwrap();
+                let mut body = String::new();
+                file.read_to_string(&mut body).unwrap();
+                (file.name().to_owned(), body)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn names_entries_relative_to_the_prefix() {
+        assert_eq!(entry_name("/logs/", "logs/2024/app.log"), "2024/app.log");
+        assert_eq!(entry_name("", "logs/app.log"), "logs/app.log");
+        assert_eq!(entry_name("other/", "logs/app.log"), "logs/app.log");
+    }
+
+    #[test]
+    fn resumes_from_a_snapshot() {
+        let dest = temp_archive("resume.zip");
+        let mut zip = ResumableZip::create(&dest).unwrap();
+        add(&mut zip, "a.txt", b"first");
+        let (mut zip, snapshot) = zip.snapshot().unwrap();
+        assert_eq!(contents(&dest), [("a.txt".into(), "first".into())]);
+
+        // cut short midway through the next entry
+        add(&mut zip, "b.txt", b"never finished");
+        drop(zip);
+
+        let expected = [entry("logs/a.txt", 5)];
+        let mut zip = ResumableZip::restore(&dest, &snapshot, "logs/", &expected)
+            .unwrap()
+            .unwrap();
+        add(&mut zip, "b.txt", b"second");
+        zip.finish().unwrap();
+
+        assert_eq!(
+            contents(&dest),
+            [
+                ("a.txt".into(), "first".into()),
+                ("b.txt".into(), "second".into())
+            ]
+        );
+        fs::remove_file(&dest).unwrap();
+    }
+
+    #[test]
+    fn restores_only_the_expected_entries() {
+        let dest = temp_archive("mismatch.zip");
+        let mut zip = ResumableZip::create(&dest).unwrap();
+        add(&mut zip, "a.txt", b"first");
+        let (zip, snapshot) = zip.snapshot().unwrap();
+        zip.finish().unwrap();
+
+        let restore = |expected: &[ArchiveEntry]| {
+            ResumableZip::restore(&dest, &snapshot, "", expected)
+                .unwrap()
+                .is_some()
+        };
+        assert!(!restore(&[entry("a.txt", 4)]));
+        assert!(!restore(&[entry("b.txt", 5)]));
+        assert!(!restore(&[entry("a.txt", 5), entry("b.txt", 5)]));
+        assert!(restore(&[entry("a.txt", 5)]));
+
+        // truncated below the snapshot, the entries are gone
+        File::options()
+            .write(true)
+            .open(&dest)
+            .unwrap()
+            .set_len(snapshot.entries_len - 1)
+            .unwrap();
+        assert!(!restore(&[entry("a.txt", 5)]));
+
+        fs::remove_file(&dest).unwrap();
+        assert!(!restore(&[entry("a.txt", 5)]));
+    }
+}
@@ -0,0 +1,254 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use balti_err::AppResult;
+use balti_s3::{__S3Object, MAX_PAGE_SIZE, S3Object, S3Remote};
+use gpui::{App, Global};
+
+use crate::{clipboard::ClipboardMode, rate, strings::t};
+
+/// Default of `Settings::confirm_objects_above`
+pub const DEFAULT_CONFIRM_OBJECTS: usize = 10_000;
+
+/// Counting stops at this multiple of the threshold, the count is then a lower bound
+const COUNT_LIMIT_FACTOR: usize = 10;
+
+/// Batches this small or quick say little about throughput
+const MIN_SAMPLE_OBJECTS: usize = 20;
+const MIN_SAMPLE_TIME: Duration = Duration::from_secs(1);
+
+/// Weight of the latest batch in the running throughput
+const SAMPLE_WEIGHT: f64 = 0.5;
+
+pub fn init(cx: &mut App) {
+    cx.set_global(Throughput::default());
+}
+
+/// Operations touching every object below what they're given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BulkOperation {
+    Delete,
+    Copy,
+    Move,
+    Download,
+}
+
+impl From<ClipboardMode> for BulkOperation {
+    fn from(mode: ClipboardMode) -> Self {
+        match mode {
+            ClipboardMode::Copy => BulkOperation::Copy,
+            ClipboardMode::Cut => BulkOperation::Move,
+        }
+    }
+}
+
+/// Objects an operation is about to touch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectCount {
+    pub objects: usize,
+    /// `false` when counting stopped early, there are at least `objects`
+    pub complete: bool,
+}
+
+impl ObjectCount {
+    pub fn exact(objects: usize) -> Self {
+        Self {
+            objects,
+            complete: true,
+        }
+    }
+
+    /// Like "12,500 objects" or "more than 100,000 objects"
+    pub fn label(&self) -> String {
+        let objects = t!(
+            "bulk.objects",
+            count = self.objects,
+            formatted = format_count(self.objects)
+        );
+        if self.complete {
+            objects
+        } else {
+            t!("bulk.more_than", objects = objects)
+        }
+    }
+}
+
+/// Whether `count` is past `threshold`, `0` never asks
+pub fn needs_confirmation(count: ObjectCount, threshold: usize) -> bool {
+    threshold > 0 && count.objects > threshold
+}
+
+/// Where counting for `threshold` can stop, the answer is clear by then
+pub fn count_limit(threshold: usize) -> usize {
+    threshold.saturating_mul(COUNT_LIMIT_FACTOR)
+}
+
+/// Counts the objects below `objects`, folders by listing them, up to `limit`
+pub async fn count_objects(
+    remote: S3Remote,
+    objects: Vec<S3Object>,
+    limit: usize,
+) -> AppResult<ObjectCount> {
+    let mut count = 0;
+    for object in objects.iter() {
+        match object.as_ref() {
+            __S3Object::File { .. } => count += 1,
+            __S3Object::Folder(prefix) => {
+                let mut token: Option<Arc<str>> = None;
+                loop {
+                    let page = balti_s3::list_objects_recursive(
+                        remote.clone(),
+                        prefix,
+                        MAX_PAGE_SIZE,
+                        token.as_deref(),
+                    )
+                    .await?;
+                    count += page.objects.len();
+
+                    match page.next_token {
+                        Some(next) if count < limit => token = Some(next),
+                        Some(_) => {
+                            return Ok(ObjectCount {
+                                objects: count,
+                                complete: false,
+                            });
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+    Ok(ObjectCount::exact(count))
+}
+
+/// Objects per second recent batches of each operation went at, for telling how long
+/// a big one would take. Kept for the session only.
+#[derive(Debug, Default)]
+pub struct Throughput(HashMap<BulkOperation, f64>);
+
+impl Global for Throughput {}
+
+impl Throughput {
+    /// Folds in a finished batch, too small ones are left out
+    pub fn record(&mut self, operation: BulkOperation, objects: usize, elapsed: Duration) {
+        if objects < MIN_SAMPLE_OBJECTS || elapsed < MIN_SAMPLE_TIME {
+            return;
+        }
+        let sample = objects as f64 / elapsed.as_secs_f64();
+        let rate = self.0.entry(operation).or_insert(sample);
+        *rate = *rate * (1. - SAMPLE_WEIGHT) + sample * SAMPLE_WEIGHT;
+    }
+
+    /// Time `objects` would take, `None` before any batch of the kind finished
+    pub fn estimate(&self, operation: BulkOperation, objects: usize) -> Option<Duration> {
+        let rate = self.0.get(&operation).filter(|rate| **rate > 0.)?;
+        Some(Duration::from_secs_f64(objects as f64 / rate))
+    }
+
+    pub fn note(operation: BulkOperation, objects: usize, elapsed: Duration, cx: &mut App) {
+        cx.global_mut::<Self>().record(operation, objects, elapsed);
+    }
+}
+
+/// Prompt detail for confirming an operation past `threshold`
+pub fn confirmation_detail(
+    operation: BulkOperation,
+    threshold: usize,
+    estimate: Option<Duration>,
+) -> String {
+    let estimate = match estimate.map(rate::format_duration) {
+        Some(duration) => match operation {
+            BulkOperation::Delete => t!("bulk.estimate_delete", duration = duration),
+            BulkOperation::Copy => t!("bulk.estimate_copy", duration = duration),
+            BulkOperation::Move => t!("bulk.estimate_move", duration = duration),
+            BulkOperation::Download => t!("bulk.estimate_download", duration = duration),
+        },
+        None => t!("bulk.no_estimate"),
+    };
+    t!(
+        "bulk.over_limit",
+        threshold = format_count(threshold),
+        estimate = estimate
+    )
+}
+
+/// Like `12,500`
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (at, digit) in digits.chars().enumerate() {
+        if at > 0 && (digits.len() - at).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asks_past_the_threshold_only() {
+        assert!(!needs_confirmation(ObjectCount::exact(10_000), 10_000));
+        assert!(needs_confirmation(ObjectCount::exact(10_001), 10_000));
+        assert!(!needs_confirmation(ObjectCount::exact(2_000_000), 0));
+        assert_eq!(count_limit(10_000), 100_000);
+    }
+
+    #[test]
+    fn labels_counts() {
+        assert_eq!(ObjectCount::exact(1).label(), "1 object");
+        assert_eq!(ObjectCount::exact(999).label(), "999 objects");
+        assert_eq!(ObjectCount::exact(12_500).label(), "12,500 objects");
+        assert_eq!(
+            ObjectCount {
+                objects: 2_000_000,
+                complete: false
+            }
+            .label(),
+            "more than 2,000,000 objects"
+        );
+    }
+
+    #[test]
+    fn estimates_from_recent_batches() {
+        let mut throughput = Throughput::default();
+        assert_eq!(throughput.estimate(BulkOperation::Delete, 1000), None);
+
+        throughput.record(BulkOperation::Delete, 1000, Duration::from_secs(10));
+        assert_eq!(
+            throughput.estimate(BulkOperation::Delete, 1000),
+            Some(Duration::from_secs(10))
+        );
+
+        // too small to count
+        throughput.record(BulkOperation::Delete, 5, Duration::from_secs(10));
+        throughput.record(BulkOperation::Delete, 5000, Duration::from_millis(100));
+        assert_eq!(
+            throughput.estimate(BulkOperation::Delete, 1000),
+            Some(Duration::from_secs(10))
+        );
+
+        throughput.record(BulkOperation::Delete, 3000, Duration::from_secs(10));
+        assert_eq!(
+            throughput.estimate(BulkOperation::Delete, 2000),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(throughput.estimate(BulkOperation::Copy, 1000), None);
+    }
+
+    #[test]
+    fn explains_the_confirmation() {
+        assert_eq!(
+            confirmation_detail(
+                BulkOperation::Delete,
+                10_000,
+                Some(Duration::from_secs(130)),
+            ),
+            "That's over the 10,000 object limit for a single operation. At the speed of \
+             recent deletes it takes about 2m 10s."
+        );
+    }
+}
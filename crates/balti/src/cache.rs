@@ -0,0 +1,270 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    mem,
+    sync::Arc,
+};
+
+use balti_s3::{__S3Object, S3Object};
+
+/// Entries of a cache by when they were last used, the least recently used go first
+/// once it holds more than its caps allow. Sizes are estimates the caller passes in.
+pub struct ByteLru<K, V> {
+    /// For the eviction logs
+    name: &'static str,
+    entries: HashMap<K, Entry<V>>,
+    /// Keys by when they were last used
+    order: BTreeMap<u64, K>,
+    /// Counts up with every use
+    clock: u64,
+    used_bytes: usize,
+    max_bytes: usize,
+    max_entries: usize,
+}
+
+struct Entry<V> {
+    used_at: u64,
+    bytes: usize,
+    value: V,
+}
+
+/// How full a cache is, for showing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_bytes: usize,
+}
+
+impl<K: Clone + Eq + Hash, V> ByteLru<K, V> {
+    pub fn new(name: &'static str, max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            name,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+            used_bytes: 0,
+            max_bytes,
+            max_entries,
+        }
+    }
+
+    pub fn usage(&self) -> CacheUsage {
+        CacheUsage {
+            entries: self.entries.len(),
+            bytes: self.used_bytes,
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    /// Value of `key` without counting it as used
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Value of `key`, which becomes the most recently used
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let clock = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.used_at);
+        self.order.insert(clock, key.clone());
+        entry.used_at = clock;
+        Some(&entry.value)
+    }
+
+    /// Keeps `value` of about `bytes` as the most recently used, evicting the least
+    /// recently used past the caps. A value larger than the whole cache isn't kept.
+    pub fn insert(&mut self, key: K, value: V, bytes: usize) {
+        self.remove(&key);
+        if bytes > self.max_bytes {
+            tracing::debug!(
+                "Not caching a {bytes} byte entry in the {} cache, it's capped at {} bytes",
+                self.name,
+                self.max_bytes
+            );
+            return;
+        }
+
+        let clock = self.tick();
+        self.order.insert(clock, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                used_at: clock,
+                bytes,
+                value,
+            },
+        );
+        self.used_bytes += bytes;
+        self.evict();
+    }
+
+    /// Entries from the most recently used on, without counting them as used
+    pub fn newest_first(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.order
+            .values()
+            .rev()
+            .filter_map(|key| self.entries.get_key_value(key))
+            .map(|(key, entry)| (key, &entry.value))
+    }
+
+    /// Changes the value of `key` in place, keeping when it was last used. `change`
+    /// returns the bytes the value holds after.
+    pub fn update(&mut self, key: &K, change: impl FnOnce(&mut V) -> usize) {
+        let Some(entry) = self.entries.get_mut(key) else {
+            return;
+        };
+        let bytes = change(&mut entry.value);
+        self.used_bytes = self.used_bytes - entry.bytes + bytes;
+        entry.bytes = bytes;
+        self.evict();
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.used_at);
+        self.used_bytes -= entry.bytes;
+        Some(entry.value)
+    }
+
+    /// Changes the byte cap, evicting right away when it shrank
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.evict();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.used_bytes = 0;
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn evict(&mut self) {
+        let (mut count, mut freed) = (0, 0);
+        while self.used_bytes > self.max_bytes || self.entries.len() > self.max_entries {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.used_bytes -= entry.bytes;
+                freed += entry.bytes;
+                count += 1;
+            }
+        }
+
+        if count > 0 {
+            tracing::debug!(
+                "Evicted {count} entries of {freed} bytes from the {} cache, {} of {} bytes used",
+                self.name,
+                self.used_bytes,
+                self.max_bytes
+            );
+        }
+    }
+}
+
+/// Rough heap and inline bytes of a listed object. Listings share objects, so this
+/// overcounts ones still shown elsewhere.
+pub fn object_bytes(object: &S3Object) -> usize {
+    let text = |text: &Option<Arc<str>>| text.as_ref().map_or(0, |text| text.len());
+
+    let owned = match object.as_ref() {
+        __S3Object::Folder(key) => key.len(),
+        __S3Object::File {
+            key,
+            last_modified,
+            etag,
+            ..
+        } => key.len() + text(last_modified) + text(etag),
+    };
+    mem::size_of::<S3Object>() + mem::size_of::<__S3Object>() + owned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_past_the_byte_cap() {
+        let mut cache = ByteLru::new("test", 10, 100);
+        cache.insert("a", (), 40);
+        cache.insert("b", (), 60);
+        // right at the cap, nothing goes
+        assert_eq!(cache.usage().bytes, 100);
+        assert_eq!(cache.usage().entries, 2);
+
+        // one byte over evicts the oldest
+        cache.insert("c", (), 1);
+        assert!(cache.peek(&"a").is_none());
+        assert_eq!(cache.usage().bytes, 61);
+
+        // as many as it takes to fit
+        cache.insert("d", (), 100);
+        assert_eq!(cache.usage().entries, 1);
+        assert!(cache.peek(&"d").is_some());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut cache = ByteLru::new("test", 3, 100);
+        cache.insert("a", 1, 10);
+        cache.insert("b", 2, 10);
+        cache.insert("c", 3, 10);
+
+        assert_eq!(cache.get(&"a"), Some(&1));
+        // peeking doesn't count as a use
+        assert_eq!(cache.peek(&"b"), Some(&2));
+        cache.insert("d", 4, 10);
+
+        assert!(cache.peek(&"a").is_some());
+        assert!(cache.peek(&"b").is_none());
+        assert_eq!(cache.usage().entries, 3);
+    }
+
+    #[test]
+    fn skips_entries_larger_than_the_cap() {
+        let mut cache = ByteLru::new("test", 10, 100);
+        cache.insert("a", 1, 50);
+        cache.insert("b", 2, 101);
+        assert!(cache.peek(&"b").is_none());
+        assert!(cache.peek(&"a").is_some());
+
+        // replacing a key with an oversized value drops the old one too
+        cache.insert("a", 3, 101);
+        assert_eq!(cache.usage().entries, 0);
+        assert_eq!(cache.usage().bytes, 0);
+
+        cache.insert("c", 4, 100);
+        assert_eq!(cache.peek(&"c"), Some(&4));
+    }
+
+    #[test]
+    fn shrinking_the_cap_evicts() {
+        let mut cache = ByteLru::new("test", 10, 100);
+        cache.insert("a", (), 30);
+        cache.insert("b", (), 30);
+        cache.insert("a", (), 40);
+        assert_eq!(cache.usage().bytes, 70);
+
+        cache.set_max_bytes(40);
+        assert!(cache.peek(&"b").is_none());
+        assert_eq!(
+            cache.usage(),
+            CacheUsage {
+                entries: 1,
+                bytes: 40,
+                max_bytes: 40
+            }
+        );
+
+        cache.set_max_bytes(0);
+        assert_eq!(cache.usage().entries, 0);
+        cache.clear();
+        assert_eq!(cache.usage().bytes, 0);
+    }
+}
@@ -0,0 +1,136 @@
+use std::{collections::HashMap, sync::Arc};
+
+use balti_s3::{Access, Capabilities, S3Remote};
+use gpui::{App, AppContext, Entity, Global, SharedString};
+
+use crate::{rt, strings::t};
+
+pub fn init(cx: &mut App) {
+    let capabilities = cx.new(|_cx| RemoteCapabilities::default());
+    cx.set_global(GlobalRemoteCapabilities(capabilities));
+}
+
+struct GlobalRemoteCapabilities(Entity<RemoteCapabilities>);
+
+impl Global for GlobalRemoteCapabilities {}
+
+/// What each remote's credentials allow, probed once per session as the remote is
+/// first browsed. Observe [`RemoteCapabilities::entity`] to follow probes finishing.
+#[derive(Default)]
+pub struct RemoteCapabilities {
+    /// `None` while the probe runs
+    remotes: HashMap<Arc<str>, Option<Capabilities>>,
+}
+
+impl RemoteCapabilities {
+    pub fn entity(cx: &App) -> Entity<Self> {
+        cx.global::<GlobalRemoteCapabilities>().0.clone()
+    }
+
+    /// Capabilities of the remote, unknown until its probe finishes
+    pub fn get(remote: &str, cx: &App) -> Capabilities {
+        Self::entity(cx)
+            .read(cx)
+            .remotes
+            .get(remote)
+            .copied()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    pub fn is_probing(remote: &str, cx: &App) -> bool {
+        Self::entity(cx)
+            .read(cx)
+            .remotes
+            .get(remote)
+            .is_some_and(Option::is_none)
+    }
+
+    /// Probes the remote unless it already was this session
+    pub fn ensure(remote: &S3Remote, cx: &mut App) {
+        if !Self::entity(cx)
+            .read(cx)
+            .remotes
+            .contains_key(&remote.remote_name)
+        {
+            Self::probe(remote.clone(), cx);
+        }
+    }
+
+    /// Probes again, like after the remote's permissions changed
+    pub fn reprobe(remote: &S3Remote, cx: &mut App) {
+        if !Self::is_probing(&remote.remote_name, cx) {
+            Self::probe(remote.clone(), cx);
+        }
+    }
+
+    /// Notes what a real upload or new folder found out, the probe leaves write access
+    /// unknown
+    pub fn learn_write(remote: &str, access: Access, cx: &mut App) {
+        Self::learn(remote, access, |capabilities| &mut capabilities.write, cx);
+    }
+
+    /// Notes what a real delete found out, the probe leaves delete access unknown
+    pub fn learn_delete(remote: &str, access: Access, cx: &mut App) {
+        Self::learn(remote, access, |capabilities| &mut capabilities.delete, cx);
+    }
+
+    fn learn(
+        remote: &str,
+        access: Access,
+        learned: fn(&mut Capabilities) -> &mut Access,
+        cx: &mut App,
+    ) {
+        if access == Access::Unknown {
+            return;
+        }
+        Self::entity(cx).update(cx, |this, cx| {
+            if let Some(Some(capabilities)) = this.remotes.get_mut(remote)
+                && *learned(capabilities) != access
+            {
+                *learned(capabilities) = access;
+                cx.notify();
+            }
+        });
+    }
+
+    /// Once the remote got new credentials, the next browse probes it again
+    pub fn forget(remote: &str, cx: &mut App) {
+        Self::entity(cx).update(cx, |this, cx| {
+            if this.remotes.remove(remote).is_some() {
+                cx.notify();
+            }
+        });
+    }
+
+    fn probe(remote: S3Remote, cx: &mut App) {
+        let entity = Self::entity(cx);
+        let remote_name = remote.remote_name.clone();
+        entity.update(cx, |this, cx| {
+            this.remotes.insert(remote_name.clone(), None);
+            cx.notify();
+        });
+
+        // not tied to the browse view that asked for it, the next one uses the result
+        let task = rt::spawn_detached(cx, balti_s3::probe_capabilities(remote));
+        cx.spawn(async move |cx| {
+            // a remote that can't be reached shows why in the listing, its access stays unknown
+            let capabilities = task.await.ok().and_then(Result::ok).unwrap_or_default();
+            let _ = entity.update(cx, |this, cx| {
+                // forgotten meanwhile, the result may be for the old credentials
+                if let Some(probed) = this.remotes.get_mut(&remote_name) {
+                    *probed = Some(capabilities);
+                    cx.notify();
+                }
+            });
+        })
+        .detach();
+    }
+}
+
+/// Why an action needing `access` is unavailable, `None` unless a probe or a real
+/// request found it denied
+pub fn missing(access: Access, permission: &str) -> Option<SharedString> {
+    (access == Access::Denied)
+        .then(|| SharedString::new(t!("capabilities.missing", permission = permission)))
+}
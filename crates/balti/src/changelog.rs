@@ -0,0 +1,76 @@
+/// Release notes bundled with the app, a `## <version>` heading per release
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+
+/// Bundled notes of `version`, `None` when none were written for it
+pub fn notes(version: &str) -> Option<&'static str> {
+    notes_for(CHANGELOG, version)
+}
+
+/// Section of `changelog` below the `## <version>` heading, up to the next heading.
+/// `None` when there's no such section or it's blank.
+pub fn notes_for<'a>(changelog: &'a str, version: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    let mut start = None;
+    for line in changelog.split_inclusive('\n') {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if start.is_some() {
+                break;
+            }
+            if heading.trim() == version {
+                start = Some(offset + line.len());
+            }
+        }
+        offset += line.len();
+    }
+
+    let notes = changelog[start?..offset].trim();
+    (!notes.is_empty()).then_some(notes)
+}
+
+/// Whether `current` is newer than the version last launched. A first launch, with
+/// nothing seen yet, isn't an update; versions that don't parse count when they differ.
+pub fn is_update(last_seen: Option<&str>, current: &str) -> bool {
+    let Some(last_seen) = last_seen else {
+        return false;
+    };
+    match (parse_version(last_seen), parse_version(current)) {
+        (Some(last_seen), Some(current)) => current > last_seen,
+        _ => last_seen.trim() != current.trim(),
+    }
+}
+
+/// `1.2.3` as its numbers, pre-release and build suffixes dropped
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let core = version.trim().split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog\n\n## 0.3.0\n\n- Faster listings\n- Fixed uploads\n\n## 0.2.0\n\n## 0.1.0\n- First release\n";
+
+    #[test]
+    fn finds_notes_of_a_version() {
+        assert_eq!(
+            notes_for(CHANGELOG, "0.3.0"),
+            Some("- Faster listings\n- Fixed uploads")
+        );
+        assert_eq!(notes_for(CHANGELOG, "0.1.0"), Some("- First release"));
+        assert_eq!(notes_for(CHANGELOG, "0.2.0"), None);
+        assert_eq!(notes_for(CHANGELOG, "0.4.0"), None);
+        assert_eq!(notes_for(CHANGELOG, "0.3"), None);
+    }
+
+    #[test]
+    fn tells_updates_from_versions() {
+        assert!(is_update(Some("0.1.0"), "0.2.0"));
+        assert!(is_update(Some("0.9.0"), "0.10.0"));
+        assert!(is_update(Some("0.2.0-beta.1"), "0.2.1"));
+        assert!(!is_update(Some("0.2.0"), "0.2.0"));
+        assert!(!is_update(Some("0.3.0"), "0.2.0"));
+        assert!(!is_update(None, "0.2.0"));
+        assert!(is_update(Some("nightly"), "0.2.0"));
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use balti_err::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::archive::{ArchiveEntry, ZipSnapshot};
+
+/// Bumped when the state file changes incompatibly, older checkpoints are dropped then
+const VERSION: u32 = 1;
+
+const STATE_EXT: &str = "json";
+/// Objects listed so far, a JSON line each, appended page by page
+const ENTRIES_EXT: &str = "entries";
+/// Central directory of the archive at a snapshot, named after the entries it covers
+const DIRECTORY_EXT: &str = "zipdir";
+const TMP_EXT: &str = "tmp";
+
+/// Where a folder download got to, so one cut short by a quit, a failure or the object
+/// budget continues from there instead of starting over. Lives in a small state file,
+/// with the listing and the archive's directory in files next to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    version: u32,
+    pub id: String,
+    pub remote: String,
+    pub prefix: String,
+    /// Zip the folder downloads into
+    pub dest: PathBuf,
+    pub updated_at: DateTime<Utc>,
+    /// Where listing continues, `None` before the first page and after the last
+    pub next_token: Option<String>,
+    pub listed_all: bool,
+    /// Lines of the entries file that count, later ones are from an update that didn't land
+    pub listed: usize,
+    /// Entries already in the archive, in listing order
+    pub completed: usize,
+    /// Where the archive's entries end as of its last snapshot
+    pub archive_len: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryLine {
+    key: String,
+    size: u64,
+}
+
+impl Checkpoint {
+    pub fn new(remote: &str, prefix: &str, dest: PathBuf, now: DateTime<Utc>) -> Self {
+        Self {
+            version: VERSION,
+            id: format!("{}-{}", now.timestamp_millis(), std::process::id()),
+            remote: remote.to_owned(),
+            prefix: prefix.to_owned(),
+            dest,
+            updated_at: now,
+            next_token: None,
+            listed_all: false,
+            listed: 0,
+            completed: 0,
+            archive_len: 0,
+        }
+    }
+
+    /// Folder being downloaded, like `2024` of `logs/2024/`
+    pub fn name(&self) -> &str {
+        self.prefix
+            .trim_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+    }
+
+    fn path(&self, dir: &Path, ext: &str) -> PathBuf {
+        dir.join(format!("{}.{ext}", self.id))
+    }
+
+    fn directory_path(&self, dir: &Path, completed: usize) -> PathBuf {
+        dir.join(format!("{}-{completed}.{DIRECTORY_EXT}", self.id))
+    }
+}
+
+/// Starts the checkpoint's listing and archive over, like when its listing is unreadable
+pub fn restart(dir: &Path, checkpoint: &mut Checkpoint) {
+    let _ = fs::remove_file(checkpoint.path(dir, ENTRIES_EXT));
+    checkpoint.next_token = None;
+    checkpoint.listed_all = false;
+    checkpoint.listed = 0;
+    checkpoint.completed = 0;
+    checkpoint.archive_len = 0;
+}
+
+/// Checkpoints left in `dir`, oldest first. Unreadable ones and those of another version
+/// are removed.
+pub fn load_all(dir: &Path) -> Vec<Checkpoint> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut checkpoints = read_dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == STATE_EXT))
+        .filter_map(|path| {
+            let checkpoint = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Checkpoint>(&content).ok())
+                .filter(|checkpoint| checkpoint.version == VERSION);
+            if checkpoint.is_none() {
+                tracing::warn!("Dropping unreadable checkpoint {:?}", path);
+                let _ = fs::remove_file(&path);
+            }
+            checkpoint
+        })
+        .collect::<Vec<_>>();
+    checkpoints.sort_by_key(|checkpoint| checkpoint.updated_at);
+    checkpoints
+}
+
+/// Replaces the state file in one step, a crash leaves either the old or the new one
+pub fn save(dir: &Path, checkpoint: &mut Checkpoint, now: DateTime<Utc>) -> AppResult<()> {
+    checkpoint.updated_at = now;
+    let content = serde_json::to_vec_pretty(checkpoint).map_err(|err| AppError::err(err))?;
+    write_atomic(&checkpoint.path(dir, STATE_EXT), &content)
+}
+
+/// Adds a listed page to the entries file. Only counts once [`save`] records the new
+/// `listed` after this.
+pub fn append_entries(
+    dir: &Path,
+    checkpoint: &Checkpoint,
+    entries: &[ArchiveEntry],
+) -> AppResult<()> {
+    let mut content = Vec::new();
+    for entry in entries {
+        let line = EntryLine {
+            key: entry.key.to_string(),
+            size: entry.size,
+        };
+        serde_json::to_writer(&mut content, &line).map_err(|err| AppError::err(err))?;
+        content.push(b'\n');
+    }
+
+    fs::create_dir_all(dir).map_err(|err| AppError::err(err))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(checkpoint.path(dir, ENTRIES_EXT))
+        .and_then(|mut file| file.write_all(&content))
+        .map_err(|err| AppError::err(err))
+}
+
+/// The `listed` entries of the checkpoint. Lines past them are cut off, so the next page
+/// appends right after.
+pub fn load_entries(dir: &Path, checkpoint: &Checkpoint) -> AppResult<Vec<ArchiveEntry>> {
+    let path = checkpoint.path(dir, ENTRIES_EXT);
+    if checkpoint.listed == 0 {
+        let _ = fs::remove_file(&path);
+        return Ok(Vec::new());
+    }
+
+    let file = File::open(&path).map_err(|err| AppError::err(err))?;
+    let mut reader = BufReader::new(file);
+    let mut entries = Vec::with_capacity(checkpoint.listed);
+    let (mut line, mut len) = (String::new(), 0);
+    while entries.len() < checkpoint.listed {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|err| AppError::err(err))?;
+        if read == 0 || !line.ends_with('\n') {
+            return Err(AppError::message(format!(
+                "Checkpoint lists {} objects, its file has {}",
+                checkpoint.listed,
+                entries.len()
+            )));
+        }
+        let parsed: EntryLine = serde_json::from_str(&line).map_err(|err| AppError::err(err))?;
+        entries.push(ArchiveEntry {
+            key: Arc::from(parsed.key),
+            size: parsed.size,
+        });
+        len += read as u64;
+    }
+
+    OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_len(len))
+        .map_err(|err| AppError::err(err))?;
+    Ok(entries)
+}
+
+/// Records a snapshot of the archive holding the first `completed` entries. The directory
+/// gets a file of its own per snapshot, so the state never points at one it didn't save.
+pub fn save_snapshot(
+    dir: &Path,
+    checkpoint: &mut Checkpoint,
+    completed: usize,
+    snapshot: &ZipSnapshot,
+    now: DateTime<Utc>,
+) -> AppResult<()> {
+    write_atomic(
+        &checkpoint.directory_path(dir, completed),
+        &snapshot.directory,
+    )?;
+
+    let previous = checkpoint.directory_path(dir, checkpoint.completed);
+    checkpoint.completed = completed;
+    checkpoint.archive_len = snapshot.entries_len;
+    save(dir, checkpoint, now)?;
+
+    if previous != checkpoint.directory_path(dir, completed) {
+        let _ = fs::remove_file(previous);
+    }
+    Ok(())
+}
+
+/// Snapshot of the archive the checkpoint points at, `None` when nothing's written yet
+pub fn load_snapshot(dir: &Path, checkpoint: &Checkpoint) -> Option<ZipSnapshot> {
+    if checkpoint.completed == 0 {
+        return None;
+    }
+    let directory = fs::read(checkpoint.directory_path(dir, checkpoint.completed)).ok()?;
+    Some(ZipSnapshot {
+        entries_len: checkpoint.archive_len,
+        directory,
+    })
+}
+
+/// Removes the checkpoint's files once the download finished or got cancelled
+pub fn remove(dir: &Path, checkpoint: &Checkpoint) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    let stem = format!("{}.", checkpoint.id);
+    let snapshot_stem = format!("{}-", checkpoint.id);
+    for entry in read_dir.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if (name.starts_with(&stem) || name.starts_with(&snapshot_stem))
+            && let Err(err) = fs::remove_file(entry.path())
+        {
+            tracing::warn!(
+                "Failed to remove checkpoint file {:?}: {}",
+                entry.path(),
+                err
+            );
+        }
+    }
+}
+
+/// Also removes the partial archive, for downloads that won't resume
+pub fn discard(dir: &Path, checkpoint: &Checkpoint) {
+    remove(dir, checkpoint);
+    if let Err(err) = fs::remove_file(&checkpoint.dest)
+        && err.kind() != std::io::ErrorKind::NotFound
+    {
+        tracing::warn!(
+            "Failed to remove partial archive {:?}: {}",
+            checkpoint.dest,
+            err
+        );
+    }
+}
+
+/// Writes `content` to a synced temporary file first, then moves it over `path`
+pub fn write_atomic(path: &Path, content: &[u8]) -> AppResult<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| AppError::err(err))?;
+    }
+    let tmp = path.with_extension(TMP_EXT);
+    File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|err| AppError::err(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("balti-checkpoint-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entries(keys: &[&str]) -> Vec<ArchiveEntry> {
+        keys.iter()
+            .map(|key| ArchiveEntry {
+                key: Arc::from(*key),
+                size: key.len() as u64,
+            })
+            .collect()
+    }
+
+    fn keys(entries: &[ArchiveEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.key.as_ref()).collect()
+    }
+
+    #[test]
+    fn keeps_only_recorded_pages() {
+        let dir = temp_dir("pages");
+        let now = Utc::now();
+        let mut checkpoint = Checkpoint::new("prod", "logs/", dir.join("logs.zip"), now);
+
+        append_entries(&dir, &checkpoint, &entries(&["logs/a", "logs/b"])).unwrap();
+        checkpoint.listed = 2;
+        checkpoint.next_token = Some("b".into());
+        save(&dir, &mut checkpoint, now).unwrap();
+
+        // the next page landed, the state recording it didn't
+        append_entries(&dir, &checkpoint, &entries(&["logs/c"])).unwrap();
+
+        let loaded = load_all(&dir);
+        assert_eq!(loaded, [checkpoint.clone()]);
+        assert_eq!(
+            keys(&load_entries(&dir, &loaded[0]).unwrap()),
+            ["logs/a", "logs/b"]
+        );
+
+        append_entries(&dir, &checkpoint, &entries(&["logs/c", "logs/d"])).unwrap();
+        checkpoint.listed = 4;
+        assert_eq!(
+            keys(&load_entries(&dir, &checkpoint).unwrap()),
+            ["logs/a", "logs/b", "logs/c", "logs/d"]
+        );
+
+        checkpoint.listed = 5;
+        assert!(load_entries(&dir, &checkpoint).is_err());
+
+        remove(&dir, &checkpoint);
+        assert!(load_all(&dir).is_empty());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn points_at_the_latest_snapshot() {
+        let dir = temp_dir("snapshots");
+        let now = Utc::now();
+        let mut checkpoint = Checkpoint::new("prod", "logs/", dir.join("logs.zip"), now);
+        save(&dir, &mut checkpoint, now).unwrap();
+        assert_eq!(load_snapshot(&dir, &checkpoint), None);
+
+        let first = ZipSnapshot {
+            entries_len: 10,
+            directory: vec![1, 2],
+        };
+        save_snapshot(&dir, &mut checkpoint, 1, &first, now).unwrap();
+        let second = ZipSnapshot {
+            entries_len: 20,
+            directory: vec![3, 4, 5],
+        };
+        save_snapshot(&dir, &mut checkpoint, 2, &second, now).unwrap();
+
+        let loaded = load_all(&dir).remove(0);
+        assert_eq!((loaded.completed, loaded.archive_len), (2, 20));
+        assert_eq!(load_snapshot(&dir, &loaded), Some(second));
+        assert!(!checkpoint.directory_path(&dir, 1).exists());
+
+        fs::write(&checkpoint.dest, b"partial").unwrap();
+        discard(&dir, &checkpoint);
+        assert!(!checkpoint.dest.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn drops_unreadable_checkpoints() {
+        let dir = temp_dir("unreadable");
+        let now = Utc::now();
+        let mut checkpoint = Checkpoint::new("prod", "logs/2024/", dir.join("2024.zip"), now);
+        assert_eq!(checkpoint.name(), "2024");
+        save(&dir, &mut checkpoint, now).unwrap();
+
+        let mut older = checkpoint.clone();
+        older.id = "older".into();
+        older.version = VERSION + 1;
+        save(&dir, &mut older, now).unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+
+        assert_eq!(load_all(&dir), [checkpoint]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
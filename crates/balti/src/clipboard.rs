@@ -0,0 +1,538 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use balti_err::{AppError, AppResult, ErrorKind};
+use balti_s3::{__S3Object, MAX_PAGE_SIZE, ObjectKind, S3Object, S3Remote};
+use futures::{StreamExt, channel::mpsc::UnboundedSender};
+use gpui::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    coalesce::Outcome,
+    quota::{self, QuotaGate},
+    undo::MovedObject,
+};
+
+/// Copies running at once during a paste
+const PASTE_CONCURRENCY: usize = 8;
+
+pub fn init(cx: &mut App) {
+    cx.set_global(ObjectClipboard::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardMode {
+    Copy,
+    /// Sources are deleted once pasted
+    Cut,
+}
+
+/// Objects copied or cut in some tab, ready to paste into any folder of any remote
+#[derive(Clone)]
+pub struct ClipboardEntry {
+    pub remote: S3Remote,
+    /// Folder the objects were taken from, they keep their path below it when pasted
+    pub prefix: Arc<str>,
+    pub objects: Vec<S3Object>,
+    pub mode: ClipboardMode,
+}
+
+impl ClipboardEntry {
+    /// Like "3 items in clipboard (cut)"
+    pub fn hint(&self) -> String {
+        let count = self.objects.len();
+        format!(
+            "{count} {} in clipboard ({})",
+            if count == 1 { "item" } else { "items" },
+            match self.mode {
+                ClipboardMode::Copy => "copy",
+                ClipboardMode::Cut => "cut",
+            }
+        )
+    }
+
+    /// Whether the objects can go into `dest` of the remote named `remote_name`
+    pub fn check(&self, remote_name: &str, dest: &str) -> PasteCheck {
+        if self.remote.remote_name.as_ref() != remote_name {
+            return PasteCheck::Ok;
+        }
+
+        let dest = folder(dest);
+        if folder(&self.prefix) == dest {
+            return PasteCheck::SameFolder;
+        }
+        let into_itself = self.objects.iter().any(|object| match object.as_ref() {
+            __S3Object::Folder(key) => dest.starts_with(&folder(key)),
+            __S3Object::File { .. } => false,
+        });
+        if into_itself {
+            return PasteCheck::IntoItself;
+        }
+        PasteCheck::Ok
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteCheck {
+    Ok,
+    /// Pasting would put every object back onto itself
+    SameFolder,
+    /// A folder would be pasted below itself
+    IntoItself,
+}
+
+/// App wide, set from any tab and shown in the status bar of all of them
+#[derive(Default)]
+pub struct ObjectClipboard(Option<ClipboardEntry>);
+
+impl Global for ObjectClipboard {}
+
+impl ObjectClipboard {
+    pub fn entry(cx: &App) -> Option<&ClipboardEntry> {
+        cx.global::<Self>().0.as_ref()
+    }
+
+    pub fn set(entry: Option<ClipboardEntry>, cx: &mut App) {
+        cx.set_global(Self(entry));
+    }
+}
+
+/// Single object to copy, folders are expanded to one item per key below them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PasteItem {
+    pub from_key: Arc<str>,
+    pub to_key: Arc<str>,
+    /// Size of the source, for showing the copy before the listing catches up
+    pub size: i64,
+}
+
+impl PasteItem {
+    fn is_marker(&self) -> bool {
+        ObjectKind::of_key(&self.from_key, self.size) == ObjectKind::FolderMarker
+    }
+}
+
+/// What a paste does with an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteAction {
+    Copy,
+    /// Overwrites what's at the destination
+    Replace,
+    Skip,
+}
+
+/// Why an item gets its action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteReason {
+    /// Nothing at the destination yet
+    New,
+    /// Destination is taken
+    Exists,
+    /// Keeps an empty folder alive, replacing one changes nothing
+    FolderMarker,
+    /// Left out while reviewing the plan
+    Excluded,
+}
+
+/// Item of a [`PastePlan`], left out ones are skipped
+#[derive(Debug, Clone)]
+pub struct PlannedPaste {
+    pub item: PasteItem,
+    /// Destination was taken when planning
+    pub taken: bool,
+    pub included: bool,
+}
+
+impl PlannedPaste {
+    pub fn action(&self) -> PasteAction {
+        match (self.included, self.taken) {
+            (false, _) => PasteAction::Skip,
+            (true, true) => PasteAction::Replace,
+            (true, false) => PasteAction::Copy,
+        }
+    }
+
+    pub fn reason(&self) -> PasteReason {
+        if !self.included {
+            PasteReason::Excluded
+        } else if self.taken {
+            PasteReason::Exists
+        } else if self.item.is_marker() {
+            PasteReason::FolderMarker
+        } else {
+            PasteReason::New
+        }
+    }
+}
+
+/// Everything a paste is about to do, item by item, for reviewing before it runs
+#[derive(Debug, Clone)]
+pub struct PastePlan {
+    pub from: Arc<str>,
+    pub to: Arc<str>,
+    pub mode: ClipboardMode,
+    pub items: Vec<PlannedPaste>,
+}
+
+impl PastePlan {
+    /// Plans the items of [`plan`], `taken` are the source keys [`conflicts`] found
+    pub fn new(
+        from: Arc<str>,
+        to: Arc<str>,
+        mode: ClipboardMode,
+        items: Vec<PasteItem>,
+        taken: &HashSet<Arc<str>>,
+    ) -> Self {
+        let items = items
+            .into_iter()
+            .map(|item| PlannedPaste {
+                taken: taken.contains(&item.from_key),
+                item,
+                included: true,
+            })
+            .collect();
+        Self {
+            from,
+            to,
+            mode,
+            items,
+        }
+    }
+
+    /// Items with the action, all of them for `None`, along with their index
+    pub fn filtered(
+        &self,
+        action: Option<PasteAction>,
+    ) -> impl Iterator<Item = (usize, &PlannedPaste)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(move |(_, planned)| action.is_none_or(|action| planned.action() == action))
+    }
+
+    pub fn count(&self, action: Option<PasteAction>) -> usize {
+        self.filtered(action).count()
+    }
+
+    /// Includes or leaves out the items with the action, all of them for `None`
+    pub fn set_included(&mut self, action: Option<PasteAction>, included: bool) {
+        let indices = self
+            .filtered(action)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for index in indices {
+            self.items[index].included = included;
+        }
+    }
+
+    /// Every item and the ones to skip among them, with whether their destination is
+    /// taken, for [`paste`]
+    pub fn into_batch(self) -> (Vec<PasteItem>, HashMap<Arc<str>, PasteReason>) {
+        let skip = self
+            .items
+            .iter()
+            .filter(|planned| !planned.included)
+            .map(|planned| {
+                let reason = if planned.taken {
+                    PasteReason::Exists
+                } else {
+                    PasteReason::Excluded
+                };
+                (planned.item.from_key.clone(), reason)
+            })
+            .collect();
+        let items = self.items.into_iter().map(|planned| planned.item).collect();
+        (items, skip)
+    }
+
+    /// Plan as JSON, for keeping a record of it
+    pub fn export(&self) -> AppResult<String> {
+        #[derive(Serialize)]
+        struct Export<'a> {
+            from: &'a str,
+            to: &'a str,
+            mode: ClipboardMode,
+            items: Vec<Record<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct Record<'a> {
+            #[serde(flatten)]
+            item: &'a PasteItem,
+            action: PasteAction,
+            reason: PasteReason,
+        }
+
+        let export = Export {
+            from: &self.from,
+            to: &self.to,
+            mode: self.mode,
+            items: self
+                .items
+                .iter()
+                .map(|planned| Record {
+                    item: &planned.item,
+                    action: planned.action(),
+                    reason: planned.reason(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&export).map_err(|err| AppError::err(err))
+    }
+}
+
+/// Paste that ran last in a tab, failed items get retried from here
+pub struct PasteBatch {
+    pub from: S3Remote,
+    pub mode: ClipboardMode,
+    pub items: HashMap<Arc<str>, PasteItem>,
+}
+
+/// Prefix as a key prefix, `""` for the bucket root and `a/b/` otherwise
+fn folder(prefix: &str) -> String {
+    match prefix.trim_matches('/') {
+        "" => String::new(),
+        prefix => format!("{prefix}/"),
+    }
+}
+
+/// Where `key`, taken from the `source` folder, lands when pasted into `dest`
+pub fn dest_key(key: &str, source: &str, dest: &str) -> String {
+    let key = key.trim_start_matches('/');
+    let relative = key.strip_prefix(&folder(source)).unwrap_or(key);
+    format!("{}{relative}", folder(dest))
+}
+
+/// Lists what pasting the entry into `dest` copies, folder markers included so
+/// empty folders come along
+pub async fn plan(entry: ClipboardEntry, dest: Arc<str>) -> AppResult<Vec<PasteItem>> {
+    let mut files = Vec::new();
+    for object in entry.objects.iter() {
+        match object.as_ref() {
+            __S3Object::File { key, size, .. } => files.push((key.clone(), *size)),
+            __S3Object::Folder(prefix) => {
+                let mut token: Option<Arc<str>> = None;
+                loop {
+                    let page = balti_s3::list_objects_recursive(
+                        entry.remote.clone(),
+                        prefix,
+                        MAX_PAGE_SIZE,
+                        token.as_deref(),
+                    )
+                    .await?;
+                    files.extend(page.objects.iter().map(|object| match object.as_ref() {
+                        __S3Object::File { key, size, .. } => (key.clone(), *size),
+                        __S3Object::Folder(key) => (key.clone(), 0),
+                    }));
+
+                    match page.next_token {
+                        Some(next) => token = Some(next),
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .map(|(key, size)| PasteItem {
+            to_key: dest_key(&key, &entry.prefix, &dest).into(),
+            from_key: key,
+            size,
+        })
+        .collect())
+}
+
+/// Source keys of the items whose destination is taken. Markers don't count,
+/// replacing one changes nothing.
+pub async fn conflicts(remote: S3Remote, items: Vec<PasteItem>) -> AppResult<HashSet<Arc<str>>> {
+    let checks = items
+        .into_iter()
+        .filter(|item| !item.is_marker())
+        .map(|item| {
+            let remote = remote.clone();
+            async move {
+                let exists = balti_s3::object_exists(remote, &item.to_key).await?;
+                AppResult::Ok(exists.then_some(item.from_key))
+            }
+        });
+
+    let mut taken = HashSet::new();
+    let mut results = futures::stream::iter(checks).buffer_unordered(PASTE_CONCURRENCY);
+    while let Some(result) = results.next().await {
+        if let Some(key) = result? {
+            taken.insert(key);
+        }
+    }
+    Ok(taken)
+}
+
+/// Copies every item to `to`, through CopyObject within a remote and streamed across
+/// remotes. Cut sources are deleted once their copy succeeds. Items in `skip` are
+/// left alone and reported as skipped. Each outcome goes to `outcomes` as it comes,
+/// moved objects go to `moved` too for undoing the move.
+#[allow(clippy::too_many_arguments)]
+pub async fn paste(
+    from: S3Remote,
+    to: S3Remote,
+    items: Vec<PasteItem>,
+    mode: ClipboardMode,
+    skip: HashMap<Arc<str>, PasteReason>,
+    gates: Vec<QuotaGate>,
+    outcomes: UnboundedSender<Outcome>,
+    moved: UnboundedSender<MovedObject>,
+) {
+    let same_remote = from.remote_name == to.remote_name;
+
+    let tasks = items.into_iter().map(|item| {
+        let from = from.clone();
+        let to = to.clone();
+        let skipped = skip.get(&item.from_key).copied();
+        let gates = &gates;
+        let moved = &moved;
+
+        async move {
+            if let Some(reason) = skipped {
+                let message = match reason {
+                    PasteReason::Exists => format!("\"{}\" already exists", item.to_key),
+                    _ => format!("\"{}\" was left out of the paste", item.from_key),
+                };
+                let err = AppError::message(message).with_kind(ErrorKind::Skipped);
+                return (item.from_key, Err(err));
+            }
+
+            let result = quota::gated(gates, async {
+                let copied = if same_remote {
+                    balti_s3::copy_object(to, &item.from_key, &item.to_key, true).await
+                } else {
+                    balti_s3::copy_between(from.clone(), &item.from_key, to, &item.to_key).await
+                };
+                match (copied, mode) {
+                    (Ok(etag), ClipboardMode::Cut) => {
+                        balti_s3::delete_file(from, &item.from_key).await?;
+                        let _ = moved.unbounded_send(MovedObject {
+                            from_key: item.from_key.clone(),
+                            to_key: item.to_key.clone(),
+                            etag,
+                        });
+                        Ok(())
+                    }
+                    (result, _) => result.map(|_| ()),
+                }
+            })
+            .await;
+            (item.from_key, result)
+        }
+    });
+
+    futures::stream::iter(tasks)
+        .buffer_unordered(PASTE_CONCURRENCY)
+        .for_each(|outcome| {
+            let _ = outcomes.unbounded_send(outcome);
+            futures::future::ready(())
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_paths_below_the_source_folder() {
+        assert_eq!(dest_key("a/b/c.txt", "a/", "x/"), "x/b/c.txt");
+        assert_eq!(dest_key("a/c.txt", "/a/", "/"), "c.txt");
+        assert_eq!(dest_key("c.txt", "/", "x/y"), "x/y/c.txt");
+        assert_eq!(dest_key("a/f/__fd.dat", "a", "x/"), "x/f/__fd.dat");
+    }
+
+    fn item(from_key: &str) -> PasteItem {
+        PasteItem {
+            from_key: from_key.into(),
+            to_key: dest_key(from_key, "a/", "x/").into(),
+            size: 3,
+        }
+    }
+
+    #[test]
+    fn plans_an_action_per_item() {
+        let taken = HashSet::from([Arc::from("a/taken.txt")]);
+        let mut plan = PastePlan::new(
+            "prod".into(),
+            "backup".into(),
+            ClipboardMode::Copy,
+            vec![item("a/new.txt"), item("a/taken.txt"), item("a/f/__fd.dat")],
+            &taken,
+        );
+
+        let actions = |plan: &PastePlan| {
+            plan.items
+                .iter()
+                .map(|planned| (planned.action(), planned.reason()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            actions(&plan),
+            [
+                (PasteAction::Copy, PasteReason::New),
+                (PasteAction::Replace, PasteReason::Exists),
+                (PasteAction::Copy, PasteReason::FolderMarker),
+            ]
+        );
+        assert_eq!(plan.count(Some(PasteAction::Copy)), 2);
+
+        plan.set_included(Some(PasteAction::Replace), false);
+        plan.items[0].included = false;
+        assert_eq!(plan.count(Some(PasteAction::Skip)), 2);
+        assert_eq!(plan.count(Some(PasteAction::Replace)), 0);
+        assert_eq!(plan.items[1].reason(), PasteReason::Excluded);
+
+        let (items, skip) = plan.clone().into_batch();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            skip,
+            HashMap::from([
+                (Arc::from("a/new.txt"), PasteReason::Excluded),
+                (Arc::from("a/taken.txt"), PasteReason::Exists),
+            ])
+        );
+
+        plan.set_included(None, true);
+        assert_eq!(plan.count(Some(PasteAction::Skip)), 0);
+    }
+
+    #[test]
+    fn exports_the_plan() {
+        let mut plan = PastePlan::new(
+            "prod".into(),
+            "backup".into(),
+            ClipboardMode::Cut,
+            vec![item("a/b.txt")],
+            &HashSet::new(),
+        );
+        plan.items[0].included = false;
+
+        let export = serde_json::from_str::<serde_json::Value>(&plan.export().unwrap()).unwrap();
+        assert_eq!(
+            export,
+            serde_json::json!({
+                "from": "prod",
+                "to": "backup",
+                "mode": "cut",
+                "items": [{
+                    "from_key": "a/b.txt",
+                    "to_key": "x/b.txt",
+                    "size": 3,
+                    "action": "skip",
+                    "reason": "excluded",
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use balti_err::{AppError, AppResult, BatchReport, ErrorKind};
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, channel::mpsc::UnboundedReceiver};
+use gpui::{AsyncWindowContext, SharedString, Task};
+use gpui_component::{WindowExt, notification::Notification};
+use tracing::Instrument;
+
+use crate::{strings::t, transfers::TransferGuard};
+
+/// Shortest gap between two updates of a batch's progress notification
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+static NEXT_OPERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Key of a batch item along with how it went, sent as each item finishes
+pub type Outcome = (Arc<str>, AppResult<()>);
+
+/// Id a bulk operation goes by in its log lines, journal entry and toasts alike. Made of
+/// when it started and a count, like `195a3f0c2e1-4`, so it's unique across sessions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OperationId(Arc<str>);
+
+impl OperationId {
+    fn new(now: DateTime<Utc>) -> Self {
+        let n = NEXT_OPERATION.fetch_add(1, Ordering::Relaxed);
+        Self(format!("{:x}-{n}", now.timestamp_millis()).into())
+    }
+
+    /// Span the operation's requests run in, each log line of them carries the id
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!("operation", id = %self.0)
+    }
+
+    /// Runs `f` as the operation: in its span, and with errors of the requests made
+    /// from it carrying the id
+    pub fn scope<F: Future>(&self, f: F) -> impl Future<Output = F::Output> + use<F> {
+        balti_err::in_operation(self.0.clone(), f).instrument(self.span())
+    }
+}
+
+impl fmt::Display for OperationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Outcomes of a bulk operation gathered as its items finish, in whatever order
+/// they do. Drives one progress notification per batch instead of one per item.
+pub struct BatchProgress {
+    operation: OperationId,
+    /// Keys in the order the batch was started with, the report keeps it
+    keys: Vec<Arc<str>>,
+    pending: HashSet<Arc<str>>,
+    outcomes: HashMap<Arc<str>, AppResult<()>>,
+    failed: usize,
+    last_refresh: Option<Instant>,
+}
+
+impl BatchProgress {
+    pub fn new(keys: Vec<Arc<str>>) -> Self {
+        Self {
+            operation: OperationId::new(Utc::now()),
+            pending: keys.iter().cloned().collect(),
+            keys,
+            outcomes: HashMap::new(),
+            failed: 0,
+            last_refresh: None,
+        }
+    }
+
+    pub fn operation(&self) -> &OperationId {
+        &self.operation
+    }
+
+    pub fn total(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn finished(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// Keeps the first outcome of each key, keys outside the batch are ignored
+    pub fn record(&mut self, key: Arc<str>, result: AppResult<()>) {
+        if !self.pending.remove(&key) {
+            return;
+        }
+        // paused items are left for the user to resume, they didn't fail
+        if result
+            .as_ref()
+            .is_err_and(|err| err.kind != ErrorKind::Paused)
+        {
+            self.failed += 1;
+        }
+        self.outcomes.insert(key, result);
+    }
+
+    /// Whether the progress notification is due for an update. One-off operations
+    /// never get one, others at most every [`REFRESH_INTERVAL`].
+    pub fn take_refresh(&mut self, now: Instant) -> bool {
+        if self.total() <= 1 || self.finished() == self.total() {
+            return false;
+        }
+        if self
+            .last_refresh
+            .is_some_and(|last| now.duration_since(last) < REFRESH_INTERVAL)
+        {
+            return false;
+        }
+
+        self.last_refresh = Some(now);
+        true
+    }
+
+    /// Whether a progress notification went up for the batch
+    pub fn was_shown(&self) -> bool {
+        self.last_refresh.is_some()
+    }
+
+    /// Tags `notification` as the batch's, it replaces the one shown before
+    pub fn tag(&self, notification: Notification) -> Notification {
+        tag(&self.operation, notification)
+    }
+
+    /// Like "Deleted 34/80…, 2 failed"
+    pub fn label(&self, verb: &str) -> String {
+        let label = t!(
+            "batch.progress",
+            verb = verb,
+            finished = self.finished(),
+            total = self.total()
+        );
+        match self.failed {
+            0 => label,
+            failed => t!("batch.progress_failed", progress = label, failed = failed),
+        }
+    }
+
+    /// Report in the order the batch started with, items that never finished
+    /// count as failed with `err`
+    pub fn into_report(mut self, err: Option<&AppError>) -> BatchReport {
+        self.keys
+            .into_iter()
+            .map(|key| {
+                let result = self.outcomes.remove(&key).unwrap_or_else(|| {
+                    Err(err
+                        .cloned()
+                        .unwrap_or_else(|| AppError::message(format!("\"{key}\" never finished"))))
+                });
+                (key, result)
+            })
+            .collect()
+    }
+}
+
+/// Gathers the outcomes of a batch running as `task`, keeping a progress notification
+/// built by `notification` and the progress of `transfer` up to date meanwhile. Returns
+/// the report along with the batch, to tag the final notification so it replaces the
+/// progress one.
+pub async fn follow(
+    mut progress: BatchProgress,
+    mut outcomes: UnboundedReceiver<Outcome>,
+    task: Task<AppResult<()>>,
+    transfer: &TransferGuard,
+    notification: impl Fn(&BatchProgress) -> Notification,
+    cx: &mut AsyncWindowContext,
+) -> (BatchReport, BatchProgressId) {
+    while let Some((key, result)) = outcomes.next().await {
+        progress.record(key, result);
+        transfer.progress(progress.finished() as u64, progress.total() as u64);
+        if progress.take_refresh(Instant::now()) {
+            let notification = progress.tag(notification(&progress));
+            let _ = cx.update(|window, cx| window.push_notification(notification, cx));
+        }
+    }
+
+    let id = BatchProgressId {
+        operation: progress.operation.clone(),
+        shown: progress.was_shown(),
+    };
+    let err = task.await.err();
+    let report = progress.into_report(err.as_ref());
+    tracing::info!(
+        operation = %id.operation,
+        "Batch finished, {} of {} went through",
+        report.succeeded(),
+        report.items.len()
+    );
+    if report.failed() > 0 {
+        transfer.fail();
+    }
+    (report, id)
+}
+
+/// Operation of a finished batch, with whether its progress notification is up
+#[derive(Debug, Clone)]
+pub struct BatchProgressId {
+    operation: OperationId,
+    shown: bool,
+}
+
+impl BatchProgressId {
+    pub fn operation(&self) -> &OperationId {
+        &self.operation
+    }
+
+    /// Tags `notification` to replace the batch's progress notification, if it had one
+    pub fn tag(&self, notification: Notification) -> Notification {
+        if self.shown {
+            tag(&self.operation, notification)
+        } else {
+            notification
+        }
+    }
+
+    /// Another notification took the progress notification's place, the next ones go
+    /// next to it
+    pub fn replaced(self) -> Self {
+        Self {
+            shown: false,
+            ..self
+        }
+    }
+}
+
+fn tag(operation: &OperationId, notification: Notification) -> Notification {
+    notification.id1::<BatchProgress>(SharedString::new(format!("batch-{operation}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<Arc<str>> {
+        keys.iter().map(|key| Arc::from(*key)).collect()
+    }
+
+    #[test]
+    fn reports_out_of_order_completion_in_batch_order() {
+        let mut progress = BatchProgress::new(keys(&["a", "b", "c"]));
+        progress.record("c".into(), Ok(()));
+        progress.record("a".into(), Err(AppError::message("denied")));
+        progress.record("c".into(), Err(AppError::message("late duplicate")));
+        progress.record("x".into(), Ok(()));
+        assert_eq!(progress.label("Deleted"), "Deleted 2/3…, 1 failed");
+
+        progress.record("b".into(), Ok(()));
+        let report = progress.into_report(None);
+        let order = report
+            .items
+            .iter()
+            .map(|(key, _)| key.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["a", "b", "c"]);
+        assert_eq!(report.succeeded(), 2);
+        assert_eq!(report.failed(), 1);
+    }
+
+    #[test]
+    fn fails_items_that_never_finished() {
+        let mut progress = BatchProgress::new(keys(&["a", "b"]));
+        progress.record("b".into(), Ok(()));
+
+        let report = progress.into_report(Some(&AppError::message("cancelled")));
+        assert_eq!(report.succeeded(), 1);
+        assert_eq!(
+            report.failures().next().map(|(key, _)| key.as_ref()),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn counts_paused_items_apart_from_failures() {
+        let mut progress = BatchProgress::new(keys(&["a", "b"]));
+        progress.record(
+            "a".into(),
+            Err(AppError::message("quota").with_kind(ErrorKind::Paused)),
+        );
+        assert_eq!(progress.label("Copied"), "Copied 1/2…");
+
+        progress.record("b".into(), Ok(()));
+        let report = progress.into_report(None);
+        assert_eq!((report.paused(), report.failed()), (1, 0));
+    }
+
+    #[test]
+    fn throttles_refreshes() {
+        let start = Instant::now();
+        let mut progress = BatchProgress::new(keys(&["a", "b", "c"]));
+        assert!(progress.take_refresh(start));
+        assert!(!progress.take_refresh(start + Duration::from_millis(100)));
+        assert!(progress.take_refresh(start + REFRESH_INTERVAL));
+
+        let mut single = BatchProgress::new(keys(&["a"]));
+        assert!(!single.take_refresh(start));
+        assert!(!single.was_shown());
+    }
+
+    #[test]
+    fn tells_operations_apart() {
+        let now = Utc::now();
+        let (first, second) = (OperationId::new(now), OperationId::new(now));
+        assert_ne!(first, second);
+        assert!(
+            first
+                .to_string()
+                .starts_with(&format!("{:x}-", now.timestamp_millis()))
+        );
+
+        let progress = BatchProgress::new(keys(&["a"]));
+        assert_ne!(progress.operation(), &first);
+    }
+
+    #[test]
+    fn labels_requests_of_the_operation() {
+        let operation = OperationId::new(Utc::now());
+        let err = futures::executor::block_on(operation.scope(async {
+            AppError::message("Access Denied").with_context("delete_file", "prod")
+        }));
+        assert_eq!(
+            err.context.unwrap().operation.as_deref(),
+            Some(operation.to_string().as_str())
+        );
+        assert_eq!(balti_err::current_operation(), None);
+    }
+}
@@ -1,26 +1,51 @@
 use std::{
-    collections::{BTreeMap, HashMap},
-    fs::{self, File},
-    io::{Read, Write},
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
     sync::{Arc, OnceLock},
 };
 
-use balti_s3::S3Config;
+use balti_s3::{
+    CustomHeader, ProxySettings, S3Config, UploadDefaults, normalize_root_prefix,
+    parse_credentials_expiry, validate_header,
+};
 use chrono::Utc;
 
 use balti_err::{AppError, AppResult};
 
+use crate::{
+    checkpoint::write_atomic,
+    diff::Overwrite,
+    settings::{LogFormat, SETTINGS_EXPORT_VERSION, Settings, SettingsExport, ViewPrefs},
+};
+
 pub const BALTI_VERSION: &str = env!("BALTI_VERSION");
 pub const BALTI_COMMIT_SHA: &str = env!("BALTI_COMMIT_SHA");
+/// Seconds since the epoch at build time, or `SOURCE_DATE_EPOCH` when set
+pub const BALTI_BUILD_TIMESTAMP: &str = env!("BALTI_BUILD_TIMESTAMP");
 
 const REMOTES_CONFIG: &str = "remotes.toml";
+/// Field of a remote's table naming its [`RemoteConfig`] variant
+const REMOTE_KIND: &str = "kind";
+/// Kind of the remotes saved before they had one
+const DEFAULT_REMOTE_KIND: &str = "s3";
+/// Held locked while the remotes config is read or written, other instances wait on it
+const REMOTES_LOCK: &str = "remotes.lock";
+const SETTINGS_CONFIG: &str = "settings.toml";
+const VIEWS_CONFIG: &str = "views.toml";
+/// Version of the app last launched, for telling updates apart
+const LAST_VERSION_FILE: &str = "last_version";
+/// Release whose update banner was dismissed
+const DISMISSED_UPDATE_FILE: &str = "dismissed_update";
+const LOCALES_DIR: &str = "locales";
 
 static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
 
 const APP_CONFIG_DIR: &str = "balti";
 
-fn config_dir() -> &'static PathBuf {
+pub fn config_dir() -> &'static PathBuf {
     CONFIG_DIR.get_or_init(|| {
         dirs::home_dir()
             .expect("failed to determine user's home directory")
@@ -29,6 +54,27 @@ fn config_dir() -> &'static PathBuf {
     })
 }
 
+/// Cached thumbnails of image objects, safe to wipe at any time
+pub fn thumbnails_dir() -> PathBuf {
+    config_dir().join("thumbnails")
+}
+
+/// Progress of folder downloads, for resuming them after a quit or a failure
+pub fn checkpoints_dir() -> PathBuf {
+    config_dir().join("checkpoints")
+}
+
+/// Journal of the changes made to remotes, kept for the retention setting
+pub fn journal_dir() -> PathBuf {
+    config_dir().join("journal")
+}
+
+/// Objects downloaded for dragging out of the app, one folder per process so instances
+/// don't clear each other's
+pub fn staging_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("balti-staging-{}", std::process::id()))
+}
+
 pub fn get_new_log_file_path() -> PathBuf {
     let logs_dir = config_dir().join("logs");
     if !logs_dir.exists() {
@@ -36,94 +82,587 @@ pub fn get_new_log_file_path() -> PathBuf {
             .map_err(|err| AppError::err(err))
             .expect("Failed to create logs folder");
     }
-    logs_dir.join(format!("balti_logs_{}.log", Utc::now()))
+    let path = logs_dir.join(format!("balti_logs_{}.log", Utc::now()));
+    let _ = LOG_FILE.set(path.clone());
+    path
 }
 
-pub fn parse_s3_remotes() -> AppResult<HashMap<String, S3Config>> {
-    let config_dir = config_dir();
+/// File this run logs to
+pub fn log_file_path() -> Option<&'static PathBuf> {
+    LOG_FILE.get()
+}
+
+/// Config of a remote, by the kind of storage it connects to
+#[derive(Debug, Clone)]
+pub enum RemoteConfig {
+    S3(S3Config),
+}
+
+impl RemoteConfig {
+    /// Saved as `kind` in the remote's table
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::S3(_) => "s3",
+        }
+    }
+
+    /// Same remote in another region, for remotes that have one
+    pub fn with_region(&self, region: &str) -> Self {
+        match self {
+            Self::S3(config) => Self::S3(config.with_region(region)),
+        }
+    }
+}
+
+/// Remotes config as last read or written by this instance, saves merge against it
+#[derive(Debug, Default)]
+pub struct RemotesBase {
+    tables: BTreeMap<String, toml::Table>,
+    hash: u64,
+}
+
+/// How a save went when another instance changed the config since it was read
+#[derive(Debug, Default)]
+pub struct SavedRemotes {
+    /// Remotes the other instance added, changed or removed (`None`), kept in the save
+    pub external: Vec<(String, Option<RemoteConfig>)>,
+    /// Remotes both changed differently, ours got saved
+    pub conflicts: Vec<String>,
+}
+
+pub fn parse_remotes() -> AppResult<(HashMap<String, RemoteConfig>, RemotesBase)> {
+    parse_remotes_in(config_dir())
+}
+
+fn parse_remotes_in(config_dir: &Path) -> AppResult<(HashMap<String, RemoteConfig>, RemotesBase)> {
     if !config_dir.exists() {
-        fs::create_dir_all(&config_dir).map_err(|err| AppError::err(err))?;
+        fs::create_dir_all(config_dir).map_err(|err| AppError::err(err))?;
     }
 
+    let _lock = lock_remotes(config_dir)?;
     let config_path = config_dir.join(REMOTES_CONFIG);
     if !config_path.exists() {
         File::create(&config_path).map_err(|err| AppError::err(err))?;
-        return Ok(HashMap::new());
     }
 
-    let mut file = File::open(&config_path).map_err(|err| AppError::err(err))?;
-    let mut buf = Vec::with_capacity(4096);
-    file.read_to_end(&mut buf)
-        .map_err(|err| AppError::err(err))?;
+    let (tables, hash) = read_remote_tables(&config_path)?;
+    let mut remote_configs = HashMap::new();
+    for (remote_name, table) in tables.iter() {
+        let config = parse_remote(remote_name, table)?;
+        remote_configs.insert(remote_name.clone(), config);
+    }
 
-    let config: toml::Table = toml::from_slice(&buf).map_err(|err| AppError::err(err))?;
+    Ok((remote_configs, RemotesBase { tables, hash }))
+}
 
-    let mut remote_configs = HashMap::new();
-    for (remote_name, value) in config.into_iter() {
-        let Some(table) = value.as_table() else {
-            continue;
-        };
+/// Saves `remotes` over what `base` read. When another instance saved in between, its
+/// changes to other remotes are merged in rather than overwritten.
+pub fn save_remotes(
+    remotes: &BTreeMap<Arc<str>, RemoteConfig>,
+    base: &mut RemotesBase,
+) -> AppResult<SavedRemotes> {
+    save_remotes_in(config_dir(), remotes, base)
+}
 
-        let access_key_id = get_table_str(&remote_name, table, "access_key_id")?;
-        let secret_access_key = get_table_str(&remote_name, table, "secret_access_key")?;
-        let region = get_table_str(&remote_name, table, "region")?;
-        let endpoint = get_table_str(&remote_name, table, "endpoint")?;
-        let bucket_name = get_table_str(&remote_name, table, "bucket_name")?;
-
-        remote_configs.insert(
-            remote_name,
-            S3Config {
-                access_key_id,
-                secret_access_key,
-                region,
-                endpoint,
-                bucket_name,
-            },
+fn save_remotes_in(
+    config_dir: &Path,
+    remotes: &BTreeMap<Arc<str>, RemoteConfig>,
+    base: &mut RemotesBase,
+) -> AppResult<SavedRemotes> {
+    if !config_dir.exists() {
+        fs::create_dir_all(config_dir).map_err(|err| AppError::err(err))?;
+    }
+
+    let _lock = lock_remotes(config_dir)?;
+    let config_path = config_dir.join(REMOTES_CONFIG);
+    let ours = remote_tables(remotes);
+
+    let (theirs, hash) = read_remote_tables(&config_path)?;
+    let mut saved = SavedRemotes::default();
+    let tables = if hash == base.hash {
+        ours
+    } else {
+        let merge = merge_remotes(&base.tables, &ours, &theirs);
+        for name in merge.external {
+            let config = match merge.tables.get(&name) {
+                Some(table) => Some(parse_remote(&name, table)?),
+                None => None,
+            };
+            saved.external.push((name, config));
+        }
+        saved.conflicts = merge.conflicts;
+        tracing::info!(
+            "Remotes config changed on disk, merged {} external change(s)",
+            saved.external.len()
         );
+        merge.tables
+    };
+
+    write_remote_tables(&config_path, tables, base)?;
+    Ok(saved)
+}
+
+/// What saving `remotes` over what `base` read would overwrite in the config on disk,
+/// `None` when the save loses nothing there, only taking in what changed elsewhere.
+/// With `replace` the config gets written over whatever it holds, like one that
+/// doesn't parse.
+pub fn review_remotes(
+    remotes: &BTreeMap<Arc<str>, RemoteConfig>,
+    base: &RemotesBase,
+    replace: bool,
+) -> AppResult<Option<Overwrite>> {
+    review_remotes_in(config_dir(), remotes, base, replace)
+}
+
+fn review_remotes_in(
+    config_dir: &Path,
+    remotes: &BTreeMap<Arc<str>, RemoteConfig>,
+    base: &RemotesBase,
+    replace: bool,
+) -> AppResult<Option<Overwrite>> {
+    if !config_dir.exists() {
+        return Ok(None);
     }
 
-    Ok(remote_configs)
+    let _lock = lock_remotes(config_dir)?;
+    let config_path = config_dir.join(REMOTES_CONFIG);
+    let old = match fs::read_to_string(&config_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(AppError::err(err)),
+    };
+
+    let ours = remote_tables(remotes);
+    let tables = if replace {
+        ours
+    } else {
+        let (theirs, hash) = read_remote_tables(&config_path)?;
+        if hash == base.hash {
+            return Ok(None);
+        }
+        let merge = merge_remotes(&base.tables, &ours, &theirs);
+        if merge.conflicts.is_empty() {
+            return Ok(None);
+        }
+        merge.tables
+    };
+
+    let new = remotes_content(&tables)?;
+    if new == old {
+        return Ok(None);
+    }
+    let (before, after) = (table_names(&old), table_names(&new));
+    Ok(Some(Overwrite {
+        added: after.difference(&before).cloned().collect(),
+        removed: before.difference(&after).cloned().collect(),
+        old,
+        new,
+    }))
 }
 
-pub fn save_s3_remotes(remotes: BTreeMap<Arc<str>, S3Config>) {
-    let config_path = config_dir().join(REMOTES_CONFIG);
-    let mut file = File::create(&config_path).expect("Failed to create config file");
+/// Writes `remotes` over the config whatever it holds, for replacing one that
+/// doesn't parse
+pub fn replace_remotes(
+    remotes: &BTreeMap<Arc<str>, RemoteConfig>,
+    base: &mut RemotesBase,
+) -> AppResult<()> {
+    replace_remotes_in(config_dir(), remotes, base)
+}
+
+fn replace_remotes_in(
+    config_dir: &Path,
+    remotes: &BTreeMap<Arc<str>, RemoteConfig>,
+    base: &mut RemotesBase,
+) -> AppResult<()> {
+    if !config_dir.exists() {
+        fs::create_dir_all(config_dir).map_err(|err| AppError::err(err))?;
+    }
+
+    let _lock = lock_remotes(config_dir)?;
+    let config_path = config_dir.join(REMOTES_CONFIG);
+    write_remote_tables(&config_path, remote_tables(remotes), base)
+}
+
+fn remote_tables(remotes: &BTreeMap<Arc<str>, RemoteConfig>) -> BTreeMap<String, toml::Table> {
+    remotes
+        .iter()
+        .map(|(name, config)| (name.to_string(), remote_table(config)))
+        .collect()
+}
+
+fn remotes_content(tables: &BTreeMap<String, toml::Table>) -> AppResult<String> {
+    let configs = tables
+        .iter()
+        .map(|(name, table)| (name.clone(), toml::Value::Table(table.clone())))
+        .collect::<toml::Table>();
+    toml::to_string(&configs).map_err(|err| AppError::err(err))
+}
 
-    let configs = remotes
+/// Writes the config with `tables`, which `base` follows from then on
+fn write_remote_tables(
+    config_path: &Path,
+    tables: BTreeMap<String, toml::Table>,
+    base: &mut RemotesBase,
+) -> AppResult<()> {
+    let content = remotes_content(&tables)?;
+    write_atomic(config_path, content.as_bytes())?;
+
+    *base = RemotesBase {
+        tables,
+        hash: content_hash(content.as_bytes()),
+    };
+    tracing::info!("Successfully saved remotes config");
+    Ok(())
+}
+
+/// Names of the tables a TOML text defines, by their headers. Reads configs that don't
+/// parse as far as their headers go.
+fn table_names(content: &str) -> BTreeSet<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let header = line.trim().strip_prefix('[')?;
+            if header.starts_with('[') {
+                return None;
+            }
+            let name = match header.trim_start().strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next()?,
+                None => header.split(['.', ']']).next()?.trim(),
+            };
+            (!name.is_empty()).then(|| name.to_owned())
+        })
+        .collect()
+}
+
+/// Waits for and holds an exclusive lock on the remotes config until dropped
+fn lock_remotes(config_dir: &Path) -> AppResult<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(config_dir.join(REMOTES_LOCK))
+        .map_err(|err| AppError::err(err))?;
+    file.lock().map_err(|err| AppError::err(err))?;
+    Ok(file)
+}
+
+/// Remote tables of the config and the hash of its content, a missing file is empty.
+/// Tables without a kind get the default one, as saving would give them.
+fn read_remote_tables(config_path: &Path) -> AppResult<(BTreeMap<String, toml::Table>, u64)> {
+    let buf = match fs::read(config_path) {
+        Ok(buf) => buf,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(AppError::err(err)),
+    };
+
+    let config: toml::Table = toml::from_slice(&buf).map_err(|err| AppError::err(err))?;
+    let tables = config
         .into_iter()
-        .fold(toml::Table::new(), |mut table, (name, config)| {
-            let mut map = toml::Table::new();
-            map.insert(
-                "access_key_id".to_owned(),
-                toml::Value::String(config.access_key_id.to_string()),
-            );
-            map.insert(
-                "secret_access_key".to_owned(),
-                toml::Value::String(config.secret_access_key.to_string()),
-            );
-            map.insert(
-                "region".to_owned(),
-                toml::Value::String(config.region.to_string()),
-            );
-            map.insert(
-                "endpoint".to_owned(),
-                toml::Value::String(config.endpoint.to_string()),
-            );
-            map.insert(
-                "bucket_name".to_owned(),
-                toml::Value::String(config.bucket_name.to_string()),
-            );
-
-            table.insert(name.to_string(), toml::Value::Table(map));
-            table
+        .filter_map(|(name, value)| match value {
+            toml::Value::Table(mut table) => {
+                table
+                    .entry(REMOTE_KIND)
+                    .or_insert_with(|| toml::Value::String(DEFAULT_REMOTE_KIND.to_owned()));
+                // as the remote would be saved, so a hand-written one that saves
+                // differently doesn't count as changed in merges
+                let table = parse_remote(&name, &table)
+                    .map(|config| remote_table(&config))
+                    .unwrap_or(table);
+                Some((name, table))
+            }
+            _ => None,
+        })
+        .collect();
+    Ok((tables, content_hash(&buf)))
+}
+
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct Merge {
+    tables: BTreeMap<String, toml::Table>,
+    /// Remotes taken from theirs
+    external: Vec<String>,
+    /// Remotes both sides changed differently, ours was kept
+    conflicts: Vec<String>,
+}
+
+/// Three-way merge of the remotes by name: a side that left a remote as it was in
+/// `base` takes the other side's version of it
+fn merge_remotes(
+    base: &BTreeMap<String, toml::Table>,
+    ours: &BTreeMap<String, toml::Table>,
+    theirs: &BTreeMap<String, toml::Table>,
+) -> Merge {
+    let names = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut merge = Merge {
+        tables: BTreeMap::new(),
+        external: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    for name in names {
+        let (base, ours, theirs) = (base.get(name), ours.get(name), theirs.get(name));
+        let kept = if ours == theirs || theirs == base {
+            ours
+        } else if ours == base {
+            merge.external.push(name.clone());
+            theirs
+        } else {
+            merge.conflicts.push(name.clone());
+            ours
+        };
+        if let Some(table) = kept {
+            merge.tables.insert(name.clone(), table.clone());
+        }
+    }
+    merge
+}
+
+/// TOML fragment defining the remote for sharing, secrets left out: the secret key, the
+/// session token, secret header values and the proxy password
+pub fn remote_snippet(remote_name: &str, config: &RemoteConfig) -> String {
+    let mut map = remote_table(config);
+    map.remove("secret_access_key");
+    map.remove("session_token");
+    if let Some(toml::Value::Table(headers)) = map.get_mut("headers") {
+        headers.retain(|_, header| {
+            !header
+                .get("secret")
+                .and_then(|v| v.as_bool())
+                .unwrap_or_default()
         });
+        if headers.is_empty() {
+            map.remove("headers");
+        }
+    }
+    if let Some(toml::Value::Table(proxy)) = map.get_mut("proxy") {
+        proxy.remove("password");
+    }
 
-    let content = toml::to_string(&configs).expect("Failed to stringify content");
+    let mut snippet = toml::Table::new();
+    snippet.insert(remote_name.to_owned(), toml::Value::Table(map));
+    toml::to_string(&snippet).expect("Failed to stringify snippet")
+}
 
-    file.write_all(content.as_bytes())
-        .expect("Failed to save remotes to config");
+/// Reads a snippet made by [`remote_snippet`], its remote has no secret key yet
+pub fn parse_remote_snippet(snippet: &str) -> AppResult<(String, RemoteConfig)> {
+    let snippet: toml::Table = toml::from_str(snippet)
+        .map_err(|err| AppError::message(format!("Snippet isn't valid TOML: {err}")))?;
+
+    let mut remotes = snippet.into_iter();
+    let (remote_name, value) = remotes
+        .next()
+        .ok_or_else(|| AppError::message("Snippet doesn't define a remote"))?;
+    if remotes.next().is_some() {
+        return Err(AppError::message("Snippet defines more than one remote"));
+    }
+    let toml::Value::Table(mut table) = value else {
+        return Err(AppError::message(format!(
+            "Snippet has no table for remote: {remote_name}"
+        )));
+    };
+
+    table
+        .entry("secret_access_key")
+        .or_insert_with(|| toml::Value::String(String::new()));
+    let config = parse_remote(&remote_name, &table)?;
+    Ok((remote_name, config))
+}
+
+/// Reads the table of a single remote, by its kind. Tables without one are S3 remotes.
+fn parse_remote(remote_name: &str, table: &toml::Table) -> AppResult<RemoteConfig> {
+    let kind = match table.get(REMOTE_KIND) {
+        Some(kind) => kind
+            .as_str()
+            .ok_or_else(|| AppError::message(format!("Invalid kind for remote: {remote_name}")))?,
+        None => DEFAULT_REMOTE_KIND,
+    };
+    match kind {
+        "s3" => parse_s3_remote(remote_name, table).map(RemoteConfig::S3),
+        kind => Err(AppError::message(format!(
+            "Unknown kind \"{kind}\" for remote: {remote_name}"
+        ))),
+    }
+}
+
+fn parse_s3_remote(remote_name: &str, table: &toml::Table) -> AppResult<S3Config> {
+    let access_key_id = get_table_str(remote_name, table, "access_key_id")?;
+    let secret_access_key = get_table_str(remote_name, table, "secret_access_key")?;
+    let session_token = table
+        .get("session_token")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.trim().into());
+    let region = get_table_str(remote_name, table, "region")?;
+    let endpoint = get_table_str(remote_name, table, "endpoint")?;
+    let bucket_name = get_table_str(remote_name, table, "bucket_name")?;
+    let page_size = get_table_int(remote_name, table, "page_size")?;
+    let root_prefix = table
+        .get("root_prefix")
+        .and_then(|v| v.as_str())
+        .and_then(normalize_root_prefix);
+    let headers = get_table_headers(remote_name, table)?;
+    let proxy = get_table_proxy(remote_name, table)?;
+    let ca_bundle = table
+        .get("ca_bundle")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| v.into());
+    let insecure_tls = table
+        .get("insecure_skip_verify")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+    let timeout_secs = match get_table_int(remote_name, table, "timeout_secs")? {
+        Some(secs) if secs > 0 => Some(secs as u64),
+        Some(_) => {
+            return Err(AppError::message(format!(
+                "Invalid timeout_secs for remote: {remote_name}"
+            )));
+        }
+        None => None,
+    };
+    let credentials_expiry = match table.get("credentials_expiry").and_then(|v| v.as_str()) {
+        Some(expiry) => parse_credentials_expiry(expiry).map_err(|err| {
+            AppError::message(format!("{} for remote: {remote_name}", err.message))
+        })?,
+        None => None,
+    };
+    let upload_defaults = get_table_upload_defaults(remote_name, table)?;
+
+    Ok(S3Config {
+        access_key_id,
+        secret_access_key,
+        session_token,
+        region,
+        endpoint,
+        bucket_name,
+        page_size,
+        root_prefix,
+        headers,
+        proxy,
+        ca_bundle,
+        insecure_tls,
+        timeout_secs,
+        credentials_expiry,
+        upload_defaults,
+    })
+}
 
-    tracing::info!("Successfully saved remotes config")
+/// Table of a single remote, as saved in the remotes config
+fn remote_table(config: &RemoteConfig) -> toml::Table {
+    let mut map = match config {
+        RemoteConfig::S3(config) => s3_remote_table(config),
+    };
+    map.insert(
+        REMOTE_KIND.to_owned(),
+        toml::Value::String(config.kind().to_owned()),
+    );
+    map
+}
+
+fn s3_remote_table(config: &S3Config) -> toml::Table {
+    let mut map = toml::Table::new();
+    map.insert(
+        "access_key_id".to_owned(),
+        toml::Value::String(config.access_key_id.to_string()),
+    );
+    map.insert(
+        "secret_access_key".to_owned(),
+        toml::Value::String(config.secret_access_key.to_string()),
+    );
+    if let Some(session_token) = config.session_token.as_ref() {
+        map.insert(
+            "session_token".to_owned(),
+            toml::Value::String(session_token.to_string()),
+        );
+    }
+    map.insert(
+        "region".to_owned(),
+        toml::Value::String(config.region.to_string()),
+    );
+    map.insert(
+        "endpoint".to_owned(),
+        toml::Value::String(config.endpoint.to_string()),
+    );
+    map.insert(
+        "bucket_name".to_owned(),
+        toml::Value::String(config.bucket_name.to_string()),
+    );
+    if let Some(page_size) = config.page_size {
+        map.insert(
+            "page_size".to_owned(),
+            toml::Value::Integer(page_size as i64),
+        );
+    }
+    if let Some(root_prefix) = config.root_prefix.as_ref() {
+        map.insert(
+            "root_prefix".to_owned(),
+            toml::Value::String(root_prefix.to_string()),
+        );
+    }
+
+    if !config.headers.is_empty() {
+        let headers = config
+            .headers
+            .iter()
+            .fold(toml::Table::new(), |mut headers, header| {
+                let mut entry = toml::Table::new();
+                entry.insert(
+                    "value".to_owned(),
+                    toml::Value::String(header.value.to_string()),
+                );
+                if header.secret {
+                    entry.insert("secret".to_owned(), toml::Value::Boolean(true));
+                }
+                headers.insert(header.name.to_string(), toml::Value::Table(entry));
+                headers
+            });
+        map.insert("headers".to_owned(), toml::Value::Table(headers));
+    }
+
+    if let Some(ca_bundle) = config.ca_bundle.as_ref() {
+        map.insert(
+            "ca_bundle".to_owned(),
+            toml::Value::String(ca_bundle.to_string()),
+        );
+    }
+    if config.insecure_tls {
+        map.insert(
+            "insecure_skip_verify".to_owned(),
+            toml::Value::Boolean(true),
+        );
+    }
+    if let Some(secs) = config.timeout_secs {
+        map.insert("timeout_secs".to_owned(), toml::Value::Integer(secs as i64));
+    }
+    if let Some(expiry) = config.credentials_expiry {
+        map.insert(
+            "credentials_expiry".to_owned(),
+            toml::Value::String(expiry.to_rfc3339()),
+        );
+    }
+    if let Some(proxy) = config.proxy.as_ref() {
+        map.insert(
+            "proxy".to_owned(),
+            toml::Value::try_from(proxy).expect("Failed to stringify proxy"),
+        );
+    }
+    if !config.upload_defaults.is_default() {
+        map.insert(
+            "upload_defaults".to_owned(),
+            toml::Value::try_from(&config.upload_defaults)
+                .expect("Failed to stringify upload defaults"),
+        );
+    }
+    map
 }
 
 fn get_table_str(
@@ -141,3 +680,838 @@ fn get_table_str(
             ))
         })
 }
+
+fn get_table_int(
+    remote_name: &str,
+    table: &toml::map::Map<String, toml::Value>,
+    key: &'static str,
+) -> AppResult<Option<i32>> {
+    let Some(value) = table.get(key) else {
+        return Ok(None);
+    };
+
+    value
+        .as_integer()
+        .and_then(|v| i32::try_from(v).ok())
+        .map(Some)
+        .ok_or_else(|| AppError::message(format!("Invalid {key} for remote: {remote_name}")))
+}
+
+/// Reads the `headers` sub-table, where each entry is either a plain value
+/// or `{ value = "...", secret = true }`
+fn get_table_headers(
+    remote_name: &str,
+    table: &toml::map::Map<String, toml::Value>,
+) -> AppResult<Vec<CustomHeader>> {
+    let Some(headers) = table.get("headers") else {
+        return Ok(Vec::new());
+    };
+    let headers = headers
+        .as_table()
+        .ok_or_else(|| AppError::message(format!("Invalid headers for remote: {remote_name}")))?;
+
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let (value, secret) = match value {
+                toml::Value::String(value) => (value.as_str(), false),
+                toml::Value::Table(entry) => (
+                    entry
+                        .get("value")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default(),
+                    entry
+                        .get("secret")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or_default(),
+                ),
+                _ => {
+                    return Err(AppError::message(format!(
+                        "Invalid header {name} for remote: {remote_name}"
+                    )));
+                }
+            };
+            validate_header(name, value).map_err(|err| {
+                AppError::message(format!("{} for remote: {remote_name}", err.message))
+            })?;
+
+            Ok(CustomHeader {
+                name: name.as_str().into(),
+                value: value.into(),
+                secret,
+            })
+        })
+        .collect()
+}
+
+/// Reads the optional `proxy` sub-table of the remote
+fn get_table_proxy(
+    remote_name: &str,
+    table: &toml::map::Map<String, toml::Value>,
+) -> AppResult<Option<ProxySettings>> {
+    let Some(value) = table.get("proxy") else {
+        return Ok(None);
+    };
+
+    let proxy: ProxySettings = value.clone().try_into().map_err(|err| {
+        AppError::message(format!("Invalid proxy for remote: {remote_name}: {err}"))
+    })?;
+    proxy
+        .validate()
+        .map_err(|err| AppError::message(format!("{} for remote: {remote_name}", err.message)))?;
+    Ok(Some(proxy))
+}
+
+/// Reads the optional `upload_defaults` sub-table of the remote, fields it leaves out
+/// keep the provider's defaults
+fn get_table_upload_defaults(
+    remote_name: &str,
+    table: &toml::map::Map<String, toml::Value>,
+) -> AppResult<UploadDefaults> {
+    let Some(value) = table.get("upload_defaults") else {
+        return Ok(UploadDefaults::default());
+    };
+
+    let defaults: UploadDefaults = value.clone().try_into().map_err(|err| {
+        AppError::message(format!(
+            "Invalid upload defaults for remote: {remote_name}: {err}"
+        ))
+    })?;
+    defaults
+        .validate()
+        .map_err(|err| AppError::message(format!("{} for remote: {remote_name}", err.message)))?;
+    Ok(defaults)
+}
+
+pub fn parse_settings() -> AppResult<Settings> {
+    let config_path = config_dir().join(SETTINGS_CONFIG);
+    if !config_path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = fs::read_to_string(&config_path).map_err(|err| AppError::err(err))?;
+    toml::from_str(&content).map_err(|err| AppError::err(err))
+}
+
+pub fn save_settings(settings: &Settings) -> AppResult<()> {
+    let config_dir = config_dir();
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).map_err(|err| AppError::err(err))?;
+    }
+
+    let content = toml::to_string(settings).map_err(|err| AppError::err(err))?;
+    fs::write(config_dir.join(SETTINGS_CONFIG), content).map_err(|err| AppError::err(err))?;
+
+    tracing::info!("Successfully saved settings");
+    Ok(())
+}
+
+/// What saving `settings` would write over the settings file as it is, `None` when
+/// nothing changes
+pub fn review_settings(settings: &Settings) -> AppResult<Option<Overwrite>> {
+    let old = match fs::read_to_string(config_dir().join(SETTINGS_CONFIG)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(AppError::err(err)),
+    };
+    let new = toml::to_string(settings).map_err(|err| AppError::err(err))?;
+    if new == old {
+        return Ok(None);
+    }
+
+    let (before, after) = (table_names(&old), table_names(&new));
+    Ok(Some(Overwrite {
+        added: after.difference(&before).cloned().collect(),
+        removed: before.difference(&after).cloned().collect(),
+        old,
+        new,
+    }))
+}
+
+/// Log format to start with, [`LogFormat::ENV`] over the settings' `log_format`. Runs
+/// before logging is set up, so it reads just that key and falls back to text quietly.
+pub fn parse_log_format() -> LogFormat {
+    let env = std::env::var(LogFormat::ENV).ok();
+    let settings = fs::read_to_string(config_dir().join(SETTINGS_CONFIG)).ok();
+    log_format(env.as_deref(), settings.as_deref())
+}
+
+fn log_format(env: Option<&str>, settings: Option<&str>) -> LogFormat {
+    env.and_then(LogFormat::parse)
+        .or_else(|| {
+            let settings = settings?.parse::<toml::Table>().ok()?;
+            LogFormat::parse(settings.get("log_format")?.as_str()?)
+        })
+        .unwrap_or_default()
+}
+
+/// View preferences of every remote, by remote name
+pub fn parse_all_view_prefs() -> AppResult<BTreeMap<String, ViewPrefs>> {
+    let config_path = config_dir().join(VIEWS_CONFIG);
+    if !config_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = fs::read_to_string(&config_path).map_err(|err| AppError::err(err))?;
+    toml::from_str(&content).map_err(|err| AppError::err(err))
+}
+
+/// View preferences of the remote, defaults when none were saved
+pub fn parse_view_prefs(remote_name: &str) -> AppResult<ViewPrefs> {
+    let prefs = parse_all_view_prefs()?
+        .remove(remote_name)
+        .map(ViewPrefs::migrate)
+        .unwrap_or_default();
+    Ok(prefs)
+}
+
+pub fn save_view_prefs(remote_name: &str, prefs: &ViewPrefs) -> AppResult<()> {
+    merge_view_prefs(BTreeMap::from([(remote_name.to_owned(), prefs.clone())]))?;
+
+    tracing::info!("Successfully saved view preferences for {remote_name}");
+    Ok(())
+}
+
+/// Saves the view preferences of the remotes in `prefs`, other remotes keep theirs
+pub fn merge_view_prefs(prefs: BTreeMap<String, ViewPrefs>) -> AppResult<()> {
+    let config_dir = config_dir();
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).map_err(|err| AppError::err(err))?;
+    }
+
+    let mut all_prefs = parse_all_view_prefs().unwrap_or_else(|err| {
+        tracing::warn!("Overwriting unreadable view preferences: {}", err.message);
+        BTreeMap::new()
+    });
+    all_prefs.extend(prefs);
+
+    let content = toml::to_string(&all_prefs).map_err(|err| AppError::err(err))?;
+    fs::write(config_dir.join(VIEWS_CONFIG), content).map_err(|err| AppError::err(err))?;
+    Ok(())
+}
+
+/// Writes settings to carry over to another machine, see [`import_settings`]
+pub fn export_settings(path: &Path, export: &SettingsExport) -> AppResult<()> {
+    let content = toml::to_string(export).map_err(|err| AppError::err(err))?;
+    fs::write(path, content).map_err(|err| AppError::err(err))?;
+
+    tracing::info!("Exported settings to {}", path.display());
+    Ok(())
+}
+
+/// Reads settings written by [`export_settings`], possibly by another version
+pub fn import_settings(path: &Path) -> AppResult<SettingsExport> {
+    let content = fs::read_to_string(path).map_err(|err| AppError::err(err))?;
+    parse_settings_export(&content)
+}
+
+fn parse_settings_export(content: &str) -> AppResult<SettingsExport> {
+    let mut export = toml::from_str::<SettingsExport>(content).map_err(|err| AppError::err(err))?;
+    if export.version > SETTINGS_EXPORT_VERSION {
+        tracing::warn!(
+            "Importing settings of a newer version ({}), unknown ones are left out",
+            export.version
+        );
+    }
+    export.settings.validate()?;
+
+    export.version = SETTINGS_EXPORT_VERSION;
+    export.view_prefs = export
+        .view_prefs
+        .into_iter()
+        .map(|(remote_name, prefs)| (remote_name, prefs.migrate()))
+        .collect();
+    Ok(export)
+}
+
+/// Whether `language` names a catalog file without leaving the locales dir
+pub fn is_valid_language(language: &str) -> bool {
+    !language.is_empty()
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Catalog of `language` from the locales dir, `None` when there's no such file
+pub fn parse_translation(language: &str) -> AppResult<Option<String>> {
+    if !is_valid_language(language) {
+        return Err(AppError::message(format!(
+            "Invalid language \"{language}\""
+        )));
+    }
+
+    let path = config_dir()
+        .join(LOCALES_DIR)
+        .join(format!("{language}.toml"));
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|err| AppError::err(err))?;
+    Ok(Some(content))
+}
+
+/// Version launched before this one, `None` on the first launch
+pub fn parse_last_seen_version() -> AppResult<Option<String>> {
+    let path = config_dir().join(LAST_VERSION_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|err| AppError::err(err))?;
+    Ok(Some(content.trim().to_owned()))
+}
+
+pub fn save_last_seen_version(version: &str) -> AppResult<()> {
+    let config_dir = config_dir();
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).map_err(|err| AppError::err(err))?;
+    }
+
+    fs::write(config_dir.join(LAST_VERSION_FILE), version).map_err(|err| AppError::err(err))?;
+    Ok(())
+}
+
+pub fn parse_dismissed_update() -> AppResult<Option<String>> {
+    let path = config_dir().join(DISMISSED_UPDATE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|err| AppError::err(err))?;
+    Ok(Some(content.trim().to_owned()))
+}
+
+pub fn save_dismissed_update(version: &str) -> AppResult<()> {
+    let config_dir = config_dir();
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir).map_err(|err| AppError::err(err))?;
+    }
+
+    fs::write(config_dir.join(DISMISSED_UPDATE_FILE), version).map_err(|err| AppError::err(err))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_snippets_without_secrets() {
+        let snippet = r#"
+            [shared]
+            access_key_id = "AKIA123"
+            secret_access_key = "hunter2"
+            region = "eu-west-1"
+            endpoint = "https://s3.eu-west-1.amazonaws.com"
+            bucket_name = "team-bucket"
+            root_prefix = "reports"
+
+            [shared.headers]
+            X-Team = "data"
+            X-Token = { value = "t0ken", secret = true }
+        "#;
+        let (name, config) = parse_remote_snippet(snippet).unwrap();
+        assert_eq!(name, "shared");
+
+        let shared = remote_snippet(&name, &config);
+        assert!(!shared.contains("hunter2"));
+        assert!(!shared.contains("t0ken"));
+
+        let (_, RemoteConfig::S3(mut temporary)) = parse_remote_snippet(&shared).unwrap();
+        temporary.session_token = Some("FwoGZXIvYXdzEB4a".into());
+        let shared = remote_snippet(&name, &RemoteConfig::S3(temporary));
+        assert!(!shared.contains("FwoGZXIvYXdzEB4a"));
+
+        let (name, RemoteConfig::S3(config)) = parse_remote_snippet(&shared).unwrap();
+        assert_eq!(name, "shared");
+        assert_eq!(config.secret_access_key.as_ref(), "");
+        assert_eq!(config.bucket_name.as_ref(), "team-bucket");
+        assert_eq!(config.root_prefix.as_deref(), Some("reports/"));
+        assert_eq!(config.headers.len(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_snippets() {
+        assert!(parse_remote_snippet("[shared").is_err());
+        assert!(parse_remote_snippet("").is_err());
+        assert!(parse_remote_snippet("shared = 1").is_err());
+        assert!(parse_remote_snippet("[a]\n[b]").is_err());
+    }
+
+    #[test]
+    fn names_missing_fields() {
+        let err = parse_remote_snippet(
+            r#"
+            [shared]
+            access_key_id = "AKIA123"
+            region = "auto"
+            bucket_name = "team-bucket"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.message,
+            "Missing or invalid endpoint for remote: shared"
+        );
+    }
+
+    #[test]
+    fn reads_timeouts() {
+        let snippet = |timeout: &str| {
+            parse_remote_snippet(&format!(
+                r#"
+                [slow]
+                access_key_id = "AKIA123"
+                region = "auto"
+                endpoint = "https://s3.example.com"
+                bucket_name = "team-bucket"
+                timeout_secs = {timeout}
+                "#
+            ))
+        };
+        let (_, RemoteConfig::S3(config)) = snippet("5").unwrap();
+        assert_eq!(config.timeout_secs, Some(5));
+        assert_eq!(
+            remote_table(&RemoteConfig::S3(config)).get("timeout_secs"),
+            Some(&toml::Value::Integer(5))
+        );
+
+        let err = snippet("0").unwrap_err();
+        assert_eq!(err.message, "Invalid timeout_secs for remote: slow");
+        assert!(snippet("\"soon\"").is_err());
+    }
+
+    #[test]
+    fn keeps_session_tokens_of_temporary_credentials() {
+        let (_, RemoteConfig::S3(mut config)) = remote("temp", "bucket");
+        assert_eq!(config.session_token, None);
+
+        config.session_token = Some("FwoGZXIvYXdzEB4a".into());
+        let table = remote_table(&RemoteConfig::S3(config));
+        let RemoteConfig::S3(config) = parse_remote("temp", &table).unwrap();
+        assert_eq!(config.session_token.as_deref(), Some("FwoGZXIvYXdzEB4a"));
+
+        // a cleared token is as good as none
+        let mut table = table;
+        table.insert("session_token".to_owned(), toml::Value::String(" ".into()));
+        let RemoteConfig::S3(config) = parse_remote("temp", &table).unwrap();
+        assert_eq!(config.session_token, None);
+    }
+
+    fn remote(name: &str, bucket: &str) -> (Arc<str>, RemoteConfig) {
+        let (_, config) = parse_remote_snippet(&format!(
+            r#"
+            [{name}]
+            access_key_id = "AKIA123"
+            secret_access_key = "hunter2"
+            region = "auto"
+            endpoint = "https://s3.example.com"
+            bucket_name = "{bucket}"
+            "#
+        ))
+        .unwrap();
+        (name.into(), config)
+    }
+
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("balti-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn merges_changes_of_other_instances() {
+        let table = |bucket: &str| remote_table(&remote("r", bucket).1);
+        let base = BTreeMap::from([
+            ("kept".to_owned(), table("a")),
+            ("ours".to_owned(), table("a")),
+            ("theirs".to_owned(), table("a")),
+            ("both".to_owned(), table("a")),
+            ("removed".to_owned(), table("a")),
+        ]);
+        let mut ours = base.clone();
+        ours.insert("ours".to_owned(), table("b"));
+        ours.insert("both".to_owned(), table("b"));
+        ours.insert("added".to_owned(), table("b"));
+        let mut theirs = base.clone();
+        theirs.insert("theirs".to_owned(), table("c"));
+        theirs.insert("both".to_owned(), table("c"));
+        theirs.remove("removed");
+
+        let merge = merge_remotes(&base, &ours, &theirs);
+        assert_eq!(
+            merge.tables.keys().collect::<Vec<_>>(),
+            ["added", "both", "kept", "ours", "theirs"]
+        );
+        assert_eq!(merge.tables["ours"], table("b"));
+        assert_eq!(merge.tables["theirs"], table("c"));
+        assert_eq!(merge.tables["both"], table("b"));
+        assert_eq!(merge.external, ["removed", "theirs"]);
+        assert_eq!(merge.conflicts, ["both"]);
+    }
+
+    #[test]
+    fn concurrent_saves_keep_both_remotes() {
+        let dir = temp_config_dir("concurrent-saves");
+        let (_, mut base) = parse_remotes_in(&dir).unwrap();
+        save_remotes_in(&dir, &BTreeMap::from([remote("shared", "a")]), &mut base).unwrap();
+
+        // both instances read the config, then save their own new remote
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let instances = ["first", "second"].map(|name| {
+            let (dir, barrier) = (dir.clone(), barrier.clone());
+            std::thread::spawn(move || {
+                let (remotes, mut base) = parse_remotes_in(&dir).unwrap();
+                let mut remotes = remotes
+                    .into_iter()
+                    .map(|(name, config)| (Arc::from(name), config))
+                    .collect::<BTreeMap<_, _>>();
+                remotes.extend([remote(name, name)]);
+                barrier.wait();
+                save_remotes_in(&dir, &remotes, &mut base).unwrap()
+            })
+        });
+        let saved = instances.map(|instance| instance.join().unwrap());
+
+        // whichever saved second merged in the other's remote
+        let merged = saved
+            .iter()
+            .flat_map(|saved| saved.external.iter())
+            .map(|(name, config)| (name.as_str(), config.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(merged.len(), 1);
+        assert!(matches!(merged[0], ("first" | "second", true)));
+        assert!(saved.iter().all(|saved| saved.conflicts.is_empty()));
+
+        let (remotes, _) = parse_remotes_in(&dir).unwrap();
+        let mut names = remotes.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["first", "second", "shared"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merges_edits_of_hand_written_remotes() {
+        let dir = temp_config_dir("hand-written");
+        fs::create_dir_all(&dir).unwrap();
+        // the blank token is left out once saved
+        fs::write(
+            dir.join(REMOTES_CONFIG),
+            r#"
+            [hand]
+            access_key_id = "AKIA123"
+            secret_access_key = "hunter2"
+            session_token = " "
+            region = "auto"
+            endpoint = "https://s3.example.com"
+            bucket_name = "a"
+            "#,
+        )
+        .unwrap();
+        let (remotes, mut first) = parse_remotes_in(&dir).unwrap();
+        let remotes = remotes
+            .into_iter()
+            .map(|(name, config)| (Arc::from(name), config))
+            .collect::<BTreeMap<_, _>>();
+
+        let (_, mut second) = parse_remotes_in(&dir).unwrap();
+        save_remotes_in(&dir, &BTreeMap::from([remote("hand", "b")]), &mut second).unwrap();
+
+        let saved = save_remotes_in(&dir, &remotes, &mut first).unwrap();
+        assert!(saved.conflicts.is_empty());
+        assert_eq!(saved.external.len(), 1);
+        assert_eq!(saved.external[0].0, "hand");
+
+        let (remotes, _) = parse_remotes_in(&dir).unwrap();
+        let RemoteConfig::S3(hand) = &remotes["hand"];
+        assert_eq!(hand.bucket_name.as_ref(), "b");
+        assert!(!dir.join(REMOTES_CONFIG).with_extension("tmp").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_conflicting_edits() {
+        let dir = temp_config_dir("conflicting-edits");
+        let (_, mut first) = parse_remotes_in(&dir).unwrap();
+        save_remotes_in(&dir, &BTreeMap::from([remote("shared", "a")]), &mut first).unwrap();
+        let (_, mut second) = parse_remotes_in(&dir).unwrap();
+
+        save_remotes_in(&dir, &BTreeMap::from([remote("shared", "b")]), &mut first).unwrap();
+        let saved =
+            save_remotes_in(&dir, &BTreeMap::from([remote("shared", "c")]), &mut second).unwrap();
+        assert_eq!(saved.conflicts, ["shared"]);
+
+        let (remotes, _) = parse_remotes_in(&dir).unwrap();
+        let RemoteConfig::S3(shared) = &remotes["shared"];
+        assert_eq!(shared.bucket_name.as_ref(), "c");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reviews_saves_that_overwrite_edits() {
+        let dir = temp_config_dir("review-overwrite");
+        let (_, mut first) = parse_remotes_in(&dir).unwrap();
+        save_remotes_in(&dir, &BTreeMap::from([remote("shared", "a")]), &mut first).unwrap();
+        let (_, mut second) = parse_remotes_in(&dir).unwrap();
+
+        // nothing on disk changed, or only other remotes did
+        let ours = BTreeMap::from([remote("shared", "b")]);
+        assert!(
+            review_remotes_in(&dir, &ours, &first, false)
+                .unwrap()
+                .is_none()
+        );
+        let theirs = BTreeMap::from([remote("shared", "a"), remote("other", "a")]);
+        save_remotes_in(&dir, &theirs, &mut second).unwrap();
+        assert!(
+            review_remotes_in(&dir, &ours, &first, false)
+                .unwrap()
+                .is_none()
+        );
+
+        // the other instance's edit of the same remote would be lost
+        let theirs = BTreeMap::from([remote("shared", "c"), remote("other", "a")]);
+        save_remotes_in(&dir, &theirs, &mut second).unwrap();
+        let overwrite = review_remotes_in(&dir, &ours, &first, false)
+            .unwrap()
+            .unwrap();
+        assert!(overwrite.old.contains("bucket_name = \"c\""));
+        assert!(overwrite.new.contains("bucket_name = \"b\""));
+        assert!(overwrite.new.contains("[other]"));
+        assert!(overwrite.added.is_empty() && overwrite.removed.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replaces_configs_that_dont_parse() {
+        let dir = temp_config_dir("replace-broken");
+        fs::create_dir_all(&dir).unwrap();
+        let broken = "[legacy]\nbucket_name = \"old\n\n[\"team.logs\"]\nregion = 1\n";
+        fs::write(dir.join(REMOTES_CONFIG), broken).unwrap();
+        assert!(parse_remotes_in(&dir).is_err());
+
+        let ours = BTreeMap::from([remote("fresh", "a")]);
+        let mut base = RemotesBase::default();
+        let overwrite = review_remotes_in(&dir, &ours, &base, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(overwrite.old, broken);
+        assert_eq!(overwrite.added, ["fresh"]);
+        assert_eq!(overwrite.removed, ["legacy", "team.logs"]);
+
+        // reviewing leaves the file alone
+        assert_eq!(
+            fs::read_to_string(dir.join(REMOTES_CONFIG)).unwrap(),
+            broken
+        );
+
+        replace_remotes_in(&dir, &ours, &mut base).unwrap();
+        let (remotes, _) = parse_remotes_in(&dir).unwrap();
+        assert_eq!(remotes.keys().collect::<Vec<_>>(), ["fresh"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_table_names_from_headers() {
+        let names = table_names(
+            "[prod]\n[prod.headers]\n[ \"a.b\" ]\n[[array]]\nkey = \"[x]\"\n# [comment]\n",
+        );
+        assert_eq!(names.into_iter().collect::<Vec<_>>(), ["a.b", "prod"]);
+    }
+
+    #[test]
+    fn saves_the_kind_of_remotes() {
+        let dir = temp_config_dir("remote-kinds");
+        let (_, mut base) = parse_remotes_in(&dir).unwrap();
+        save_remotes_in(&dir, &BTreeMap::from([remote("shared", "a")]), &mut base).unwrap();
+
+        let content = fs::read_to_string(dir.join(REMOTES_CONFIG)).unwrap();
+        let table = toml::from_str::<toml::Table>(&content).unwrap();
+        assert_eq!(table["shared"]["kind"].as_str(), Some("s3"));
+
+        let (remotes, _) = parse_remotes_in(&dir).unwrap();
+        let RemoteConfig::S3(shared) = &remotes["shared"];
+        assert_eq!(shared.bucket_name.as_ref(), "a");
+        assert_eq!(shared.secret_access_key.as_ref(), "hunter2");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrates_remotes_without_a_kind() {
+        let dir = temp_config_dir("remotes-without-kind");
+        fs::create_dir_all(&dir).unwrap();
+        let legacy = r#"
+            [legacy]
+            access_key_id = "AKIA123"
+            secret_access_key = "hunter2"
+            region = "auto"
+            endpoint = "https://s3.example.com"
+            bucket_name = "old-bucket"
+        "#;
+        fs::write(dir.join(REMOTES_CONFIG), legacy).unwrap();
+
+        let (remotes, mut base) = parse_remotes_in(&dir).unwrap();
+        let RemoteConfig::S3(legacy) = &remotes["legacy"];
+        assert_eq!(legacy.bucket_name.as_ref(), "old-bucket");
+
+        // the next save writes the kind
+        let remotes = remotes
+            .into_iter()
+            .map(|(name, config)| (Arc::from(name), config))
+            .collect::<BTreeMap<_, _>>();
+        let saved = save_remotes_in(&dir, &remotes, &mut base).unwrap();
+        assert!(saved.external.is_empty() && saved.conflicts.is_empty());
+
+        let content = fs::read_to_string(dir.join(REMOTES_CONFIG)).unwrap();
+        let table = toml::from_str::<toml::Table>(&content).unwrap();
+        assert_eq!(table["legacy"]["kind"].as_str(), Some("s3"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn round_trips_upload_defaults() {
+        // remotes saved before them read with the provider's defaults, and save as before
+        let (_, plain) = remote("plain", "a");
+        let RemoteConfig::S3(defaults) = &plain;
+        assert!(defaults.upload_defaults.is_default());
+        assert!(!remote_table(&plain).contains_key("upload_defaults"));
+
+        let (name, config) = parse_remote_snippet(
+            r#"
+            [archive]
+            access_key_id = "AKIA123"
+            secret_access_key = "hunter2"
+            region = "auto"
+            endpoint = "https://s3.example.com"
+            bucket_name = "archive"
+
+            [archive.upload_defaults]
+            storage_class = "STANDARD_IA"
+            encryption = "aws:kms"
+            "#,
+        )
+        .unwrap();
+        let RemoteConfig::S3(archive) = &config;
+        assert_eq!(
+            archive.upload_defaults.storage_class.as_deref(),
+            Some("STANDARD_IA")
+        );
+        assert!(archive.upload_defaults.acl.is_none());
+        assert!(!archive.upload_defaults.detect_content_type);
+
+        let (_, RemoteConfig::S3(reparsed)) =
+            parse_remote_snippet(&remote_snippet(&name, &config)).unwrap();
+        assert_eq!(reparsed.upload_defaults, archive.upload_defaults);
+
+        let err = parse_remote_snippet(
+            r#"
+            [cdn]
+            access_key_id = "AKIA123"
+            secret_access_key = "hunter2"
+            region = "auto"
+            endpoint = "https://s3.example.com"
+            bucket_name = "cdn"
+            upload_defaults = { acl = "everyone" }
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.message.ends_with("for remote: cdn"));
+    }
+
+    #[test]
+    fn rejects_unknown_remote_kinds() {
+        let err = parse_remote_snippet(
+            r#"
+            [shared]
+            kind = "ftp"
+            endpoint = "ftp://example.com"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(err.message, "Unknown kind \"ftp\" for remote: shared");
+        assert!(parse_remote_snippet("[shared]\nkind = 3").is_err());
+    }
+
+    #[test]
+    fn round_trips_exported_settings() {
+        let mut export = SettingsExport::default();
+        export.settings.page_size = 250;
+        export.settings.ui_scale = 1.2;
+        export.settings.language = Some("pt-BR".into());
+        export.view_prefs.insert(
+            "media".into(),
+            ViewPrefs {
+                show_thumbnails: true,
+                auto_refresh: Some(30),
+                ..Default::default()
+            },
+        );
+
+        let content = toml::to_string(&export).unwrap();
+        let imported = parse_settings_export(&content).unwrap();
+        assert_eq!(imported.settings.page_size, 250);
+        assert_eq!(imported.settings.ui_scale, 1.2);
+        assert_eq!(imported.settings.language.as_deref(), Some("pt-BR"));
+        assert_eq!(imported.view_prefs, export.view_prefs);
+        assert_eq!(toml::to_string(&imported).unwrap(), content);
+    }
+
+    #[test]
+    fn imports_settings_of_other_versions() {
+        let imported = parse_settings_export(
+            r#"
+            version = 7
+            themes = ["dark"]
+
+            [settings]
+            page_size = 100
+            download_dir = "~/Downloads"
+
+            [view_prefs.logs]
+            version = 0
+            show_folder_counts = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(imported.version, SETTINGS_EXPORT_VERSION);
+        assert_eq!(imported.settings.page_size, 100);
+        assert_eq!(imported.settings.ui_scale, 1.0);
+        let logs = &imported.view_prefs["logs"];
+        assert!(logs.show_folder_counts);
+        assert_eq!(logs.version, crate::settings::VIEW_PREFS_VERSION);
+
+        // older files carry just the settings
+        let imported = parse_settings_export("[settings]\nreduce_motion = true").unwrap();
+        assert!(imported.settings.reduce_motion);
+        assert!(imported.view_prefs.is_empty());
+    }
+
+    #[test]
+    fn reads_the_log_format_early() {
+        let settings = "page_size = 100\nlog_format = \"json\"\n\n[proxy]\nurl = \"x\"";
+        assert_eq!(log_format(None, Some(settings)), LogFormat::Json);
+        assert_eq!(log_format(Some("text"), Some(settings)), LogFormat::Text);
+        assert_eq!(log_format(Some("JSON"), None), LogFormat::Json);
+
+        // anything unreadable leaves the default
+        assert_eq!(log_format(Some("xml"), None), LogFormat::Text);
+        assert_eq!(log_format(None, Some("log_format = [")), LogFormat::Text);
+        assert_eq!(log_format(None, Some("log_format = 1")), LogFormat::Text);
+    }
+
+    #[test]
+    fn rejects_invalid_imported_settings() {
+        assert!(parse_settings_export("[settings]\npage_size = 0").is_err());
+        assert!(parse_settings_export("[settings]\nui_scale = 9.0").is_err());
+        assert!(parse_settings_export("[settings]\nlanguage = \"../../x\"").is_err());
+        assert!(parse_settings_export("settings = 3").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::{collections::HashSet, sync::Arc};
+
+use balti_err::{AppError, ErrorKind};
+use balti_s3::{CredentialsSource, S3Config};
+use chrono::{DateTime, TimeDelta, Utc};
+use gpui::*;
+
+use crate::strings::t;
+
+/// How long before expiry the app warns about temporary credentials
+pub const EXPIRY_WARNING: TimeDelta = TimeDelta::minutes(5);
+
+pub fn init(cx: &mut App) {
+    let expired = cx.new(|_cx| ExpiredRemotes::default());
+    cx.set_global(GlobalExpiredRemotes(expired));
+}
+
+struct GlobalExpiredRemotes(Entity<ExpiredRemotes>);
+
+impl Global for GlobalExpiredRemotes {}
+
+/// Remotes whose credentials ran out, by their expiry or as a request found out.
+/// Observe [`ExpiredRemotes::entity`] to follow them.
+#[derive(Default)]
+pub struct ExpiredRemotes {
+    names: HashSet<Arc<str>>,
+}
+
+impl ExpiredRemotes {
+    pub fn entity(cx: &App) -> Entity<Self> {
+        cx.global::<GlobalExpiredRemotes>().0.clone()
+    }
+
+    pub fn contains(&self, remote: &str) -> bool {
+        self.names.contains(remote)
+    }
+
+    pub fn mark(remote: Arc<str>, cx: &mut App) {
+        Self::entity(cx).update(cx, |this, cx| {
+            if this.names.insert(remote) {
+                cx.notify();
+            }
+        });
+    }
+
+    /// Once the remote got new credentials
+    pub fn clear(remote: &str, cx: &mut App) {
+        Self::entity(cx).update(cx, |this, cx| {
+            if this.names.remove(remote) {
+                cx.notify();
+            }
+        });
+    }
+
+    /// Marks the remote when `err` says its credentials expired
+    pub fn note_error(remote: &Arc<str>, err: &AppError, cx: &mut App) {
+        if err.kind == ErrorKind::ExpiredCredentials {
+            Self::mark(remote.clone(), cx);
+        }
+    }
+}
+
+/// Like "Credentials expire in 1h 05m", or that they expired once `expiry` passed
+pub fn countdown(expiry: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let left = expiry - now;
+    if left <= TimeDelta::zero() {
+        return t!(
+            "credentials.expired_at",
+            at = expiry.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+
+    let minutes = (left.num_seconds() + 59) / 60;
+    let left = match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes:02}m"),
+    };
+    t!("credentials.expire_in", left = left)
+}
+
+/// Where the remote's credentials come from, with when they expire for temporary ones
+pub fn describe(config: &S3Config, now: DateTime<Utc>) -> String {
+    match (config.credentials_source(), config.credentials_expiry) {
+        (CredentialsSource::Temporary, Some(expiry)) => {
+            t!("credentials.temporary", state = countdown(expiry, now))
+        }
+        _ => t!("credentials.static"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_down_to_expiry() {
+        let expiry = DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(
+            countdown(expiry, expiry - TimeDelta::seconds(30)),
+            "Credentials expire in 1m"
+        );
+        assert_eq!(
+            countdown(expiry, expiry - TimeDelta::minutes(65)),
+            "Credentials expire in 1h 05m"
+        );
+        assert_eq!(
+            countdown(expiry, expiry),
+            "Credentials expired at 2026-01-01 12:00 UTC"
+        );
+    }
+}
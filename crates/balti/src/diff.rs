@@ -0,0 +1,197 @@
+/// Texts whose changed lines span more cells than this get diffed as one block, the
+/// table would take too much memory otherwise
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// How a line differs between two texts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: Change,
+    pub text: String,
+}
+
+/// Row of a diff to show, unchanged lines away from any change fold into their count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffRow {
+    Line(DiffLine),
+    Folded(usize),
+}
+
+/// Text about to replace a file, with the names of the entries it adds and removes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overwrite {
+    pub old: String,
+    pub new: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Lines of `old` and `new` as a diff from one to the other, by their longest common
+/// subsequence. Removed lines come before the added ones they're replaced with.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let line = |change, text: &str| DiffLine {
+        change,
+        text: text.to_owned(),
+    };
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|text| line(Change::Same, text))
+        .collect::<Vec<_>>();
+    let (n, m) = (a.len(), b.len());
+    if n * m > MAX_DIFF_CELLS {
+        lines.extend(a.iter().map(|text| line(Change::Removed, text)));
+        lines.extend(b.iter().map(|text| line(Change::Added, text)));
+    } else {
+        // common lines of `a[i..]` and `b[j..]`
+        let at = |i: usize, j: usize| i * (m + 1) + j;
+        let mut common = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                common[at(i, j)] = if a[i] == b[j] {
+                    common[at(i + 1, j + 1)] + 1
+                } else {
+                    common[at(i + 1, j)].max(common[at(i, j + 1)])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if a[i] == b[j] {
+                lines.push(line(Change::Same, a[i]));
+                i += 1;
+                j += 1;
+            } else if common[at(i + 1, j)] >= common[at(i, j + 1)] {
+                lines.push(line(Change::Removed, a[i]));
+                i += 1;
+            } else {
+                lines.push(line(Change::Added, b[j]));
+                j += 1;
+            }
+        }
+        lines.extend(a[i..].iter().map(|text| line(Change::Removed, text)));
+        lines.extend(b[j..].iter().map(|text| line(Change::Added, text)));
+    }
+
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|text| line(Change::Same, text)),
+    );
+    lines
+}
+
+/// Keeps `context` unchanged lines around each change, folding the rest
+pub fn fold(lines: Vec<DiffLine>, context: usize) -> Vec<DiffRow> {
+    let changed = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.change != Change::Same)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let near_change = |i: usize| {
+        let first = changed.partition_point(|&changed| changed + context < i);
+        changed
+            .get(first)
+            .is_some_and(|&changed| changed <= i + context)
+    };
+
+    let mut rows = Vec::new();
+    let mut folded = 0;
+    for (i, line) in lines.into_iter().enumerate() {
+        if line.change == Change::Same && !near_change(i) {
+            folded += 1;
+            continue;
+        }
+        if folded > 0 {
+            rows.push(DiffRow::Folded(folded));
+            folded = 0;
+        }
+        rows.push(DiffRow::Line(line));
+    }
+    if folded > 0 {
+        rows.push(DiffRow::Folded(folded));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(old: &str, new: &str) -> Vec<(Change, String)> {
+        diff_lines(old, new)
+            .into_iter()
+            .map(|line| (line.change, line.text))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_changed_lines() {
+        assert!(
+            changes("a\nb\n", "a\nb\n")
+                .iter()
+                .all(|(change, _)| *change == Change::Same)
+        );
+
+        let diff = changes(
+            "[prod]\nbucket = \"a\"\n[old]\n",
+            "[prod]\nbucket = \"b\"\n[new]\n",
+        );
+        assert_eq!(
+            diff,
+            [
+                (Change::Same, "[prod]".to_owned()),
+                (Change::Removed, "bucket = \"a\"".to_owned()),
+                (Change::Removed, "[old]".to_owned()),
+                (Change::Added, "bucket = \"b\"".to_owned()),
+                (Change::Added, "[new]".to_owned()),
+            ]
+        );
+
+        // a line in between is kept, not removed and added again
+        let diff = changes("a\nx\nb\n", "c\nx\nd\n");
+        assert_eq!(diff[2], (Change::Same, "x".to_owned()));
+        assert_eq!(diff.len(), 5);
+
+        assert_eq!(changes("", "a"), [(Change::Added, "a".to_owned())]);
+        assert_eq!(changes("a", ""), [(Change::Removed, "a".to_owned())]);
+    }
+
+    #[test]
+    fn folds_lines_away_from_changes() {
+        let old = (0..10).map(|i| format!("{i}\n")).collect::<String>();
+        let new = old.replace("5\n", "five\n");
+
+        let rows = fold(diff_lines(&old, &new), 1);
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0], DiffRow::Folded(4));
+        assert!(matches!(&rows[1], DiffRow::Line(line) if line.text == "4"));
+        assert!(matches!(&rows[4], DiffRow::Line(line) if line.text == "6"));
+        assert_eq!(rows[5], DiffRow::Folded(3));
+
+        // nothing to fold without unchanged lines
+        assert_eq!(fold(diff_lines("a", "b"), 3).len(), 2);
+    }
+}
@@ -0,0 +1,44 @@
+/// Tells the latest of a series of requests apart from the ones it superseded, so a
+/// late answer to an old request gets dropped instead of overwriting a newer one
+#[derive(Debug, Default)]
+pub struct Generations(u64);
+
+/// Ticket of a single request, see [`Generations::is_current`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generation(u64);
+
+impl Generations {
+    /// Starts a request, the ones started before are stale from now on
+    pub fn start(&mut self) -> Generation {
+        self.0 += 1;
+        Generation(self.0)
+    }
+
+    /// Makes every request in flight stale, like once their view is left
+    pub fn invalidate(&mut self) {
+        self.0 += 1;
+    }
+
+    pub fn is_current(&self, generation: Generation) -> bool {
+        self.0 == generation.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_latest_is_current() {
+        let mut generations = Generations::default();
+        let first = generations.start();
+        assert!(generations.is_current(first));
+
+        let second = generations.start();
+        assert!(!generations.is_current(first));
+        assert!(generations.is_current(second));
+
+        generations.invalidate();
+        assert!(!generations.is_current(second));
+    }
+}
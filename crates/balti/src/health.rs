@@ -0,0 +1,288 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use balti_err::{AppError, ErrorKind};
+use balti_s3::S3Remote;
+use gpui::{App, AppContext, Entity, EventEmitter, Global};
+
+use crate::rt;
+
+/// Unreachable failures in a row after which a remote counts as degraded
+const DEGRADED_AFTER: u32 = 3;
+
+/// Background probes of a degraded remote start this far apart, doubling up to the max
+const FIRST_PROBE: Duration = Duration::from_secs(5);
+const MAX_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Wait between retries the user starts on a degraded remote
+const RETRY_COOLDOWN: Duration = Duration::from_secs(10);
+
+pub fn init(cx: &mut App) {
+    let health = cx.new(|_cx| RemoteHealth::default());
+    cx.set_global(GlobalRemoteHealth(health));
+}
+
+struct GlobalRemoteHealth(Entity<RemoteHealth>);
+
+impl Global for GlobalRemoteHealth {}
+
+/// Emitted as a remote's endpoint stops answering and once it answers again
+pub enum HealthEvent {
+    Degraded(Arc<str>),
+    Recovered(Arc<str>),
+}
+
+/// Whether the remotes' endpoints answer lately. Remotes whose requests keep failing
+/// to reach them are degraded: background work pauses and retries back off until a
+/// probe gets through. Observe [`RemoteHealth::entity`] to follow it.
+#[derive(Default)]
+pub struct RemoteHealth {
+    remotes: HashMap<Arc<str>, Health>,
+}
+
+impl EventEmitter<HealthEvent> for RemoteHealth {}
+
+impl RemoteHealth {
+    pub fn entity(cx: &App) -> Entity<Self> {
+        cx.global::<GlobalRemoteHealth>().0.clone()
+    }
+
+    pub fn is_degraded(remote: &str, cx: &App) -> bool {
+        Self::entity(cx)
+            .read(cx)
+            .remotes
+            .get(remote)
+            .is_some_and(Health::is_degraded)
+    }
+
+    /// Time until the user may retry the degraded remote, `None` once they may
+    pub fn retry_in(remote: &str, cx: &App) -> Option<Duration> {
+        Self::entity(cx)
+            .read(cx)
+            .remotes
+            .get(remote)?
+            .retry_in(Instant::now())
+    }
+
+    /// Folds in a failed request, only unreachable endpoints count against the remote
+    pub fn note_error(remote: &Arc<str>, err: &AppError, cx: &mut App) {
+        Self::note(remote, err.kind != ErrorKind::Unreachable, cx);
+    }
+
+    pub fn note_success(remote: &Arc<str>, cx: &mut App) {
+        Self::note(remote, true, cx);
+    }
+
+    fn note(remote: &Arc<str>, answered: bool, cx: &mut App) {
+        Self::entity(cx).update(cx, |this, cx| {
+            let health = this.remotes.entry(remote.clone()).or_default();
+            if answered && health.succeed() {
+                cx.emit(HealthEvent::Recovered(remote.clone()));
+                cx.notify();
+            } else if !answered && health.fail(Instant::now()) {
+                cx.emit(HealthEvent::Degraded(remote.clone()));
+                cx.notify();
+            }
+        });
+    }
+
+    /// The user retried the degraded remote, the next retry waits out the cooldown
+    pub fn note_retry(remote: &str, cx: &mut App) {
+        Self::entity(cx).update(cx, |this, cx| {
+            if let Some(health) = this.remotes.get_mut(remote) {
+                health.note_retry(Instant::now());
+                cx.notify();
+            }
+        });
+    }
+
+    /// Once the remote got replaced or deleted
+    pub fn forget(remote: &str, cx: &mut App) {
+        Self::entity(cx).update(cx, |this, cx| {
+            if this.remotes.remove(remote).is_some() {
+                cx.notify();
+            }
+        });
+    }
+
+    /// Probes the degraded ones of `remotes` whose backoff ran out, and re-renders the
+    /// retry countdowns. Called on a timer.
+    pub fn probe_degraded(remotes: &[S3Remote], cx: &mut App) {
+        let entity = Self::entity(cx);
+        let now = Instant::now();
+        let due = entity.update(cx, |this, cx| {
+            let due = remotes
+                .iter()
+                .filter(|remote| {
+                    this.remotes
+                        .get_mut(&remote.remote_name)
+                        .is_some_and(|health| health.take_probe(now))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if this.remotes.values().any(Health::is_degraded) {
+                cx.notify();
+            }
+            due
+        });
+
+        for remote in due {
+            let remote_name = remote.remote_name.clone();
+            let task = rt::spawn(cx, async move {
+                let root = remote.root();
+                balti_s3::list_objects(remote, root.trim_start_matches('/'), 1, None).await
+            });
+            cx.spawn(async move |cx| {
+                let result = task.await.flatten();
+                let _ = cx.update(|cx| match result {
+                    Ok(_) => Self::note_success(&remote_name, cx),
+                    Err(err) => Self::note_error(&remote_name, &err, cx),
+                });
+            })
+            .detach();
+        }
+    }
+}
+
+/// Failures in a row of a single remote, and its backoff once degraded
+#[derive(Debug, Default)]
+struct Health {
+    failures: u32,
+    degraded: Option<Backoff>,
+}
+
+#[derive(Debug)]
+struct Backoff {
+    next_probe: Instant,
+    probe_interval: Duration,
+    retry_at: Instant,
+}
+
+impl Health {
+    fn is_degraded(&self) -> bool {
+        self.degraded.is_some()
+    }
+
+    /// Counts a failure, `true` when it degraded the remote
+    fn fail(&mut self, now: Instant) -> bool {
+        self.failures += 1;
+        if self.degraded.is_some() || self.failures < DEGRADED_AFTER {
+            return false;
+        }
+        self.degraded = Some(Backoff {
+            next_probe: now + FIRST_PROBE,
+            probe_interval: FIRST_PROBE,
+            retry_at: now + RETRY_COOLDOWN,
+        });
+        true
+    }
+
+    /// The endpoint answered, `true` when that recovered the remote
+    fn succeed(&mut self) -> bool {
+        self.failures = 0;
+        self.degraded.take().is_some()
+    }
+
+    fn retry_in(&self, now: Instant) -> Option<Duration> {
+        let left = self
+            .degraded
+            .as_ref()?
+            .retry_at
+            .checked_duration_since(now)?;
+        (!left.is_zero()).then_some(left)
+    }
+
+    fn note_retry(&mut self, now: Instant) {
+        if let Some(backoff) = self.degraded.as_mut() {
+            backoff.retry_at = now + RETRY_COOLDOWN;
+        }
+    }
+
+    /// Whether a probe is due, scheduling the next one further out if so
+    fn take_probe(&mut self, now: Instant) -> bool {
+        let Some(backoff) = self.degraded.as_mut() else {
+            return false;
+        };
+        if backoff.next_probe > now {
+            return false;
+        }
+        backoff.probe_interval = (backoff.probe_interval * 2).min(MAX_PROBE_INTERVAL);
+        backoff.next_probe = now + backoff.probe_interval;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrades_after_failures_in_a_row() {
+        let now = Instant::now();
+        let mut health = Health::default();
+
+        assert!(!health.fail(now));
+        assert!(!health.fail(now));
+        assert!(!health.succeed());
+        assert!(!health.fail(now));
+        assert!(!health.fail(now));
+        assert!(health.fail(now));
+        assert!(health.is_degraded());
+
+        // already degraded, no second transition
+        assert!(!health.fail(now));
+        assert!(health.succeed());
+        assert!(!health.is_degraded());
+        assert!(!health.fail(now));
+    }
+
+    #[test]
+    fn backs_off_probes() {
+        let now = Instant::now();
+        let mut health = Health::default();
+        assert!(!health.take_probe(now));
+        (0..DEGRADED_AFTER).for_each(|_| {
+            health.fail(now);
+        });
+
+        assert!(!health.take_probe(now));
+        assert!(health.take_probe(now + FIRST_PROBE));
+        assert!(!health.take_probe(now + FIRST_PROBE));
+
+        let mut at = now + FIRST_PROBE;
+        let mut intervals = Vec::new();
+        for _ in 0..5 {
+            let interval = health.degraded.as_ref().unwrap().probe_interval;
+            intervals.push(interval.as_secs());
+            at += interval;
+            assert!(health.take_probe(at));
+        }
+        assert_eq!(intervals, [10, 20, 40, 60, 60]);
+    }
+
+    #[test]
+    fn debounces_retries() {
+        let now = Instant::now();
+        let mut health = Health::default();
+        assert_eq!(health.retry_in(now), None);
+        (0..DEGRADED_AFTER).for_each(|_| {
+            health.fail(now);
+        });
+
+        assert_eq!(health.retry_in(now), Some(RETRY_COOLDOWN));
+        assert_eq!(health.retry_in(now + RETRY_COOLDOWN), None);
+
+        let later = now + Duration::from_secs(30);
+        health.note_retry(later);
+        assert_eq!(
+            health.retry_in(later + Duration::from_secs(4)),
+            Some(Duration::from_secs(6))
+        );
+
+        health.succeed();
+        assert_eq!(health.retry_in(later), None);
+    }
+}
@@ -0,0 +1,641 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use balti_err::{AppError, AppResult, BatchReport, ErrorKind, csv_field};
+use chrono::{DateTime, TimeDelta, Utc};
+use gpui::App;
+use serde::{Deserialize, Serialize};
+
+use crate::{coalesce::OperationId, config, rt, settings::Settings, util};
+
+/// Entries get appended here, a JSON line each
+const CURRENT_FILE: &str = "journal.jsonl";
+/// Rotated files are named `journal-<millis>.jsonl`, after their last entry
+const ROTATED_PREFIX: &str = "journal-";
+const EXT: &str = "jsonl";
+/// Size the current file grows to before it's rotated, it's rotated every day as well
+const ROTATE_BYTES: u64 = 8 * 1024 * 1024;
+
+const CSV_HEADER: &str = "timestamp,user,hostname,remote,from_remote,action,keys,failed_keys,to,outcome,error,request_ids,operation\n";
+
+/// Appends of this process go one at a time, so a rotation doesn't cut into one
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+/// Login and machine the entries of this process are recorded under
+static IDENTITY: OnceLock<(String, String)> = OnceLock::new();
+
+/// Drops journal files older than the retention setting
+pub fn init(cx: &mut App) {
+    let retention = Settings::get(cx).journal_retention();
+    rt::spawn_blocking(cx, move || {
+        prune(&config::journal_dir(), retention, Utc::now());
+    })
+    .detach();
+}
+
+/// Change an entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Delete,
+    Rename,
+    Copy,
+    Move,
+    Duplicate,
+    CreateFolder,
+    Upload,
+    Undo,
+}
+
+/// Every action, in the order filters offer them
+pub const ACTIONS: [Action; 8] = [
+    Action::Delete,
+    Action::Rename,
+    Action::Copy,
+    Action::Move,
+    Action::Duplicate,
+    Action::CreateFolder,
+    Action::Upload,
+    Action::Undo,
+];
+
+impl Action {
+    /// As it's written to the journal
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Delete => "delete",
+            Action::Rename => "rename",
+            Action::Copy => "copy",
+            Action::Move => "move",
+            Action::Duplicate => "duplicate",
+            Action::CreateFolder => "create_folder",
+            Action::Upload => "upload",
+            Action::Undo => "undo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Ok,
+    /// Went through for some keys only
+    Partial,
+    Failed,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::Partial => "partial",
+            Outcome::Failed => "failed",
+        }
+    }
+}
+
+/// Change made to a remote from the app, a line of the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    /// Login it was made under, filled in as the entry is written
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub remote: String,
+    /// Where copies and moves came from, when that's another remote
+    #[serde(default)]
+    pub from_remote: Option<String>,
+    pub action: Action,
+    /// Keys it went through for, the sources of copies and moves
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub failed_keys: Vec<String>,
+    /// Where the keys went, the new key or the folder pasted into
+    #[serde(default)]
+    pub to: Option<String>,
+    pub outcome: Outcome,
+    /// First error the provider gave
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Of the failed requests, for looking them up with the provider
+    #[serde(default)]
+    pub request_ids: Vec<String>,
+    /// Bulk operation it was part of, its log lines and toasts go by the same id
+    #[serde(default)]
+    pub operation: Option<String>,
+}
+
+impl JournalEntry {
+    /// Entry of `action` on `remote`, from the error of each key it was tried on. Keys
+    /// without one went through.
+    pub fn new<'a>(
+        remote: &str,
+        action: Action,
+        results: impl IntoIterator<Item = (&'a str, Option<&'a AppError>)>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let (mut keys, mut failed_keys) = (Vec::new(), Vec::new());
+        let (mut error, mut request_ids) = (None, Vec::<String>::new());
+        for (key, err) in results {
+            let Some(err) = err else {
+                keys.push(key.to_owned());
+                continue;
+            };
+            failed_keys.push(key.to_owned());
+            error.get_or_insert_with(|| err.message.clone());
+            if !err.req_id.is_empty() && !request_ids.contains(&err.req_id) {
+                request_ids.push(err.req_id.clone());
+            }
+        }
+
+        let outcome = match (keys.is_empty(), failed_keys.is_empty()) {
+            (_, true) => Outcome::Ok,
+            (true, false) => Outcome::Failed,
+            (false, false) => Outcome::Partial,
+        };
+        Self {
+            timestamp: now,
+            user: String::new(),
+            hostname: String::new(),
+            remote: remote.to_owned(),
+            from_remote: None,
+            action,
+            keys,
+            failed_keys,
+            to: None,
+            outcome,
+            error,
+            request_ids,
+            operation: None,
+        }
+    }
+
+    /// Entry of a batch, its skipped and paused keys weren't touched and are left out
+    pub fn from_report(
+        remote: &str,
+        action: Action,
+        report: &BatchReport,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let touched = report.items.iter().filter(|(_, result)| {
+            !matches!(result, Err(err) if matches!(err.kind, ErrorKind::Skipped | ErrorKind::Paused))
+        });
+        Self::new(
+            remote,
+            action,
+            touched.map(|(key, result)| (key.as_ref(), result.as_ref().err())),
+            now,
+        )
+    }
+
+    /// Notes the keys came from `from_remote`, unless that's the entry's remote
+    pub fn source_remote(mut self, from_remote: &str) -> Self {
+        if from_remote != self.remote {
+            self.from_remote = Some(from_remote.to_owned());
+        }
+        self
+    }
+
+    pub fn to(mut self, to: &str) -> Self {
+        self.to = Some(to.to_owned());
+        self
+    }
+
+    pub fn operation(mut self, operation: &OperationId) -> Self {
+        self.operation = Some(operation.to_string());
+        self
+    }
+
+    fn csv_row(&self) -> String {
+        let fields = [
+            self.timestamp.to_rfc3339(),
+            self.user.clone(),
+            self.hostname.clone(),
+            self.remote.clone(),
+            self.from_remote.clone().unwrap_or_default(),
+            self.action.name().to_owned(),
+            // line breaks within a quoted field, keys can hold any other separator
+            self.keys.join("\n"),
+            self.failed_keys.join("\n"),
+            self.to.clone().unwrap_or_default(),
+            self.outcome.name().to_owned(),
+            self.error.clone().unwrap_or_default(),
+            self.request_ids.join(" "),
+            self.operation.clone().unwrap_or_default(),
+        ];
+        let mut row = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        row.push('\n');
+        row
+    }
+}
+
+/// Writes `entry` to the journal in the background, under the login and machine the
+/// app runs on. Entries that touched no key are dropped.
+pub fn record(mut entry: JournalEntry, cx: &App) {
+    if entry.keys.is_empty() && entry.failed_keys.is_empty() {
+        return;
+    }
+
+    let retention = Settings::get(cx).journal_retention();
+    rt::spawn_blocking(cx, move || {
+        let (user, hostname) = IDENTITY.get_or_init(|| (util::user_name(), util::host_name()));
+        entry.user = user.clone();
+        entry.hostname = hostname.clone();
+        if let Err(err) = append(&config::journal_dir(), &entry, retention, Utc::now()) {
+            tracing::error!("Failed to write the journal: {}", err.message);
+        }
+    })
+    .detach();
+}
+
+/// Entries an export takes, all of them when nothing is set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalFilter {
+    /// Made on the remote, or copied or moved out of it
+    pub remote: Option<String>,
+    pub action: Option<Action>,
+    /// Part of the bulk operation of this id
+    pub operation: Option<String>,
+    /// Made at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Made before
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        let remote = self.remote.as_ref().is_none_or(|remote| {
+            entry.remote == *remote || entry.from_remote.as_ref() == Some(remote)
+        });
+        remote
+            && self.action.is_none_or(|action| entry.action == action)
+            && self
+                .operation
+                .as_ref()
+                .is_none_or(|operation| entry.operation.as_ref() == Some(operation))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A JSON object per line, as the journal stores them
+    Jsonl,
+    /// A row per entry under a header, keys on lines of their own within their field
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => EXT,
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Adds `entry` to the journal in `dir` as a line of the current file. A current file
+/// that's full or from an earlier day is rotated first, rotated files past `retention`
+/// are removed then.
+pub fn append(
+    dir: &Path,
+    entry: &JournalEntry,
+    retention: Option<TimeDelta>,
+    now: DateTime<Utc>,
+) -> AppResult<()> {
+    let _lock = WRITE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    fs::create_dir_all(dir).map_err(|err| AppError::err(err))?;
+
+    let current = dir.join(CURRENT_FILE);
+    if let Ok(meta) = fs::metadata(&current) {
+        let modified = meta.modified().map(DateTime::<Utc>::from).unwrap_or(now);
+        if meta.len() >= ROTATE_BYTES || modified.date_naive() < now.date_naive() {
+            let rotated = dir.join(format!(
+                "{ROTATED_PREFIX}{}.{EXT}",
+                modified.timestamp_millis()
+            ));
+            match fs::rename(&current, rotated) {
+                // another instance rotated it just now
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                result => result.map_err(|err| AppError::err(err))?,
+            }
+            prune(dir, retention, now);
+        }
+    }
+
+    let mut line = serde_json::to_vec(entry).map_err(|err| AppError::err(err))?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&current)
+        .and_then(|mut file| file.write_all(&line))
+        .map_err(|err| AppError::err(err))
+}
+
+/// Journal files of `dir` oldest first, with the time of their last entry
+fn journal_files(dir: &Path) -> Vec<(PathBuf, DateTime<Utc>)> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = read_dir
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?;
+            let last = if name == CURRENT_FILE {
+                DateTime::<Utc>::from(entry.metadata().ok()?.modified().ok()?)
+            } else {
+                let millis = name
+                    .strip_prefix(ROTATED_PREFIX)?
+                    .strip_suffix(&format!(".{EXT}"))?
+                    .parse()
+                    .ok()?;
+                DateTime::from_timestamp_millis(millis)?
+            };
+            Some((path, last))
+        })
+        .collect::<Vec<_>>();
+    // the current file goes after the rotated ones even when its clock went backwards
+    files.sort_by_key(|(path, last)| (path.ends_with(CURRENT_FILE), *last));
+    files
+}
+
+/// Removes journal files whose entries are all older than `retention`, `None` keeps
+/// everything
+pub fn prune(dir: &Path, retention: Option<TimeDelta>, now: DateTime<Utc>) {
+    let Some(retention) = retention else {
+        return;
+    };
+    for (path, last) in journal_files(dir) {
+        if last < now - retention
+            && let Err(err) = fs::remove_file(&path)
+        {
+            tracing::warn!("Failed to remove old journal file {:?}: {err}", path);
+        }
+    }
+}
+
+/// Writes the entries of the journal in `dir` that `filter` takes to `out`, oldest
+/// first. Files are read a line at a time, so journals of any length stream through.
+/// Returns how many entries were written.
+pub fn export(
+    dir: &Path,
+    filter: &JournalFilter,
+    format: ExportFormat,
+    out: &mut impl Write,
+) -> AppResult<usize> {
+    if format == ExportFormat::Csv {
+        out.write_all(CSV_HEADER.as_bytes())
+            .map_err(|err| AppError::err(err))?;
+    }
+
+    let mut count = 0;
+    for (path, last) in journal_files(dir) {
+        // everything in it is from before the range
+        if filter.since.is_some_and(|since| last < since) {
+            continue;
+        }
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            // rotated or pruned since it was listed
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(AppError::err(err)),
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|err| AppError::err(err))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(entry) => entry,
+                // like the tail of a write cut short by a crash
+                Err(err) => {
+                    tracing::warn!("Skipping unreadable journal line in {:?}: {err}", path);
+                    continue;
+                }
+            };
+            if !filter.matches(&entry) {
+                continue;
+            }
+
+            match format {
+                ExportFormat::Jsonl => {
+                    out.write_all(line.as_bytes())
+                        .and_then(|_| out.write_all(b"\n"))
+                        .map_err(|err| AppError::err(err))?;
+                }
+                ExportFormat::Csv => out
+                    .write_all(entry.csv_row().as_bytes())
+                    .map_err(|err| AppError::err(err))?,
+            }
+            count += 1;
+        }
+    }
+    out.flush().map_err(|err| AppError::err(err))?;
+    Ok(count)
+}
+
+/// Exports the journal like [`export`] into a new file at `path`
+pub fn export_to(
+    dir: &Path,
+    path: &Path,
+    filter: &JournalFilter,
+    format: ExportFormat,
+) -> AppResult<usize> {
+    let file = File::create(path).map_err(|err| AppError::err(err))?;
+    export(dir, filter, format, &mut BufWriter::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("balti-journal-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry(remote: &str, action: Action, key: &str, at: DateTime<Utc>) -> JournalEntry {
+        JournalEntry::new(remote, action, [(key, None)], at)
+    }
+
+    fn exported(dir: &Path, filter: &JournalFilter, format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        export(dir, filter, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn records_the_outcome_of_each_key() {
+        let now = Utc::now();
+        let mut err = AppError::message("Access Denied");
+        err.req_id = "REQ1".into();
+        let entry = JournalEntry::new(
+            "prod",
+            Action::Delete,
+            [("a", None), ("b", Some(&err)), ("c", Some(&err))],
+            now,
+        );
+        assert_eq!(entry.keys, ["a"]);
+        assert_eq!(entry.failed_keys, ["b", "c"]);
+        assert_eq!(entry.outcome, Outcome::Partial);
+        assert_eq!(entry.error.as_deref(), Some("Access Denied"));
+        assert_eq!(entry.request_ids, ["REQ1"]);
+
+        let mut report = BatchReport::default();
+        report.push("a".into(), Ok(()));
+        report.push(
+            "b".into(),
+            Err(AppError::message("taken").with_kind(ErrorKind::Skipped)),
+        );
+        let entry = JournalEntry::from_report("dev", Action::Copy, &report, now)
+            .source_remote("prod")
+            .to("backup/");
+        assert_eq!(entry.keys, ["a"]);
+        assert!(entry.failed_keys.is_empty());
+        assert_eq!(entry.outcome, Outcome::Ok);
+        assert_eq!(entry.from_remote.as_deref(), Some("prod"));
+        assert_eq!(
+            JournalEntry::from_report("prod", Action::Copy, &report, now)
+                .source_remote("prod")
+                .from_remote,
+            None
+        );
+    }
+
+    #[test]
+    fn filters_a_remote_and_a_month() {
+        let dir = temp_dir("filter");
+        let at = |day: &str| {
+            DateTime::parse_from_rfc3339(&format!("{day}T12:00:00Z"))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let entries = [
+            entry("prod", Action::Delete, "feb", at("2025-02-28")),
+            entry("prod", Action::Delete, "march", at("2025-03-14")),
+            entry("dev", Action::Delete, "dev", at("2025-03-15")),
+            entry("prod", Action::Rename, "renamed", at("2025-03-16")),
+            entry("dev", Action::Move, "moved", at("2025-03-17")).source_remote("prod"),
+            JournalEntry {
+                operation: Some("195a3f0c2e1-4".into()),
+                ..entry("prod", Action::Delete, "batch", at("2025-03-18"))
+            },
+            entry("prod", Action::Delete, "april", at("2025-04-01")),
+        ];
+        for entry in &entries {
+            append(&dir, entry, None, entry.timestamp).unwrap();
+        }
+
+        let march = JournalFilter {
+            remote: Some("prod".into()),
+            action: None,
+            operation: None,
+            since: Some(at("2025-03-01")),
+            until: Some(at("2025-04-01")),
+        };
+        let keys = |filter: &JournalFilter| {
+            exported(&dir, filter, ExportFormat::Jsonl)
+                .lines()
+                .map(|line| serde_json::from_str::<JournalEntry>(line).unwrap().keys[0].clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&march), ["march", "renamed", "moved", "batch"]);
+
+        let deletes = JournalFilter {
+            action: Some(Action::Delete),
+            ..march.clone()
+        };
+        assert_eq!(keys(&deletes), ["march", "batch"]);
+        let batch = JournalFilter {
+            operation: Some("195a3f0c2e1-4".into()),
+            ..march
+        };
+        assert_eq!(keys(&batch), ["batch"]);
+        assert_eq!(keys(&JournalFilter::default()).len(), entries.len());
+    }
+
+    #[test]
+    fn rotates_daily_and_prunes_past_retention() {
+        let dir = temp_dir("rotate");
+        let now = Utc::now();
+        let earlier = now - TimeDelta::days(40);
+        append(
+            &dir,
+            &entry("prod", Action::Delete, "a", earlier),
+            None,
+            earlier,
+        )
+        .unwrap();
+        File::options()
+            .write(true)
+            .open(dir.join(CURRENT_FILE))
+            .unwrap()
+            .set_modified(earlier.into())
+            .unwrap();
+
+        // the first entry of a later day starts a new file
+        append(&dir, &entry("prod", Action::Delete, "b", now), None, now).unwrap();
+        assert_eq!(journal_files(&dir).len(), 2);
+        let rows = exported(&dir, &JournalFilter::default(), ExportFormat::Jsonl);
+        assert_eq!(rows.lines().count(), 2);
+
+        // the rotated file only goes once its last entry is past retention
+        prune(&dir, Some(TimeDelta::days(50)), now);
+        assert_eq!(journal_files(&dir).len(), 2);
+        prune(&dir, Some(TimeDelta::days(30)), now);
+        let files = journal_files(&dir);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].0.ends_with(CURRENT_FILE));
+        prune(&dir, None, now + TimeDelta::days(3650));
+        assert_eq!(journal_files(&dir).len(), 1);
+    }
+
+    #[test]
+    fn exports_csv_rows_and_skips_torn_lines() {
+        let dir = temp_dir("csv");
+        let now = DateTime::parse_from_rfc3339("2025-03-14T09:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut err = AppError::message("Access Denied");
+        err.req_id = "REQ1".into();
+        let mut deleted = JournalEntry::new(
+            "prod",
+            Action::Delete,
+            [("a,1.txt", None), ("b\"2\".txt", Some(&err))],
+            now,
+        );
+        deleted.user = "ana".into();
+        deleted.hostname = "laptop".into();
+        append(&dir, &deleted, None, Utc::now()).unwrap();
+        // a write cut short by a crash
+        OpenOptions::new()
+            .append(true)
+            .open(dir.join(CURRENT_FILE))
+            .unwrap()
+            .write_all(b"{\"timestamp\":")
+            .unwrap();
+
+        let csv = exported(&dir, &JournalFilter::default(), ExportFormat::Csv);
+        assert_eq!(
+            csv,
+            format!(
+                "{CSV_HEADER}2025-03-14T09:30:00+00:00,ana,laptop,prod,,delete,\"a,1.txt\",\"b\"\"2\"\".txt\",,partial,Access Denied,REQ1,\n"
+            )
+        );
+    }
+}
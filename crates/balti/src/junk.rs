@@ -0,0 +1,150 @@
+use balti_err::{AppError, AppResult};
+use balti_s3::{__S3Object, S3Object};
+
+use crate::pattern::{NamePattern, PatternKind};
+
+/// Globs of the names OS shells, editors and office suites leave behind
+pub const DEFAULT_JUNK_PATTERNS: [&str; 10] = [
+    ".DS_Store",
+    "._*",
+    "Thumbs.db",
+    "desktop.ini",
+    "*.swp",
+    "*.swo",
+    "*~",
+    ".#*",
+    "~$*",
+    "*.tmp",
+];
+
+/// Why an object counts as junk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JunkReason {
+    /// Its name matches the glob
+    Pattern(String),
+    /// It holds nothing, like the leftover of a crashed upload
+    Empty,
+}
+
+/// Tells junk objects from the rest, by name globs and emptiness. Folder markers are
+/// never junk, empty as they are.
+#[derive(Debug, Clone)]
+pub struct JunkMatcher {
+    patterns: Vec<(String, NamePattern)>,
+    empty: bool,
+}
+
+impl JunkMatcher {
+    /// Matcher of the `patterns` globs, and of every empty object when `empty`
+    pub fn new(patterns: &[String], empty: bool) -> AppResult<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let glob = pattern.trim();
+                if glob.is_empty() {
+                    return Err(AppError::message("Junk patterns can't be blank"));
+                }
+                Ok((
+                    glob.to_owned(),
+                    NamePattern::parse(glob, PatternKind::Glob)?,
+                ))
+            })
+            .collect::<AppResult<_>>()?;
+        Ok(Self { patterns, empty })
+    }
+
+    /// Why `object` is junk, `None` when it isn't. Folders never are, junk below them
+    /// shows up in recursive listings.
+    pub fn reason(&self, object: &S3Object) -> Option<JunkReason> {
+        let __S3Object::File {
+            key, size, marker, ..
+        } = object.as_ref()
+        else {
+            return None;
+        };
+        if *marker {
+            return None;
+        }
+
+        let name = key.rsplit('/').next().unwrap_or_default();
+        let matched = self
+            .patterns
+            .iter()
+            .find(|(_, pattern)| pattern.matches(name));
+        match matched {
+            Some((glob, _)) => Some(JunkReason::Pattern(glob.clone())),
+            None if self.empty && *size == 0 => Some(JunkReason::Empty),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use balti_s3::FOLDER_MARKER;
+
+    use super::*;
+
+    fn file(key: &str, size: i64) -> S3Object {
+        Arc::new(__S3Object::file(key.into(), size, None, None))
+    }
+
+    fn defaults(empty: bool) -> JunkMatcher {
+        let patterns = DEFAULT_JUNK_PATTERNS.map(String::from);
+        JunkMatcher::new(&patterns, empty).unwrap()
+    }
+
+    #[test]
+    fn matches_the_usual_suspects() {
+        let matcher = defaults(false);
+        for key in [
+            "photos/.DS_Store",
+            "photos/._IMG_0001.jpg",
+            "Thumbs.db",
+            "docs/.notes.txt.swp",
+            "docs/notes.txt~",
+            "docs/~$report.docx",
+        ] {
+            assert!(
+                matches!(matcher.reason(&file(key, 10)), Some(JunkReason::Pattern(_))),
+                "{key}"
+            );
+        }
+        assert_eq!(matcher.reason(&file("docs/report.docx", 10)), None);
+        // only the name counts, not the folders it's in
+        assert_eq!(matcher.reason(&file("._drafts/report.docx", 10)), None);
+    }
+
+    #[test]
+    fn matches_empty_objects_but_not_folder_markers() {
+        let matcher = defaults(true);
+        assert_eq!(
+            matcher.reason(&file("uploads/part.bin", 0)),
+            Some(JunkReason::Empty)
+        );
+        assert_eq!(
+            matcher.reason(&file(&format!("uploads/{FOLDER_MARKER}"), 0)),
+            None
+        );
+        // nor are the empty folder-named markers other tools write
+        assert_eq!(matcher.reason(&file("uploads/", 0)), None);
+        assert_eq!(
+            matcher.reason(&Arc::new(__S3Object::Folder("uploads/".into()))),
+            None
+        );
+        assert_eq!(defaults(false).reason(&file("uploads/part.bin", 0)), None);
+        // a matching name says why rather than being empty
+        assert_eq!(
+            matcher.reason(&file("uploads/.DS_Store", 0)),
+            Some(JunkReason::Pattern(".DS_Store".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_blank_patterns() {
+        assert!(JunkMatcher::new(&[" ".into()], true).is_err());
+        assert!(JunkMatcher::new(&[], true).is_ok());
+    }
+}
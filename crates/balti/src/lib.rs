@@ -0,0 +1,15 @@
+//! Parts of Balti that don't need a window, the app builds on them and `tests/`
+//! drives them against a bucket in memory
+
+pub mod archive;
+pub mod cache;
+pub mod changelog;
+pub mod checkpoint;
+pub mod diff;
+pub mod generation;
+pub mod junk;
+pub mod listing;
+pub mod pattern;
+pub mod rate;
+pub mod remote_form;
+pub mod selection;
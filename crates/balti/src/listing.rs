@@ -0,0 +1,206 @@
+use std::{collections::HashSet, sync::Arc, time::Instant};
+
+use balti_err::AppError;
+use balti_s3::{ObjectPage, S3Object};
+
+use crate::{
+    generation::{Generation, Generations},
+    selection::Selection,
+};
+
+/// Objects of a prefix as its listing streams pages in, apart from how they're shown.
+/// Pages and outcomes of a listing that got replaced or stopped are dropped.
+#[derive(Debug, Default)]
+pub struct Listing {
+    pub objects: Vec<S3Object>,
+    pub next_token: Option<Arc<str>>,
+    /// Prefix holds a folder marker, which is kept out of `objects`
+    pub has_marker: bool,
+    /// A listing from the start runs and its first page hasn't landed yet
+    pub loading: bool,
+    /// A listing continues after what's shown
+    pub loading_more: bool,
+    /// `objects` are what the prefix showed last, the listing's first page replaces them
+    pub stale: bool,
+    /// First page of the last listing from the start failed
+    pub error: Option<AppError>,
+    /// When the last listing landed, auto refresh counts from here
+    pub last_refreshed: Option<Instant>,
+    generations: Generations,
+}
+
+impl Listing {
+    /// Starts out with what the prefix showed last time
+    pub fn remembered(objects: Vec<S3Object>) -> Self {
+        Self {
+            stale: !objects.is_empty(),
+            objects,
+            ..Default::default()
+        }
+    }
+
+    /// Starts a listing from the start, or continuing after `next_token` without
+    /// `restart`. The one before is stale from now on.
+    pub fn start(&mut self, restart: bool) -> Generation {
+        if restart {
+            self.loading = true;
+        } else {
+            self.loading_more = true;
+        }
+        self.generations.start()
+    }
+
+    /// Takes in a page of the listing `generation`, `replace` drops what was shown
+    /// for a fresh listing. `false` when the listing got replaced or stopped.
+    pub fn apply_page(&mut self, generation: Generation, page: ObjectPage, replace: bool) -> bool {
+        if !self.generations.is_current(generation) {
+            return false;
+        }
+
+        let (markers, objects): (Vec<_>, Vec<_>) =
+            page.objects.into_iter().partition(|obj| obj.is_marker());
+        if replace {
+            self.loading = false;
+            self.stale = false;
+            self.last_refreshed = Some(Instant::now());
+            self.error = None;
+            self.objects = objects;
+            self.has_marker = !markers.is_empty();
+        } else {
+            self.objects.extend(objects);
+            self.has_marker |= !markers.is_empty();
+        }
+
+        self.next_token = page.next_token;
+        // the stream goes on while there's more
+        self.loading_more = self.next_token.is_some();
+        true
+    }
+
+    /// Ends the listing `generation` once its stream stopped. `false` when a newer
+    /// listing took over, its state isn't this one's to touch.
+    pub fn finish(&mut self, generation: Generation) -> bool {
+        if !self.generations.is_current(generation) {
+            return false;
+        }
+        self.loading = false;
+        self.loading_more = false;
+        true
+    }
+
+    /// Notes that the first page of a listing from the start failed, nothing of it
+    /// is shown
+    pub fn fail(&mut self, err: AppError) {
+        self.last_refreshed = Some(Instant::now());
+        self.error = Some(err);
+    }
+
+    /// Stops the listing running, like once the view is left. What landed stays,
+    /// anything of the listing landing afterwards is dropped. `true` when it was
+    /// still waiting for its first page.
+    pub fn stop(&mut self) -> bool {
+        self.generations.invalidate();
+        let waiting = self.loading;
+        self.loading = false;
+        self.loading_more = false;
+        waiting
+    }
+
+    /// Nothing is left below the prefix, not even a marker, a folder there only
+    /// existed through keys that are gone now
+    pub fn is_gone(&self) -> bool {
+        self.objects.is_empty() && !self.has_marker && self.next_token.is_none()
+    }
+
+    /// Unchecks keys a fresh listing doesn't have anymore
+    pub fn prune(&self, selection: &mut Selection) {
+        let keys = self
+            .objects
+            .iter()
+            .map(|obj| obj.key().as_ref())
+            .collect::<HashSet<_>>();
+        selection.retain(|key| keys.contains(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use balti_s3::__S3Object;
+
+    use super::*;
+
+    fn page(keys: &[&str], next_token: Option<&str>) -> ObjectPage {
+        ObjectPage {
+            objects: keys
+                .iter()
+                .map(|key| Arc::new(__S3Object::file((*key).into(), 1, None, None)))
+                .collect(),
+            next_token: next_token.map(Arc::from),
+        }
+    }
+
+    fn keys(listing: &Listing) -> Vec<&str> {
+        listing
+            .objects
+            .iter()
+            .map(|obj| obj.key().as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn streams_pages_until_complete() {
+        let mut listing = Listing::remembered(vec![Arc::new(__S3Object::Folder("a/".into()))]);
+        assert!(listing.stale);
+
+        let generation = listing.start(true);
+        assert!(listing.loading);
+        assert!(listing.apply_page(generation, page(&["a.txt"], Some("t")), true));
+        assert!(!listing.loading && !listing.stale && listing.loading_more);
+        assert!(listing.apply_page(generation, page(&["b.txt"], None), false));
+        assert!(listing.finish(generation));
+
+        assert_eq!(keys(&listing), ["a.txt", "b.txt"]);
+        assert!(!listing.loading_more);
+        assert!(listing.last_refreshed.is_some());
+    }
+
+    #[test]
+    fn late_answers_dont_overwrite_newer_ones() {
+        let mut listing = Listing::default();
+
+        let first = listing.start(true);
+        let second = listing.start(true);
+        assert!(listing.apply_page(second, page(&["a/new.txt"], None), true));
+        assert!(!listing.apply_page(first, page(&["a/old.txt"], None), true));
+        assert_eq!(keys(&listing), ["a/new.txt"]);
+
+        // the old one ending doesn't end the one that replaced it either
+        let third = listing.start(true);
+        assert!(!listing.finish(second));
+        assert!(listing.loading);
+        assert!(listing.apply_page(third, page(&["a/newer.txt"], None), true));
+        assert!(listing.finish(third));
+        assert_eq!(keys(&listing), ["a/newer.txt"]);
+    }
+
+    #[test]
+    fn drops_answers_once_stopped() {
+        let mut listing = Listing::default();
+        let first = listing.start(true);
+        listing.apply_page(first, page(&["a/shown.txt"], Some("t")), true);
+
+        // popped off the nav stack while more pages stream in
+        let more = listing.start(false);
+        assert!(!listing.stop());
+        assert!(!listing.apply_page(more, page(&["a/late.txt"], None), false));
+        assert!(!listing.finish(more));
+        assert_eq!(keys(&listing), ["a/shown.txt"]);
+        assert!(!listing.loading_more);
+        assert_eq!(listing.next_token.as_deref(), Some("t"));
+
+        let restart = listing.start(true);
+        assert!(listing.stop());
+        assert!(!listing.loading);
+        assert!(!listing.finish(restart));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{mem, sync::Arc};
+
+use balti_s3::S3Object;
+use gpui::{App, Global, Pixels, Point, SharedString};
+
+use crate::{
+    cache::{self, ByteLru, CacheUsage},
+    settings::Settings,
+};
+
+/// Prefixes remembered for the session, the least recently listed drop off past this
+const MAX_LISTINGS: usize = 64;
+/// Listings longer than this only keep their count, not the objects
+const MAX_REMEMBERED_OBJECTS: usize = 10_000;
+/// Objects remembered across all prefixes, listings past it from the least recently
+/// listed on keep only their count and scroll offset
+const MAX_TOTAL_OBJECTS: usize = 20_000;
+/// Skeleton rows below the viewport, a scroll while loading doesn't run out of them
+const SKELETON_MARGIN: usize = 4;
+/// Skeleton rows of a prefix that was never listed
+const DEFAULT_SKELETON_ROWS: usize = 7;
+
+pub fn init(cx: &mut App) {
+    let max_bytes = Settings::get(cx).listing_cache_bytes();
+    cx.set_global(ListingMemory::new(max_bytes));
+}
+
+/// What a prefix showed last, a fresh view of it starts from there rather than empty
+#[derive(Clone)]
+pub struct RememberedListing {
+    /// `None` when there were too many to keep
+    pub objects: Option<Vec<S3Object>>,
+    pub count: usize,
+    pub scroll_offset: Point<Pixels>,
+}
+
+impl RememberedListing {
+    pub fn new(objects: &[S3Object], scroll_offset: Point<Pixels>) -> Self {
+        Self {
+            objects: (objects.len() <= MAX_REMEMBERED_OBJECTS).then(|| objects.to_vec()),
+            count: objects.len(),
+            scroll_offset,
+        }
+    }
+
+    /// Rough bytes the listing holds on to
+    fn estimated_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .objects
+                .iter()
+                .flatten()
+                .map(cache::object_bytes)
+                .sum::<usize>()
+    }
+}
+
+/// Last listing of each prefix the session showed, by remote
+pub struct ListingMemory {
+    listings: ByteLru<(Arc<str>, SharedString), RememberedListing>,
+}
+
+impl Global for ListingMemory {}
+
+impl ListingMemory {
+    pub fn remember(
+        remote: &Arc<str>,
+        prefix: &SharedString,
+        listing: RememberedListing,
+        cx: &mut App,
+    ) {
+        // the cap may have changed since, with an imported settings file
+        let max_bytes = Settings::get(cx).listing_cache_bytes();
+        let memory = cx.global_mut::<Self>();
+        memory.listings.set_max_bytes(max_bytes);
+        memory.insert((remote.clone(), prefix.clone()), listing);
+    }
+
+    pub fn recall(remote: &Arc<str>, prefix: &SharedString, cx: &App) -> Option<RememberedListing> {
+        cx.global::<Self>()
+            .listings
+            .peek(&(remote.clone(), prefix.clone()))
+            .cloned()
+    }
+
+    pub fn usage(cx: &App) -> CacheUsage {
+        cx.global::<Self>().listings.usage()
+    }
+
+    pub fn clear(cx: &mut App) {
+        cx.global_mut::<Self>().listings.clear();
+    }
+
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            listings: ByteLru::new("listing", MAX_LISTINGS, max_bytes),
+        }
+    }
+
+    fn insert(&mut self, key: (Arc<str>, SharedString), listing: RememberedListing) {
+        let bytes = listing.estimated_bytes();
+        self.listings.insert(key, listing, bytes);
+        self.forget_objects();
+    }
+
+    /// Drops the objects of the least recently listed prefixes past [`MAX_TOTAL_OBJECTS`]
+    fn forget_objects(&mut self) {
+        let mut kept = 0;
+        let over = self
+            .listings
+            .newest_first()
+            .filter(|(_, listing)| {
+                kept += listing.objects.as_ref().map_or(0, Vec::len);
+                kept > MAX_TOTAL_OBJECTS && listing.objects.is_some()
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in over {
+            self.listings.update(&key, |listing| {
+                listing.objects = None;
+                listing.estimated_bytes()
+            });
+        }
+    }
+}
+
+/// Skeleton rows while a prefix loads: as many as it had objects last time, up to a
+/// viewport of `viewport_rows` and a margin. At least one, so loading still shows.
+pub fn skeleton_rows(last_count: Option<usize>, viewport_rows: usize) -> usize {
+    match last_count {
+        Some(count) => count.min(viewport_rows + SKELETON_MARGIN).max(1),
+        None => DEFAULT_SKELETON_ROWS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use balti_s3::__S3Object;
+
+    use super::*;
+
+    #[test]
+    fn fills_the_viewport_with_skeletons() {
+        assert_eq!(skeleton_rows(None, 20), DEFAULT_SKELETON_ROWS);
+        assert_eq!(skeleton_rows(Some(3), 20), 3);
+        assert_eq!(skeleton_rows(Some(200), 20), 20 + SKELETON_MARGIN);
+        assert_eq!(skeleton_rows(Some(0), 20), 1);
+    }
+
+    #[test]
+    fn forgets_the_oldest_listing() {
+        let mut memory = ListingMemory::new(usize::MAX);
+        let listing = RememberedListing::new(&[], Point::default());
+        let key = |i: usize| (Arc::from("prod"), SharedString::from(format!("logs/{i}/")));
+
+        for i in 0..MAX_LISTINGS {
+            memory.insert(key(i), listing.clone());
+        }
+        // listed again, it's the most recent now
+        memory.insert(key(0), listing.clone());
+        memory.insert(key(MAX_LISTINGS), listing);
+
+        assert_eq!(memory.listings.usage().entries, MAX_LISTINGS);
+        assert!(memory.listings.peek(&key(0)).is_some());
+        assert!(memory.listings.peek(&key(1)).is_none());
+    }
+
+    #[test]
+    fn keeps_only_counts_of_old_listings_past_the_total() {
+        let mut memory = ListingMemory::new(usize::MAX);
+        let objects = (0..MAX_REMEMBERED_OBJECTS)
+            .map(|i| Arc::new(__S3Object::Folder(format!("{i}/").into())))
+            .collect::<Vec<_>>();
+        let listing = RememberedListing::new(&objects, Point::default());
+        let key = |i: usize| (Arc::from("prod"), SharedString::from(format!("logs/{i}/")));
+
+        let listings = MAX_TOTAL_OBJECTS / MAX_REMEMBERED_OBJECTS + 1;
+        for i in 0..listings {
+            memory.insert(key(i), listing.clone());
+        }
+
+        let oldest = memory.listings.peek(&key(0)).unwrap();
+        assert!(oldest.objects.is_none());
+        assert_eq!(oldest.count, MAX_REMEMBERED_OBJECTS);
+        for i in 1..listings {
+            assert!(memory.listings.peek(&key(i)).unwrap().objects.is_some());
+        }
+    }
+}
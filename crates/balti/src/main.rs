@@ -1,15 +1,39 @@
+use balti::{
+    archive, cache, changelog, checkpoint, diff, generation, junk, listing, pattern, rate,
+    remote_form, selection,
+};
 use gpui::*;
-use gpui_component::{ActiveTheme, TitleBar};
+use gpui_component::TitleBar;
 use tracing::Level;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod about;
 mod assets;
+mod bulk;
+mod capabilities;
+mod clipboard;
+mod coalesce;
 mod config;
+mod credentials;
+mod health;
+mod journal;
+mod listing_memory;
 mod nav;
+mod pending_reports;
+mod platform_progress;
+mod quota;
+mod reconcile;
 mod rt;
 mod s3;
+mod settings;
+mod staging;
+mod strings;
 mod theme;
+mod thumbnail;
+mod transfers;
 mod ui;
+mod undo;
+mod update;
 mod util;
 
 fn get_window_options(cx: &mut App) -> WindowOptions {
@@ -23,7 +47,7 @@ fn get_window_options(cx: &mut App) -> WindowOptions {
     WindowOptions {
         window_bounds: Some(WindowBounds::Windowed(bounds)),
         titlebar: Some(TitleBar::title_bar_options()),
-        window_min_size: Some(size(px(800.0), px(600.0))),
+        window_min_size: Some(size(px(640.0), px(480.0))),
         kind: WindowKind::Normal,
         window_decorations: Some(WindowDecorations::Client),
         tabbing_identifier: Some("Balti".into()),
@@ -33,32 +57,75 @@ fn get_window_options(cx: &mut App) -> WindowOptions {
 }
 
 fn set_menus(cx: &mut App) {
-    cx.set_menus(vec![Menu {
-        name: SharedString::new("Balti"),
-        items: vec![
-            MenuItem::action("About Balti", ui::About),
-            MenuItem::action("Check for updates", ui::CheckForUpdates),
-            MenuItem::separator(),
-            MenuItem::os_submenu("Services", SystemMenuType::Services),
-            MenuItem::separator(),
-            MenuItem::action("Quit", ui::Quit),
-        ],
-    }]);
+    cx.set_menus(vec![
+        Menu {
+            name: SharedString::new("Balti"),
+            items: vec![
+                MenuItem::action("About Balti", ui::About),
+                MenuItem::action("What's New", ui::WhatsNew),
+                MenuItem::action("Check for updates", ui::CheckForUpdates),
+                MenuItem::separator(),
+                MenuItem::action("Export Settings…", ui::ExportSettings),
+                MenuItem::action("Import Settings…", ui::ImportSettings),
+                MenuItem::action("Export Audit Journal…", ui::ExportJournal),
+                MenuItem::separator(),
+                MenuItem::os_submenu("Services", SystemMenuType::Services),
+                MenuItem::separator(),
+                MenuItem::action("Quit", ui::Quit),
+            ],
+        },
+        Menu {
+            name: SharedString::new("View"),
+            items: vec![
+                MenuItem::action("Zoom In", ui::ZoomIn),
+                MenuItem::action("Zoom Out", ui::ZoomOut),
+                MenuItem::action("Actual Size", ui::ResetZoom),
+                MenuItem::separator(),
+                MenuItem::action("Reduce Motion", ui::ToggleReduceMotion),
+                MenuItem::action("Compact Density", ui::ToggleCompactDensity),
+                MenuItem::separator(),
+                MenuItem::action("Request Usage", ui::ShowUsage),
+                MenuItem::separator(),
+                MenuItem::action("Go to Location…", ui::GoToLocation),
+            ],
+        },
+        Menu {
+            name: SharedString::new("Window"),
+            items: vec![
+                MenuItem::action("Minimize", ui::Minimize),
+                MenuItem::action("Zoom", ui::ZoomWindow),
+                MenuItem::separator(),
+                MenuItem::action("Enter Full Screen", ui::ToggleFullScreen),
+            ],
+        },
+    ]);
 }
 
 fn main() {
     let file =
         std::fs::File::create(config::get_new_log_file_path()).expect("Failed to create log file");
 
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_writer(file)
+        .with_ansi(false)
+        .with_thread_ids(true);
+    let (text_layer, json_layer) = match config::parse_log_format() {
+        settings::LogFormat::Text => (Some(file_layer), None),
+        settings::LogFormat::Json => {
+            let json = file_layer
+                .json()
+                .flatten_event(true)
+                .with_current_span(false)
+                .with_span_list(false);
+            (None, Some(json))
+        }
+    };
+
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::from_default_env().add_directive(Level::INFO.into()))
         .with(tracing_subscriber::fmt::layer().with_thread_ids(true))
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_writer(file)
-                .with_ansi(false)
-                .with_thread_ids(true),
-        )
+        .with(text_layer)
+        .with(json_layer)
         .init();
 
     Application::new()
@@ -66,6 +133,21 @@ fn main() {
         .run(|cx: &mut App| {
             set_menus(cx);
             rt::init(cx);
+            settings::init(cx);
+            strings::init(cx);
+            bulk::init(cx);
+            transfers::init(cx);
+            clipboard::init(cx);
+            credentials::init(cx);
+            capabilities::init(cx);
+            health::init(cx);
+            quota::init(cx);
+            listing_memory::init(cx);
+            thumbnail::init(cx);
+            undo::init(cx);
+            pending_reports::init(cx);
+            staging::init(cx);
+            journal::init(cx);
 
             cx.activate(true);
 
@@ -73,7 +155,8 @@ fn main() {
             cx.open_window(window_options, |win, cx| {
                 gpui_component::init(cx);
                 gpui_component::theme::init(cx);
-                theme::change_color_mode(cx.theme().mode, cx);
+                theme::follow_window_appearance(win, cx);
+                platform_progress::init(win, cx);
 
                 let root_view = ui::Rooter::view(win, cx);
                 cx.new(|cx| gpui_component::Root::new(root_view, win, cx))
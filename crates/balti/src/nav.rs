@@ -2,13 +2,67 @@ use std::collections::HashMap;
 
 use gpui::*;
 
+/// Coarse state of the view currently shown inside a tab
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TabStatus {
+    #[default]
+    Ok,
+    Loading,
+    Error,
+}
+
+/// What closing a tab would throw away
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PendingState {
+    /// Checked objects across the tab's listings
+    pub selected: usize,
+    /// Operations the tab's listings still run, like deletes or pastes
+    pub running: usize,
+}
+
+impl PendingState {
+    pub fn has_pending_state(&self) -> bool {
+        self.selected > 0 || self.running > 0
+    }
+}
+
+impl std::ops::Add for PendingState {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            selected: self.selected + other.selected,
+            running: self.running + other.running,
+        }
+    }
+}
+
+impl std::iter::Sum for PendingState {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |sum, state| sum + state)
+    }
+}
+
 pub trait TabId: Render {
     fn id(&self) -> SharedString;
+    /// Name shared by all tabs opened for the same remote
+    fn group(&self) -> SharedString;
+    fn status(&self) -> Entity<TabStatus>;
+    fn pending_state(&self, cx: &App) -> PendingState;
+}
+
+struct TabEntry {
+    view: AnyView,
+    group: SharedString,
+    status: Entity<TabStatus>,
+    /// Asks the typed view for its [`PendingState`]
+    pending_state: Box<dyn Fn(&App) -> PendingState>,
+    _subscriptions: Vec<Subscription>,
 }
 
 pub struct TabNav {
     active_index: usize,
-    views: HashMap<SharedString, AnyView>,
+    entries: HashMap<SharedString, TabEntry>,
     tabs: Vec<SharedString>,
 }
 
@@ -16,7 +70,7 @@ impl TabNav {
     pub fn new() -> Self {
         Self {
             active_index: 0,
-            views: HashMap::new(),
+            entries: HashMap::new(),
             tabs: Vec::new(),
         }
     }
@@ -29,19 +83,60 @@ impl TabNav {
         &self.tabs
     }
 
+    /// Views of the open tabs, in tab order
+    pub fn views(&self) -> impl Iterator<Item = &AnyView> {
+        self.tabs
+            .iter()
+            .filter_map(|id| self.entries.get(id))
+            .map(|entry| &entry.view)
+    }
+
     pub fn active_view(&self) -> Option<&AnyView> {
         self.tabs
             .iter()
             .nth(self.active_index)
-            .and_then(|s| self.views.get(s))
+            .and_then(|s| self.entries.get(s))
+            .map(|entry| &entry.view)
+    }
+
+    pub fn pending_state(&self, index: usize, cx: &App) -> PendingState {
+        self.tabs
+            .get(index)
+            .and_then(|id| self.entries.get(id))
+            .map(|entry| (entry.pending_state)(cx))
+            .unwrap_or_default()
+    }
+
+    /// Pending state of every tab opened for the remote, together
+    pub fn remote_pending_state(&self, remote: &str, cx: &App) -> PendingState {
+        self.entries
+            .values()
+            .filter(|entry| entry.group.as_ref() == remote)
+            .map(|entry| (entry.pending_state)(cx))
+            .sum()
+    }
+
+    pub fn tab_status(&self, id: &SharedString, cx: &App) -> TabStatus {
+        self.entries
+            .get(id)
+            .map(|entry| *entry.status.read(cx))
+            .unwrap_or_default()
     }
 
     pub fn select_tab(&mut self, index: usize) {
         self.active_index = index;
     }
 
-    pub fn new_tab<N: TabId, T: 'static>(&mut self, view: Entity<N>, cx: &mut Context<T>) {
-        let id = view.read(cx).id();
+    /// Opens the view in a new tab, or focuses the existing tab with the same id.
+    /// `subscriptions` live as long as the tab does.
+    pub fn new_tab<N: TabId, T: 'static>(
+        &mut self,
+        view: Entity<N>,
+        mut subscriptions: Vec<Subscription>,
+        cx: &mut Context<T>,
+    ) {
+        let (id, group, status) =
+            view.read_with(cx, |this, _cx| (this.id(), this.group(), this.status()));
 
         match self
             .tabs
@@ -54,21 +149,33 @@ impl TabNav {
                 self.active_index = index;
             }
             None => {
+                // re-render the tab bar whenever the tab's view changes its status
+                subscriptions.push(cx.observe(&status, |_, _, cx| cx.notify()));
+
+                let typed = view.clone();
                 self.tabs.push(id.clone());
-                self.views.insert(id, view.into());
+                self.entries.insert(
+                    id,
+                    TabEntry {
+                        view: view.into(),
+                        group,
+                        status,
+                        pending_state: Box::new(move |cx| typed.read(cx).pending_state(cx)),
+                        _subscriptions: subscriptions,
+                    },
+                );
                 self.active_index = self.tabs.len() - 1;
             }
         };
         cx.notify();
     }
 
-    pub fn close_tab_by_remote<T: 'static>(&mut self, id: SharedString, cx: &mut Context<T>) {
-        if let Some(index) = self
+    /// Closes every tab opened for the remote
+    pub fn close_tab_by_remote<T: 'static>(&mut self, remote: SharedString, cx: &mut Context<T>) {
+        while let Some(index) = self
             .tabs
             .iter()
-            .enumerate()
-            .find(|(_, s)| s == &&id)
-            .map(|(i, _)| i)
+            .position(|id| self.entries.get(id).is_some_and(|e| e.group == remote))
         {
             self.close_tab(index);
         }
@@ -82,7 +189,7 @@ impl TabNav {
         }
 
         let id = self.tabs.remove(index);
-        self.views.remove(&id);
+        self.entries.remove(&id);
 
         if self.active_index > index && self.active_index > 0 {
             self.active_index -= 1;
@@ -96,7 +203,7 @@ impl TabNav {
 
         let len = self.tabs.len();
         let id = self.tabs.remove(self.active_index);
-        self.views.remove(&id);
+        self.entries.remove(&id);
 
         if self.active_index == len - 1 && self.active_index > 0 {
             self.active_index -= 1;
@@ -153,6 +260,11 @@ impl BucketNav {
             .and_then(|(_, prefix)| self.views.get(prefix))
     }
 
+    /// Views kept in history, the one shown among them
+    pub fn views(&self) -> impl Iterator<Item = &AnyView> {
+        self.views.values()
+    }
+
     pub fn stack(&self) -> &Vec<(SharedString, SharedString)> {
         &self.stack
     }
@@ -169,12 +281,13 @@ impl BucketNav {
         cx.notify();
     }
 
-    pub fn trim(&mut self, index: usize) {
+    /// Drops the history after `index`, returning the views that went with it
+    pub fn trim(&mut self, index: usize) -> Vec<AnyView> {
         self.ptr = index;
-        self.drop_later_and_views();
+        self.drop_later_and_views()
     }
 
-    fn drop_later_and_views(&mut self) {
+    fn drop_later_and_views(&mut self) -> Vec<AnyView> {
         // trim stack
         self.stack = self.stack.drain(..=self.ptr).collect();
 
@@ -192,6 +305,13 @@ impl BucketNav {
             }
         }
 
+        let dropped = self
+            .views
+            .iter()
+            .filter(|(prefix, _)| !views.contains_key(*prefix))
+            .map(|(_, view)| view.clone())
+            .collect();
         self.views = views;
+        dropped
     }
 }
@@ -0,0 +1,112 @@
+use balti_err::{AppError, AppResult};
+use regex::Regex;
+
+/// How a name pattern is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PatternKind {
+    /// `*` matches any run of characters, `?` a single one and `[a-z]`/`[!a-z]` a class
+    #[default]
+    Glob,
+    Regex,
+}
+
+/// Name pattern matched against whole object names, trailing `/` of folders left out
+#[derive(Debug, Clone)]
+pub struct NamePattern(Regex);
+
+impl NamePattern {
+    pub fn parse(input: &str, kind: PatternKind) -> AppResult<Self> {
+        let source = match kind {
+            PatternKind::Glob => glob_to_regex(input),
+            PatternKind::Regex => format!("^(?:{input})$"),
+        };
+        Regex::new(&source)
+            .map(Self)
+            .map_err(|err| AppError::message(format!("Invalid pattern: {err}")))
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.is_match(name.trim_end_matches('/'))
+    }
+}
+
+/// Anchored regex for a glob, `[` without a closing `]` matches itself
+fn glob_to_regex(glob: &str) -> String {
+    let mut source = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => source.push_str(".*"),
+            '?' => source.push('.'),
+            '[' => {
+                let class = chars.clone().collect::<String>();
+                let Some(end) = class.find(']').filter(|end| *end > 0) else {
+                    source.push_str(r"\[");
+                    continue;
+                };
+
+                let class = &class[..end];
+                let (negated, class) = match class.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, class),
+                };
+                source.push('[');
+                if negated {
+                    source.push('^');
+                }
+                for c in class.chars() {
+                    if matches!(c, '\\' | '[' | '^') {
+                        source.push('\\');
+                    }
+                    source.push(c);
+                }
+                source.push(']');
+                for _ in 0..=end {
+                    chars.next();
+                }
+            }
+            c => source.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    source.push('$');
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str) -> NamePattern {
+        NamePattern::parse(pattern, PatternKind::Glob).unwrap()
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob("*.tmp").matches("cache.tmp"));
+        assert!(!glob("*.tmp").matches("cache.tmp.bak"));
+        assert!(glob("report-??.csv").matches("report-07.csv"));
+        assert!(!glob("report-??.csv").matches("report-7.csv"));
+        assert!(glob("img[0-9].png").matches("img4.png"));
+        assert!(!glob("img[!0-9].png").matches("img4.png"));
+        assert!(glob("a+b (1).txt").matches("a+b (1).txt"));
+        assert!(glob("[draft").matches("[draft"));
+    }
+
+    #[test]
+    fn matches_folders_without_their_slash() {
+        assert!(glob("logs-*").matches("logs-2024/"));
+        assert!(
+            NamePattern::parse("logs-\\d+", PatternKind::Regex)
+                .unwrap()
+                .matches("logs-2024/")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_regexes() {
+        assert!(NamePattern::parse("(unclosed", PatternKind::Regex).is_err());
+        assert!(NamePattern::parse("(unclosed", PatternKind::Glob).is_ok());
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind as IoErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use balti_err::{AppError, AppResult, BatchReport, ErrorKind};
+use balti_s3::{__S3Object, S3Object};
+use gpui::{App, Global};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint,
+    clipboard::{ClipboardMode, PasteBatch, PasteItem},
+    config,
+};
+
+/// Unresolved reports the app quit with, read back on the next launch
+const PENDING_REPORTS_FILE: &str = "pending-reports.json";
+
+/// Restores the reports the last session quit with and saves this session's as it quits
+pub fn init(cx: &mut App) {
+    let path = pending_reports_path();
+    let restored = load(&path).unwrap_or_else(|err| {
+        tracing::warn!("Failed to read the pending reports: {}", err.message);
+        Vec::new()
+    });
+    cx.set_global(PendingReports {
+        reports: Vec::new(),
+        restored,
+    });
+
+    cx.on_app_quit(|cx| {
+        let reports = cx.global::<PendingReports>().unresolved();
+        async move {
+            if let Err(err) = save(&pending_reports_path(), &reports) {
+                tracing::error!("Failed to save the pending reports: {}", err.message);
+            }
+        }
+    })
+    .detach();
+}
+
+fn pending_reports_path() -> PathBuf {
+    config::config_dir().join(PENDING_REPORTS_FILE)
+}
+
+/// How an item of the batch went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Ok,
+    Skipped,
+    Paused,
+    Failed,
+}
+
+/// Item of a saved report, with what it takes to queue it again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingItem {
+    pub key: Arc<str>,
+    pub status: ItemStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether a delete was removing a whole folder
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub folder: bool,
+    /// Where a paste was writing the item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_key: Option<Arc<str>>,
+    #[serde(default)]
+    pub size: i64,
+}
+
+impl PendingItem {
+    fn new(key: &Arc<str>, result: &AppResult<()>) -> Self {
+        let (status, error) = match result {
+            Ok(_) => (ItemStatus::Ok, None),
+            Err(err) if err.kind == ErrorKind::Skipped => {
+                (ItemStatus::Skipped, Some(err.message.clone()))
+            }
+            Err(err) if err.kind == ErrorKind::Paused => {
+                (ItemStatus::Paused, Some(err.message.clone()))
+            }
+            Err(err) => (ItemStatus::Failed, Some(err.message.clone())),
+        };
+        Self {
+            key: key.clone(),
+            status,
+            error,
+            folder: false,
+            to_key: None,
+            size: 0,
+        }
+    }
+
+    /// A quota pause doesn't outlive the session, a restored paused item is one more to retry
+    fn result(&self) -> AppResult<()> {
+        let message = || self.error.clone().unwrap_or_default();
+        match self.status {
+            ItemStatus::Ok => Ok(()),
+            ItemStatus::Skipped => Err(AppError::message(message()).with_kind(ErrorKind::Skipped)),
+            ItemStatus::Paused | ItemStatus::Failed => Err(AppError::message(message())),
+        }
+    }
+}
+
+/// Where a paste copied from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingPaste {
+    pub from: Arc<str>,
+    pub mode: ClipboardMode,
+}
+
+/// Batch report saved across sessions, enough of the batch to retry what didn't go through
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingReport {
+    /// Remote the batch ran against, the one a paste wrote to
+    pub remote: Arc<str>,
+    /// `None` for a delete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste: Option<PendingPaste>,
+    pub items: Vec<PendingItem>,
+}
+
+impl PendingReport {
+    /// Report of a delete of `objects` on `remote`
+    pub fn delete(
+        remote: Arc<str>,
+        report: &BatchReport,
+        objects: &HashMap<Arc<str>, S3Object>,
+    ) -> Self {
+        let items = report
+            .items
+            .iter()
+            .map(|(key, result)| {
+                let mut item = PendingItem::new(key, result);
+                match objects.get(key).map(|object| object.as_ref()) {
+                    Some(__S3Object::Folder(_)) => item.folder = true,
+                    Some(__S3Object::File { size, .. }) => item.size = *size,
+                    None => {}
+                }
+                item
+            })
+            .collect();
+        Self {
+            remote,
+            paste: None,
+            items,
+        }
+    }
+
+    /// Report of pasting `batch` into `remote`
+    pub fn paste(remote: Arc<str>, report: &BatchReport, batch: &PasteBatch) -> Self {
+        let items = report
+            .items
+            .iter()
+            .map(|(key, result)| {
+                let mut item = PendingItem::new(key, result);
+                if let Some(paste) = batch.items.get(key) {
+                    item.to_key = Some(paste.to_key.clone());
+                    item.size = paste.size;
+                }
+                item
+            })
+            .collect();
+        Self {
+            remote,
+            paste: Some(PendingPaste {
+                from: batch.from.remote_name.clone(),
+                mode: batch.mode,
+            }),
+            items,
+        }
+    }
+
+    /// `None` for a delete
+    pub fn mode(&self) -> Option<ClipboardMode> {
+        self.paste.as_ref().map(|paste| paste.mode)
+    }
+
+    /// Whether something failed or got paused, only skipped items were left alone on purpose
+    pub fn is_unresolved(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item.status, ItemStatus::Paused | ItemStatus::Failed))
+    }
+
+    pub fn report(&self) -> BatchReport {
+        self.items
+            .iter()
+            .map(|item| (item.key.clone(), item.result()))
+            .collect()
+    }
+
+    /// Objects of a delete to queue again, of those at `keys`
+    pub fn objects(&self, keys: &[Arc<str>]) -> Vec<S3Object> {
+        self.retried(keys)
+            .map(|item| {
+                let object = if item.folder {
+                    __S3Object::Folder(item.key.clone())
+                } else {
+                    __S3Object::file(item.key.clone(), item.size, None, None)
+                };
+                Arc::new(object)
+            })
+            .collect()
+    }
+
+    /// Items of a paste to queue again, of those at `keys`
+    pub fn paste_items(&self, keys: &[Arc<str>]) -> Vec<PasteItem> {
+        self.retried(keys)
+            .filter_map(|item| {
+                Some(PasteItem {
+                    from_key: item.key.clone(),
+                    to_key: item.to_key.clone()?,
+                    size: item.size,
+                })
+            })
+            .collect()
+    }
+
+    fn retried(&self, keys: &[Arc<str>]) -> impl Iterator<Item = &PendingItem> {
+        self.items.iter().filter(|item| keys.contains(&item.key))
+    }
+
+    /// Same remote and operation, a later batch of it replaces the report
+    fn is_same_batch(&self, other: &PendingReport) -> bool {
+        self.remote == other.remote && self.mode() == other.mode()
+    }
+}
+
+/// Reports of batches that left something unresolved, the latest of each remote and
+/// operation. Reports with failures outlive a quit.
+#[derive(Default)]
+pub struct PendingReports {
+    reports: Vec<PendingReport>,
+    /// Left by the last session, until retried or dismissed
+    restored: Vec<PendingReport>,
+}
+
+impl Global for PendingReports {}
+
+impl PendingReports {
+    /// Keeps `report` while it's unresolved, in place of the batch's earlier one
+    pub fn note(report: PendingReport, cx: &mut App) {
+        cx.global_mut::<Self>().note_report(report);
+    }
+
+    pub fn restored(cx: &App) -> &[PendingReport] {
+        &cx.global::<Self>().restored
+    }
+
+    /// Forgets the restored `report`, once it's retried or dismissed
+    pub fn dismiss(report: &PendingReport, cx: &mut App) {
+        cx.global_mut::<Self>()
+            .restored
+            .retain(|restored| restored != report);
+    }
+
+    fn note_report(&mut self, report: PendingReport) {
+        self.reports.retain(|noted| !noted.is_same_batch(&report));
+        if report.is_unresolved() {
+            self.reports.push(report);
+        }
+    }
+
+    fn unresolved(&self) -> Vec<PendingReport> {
+        self.restored
+            .iter()
+            .chain(self.reports.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+fn load(path: &Path) -> AppResult<Vec<PendingReport>> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|err| AppError::err(err)),
+        Err(err) if err.kind() == IoErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(AppError::err(err)),
+    }
+}
+
+/// Writes `reports`, or removes the file when there are none
+fn save(path: &Path, reports: &[PendingReport]) -> AppResult<()> {
+    if reports.is_empty() {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() != IoErrorKind::NotFound => Err(AppError::err(err)),
+            _ => Ok(()),
+        };
+    }
+    let content = serde_json::to_vec_pretty(reports).map_err(|err| AppError::err(err))?;
+    checkpoint::write_atomic(path, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pasted() -> PendingReport {
+        let report = BatchReport::from_iter([
+            (Arc::from("a/1.csv"), Ok(())),
+            (
+                Arc::from("a/2.csv"),
+                Err(AppError::message("access denied")),
+            ),
+            (
+                Arc::from("a/3.csv"),
+                Err(AppError::message("storage cap exceeded").with_kind(ErrorKind::Paused)),
+            ),
+        ]);
+        let mut report = PendingReport {
+            remote: "prod".into(),
+            paste: Some(PendingPaste {
+                from: "staging".into(),
+                mode: ClipboardMode::Cut,
+            }),
+            items: report
+                .items
+                .iter()
+                .map(|(key, result)| PendingItem::new(key, result))
+                .collect(),
+        };
+        for item in report.items.iter_mut() {
+            item.to_key = Some(item.key.replace("a/", "b/").into());
+            item.size = 10;
+        }
+        report
+    }
+
+    #[test]
+    fn round_trips_reports() {
+        let dir = std::env::temp_dir().join(format!("balti-pending-{}", std::process::id()));
+        let path = dir.join(PENDING_REPORTS_FILE);
+        assert!(load(&path).unwrap().is_empty());
+
+        let reports = vec![pasted()];
+        save(&path, &reports).unwrap();
+        assert_eq!(load(&path).unwrap(), reports);
+
+        save(&path, &[]).unwrap();
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn retries_paused_items_as_failed() {
+        let pending = pasted();
+        let report = pending.report();
+        assert_eq!(report.succeeded(), 1);
+        assert_eq!(report.paused(), 0);
+        assert_eq!(report.failed(), 2);
+
+        let keys = report
+            .failures()
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        let items = pending.paste_items(&keys);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].to_key.as_ref(), "b/2.csv");
+    }
+
+    #[test]
+    fn keeps_the_latest_unresolved_batch() {
+        let mut reports = PendingReports::default();
+        reports.note_report(pasted());
+        reports.note_report(pasted());
+        assert_eq!(reports.unresolved().len(), 1);
+
+        // a clean retry of the batch resolves it
+        let mut retried = pasted();
+        retried
+            .items
+            .iter_mut()
+            .for_each(|item| item.status = ItemStatus::Ok);
+        reports.note_report(retried);
+        assert!(reports.unresolved().is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use gpui::{App, Window};
+
+use crate::transfers::{OverallProgress, Transfers};
+
+/// Mirrors the overall progress of transfers onto the app's icon, where it stays in
+/// sight while the window is in the background: the taskbar button of `window` on
+/// Windows and a badge on the dock icon on macOS. Does nothing elsewhere.
+pub fn init(window: &Window, cx: &mut App) {
+    let indicator = imp::Indicator::new(window);
+    let mut shown = OverallProgress::Idle;
+
+    let transfers = Transfers::entity(cx);
+    cx.observe(&transfers, move |transfers, cx| {
+        let progress = transfers.read(cx).overall();
+        if progress != shown {
+            indicator.show(progress);
+            shown = progress;
+        }
+    })
+    .detach();
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use gpui::Window;
+    use objc2::MainThreadMarker;
+    use objc2_app_kit::NSApplication;
+    use objc2_foundation::NSString;
+
+    use crate::transfers::OverallProgress;
+
+    pub struct Indicator;
+
+    impl Indicator {
+        pub fn new(_window: &Window) -> Self {
+            Self
+        }
+
+        pub fn show(&self, progress: OverallProgress) {
+            let Some(mtm) = MainThreadMarker::new() else {
+                return;
+            };
+            let label = match progress {
+                OverallProgress::Idle => None,
+                OverallProgress::Running(None) => Some(NSString::from_str("…")),
+                OverallProgress::Running(Some(percent)) => {
+                    Some(NSString::from_str(&format!("{percent}%")))
+                }
+                OverallProgress::Failed => Some(NSString::from_str("!")),
+            };
+            NSApplication::sharedApplication(mtm)
+                .dockTile()
+                .setBadgeLabel(label.as_deref());
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use gpui::Window;
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows::Win32::{
+        Foundation::HWND,
+        System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance},
+        UI::Shell::{
+            ITaskbarList3, TBPF_ERROR, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL,
+            TaskbarList,
+        },
+    };
+
+    use crate::transfers::OverallProgress;
+
+    pub struct Indicator {
+        hwnd: HWND,
+        /// `None` when the shell doesn't offer it, the progress then isn't shown
+        taskbar: Option<ITaskbarList3>,
+    }
+
+    impl Indicator {
+        pub fn new(window: &Window) -> Self {
+            let hwnd = match window.window_handle().map(|handle| handle.as_raw()) {
+                Ok(RawWindowHandle::Win32(handle)) => HWND(handle.hwnd.get() as _),
+                _ => HWND::default(),
+            };
+            // gpui already initialized COM on the main thread
+            let taskbar = unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER) }
+                .and_then(|taskbar: ITaskbarList3| unsafe { taskbar.HrInit() }.map(|_| taskbar))
+                .inspect_err(|err| tracing::warn!("No taskbar progress: {err}"))
+                .ok();
+
+            Self { hwnd, taskbar }
+        }
+
+        pub fn show(&self, progress: OverallProgress) {
+            let Some(taskbar) = self.taskbar.as_ref() else {
+                return;
+            };
+            let (state, percent) = match progress {
+                OverallProgress::Idle => (TBPF_NOPROGRESS, None),
+                OverallProgress::Running(None) => (TBPF_INDETERMINATE, None),
+                OverallProgress::Running(Some(percent)) => (TBPF_NORMAL, Some(percent)),
+                OverallProgress::Failed => (TBPF_ERROR, Some(100)),
+            };
+
+            let result = unsafe {
+                taskbar
+                    .SetProgressState(self.hwnd, state)
+                    .and_then(|_| match percent {
+                        Some(percent) => taskbar.SetProgressValue(self.hwnd, percent as u64, 100),
+                        None => Ok(()),
+                    })
+            };
+            if let Err(err) = result {
+                tracing::warn!("Failed to show taskbar progress: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod imp {
+    use gpui::Window;
+
+    use crate::transfers::OverallProgress;
+
+    pub struct Indicator;
+
+    impl Indicator {
+        pub fn new(_window: &Window) -> Self {
+            Self
+        }
+
+        pub fn show(&self, _progress: OverallProgress) {}
+    }
+}
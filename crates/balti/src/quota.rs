@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use balti_err::{AppError, AppResult, BatchResult, ErrorKind};
+use gpui::{App, Global};
+
+pub fn init(cx: &mut App) {
+    cx.set_global(QuotaGates::default());
+}
+
+/// Pause switches of the remotes' batches, created as batches start on a remote
+#[derive(Default)]
+pub struct QuotaGates {
+    gates: HashMap<Arc<str>, QuotaGate>,
+}
+
+impl Global for QuotaGates {}
+
+impl QuotaGates {
+    /// Gate every batch on `remote` goes through
+    pub fn gate(remote: &Arc<str>, cx: &mut App) -> QuotaGate {
+        cx.global_mut::<Self>()
+            .gates
+            .entry(remote.clone())
+            .or_insert_with(|| QuotaGate::new(remote.clone()))
+            .clone()
+    }
+
+    /// Lets batches on `remote` start items again, the paused ones have to be resumed
+    pub fn resume(remote: &str, cx: &mut App) {
+        if let Some(gate) = cx.global::<Self>().gates.get(remote) {
+            gate.paused.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Closes once a request on the remote hits a quota, batches stop starting items on
+/// it until the user resumes. Shared with the batches running on the runtime.
+#[derive(Debug, Clone)]
+pub struct QuotaGate {
+    remote: Arc<str>,
+    paused: Arc<AtomicBool>,
+}
+
+impl QuotaGate {
+    fn new(remote: Arc<str>) -> Self {
+        Self {
+            remote,
+            paused: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs a batch item's `request` unless one of `gates` is paused. A quota error pauses
+/// the gate of the remote it came from, or all of them when it doesn't tell. Either
+/// way the item counts as paused rather than failed.
+pub async fn gated(
+    gates: &[QuotaGate],
+    request: impl Future<Output = AppResult<()>>,
+) -> AppResult<()> {
+    if gates.iter().any(QuotaGate::is_paused) {
+        return Err(paused());
+    }
+
+    request.await.map_err(|err| pause_on_quota(gates, err))
+}
+
+/// [`gated`] for a request over many `keys`, which all count as paused when a gate
+/// already is. Keys that failed over a quota count as paused too.
+pub async fn gated_batch(
+    gates: &[QuotaGate],
+    keys: &[Arc<str>],
+    request: impl Future<Output = BatchResult>,
+) -> BatchResult {
+    if gates.iter().any(QuotaGate::is_paused) {
+        return BatchResult::failed_with(keys.iter().cloned(), &paused());
+    }
+
+    let mut result = request.await;
+    result.failed = result
+        .failed
+        .into_iter()
+        .map(|(key, err)| (key, pause_on_quota(gates, err)))
+        .collect();
+    result
+}
+
+fn paused() -> AppError {
+    AppError::message("Paused after the provider refused a request over a quota or usage cap")
+        .with_kind(ErrorKind::Paused)
+}
+
+/// Pauses the gate of the remote a quota error came from, or all of them when it
+/// doesn't tell, and turns the error into a paused one. Other errors pass through.
+fn pause_on_quota(gates: &[QuotaGate], err: AppError) -> AppError {
+    if err.kind != ErrorKind::QuotaExceeded {
+        return err;
+    }
+
+    let remote = err.context.as_ref().map(|context| &context.remote);
+    gates
+        .iter()
+        .filter(|gate| remote.is_none_or(|remote| *remote == gate.remote))
+        .for_each(|gate| gate.paused.store(true, Ordering::Relaxed));
+    err.with_kind(ErrorKind::Paused)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn quota_error(remote: &str) -> AppResult<()> {
+        Err(AppError::message("storage cap exceeded")
+            .with_kind(ErrorKind::QuotaExceeded)
+            .with_context("copy_object", remote))
+    }
+
+    #[test]
+    fn pauses_after_a_quota_error() {
+        let gate = QuotaGate::new("b2".into());
+        let gates = [gate.clone()];
+
+        assert!(block_on(gated(&gates, async { Ok(()) })).is_ok());
+        let err = block_on(gated(&gates, async { quota_error("b2") })).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Paused);
+        assert_eq!(err.message, "storage cap exceeded");
+        assert!(gate.is_paused());
+
+        let err = block_on(gated(&gates, async { unreachable!("ran while paused") })).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Paused);
+
+        gate.paused.store(false, Ordering::Relaxed);
+        assert!(block_on(gated(&gates, async { Ok(()) })).is_ok());
+    }
+
+    #[test]
+    fn pauses_the_remote_the_error_came_from() {
+        let (from, to) = (QuotaGate::new("b2".into()), QuotaGate::new("minio".into()));
+        let gates = [from.clone(), to.clone()];
+
+        let _ = block_on(gated(&gates, async { quota_error("minio") }));
+        assert!(!from.is_paused() && to.is_paused());
+
+        let _ = block_on(gated(&[from.clone()], async {
+            Err(AppError::message("quota").with_kind(ErrorKind::QuotaExceeded))
+        }));
+        assert!(from.is_paused());
+
+        let failed = block_on(gated(&[QuotaGate::new("r2".into())], async {
+            Err(AppError::message("Access Denied").with_kind(ErrorKind::AccessDenied))
+        }));
+        assert_eq!(failed.unwrap_err().kind, ErrorKind::AccessDenied);
+    }
+
+    #[test]
+    fn pauses_a_batch_over_a_quota() {
+        let gate = QuotaGate::new("b2".into());
+        let gates = [gate.clone()];
+        let keys = [Arc::from("a.txt"), Arc::from("b.txt")];
+
+        let result = block_on(gated_batch(&gates, &keys, async {
+            BatchResult {
+                succeeded: vec![keys[0].clone()],
+                failed: vec![(keys[1].clone(), quota_error("b2").unwrap_err())],
+            }
+        }));
+        assert_eq!(result.succeeded, [keys[0].clone()]);
+        assert_eq!(result.failed[0].1.kind, ErrorKind::Paused);
+        assert!(gate.is_paused());
+
+        let result = block_on(gated_batch(&gates, &keys, async {
+            unreachable!("ran while paused")
+        }));
+        assert!(result.succeeded.is_empty());
+        assert!(
+            result
+                .failed
+                .iter()
+                .all(|(_, err)| err.kind == ErrorKind::Paused)
+        );
+    }
+}
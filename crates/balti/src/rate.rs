@@ -0,0 +1,202 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Throughput is averaged over samples this recent
+pub const RATE_WINDOW: Duration = Duration::from_secs(5);
+/// A transfer without progress for this long is reported as stalled
+pub const STALL_AFTER: Duration = Duration::from_secs(10);
+
+/// Bounds the ring buffer when progress is reported very often
+const MAX_SAMPLES: usize = 64;
+
+/// Moving average throughput of a transfer, fed with its running byte total
+#[derive(Debug, Clone, Default)]
+pub struct TransferRate {
+    samples: VecDeque<(Instant, u64)>,
+    /// When the byte total last grew, or when sampling started
+    last_progress: Option<Instant>,
+}
+
+impl TransferRate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `bytes_done`, the total transferred so far, as of `now`
+    pub fn record(&mut self, now: Instant, bytes_done: u64) {
+        match self.samples.back() {
+            Some((_, last)) if *last >= bytes_done => {}
+            _ => self.last_progress = Some(now),
+        }
+
+        self.samples.push_back((now, bytes_done));
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        // keep one sample at or before the window start as the baseline
+        while self.samples.len() > 2
+            && self
+                .samples
+                .get(1)
+                .is_some_and(|(at, _)| now.duration_since(*at) >= RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Average bytes per second over the window, `None` until two samples apart in time exist
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        let (first_at, first) = self.samples.front()?;
+        let (last_at, last) = self.samples.back()?;
+
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        if elapsed <= 0. {
+            return None;
+        }
+        Some(last.saturating_sub(*first) as f64 / elapsed)
+    }
+
+    /// Time left for `remaining` bytes at the current rate
+    pub fn eta(&self, remaining: u64) -> Option<Duration> {
+        if remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+        let rate = self.bytes_per_sec().filter(|rate| *rate > 0.)?;
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+
+    pub fn is_stalled(&self, now: Instant) -> bool {
+        self.last_progress
+            .is_some_and(|at| now.duration_since(at) >= STALL_AFTER)
+    }
+}
+
+/// Compact duration for ETAs, like `45s`, `2m 10s` or `1h 5m`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s}s"),
+        (h, m, _) => format!("{h}h {m}m"),
+    }
+}
+
+/// Reads a duration written like [`format_duration`] does, plus days: `90s`, `15m`,
+/// `1h 30m` or `7d`. `None` unless every part has a unit.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut secs = 0u64;
+    let mut parts = input.split_whitespace().peekable();
+    parts.peek()?;
+    for part in parts {
+        let unit_at = part.find(|c: char| !c.is_ascii_digit())?;
+        let (value, unit) = part.split_at(unit_at);
+        let unit_secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            "d" => 24 * 3600,
+            _ => return None,
+        };
+        secs = secs.checked_add(value.parse::<u64>().ok()?.checked_mul(unit_secs)?)?;
+    }
+    Some(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: f64) -> Duration {
+        Duration::from_secs_f64(s)
+    }
+
+    #[test]
+    fn no_rate_until_time_passes() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+        assert_eq!(rate.bytes_per_sec(), None);
+
+        rate.record(start, 100);
+        assert_eq!(rate.bytes_per_sec(), None);
+        assert_eq!(rate.eta(100), None);
+    }
+
+    #[test]
+    fn averages_over_window() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+        for i in 0..=10 {
+            rate.record(start + secs(i as f64), i * 1000);
+        }
+
+        assert_eq!(rate.bytes_per_sec(), Some(1000.));
+        assert_eq!(rate.eta(5000), Some(secs(5.)));
+        assert_eq!(rate.eta(0), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn old_samples_fall_out_of_window() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+        // fast start, then a slower steady rate
+        rate.record(start, 0);
+        rate.record(start + secs(1.), 100_000);
+        for i in 2..=20 {
+            rate.record(start + secs(i as f64), 100_000 + (i - 1) * 10);
+        }
+
+        let bytes_per_sec = rate.bytes_per_sec().unwrap();
+        assert!((bytes_per_sec - 10.).abs() < 1e-9, "{bytes_per_sec}");
+    }
+
+    #[test]
+    fn stalls_without_progress() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+        rate.record(start, 0);
+        rate.record(start + secs(1.), 500);
+        assert!(!rate.is_stalled(start + secs(2.)));
+
+        // repeated totals are not progress
+        rate.record(start + secs(5.), 500);
+        assert!(!rate.is_stalled(start + secs(10.)));
+        assert!(rate.is_stalled(start + secs(11.)));
+
+        rate.record(start + secs(12.), 600);
+        assert!(!rate.is_stalled(start + secs(12.)));
+    }
+
+    #[test]
+    fn stalled_before_first_byte() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new();
+        assert!(!rate.is_stalled(start + secs(60.)));
+
+        rate.record(start, 0);
+        assert!(rate.is_stalled(start + STALL_AFTER));
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(secs(0.)), "0s");
+        assert_eq!(format_duration(secs(45.)), "45s");
+        assert_eq!(format_duration(secs(130.)), "2m 10s");
+        assert_eq!(format_duration(secs(3900.)), "1h 5m");
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("15m"), Some(secs(900.)));
+        assert_eq!(parse_duration(" 1h 30m "), Some(secs(5400.)));
+        assert_eq!(parse_duration("7d"), Some(secs(7. * 24. * 3600.)));
+        assert_eq!(parse_duration("45s"), Some(secs(45.)));
+
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("15"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("2w"), None);
+        assert_eq!(parse_duration("1.5h"), None);
+    }
+}
@@ -0,0 +1,195 @@
+use std::{collections::HashSet, sync::Arc};
+
+use balti_s3::{__S3Object, ObjectKind, S3Object};
+
+use crate::settings::ListingOrder;
+
+/// What mutations changed in a listed prefix. Applied to the shown listing right away,
+/// providers with eventual consistency may still list the old state for a moment.
+#[derive(Debug, Default)]
+pub struct Expected {
+    pub removed: HashSet<Arc<str>>,
+    pub added: Vec<S3Object>,
+}
+
+impl Expected {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+
+    /// Folds in a later change, which wins where the two disagree
+    pub fn merge(&mut self, later: Expected) {
+        self.added
+            .retain(|object| !later.removed.contains(object.key()));
+        self.removed
+            .retain(|key| !later.added.iter().any(|object| object.key() == key));
+        self.removed.extend(later.removed);
+        self.added.extend(later.added);
+    }
+
+    /// Applies the change to `objects`, keeping them in `order`. Unless `complete`,
+    /// additions past the last loaded key are left to the pages still to come.
+    pub fn apply(&self, objects: &mut Vec<S3Object>, order: ListingOrder, complete: bool) {
+        objects.retain(|object| !self.removed.contains(object.key()));
+
+        // pages come in key order whatever order they're shown in
+        let last = last_key(objects);
+        for object in self.added.iter() {
+            if !complete && last.as_ref().is_none_or(|last| object.key() > last) {
+                continue;
+            }
+            if let Err(index) = objects.binary_search_by(|listed| order.compare(listed, object)) {
+                objects.insert(index, object.clone());
+            }
+        }
+    }
+
+    /// Keys a fresh listing disagrees on, removed ones still listed and added ones
+    /// missing. Unless `complete`, additions past the last listed key aren't checked.
+    pub fn discrepancies(&self, listed: &[S3Object], complete: bool) -> Vec<Arc<str>> {
+        let keys = listed
+            .iter()
+            .map(|object| object.key().as_ref())
+            .collect::<HashSet<_>>();
+        let last = last_key(listed);
+
+        let stale = self
+            .removed
+            .iter()
+            .filter(|key| keys.contains(key.as_ref()))
+            .cloned();
+        let missing = self
+            .added
+            .iter()
+            .map(|object| object.key())
+            .filter(|key| complete || last.as_ref().is_some_and(|last| *key <= last))
+            .filter(|key| !keys.contains(key.as_ref()))
+            .cloned();
+        stale.chain(missing).collect()
+    }
+}
+
+/// Largest key loaded so far, pages are listed up to it
+pub fn last_key(objects: &[S3Object]) -> Option<Arc<str>> {
+    objects.iter().map(|object| object.key()).max().cloned()
+}
+
+/// How `key`, written with `size`, shows in the listing of `prefix` (`""` or `a/b/`):
+/// the file itself, or the folder of the listing leading to it. `None` when it's
+/// outside the prefix or a folder marker, which isn't listed.
+pub fn listed_entry(prefix: &str, key: &str, size: i64) -> Option<S3Object> {
+    let relative = key.strip_prefix(prefix)?;
+    match relative.split_once('/') {
+        Some(("", _)) => None,
+        Some((folder, _)) => Some(Arc::new(__S3Object::Folder(
+            format!("{prefix}{folder}/").into(),
+        ))),
+        None if relative.is_empty()
+            || ObjectKind::of_key(key, size) == ObjectKind::FolderMarker =>
+        {
+            None
+        }
+        None => Some(Arc::new(__S3Object::file(key.into(), size, None, None))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(key: &str) -> S3Object {
+        Arc::new(__S3Object::Folder(key.into()))
+    }
+
+    fn file(key: &str) -> S3Object {
+        listed_entry("", key, 1).unwrap()
+    }
+
+    fn keys(objects: &[S3Object]) -> Vec<&str> {
+        objects.iter().map(|object| object.key().as_ref()).collect()
+    }
+
+    #[test]
+    fn applies_changes_in_key_order() {
+        let mut objects = vec![folder("a/"), file("b.txt"), file("d.txt")];
+        let mut expected = Expected {
+            removed: HashSet::from([Arc::from("b.txt")]),
+            added: vec![file("c.txt"), folder("a/")],
+        };
+        expected.merge(Expected {
+            removed: HashSet::new(),
+            added: vec![file("b.txt"), file("z.txt")],
+        });
+        assert!(expected.removed.is_empty());
+
+        expected.apply(&mut objects, ListingOrder::Raw, false);
+        assert_eq!(keys(&objects), ["a/", "b.txt", "c.txt", "d.txt"]);
+
+        expected.apply(&mut objects, ListingOrder::Raw, true);
+        assert_eq!(keys(&objects), ["a/", "b.txt", "c.txt", "d.txt", "z.txt"]);
+    }
+
+    #[test]
+    fn applies_changes_in_natural_order() {
+        let mut objects = vec![folder("b/"), file("a1.txt"), file("a10.txt")];
+        let expected = Expected {
+            removed: HashSet::new(),
+            added: vec![
+                file("A2.txt"),
+                folder("a/"),
+                file("b1.txt"),
+                file("a10.txt"),
+            ],
+        };
+
+        // "b1.txt" sorts past "b/", the last loaded key
+        expected.apply(&mut objects, ListingOrder::Natural, false);
+        assert_eq!(keys(&objects), ["a/", "b/", "a1.txt", "A2.txt", "a10.txt"]);
+
+        expected.apply(&mut objects, ListingOrder::Natural, true);
+        assert_eq!(
+            keys(&objects),
+            ["a/", "b/", "a1.txt", "A2.txt", "a10.txt", "b1.txt"]
+        );
+    }
+
+    #[test]
+    fn finds_stale_and_missing_keys() {
+        let expected = Expected {
+            removed: HashSet::from([Arc::from("old.txt")]),
+            added: vec![file("new.txt"), file("zz.txt")],
+        };
+        let listed = [file("a.txt"), file("old.txt"), file("x.txt")];
+
+        assert_eq!(
+            expected.discrepancies(&listed, false),
+            [Arc::from("old.txt"), Arc::from("new.txt")]
+        );
+        assert_eq!(expected.discrepancies(&listed, true).len(), 3);
+        assert!(
+            expected
+                .discrepancies(&[file("new.txt"), file("zz.txt")], true)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn maps_written_keys_to_listed_entries() {
+        let key = |object: Option<S3Object>| object.map(|object| object.key().to_string());
+
+        assert_eq!(
+            key(listed_entry("logs/", "logs/a.txt", 3)).as_deref(),
+            Some("logs/a.txt")
+        );
+        assert_eq!(
+            key(listed_entry("logs/", "logs/old/b.txt", 3)).as_deref(),
+            Some("logs/old/")
+        );
+        assert_eq!(
+            key(listed_entry("", "new/__fd.dat", 2)).as_deref(),
+            Some("new/")
+        );
+        assert!(listed_entry("logs/", "logs/__fd.dat", 2).is_none());
+        assert!(listed_entry("logs/", "other/a.txt", 3).is_none());
+    }
+}
@@ -0,0 +1,685 @@
+use std::{path::Path, sync::Arc};
+
+use balti_err::AppResult;
+use balti_s3::{
+    CustomHeader, EndpointUrl, MAX_PAGE_SIZE, ProxySettings, RemoteMatch, RemoteTarget, S3Config,
+    UploadDefaults, endpoint_for_region, endpoint_url, normalize_root_prefix,
+    parse_credentials_expiry, parse_object_locator, read_ca_bundle, validate_header,
+};
+use chrono::SecondsFormat;
+
+/// Field of the remote dialog, for telling which one an error belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    RemoteName,
+    AccessKeyId,
+    SecretAccessKey,
+    CredentialsExpiry,
+    Endpoint,
+    BucketName,
+    PageSize,
+    Headers,
+    Proxy,
+    Timeout,
+    CaBundle,
+    UploadDefaults,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    /// Required field left blank
+    Missing(Field),
+    /// Field that doesn't parse, with why when there's more to say than that
+    Invalid(Field, Option<String>),
+}
+
+impl FieldError {
+    pub fn field(&self) -> Field {
+        match self {
+            Self::Missing(field) | Self::Invalid(field, _) => *field,
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Invalid(_, Some(message)) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// Value read from the page size field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageSize {
+    Default,
+    Value { value: i32, clamped: bool },
+    Invalid,
+}
+
+impl PageSize {
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim();
+        if input.is_empty() {
+            return Self::Default;
+        }
+
+        match input.parse::<i64>() {
+            Ok(raw) => {
+                let value = raw.clamp(1, MAX_PAGE_SIZE as i64) as i32;
+                Self::Value {
+                    value,
+                    clamped: value as i64 != raw,
+                }
+            }
+            Err(_) => Self::Invalid,
+        }
+    }
+
+    pub fn value(&self) -> Option<i32> {
+        match self {
+            Self::Value { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Custom header row as typed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderValues {
+    pub name: String,
+    pub value: String,
+    pub secret: bool,
+}
+
+impl HeaderValues {
+    /// Rows left blank are ignored
+    pub fn is_blank(&self) -> bool {
+        self.name.trim().is_empty() && self.value.is_empty()
+    }
+
+    fn header(&self) -> CustomHeader {
+        CustomHeader {
+            name: self.name.trim().into(),
+            value: self.value.as_str().into(),
+            secret: self.secret,
+        }
+    }
+}
+
+/// AWS endpoints the region of a pasted AWS URL is put into, China has its own
+const AWS_ENDPOINT: &str = "https://s3.amazonaws.com";
+const AWS_CHINA_ENDPOINT: &str = "https://s3.amazonaws.com.cn";
+
+/// Bucket a pasted `s3://` or `https://` URL points into, to fill in a new remote with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PastedLocation {
+    pub bucket_name: String,
+    /// Folder of the key the URL names, blank at the bucket root
+    pub root_prefix: String,
+    /// Only AWS URLs name the region
+    pub region: Option<String>,
+    /// `None` when the URL doesn't tell, like `s3://` ones
+    pub endpoint: Option<String>,
+}
+
+impl PastedLocation {
+    /// `None` when the input names no bucket, bare keys included
+    pub fn parse(input: &str) -> Option<Self> {
+        let (matched, key) = parse_object_locator(input, std::iter::empty())?;
+        let RemoteMatch::Bucket {
+            bucket,
+            region,
+            endpoint,
+        } = matched
+        else {
+            return None;
+        };
+
+        let root_prefix = key.rfind('/').map_or("", |at| &key[..=at]).to_owned();
+        let endpoint = endpoint.or_else(|| {
+            region.as_deref().map(|region| {
+                let aws = if region.starts_with("cn-") {
+                    AWS_CHINA_ENDPOINT
+                } else {
+                    AWS_ENDPOINT
+                };
+                endpoint_for_region(aws, region)
+            })
+        });
+        Some(Self {
+            bucket_name: bucket,
+            root_prefix,
+            region,
+            endpoint,
+        })
+    }
+}
+
+/// Everything typed into the remote dialog, read into a config once it's valid
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormValues {
+    pub remote_name: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Blank unless the keys are temporary
+    pub session_token: String,
+    pub credentials_expiry: String,
+    /// Blank for `auto`
+    pub region: String,
+    pub endpoint: String,
+    pub bucket_name: String,
+    pub root_prefix: String,
+    /// Blank for the app's page size
+    pub page_size: String,
+    /// Blank rows left out
+    pub headers: Vec<HeaderValues>,
+    pub proxy_url: String,
+    pub proxy_username: String,
+    pub proxy_password: String,
+    pub no_proxy: String,
+    /// Blank for the default timeout
+    pub timeout_secs: String,
+    pub ca_bundle: String,
+    pub insecure_tls: bool,
+    /// Blank for the bucket's defaults
+    pub storage_class: String,
+    pub encryption: String,
+    pub kms_key_id: String,
+    pub acl: String,
+    pub detect_content_type: bool,
+}
+
+impl FormValues {
+    /// Fields as they show the remote when editing it
+    pub fn from_config(remote_name: &str, config: &S3Config) -> Self {
+        let proxy = config.proxy.as_ref();
+        let proxy_field = |field: fn(&ProxySettings) -> &Option<Arc<str>>| -> String {
+            proxy
+                .and_then(|proxy| field(proxy).as_deref())
+                .unwrap_or_default()
+                .to_owned()
+        };
+        let defaults = &config.upload_defaults;
+        let upload_field =
+            |field: &Option<Arc<str>>| field.as_deref().unwrap_or_default().to_owned();
+
+        Self {
+            remote_name: remote_name.to_owned(),
+            access_key_id: config.access_key_id.to_string(),
+            secret_access_key: config.secret_access_key.to_string(),
+            session_token: config
+                .session_token
+                .as_deref()
+                .unwrap_or_default()
+                .to_owned(),
+            credentials_expiry: config
+                .credentials_expiry
+                .map(|expiry| expiry.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default(),
+            region: match config.region.as_ref() {
+                "auto" => String::new(),
+                region => region.to_owned(),
+            },
+            endpoint: config.endpoint.to_string(),
+            bucket_name: config.bucket_name.to_string(),
+            root_prefix: config.root_prefix.as_deref().unwrap_or_default().to_owned(),
+            page_size: config
+                .page_size
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            headers: config
+                .headers
+                .iter()
+                .map(|header| HeaderValues {
+                    name: header.name.to_string(),
+                    value: header.value.to_string(),
+                    secret: header.secret,
+                })
+                .collect(),
+            proxy_url: proxy.map(|proxy| proxy.url.to_string()).unwrap_or_default(),
+            proxy_username: proxy_field(|proxy| &proxy.username),
+            proxy_password: proxy_field(|proxy| &proxy.password),
+            no_proxy: proxy_field(|proxy| &proxy.no_proxy),
+            timeout_secs: config
+                .timeout_secs
+                .map(|secs| secs.to_string())
+                .unwrap_or_default(),
+            ca_bundle: config.ca_bundle.as_deref().unwrap_or_default().to_owned(),
+            insecure_tls: config.insecure_tls,
+            storage_class: upload_field(&defaults.storage_class),
+            encryption: upload_field(&defaults.encryption),
+            kms_key_id: upload_field(&defaults.kms_key_id),
+            acl: upload_field(&defaults.acl),
+            detect_content_type: defaults.detect_content_type,
+        }
+    }
+
+    /// Any of the rarely needed settings is set
+    pub fn uses_advanced(&self) -> bool {
+        !self.headers.is_empty()
+            || !self.proxy_url.trim().is_empty()
+            || !self.timeout_secs.trim().is_empty()
+            || !self.ca_bundle.is_empty()
+            || self.insecure_tls
+    }
+
+    /// Any upload default is set
+    pub fn uses_upload_defaults(&self) -> bool {
+        !self.upload_defaults().is_default()
+    }
+
+    pub fn page_size(&self) -> PageSize {
+        PageSize::parse(&self.page_size)
+    }
+
+    /// `None` while the endpoint is blank
+    pub fn endpoint(&self) -> Option<AppResult<EndpointUrl>> {
+        (!self.endpoint.trim().is_empty()).then(|| EndpointUrl::parse(&self.endpoint))
+    }
+
+    pub fn target(&self) -> RemoteTarget {
+        RemoteTarget::new(&self.endpoint, &self.bucket_name, &self.root_prefix)
+    }
+
+    fn headers(&self) -> Vec<CustomHeader> {
+        self.headers.iter().map(HeaderValues::header).collect()
+    }
+
+    /// `None` when the url is left blank, the app wide proxy applies then
+    fn proxy(&self) -> Option<ProxySettings> {
+        let url = self.proxy_url.trim();
+        if url.is_empty() {
+            return None;
+        }
+
+        let optional = |value: &str| (!value.trim().is_empty()).then(|| value.trim().into());
+        Some(ProxySettings {
+            url: url.into(),
+            username: optional(&self.proxy_username),
+            password: optional(&self.proxy_password),
+            no_proxy: optional(&self.no_proxy),
+        })
+    }
+
+    fn upload_defaults(&self) -> UploadDefaults {
+        let optional = |value: &str| (!value.trim().is_empty()).then(|| value.trim().into());
+        UploadDefaults {
+            storage_class: optional(&self.storage_class),
+            encryption: optional(&self.encryption),
+            kms_key_id: optional(&self.kms_key_id),
+            acl: optional(&self.acl),
+            detect_content_type: self.detect_content_type,
+        }
+    }
+
+    /// `Ok(None)` while blank, an error unless it's a whole number of seconds above 0
+    fn timeout_secs(&self) -> Result<Option<u64>, ()> {
+        let secs = self.timeout_secs.trim();
+        if secs.is_empty() {
+            return Ok(None);
+        }
+        match secs.parse::<u64>() {
+            Ok(secs) if secs > 0 => Ok(Some(secs)),
+            _ => Err(()),
+        }
+    }
+
+    fn session_token(&self) -> Option<Arc<str>> {
+        let token = self.session_token.trim();
+        (!token.is_empty()).then(|| token.into())
+    }
+
+    fn ca_bundle(&self) -> Option<Arc<str>> {
+        let path = self.ca_bundle.trim();
+        (!path.is_empty()).then(|| path.into())
+    }
+
+    /// Problems of every field, in the order they're laid out
+    pub fn errors(&self) -> Vec<FieldError> {
+        let mut errors = [
+            (Field::RemoteName, &self.remote_name),
+            (Field::AccessKeyId, &self.access_key_id),
+            (Field::SecretAccessKey, &self.secret_access_key),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_empty())
+        .map(|(field, _)| FieldError::Missing(field))
+        .collect::<Vec<_>>();
+
+        if let Err(err) = parse_credentials_expiry(&self.credentials_expiry) {
+            errors.push(FieldError::Invalid(
+                Field::CredentialsExpiry,
+                Some(err.message),
+            ));
+        }
+        match self.endpoint() {
+            None => errors.push(FieldError::Missing(Field::Endpoint)),
+            Some(Err(err)) => errors.push(FieldError::Invalid(Field::Endpoint, Some(err.message))),
+            Some(Ok(_)) => {}
+        }
+        if self.bucket_name.is_empty() {
+            errors.push(FieldError::Missing(Field::BucketName));
+        }
+        if self.page_size() == PageSize::Invalid {
+            errors.push(FieldError::Invalid(Field::PageSize, None));
+        }
+
+        let headers = self.headers();
+        if let Some(err) = headers
+            .iter()
+            .find_map(|h| validate_header(&h.name, &h.value).err())
+        {
+            errors.push(FieldError::Invalid(Field::Headers, Some(err.message)));
+        }
+        if let Some(err) = self.proxy().and_then(|proxy| proxy.validate().err()) {
+            errors.push(FieldError::Invalid(Field::Proxy, Some(err.message)));
+        }
+        if self.timeout_secs().is_err() {
+            errors.push(FieldError::Invalid(Field::Timeout, None));
+        }
+        if let Some(err) = self
+            .ca_bundle()
+            .and_then(|path| read_ca_bundle(Path::new(path.as_ref())).err())
+        {
+            errors.push(FieldError::Invalid(Field::CaBundle, Some(err.message)));
+        }
+        if let Err(err) = self.upload_defaults().validate() {
+            errors.push(FieldError::Invalid(
+                Field::UploadDefaults,
+                Some(err.message),
+            ));
+        }
+        errors
+    }
+
+    /// Config of the remote, or the problems of every field that keeps it from one
+    pub fn read_config(&self) -> Result<S3Config, Vec<FieldError>> {
+        let errors = self.errors();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(self.config())
+    }
+
+    /// Config to look up the buckets the keys can see with, before the remote is named
+    /// or a bucket picked. `None` until the rest of the form is valid.
+    pub fn bucket_lookup_config(&self) -> Option<S3Config> {
+        self.errors()
+            .iter()
+            .all(|error| matches!(error.field(), Field::RemoteName | Field::BucketName))
+            .then(|| self.config())
+    }
+
+    fn config(&self) -> S3Config {
+        let region = match self.region.trim() {
+            "" => "auto",
+            region => region,
+        };
+        S3Config {
+            access_key_id: self.access_key_id.as_str().into(),
+            secret_access_key: self.secret_access_key.as_str().into(),
+            session_token: self.session_token(),
+            region: region.into(),
+            endpoint: endpoint_url(&self.endpoint).into(),
+            bucket_name: self.bucket_name.as_str().into(),
+            page_size: self.page_size().value(),
+            root_prefix: normalize_root_prefix(&self.root_prefix),
+            headers: self.headers(),
+            proxy: self.proxy(),
+            ca_bundle: self.ca_bundle(),
+            insecure_tls: self.insecure_tls,
+            timeout_secs: self.timeout_secs().ok().flatten(),
+            credentials_expiry: parse_credentials_expiry(&self.credentials_expiry)
+                .ok()
+                .flatten(),
+            upload_defaults: self.upload_defaults(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled() -> FormValues {
+        FormValues {
+            remote_name: "media".into(),
+            access_key_id: "AKIA123".into(),
+            secret_access_key: "hunter2".into(),
+            endpoint: "https://s3.example.com/".into(),
+            bucket_name: "team-bucket".into(),
+            ..Default::default()
+        }
+    }
+
+    fn pasted(
+        bucket_name: &str,
+        root_prefix: &str,
+        region: Option<&str>,
+        endpoint: Option<&str>,
+    ) -> Option<PastedLocation> {
+        Some(PastedLocation {
+            bucket_name: bucket_name.to_owned(),
+            root_prefix: root_prefix.to_owned(),
+            region: region.map(str::to_owned),
+            endpoint: endpoint.map(str::to_owned),
+        })
+    }
+
+    fn fields(errors: &[FieldError]) -> Vec<Field> {
+        errors.iter().map(FieldError::field).collect()
+    }
+
+    #[test]
+    fn reads_filled_in_forms() {
+        let mut values = filled();
+        values.root_prefix = "/reports/".into();
+        values.page_size = " 250 ".into();
+        values.headers.push(HeaderValues {
+            name: " X-Team ".into(),
+            value: "data".into(),
+            secret: false,
+        });
+
+        let config = values.read_config().unwrap();
+        assert_eq!(config.region.as_ref(), "auto");
+        assert_eq!(config.endpoint.as_ref(), "https://s3.example.com");
+        assert_eq!(config.root_prefix.as_deref(), Some("reports/"));
+        assert_eq!(config.page_size, Some(250));
+        assert_eq!(config.headers[0].name.as_ref(), "X-Team");
+        assert!(config.proxy.is_none() && config.ca_bundle.is_none());
+        assert!(config.credentials_expiry.is_none());
+        assert!(config.session_token.is_none());
+        assert!(config.timeout_secs.is_none());
+    }
+
+    #[test]
+    fn reads_timeouts() {
+        let mut values = filled();
+        values.timeout_secs = " 5 ".into();
+        assert_eq!(values.read_config().unwrap().timeout_secs, Some(5));
+        assert!(values.uses_advanced());
+
+        values.timeout_secs = "1.5".into();
+        assert_eq!(fields(&values.read_config().unwrap_err()), [Field::Timeout]);
+    }
+
+    #[test]
+    fn keeps_session_tokens() {
+        let mut values = filled();
+        values.session_token = " FwoGZXIvYXdzEB4a\n".into();
+        let config = values.read_config().unwrap();
+        assert_eq!(config.session_token.as_deref(), Some("FwoGZXIvYXdzEB4a"));
+
+        let values = FormValues::from_config("media", &config);
+        assert_eq!(values.session_token, "FwoGZXIvYXdzEB4a");
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let values = FormValues {
+            credentials_expiry: "tomorrow".into(),
+            endpoint: "s3.example.com".into(),
+            page_size: "lots".into(),
+            headers: vec![HeaderValues {
+                name: "Bad Header".into(),
+                value: "x".into(),
+                secret: false,
+            }],
+            proxy_url: "not a url".into(),
+            timeout_secs: "0".into(),
+            ca_bundle: "/nonexistent/balti-ca.pem".into(),
+            ..Default::default()
+        };
+
+        let errors = values.read_config().unwrap_err();
+        assert_eq!(
+            fields(&errors),
+            [
+                Field::RemoteName,
+                Field::AccessKeyId,
+                Field::SecretAccessKey,
+                Field::CredentialsExpiry,
+                Field::Endpoint,
+                Field::BucketName,
+                Field::PageSize,
+                Field::Headers,
+                Field::Proxy,
+                Field::Timeout,
+                Field::CaBundle,
+            ]
+        );
+        assert!(matches!(errors[0], FieldError::Missing(Field::RemoteName)));
+        assert!(errors[4].message().is_some());
+        assert_eq!(errors[6], FieldError::Invalid(Field::PageSize, None));
+    }
+
+    #[test]
+    fn clamps_page_sizes() {
+        assert_eq!(PageSize::parse(""), PageSize::Default);
+        assert_eq!(
+            PageSize::parse("0"),
+            PageSize::Value {
+                value: 1,
+                clamped: true
+            }
+        );
+        assert_eq!(PageSize::parse("x"), PageSize::Invalid);
+
+        let mut values = filled();
+        values.page_size = (MAX_PAGE_SIZE as i64 + 1).to_string();
+        assert_eq!(values.read_config().unwrap().page_size, Some(MAX_PAGE_SIZE));
+    }
+
+    #[test]
+    fn round_trips_configs() {
+        let mut values = filled();
+        values.endpoint = "https://s3.example.com".into();
+        values.region = "eu-west-1".into();
+        values.credentials_expiry = "2026-01-01T12:00:00Z".into();
+        values.page_size = "100".into();
+        values.proxy_url = "http://proxy.corp:3128".into();
+        values.no_proxy = "localhost".into();
+        values.insecure_tls = true;
+
+        let config = values.read_config().unwrap();
+        assert_eq!(
+            FormValues::from_config(&values.remote_name, &config),
+            values
+        );
+        assert!(values.uses_advanced());
+
+        // the default region shows as blank
+        let config = filled().read_config().unwrap();
+        assert_eq!(FormValues::from_config("media", &config).region, "");
+        assert!(!filled().uses_advanced());
+    }
+
+    #[test]
+    fn looks_up_buckets_before_one_is_picked() {
+        let mut values = FormValues {
+            remote_name: String::new(),
+            bucket_name: String::new(),
+            ..filled()
+        };
+        let config = values.bucket_lookup_config().unwrap();
+        assert_eq!(config.access_key_id.as_ref(), "AKIA123");
+        assert!(values.read_config().is_err());
+
+        values.secret_access_key.clear();
+        assert!(values.bucket_lookup_config().is_none());
+    }
+
+    #[test]
+    fn reads_upload_defaults() {
+        let mut values = filled();
+        values.endpoint = "https://s3.example.com".into();
+        assert!(!values.uses_upload_defaults());
+
+        values.storage_class = " STANDARD_IA ".into();
+        values.encryption = "aws:kms".into();
+        values.detect_content_type = true;
+        let config = values.read_config().unwrap();
+        let defaults = &config.upload_defaults;
+        assert_eq!(defaults.storage_class.as_deref(), Some("STANDARD_IA"));
+        assert_eq!(defaults.encryption.as_deref(), Some("aws:kms"));
+        assert!(defaults.kms_key_id.is_none() && defaults.acl.is_none());
+        assert!(values.uses_upload_defaults());
+
+        values.storage_class = "STANDARD_IA".into();
+        assert_eq!(FormValues::from_config("media", &config), values);
+
+        values.acl = "everyone".into();
+        let errors = values.read_config().unwrap_err();
+        assert_eq!(fields(&errors), [Field::UploadDefaults]);
+        assert!(errors[0].message().is_some());
+    }
+
+    #[test]
+    fn reads_pasted_locations() {
+        assert_eq!(
+            PastedLocation::parse("s3://acme-data-bucket/reports/"),
+            pasted("acme-data-bucket", "reports/", None, None)
+        );
+        assert_eq!(
+            PastedLocation::parse("s3://acme-data-bucket/reports/2024/q1.csv"),
+            pasted("acme-data-bucket", "reports/2024/", None, None)
+        );
+        assert_eq!(
+            PastedLocation::parse("s3://acme-data-bucket"),
+            pasted("acme-data-bucket", "", None, None)
+        );
+        assert_eq!(
+            PastedLocation::parse("https://acme.s3.eu-west-1.amazonaws.com/logs/a.txt"),
+            pasted(
+                "acme",
+                "logs/",
+                Some("eu-west-1"),
+                Some("https://s3.eu-west-1.amazonaws.com")
+            )
+        );
+        assert_eq!(
+            PastedLocation::parse(
+                "https://s3.console.aws.amazon.com/s3/buckets/acme?region=ap-south-1&prefix=team/shared/"
+            ),
+            pasted(
+                "acme",
+                "team/shared/",
+                Some("ap-south-1"),
+                Some("https://s3.ap-south-1.amazonaws.com")
+            )
+        );
+        assert_eq!(
+            PastedLocation::parse("http://192.168.1.50:9000/media/videos/"),
+            pasted("media", "videos/", None, Some("http://192.168.1.50:9000"))
+        );
+    }
+
+    #[test]
+    fn rejects_pastes_naming_no_bucket() {
+        assert_eq!(PastedLocation::parse("reports/2024/"), None);
+        assert_eq!(PastedLocation::parse("s3:///reports/"), None);
+        assert_eq!(PastedLocation::parse("ftp://files.example.org/a/"), None);
+        assert_eq!(PastedLocation::parse("   "), None);
+    }
+}
@@ -1,4 +1,7 @@
 use gpui::*;
+use tokio::{runtime::Handle, task::JoinHandle};
+
+use crate::coalesce::OperationId;
 
 pub fn init(cx: &mut App) {
     cx.set_global(GlobalTokio::new());
@@ -23,25 +26,117 @@ impl GlobalTokio {
             .expect("Failed to build tokio");
         Self { rt }
     }
+
+    /// Runs `f` on the runtime for as long as the returned future is around
+    fn followed<Fut, R>(
+        &self,
+        f: Fut,
+    ) -> impl Future<Output = balti_err::AppResult<R>> + use<Fut, R>
+    where
+        Fut: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        follow(self.rt.spawn(f))
+    }
+
+    fn followed_blocking<F, R>(
+        &self,
+        f: F,
+    ) -> impl Future<Output = balti_err::AppResult<R>> + use<F, R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        follow(self.rt.spawn_blocking(f))
+    }
+
+    fn detached<Fut, R>(&self, f: Fut) -> JoinHandle<R>
+    where
+        Fut: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.rt.spawn(f)
+    }
+
+    fn handle(&self) -> Handle {
+        self.rt.handle().clone()
+    }
 }
 
+/// Runs `f` on the tokio runtime, following it from the returned gpui task. Dropping
+/// that task, like when the view holding it goes away, aborts `f` at its next await.
+/// Futures already on the runtime can reach it through `tokio::spawn` instead.
 pub fn spawn<C, Fut, R>(cx: &C, f: Fut) -> C::Result<Task<balti_err::AppResult<R>>>
 where
     C: AppContext,
     Fut: Future<Output = R> + Send + 'static,
     R: Send + 'static,
 {
-    cx.read_global(|rt: &GlobalTokio, cx| {
-        let join = rt.rt.spawn(f);
-        let abort = join.abort_handle();
-        let cancel = defer(move || {
-            abort.abort();
-        });
-        cx.background_spawn(async move {
-            let result = join.await;
-            drop(cancel);
-            result.map_err(|err| balti_err::AppError::err(err))
-        })
+    cx.read_global(|rt: &GlobalTokio, cx| cx.background_spawn(rt.followed(f)))
+}
+
+/// [`spawn`] for the requests of a user operation: they log in its span and their
+/// errors carry its id
+pub fn spawn_operation<C, Fut, R>(
+    cx: &C,
+    operation: &OperationId,
+    f: Fut,
+) -> C::Result<Task<balti_err::AppResult<R>>>
+where
+    C: AppContext,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+{
+    spawn(cx, operation.scope(f))
+}
+
+/// Runs cpu or disk heavy `f`, like assembling archives or hashing, on the runtime's
+/// blocking pool. Dropping the returned task only stops `f` if it hasn't started yet,
+/// once running it finishes and its result is discarded.
+pub fn spawn_blocking<C, F, R>(cx: &C, f: F) -> C::Result<Task<balti_err::AppResult<R>>>
+where
+    C: AppContext,
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    cx.read_global(|rt: &GlobalTokio, cx| cx.background_spawn(rt.followed_blocking(f)))
+}
+
+/// Runs `f` on the tokio runtime without tying it to any view, for long-lived work like
+/// watchers or listeners. Dropping the handle detaches it, aborting it stops it; either
+/// way it's dropped at its next await once the runtime shuts down with the app.
+pub fn spawn_detached<C, Fut, R>(cx: &C, f: Fut) -> C::Result<JoinHandle<R>>
+where
+    C: AppContext,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+{
+    cx.read_global(|rt: &GlobalTokio, _cx| rt.detached(f))
+}
+
+/// Handle of the app's runtime, for libraries spawning on their own and for synchronous
+/// code bridging into async with `block_on`, which must not run on the runtime itself
+#[allow(dead_code)]
+pub fn handle<C: AppContext>(cx: &C) -> C::Result<Handle> {
+    cx.read_global(|rt: &GlobalTokio, _cx| rt.handle())
+}
+
+/// Follows `join`, aborting it once the returned future is dropped
+fn follow<R: Send + 'static>(
+    join: JoinHandle<R>,
+) -> impl Future<Output = balti_err::AppResult<R>> + use<R> {
+    let cancel = abort_on_drop(&join);
+    async move {
+        let result = join.await;
+        drop(cancel);
+        result.map_err(|err| balti_err::AppError::err(err))
+    }
+}
+
+fn abort_on_drop<R>(join: &JoinHandle<R>) -> Deferred<impl FnOnce() + use<R>> {
+    let abort = join.abort_handle();
+    defer(move || {
+        abort.abort();
     })
 }
 
@@ -60,3 +155,70 @@ impl<F: FnOnce()> Drop for Deferred<F> {
 pub fn defer<F: FnOnce()>(f: F) -> Deferred<F> {
     Deferred(Some(f))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use super::*;
+
+    /// Finishes shortly, leaving time to drop what spawned it first
+    async fn work(finished: Arc<AtomicUsize>) -> u32 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        finished.fetch_add(1, Ordering::SeqCst);
+        42
+    }
+
+    #[test]
+    fn detached_tasks_outlive_their_spawner() {
+        let runtime = GlobalTokio::new();
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        // what the gpui task of `spawn` awaits, dropped along with its view
+        let followed = runtime.followed(work(finished.clone()));
+        let detached = runtime.detached(work(finished.clone()));
+        drop(followed);
+
+        assert_eq!(runtime.rt.block_on(detached).unwrap(), 42);
+        // long enough for the followed one to finish too, had it not been aborted
+        runtime.rt.block_on(async {
+            tokio::time::sleep(Duration::from_millis(40)).await;
+        });
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn handles_reach_the_app_runtime() {
+        let runtime = GlobalTokio::new();
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        // a library spawning on its own, then synchronous code waiting on it
+        let handle = runtime.handle();
+        let spawned = handle.spawn(work(finished.clone()));
+        assert_eq!(handle.block_on(spawned).unwrap(), 42);
+        assert_eq!(handle.block_on(work(finished.clone())), 42);
+        assert_eq!(finished.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn followed_tasks_hand_back_their_result() {
+        let runtime = GlobalTokio::new();
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let result = runtime
+            .rt
+            .block_on(runtime.followed(work(finished.clone())));
+        assert_eq!(result.unwrap(), 42);
+
+        let sum = runtime
+            .rt
+            .block_on(runtime.followed_blocking(|| (1..=10).sum::<u32>()));
+        assert_eq!(sum.unwrap(), 55);
+    }
+}
@@ -1,25 +1,43 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
-use balti_err::AppResult;
-use balti_s3::{__S3Remote, S3Config, S3Remote};
+use balti_err::{AppError, AppResult};
+use balti_s3::{__S3Remote, ProxySettings, RemoteTarget, S3Remote, Usage, UsageCounter};
 
-use crate::config::{parse_s3_remotes, save_s3_remotes};
+use crate::{
+    config::{self, RemoteConfig, RemotesBase},
+    diff::Overwrite,
+};
 
 pub struct S3RemoteManager {
     remotes: BTreeMap<Arc<str>, S3Remote>,
     had_parse_error: bool,
+    /// Config as last read or saved, saves merge in what other instances changed since
+    base: RemotesBase,
+    /// App wide proxy, for remotes without one of their own
+    default_proxy: Option<ProxySettings>,
+    /// Requests counted per remote name this session, kept across edits and removals
+    usage: BTreeMap<Arc<str>, Arc<UsageCounter>>,
 }
 impl S3RemoteManager {
-    pub fn empty() -> Self {
+    pub fn empty(default_proxy: Option<ProxySettings>) -> Self {
         Self {
             remotes: BTreeMap::new(),
             had_parse_error: false,
+            base: RemotesBase::default(),
+            default_proxy,
+            usage: BTreeMap::new(),
         }
     }
 
     pub fn parse(&mut self) -> AppResult<()> {
-        let s3_remotes = match parse_s3_remotes() {
-            Ok(remotes) => remotes,
+        let s3_remotes = match config::parse_remotes() {
+            Ok((remotes, base)) => {
+                self.base = base;
+                remotes
+            }
             Err(err) => {
                 self.had_parse_error = true;
                 return Err(err);
@@ -29,21 +47,68 @@ impl S3RemoteManager {
         self.remotes.clear();
 
         for (remote_name, config) in s3_remotes.into_iter() {
-            let remote_name = Arc::<str>::from(remote_name.as_str());
-            self.remotes
-                .insert(remote_name.clone(), __S3Remote::new(remote_name, config));
+            self.add_remote(Arc::from(remote_name.as_str()), config);
         }
 
         Ok(())
     }
 
-    pub fn dummy_remote(&self, config: S3Config) -> S3Remote {
-        __S3Remote::new(Arc::<str>::from("dummy_test_remote"), config)
+    pub fn dummy_remote(&self, config: RemoteConfig) -> S3Remote {
+        self.connect(Arc::from("dummy_test_remote"), config, Arc::default())
     }
 
-    pub fn add_remote(&mut self, remote_name: Arc<str>, config: S3Config) {
-        self.remotes
-            .insert(remote_name.clone(), __S3Remote::new(remote_name, config));
+    pub fn add_remote(&mut self, remote_name: Arc<str>, config: RemoteConfig) {
+        let usage = self.usage_counter(&remote_name);
+        let remote = self.connect(remote_name.clone(), config, usage);
+        self.remotes.insert(remote_name, remote);
+    }
+
+    /// Rebuilds the remote's client from its config as saved, picking up credentials
+    /// another instance or tool wrote there since
+    pub fn reload_remote(&mut self, remote_name: Arc<str>) -> AppResult<()> {
+        let (mut remotes, _) = config::parse_remotes()?;
+        let config = remotes.remove(remote_name.as_ref()).ok_or_else(|| {
+            AppError::message(format!(
+                "Remote \"{remote_name}\" is no longer in the config"
+            ))
+        })?;
+        self.add_remote(remote_name, config);
+        Ok(())
+    }
+
+    /// Client of the remote, by the kind of its config
+    fn connect(
+        &self,
+        remote_name: Arc<str>,
+        config: RemoteConfig,
+        usage: Arc<UsageCounter>,
+    ) -> S3Remote {
+        match config {
+            RemoteConfig::S3(config) => {
+                __S3Remote::new(remote_name, config, self.default_proxy.as_ref(), usage)
+            }
+        }
+    }
+
+    fn usage_counter(&mut self, remote_name: &Arc<str>) -> Arc<UsageCounter> {
+        self.usage.entry(remote_name.clone()).or_default().clone()
+    }
+
+    /// Session totals of the remotes that sent requests, by name
+    pub fn usage(&self) -> Vec<(Arc<str>, Usage)> {
+        self.usage
+            .iter()
+            .map(|(name, counter)| (name.clone(), counter.snapshot()))
+            .filter(|(_, usage)| !usage.is_empty())
+            .collect()
+    }
+
+    /// Resets the totals of `remote_name`, or of every remote with `None`
+    pub fn reset_usage(&self, remote_name: Option<&str>) {
+        self.usage
+            .iter()
+            .filter(|(name, _)| remote_name.is_none_or(|remote_name| name.as_ref() == remote_name))
+            .for_each(|(_, counter)| counter.reset());
     }
 
     pub fn remove_remote(&mut self, remote_name: Arc<str>) {
@@ -58,18 +123,85 @@ impl S3RemoteManager {
         self.remotes.contains_key(&remote_name)
     }
 
-    pub fn save_remotes(&self) {
+    /// Remote other than `except` pointing at the same bucket and prefix as `target`
+    pub fn duplicate_of(&self, target: &RemoteTarget, except: Option<&str>) -> Option<Arc<str>> {
+        self.remotes
+            .iter()
+            .find(|(name, remote)| {
+                Some(name.as_ref()) != except && remote.config.target() == *target
+            })
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Remotes sharing their target with others, mapped to the names of those others
+    pub fn duplicates(&self) -> HashMap<Arc<str>, Vec<Arc<str>>> {
+        let mut by_target = HashMap::<RemoteTarget, Vec<Arc<str>>>::new();
+        for (name, remote) in self.remotes.iter() {
+            by_target
+                .entry(remote.config.target())
+                .or_default()
+                .push(name.clone());
+        }
+
+        by_target
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .flat_map(|names| {
+                names.clone().into_iter().map(move |name| {
+                    let others = names.iter().filter(|n| **n != name).cloned().collect();
+                    (name, others)
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the config didn't parse, saving would replace it
+    pub fn had_parse_error(&self) -> bool {
+        self.had_parse_error
+    }
+
+    fn configs(&self) -> BTreeMap<Arc<str>, RemoteConfig> {
+        self.remotes
+            .iter()
+            .map(|(k, v)| (k.clone(), RemoteConfig::S3(v.config.clone())))
+            .collect()
+    }
+
+    /// What saving would overwrite in the config on disk: another instance's edits to the
+    /// same remotes, or all of a config that didn't parse. `None` when saving loses nothing.
+    pub fn review_save(&self) -> AppResult<Option<Overwrite>> {
+        config::review_remotes(&self.configs(), &self.base, self.had_parse_error)
+    }
+
+    /// Saves the remotes like [`Self::save_remotes`], replacing a config that didn't
+    /// parse. For after the user went over [`Self::review_save`].
+    pub fn overwrite_remotes(&mut self) -> AppResult<Vec<Arc<str>>> {
+        if self.had_parse_error {
+            config::replace_remotes(&self.configs(), &mut self.base)?;
+            self.had_parse_error = false;
+            return Ok(Vec::new());
+        }
+        self.save_remotes()
+    }
+
+    /// Saves the remotes, taking in what other instances changed meanwhile. Returns the
+    /// remotes both changed, this instance's version of them was saved.
+    pub fn save_remotes(&mut self) -> AppResult<Vec<Arc<str>>> {
         if self.had_parse_error {
             // don't want to overwrite incorrect syntax with empty data
             tracing::warn!("Won't save config; had parsing error");
-            return;
+            return Ok(Vec::new());
         }
 
-        let remotes = self
-            .remotes
-            .iter()
-            .map(|(k, v)| (k.clone(), v.config.clone()))
-            .collect();
-        save_s3_remotes(remotes);
+        let saved = config::save_remotes(&self.configs(), &mut self.base)?;
+
+        for (remote_name, config) in saved.external {
+            let remote_name = Arc::<str>::from(remote_name);
+            match config {
+                Some(config) => self.add_remote(remote_name, config),
+                None => self.remove_remote(remote_name),
+            }
+        }
+        Ok(saved.conflicts.into_iter().map(Arc::from).collect())
     }
 }
@@ -0,0 +1,153 @@
+use std::{collections::HashSet, sync::Arc};
+
+/// Checked objects of a listing. Checking items one by one keeps their keys, selecting
+/// everything keeps just the prefix and the keys unchecked since, so it doesn't grow
+/// with the folder.
+#[derive(Debug, Clone, Default)]
+pub struct Selection(Mode);
+
+#[derive(Debug, Clone)]
+enum Mode {
+    Keys(HashSet<Arc<str>>),
+    /// Every key below `prefix` except the `excluded` ones
+    Prefix {
+        prefix: Arc<str>,
+        excluded: HashSet<Arc<str>>,
+    },
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Keys(HashSet::new())
+    }
+}
+
+impl Selection {
+    pub fn contains(&self, key: &str) -> bool {
+        match &self.0 {
+            Mode::Keys(keys) => keys.contains(key),
+            Mode::Prefix { prefix, excluded } => is_below(prefix, key) && !excluded.contains(key),
+        }
+    }
+
+    pub fn set(&mut self, key: Arc<str>, checked: bool) {
+        match &mut self.0 {
+            Mode::Keys(keys) if checked => {
+                keys.insert(key);
+            }
+            Mode::Keys(keys) => {
+                keys.remove(&key);
+            }
+            Mode::Prefix { excluded, .. } if checked => {
+                excluded.remove(&key);
+            }
+            Mode::Prefix { prefix, excluded } => {
+                if is_below(prefix, &key) {
+                    excluded.insert(key);
+                }
+            }
+        }
+    }
+
+    /// Selects every key below `prefix`, whether it's loaded yet or not
+    pub fn select_all(&mut self, prefix: &str) {
+        self.0 = Mode::Prefix {
+            prefix: balti_s3::listed_prefix(prefix).into(),
+            excluded: HashSet::new(),
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = Mode::default();
+    }
+
+    /// Nothing is checked, a prefix-wide selection never counts as empty
+    pub fn is_empty(&self) -> bool {
+        match &self.0 {
+            Mode::Keys(keys) => keys.is_empty(),
+            Mode::Prefix { .. } => false,
+        }
+    }
+
+    /// Drops checked or excluded keys that `keep` rejects, like those gone after a refresh
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        match &mut self.0 {
+            Mode::Keys(keys) => keys.retain(|key| keep(key)),
+            Mode::Prefix { excluded, .. } => excluded.retain(|key| keep(key)),
+        }
+    }
+
+    /// Selected items of `listed`, lazily and in their order. Listing pages can be
+    /// streamed through it without collecting the selection first.
+    pub fn filter<'a, T: 'a>(
+        &'a self,
+        listed: impl IntoIterator<Item = T> + 'a,
+        key: impl Fn(&T) -> &str + 'a,
+    ) -> impl Iterator<Item = T> + 'a {
+        listed
+            .into_iter()
+            .filter(move |item| self.contains(key(item)))
+    }
+}
+
+/// Whether `key` lies below `prefix`, the prefix's own key excluded
+fn is_below(prefix: &str, key: &str) -> bool {
+    key.len() > prefix.len() && key.starts_with(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LISTED: [&str; 4] = ["logs/a.txt", "logs/b.txt", "logs/old/", "logs/c.txt"];
+
+    fn selected(selection: &Selection) -> Vec<&'static str> {
+        selection.filter(LISTED, |key| *key).collect()
+    }
+
+    #[test]
+    fn tracks_explicit_keys() {
+        let mut selection = Selection::default();
+        assert!(selection.is_empty());
+
+        selection.set("logs/c.txt".into(), true);
+        selection.set("logs/a.txt".into(), true);
+        selection.set("logs/c.txt".into(), false);
+        assert_eq!(selected(&selection), ["logs/a.txt"]);
+
+        selection.retain(|key| key != "logs/a.txt");
+        assert!(selection.is_empty());
+    }
+
+    #[test]
+    fn selects_everything_below_a_prefix_minus_exclusions() {
+        let mut selection = Selection::default();
+        selection.select_all("/logs/");
+        assert!(!selection.is_empty());
+        assert!(selection.contains("logs/not-loaded-yet.txt"));
+        assert!(!selection.contains("logs/"));
+        assert!(!selection.contains("other/a.txt"));
+
+        selection.set("logs/b.txt".into(), false);
+        selection.set("other/a.txt".into(), false);
+        assert_eq!(
+            selected(&selection),
+            ["logs/a.txt", "logs/old/", "logs/c.txt"]
+        );
+
+        selection.set("logs/b.txt".into(), true);
+        assert_eq!(selected(&selection).len(), LISTED.len());
+
+        selection.clear();
+        assert!(selection.is_empty());
+        assert!(!selection.contains("logs/a.txt"));
+    }
+
+    #[test]
+    fn selects_the_whole_root() {
+        let mut selection = Selection::default();
+        selection.select_all("/");
+        assert!(selection.contains("a.txt"));
+        assert!(selection.contains("logs/a.txt"));
+    }
+}
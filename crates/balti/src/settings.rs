@@ -0,0 +1,414 @@
+use std::{cmp::Ordering, collections::BTreeMap, time::Duration};
+
+use balti_err::{AppError, AppResult};
+use balti_s3::{MAX_PAGE_SIZE, MAX_PRESIGN_EXPIRY, ProxySettings, S3Object, S3Remote, Usage};
+use chrono::TimeDelta;
+use gpui::{App, Global, Pixels};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bulk, config,
+    junk::{self, JunkMatcher},
+    rate, util,
+};
+
+/// App wide preferences, persisted to `settings.toml` in the config dir.
+///
+/// Every field has a default so older or partial files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Keys requested per listing page when a remote doesn't override it
+    pub page_size: i32,
+    /// Multiplier for font sizes, row heights and paddings
+    pub ui_scale: f32,
+    /// Proxy for remotes that don't set their own, read at startup
+    pub proxy: Option<ProxySettings>,
+    /// Still loading indicators instead of spinners and pulsing placeholders.
+    /// gpui doesn't expose the OS preference, so this is set by hand
+    pub reduce_motion: bool,
+    /// Provider prices for a rough cost estimate of the session's requests
+    pub unit_prices: Option<UnitPrices>,
+    /// Objects past which delete, copy, move and download ask once more, `0` never asks
+    pub confirm_objects_above: usize,
+    /// Objects a folder download zips per run before pausing to be resumed, `0` for no limit
+    pub object_budget: usize,
+    /// Language of the UI like `de` or `pt-BR`, English when unset. Read at startup
+    pub language: Option<String>,
+    /// Format of the log file, read before the first log line. `BALTI_LOG_FORMAT`
+    /// overrides it
+    pub log_format: LogFormat,
+    /// Estimated MB the remembered listings of the session may take
+    pub listing_cache_mb: usize,
+    /// Estimated MB the thumbnails held for display may take
+    pub thumbnail_cache_mb: usize,
+    /// Row heights and paddings of listings and the status bar, applies live
+    pub density: Density,
+    /// Named setups offered in one click when copying a presigned link
+    pub presign_presets: Vec<PresignPreset>,
+    /// Days changes to remotes stay in the journal, `0` keeps them forever
+    pub journal_retention_days: u32,
+    /// Globs of names "Find junk…" matches, like `.DS_Store` or `*.swp`
+    pub junk_patterns: Vec<String>,
+    /// Whether "Find junk…" matches empty objects too, folder markers aside
+    pub junk_empty_objects: bool,
+    /// Look for a newer release at startup
+    pub check_for_updates: bool,
+    /// Minor versions the app may fall behind the latest release before a banner
+    /// suggests updating
+    pub update_banner_minor_versions: u32,
+}
+
+/// Range the UI scale factor is clamped to
+pub const UI_SCALE_RANGE: (f32, f32) = (0.9, 1.5);
+/// Change applied per zoom in / out
+pub const UI_SCALE_STEP: f32 = 0.1;
+/// Row heights when compact, relative to comfortable ones
+const COMPACT_ROW_FACTOR: f32 = 0.7;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            page_size: MAX_PAGE_SIZE,
+            ui_scale: 1.0,
+            proxy: None,
+            reduce_motion: false,
+            unit_prices: None,
+            confirm_objects_above: bulk::DEFAULT_CONFIRM_OBJECTS,
+            object_budget: 0,
+            language: None,
+            log_format: LogFormat::default(),
+            listing_cache_mb: 64,
+            thumbnail_cache_mb: 16,
+            density: Density::default(),
+            presign_presets: Vec::new(),
+            journal_retention_days: 90,
+            junk_patterns: junk::DEFAULT_JUNK_PATTERNS.map(String::from).to_vec(),
+            junk_empty_objects: true,
+            check_for_updates: true,
+            update_banner_minor_versions: 3,
+        }
+    }
+}
+
+impl Global for Settings {}
+
+impl Settings {
+    pub fn get(cx: &App) -> &Self {
+        cx.global::<Self>()
+    }
+
+    /// Applies the change and writes the settings file
+    pub fn update(cx: &mut App, f: impl FnOnce(&mut Self)) {
+        cx.update_global::<Self, _>(|settings, _cx| {
+            f(settings);
+            if let Err(err) = config::save_settings(settings) {
+                tracing::error!("Failed to save settings: {}", err.message);
+            }
+        });
+    }
+
+    /// Page size for listings on the remote
+    pub fn page_size(&self, remote: &S3Remote) -> i32 {
+        remote
+            .config
+            .page_size
+            .unwrap_or(self.page_size)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn listing_cache_bytes(&self) -> usize {
+        self.listing_cache_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn thumbnail_cache_bytes(&self) -> usize {
+        self.thumbnail_cache_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn ui_scale(&self) -> f32 {
+        let (min, max) = UI_SCALE_RANGE;
+        if self.ui_scale.is_finite() {
+            self.ui_scale.clamp(min, max)
+        } else {
+            1.0
+        }
+    }
+
+    /// Steps the scale by `steps` increments, `None` resets it
+    pub fn zoom(&mut self, steps: Option<i32>) {
+        self.ui_scale = match steps {
+            Some(steps) => {
+                let (min, max) = UI_SCALE_RANGE;
+                let scale = self.ui_scale() + UI_SCALE_STEP * steps as f32;
+                // keep clean steps, repeated float additions drift
+                ((scale * 10.).round() / 10.).clamp(min, max)
+            }
+            None => 1.0,
+        };
+    }
+
+    /// Scales a fixed pixel size, for layout that doesn't follow the rem size
+    pub fn scaled(&self, value: Pixels) -> Pixels {
+        value * self.ui_scale()
+    }
+
+    /// Scales a row height like [`Self::scaled`], shorter when compact
+    pub fn row_height(&self, value: Pixels) -> Pixels {
+        match self.density {
+            Density::Comfortable => self.scaled(value),
+            Density::Compact => self.scaled(value * COMPACT_ROW_FACTOR),
+        }
+    }
+
+    /// Matcher of what "Find junk…" looks for
+    pub fn junk_matcher(&self) -> AppResult<JunkMatcher> {
+        JunkMatcher::new(&self.junk_patterns, self.junk_empty_objects)
+    }
+
+    /// How long journal entries are kept, `None` for good
+    pub fn journal_retention(&self) -> Option<TimeDelta> {
+        (self.journal_retention_days > 0)
+            .then(|| TimeDelta::days(self.journal_retention_days.into()))
+    }
+
+    pub fn is_compact(&self) -> bool {
+        self.density == Density::Compact
+    }
+
+    /// Checks values that came from outside the app, like an imported file
+    pub fn validate(&self) -> AppResult<()> {
+        if !(1..=MAX_PAGE_SIZE).contains(&self.page_size) {
+            return Err(AppError::message(format!(
+                "Page size must be between 1 and {MAX_PAGE_SIZE}"
+            )));
+        }
+        let (min, max) = UI_SCALE_RANGE;
+        if !(min..=max).contains(&self.ui_scale) {
+            return Err(AppError::message(format!(
+                "UI scale must be between {min} and {max}"
+            )));
+        }
+        if let Some(proxy) = &self.proxy {
+            proxy.validate()?;
+        }
+        if let Some(prices) = &self.unit_prices {
+            let prices = [
+                prices.class_a_per_1k,
+                prices.class_b_per_1k,
+                prices.egress_per_gb,
+            ];
+            if !prices.iter().all(|price| price.is_finite() && *price >= 0.) {
+                return Err(AppError::message("Unit prices can't be negative"));
+            }
+        }
+        for preset in &self.presign_presets {
+            if preset.name.trim().is_empty() {
+                return Err(AppError::message("Presign presets need a name"));
+            }
+            if preset.expires_in().is_none() {
+                return Err(AppError::message(format!(
+                    "Presign preset \"{}\" must expire within 7 days, like 15m, 24h or 7d",
+                    preset.name
+                )));
+            }
+        }
+        self.junk_matcher()?;
+        if let Some(language) = &self.language
+            && !config::is_valid_language(language)
+        {
+            return Err(AppError::message(format!(
+                "Invalid language \"{language}\""
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Layout version of [`SettingsExport`], bump it when a field changes meaning
+pub const SETTINGS_EXPORT_VERSION: u32 = 1;
+
+/// Settings carried over to another machine, remotes stay behind. Written by "Export
+/// Settings…" and read back by "Import Settings…".
+///
+/// Fields are defaulted and unknown ones ignored, so files of other versions load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsExport {
+    pub version: u32,
+    pub settings: Settings,
+    /// View preferences by remote name, empty unless they were included
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub view_prefs: BTreeMap<String, ViewPrefs>,
+}
+
+impl Default for SettingsExport {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_EXPORT_VERSION,
+            settings: Settings::default(),
+            view_prefs: BTreeMap::new(),
+        }
+    }
+}
+
+/// What the provider bills, in its currency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnitPrices {
+    /// Per thousand class A requests, writes and listings
+    pub class_a_per_1k: f64,
+    /// Per thousand class B requests, reads
+    pub class_b_per_1k: f64,
+    /// Per GB downloaded
+    pub egress_per_gb: f64,
+}
+
+impl UnitPrices {
+    /// Rough cost of `usage`, ignoring free tiers and storage
+    pub fn estimate(&self, usage: &Usage) -> f64 {
+        usage.class_a as f64 / 1000. * self.class_a_per_1k
+            + usage.class_b as f64 / 1000. * self.class_b_per_1k
+            + usage.bytes_out as f64 / 1e9 * self.egress_per_gb
+    }
+}
+
+/// Layout version of [`ViewPrefs`], bump it when a field changes meaning
+pub const VIEW_PREFS_VERSION: u32 = 1;
+
+/// How a remote's listings are shown, persisted per remote to `views.toml`.
+///
+/// Fields are defaulted so files written by older versions keep loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewPrefs {
+    pub version: u32,
+    /// Show immediate child counts on folder rows
+    pub show_folder_counts: bool,
+    /// Show thumbnails in place of the icon on image rows
+    pub show_thumbnails: bool,
+    /// Auto refresh interval in seconds, `None` when turned off
+    pub auto_refresh: Option<u64>,
+    pub view_mode: ViewMode,
+    pub order: ListingOrder,
+}
+
+/// How the log file is written, the console always gets text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// A JSON object per line with `timestamp`, `level`, `target` and `message`, plus
+    /// `op`, `remote` and `req_id` for errors of a remote. For log collectors
+    Json,
+}
+
+impl LogFormat {
+    /// Env var overriding the setting, `text` or `json`
+    pub const ENV: &str = "BALTI_LOG_FORMAT";
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Named presigned link setup, like a day's read access for clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresignPreset {
+    pub name: String,
+    /// How long links stay valid, like `15m`, `24h` or `7d`
+    pub expiry: String,
+    /// Makes browsers save the object under its file name rather than open it
+    #[serde(default)]
+    pub force_download: bool,
+}
+
+impl PresignPreset {
+    /// `None` unless the expiry reads as a duration of up to a week
+    pub fn expires_in(&self) -> Option<Duration> {
+        rate::parse_duration(&self.expiry)
+            .filter(|expiry| !expiry.is_zero() && *expiry <= MAX_PRESIGN_EXPIRY)
+    }
+}
+
+/// How tightly rows and bars are spaced, compact fits more on small screens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+/// Layout of the object listing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViewMode {
+    #[default]
+    List,
+    /// Tiles with a larger icon or thumbnail, for media buckets
+    Grid,
+}
+
+/// Order objects of a listing are shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListingOrder {
+    /// Folders first, then files, each by name with numbers compared by value
+    #[default]
+    Natural,
+    /// Byte order of the keys, as the provider lists them
+    Raw,
+}
+
+impl ListingOrder {
+    pub fn compare(self, a: &S3Object, b: &S3Object) -> Ordering {
+        match self {
+            ListingOrder::Raw => a.key().cmp(b.key()),
+            ListingOrder::Natural => b
+                .is_folder()
+                .cmp(&a.is_folder())
+                .then_with(|| util::natural_cmp(a.key(), b.key())),
+        }
+    }
+}
+
+impl Default for ViewPrefs {
+    fn default() -> Self {
+        Self {
+            version: VIEW_PREFS_VERSION,
+            show_folder_counts: false,
+            show_thumbnails: false,
+            auto_refresh: None,
+            view_mode: ViewMode::List,
+            order: ListingOrder::Natural,
+        }
+    }
+}
+
+impl ViewPrefs {
+    /// Interval the remote's views refresh at, `None` when turned off
+    pub fn auto_refresh(&self) -> Option<Duration> {
+        self.auto_refresh
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Brings prefs written by an older version up to date
+    pub fn migrate(mut self) -> Self {
+        self.version = VIEW_PREFS_VERSION;
+        self
+    }
+}
+
+pub fn init(cx: &mut App) {
+    let settings = config::parse_settings().unwrap_or_else(|err| {
+        tracing::warn!("Using default settings: {}", err.message);
+        Settings::default()
+    });
+    cx.set_global(settings);
+}
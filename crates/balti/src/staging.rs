@@ -0,0 +1,270 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use balti_err::{AppError, AppResult, ErrorKind};
+use balti_s3::S3Remote;
+use futures::channel::mpsc::UnboundedSender;
+use gpui::App;
+use serde::{Deserialize, Serialize};
+
+use crate::{archive::PartialFile, checkpoint, config};
+
+/// Don't flood the UI with an update per chunk
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// Name of staged objects whose key has no file name to keep
+const FALLBACK_NAME: &str = "object";
+
+/// Where an interrupted download got to, next to its partial file
+const RESUME_FILE: &str = ".resume.json";
+
+/// Clears the staged files once the app quits
+pub fn init(cx: &mut App) {
+    cx.on_app_quit(|_cx| async { clear(&config::staging_dir()) })
+        .detach();
+}
+
+/// Where the object gets staged in `dir`. Each object gets a folder of its own, so the
+/// file keeps the name it has in the bucket.
+pub fn staged_path(dir: &Path, remote_name: &str, key: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    (remote_name, key).hash(&mut hasher);
+    let name = key
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+        .unwrap_or(FALLBACK_NAME);
+    dir.join(format!("{:016x}", hasher.finish())).join(name)
+}
+
+/// Bytes of a partial download that made it to disk, and the ETag of the object they
+/// came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumePoint {
+    pub offset: u64,
+    pub etag: String,
+}
+
+fn resume_path(dest: &Path) -> PathBuf {
+    dest.with_file_name(RESUME_FILE)
+}
+
+/// Whether the download to `dest` broke off with something to resume from
+pub fn can_resume(dest: &Path) -> bool {
+    resume_path(dest).exists()
+}
+
+/// Resume point of the partial file at `dest`. Bytes past it were written after the
+/// record and get cut off, so the rest appends right after. `None` when there's none or
+/// the file holds less than it claims, the record is dropped then.
+pub fn load_resume_point(dest: &Path) -> Option<ResumePoint> {
+    let path = resume_path(dest);
+    let len = fs::metadata(dest).map(|metadata| metadata.len()).ok();
+    let point = fs::read(&path)
+        .ok()
+        .and_then(|content| serde_json::from_slice::<ResumePoint>(&content).ok())
+        .filter(|point| point.offset > 0 && len.is_some_and(|len| point.offset <= len))
+        .filter(|point| {
+            OpenOptions::new()
+                .write(true)
+                .open(dest)
+                .and_then(|file| file.set_len(point.offset))
+                .is_ok()
+        });
+    if point.is_none() {
+        let _ = fs::remove_file(&path);
+    }
+    point
+}
+
+/// Records how far the download to `dest` got. The file is synced first, so the record
+/// never counts bytes that aren't on disk.
+fn save_resume_point(file: &File, dest: &Path, point: &ResumePoint) -> AppResult<()> {
+    file.sync_all().map_err(|err| AppError::err(err))?;
+    let content = serde_json::to_vec(point).map_err(|err| AppError::err(err))?;
+    checkpoint::write_atomic(&resume_path(dest), &content)
+}
+
+/// Downloads the object to `dest`, sending the bytes done so far to `progress`. A
+/// download that broke off before continues from its resume point with a ranged
+/// request, or starts over when the object changed since. A failure keeps the partial
+/// file with a resume point, a download dropped midway removes it.
+pub async fn stage(
+    remote: S3Remote,
+    key: &str,
+    dest: PathBuf,
+    progress: UnboundedSender<u64>,
+) -> AppResult<PathBuf> {
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(|err| AppError::err(err))?;
+    }
+
+    let resume = load_resume_point(&dest);
+    let (download, offset) = match resume {
+        Some(point) => {
+            let ranged =
+                balti_s3::download_from(remote.clone(), key, point.offset, Some(&point.etag)).await;
+            match ranged {
+                Ok(download) => (download, point.offset),
+                Err(err) if err.kind == ErrorKind::Modified => {
+                    tracing::info!("{key} changed since its download broke off, starting over");
+                    (balti_s3::download_from(remote, key, 0, None).await?, 0)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        None => (balti_s3::download_from(remote, key, 0, None).await?, 0),
+    };
+    let _ = fs::remove_file(resume_path(&dest));
+
+    let mut file = if offset > 0 {
+        OpenOptions::new().append(true).open(&dest)
+    } else {
+        File::create(&dest)
+    }
+    .map_err(|err| AppError::err(err))?;
+    let mut partial = PartialFile::new(dest.clone());
+
+    let mut body = download.body;
+    let mut bytes_done = offset;
+    let written = async {
+        let mut unreported = 0;
+        while let Some(chunk) = body.try_next().await.map_err(|err| AppError::err(err))? {
+            file.write_all(&chunk).map_err(|err| AppError::err(err))?;
+
+            bytes_done += chunk.len() as u64;
+            unreported += chunk.len() as u64;
+            if unreported >= PROGRESS_STEP_BYTES {
+                unreported = 0;
+                let _ = progress.unbounded_send(bytes_done);
+            }
+        }
+        AppResult::Ok(())
+    }
+    .await;
+    let _ = progress.unbounded_send(bytes_done);
+
+    if let Err(err) = written {
+        if let Some(etag) = download.etag
+            && bytes_done > 0
+        {
+            let point = ResumePoint {
+                offset: bytes_done,
+                etag: etag.to_string(),
+            };
+            match save_resume_point(&file, &dest, &point) {
+                Ok(()) => partial.keep(),
+                Err(err) => {
+                    tracing::warn!("Failed to record where {key} broke off: {}", err.message)
+                }
+            }
+        }
+        return Err(err);
+    }
+
+    partial.keep();
+    Ok(dest)
+}
+
+/// Removes a staged file along with its folder
+pub fn remove(path: &Path) {
+    let Some(dir) = path.parent() else {
+        return;
+    };
+    if let Err(err) = fs::remove_dir_all(dir) {
+        tracing::warn!("Failed to remove staged file {:?}: {}", path, err);
+    }
+}
+
+/// Removes everything staged in `dir`
+pub fn clear(dir: &Path) {
+    if !dir.exists() {
+        return;
+    }
+    if let Err(err) = fs::remove_dir_all(dir) {
+        tracing::warn!("Failed to clear staged files in {:?}: {}", dir, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial_file(name: &str, content: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("balti-staging-{name}-{}", std::process::id()))
+            .join("0123456789abcdef");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("video.mp4");
+        fs::write(&dest, content).unwrap();
+        dest
+    }
+
+    #[test]
+    fn resumes_from_the_recorded_offset() {
+        let dest = partial_file("resume", b"0123456789");
+        assert_eq!(load_resume_point(&dest), None);
+
+        // bytes past the record came after it, they may be torn
+        let point = ResumePoint {
+            offset: 6,
+            etag: "\"e1\"".into(),
+        };
+        let file = File::open(&dest).unwrap();
+        save_resume_point(&file, &dest, &point).unwrap();
+        assert!(can_resume(&dest));
+
+        assert_eq!(load_resume_point(&dest), Some(point));
+        assert_eq!(fs::read(&dest).unwrap(), b"012345");
+    }
+
+    #[test]
+    fn drops_records_the_file_doesnt_back() {
+        let dest = partial_file("short", b"0123");
+        let file = File::open(&dest).unwrap();
+        let point = ResumePoint {
+            offset: 6,
+            etag: "\"e1\"".into(),
+        };
+        save_resume_point(&file, &dest, &point).unwrap();
+
+        assert_eq!(load_resume_point(&dest), None);
+        assert!(!can_resume(&dest));
+        assert_eq!(fs::read(&dest).unwrap(), b"0123");
+
+        // the file went away with a cancelled download
+        save_resume_point(&file, &dest, &point).unwrap();
+        fs::remove_file(&dest).unwrap();
+        assert_eq!(load_resume_point(&dest), None);
+        assert!(!can_resume(&dest));
+    }
+
+    #[test]
+    fn keeps_the_file_name() {
+        let dir = Path::new("/tmp/staging");
+        let path = staged_path(dir, "prod", "logs/2024/app.log");
+        assert_eq!(path.file_name().unwrap(), "app.log");
+        assert_eq!(path.parent().unwrap().parent().unwrap(), dir);
+
+        assert_ne!(
+            path.parent(),
+            staged_path(dir, "prod", "logs/2025/app.log").parent()
+        );
+        assert_ne!(
+            path.parent(),
+            staged_path(dir, "backup", "logs/2024/app.log").parent()
+        );
+        assert_eq!(path, staged_path(dir, "prod", "logs/2024/app.log"));
+
+        assert_eq!(
+            staged_path(dir, "prod", "logs/..").file_name().unwrap(),
+            FALLBACK_NAME
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, OnceLock},
+};
+
+use balti_err::{AppError, AppResult};
+use gpui::App;
+
+use crate::{config, settings::Settings};
+
+/// Catalog the UI is written in, every key has an entry here
+const ENGLISH_CATALOG: &str = include_str!("../locales/en.toml");
+
+/// CLDR plural categories a plural entry may have forms for, `other` is required
+const PLURAL_CATEGORIES: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+static ENGLISH: LazyLock<Catalog> = LazyLock::new(|| {
+    Catalog::parse("en", ENGLISH_CATALOG).expect("the bundled English catalog is invalid")
+});
+
+/// Catalog of `Settings::language`, `None` for English or when it failed to load
+static TRANSLATION: OnceLock<Option<Catalog>> = OnceLock::new();
+
+/// Looks up a string of the catalog, interpolating `{name}` arguments. Plural entries
+/// pick their form from the `count` argument.
+///
+/// ```ignore
+/// t!("browse.load_more");
+/// t!("browse.items_selected", count = selected.len());
+/// ```
+macro_rules! t {
+    ($key:literal) => {
+        $crate::strings::get($key, &[])
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::strings::get($key, &[$((stringify!($name), ($value).to_string())),+])
+    };
+}
+pub(crate) use t;
+
+/// Loads the translation of the language set in settings, read at startup
+pub fn init(cx: &mut App) {
+    let Some(language) = Settings::get(cx).language.clone() else {
+        return;
+    };
+    if language == ENGLISH.language {
+        return;
+    }
+
+    let catalog = config::parse_translation(&language).and_then(|content| match content {
+        Some(content) => Catalog::parse(&language, &content).map(Some),
+        None => Ok(None),
+    });
+    let catalog = match catalog {
+        Ok(Some(catalog)) => Some(catalog),
+        Ok(None) => {
+            tracing::warn!("No translation for {language}, using English");
+            None
+        }
+        Err(err) => {
+            tracing::error!("Failed to load the {language} translation: {}", err.message);
+            None
+        }
+    };
+    let _ = TRANSLATION.set(catalog);
+}
+
+/// String of `key` in the set language, English where the translation lacks it
+pub fn get(key: &str, args: &[(&str, String)]) -> String {
+    TRANSLATION
+        .get()
+        .and_then(Option::as_ref)
+        .and_then(|catalog| catalog.render(key, args))
+        .or_else(|| ENGLISH.render(key, args))
+        .unwrap_or_else(|| {
+            tracing::warn!("Missing string {key}");
+            key.to_owned()
+        })
+}
+
+#[derive(Debug)]
+enum Entry {
+    Text(String),
+    /// Forms by plural category
+    Plural(HashMap<String, String>),
+}
+
+#[derive(Debug)]
+struct Catalog {
+    language: String,
+    /// By dotted key, like `browse.load_more` for `load_more` in `[browse]`
+    entries: HashMap<String, Entry>,
+}
+
+impl Catalog {
+    fn parse(language: &str, content: &str) -> AppResult<Self> {
+        let table = toml::from_str::<toml::Table>(content).map_err(|err| AppError::err(err))?;
+        let mut entries = HashMap::new();
+        flatten("", table, &mut entries)?;
+        Ok(Self {
+            language: language.to_owned(),
+            entries,
+        })
+    }
+
+    fn render(&self, key: &str, args: &[(&str, String)]) -> Option<String> {
+        let template = match self.entries.get(key)? {
+            Entry::Text(text) => text,
+            Entry::Plural(forms) => {
+                let count = args
+                    .iter()
+                    .find(|(name, _)| *name == "count")
+                    .and_then(|(_, value)| value.parse::<u64>().ok())
+                    .unwrap_or_default();
+                forms
+                    .get(plural_category(&self.language, count))
+                    .or_else(|| forms.get("other"))?
+            }
+        };
+        Some(interpolate(template, args))
+    }
+}
+
+/// Collects the strings of `table`, sections nest with dots. A table of plural
+/// categories is one plural entry.
+fn flatten(
+    prefix: &str,
+    table: toml::Table,
+    entries: &mut HashMap<String, Entry>,
+) -> AppResult<()> {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}.{name}")
+        };
+        match value {
+            toml::Value::String(text) => {
+                entries.insert(key, Entry::Text(text));
+            }
+            toml::Value::Table(table) if is_plural(&table) => {
+                let forms = table
+                    .into_iter()
+                    .filter_map(|(category, form)| Some((category, form.as_str()?.to_owned())))
+                    .collect();
+                entries.insert(key, Entry::Plural(forms));
+            }
+            toml::Value::Table(table) => flatten(&key, table, entries)?,
+            _ => return Err(AppError::message(format!("{key} isn't text"))),
+        }
+    }
+    Ok(())
+}
+
+fn is_plural(table: &toml::Table) -> bool {
+    table.contains_key("other")
+        && table
+            .iter()
+            .all(|(category, form)| PLURAL_CATEGORIES.contains(&category.as_str()) && form.is_str())
+}
+
+fn interpolate(template: &str, args: &[(&str, String)]) -> String {
+    args.iter()
+        .fold(template.to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+/// Plural category of `count` in `language`, after the CLDR rules for whole numbers
+fn plural_category(language: &str, count: u64) -> &'static str {
+    let base = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let (tens, hundreds) = (count % 10, count % 100);
+    match base.as_str() {
+        "ja" | "ko" | "zh" | "th" | "vi" | "id" | "ms" => "other",
+        "fr" if count <= 1 => "one",
+        "ru" | "uk" | "be" => match (tens, hundreds) {
+            (1, h) if h != 11 => "one",
+            (2..=4, h) if !(12..=14).contains(&h) => "few",
+            _ => "many",
+        },
+        "pl" => match (count, tens, hundreds) {
+            (1, _, _) => "one",
+            (_, 2..=4, h) if !(12..=14).contains(&h) => "few",
+            _ => "many",
+        },
+        "cs" | "sk" => match count {
+            1 => "one",
+            2..=4 => "few",
+            _ => "other",
+        },
+        _ if count == 1 => "one",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_catalog_loads() {
+        assert!(!ENGLISH.entries.is_empty());
+        assert_eq!(
+            ENGLISH.render("browse.items_selected", &[("count", "1".into())]),
+            Some("1 item selected".to_owned())
+        );
+        assert_eq!(
+            ENGLISH.render("browse.items_selected", &[("count", "3".into())]),
+            Some("3 items selected".to_owned())
+        );
+    }
+
+    #[test]
+    fn picks_plural_forms_by_language() {
+        let catalog = Catalog::parse(
+            "ru",
+            r#"
+            [browse]
+            title = "Папка {name}"
+            files = { one = "{count} файл", few = "{count} файла", many = "{count} файлов", other = "{count} файла" }
+            "#,
+        )
+        .unwrap();
+
+        let files = |count: u64| catalog.render("browse.files", &[("count", count.to_string())]);
+        assert_eq!(files(1).as_deref(), Some("1 файл"));
+        assert_eq!(files(3).as_deref(), Some("3 файла"));
+        assert_eq!(files(11).as_deref(), Some("11 файлов"));
+        assert_eq!(files(21).as_deref(), Some("21 файл"));
+        assert_eq!(
+            catalog
+                .render("browse.title", &[("name", "logs".into())])
+                .as_deref(),
+            Some("Папка logs")
+        );
+        assert_eq!(catalog.render("browse.missing", &[]), None);
+    }
+
+    #[test]
+    fn plural_categories() {
+        assert_eq!(plural_category("en", 0), "other");
+        assert_eq!(plural_category("en-GB", 1), "one");
+        assert_eq!(plural_category("fr", 0), "one");
+        assert_eq!(plural_category("pl", 22), "few");
+        assert_eq!(plural_category("pl", 25), "many");
+        assert_eq!(plural_category("ja", 1), "other");
+    }
+
+    #[test]
+    fn rejects_non_text_entries() {
+        assert!(Catalog::parse("de", "[browse]\nload_more = 3").is_err());
+    }
+}
@@ -1,8 +1,13 @@
 use std::{collections::BTreeMap, rc::Rc, sync::LazyLock};
 
-use gpui::{App, SharedString};
+use gpui::{App, Pixels, SharedString, Window, WindowAppearance, px};
 use gpui_component::{Theme, ThemeConfig, ThemeMode, ThemeSet};
 
+use crate::settings::Settings;
+
+/// Font size at a UI scale of 1, everything sized in rems follows it
+const BASE_FONT_SIZE: Pixels = px(16.);
+
 pub static THEMES: LazyLock<BTreeMap<SharedString, ThemeConfig>> = LazyLock::new(|| {
     let mut themes = BTreeMap::new();
     for source in [include_str!("./balti.json")].into_iter() {
@@ -28,4 +33,33 @@ pub fn change_color_mode(mode: ThemeMode, cx: &mut App) {
         theme.mode = mode;
         theme.apply_config(&theme_config);
     }
+
+    apply_ui_scale(cx);
+}
+
+/// Picks the light or dark theme after the OS appearance, like the GTK dark preference,
+/// and follows it as it changes. Toggling the theme by hand holds until it changes next.
+pub fn follow_window_appearance(window: &mut Window, cx: &mut App) {
+    change_color_mode(appearance_mode(window.appearance()), cx);
+
+    window
+        .observe_window_appearance(|window, cx| {
+            change_color_mode(appearance_mode(window.appearance()), cx);
+            window.refresh();
+        })
+        .detach();
+}
+
+fn appearance_mode(appearance: WindowAppearance) -> ThemeMode {
+    match appearance {
+        WindowAppearance::Light | WindowAppearance::VibrantLight => ThemeMode::Light,
+        WindowAppearance::Dark | WindowAppearance::VibrantDark => ThemeMode::Dark,
+    }
+}
+
+/// Resizes the theme fonts, and with them the rem size, to the UI scale setting
+pub fn apply_ui_scale(cx: &mut App) {
+    let scale = Settings::get(cx).ui_scale();
+    let theme = Theme::global_mut(cx);
+    theme.font_size = BASE_FONT_SIZE * scale;
 }
@@ -0,0 +1,136 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use balti_err::{AppError, AppResult};
+use balti_s3::S3Remote;
+use gpui::{App, Global};
+use image::{ImageFormat, imageops::FilterType};
+
+use crate::{
+    cache::{ByteLru, CacheUsage},
+    settings::Settings,
+};
+
+/// Edge of the square thumbnails, in pixels
+pub const THUMBNAIL_SIZE: u32 = 32;
+/// Images larger than this are never fetched for a thumbnail
+pub const MAX_SOURCE_BYTES: i64 = 10 * 1024 * 1024;
+
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico"];
+
+pub fn init(cx: &mut App) {
+    let max_bytes = Settings::get(cx).thumbnail_cache_bytes();
+    cx.set_global(ThumbnailMemory {
+        thumbnails: ByteLru::new("thumbnail", usize::MAX, max_bytes),
+    });
+}
+
+/// Thumbnails ready to show, by remote and key. Evicted ones show the file icon until
+/// their row is in the viewport again and they're read back from the disk cache.
+pub struct ThumbnailMemory {
+    thumbnails: ByteLru<(Arc<str>, Arc<str>), PathBuf>,
+}
+
+impl Global for ThumbnailMemory {}
+
+impl ThumbnailMemory {
+    pub fn remember(remote: &Arc<str>, key: &Arc<str>, path: PathBuf, cx: &mut App) {
+        // decoded to RGBA for display
+        let bytes = (THUMBNAIL_SIZE * THUMBNAIL_SIZE * 4) as usize + path.as_os_str().len();
+        let max_bytes = Settings::get(cx).thumbnail_cache_bytes();
+        let memory = cx.global_mut::<Self>();
+        memory.thumbnails.set_max_bytes(max_bytes);
+        memory
+            .thumbnails
+            .insert((remote.clone(), key.clone()), path, bytes);
+    }
+
+    /// Path of the thumbnail to show, without counting it as used. Rendering can't.
+    pub fn recall(remote: &Arc<str>, key: &Arc<str>, cx: &App) -> Option<PathBuf> {
+        cx.global::<Self>()
+            .thumbnails
+            .peek(&(remote.clone(), key.clone()))
+            .cloned()
+    }
+
+    /// Whether the thumbnail at `path` is held, counting it as used when it is
+    pub fn touch(remote: &Arc<str>, key: &Arc<str>, path: &Path, cx: &mut App) -> bool {
+        cx.global_mut::<Self>()
+            .thumbnails
+            .get(&(remote.clone(), key.clone()))
+            .is_some_and(|held| held == path)
+    }
+
+    pub fn usage(cx: &App) -> CacheUsage {
+        cx.global::<Self>().thumbnails.usage()
+    }
+
+    pub fn clear(cx: &mut App) {
+        cx.global_mut::<Self>().thumbnails.clear();
+    }
+}
+
+/// Whether the key looks like an image we can decode
+pub fn is_image(key: &str) -> bool {
+    key.rsplit_once('.').is_some_and(|(_, ext)| {
+        IMAGE_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(ext))
+    })
+}
+
+/// Where the thumbnail of the object lives in `cache_dir`.
+///
+/// The etag is part of the name, so a changed object gets a fresh thumbnail.
+pub fn cache_path(cache_dir: &Path, remote_name: &str, key: &str, etag: Option<&str>) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    (remote_name, key, etag).hash(&mut hasher);
+    cache_dir.join(format!("{:016x}.png", hasher.finish()))
+}
+
+/// Fetches the object and writes its thumbnail to `cache_path`, unless it's already cached
+pub async fn load(
+    remote: S3Remote,
+    key: &str,
+    size: i64,
+    cache_path: PathBuf,
+) -> AppResult<PathBuf> {
+    if cache_path.exists() {
+        return Ok(cache_path);
+    }
+    if size > MAX_SOURCE_BYTES {
+        return Err(AppError::message(format!(
+            "\"{key}\" is too large for a thumbnail"
+        )));
+    }
+
+    let body = balti_s3::download_file(remote, key, &cache_path).await?;
+    let bytes = body
+        .collect()
+        .await
+        .map_err(|err| AppError::err(err))?
+        .into_bytes();
+
+    // decoding and resizing are cpu bound, keep them off the async workers
+    tokio::task::spawn_blocking(move || {
+        let thumbnail = image::load_from_memory(&bytes)
+            .map_err(|err| AppError::err(err))?
+            .resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle);
+
+        if let Some(dir) = cache_path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| AppError::err(err))?;
+        }
+        // written aside first, a half written file would be taken as cached
+        let partial = cache_path.with_extension("part");
+        thumbnail
+            .save_with_format(&partial, ImageFormat::Png)
+            .map_err(|err| AppError::err(err))?;
+        std::fs::rename(&partial, &cache_path).map_err(|err| AppError::err(err))?;
+        Ok(cache_path)
+    })
+    .await
+    .map_err(|err| AppError::err(err))?
+}
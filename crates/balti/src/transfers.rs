@@ -0,0 +1,276 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futures::{
+    StreamExt,
+    channel::mpsc::{self, UnboundedSender},
+};
+use gpui::*;
+
+use crate::strings::t;
+
+pub fn init(cx: &mut App) {
+    let transfers = cx.new(Transfers::new);
+    cx.set_global(GlobalTransfers(transfers));
+}
+
+struct GlobalTransfers(Entity<Transfers>);
+
+impl Global for GlobalTransfers {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransferKind {
+    Upload,
+    Download,
+    /// Paste of copied or cut objects into the remote
+    Copy,
+    Delete,
+    /// Integrity check of the objects below a prefix
+    Verify,
+}
+
+impl TransferKind {
+    /// In the order summaries list them
+    const ALL: [Self; 5] = [
+        Self::Upload,
+        Self::Download,
+        Self::Copy,
+        Self::Delete,
+        Self::Verify,
+    ];
+
+    /// Like "3 uploads"
+    fn counted(&self, count: usize) -> String {
+        match self {
+            Self::Upload => t!("transfers.uploads", count = count),
+            Self::Download => t!("transfers.downloads", count = count),
+            Self::Copy => t!("transfers.copies", count = count),
+            Self::Delete => t!("transfers.deletes", count = count),
+            Self::Verify => t!("transfers.verifications", count = count),
+        }
+    }
+}
+
+/// Progress of all running operations together, like the dock or taskbar shows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverallProgress {
+    Idle,
+    /// Percent done of the operations reporting progress, `None` while none do
+    Running(Option<u8>),
+    /// An operation failed since the last time none ran
+    Failed,
+}
+
+/// Sent by [`TransferGuard`] for its operation
+enum Update {
+    Progress { id: u64, done: u64, total: u64 },
+    Failed(u64),
+    Done(u64),
+}
+
+struct Running {
+    remote: Arc<str>,
+    kind: TransferKind,
+    /// Done out of total, in the units the operation counts like bytes or batch items
+    progress: Option<(u64, u64)>,
+}
+
+/// Operations in flight against each remote, across all tabs. Observe
+/// [`Transfers::entity`] to follow them.
+pub struct Transfers {
+    next_id: u64,
+    running: HashMap<u64, Running>,
+    failed: bool,
+    updates_tx: UnboundedSender<Update>,
+    _updates_task: Task<()>,
+}
+
+impl Transfers {
+    fn new(cx: &mut Context<Self>) -> Self {
+        let (updates_tx, mut updates_rx) = mpsc::unbounded::<Update>();
+        let updates_task = cx.spawn(async move |this, cx| {
+            while let Some(update) = updates_rx.next().await {
+                let _ = this.update(cx, |this, cx| match update {
+                    Update::Progress { id, done, total } => {
+                        let before = this.overall();
+                        if let Some(running) = this.running.get_mut(&id) {
+                            running.progress = Some((done, total));
+                        }
+                        // progress comes in often, only whole percents are worth a render
+                        if this.overall() != before {
+                            cx.notify();
+                        }
+                    }
+                    Update::Failed(id) => {
+                        if this.running.contains_key(&id) {
+                            this.failed = true;
+                            cx.notify();
+                        }
+                    }
+                    Update::Done(id) => {
+                        this.running.remove(&id);
+                        cx.notify();
+                    }
+                });
+            }
+        });
+
+        Self {
+            next_id: 0,
+            running: HashMap::new(),
+            failed: false,
+            updates_tx,
+            _updates_task: updates_task,
+        }
+    }
+
+    pub fn entity(cx: &App) -> Entity<Self> {
+        cx.global::<GlobalTransfers>().0.clone()
+    }
+
+    /// Counts an operation on `remote` as running until the guard is dropped,
+    /// so it also clears when the tab running it closes
+    pub fn start(remote: Arc<str>, kind: TransferKind, cx: &mut App) -> TransferGuard {
+        Self::entity(cx).update(cx, |this, cx| {
+            let id = this.next_id;
+            this.next_id += 1;
+            if this.running.is_empty() {
+                this.failed = false;
+            }
+            this.running.insert(
+                id,
+                Running {
+                    remote,
+                    kind,
+                    progress: None,
+                },
+            );
+            cx.notify();
+
+            TransferGuard {
+                id,
+                updates_tx: this.updates_tx.clone(),
+            }
+        })
+    }
+
+    /// Running operations on `remote` by kind, kinds without any left out
+    pub fn counts(&self, remote: &str) -> Vec<(TransferKind, usize)> {
+        TransferKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let count = self
+                    .running
+                    .values()
+                    .filter(|running| running.remote.as_ref() == remote && running.kind == kind)
+                    .count();
+                (kind, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    /// Progress of every running operation on any remote
+    pub fn overall(&self) -> OverallProgress {
+        if self.failed {
+            return OverallProgress::Failed;
+        }
+        overall(self.running.values().map(|running| running.progress))
+    }
+}
+
+/// Each operation weighs the same whatever it counts, so a batch of many small items
+/// and a large download of one file average out. `None` are operations yet to report.
+fn overall(progress: impl Iterator<Item = Option<(u64, u64)>>) -> OverallProgress {
+    let mut running = 0;
+    let fractions = progress
+        .inspect(|_| running += 1)
+        .flatten()
+        .map(|(done, total)| match total {
+            0 => 1.,
+            total => done.min(total) as f64 / total as f64,
+        })
+        .collect::<Vec<_>>();
+
+    if running == 0 {
+        return OverallProgress::Idle;
+    }
+    if fractions.is_empty() {
+        return OverallProgress::Running(None);
+    }
+    let average = fractions.iter().sum::<f64>() / fractions.len() as f64;
+    OverallProgress::Running(Some((average * 100.).floor() as u8))
+}
+
+/// Like "2 uploads, 1 delete running", `None` when nothing runs
+pub fn summary(counts: &[(TransferKind, usize)]) -> Option<String> {
+    if counts.is_empty() {
+        return None;
+    }
+
+    let parts = counts
+        .iter()
+        .map(|(kind, count)| kind.counted(*count))
+        .collect::<Vec<_>>();
+    Some(t!("transfers.running", transfers = parts.join(", ")))
+}
+
+/// Keeps its operation counted while alive
+pub struct TransferGuard {
+    id: u64,
+    updates_tx: UnboundedSender<Update>,
+}
+
+impl TransferGuard {
+    /// Reports `done` out of `total`, in whatever units the operation counts
+    pub fn progress(&self, done: u64, total: u64) {
+        let _ = self.updates_tx.unbounded_send(Update::Progress {
+            id: self.id,
+            done,
+            total,
+        });
+    }
+
+    /// Flags the operation as failed, which shows until operations start from idle again
+    pub fn fail(&self) {
+        let _ = self.updates_tx.unbounded_send(Update::Failed(self.id));
+    }
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        let _ = self.updates_tx.unbounded_send(Update::Done(self.id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_counts() {
+        assert_eq!(summary(&[]), None);
+        assert_eq!(
+            summary(&[(TransferKind::Download, 1)]).as_deref(),
+            Some("1 download running")
+        );
+        assert_eq!(
+            summary(&[(TransferKind::Upload, 2), (TransferKind::Delete, 1)]).as_deref(),
+            Some("2 uploads, 1 delete running")
+        );
+    }
+
+    #[test]
+    fn averages_overall_progress() {
+        assert_eq!(overall([].into_iter()), OverallProgress::Idle);
+        assert_eq!(overall([None].into_iter()), OverallProgress::Running(None));
+        // a batch halfway through its items and a download a tenth through its bytes
+        assert_eq!(
+            overall([Some((40, 80)), Some((100, 1000)), None].into_iter()),
+            OverallProgress::Running(Some(30))
+        );
+        assert_eq!(
+            overall([Some((0, 0)), Some((5, 4))].into_iter()),
+            OverallProgress::Running(Some(100))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::rc::Rc;
+
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    notification::Notification,
+    v_flex,
+};
+
+use crate::{
+    about::{self, Dependency, Diagnostics},
+    strings::t,
+};
+
+/// Third-party licenses show in a list this tall
+const LICENSES_HEIGHT: Pixels = px(200.);
+const LICENSE_ROW_HEIGHT: Pixels = px(28.);
+
+/// Version, build and where the app keeps its files, along with the licenses of the
+/// crates it's built with
+pub fn open_dialog(diagnostics: Diagnostics, window: &mut Window, cx: &mut App) {
+    let diagnostics = Rc::new(diagnostics);
+    let dependencies = Rc::new(about::dependencies());
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let build_date = diagnostics
+            .build_date
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| t!("about.unknown"));
+        let log_file = diagnostics
+            .log_file
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| t!("about.unknown"));
+        let config_dir = diagnostics.config_dir.clone();
+
+        let _dependencies = dependencies.clone();
+        let licenses = uniform_list(
+            "about-licenses",
+            dependencies.len(),
+            move |range, _window, cx| {
+                _dependencies[range]
+                    .iter()
+                    .map(|dependency| license_row(dependency, cx))
+                    .collect()
+            },
+        )
+        .h(LICENSES_HEIGHT);
+
+        let _diagnostics = diagnostics.clone();
+        dialog
+            .rounded_lg()
+            .w(px(560.))
+            .title(t!("about.title"))
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(info_row(
+                        t!("about.version"),
+                        diagnostics.version.to_owned(),
+                        cx,
+                    ))
+                    .child(info_row(
+                        t!("about.commit"),
+                        diagnostics.commit.to_owned(),
+                        cx,
+                    ))
+                    .child(info_row(t!("about.built"), build_date, cx))
+                    .child(info_row(t!("about.os"), diagnostics.os.clone(), cx))
+                    .child(info_row(
+                        t!("about.config_dir"),
+                        h_flex()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .min_w_0()
+                                    .truncate()
+                                    .child(diagnostics.config_dir.display().to_string()),
+                            )
+                            .child(
+                                Button::new("open_config_dir")
+                                    .xsmall()
+                                    .ghost()
+                                    .label(t!("about.open"))
+                                    .on_click(move |_ev, _window, cx| {
+                                        cx.open_with_system(&config_dir)
+                                    }),
+                            ),
+                        cx,
+                    ))
+                    .child(info_row(t!("about.log_file"), log_file, cx))
+                    .child(
+                        div()
+                            .pt_2()
+                            .text_sm()
+                            .font_medium()
+                            .child(t!("about.licenses", count = dependencies.len())),
+                    )
+                    .child(
+                        div()
+                            .border_1()
+                            .border_color(cx.theme().sidebar_border)
+                            .rounded_md()
+                            .child(licenses),
+                    ),
+            )
+            .footer(move |_, _, _, _cx| {
+                let diagnostics = _diagnostics.clone();
+
+                let copy = Button::new("copy_diagnostics")
+                    .label(t!("about.copy_diagnostics"))
+                    .on_click(move |_ev, window, cx| {
+                        cx.write_to_clipboard(ClipboardItem::new_string(diagnostics.report()));
+                        window.push_notification(
+                            Notification::info(t!("about.diagnostics_copied")),
+                            cx,
+                        );
+                    });
+
+                let close = Button::new("close_dialog")
+                    .primary()
+                    .label(t!("common.close"))
+                    .on_click(|_, window, cx| {
+                        window.close_dialog(cx);
+                    });
+
+                vec![copy, close]
+            })
+    });
+}
+
+fn info_row(label: String, value: impl IntoElement, cx: &App) -> impl IntoElement {
+    h_flex()
+        .items_start()
+        .gap_3()
+        .text_sm()
+        .child(
+            div()
+                .w(px(96.))
+                .flex_shrink_0()
+                .text_color(cx.theme().muted_foreground)
+                .child(label),
+        )
+        .child(div().min_w_0().child(value))
+}
+
+fn license_row(dependency: &Dependency, cx: &App) -> impl IntoElement {
+    h_flex()
+        .h(LICENSE_ROW_HEIGHT)
+        .gap_3()
+        .px_2()
+        .text_sm()
+        .border_b_1()
+        .border_color(cx.theme().sidebar_border)
+        .child(
+            div()
+                .flex_1()
+                .min_w_0()
+                .truncate()
+                .child(format!("{} {}", dependency.name, dependency.version)),
+        )
+        .child(
+            div()
+                .flex_shrink_0()
+                .text_color(cx.theme().muted_foreground)
+                .child(dependency.license),
+        )
+}
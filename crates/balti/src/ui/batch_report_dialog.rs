@@ -0,0 +1,536 @@
+use std::{cell::Cell, rc::Rc, sync::Arc};
+
+use balti_err::{AppError, BatchReport, ErrorKind};
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, Icon, IconName, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    dialog::Dialog,
+    h_flex,
+    notification::Notification,
+};
+
+use crate::{
+    coalesce::{BatchProgress, BatchProgressId, OperationId},
+    strings::t,
+    ui::{dismiss::DismissExt, error_notification, loading::LoadingExt},
+};
+
+/// Name a report exported as CSV is suggested to save as
+const CSV_FILE_NAME: &str = "batch-report.csv";
+
+/// Bulk operation a [`BatchReport`] came out of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOperation {
+    Delete,
+    Copy,
+    Move,
+    Verify,
+    Upload,
+}
+
+impl BatchOperation {
+    fn running(&self) -> String {
+        match self {
+            BatchOperation::Delete => t!("batch.delete_running"),
+            BatchOperation::Copy => t!("batch.copy_running"),
+            BatchOperation::Move => t!("batch.move_running"),
+            BatchOperation::Verify => t!("batch.verify_running"),
+            BatchOperation::Upload => t!("batch.upload_running"),
+        }
+    }
+
+    fn finished(&self) -> String {
+        match self {
+            BatchOperation::Delete => t!("batch.delete_finished"),
+            BatchOperation::Copy => t!("batch.copy_finished"),
+            BatchOperation::Move => t!("batch.move_finished"),
+            BatchOperation::Verify => t!("batch.verify_finished"),
+            BatchOperation::Upload => t!("batch.upload_finished"),
+        }
+    }
+
+    fn finished_with_issues(&self) -> String {
+        match self {
+            BatchOperation::Delete => t!("batch.delete_finished_with_issues"),
+            BatchOperation::Copy => t!("batch.copy_finished_with_issues"),
+            BatchOperation::Move => t!("batch.move_finished_with_issues"),
+            BatchOperation::Verify => t!("batch.verify_finished_with_issues"),
+            BatchOperation::Upload => t!("batch.upload_finished_with_issues"),
+        }
+    }
+
+    /// Title and detail of the toast for the `count` items left paused. A verification
+    /// pauses when it's cancelled, the others when a quota runs out.
+    fn paused(&self, count: usize) -> (String, String) {
+        match self {
+            BatchOperation::Verify => (
+                t!("batch.verify_cancelled"),
+                t!("batch.verify_cancelled_detail", count = count),
+            ),
+            _ => (
+                t!("batch.quota_paused"),
+                t!("batch.quota_paused_detail", count = count),
+            ),
+        }
+    }
+
+    fn verb(&self) -> String {
+        match self {
+            BatchOperation::Delete => t!("batch.deleted"),
+            BatchOperation::Copy => t!("batch.copied"),
+            BatchOperation::Move => t!("batch.moved"),
+            BatchOperation::Verify => t!("batch.verified"),
+            BatchOperation::Upload => t!("batch.uploaded"),
+        }
+    }
+}
+
+/// Toast following a batch while it runs, updated in place as items finish
+pub fn progress_notification(operation: BatchOperation, progress: &BatchProgress) -> Notification {
+    Notification::new()
+        .message(progress.label(&operation.verb()))
+        .title(operation.running())
+        .autohide(false)
+}
+
+pub trait BatchReportDialog: Render {
+    fn retry_failed(
+        &mut self,
+        operation: BatchOperation,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+
+    /// Lets the batch's remotes run again after a quota paused them, or a cancelled
+    /// verification go on, then retries `keys`
+    fn resume_paused(
+        &mut self,
+        operation: BatchOperation,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+
+    fn is_retrying(&self, operation: BatchOperation) -> bool;
+
+    /// Reverses the operation the undo `id` was kept for, the latest one when `None`
+    fn undo(&mut self, id: Option<u64>, window: &mut Window, cx: &mut Context<Self>);
+}
+
+/// Forgets a report the last session left, instead of retrying it
+pub type DismissReport = Rc<dyn Fn(&mut Window, &mut App)>;
+
+/// Toast for a report, `None` when the report needs the full dialog
+fn notification(operation: BatchOperation, report: &BatchReport) -> Option<Notification> {
+    if !report.is_clean() {
+        return None;
+    }
+
+    Some(
+        Notification::success(t!("batch.items", count = report.succeeded()))
+            .title(operation.finished())
+            .icon(Icon::new(IconName::CircleCheck).text_color(green())),
+    )
+}
+
+/// Adds an undo button to a batch's toast, when the batch left something to undo
+fn with_undo<T: BatchReportDialog>(
+    notification: Notification,
+    undo: Option<u64>,
+    entity: WeakEntity<T>,
+) -> Notification {
+    let Some(id) = undo else {
+        return notification;
+    };
+
+    notification.action(move |_this, _window, cx| {
+        let entity = entity.clone();
+        Button::new("undo_batch")
+            .small()
+            .label(t!("batch.undo"))
+            .on_click(cx.listener(move |this, _ev, window, cx| {
+                let _ = entity.update(cx, |this, cx| this.undo(Some(id), window, cx));
+                this.dismiss(window, cx);
+            }))
+    })
+}
+
+/// Stays until the user resumes the paused items or dismisses it
+fn paused_notification<T: BatchReportDialog>(
+    operation: BatchOperation,
+    report: &BatchReport,
+    entity: WeakEntity<T>,
+) -> Notification {
+    let keys = Rc::new(report.paused_keys().cloned().collect::<Vec<_>>());
+    let (title, detail) = operation.paused(keys.len());
+
+    Notification::warning(detail)
+        .title(title)
+        .autohide(false)
+        .action(move |_this, _window, cx| {
+            let keys = keys.clone();
+            let entity = entity.clone();
+            Button::new("resume_paused")
+                .primary()
+                .small()
+                .label(t!("batch.resume"))
+                .on_click(cx.listener(move |this, _ev, window, cx| {
+                    let _ = entity.update(cx, |this, cx| {
+                        this.resume_paused(operation, keys.to_vec(), window, cx);
+                        cx.notify();
+                    });
+                    this.dismiss(window, cx);
+                }))
+        })
+}
+
+/// Stays until the user opens the report's dialog from it or dismisses it
+fn issues_notification<T: BatchReportDialog>(
+    operation: BatchOperation,
+    report: BatchReport,
+    id: OperationId,
+    entity: WeakEntity<T>,
+) -> Notification {
+    let message = match report.failed() {
+        0 => report.summary(),
+        failed => t!(
+            "batch.failed_of",
+            failed = failed,
+            total = report.items.len()
+        ),
+    };
+    let report = Rc::new(report);
+
+    Notification::warning(message)
+        .title(operation.finished_with_issues())
+        .autohide(false)
+        .action(move |_this, _window, cx| {
+            let report = report.clone();
+            let id = id.clone();
+            let entity = entity.clone();
+            Button::new("batch_details")
+                .small()
+                .label(t!("common.show_details"))
+                .on_click(cx.listener(move |this, _ev, window, cx| {
+                    let id = Some(id.clone());
+                    open_dialog(
+                        operation,
+                        report.clone(),
+                        id,
+                        None,
+                        entity.clone(),
+                        window,
+                        cx,
+                    );
+                    this.dismiss(window, cx);
+                }))
+        })
+}
+
+/// Shows the toast for a report, replacing the progress toast of the batch. A clean
+/// one offers `undo` when the batch left one, one with issues links to the report
+/// dialog, which names the operation to find it in the journal by. The undo shortcut
+/// still reverts it. Paused items get a toast of their own to resume from.
+pub fn show<T: BatchReportDialog>(
+    operation: BatchOperation,
+    report: BatchReport,
+    mut progress: BatchProgressId,
+    undo: Option<u64>,
+    entity: WeakEntity<T>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    report.log();
+    if let Some(notification) = notification(operation, &report) {
+        let notification = with_undo(notification, undo, entity);
+        window.push_notification(progress.tag(notification), cx);
+        return;
+    }
+    if report.paused() > 0 {
+        let notification = paused_notification(operation, &report, entity.clone());
+        window.push_notification(progress.tag(notification), cx);
+        if report.failed() == 0 {
+            return;
+        }
+        // the paused toast took the progress toast's place, this one goes next to it
+        progress = progress.replaced();
+    }
+
+    let id = progress.operation().clone();
+    let notification = issues_notification(operation, report, id, entity);
+    window.push_notification(progress.tag(notification), cx);
+}
+
+/// Shows the dialog of a report the last session left unresolved, to retry what
+/// didn't go through or `dismiss` it
+pub fn show_restored<T: BatchReportDialog>(
+    operation: BatchOperation,
+    report: BatchReport,
+    dismiss: DismissReport,
+    entity: WeakEntity<T>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    open_dialog(
+        operation,
+        Rc::new(report),
+        None,
+        Some(dismiss),
+        entity,
+        window,
+        cx,
+    );
+}
+
+/// Opens with the failed items listed, they're what the user came for. Restored
+/// reports come without the `id` of their operation.
+fn open_dialog<T: BatchReportDialog>(
+    operation: BatchOperation,
+    report: Rc<BatchReport>,
+    id: Option<OperationId>,
+    dismiss: Option<DismissReport>,
+    entity: WeakEntity<T>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let expanded = Rc::new(Cell::new(report.failed() > 0));
+    window.open_dialog(cx, move |dialog_, _window, cx| {
+        dialog(
+            dialog_,
+            operation,
+            report.clone(),
+            id.clone(),
+            expanded.clone(),
+            dismiss.clone(),
+            entity.clone(),
+            cx,
+        )
+    });
+}
+
+fn dialog<T: BatchReportDialog>(
+    dialog: Dialog,
+    operation: BatchOperation,
+    report: Rc<BatchReport>,
+    id: Option<OperationId>,
+    expanded: Rc<Cell<bool>>,
+    dismiss: Option<DismissReport>,
+    entity: WeakEntity<T>,
+    cx: &mut App,
+) -> Dialog {
+    let muted = cx.theme().muted_foreground;
+    let border = cx.theme().sidebar_border;
+    let danger = cx.theme().danger;
+    let warning = cx.theme().warning;
+
+    let _expanded = expanded.clone();
+    let _entity = entity.clone();
+
+    dialog
+        .alert()
+        .cancel_on_escape(false, move |cx| {
+            _entity
+                .read_with(cx, |this, _cx| this.is_retrying(operation))
+                .unwrap_or_default()
+        })
+        .rounded_lg()
+        .title(operation.finished_with_issues())
+        .v_flex()
+        .gap_2()
+        .child(
+            h_flex()
+                .gap_4()
+                .text_sm()
+                .child(t!("batch.succeeded", count = report.succeeded()))
+                .child(
+                    div()
+                        .text_color(warning)
+                        .child(t!("batch.skipped", count = report.skipped())),
+                )
+                .child(
+                    div()
+                        .text_color(danger)
+                        .child(t!("batch.failed", count = report.failed())),
+                )
+                .when(report.paused() > 0, |this| {
+                    this.child(
+                        div()
+                            .text_color(warning)
+                            .child(t!("batch.paused", count = report.paused())),
+                    )
+                }),
+        )
+        .when_some(id, |this, id| {
+            let copied = id.to_string();
+            this.child(
+                h_flex()
+                    .gap_1()
+                    .text_xs()
+                    .text_color(muted)
+                    .child(t!("batch.operation", id = id))
+                    .child(
+                        Button::new("copy_operation")
+                            .xsmall()
+                            .ghost()
+                            .icon(IconName::Copy)
+                            .tooltip(t!("batch.copy_operation"))
+                            .on_click(move |_ev, _window, cx| {
+                                cx.write_to_clipboard(ClipboardItem::new_string(copied.clone()));
+                            }),
+                    ),
+            )
+        })
+        .child(
+            Button::new("toggle_details")
+                .small()
+                .ghost()
+                .icon(if expanded.get() {
+                    IconName::ChevronUp
+                } else {
+                    IconName::ChevronDown
+                })
+                .label(if expanded.get() {
+                    t!("common.hide_details")
+                } else {
+                    t!("common.show_details")
+                })
+                .on_click(move |_ev, window, _cx| {
+                    _expanded.set(!_expanded.get());
+                    window.refresh();
+                }),
+        )
+        .when(expanded.get(), |this| {
+            this.child(
+                div()
+                    .id("batch-report-items")
+                    .flex()
+                    .flex_col()
+                    .max_h(px(320.))
+                    .overflow_y_scroll()
+                    .border_1()
+                    .border_color(border)
+                    .rounded_md()
+                    // the ones that went through are only counted
+                    .children(report.items.iter().filter_map(|(key, result)| {
+                        let err = result.as_ref().err()?;
+                        let (status, color) = match err.kind {
+                            ErrorKind::Skipped => (t!("batch.status_skipped"), warning),
+                            ErrorKind::Paused => (t!("batch.status_paused"), warning),
+                            _ => (t!("batch.status_failed"), danger),
+                        };
+
+                        let row = h_flex()
+                            .items_start()
+                            .gap_3()
+                            .px_2()
+                            .py_1()
+                            .border_b_1()
+                            .border_color(border)
+                            .text_sm()
+                            .child(
+                                div()
+                                    .w(px(56.))
+                                    .flex_shrink_0()
+                                    .text_color(color)
+                                    .child(status),
+                            )
+                            .child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .min_w_0()
+                                    .child(
+                                        div().font_medium().child(SharedString::new(key.clone())),
+                                    )
+                                    .child(div().text_color(muted).child(err.message.clone())),
+                            );
+                        Some(row)
+                    })),
+            )
+        })
+        .footer(move |_, _, _, cx| {
+            let entity = entity.clone();
+            let _report = report.clone();
+            let csv_report = report.clone();
+            let report = report.clone();
+
+            let is_retrying = entity
+                .read_with(cx, |this, _cx| this.is_retrying(operation))
+                .unwrap_or_default();
+
+            let copy = Button::new("copy_report")
+                .label(t!("batch.copy_report"))
+                .icon(IconName::Copy)
+                .on_click(move |_ev, window, cx| {
+                    cx.write_to_clipboard(ClipboardItem::new_string(_report.report()));
+                    window.push_notification(Notification::info(t!("batch.report_copied")), cx);
+                });
+
+            let export = Button::new("export_csv")
+                .label(t!("batch.export_csv"))
+                .on_click(move |_ev, window, cx| save_csv(&csv_report, window, cx));
+
+            let close = match dismiss.clone() {
+                Some(dismiss) => Button::new("dismiss_report")
+                    .label(t!("batch.dismiss"))
+                    .disabled(is_retrying)
+                    .on_click(move |_, window, cx| {
+                        window.close_dialog(cx);
+                        dismiss(window, cx);
+                    }),
+                None => Button::new("close_dialog")
+                    .label(t!("common.close"))
+                    .disabled(is_retrying)
+                    .on_click(|_, window, cx| {
+                        window.close_dialog(cx);
+                    }),
+            };
+
+            let mut buttons = vec![copy, export, close];
+            if report.failed() > 0 {
+                buttons.push(
+                    Button::new("retry_dialog")
+                        .danger()
+                        .label(t!("batch.retry_failed"))
+                        .disabled(is_retrying)
+                        .loading_state(is_retrying, cx)
+                        .on_click(move |_ev, window, cx| {
+                            let keys = report.failures().map(|(key, _)| key.clone()).collect();
+                            let _ = entity.update(cx, |this, cx| {
+                                this.retry_failed(operation, keys, window, cx);
+                                cx.notify();
+                            });
+                        }),
+                );
+            }
+
+            buttons
+        })
+}
+
+/// Saves the report as CSV where the user picks
+fn save_csv(report: &BatchReport, window: &mut Window, cx: &mut App) {
+    let content = report.csv();
+    let directory = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default();
+    let path_prompt = cx.prompt_for_new_path(&directory, Some(CSV_FILE_NAME));
+
+    window
+        .spawn(cx, async move |cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+            let result = std::fs::write(&path, content).map_err(|err| AppError::err(err));
+            let _ = cx.update(|window, cx| {
+                let notification = match result {
+                    Ok(_) => Notification::success(t!("batch.csv_exported", path = path.display())),
+                    Err(err) => error_notification(&err).title(t!("batch.export_csv_failed")),
+                };
+                window.push_notification(notification, cx);
+            });
+        })
+        .detach();
+}
@@ -1,119 +1,3237 @@
-use std::{collections::HashMap, rc::Rc, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use balti_err::AppError;
-use balti_s3::{__S3Object, S3Object, S3Remote, TrimPrefix};
-use futures::StreamExt;
+use balti_err::{AppError, AppResult, BatchReport, ErrorKind};
+use balti_s3::{
+    __S3Object, Access, Collision, FOLDER_MARKER, KeyQuirk, MAX_DELETE_BATCH, MAX_PAGE_SIZE,
+    ObjectKind, ObjectMeta, ObjectPage, S3Object, S3Remote, TrimPrefix, UPLOAD_CONCURRENCY,
+    UploadDefaults, UploadPlan, VerifyTier,
+};
+use chrono::Utc;
+use futures::{StreamExt, channel::mpsc};
 use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    ActiveTheme, Disableable, Icon, IconName, Sizable, StyledExt, VirtualListScrollHandle,
-    WindowExt,
+    ActiveTheme, Disableable, Icon, IconName, Selectable, Sizable, StyledExt,
+    VirtualListScrollHandle, WindowExt,
     button::{Button, ButtonVariants},
     checkbox::Checkbox,
     h_flex,
-    input::InputState,
+    input::{InputEvent, InputState},
+    menu::{ContextMenuExt, PopupMenu},
     notification::Notification,
     scroll::ScrollableElement,
-    skeleton::Skeleton,
+    tooltip::Tooltip,
     v_virtual_list,
 };
+use tokio::sync::Semaphore;
 
 use crate::{
-    nav::BrowsePrefix,
+    archive::{self, ArchiveProgress, ResumableZip, ZipOutcome, ZipUpdate},
+    bulk::{self, BulkOperation, ObjectCount, Throughput},
+    capabilities::{self, RemoteCapabilities},
+    checkpoint::{self, Checkpoint},
+    clipboard::{
+        self, ClipboardEntry, ClipboardMode, ObjectClipboard, PasteBatch, PasteCheck, PasteItem,
+        PastePlan, PasteReason,
+    },
+    coalesce::{self, BatchProgress, Outcome},
+    config,
+    credentials::ExpiredRemotes,
+    generation::Generation,
+    health::RemoteHealth,
+    journal::{self, Action, JournalEntry},
+    listing::Listing,
+    listing_memory::{self, ListingMemory, RememberedListing},
+    nav::{BrowsePrefix, PendingState, TabStatus},
+    pattern::NamePattern,
+    pending_reports::{PendingReport, PendingReports},
+    quota::{self, QuotaGates},
+    rate::{self, TransferRate},
+    reconcile::{self, Expected},
     rt,
+    selection::Selection,
+    settings::{ListingOrder, Settings, ViewMode, ViewPrefs},
+    staging,
+    strings::t,
+    thumbnail::{self, ThumbnailMemory},
+    transfers::{TransferKind, Transfers},
     ui::{
-        create_folder_dialog, delete_object_dialog,
-        remote::{BrowseNav, BrowseRefreshEvent},
+        EmptyAction,
+        batch_report_dialog::{self, BatchOperation, BatchReportDialog},
+        bulk_confirm::{self, BulkChoice},
+        conflict::{self, ConflictChoice},
+        create_folder_dialog, delete_object_dialog, error_notification, junk_dialog,
+        loading::{self, LoadingExt},
+        object_properties_dialog, paste_plan_dialog, presign_dialog,
+        remote::{
+            BrowseFolderGoneEvent, BrowseNav, BrowseOpenTabEvent, BrowseRefreshEvent,
+            BrowseRevealEvent, BrowseStatusEvent, BrowseSwitchRegionEvent,
+        },
+        rename_dialog, select_pattern_dialog, upload_options_dialog, verify_dialog,
+        wrong_region_notification,
     },
+    undo::{self, MovedObject, Reverted, Undo, UndoConflict, UndoStack},
     util,
 };
 
+actions!(browse, [DeleteSelection, ClearSelection, DownloadSelection]);
+
+/// Key context of a listing, with `selection` added while objects are checked
+pub const BROWSE_CONTEXT: &str = "Browse";
+/// Bindings acting on the checked objects. Without any, the keys fall through to
+/// whatever else handles them.
+pub const SELECTION_CONTEXT: &str = "Browse && selection";
+
+/// Height of an object row at a UI scale of 1
+const ROW_HEIGHT: Pixels = px(40.);
+
+/// Tile size in grid mode at a UI scale of 1
+const TILE_WIDTH: Pixels = px(128.);
+const TILE_HEIGHT: Pixels = px(120.);
+const TILE_GAP: Pixels = px(8.);
+
+/// How often the download status re-renders without progress
+const TRANSFER_TICK: Duration = Duration::from_secs(1);
+
+/// Wait after a mutation before re-listing, lets eventually consistent providers catch up
+const RECONCILE_DELAY: Duration = Duration::from_secs(2);
+
+/// Pages a listing streams in before waiting for "Load more"
+const STREAMED_PAGES: usize = 10;
+
+/// Objects paged in looking for a revealed key before giving up
+const REVEAL_LIMIT: usize = 10_000;
+/// How long a revealed object stays highlighted
+const REVEAL_FLASH: Duration = Duration::from_millis(1500);
+
+/// Maximum folder count listings in flight per view
+const FOLDER_COUNT_CONCURRENCY: usize = 4;
+/// Maximum thumbnail downloads in flight per view
+const THUMBNAIL_CONCURRENCY: usize = 3;
+/// Objects a verification checks at once
+const VERIFY_CONCURRENCY: usize = 8;
+/// Files an upload hands over at once, their outcomes come in together
+const UPLOAD_BATCH: usize = 4 * UPLOAD_CONCURRENCY;
+/// Keys a prompt about upload conflicts names, the rest it counts
+const LISTED_CONFLICTS: usize = 8;
+/// Pause in typing before the search input searches, each keystroke restarts it
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Last verification of a view, failed and cancelled objects get checked again from here
+struct VerifyBatch {
+    tier: VerifyTier,
+    full_limit: u64,
+    /// Listed size of each object
+    sizes: HashMap<Arc<str>, i64>,
+}
+
+enum ZipPhase {
+    Listing,
+    Zipping(ArchiveProgress),
+}
+
+/// Folder being downloaded as a zip, dropping it cancels the download
+struct ZipJob {
+    name: SharedString,
+    phase: ZipPhase,
+    rate: TransferRate,
+    /// Set once the destination is picked, cancelling discards it with the archive
+    checkpoint: Option<Checkpoint>,
+    _task: Task<()>,
+    /// Re-renders the status while no progress arrives, so a stall shows up
+    _ticker: Task<()>,
+}
+
+/// File being saved to disk, dropping it cancels the download
+struct FileDownload {
+    id: usize,
+    name: SharedString,
+    /// Set once the destination is picked, cancelling removes the partial file
+    dest: Option<PathBuf>,
+    bytes_done: u64,
+    /// `None` when the provider didn't send a length
+    total: Option<u64>,
+    rate: TransferRate,
+    _task: Task<()>,
+    /// Re-renders the status while no progress arrives, so a stall shows up
+    _ticker: Task<()>,
+}
+
+/// Files below the prefix with the query somewhere in their path, shown in place of
+/// the listing
+struct Search {
+    hits: Vec<S3Object>,
+    /// Every key below the prefix was looked at, or the hits reached their cap
+    complete: bool,
+    error: Option<AppError>,
+    /// Dropping it cancels the search
+    task: Option<Task<()>>,
+}
+
+/// File row being dragged out of the window, it's downloaded to the staging dir meanwhile.
+/// Dropping it cancels the download.
+struct DragOut {
+    key: Arc<str>,
+    size: u64,
+    bytes_done: u64,
+    /// Set once the download finished
+    path: Option<PathBuf>,
+    /// The drag ended outside the list, the file gets revealed once it's staged
+    released: bool,
+    _task: Task<()>,
+}
+
+/// File row picked up by a drag, also renders as the drag preview
+#[derive(Clone)]
+struct DraggedObject {
+    key: Arc<str>,
+    size: u64,
+    name: SharedString,
+}
+
+impl Render for DraggedObject {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .text_sm()
+            .bg(cx.theme().secondary)
+            .border_1()
+            .border_color(cx.theme().border)
+            .child(Icon::new(IconName::File).small())
+            .child(self.name.clone())
+    }
+}
+
+/// Thumbnail of an image row, fetched while the row is in the viewport
+enum Thumbnail {
+    /// Dropping the task cancels the fetch
+    Pending(Task<()>),
+    /// Held in [`ThumbnailMemory`] from here on
+    Ready,
+    Unavailable,
+}
+
+/// Immediate children of a folder row, fetched lazily
+#[derive(Debug, Clone, Copy)]
+enum FolderCount {
+    Pending,
+    Count { count: usize, more: bool },
+    Failed,
+}
+
+/// Display data of a listed object, built when the listing lands instead of every frame
+struct RowModel {
+    /// Object key, also keeps element state with its object across refreshes
+    id: SharedString,
+    name: SharedString,
+    size: Option<SharedString>,
+    last_modified: SharedString,
+    quirk: Option<KeyQuirk>,
+}
+
+impl RowModel {
+    fn new(object: &S3Object, prefix: &str) -> Self {
+        let key = object.key();
+        let (size, last_modified) = match object.as_ref() {
+            __S3Object::Folder(_) => (None, SharedString::default()),
+            __S3Object::File {
+                size,
+                last_modified,
+                ..
+            } => (
+                Some(util::human_readable_size(*size)),
+                last_modified
+                    .clone()
+                    .map(SharedString::new)
+                    .unwrap_or_default(),
+            ),
+        };
+
+        let name = key.trim_key_prefix(prefix);
+        Self {
+            id: SharedString::new(key.clone()),
+            name: SharedString::new(display_name(&name)),
+            size,
+            last_modified,
+            quirk: object.quirk(),
+        }
+    }
+}
+
+/// Name of a path segment, empty ones like the middle of `a//b.txt` get a placeholder
+/// instead of vanishing
+/// Detail and choices of the prompt before a folder upload that runs into what's there
+fn upload_conflicts(plan: &UploadPlan) -> (String, Vec<(ConflictChoice, String)>) {
+    let mut details = Vec::new();
+    if !plan.collisions.is_empty() {
+        let keys = key_list(plan.collisions.iter().map(Collision::key));
+        details.push(t!(
+            "browse.upload_collisions",
+            count = plan.collisions.len(),
+            keys = keys
+        ));
+    }
+
+    let choices = if plan.overwrites.is_empty() {
+        vec![(ConflictChoice::Proceed, t!("browse.upload_rest"))]
+    } else {
+        let keys = key_list(plan.overwrites.iter().map(String::as_str));
+        details.push(t!(
+            "browse.upload_overwrites",
+            count = plan.overwrites.len(),
+            keys = keys
+        ));
+        vec![
+            (ConflictChoice::Skip, t!("browse.skip_existing")),
+            (ConflictChoice::Proceed, t!("browse.replace_existing")),
+        ]
+    };
+    (details.join("\n\n"), choices)
+}
+
+/// The first few keys a line each, the rest as a count
+fn key_list<'a>(keys: impl ExactSizeIterator<Item = &'a str>) -> String {
+    let rest = keys.len().saturating_sub(LISTED_CONFLICTS);
+    let mut list = keys.take(LISTED_CONFLICTS).collect::<Vec<_>>().join("\n");
+    if rest > 0 {
+        list.push('\n');
+        list.push_str(&t!("browse.and_more", count = rest));
+    }
+    list
+}
+
+fn display_name(name: &str) -> String {
+    let suffix = if name.ends_with('/') { "/" } else { "" };
+    match name.strip_suffix('/').unwrap_or(name) {
+        "" => format!("{}{suffix}", t!("browse.unnamed")),
+        _ => name.to_owned(),
+    }
+}
+
+/// Path of the view listing the folder `key` is in
+fn containing_folder(key: &str) -> SharedString {
+    match key.rfind('/') {
+        Some(at) => SharedString::new(balti_s3::folder_path(&key[..=at])),
+        None => SharedString::new_static("/"),
+    }
+}
+
+/// Warning for keys other tools wrote that don't map onto folders cleanly
+fn quirk_badge(id: &SharedString, quirk: KeyQuirk, cx: &App) -> impl IntoElement {
+    let tooltip = SharedString::new(match quirk {
+        KeyQuirk::EmptySegment => t!("browse.quirk_empty_segment"),
+        KeyQuirk::FolderWithContent => t!("browse.quirk_folder_with_content"),
+    });
+
+    div()
+        .id(SharedString::new(format!("quirk-{id}")))
+        .flex_shrink_0()
+        .text_color(cx.theme().warning)
+        .child(Icon::new(IconName::TriangleAlert).xsmall())
+        .tooltip(move |window, cx| Tooltip::new(tooltip.clone()).build(window, cx))
+}
+
 pub struct BrowseUi {
+    focus_handle: FocusHandle,
     browse_nav: Entity<BrowseNav>,
     s3_remote: S3Remote,
+    view_prefs: Entity<ViewPrefs>,
     prefix: SharedString,
 
-    objects: Vec<S3Object>,
+    listing: Listing,
+    /// Display data, one per entry of the listing's objects
+    rows: Vec<RowModel>,
     item_sizes: Rc<Vec<Size<Pixels>>>,
+    grid_row_sizes: Rc<Vec<Size<Pixels>>>,
+    grid_columns: usize,
     objects_scroll_handle: VirtualListScrollHandle,
-    checked_objects: HashMap<Arc<str>, S3Object>,
+    selection: Selection,
+    /// Last object toggled, shift-click checks the range from here
+    selection_anchor: Option<usize>,
+    /// Re-check whether the prefix still exists once the next listing lands
+    check_folder_gone: bool,
+
+    /// Objects of the last delete, failed keys get retried from here
+    delete_batch: HashMap<Arc<str>, S3Object>,
+    zip_job: Option<ZipJob>,
+    /// Files being downloaded, each with its own progress in the status strip
+    downloads: Vec<FileDownload>,
+    next_download_id: usize,
+    drag_out: Option<DragOut>,
+    /// Last paste into this view, failed items get retried from here
+    paste_batch: Option<PasteBatch>,
+    verify_batch: Option<VerifyBatch>,
+    /// Local file of each key of the last upload, failed ones get retried from here
+    upload_batch: HashMap<Arc<str>, PathBuf>,
+    /// Options the last upload set for itself, its retries upload with them too
+    upload_options: Option<UploadDefaults>,
 
-    loading: bool,
+    folder_counts: HashMap<Arc<str>, FolderCount>,
+    folder_count_permits: Arc<Semaphore>,
+    thumbnails: HashMap<Arc<str>, Thumbnail>,
+    thumbnail_permits: Arc<Semaphore>,
+
+    /// Objects the prefix had last time, the skeleton shows about as many rows
+    remembered_count: Option<usize>,
+    /// Scroll back to where the prefix was left, once the view first shows
+    restore_scroll: bool,
     creating_folder: bool,
+    renaming: bool,
     deleting_objects: bool,
-    error: Option<AppError>,
+    pasting: bool,
+    verifying: bool,
+    uploading: bool,
+    finding_junk: bool,
+    /// Listing streaming pages in, dropping it stops the stream
+    _listing_task: Option<Task<()>>,
+    /// Mutations shown ahead of the listing, checked against the next one
+    expected: Option<Expected>,
+    /// Delayed re-list after a mutation
+    _reconcile_task: Option<Task<()>>,
+    /// Key to scroll to once the listing reaches it
+    reveal: Option<Arc<str>>,
+    /// Object highlighted for a moment after being revealed
+    flashed: Option<Arc<str>>,
+    _flash_task: Option<Task<()>>,
+    /// Key the properties dialog shows, with its metadata once loaded
+    properties: Option<(Arc<str>, Option<AppResult<ObjectMeta>>)>,
+    _properties_task: Option<Task<()>>,
+    /// Query for files anywhere below the prefix, shown in the remote's header
+    search_input: Entity<InputState>,
+    /// Shown in place of the listing while the search input holds a query
+    search: Option<Search>,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl BrowseUi {
     fn new(
         browse_nav: Entity<BrowseNav>,
         s3_remote: S3Remote,
+        view_prefs: Entity<ViewPrefs>,
         prefix: SharedString,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) -> Self {
-        Self {
+        // rows are sized in pixels, follow UI scale and density changes
+        let settings_sub = cx.observe_global::<Settings>(|this, cx| {
+            this.update_item_sizes(cx);
+            cx.notify();
+        });
+        let prefs_sub = cx.observe(&view_prefs, |this, prefs, cx| {
+            if !prefs.read(cx).show_thumbnails {
+                // cancels the pending fetches
+                this.thumbnails.clear();
+            }
+            this.sort_objects(cx);
+            cx.notify();
+        });
+
+        // every tab shows what the clipboard holds
+        let clipboard_sub = cx.observe_global::<ObjectClipboard>(|_this, cx| cx.notify());
+
+        // actions the credentials don't allow get disabled once the probe finishes
+        RemoteCapabilities::ensure(&s3_remote, cx);
+        let capabilities_sub = cx.observe(&RemoteCapabilities::entity(cx), |_this, _caps, cx| {
+            cx.notify()
+        });
+        // the degraded banner comes and goes, its retry countdown ticks
+        let health_sub = cx.observe(&RemoteHealth::entity(cx), |_this, _health, cx| cx.notify());
+        // a refresh replaces the view, the next one starts from what this one showed
+        let release_sub = cx.on_release(|this, cx| this.remember(cx));
+
+        let search_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder(t!("browse.search_hint")));
+        let search_sub = cx.subscribe_in(
+            &search_input,
+            window,
+            |this, _input, event: &InputEvent, window, cx| {
+                if let InputEvent::Change = event {
+                    this.search(window, cx);
+                }
+            },
+        );
+
+        let remembered = ListingMemory::recall(&s3_remote.remote_name, &prefix, cx);
+        let remembered_count = remembered.as_ref().map(|listing| listing.count);
+        let objects = remembered
+            .and_then(|listing| listing.objects)
+            .unwrap_or_default();
+
+        let mut this = Self {
+            focus_handle: cx.focus_handle(),
             browse_nav,
             s3_remote,
+            view_prefs,
             prefix,
-            objects: Vec::new(),
+            listing: Listing::remembered(objects),
+            remembered_count,
+            restore_scroll: true,
+            rows: Vec::new(),
             item_sizes: Rc::new(Vec::new()),
+            grid_row_sizes: Rc::new(Vec::new()),
+            grid_columns: 1,
             objects_scroll_handle: VirtualListScrollHandle::new(),
-            checked_objects: HashMap::new(),
-            loading: false,
+            selection: Selection::default(),
+            selection_anchor: None,
+            check_folder_gone: false,
+            delete_batch: HashMap::new(),
+            zip_job: None,
+            downloads: Vec::new(),
+            next_download_id: 0,
+            drag_out: None,
+            paste_batch: None,
+            upload_batch: HashMap::new(),
+            upload_options: None,
+            verify_batch: None,
+            folder_counts: HashMap::new(),
+            folder_count_permits: Arc::new(Semaphore::new(FOLDER_COUNT_CONCURRENCY)),
+            thumbnails: HashMap::new(),
+            thumbnail_permits: Arc::new(Semaphore::new(THUMBNAIL_CONCURRENCY)),
             creating_folder: false,
+            renaming: false,
             deleting_objects: false,
-            error: None,
+            pasting: false,
+            verifying: false,
+            uploading: false,
+            finding_junk: false,
+            _listing_task: None,
+            expected: None,
+            _reconcile_task: None,
+            reveal: None,
+            flashed: None,
+            _flash_task: None,
+            properties: None,
+            _properties_task: None,
+            search_input,
+            search: None,
+            _subscriptions: vec![
+                settings_sub,
+                prefs_sub,
+                clipboard_sub,
+                capabilities_sub,
+                health_sub,
+                release_sub,
+                search_sub,
+            ],
+        };
+        if this.listing.stale {
+            this.sort_objects(cx);
+            this.update_item_sizes(cx);
         }
+        this
     }
 
     pub fn view(
         browse_nav: Entity<BrowseNav>,
         s3_remote: S3Remote,
+        view_prefs: Entity<ViewPrefs>,
         prefix: SharedString,
         window: &mut Window,
         cx: &mut App,
     ) -> Entity<Self> {
         cx.new(|cx| {
-            let mut view = Self::new(browse_nav, s3_remote, prefix, window, cx);
+            let mut view = Self::new(browse_nav, s3_remote, view_prefs, prefix, window, cx);
             view.list_objects(window, cx);
             view
         })
     }
 
+    /// Lists the prefix from the start, what's shown is replaced once the first page lands
     fn list_objects(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // what the search found may have changed as well
+        if self.search.is_some() {
+            self.search(window, cx);
+        }
+        self.stream_listing(None, window, cx);
+    }
+
+    fn load_more(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(token) = self.listing.next_token.clone() else {
+            return;
+        };
+        if self.listing.loading || self.listing.loading_more {
+            return;
+        }
+
+        self.stream_listing(Some(token), window, cx);
+    }
+
+    /// Stops filling in pages, like once the user navigated away. What landed stays
+    /// and "Load more" picks up from there. Pages of the stopped listing that land
+    /// afterwards are dropped.
+    pub fn stop_listing(&mut self, cx: &mut Context<Self>) {
+        let waiting = self.listing.stop();
+        if self
+            .search
+            .as_mut()
+            .and_then(|search| search.task.take())
+            .is_some()
+        {
+            cx.notify();
+        }
+        if self._listing_task.take().is_some() {
+            if waiting {
+                let status = match self.listing.error {
+                    Some(_) => TabStatus::Error,
+                    None => TabStatus::Ok,
+                };
+                self.emit_status(status, cx);
+            }
+            cx.notify();
+        }
+    }
+
+    /// Whether a listing from the start runs and its first page hasn't landed yet
+    pub fn is_listing(&self) -> bool {
+        self.listing.loading
+    }
+
+    /// Whether a listing of the prefix ever came back, a refresh keeps what it showed
+    pub fn has_listed(&self) -> bool {
+        self.listing.last_refreshed.is_some()
+    }
+
+    /// Streams up to [`STREAMED_PAGES`] pages into the view as they arrive, from the
+    /// start without `token` or continuing after it. Pages come in key order, so
+    /// appending them keeps the listing sorted. Starting another stream or dropping
+    /// the view cancels this one.
+    fn stream_listing(
+        &mut self,
+        token: Option<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
         let remote = self.s3_remote.clone();
         let prefix = self.prefix.clone();
+        let page_size = Settings::get(cx).page_size(&remote);
+        let restart = token.is_none();
+        let generation = self.listing.start(restart);
+        let (pages_tx, mut pages) = mpsc::unbounded();
+
+        let task = rt::spawn(cx, async move {
+            let result = balti_s3::list_objects_paged(
+                remote.clone(),
+                balti_s3::listed_prefix(&prefix),
+                page_size,
+                token.as_deref(),
+                STREAMED_PAGES,
+                // the receiver is gone once the stream got cancelled
+                |page| pages_tx.unbounded_send(page).is_ok(),
+            )
+            .await;
+            // offered as a fix in the error notification
+            let region = match &result {
+                Err(err) if err.kind == ErrorKind::WrongRegion => {
+                    balti_s3::bucket_region(remote).await.ok()
+                }
+                _ => None,
+            };
+            (result, region)
+        });
+
+        if restart {
+            self.emit_status(TabStatus::Loading, cx);
+        }
+        cx.notify();
+
+        self._listing_task = Some(cx.spawn_in(window, async move |this, cx| {
+            let mut replace = restart;
+            while let Some(page) = pages.next().await {
+                let _ = this.update_in(cx, |this, window, cx| {
+                    if this.show_page(generation, page, replace, cx) {
+                        replace = false;
+                        this.try_reveal(window, cx);
+                        cx.notify();
+                    }
+                });
+            }
+
+            let (result, region) = match task.await {
+                Ok(listed) => listed,
+                Err(err) => (Err(err), None),
+            };
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                // a newer listing took over, its state isn't ours to touch
+                if !this.listing.finish(generation) {
+                    return;
+                }
+                this._listing_task = None;
+                if result.is_ok() {
+                    this.remember(cx);
+                }
+
+                match result {
+                    Ok(()) if restart => {
+                        this.check_expected();
+                        this.listing.prune(&mut this.selection);
+
+                        if std::mem::take(&mut this.check_folder_gone) && this.is_folder_gone() {
+                            this.browse_nav.update(cx, |_, cx| {
+                                cx.emit(BrowseFolderGoneEvent(this.prefix.clone()));
+                            });
+                        }
+                    }
+                    Ok(()) => {}
+                    // the first page failed, nothing of the new listing is shown
+                    Err(err) if replace => {
+                        this.note_error(&err, cx);
+                        let notification = match region {
+                            Some(region) => {
+                                let browse_nav = this.browse_nav.clone();
+                                let prefix = this.prefix.clone();
+                                wrong_region_notification(
+                                    &err,
+                                    region.clone(),
+                                    move |_window, cx| {
+                                        browse_nav.update(cx, |_nav, cx| {
+                                            cx.emit(BrowseSwitchRegionEvent(
+                                                prefix.clone(),
+                                                region.clone(),
+                                            ));
+                                        });
+                                    },
+                                )
+                            }
+                            None => error_notification(&err).title(t!("browse.fetch_failed")),
+                        };
+                        // the degraded banner tells already
+                        if !this.is_degraded(cx) {
+                            window.push_notification(notification, cx);
+                        }
+                        this.reveal = None;
+                        this.emit_status(TabStatus::Error, cx);
+                        this.listing.fail(err);
+                    }
+                    Err(err) => {
+                        this.reveal = None;
+                        this.note_error(&err, cx);
+                        let notification =
+                            error_notification(&err).title(t!("browse.fetch_more_failed"));
+                        if !this.is_degraded(cx) {
+                            window.push_notification(notification, cx);
+                        }
+                    }
+                };
+
+                this.try_reveal(window, cx);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Input searching below the prefix, the remote's header shows it
+    pub fn search_input(&self) -> &Entity<InputState> {
+        &self.search_input
+    }
+
+    /// Query in the search input, empty while the listing shows
+    pub fn search_query(&self, cx: &App) -> SharedString {
+        self.search_input.read(cx).value()
+    }
+
+    /// Searches for `query`, like when a fresh view takes over from the one before
+    pub fn search_for(&mut self, query: SharedString, window: &mut Window, cx: &mut Context<Self>) {
+        self.search_input
+            .update(cx, |input, cx| input.set_value(query, window, cx));
+        self.search(window, cx);
+    }
+
+    /// Searches below the prefix for the query in the search input once typing pauses,
+    /// cancelling the search before. Hits show as they're found, clearing the input
+    /// goes back to the listing.
+    fn search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.search_input.read(cx).value().trim().to_owned();
+        if query.is_empty() {
+            self.search = None;
+            cx.notify();
+            return;
+        }
+
+        let remote = self.s3_remote.clone();
+        let prefix = self.listed_prefix().to_owned();
+        let (hits_tx, mut hits) = mpsc::unbounded();
+
+        let task = cx.spawn_in(window, async move |this, cx| {
+            cx.background_executor().timer(SEARCH_DEBOUNCE).await;
+            let Ok(task) = this.update(cx, |_this, cx| {
+                rt::spawn(cx, async move {
+                    // the receiver is gone once the search got cancelled
+                    balti_s3::search_objects(remote, &prefix, &query, |matches| {
+                        hits_tx.unbounded_send(matches).is_ok()
+                    })
+                    .await
+                })
+            }) else {
+                return;
+            };
+
+            while let Some(matches) = hits.next().await {
+                let _ = this.update(cx, |this, cx| {
+                    if let Some(search) = this.search.as_mut() {
+                        search.hits.extend(matches);
+                        cx.notify();
+                    }
+                });
+            }
+
+            let result = task.await.flatten();
+            let _ = this.update(cx, |this, cx| {
+                if let Err(err) = &result {
+                    err.log();
+                    this.note_error(err, cx);
+                }
+                if let Some(search) = this.search.as_mut() {
+                    search.task = None;
+                    match result {
+                        Ok(()) => search.complete = true,
+                        Err(err) => search.error = Some(err),
+                    }
+                }
+                cx.notify();
+            });
+        });
+
+        self.search = Some(Search {
+            hits: Vec::new(),
+            complete: false,
+            error: None,
+            task: Some(task),
+        });
+        cx.notify();
+    }
+
+    /// Scrolls to the object at `key` and highlights it for a moment. Pages of a large
+    /// folder are loaded until it shows up, up to [`REVEAL_LIMIT`] objects.
+    pub fn reveal(&mut self, key: Arc<str>, window: &mut Window, cx: &mut Context<Self>) {
+        self.reveal = Some(key);
+        self.try_reveal(window, cx);
+    }
+
+    /// Reveals the pending key once it's loaded, otherwise loads more or gives up
+    fn try_reveal(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(key) = self.reveal.clone() else {
+            return;
+        };
+        let name = key.trim_key_prefix(self.prefix.as_str()).to_string();
+
+        let position = self
+            .listing
+            .objects
+            .iter()
+            .position(|object| *object.key() == key)
+            .ok_or_else(|| reconcile::last_key(&self.listing.objects));
+        match position {
+            Ok(index) => {
+                self.reveal = None;
+                let item = match self.view_prefs.read(cx).view_mode {
+                    ViewMode::List => index,
+                    ViewMode::Grid => index / self.grid_columns.max(1),
+                };
+                self.objects_scroll_handle
+                    .scroll_to_item(item, ScrollStrategy::Center);
+                self.flash(key, cx);
+            }
+            // what's shown gets replaced by the fresh listing
+            Err(_) if self.listing.loading => {}
+            // pages come in key order, it'd have been listed by now
+            Err(last)
+                if last.is_some_and(|last| key < last) || self.listing.next_token.is_none() =>
+            {
+                self.reveal = None;
+                window.push_notification(
+                    Notification::warning(t!("browse.reveal_missing", name = name)),
+                    cx,
+                );
+            }
+            // the stream is still filling in pages
+            Err(_) if self.listing.loading_more => {}
+            Err(_) if self.listing.objects.len() >= REVEAL_LIMIT => {
+                self.reveal = None;
+                window.push_notification(
+                    Notification::warning(t!(
+                        "browse.reveal_not_loaded",
+                        name = name,
+                        count = self.listing.objects.len()
+                    )),
+                    cx,
+                );
+            }
+            Err(_) => self.load_more(window, cx),
+        }
+    }
+
+    fn flash(&mut self, key: Arc<str>, cx: &mut Context<Self>) {
+        self.flashed = Some(key);
+        self._flash_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(REVEAL_FLASH).await;
+            let _ = this.update(cx, |this, cx| {
+                this.flashed = None;
+                cx.notify();
+            });
+        }));
+        cx.notify();
+    }
+
+    /// Keeps what's shown for the next view of the prefix, like after a refresh
+    fn remember(&self, cx: &mut App) {
+        if self.listing.error.is_some() || self.listing.stale {
+            return;
+        }
+        let listing =
+            RememberedListing::new(&self.listing.objects, self.objects_scroll_handle.offset());
+        ListingMemory::remember(&self.s3_remote.remote_name, &self.prefix, listing, cx);
+    }
+
+    fn is_flashed(&self, object: &S3Object) -> bool {
+        self.flashed.as_ref() == Some(object.key())
+    }
+
+    /// Shows a page of the listing `generation`, `replace` drops what was shown before
+    /// for a fresh listing. `false` when the listing got replaced or stopped.
+    fn show_page(
+        &mut self,
+        generation: Generation,
+        page: ObjectPage,
+        replace: bool,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if !self.listing.apply_page(generation, page, replace) {
+            return false;
+        }
+
+        RemoteHealth::note_success(&self.s3_remote.remote_name, cx);
+        if replace {
+            self.emit_status(TabStatus::Ok, cx);
+            self.rows.clear();
+            self.selection_anchor = None;
+            self.folder_counts.clear();
+            self.thumbnails.clear();
+        }
+        self.sort_objects(cx);
+        self.update_item_sizes(cx);
+        true
+    }
+
+    /// Shows what a mutation changed right away, then re-lists a bit later to replace
+    /// the guess with what the provider lists
+    fn reconcile(&mut self, change: Expected, window: &mut Window, cx: &mut Context<Self>) {
+        if !change.is_empty() {
+            change.apply(
+                &mut self.listing.objects,
+                self.view_prefs.read(cx).order,
+                self.listing.next_token.is_none(),
+            );
+            self.selection.retain(|key| !change.removed.contains(key));
+            self.rows.clear();
+            self.update_rows();
+            self.selection_anchor = None;
+            self.update_item_sizes(cx);
+
+            match self.expected.as_mut() {
+                Some(expected) => expected.merge(change),
+                None => self.expected = Some(change),
+            }
+        }
+        cx.notify();
+
+        self._reconcile_task = Some(cx.spawn_in(window, async move |this, cx| {
+            cx.background_executor().timer(RECONCILE_DELAY).await;
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.list_objects(window, cx);
+            });
+        }));
+    }
+
+    /// Logs where a fresh listing disagrees with the mutations shown ahead of it
+    fn check_expected(&mut self) {
+        let Some(expected) = self.expected.take() else {
+            return;
+        };
+        let stale =
+            expected.discrepancies(&self.listing.objects, self.listing.next_token.is_none());
+        if !stale.is_empty() {
+            tracing::warn!(
+                "Listing of {} on {} still disagrees with recent changes on: {}",
+                self.prefix,
+                self.s3_remote.remote_name,
+                stale.join(", ")
+            );
+        }
+    }
+
+    /// Listing prefix of the view, `""` at the bucket root and `a/b/` otherwise
+    fn listed_prefix(&self) -> &str {
+        balti_s3::listed_prefix(&self.prefix)
+    }
+
+    /// Fetches child counts for the folder rows currently in the viewport
+    fn request_folder_counts(&mut self, range: Range<usize>, cx: &mut Context<Self>) {
+        if !self.view_prefs.read(cx).show_folder_counts || self.is_degraded(cx) {
+            return;
+        }
+
+        let folders = self.listing.objects[range.start.min(self.listing.objects.len())
+            ..range.end.min(self.listing.objects.len())]
+            .iter()
+            .filter_map(|obj| match obj.as_ref() {
+                __S3Object::Folder(key) if !self.folder_counts.contains_key(key) => {
+                    Some(key.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for key in folders.into_iter() {
+            self.folder_counts.insert(key.clone(), FolderCount::Pending);
+
+            let remote = self.s3_remote.clone();
+            let permits = self.folder_count_permits.clone();
+            let list_key = key.clone();
+
+            let task = rt::spawn(cx, async move {
+                let _permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|err| AppError::err(err))?;
+                balti_s3::list_objects(remote, list_key.as_ref(), MAX_PAGE_SIZE, None).await
+            });
+
+            cx.spawn(async move |this, cx| {
+                let result = task.await.flatten();
+
+                let _ = this.update(cx, |this, cx| {
+                    let count = match result {
+                        Ok(page) => FolderCount::Count {
+                            count: page.objects.len(),
+                            more: page.next_token.is_some(),
+                        },
+                        Err(_) => FolderCount::Failed,
+                    };
+                    this.folder_counts.insert(key, count);
+                    cx.notify();
+                });
+            })
+            .detach();
+        }
+    }
+
+    /// Fetches thumbnails for the image rows currently in the viewport, and
+    /// cancels the ones still pending for rows scrolled out of it
+    fn request_thumbnails(&mut self, range: Range<usize>, cx: &mut Context<Self>) {
+        if !self.view_prefs.read(cx).show_thumbnails || self.is_degraded(cx) {
+            return;
+        }
+
+        let visible = self.listing.objects[range.start.min(self.listing.objects.len())
+            ..range.end.min(self.listing.objects.len())]
+            .iter()
+            .filter_map(|obj| match obj.as_ref() {
+                __S3Object::File {
+                    key,
+                    size,
+                    etag,
+                    marker: false,
+                    ..
+                } if thumbnail::is_image(key) => Some((key.clone(), *size, etag.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let visible_keys = visible.iter().map(|(key, ..)| key).collect::<HashSet<_>>();
+        self.thumbnails.retain(|key, thumbnail| {
+            !matches!(thumbnail, Thumbnail::Pending(_)) || visible_keys.contains(key)
+        });
+
+        let cache_dir = config::thumbnails_dir();
+        for (key, size, etag) in visible.into_iter() {
+            let remote = self.s3_remote.clone();
+            let cache_path =
+                thumbnail::cache_path(&cache_dir, &remote.remote_name, &key, etag.as_deref());
+            // a changed etag makes for a different path, that one gets fetched
+            if ThumbnailMemory::touch(&remote.remote_name, &key, &cache_path, cx) {
+                continue;
+            }
+            if matches!(
+                self.thumbnails.get(&key),
+                Some(Thumbnail::Pending(_) | Thumbnail::Unavailable)
+            ) {
+                continue;
+            }
+            if size > thumbnail::MAX_SOURCE_BYTES {
+                self.thumbnails.insert(key, Thumbnail::Unavailable);
+                continue;
+            }
+
+            let permits = self.thumbnail_permits.clone();
+            let load_key = key.clone();
+
+            let task = rt::spawn(cx, async move {
+                let _permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|err| AppError::err(err))?;
+                thumbnail::load(remote, &load_key, size, cache_path).await
+            });
+
+            let result_key = key.clone();
+            let task = cx.spawn(async move |this, cx| {
+                let result = task.await.flatten();
+
+                let _ = this.update(cx, |this, cx| {
+                    let thumbnail = match result {
+                        Ok(path) => {
+                            let remote_name = this.s3_remote.remote_name.clone();
+                            ThumbnailMemory::remember(&remote_name, &result_key, path, cx);
+                            Thumbnail::Ready
+                        }
+                        Err(_) => Thumbnail::Unavailable,
+                    };
+                    this.thumbnails.insert(result_key, thumbnail);
+                    cx.notify();
+                });
+            });
+            self.thumbnails.insert(key, Thumbnail::Pending(task));
+        }
+    }
+
+    /// Icon or thumbnail of the object, `large` for grid tiles
+    fn render_object_icon(&self, object: &S3Object, large: bool, cx: &App) -> AnyElement {
+        match object.as_ref() {
+            __S3Object::Folder(_) => Icon::new(IconName::Folder)
+                .when(large, |this| this.size_12())
+                .into_any_element(),
+            __S3Object::File { key, .. } => {
+                let thumbnail = self
+                    .view_prefs
+                    .read(cx)
+                    .show_thumbnails
+                    .then(|| ThumbnailMemory::recall(&self.s3_remote.remote_name, key, cx))
+                    .flatten();
+
+                match thumbnail {
+                    Some(path) => img(path)
+                        .map(|this| if large { this.size_12() } else { this.size_6() })
+                        .rounded_sm()
+                        .object_fit(ObjectFit::Cover)
+                        .into_any_element(),
+                    None => Icon::empty()
+                        .path("icons/file-digit.svg")
+                        .when(large, |this| this.size_12())
+                        .into_any_element(),
+                }
+            }
+        }
+    }
+
+    fn folder_count_label(&self, key: &Arc<str>, cx: &App) -> Option<SharedString> {
+        if !self.view_prefs.read(cx).show_folder_counts {
+            return None;
+        }
+
+        match self.folder_counts.get(key)? {
+            FolderCount::Pending => Some(SharedString::new_static("…")),
+            FolderCount::Count { count, more } if *more || *count >= MAX_PAGE_SIZE as usize => {
+                Some(SharedString::new(t!(
+                    "browse.folder_count_more",
+                    count = MAX_PAGE_SIZE
+                )))
+            }
+            FolderCount::Count { count, .. } => {
+                Some(SharedString::new(t!("browse.folder_count", count = count)))
+            }
+            FolderCount::Failed => None,
+        }
+    }
+
+    /// Downloads the checked file, or zips the checked folder. Several objects can't be
+    /// downloaded at once yet.
+    fn download_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let can_read =
+            RemoteCapabilities::get(&self.s3_remote.remote_name, cx).read != Access::Denied;
+        let selected = self.selected_objects();
+        match selected.as_slice() {
+            [object] if object.is_folder() && can_read => {
+                let prefix = SharedString::new(object.key().clone());
+                self.download_zip(prefix, window, cx);
+            }
+            [object] if can_read => self.download_file(object.key().clone(), window, cx),
+            _ => window
+                .push_notification(Notification::warning(t!("browse.download_single_only")), cx),
+        }
+    }
+
+    /// Asks where to save the file at `key` and downloads it there, showing its progress
+    /// in the status strip
+    fn download_file(&mut self, key: Arc<str>, window: &mut Window, cx: &mut Context<Self>) {
+        let name = SharedString::new(key.rsplit('/').next().unwrap_or_default().to_owned());
+        let directory = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let path_prompt = cx.prompt_for_new_path(&directory, Some(&name));
+        let remote = self.s3_remote.clone();
+        self.next_download_id += 1;
+        let id = self.next_download_id;
+
+        let task = cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(dest))) = path_prompt.await else {
+                let _ = this.update(cx, |this, cx| {
+                    this.downloads.retain(|download| download.id != id);
+                    cx.notify();
+                });
+                return;
+            };
+
+            // lives as long as this task, which goes away with the download
+            let Ok(transfer) = this.update(cx, |this, cx| {
+                if let Some(download) = this.download_mut(id) {
+                    download.dest = Some(dest.clone());
+                }
+                Transfers::start(remote.remote_name.clone(), TransferKind::Download, cx)
+            }) else {
+                return;
+            };
+
+            let (progress_tx, mut progress_rx) = mpsc::unbounded();
+            let Ok(download_task) = this.update(cx, |_this, cx| {
+                let dest = dest.clone();
+                rt::spawn(cx, async move {
+                    let on_progress = move |bytes_done: u64, total: Option<u64>| {
+                        let _ = progress_tx.unbounded_send((bytes_done, total));
+                    };
+                    balti_s3::download_file_with_progress(remote, &key, &dest, &on_progress).await
+                })
+            }) else {
+                return;
+            };
+
+            while let Some((bytes_done, total)) = progress_rx.next().await {
+                if let Some(total) = total {
+                    transfer.progress(bytes_done, total);
+                }
+                let _ = this.update(cx, |this, cx| {
+                    if let Some(download) = this.download_mut(id) {
+                        download.rate.record(Instant::now(), bytes_done);
+                        download.bytes_done = bytes_done;
+                        download.total = total;
+                    }
+                    cx.notify();
+                });
+            }
+
+            let result = download_task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                let notification = match result {
+                    Ok(_) => Notification::success(t!("browse.saved", path = dest.display()))
+                        .title(t!("browse.file_downloaded"))
+                        .icon(Icon::new(IconName::CircleCheck).text_color(green())),
+                    Err(err) => {
+                        transfer.fail();
+                        error_notification(&err).title(t!("browse.file_download_failed"))
+                    }
+                };
+                window.push_notification(notification, cx);
+
+                // drops this task, keep it last
+                this.downloads.retain(|download| download.id != id);
+                cx.notify();
+            });
+        });
+
+        let ticker = cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(TRANSFER_TICK).await;
+                if this.update(cx, |_this, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.downloads.push(FileDownload {
+            id,
+            name,
+            dest: None,
+            bytes_done: 0,
+            total: None,
+            rate: TransferRate::new(),
+            _task: task,
+            _ticker: ticker,
+        });
+        cx.notify();
+    }
+
+    fn download_mut(&mut self, id: usize) -> Option<&mut FileDownload> {
+        self.downloads.iter_mut().find(|download| download.id == id)
+    }
+
+    /// Drops the download `id`, which aborts it, along with what it saved so far
+    fn cancel_download(&mut self, id: usize, cx: &mut Context<Self>) {
+        let Some(index) = self.downloads.iter().position(|download| download.id == id) else {
+            return;
+        };
+        if let Some(dest) = self.downloads.remove(index).dest {
+            let _ = std::fs::remove_file(dest);
+        }
+        cx.notify();
+    }
+
+    fn download_zip(&mut self, prefix: SharedString, window: &mut Window, cx: &mut Context<Self>) {
+        if self.zip_job.is_some() {
+            window.push_notification(Notification::warning(t!("browse.zip_in_progress")), cx);
+            return;
+        }
+
+        let name = SharedString::new(
+            prefix
+                .trim_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_owned(),
+        );
+        let file_name = format!("{name}.zip");
+        let directory = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let path_prompt = cx.prompt_for_new_path(&directory, Some(&file_name));
+        let remote_name = self.s3_remote.remote_name.clone();
+
+        let task = cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(dest))) = path_prompt.await else {
+                let _ = this.update(cx, |this, cx| {
+                    this.zip_job = None;
+                    cx.notify();
+                });
+                return;
+            };
+            let checkpoint = Checkpoint::new(&remote_name, &prefix, dest, Utc::now());
+            Self::run_zip(this, checkpoint, cx).await;
+        });
+        self.start_zip_job(name, task, cx);
+    }
+
+    /// Queues the `keys` of a report the last session left again, `from` is the remote
+    /// a paste copied from
+    pub fn retry_restored(
+        &mut self,
+        report: &PendingReport,
+        from: Option<S3Remote>,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match (report.mode(), from) {
+            (None, _) => self.delete(report.objects(&keys), None, window, cx),
+            (Some(mode), Some(from)) => {
+                let items = report.paste_items(&keys);
+                self.run_paste(from, mode, items, HashMap::new(), window, cx);
+            }
+            (Some(_), None) => {}
+        }
+    }
+
+    /// Continues a folder download where its checkpoint left off
+    pub fn resume_zip(
+        &mut self,
+        checkpoint: Checkpoint,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.zip_job.is_some() {
+            window.push_notification(Notification::warning(t!("browse.zip_in_progress")), cx);
+            return;
+        }
+
+        let name = SharedString::new(checkpoint.name().to_owned());
+        let task = cx.spawn_in(window, async move |this, cx| {
+            Self::run_zip(this, checkpoint, cx).await;
+        });
+        self.start_zip_job(name, task, cx);
+    }
+
+    fn start_zip_job(&mut self, name: SharedString, task: Task<()>, cx: &mut Context<Self>) {
+        let ticker = cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(TRANSFER_TICK).await;
+                if this.update(cx, |_this, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.zip_job = Some(ZipJob {
+            name,
+            phase: ZipPhase::Listing,
+            rate: TransferRate::new(),
+            checkpoint: None,
+            _task: task,
+            _ticker: ticker,
+        });
+        cx.notify();
+    }
+
+    /// Lists the checkpoint's folder and zips it, both from where it got to before.
+    /// The checkpoint follows along, so a failure or a quit midway can resume later.
+    async fn run_zip(
+        this: WeakEntity<Self>,
+        mut checkpoint: Checkpoint,
+        cx: &mut AsyncWindowContext,
+    ) {
+        let dir = config::checkpoints_dir();
+        // lives as long as this task, which goes away with the job
+        let Ok((remote, transfer, budget)) = this.update(cx, |this, cx| {
+            if let Some(job) = this.zip_job.as_mut() {
+                job.checkpoint = Some(checkpoint.clone());
+            }
+            let transfer = Transfers::start(
+                this.s3_remote.remote_name.clone(),
+                TransferKind::Download,
+                cx,
+            );
+            (
+                this.s3_remote.clone(),
+                transfer,
+                Settings::get(cx).object_budget,
+            )
+        }) else {
+            return;
+        };
+
+        let mut entries = checkpoint::load_entries(&dir, &checkpoint).unwrap_or_else(|err| {
+            tracing::warn!("Listing {} again: {}", checkpoint.prefix, err.message);
+            checkpoint::restart(&dir, &mut checkpoint);
+            Vec::new()
+        });
+        let listed_now = !checkpoint.listed_all;
+        let list_prefix = balti_s3::listed_prefix(&checkpoint.prefix).to_owned();
+
+        while !checkpoint.listed_all {
+            let Ok(list_task) = this.update(cx, |_this, cx| {
+                let (remote, list_prefix) = (remote.clone(), list_prefix.clone());
+                let token = checkpoint.next_token.clone();
+                rt::spawn(cx, async move {
+                    archive::list_page(remote, &list_prefix, token.as_deref()).await
+                })
+            }) else {
+                return;
+            };
+
+            match list_task.await.flatten() {
+                Ok((page, next_token)) => {
+                    if let Err(err) = checkpoint::append_entries(&dir, &checkpoint, &page) {
+                        tracing::warn!("Failed to record listed objects: {}", err.message);
+                    }
+                    checkpoint.listed += page.len();
+                    checkpoint.listed_all = next_token.is_none();
+                    checkpoint.next_token = next_token.map(|token| token.to_string());
+                    Self::save_checkpoint(&dir, &mut checkpoint);
+                    entries.extend(page);
+                }
+                Err(err) => {
+                    transfer.fail();
+                    let _ = this.update_in(cx, |this, window, cx| {
+                        let notification = Self::resume_notification(
+                            error_notification(&err).title(t!("browse.list_folder_failed")),
+                            checkpoint,
+                            cx,
+                        );
+                        window.push_notification(notification, cx);
+                        this.zip_job = None;
+                        cx.notify();
+                    });
+                    return;
+                }
+            }
+        }
+
+        let count = ObjectCount::exact(entries.len());
+        let total = archive::total_size(&entries);
+        // a download resumed past its listing got confirmed back then
+        if listed_now && !Self::confirm_zip(&this, count, total, cx).await {
+            checkpoint::discard(&dir, &checkpoint);
+            let _ = this.update(cx, |this, cx| {
+                this.zip_job = None;
+                cx.notify();
+            });
+            return;
+        }
+
+        let snapshot = checkpoint::load_snapshot(&dir, &checkpoint);
+        let Ok(open_task) = this.update(cx, |_this, cx| {
+            let (dest, prefix) = (checkpoint.dest.clone(), checkpoint.prefix.clone());
+            let completed = checkpoint.completed.min(entries.len());
+            let expected = entries[..completed].to_vec();
+            rt::spawn(cx, async move {
+                if let Some(snapshot) = snapshot
+                    && let Some(zip) = ResumableZip::restore(&dest, &snapshot, &prefix, &expected)?
+                {
+                    return Ok((zip, completed));
+                }
+                ResumableZip::create(&dest).map(|zip| (zip, 0))
+            })
+        }) else {
+            return;
+        };
+
+        let (zip, completed) = match open_task.await.flatten() {
+            Ok(opened) => opened,
+            Err(err) => {
+                transfer.fail();
+                let _ = this.update_in(cx, |this, window, cx| {
+                    window.push_notification(
+                        error_notification(&err).title(t!("browse.download_failed")),
+                        cx,
+                    );
+                    this.zip_job = None;
+                    cx.notify();
+                });
+                return;
+            }
+        };
+        if completed != checkpoint.completed {
+            // the archive starts over, its old snapshot no longer applies
+            checkpoint.completed = 0;
+            checkpoint.archive_len = 0;
+            Self::save_checkpoint(&dir, &mut checkpoint);
+        }
+
+        let started = Instant::now();
+        let (updates_tx, mut updates_rx) = mpsc::unbounded();
+        let Ok(zip_task) = this.update(cx, |_this, cx| {
+            let prefix = Arc::from(checkpoint.prefix.as_str());
+            rt::spawn(
+                cx,
+                archive::write_zip(remote, prefix, entries, zip, completed, budget, updates_tx),
+            )
+        }) else {
+            return;
+        };
+
+        while let Some(update) = updates_rx.next().await {
+            match update {
+                ZipUpdate::Progress(progress) => {
+                    transfer.progress(progress.bytes_done, total);
+                    let _ = this.update(cx, |this, cx| {
+                        if let Some(job) = this.zip_job.as_mut() {
+                            job.rate.record(Instant::now(), progress.bytes_done);
+                            job.phase = ZipPhase::Zipping(progress);
+                        }
+                        cx.notify();
+                    });
+                }
+                ZipUpdate::Snapshot {
+                    completed,
+                    snapshot,
+                } => {
+                    if let Err(err) = checkpoint::save_snapshot(
+                        &dir,
+                        &mut checkpoint,
+                        completed,
+                        &snapshot,
+                        Utc::now(),
+                    ) {
+                        tracing::warn!("Failed to save download checkpoint: {}", err.message);
+                    }
+                }
+            }
+        }
+
+        let result = zip_task.await.flatten();
+
+        let _ = this.update_in(cx, |this, window, cx| {
+            let notification = match result {
+                Ok(ZipOutcome::Done) => {
+                    checkpoint::remove(&dir, &checkpoint);
+                    Throughput::note(
+                        BulkOperation::Download,
+                        count.objects - completed,
+                        started.elapsed(),
+                        cx,
+                    );
+                    Notification::success(t!("browse.saved", path = checkpoint.dest.display()))
+                        .title(t!("browse.folder_downloaded"))
+                        .icon(Icon::new(IconName::CircleCheck).text_color(green()))
+                }
+                Ok(ZipOutcome::Paused) => {
+                    let name = checkpoint.name().to_owned();
+                    Self::resume_notification(
+                        Notification::info(t!(
+                            "browse.zip_paused_detail",
+                            name = name,
+                            count = budget
+                        ))
+                        .title(t!("browse.zip_paused")),
+                        checkpoint,
+                        cx,
+                    )
+                }
+                Err(err) => {
+                    transfer.fail();
+                    Self::resume_notification(
+                        error_notification(&err).title(t!("browse.download_failed")),
+                        checkpoint,
+                        cx,
+                    )
+                }
+            };
+            window.push_notification(notification, cx);
+
+            // drops this task, keep it last
+            this.zip_job = None;
+            cx.notify();
+        });
+    }
+
+    /// Asks before zipping a lot of objects or bytes, `true` to go ahead
+    async fn confirm_zip(
+        this: &WeakEntity<Self>,
+        count: ObjectCount,
+        total: u64,
+        cx: &mut AsyncWindowContext,
+    ) -> bool {
+        let Ok(too_many) = this.update(cx, |_this, cx| bulk_confirm::needs_confirmation(count, cx))
+        else {
+            return false;
+        };
+
+        if too_many {
+            let Ok(answer) = this.update_in(cx, |_this, window, cx| {
+                bulk_confirm::ask(BulkOperation::Download, count, false, window, cx)
+            }) else {
+                return false;
+            };
+            answer.await == BulkChoice::Proceed
+        } else if total > archive::LARGE_ARCHIVE_BYTES {
+            let Ok(answer) = this.update_in(cx, |_this, window, cx| {
+                window.prompt(
+                    PromptLevel::Warning,
+                    &t!("browse.large_download"),
+                    Some(&t!(
+                        "browse.large_download_detail",
+                        count = count.objects,
+                        size = util::human_readable_size(total as i64)
+                    )),
+                    &[
+                        PromptButton::Cancel(t!("common.cancel").into()),
+                        PromptButton::Ok(t!("browse.download").into()),
+                    ],
+                    cx,
+                )
+            }) else {
+                return false;
+            };
+            answer.await == Ok(1)
+        } else {
+            true
+        }
+    }
+
+    fn save_checkpoint(dir: &Path, checkpoint: &mut Checkpoint) {
+        if let Err(err) = checkpoint::save(dir, checkpoint, Utc::now()) {
+            tracing::warn!("Failed to save download checkpoint: {}", err.message);
+        }
+    }
+
+    /// Adds a button continuing the download from its checkpoint
+    fn resume_notification(
+        notification: Notification,
+        checkpoint: Checkpoint,
+        cx: &mut Context<Self>,
+    ) -> Notification {
+        let entity = cx.weak_entity();
+
+        notification
+            .autohide(false)
+            .action(move |_this, _window, cx| {
+                let checkpoint = checkpoint.clone();
+                let entity = entity.clone();
+                Button::new("resume_zip")
+                    .primary()
+                    .small()
+                    .label(t!("browse.resume_download"))
+                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                        let _ = entity.update(cx, |browse, cx| {
+                            browse.resume_zip(checkpoint.clone(), window, cx)
+                        });
+                        this.dismiss(window, cx);
+                    }))
+            })
+    }
+
+    /// Downloads the dragged file to the staging dir while the drag goes on. gpui can't
+    /// hand files to the OS drag, so once the drag ends outside the list the staged
+    /// file is revealed in the file manager to be dragged on from there.
+    fn start_drag_out(
+        &mut self,
+        dragged: &DraggedObject,
+        preview: &Entity<DraggedObject>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote.clone();
+        let key = dragged.key.clone();
+        let size = dragged.size;
+        let dest = staging::staged_path(&config::staging_dir(), &remote.remote_name, &key);
+        let failed_key = key.clone();
+
+        let task = cx.spawn_in(window, async move |this, cx| {
+            // lives as long as this task, which goes away with the drag
+            let Ok(transfer) = this.update(cx, |this, cx| {
+                Transfers::start(
+                    this.s3_remote.remote_name.clone(),
+                    TransferKind::Download,
+                    cx,
+                )
+            }) else {
+                return;
+            };
+
+            let (progress_tx, mut progress_rx) = mpsc::unbounded();
+            let Ok(stage_task) = this.update(cx, |_this, cx| {
+                rt::spawn(cx, async move {
+                    staging::stage(remote, &key, dest, progress_tx).await
+                })
+            }) else {
+                return;
+            };
+
+            while let Some(bytes_done) = progress_rx.next().await {
+                transfer.progress(bytes_done, size);
+                let _ = this.update(cx, |this, cx| {
+                    if let Some(drag) = this.drag_out.as_mut() {
+                        drag.bytes_done = bytes_done;
+                    }
+                    cx.notify();
+                });
+            }
+
+            let result = stage_task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok(path) => {
+                    if let Some(drag) = this.drag_out.as_mut() {
+                        drag.path = Some(path);
+                    }
+                    this.finish_drag_out(cx);
+                }
+                Err(err) => {
+                    transfer.fail();
+                    let notification = this.drag_out_failed(failed_key, size, err, cx);
+                    window.push_notification(notification, cx);
+                    // drops this task, keep it last
+                    this.drag_out = None;
+                    cx.notify();
+                }
+            });
+        });
+
+        // the drag is over once its preview goes away, a drop on the list came first
+        cx.observe_release(preview, |this, _preview, cx| {
+            if let Some(drag) = this.drag_out.as_mut() {
+                drag.released = true;
+            }
+            this.finish_drag_out(cx);
+        })
+        .detach();
+
+        self.drag_out = Some(DragOut {
+            key: dragged.key.clone(),
+            size: dragged.size,
+            bytes_done: 0,
+            path: None,
+            released: false,
+            _task: task,
+        });
+        cx.notify();
+    }
+
+    /// Error toast of a dragged out file, with a button to resume it when it got partway
+    fn drag_out_failed(
+        &self,
+        key: Arc<str>,
+        size: u64,
+        err: AppError,
+        cx: &mut Context<Self>,
+    ) -> Notification {
+        let notification = error_notification(&err).title(t!("browse.drag_out_failed"));
+        let dest = staging::staged_path(&config::staging_dir(), &self.s3_remote.remote_name, &key);
+        if !staging::can_resume(&dest) {
+            return notification;
+        }
+
+        let entity = cx.weak_entity();
+        notification
+            .autohide(false)
+            .action(move |_this, _window, cx| {
+                let key = key.clone();
+                let entity = entity.clone();
+                Button::new("resume_drag_out")
+                    .primary()
+                    .small()
+                    .label(t!("browse.resume_download"))
+                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                        let _ = entity.update(cx, |browse, cx| {
+                            browse.resume_drag_out(key.clone(), size, window, cx)
+                        });
+                        this.dismiss(window, cx);
+                    }))
+            })
+    }
+
+    /// Continues a dragged out file where its download broke off. The drag is long over,
+    /// so the file gets revealed to drag on once it's done.
+    fn resume_drag_out(
+        &mut self,
+        key: Arc<str>,
+        size: u64,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote.clone();
+        let dest = staging::staged_path(&config::staging_dir(), &remote.remote_name, &key);
+        let transfer = Transfers::start(remote.remote_name.clone(), TransferKind::Download, cx);
+        let (progress_tx, mut progress_rx) = mpsc::unbounded();
+        let stage_key = key.clone();
+        let task = rt::spawn(cx, async move {
+            staging::stage(remote, &stage_key, dest, progress_tx).await
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            while let Some(bytes_done) = progress_rx.next().await {
+                transfer.progress(bytes_done, size);
+            }
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok(path) => cx.reveal_path(&path),
+                Err(err) => {
+                    transfer.fail();
+                    let notification = this.drag_out_failed(key, size, err, cx);
+                    window.push_notification(notification, cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Reveals the staged file once both the drag ended and the download finished
+    fn finish_drag_out(&mut self, cx: &mut Context<Self>) {
+        let Some(DragOut {
+            path: Some(path),
+            released: true,
+            ..
+        }) = self.drag_out.as_ref()
+        else {
+            return;
+        };
+
+        cx.reveal_path(path);
+        self.drag_out = None;
+        cx.notify();
+    }
+
+    /// Dropped back onto the list
+    fn cancel_drag_out(&mut self, cx: &mut Context<Self>) {
+        // dropping the task aborts the download and removes the partial file
+        let Some(drag) = self.drag_out.take() else {
+            return;
+        };
+        if let Some(path) = drag.path.as_ref() {
+            staging::remove(path);
+        }
+        cx.notify();
+    }
+
+    /// Like "Preparing 45%" for the file row being dragged out
+    fn drag_out_label(&self, key: &str) -> Option<SharedString> {
+        let drag = self.drag_out.as_ref().filter(|drag| drag.path.is_none())?;
+        if drag.key.as_ref() != key {
+            return None;
+        }
+        let percent = (drag.bytes_done * 100)
+            .checked_div(drag.size)
+            .unwrap_or(100)
+            .min(100);
+        Some(SharedString::new(t!(
+            "browse.drag_out_progress",
+            percent = percent
+        )))
+    }
+
+    /// Re-lists in place once `interval` has passed, unless that would pull
+    /// the listing out from under the user
+    pub fn auto_refresh(
+        &mut self,
+        interval: Duration,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // keeps the "last refreshed" label ticking
+        cx.notify();
+
+        if self.listing.loading
+            || self.listing.loading_more
+            || self.creating_folder
+            || self.renaming
+            || self.deleting_objects
+            || !self.selection.is_empty()
+            || self.search.is_some()
+            || window.has_active_dialog(cx)
+            || self.is_degraded(cx)
+        {
+            return;
+        }
+        if self
+            .listing
+            .last_refreshed
+            .is_some_and(|refreshed| refreshed.elapsed() < interval)
+        {
+            return;
+        }
+
+        self.list_objects(window, cx);
+    }
+
+    fn last_refreshed_label(&self, cx: &App) -> Option<SharedString> {
+        self.view_prefs.read(cx).auto_refresh()?;
+
+        let secs = self.listing.last_refreshed?.elapsed().as_secs();
+        let ago = if secs < 60 {
+            format!("{secs}s")
+        } else {
+            format!("{}m", secs / 60)
+        };
+        Some(SharedString::new(t!("browse.last_refreshed", ago = ago)))
+    }
+
+    /// Nothing is left under a non-root prefix, so S3 no longer has the folder
+    fn is_folder_gone(&self) -> bool {
+        self.prefix != "/" && self.listing.is_gone()
+    }
+
+    /// Checks or unchecks the object at `index`, `extend` covers the range
+    /// from the previously toggled object as well
+    fn toggle_checked(&mut self, index: usize, checked: bool, extend: bool) {
+        let range = match self.selection_anchor {
+            Some(anchor) if extend => anchor.min(index)..=anchor.max(index),
+            _ => index..=index,
+        };
+
+        for object in self.listing.objects
+            [*range.start()..=(*range.end()).min(self.listing.objects.len() - 1)]
+            .iter()
+        {
+            self.selection.set(object.key().clone(), checked);
+        }
+        self.selection_anchor = Some(index);
+    }
+
+    /// Loaded objects that are checked, in listing order
+    fn selected_objects(&self) -> Vec<S3Object> {
+        self.selection
+            .filter(self.listing.objects.iter(), |object| object.key().as_ref())
+            .cloned()
+            .collect()
+    }
+
+    /// Selection and running operations closing the tab would drop
+    pub fn pending_state(&self) -> PendingState {
+        let running = [
+            self.deleting_objects,
+            self.pasting,
+            self.verifying,
+            self.uploading,
+            self.finding_junk,
+            self.creating_folder,
+            self.renaming,
+            self.zip_job.is_some(),
+            self.drag_out.is_some(),
+        ];
+        let running = running.into_iter().filter(|running| *running).count() + self.downloads.len();
+        PendingState {
+            selected: self.selected_count(),
+            running,
+        }
+    }
+
+    /// Asks before deleting the checked objects, unless the credentials can't delete
+    fn confirm_delete(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let allowed = RemoteCapabilities::get(&self.s3_remote.remote_name, cx);
+        if allowed.delete == Access::Denied || self.deleting_objects {
+            return;
+        }
+
+        let count = self.selected_count();
+        let entity = cx.weak_entity();
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            delete_object_dialog::dialog(dialog, count, entity.clone())
+        });
+    }
+
+    fn selected_count(&self) -> usize {
+        self.selection
+            .filter(self.listing.objects.iter(), |object| object.key().as_ref())
+            .count()
+    }
+
+    fn set_grid_width(&mut self, width: Pixels, cx: &mut Context<Self>) {
+        let settings = Settings::get(cx);
+        let tile = settings.scaled(TILE_WIDTH) + settings.scaled(TILE_GAP);
+        let columns = ((width + settings.scaled(TILE_GAP)) / tile).floor().max(1.) as usize;
+
+        if columns != self.grid_columns {
+            self.grid_columns = columns;
+            self.update_item_sizes(cx);
+            cx.notify();
+        }
+    }
+
+    /// Builds rows for the objects that don't have one yet, clear `rows` when replacing the listing
+    /// Puts the objects in the view's order, pages are listed in key order so this runs
+    /// after each one. Rows move along with their objects.
+    fn sort_objects(&mut self, cx: &App) {
+        self.update_rows();
+        let order = self.view_prefs.read(cx).order;
+        let anchor = self
+            .selection_anchor
+            .and_then(|index| self.listing.objects.get(index))
+            .map(|object| object.key().clone());
+
+        let mut listed = std::mem::take(&mut self.listing.objects)
+            .into_iter()
+            .zip(std::mem::take(&mut self.rows))
+            .collect::<Vec<_>>();
+        // already sorted runs merge in linear time
+        listed.sort_by(|(a, _), (b, _)| order.compare(a, b));
+        (self.listing.objects, self.rows) = listed.into_iter().unzip();
+
+        self.selection_anchor = anchor.and_then(|key| {
+            self.listing
+                .objects
+                .iter()
+                .position(|object| *object.key() == key)
+        });
+    }
+
+    fn update_rows(&mut self) {
+        let rows = self.listing.objects[self.rows.len().min(self.listing.objects.len())..]
+            .iter()
+            .map(|object| RowModel::new(object, self.prefix.as_str()));
+        self.rows.extend(rows);
+    }
+
+    fn update_item_sizes(&mut self, cx: &App) {
+        let settings = Settings::get(cx);
+        Self::resize_item_sizes(
+            &mut self.item_sizes,
+            self.listing.objects.len(),
+            size(px(256.), settings.row_height(ROW_HEIGHT)),
+        );
+
+        let grid_rows = self
+            .listing
+            .objects
+            .len()
+            .div_ceil(self.grid_columns.max(1));
+        let grid_row_height = settings.scaled(TILE_HEIGHT) + settings.scaled(TILE_GAP);
+        Self::resize_item_sizes(
+            &mut self.grid_row_sizes,
+            grid_rows,
+            size(px(256.), grid_row_height),
+        );
+    }
+
+    /// Resizes `sizes` in place, entries are only rebuilt when the row size changed
+    fn resize_item_sizes(sizes: &mut Rc<Vec<Size<Pixels>>>, len: usize, item: Size<Pixels>) {
+        if sizes.len() == len && sizes.first().is_none_or(|first| *first == item) {
+            return;
+        }
+
+        let sizes = Rc::make_mut(sizes);
+        if sizes.first().is_some_and(|first| *first != item) {
+            sizes.clear();
+        }
+        sizes.resize(len, item);
+    }
+
+    /// Degrades the remote in the sidebar when its credentials ran out
+    fn note_error(&self, err: &AppError, cx: &mut Context<Self>) {
+        ExpiredRemotes::note_error(&self.s3_remote.remote_name, err, cx);
+        RemoteHealth::note_error(&self.s3_remote.remote_name, err, cx);
+    }
+
+    /// The remote's endpoint stopped answering, background requests wait for it
+    fn is_degraded(&self, cx: &App) -> bool {
+        RemoteHealth::is_degraded(&self.s3_remote.remote_name, cx)
+    }
+
+    /// Like [`BrowseUi::note_error`] for every failed item of a batch
+    fn note_report(&self, report: &BatchReport, cx: &mut Context<Self>) {
+        for (_, result) in report.items.iter() {
+            if let Err(err) = result {
+                self.note_error(err, cx);
+            }
+        }
+    }
+
+    /// Journal entry of `action` on the remote, `results` holds the error of every key
+    /// it failed for
+    fn journal_entry<'a>(
+        &self,
+        action: Action,
+        results: impl IntoIterator<Item = (&'a str, Option<&'a AppError>)>,
+    ) -> JournalEntry {
+        JournalEntry::new(&self.s3_remote.remote_name, action, results, Utc::now())
+    }
+
+    fn emit_status(&self, status: TabStatus, cx: &mut Context<Self>) {
+        let prefix = self.prefix.clone();
+        self.browse_nav.update(cx, |_nav, cx| {
+            cx.emit(BrowseStatusEvent(prefix, status));
+        });
+    }
+
+    fn open_in_new_tab(&mut self, prefix: SharedString, cx: &mut Context<Self>) {
+        self.browse_nav.update(cx, |_nav, cx| {
+            cx.emit(BrowseOpenTabEvent(prefix));
+        });
+    }
+
+    /// Adds a button to `notification` taking back the operation kept as undo `id`
+    fn with_undo(
+        &self,
+        notification: Notification,
+        id: u64,
+        cx: &mut Context<Self>,
+    ) -> Notification {
+        let entity = cx.weak_entity();
+        notification.action(move |_this, _window, cx| {
+            let entity = entity.clone();
+            Button::new("undo_operation")
+                .small()
+                .label(t!("batch.undo"))
+                .on_click(cx.listener(move |this, _ev, window, cx| {
+                    let _ = entity.update(cx, |browse, cx| browse.undo(Some(id), window, cx));
+                    this.dismiss(window, cx);
+                }))
+        })
+    }
+
+    fn rename_dialog(&mut self, object: S3Object, window: &mut Window, cx: &mut Context<Self>) {
+        rename_dialog::open_dialog(cx.weak_entity(), object, window, cx);
+    }
+
+    fn verify_dialog(&mut self, prefix: Arc<str>, window: &mut Window, cx: &mut Context<Self>) {
+        verify_dialog::open_dialog(cx.weak_entity(), prefix, window, cx);
+    }
+
+    /// Looks for junk in the folder shown and everything below it
+    pub fn find_junk_here(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let prefix = Arc::from(self.listed_prefix());
+        self.find_junk(prefix, window, cx);
+    }
+
+    /// Lists every key below `prefix` for the junk the settings describe, the matches
+    /// kept after review go through the usual batch delete
+    fn find_junk(&mut self, prefix: Arc<str>, window: &mut Window, cx: &mut Context<Self>) {
+        if self.finding_junk {
+            window.push_notification(Notification::warning(t!("browse.junk_in_progress")), cx);
+            return;
+        }
+        let matcher = match Settings::get(cx).junk_matcher() {
+            Ok(matcher) => matcher,
+            Err(err) => {
+                window.push_notification(
+                    error_notification(&err).title(t!("browse.find_junk_failed")),
+                    cx,
+                );
+                return;
+            }
+        };
+
+        let remote = self.s3_remote.clone();
+        let _prefix = prefix.clone();
+        let task = rt::spawn(cx, async move {
+            let mut found = Vec::new();
+            let mut token: Option<Arc<str>> = None;
+            loop {
+                let page = balti_s3::list_objects_recursive(
+                    remote.clone(),
+                    &_prefix,
+                    MAX_PAGE_SIZE,
+                    token.as_deref(),
+                )
+                .await?;
+                found.extend(page.objects.into_iter().filter_map(|object| {
+                    let reason = matcher.reason(&object)?;
+                    Some((object, reason))
+                }));
+
+                match page.next_token {
+                    Some(next) => token = Some(next),
+                    None => break,
+                }
+            }
+            AppResult::Ok(found)
+        });
+
+        self.finding_junk = true;
+        window.push_notification(Notification::info(t!("browse.finding_junk")), cx);
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+
+            let Ok(Some(review)) = this.update_in(cx, |this, window, cx| {
+                this.finding_junk = false;
+                cx.notify();
+                match result {
+                    Ok(found) if found.is_empty() => {
+                        window.push_notification(Notification::success(t!("browse.no_junk")), cx);
+                        None
+                    }
+                    Ok(found) => Some(junk_dialog::review(prefix, found, window, cx)),
+                    Err(err) => {
+                        this.note_error(&err, cx);
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.find_junk_failed")),
+                            cx,
+                        );
+                        None
+                    }
+                }
+            }) else {
+                return;
+            };
+            let Some(approved) = review.await else {
+                return;
+            };
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                if this.deleting_objects {
+                    window.push_notification(
+                        Notification::warning(t!("browse.delete_in_progress")),
+                        cx,
+                    );
+                    return;
+                }
+                let total = approved.len();
+                this.delete(approved, Some(total), window, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Renames a file in place, undone like a move
+    fn rename_file(
+        &mut self,
+        from_key: Arc<str>,
+        to_key: String,
+        size: i64,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote.clone();
+        let (_from_key, _to_key) = (from_key.clone(), to_key.clone());
+        let task = rt::spawn(cx, async move {
+            balti_s3::rename_object(remote, &_from_key, &_to_key).await
+        });
+
+        self.renaming = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.renaming = false;
+
+                match result {
+                    Ok(etag) => {
+                        window.close_dialog(cx);
+                        let entry = this.journal_entry(Action::Rename, [(from_key.as_ref(), None)]);
+                        journal::record(entry.to(&to_key), cx);
+                        let added = reconcile::listed_entry(this.listed_prefix(), &to_key, size);
+                        let change = Expected {
+                            added: added.into_iter().collect(),
+                            removed: HashSet::from([from_key.clone()]),
+                        };
+                        this.reconcile(change, window, cx);
+
+                        let name = to_key.rsplit('/').next().unwrap_or_default().to_owned();
+                        let undo = Undo::Move {
+                            from: this.s3_remote.clone(),
+                            to: this.s3_remote.clone(),
+                            objects: vec![MovedObject {
+                                from_key,
+                                to_key: to_key.into(),
+                                etag,
+                            }],
+                        };
+                        let id = UndoStack::push(undo, cx);
+                        let notification = Notification::success(t!("browse.renamed", name = name));
+                        window.push_notification(this.with_undo(notification, id, cx), cx);
+                    }
+                    Err(err) => {
+                        let entry =
+                            this.journal_entry(Action::Rename, [(from_key.as_ref(), Some(&err))]);
+                        journal::record(entry.to(&to_key), cx);
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.rename_failed")),
+                            cx,
+                        );
+                    }
+                };
+
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Renames a folder by moving every key below it to `to_prefix`, as a cut and paste
+    /// would. Failed keys show in the batch report and can be retried from there.
+    fn rename_folder(
+        &mut self,
+        folder: S3Object,
+        to_prefix: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.pasting {
+            window.push_notification(Notification::warning(t!("browse.paste_in_progress")), cx);
+            return;
+        }
+
+        let remote = self.s3_remote.clone();
+        let entry = ClipboardEntry {
+            remote: remote.clone(),
+            prefix: folder.key().clone(),
+            objects: vec![folder],
+            mode: ClipboardMode::Cut,
+        };
+        let task = rt::spawn(cx, async move {
+            // moving into a folder that's there already would merge the two
+            if balti_s3::object_exists(remote, &to_prefix).await? {
+                return Err(AppError::message(format!("\"{to_prefix}\" already exists"))
+                    .with_kind(ErrorKind::AlreadyExists));
+            }
+            clipboard::plan(entry, to_prefix.into()).await
+        });
+
+        self.renaming = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.renaming = false;
+
+                match result {
+                    Ok(items) => {
+                        window.close_dialog(cx);
+                        let from = this.s3_remote.clone();
+                        this.run_paste(from, ClipboardMode::Cut, items, HashMap::new(), window, cx);
+                    }
+                    Err(err) => window.push_notification(
+                        error_notification(&err).title(t!("browse.rename_failed")),
+                        cx,
+                    ),
+                };
+
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Opens the properties dialog of the file at `key`, its metadata loads in
+    fn open_properties(&mut self, key: Arc<str>, window: &mut Window, cx: &mut Context<Self>) {
+        let remote = self.s3_remote.clone();
+        let _key = key.clone();
+        let task = rt::spawn(
+            cx,
+            async move { balti_s3::head_object(remote, &_key).await },
+        );
+
+        self.properties = Some((key.clone(), None));
+        let loaded_key = key.clone();
+        self._properties_task = Some(cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                match &result {
+                    // deleted since it was listed, the listing catches up
+                    Err(err) if err.kind == ErrorKind::NotFound => {
+                        let change = Expected {
+                            removed: HashSet::from([loaded_key.clone()]),
+                            ..Default::default()
+                        };
+                        this.reconcile(change, window, cx);
+                    }
+                    Err(err) => {
+                        err.log();
+                        this.note_error(err, cx);
+                    }
+                    Ok(_) => {}
+                }
+                if let Some((shown, properties)) = this.properties.as_mut()
+                    && *shown == loaded_key
+                {
+                    *properties = Some(result);
+                }
+                window.refresh();
+            });
+        }));
+
+        let entity = cx.weak_entity();
+        window.open_dialog(cx, move |dialog, _window, cx| {
+            object_properties_dialog::dialog(dialog, key.clone(), entity.clone(), cx)
+        });
+    }
+
+    /// Copies the file at `key` next to itself, numbered to keep clear of existing names
+    fn duplicate(&mut self, key: Arc<str>, size: i64, window: &mut Window, cx: &mut Context<Self>) {
+        let remote = self.s3_remote.clone();
+        let _key = key.clone();
+        let task = rt::spawn(cx, async move {
+            balti_s3::duplicate_object(remote, &_key).await
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                match result {
+                    Ok(to_key) => {
+                        let entry = this.journal_entry(Action::Duplicate, [(key.as_ref(), None)]);
+                        journal::record(entry.to(&to_key), cx);
+                        let added = reconcile::listed_entry(this.listed_prefix(), &to_key, size);
+                        let change = Expected {
+                            added: added.into_iter().collect(),
+                            ..Default::default()
+                        };
+                        this.reconcile(change, window, cx);
+
+                        let name = to_key.rsplit('/').next().unwrap_or_default();
+                        window.push_notification(
+                            Notification::success(t!("browse.duplicated", name = name)),
+                            cx,
+                        );
+                    }
+                    Err(err) => {
+                        let entry =
+                            this.journal_entry(Action::Duplicate, [(key.as_ref(), Some(&err))]);
+                        journal::record(entry, cx);
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.duplicate_failed")),
+                            cx,
+                        );
+                    }
+                };
+
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn new_folder_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        create_folder_dialog::open_dialog(cx.weak_entity(), self.prefix.clone(), window, cx);
+    }
+
+    /// Asks for local files to upload with `options`, or the remote's upload defaults
+    /// when `None`
+    fn open_upload_prompt(
+        &mut self,
+        options: Option<UploadDefaults>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
+            prompt: None,
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.upload_files(paths, options, window, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Asks for a local folder to upload with `options`, or the remote's upload defaults
+    /// when `None`
+    fn open_upload_folder_prompt(
+        &mut self,
+        options: Option<UploadDefaults>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: None,
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.upload_folders(paths, Vec::new(), options, window, cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Uploads files and folders dropped from the desktop like picked ones, unless the
+    /// credentials can't write or an upload still runs
+    fn drop_files(&mut self, paths: &ExternalPaths, window: &mut Window, cx: &mut Context<Self>) {
+        let allowed = RemoteCapabilities::get(&self.s3_remote.remote_name, cx);
+        if allowed.write == Access::Denied || self.uploading {
+            return;
+        }
+        let (dirs, files): (Vec<_>, Vec<_>) = paths
+            .paths()
+            .iter()
+            .filter(|path| path.is_dir() || path.is_file())
+            .cloned()
+            .partition(|path| path.is_dir());
+        if dirs.is_empty() {
+            self.upload_files(files, None, window, cx);
+        } else {
+            self.upload_folders(dirs, files, None, window, cx);
+        }
+    }
+
+    /// Uploads local files right below the prefix, each under its own name. With
+    /// `options`, or the remote's upload defaults when `None`.
+    fn upload_files(
+        &mut self,
+        paths: Vec<PathBuf>,
+        options: Option<UploadDefaults>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let items = self.upload_items(paths);
+        if !items.is_empty() {
+            self.upload(items, options, window, cx);
+        }
+    }
+
+    /// Keys local files upload to right below the prefix
+    fn upload_items(&self, paths: Vec<PathBuf>) -> Vec<(Arc<str>, PathBuf)> {
+        let prefix = self.listed_prefix();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let key = format!("{prefix}{}", path.file_name()?.to_string_lossy());
+                Some((Arc::from(key), path))
+            })
+            .collect()
+    }
+
+    /// Uploads local folders below the prefix under their own names, along with `files`
+    /// dropped next to them. The destination is checked first: names taken by the other
+    /// of file and folder are left out, replacing files is asked about, and missing
+    /// folder markers get written before any file moves. Files upload with `options`,
+    /// or the remote's upload defaults when `None`.
+    fn upload_folders(
+        &mut self,
+        dirs: Vec<PathBuf>,
+        files: Vec<PathBuf>,
+        options: Option<UploadDefaults>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote.clone();
+        let marker_remote = remote.clone();
+        let prefix = self.listed_prefix().to_owned();
+        let files = self.upload_items(files);
+        let task = rt::spawn(cx, async move {
+            let mut plan = UploadPlan::default();
+            for dir in dirs {
+                let planned = balti_s3::plan_upload_dir(remote.clone(), &dir, &prefix).await?;
+                plan.markers.extend(planned.markers);
+                plan.uploads.extend(planned.uploads);
+                plan.overwrites.extend(planned.overwrites);
+                plan.collisions.extend(planned.collisions);
+            }
+            AppResult::Ok(plan)
+        });
+
+        self.uploading = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let mut plan = match task.await.flatten() {
+                Ok(plan) => plan,
+                Err(err) => {
+                    let _ = this.update_in(cx, |this, window, cx| {
+                        this.uploading = false;
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.upload_folder_failed")),
+                            cx,
+                        );
+                        cx.notify();
+                    });
+                    return;
+                }
+            };
+
+            if !plan.collisions.is_empty() || !plan.overwrites.is_empty() {
+                let Ok(answer) = this.update_in(cx, |_this, window, cx| {
+                    let (detail, choices) = upload_conflicts(&plan);
+                    conflict::ask(
+                        &t!("browse.upload_conflicts"),
+                        &detail,
+                        &choices,
+                        window,
+                        cx,
+                    )
+                }) else {
+                    return;
+                };
+
+                match answer.await {
+                    ConflictChoice::Proceed => {}
+                    ConflictChoice::Skip => {
+                        let taken = plan.overwrites.iter().collect::<HashSet<_>>();
+                        plan.uploads.retain(|(key, _)| !taken.contains(key));
+                    }
+                    ConflictChoice::Cancel | ConflictChoice::Rename => {
+                        let _ = this.update(cx, |this, cx| {
+                            this.uploading = false;
+                            cx.notify();
+                        });
+                        return;
+                    }
+                }
+            }
+
+            let markers = plan.markers;
+            let _markers = markers.clone();
+            let Ok(task) = this.update(cx, |_this, cx| {
+                rt::spawn(cx, async move {
+                    // parents come before their children
+                    for marker in &_markers {
+                        let folder = marker.strip_suffix(FOLDER_MARKER).unwrap_or(marker);
+                        balti_s3::create_folder(marker_remote.clone(), folder).await?;
+                    }
+                    AppResult::Ok(())
+                })
+            }) else {
+                return;
+            };
+            let created = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                if let Err(err) = created {
+                    this.uploading = false;
+                    window.push_notification(
+                        error_notification(&err).title(t!("browse.upload_folder_failed")),
+                        cx,
+                    );
+                    cx.notify();
+                    return;
+                }
+
+                if !markers.is_empty() {
+                    let entry = this.journal_entry(
+                        Action::CreateFolder,
+                        markers.iter().map(|marker| (marker.as_str(), None)),
+                    );
+                    journal::record(entry, cx);
+                    let prefix = this.listed_prefix();
+                    let added = markers
+                        .iter()
+                        .filter_map(|marker| reconcile::listed_entry(prefix, marker, 0))
+                        .collect();
+                    let change = Expected {
+                        added,
+                        ..Default::default()
+                    };
+                    this.reconcile(change, window, cx);
+                }
+
+                let items = plan
+                    .uploads
+                    .into_iter()
+                    .map(|(key, path)| (Arc::from(key), path))
+                    .chain(files)
+                    .collect::<Vec<_>>();
+                if items.is_empty() {
+                    this.uploading = false;
+                    cx.notify();
+                    return;
+                }
+                this.upload(items, options, window, cx);
+            });
+        })
+        .detach();
+    }
+}
+
+impl create_folder_dialog::CreateFolderDialog for BrowseUi {
+    fn create_folder(
+        &mut self,
+        folder_name: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote.clone();
+        let undo_remote = remote.clone();
+        let key = format!(
+            "{}/{}",
+            self.prefix.trim_matches('/'),
+            folder_name
+                .trim()
+                .trim_matches('/')
+                .replace(".", "")
+                .replace("..", "")
+        );
+
+        let name = key.rsplit('/').next().unwrap_or_default().to_owned();
+        let marker_key = format!("{}/{FOLDER_MARKER}", key.trim_matches('/'));
+        // S3 lets a file and a folder share a name, which confuses other tools
+        let check_remote = remote.clone();
+        let check_key = key.trim_matches('/').to_owned();
+        let check = rt::spawn(cx, async move {
+            balti_s3::object_exists(check_remote, &check_key).await
+        });
+
+        self.creating_folder = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let clash = match check.await.flatten() {
+                Ok(clash) => clash,
+                Err(err) => {
+                    let _ = this.update_in(cx, |this, window, cx| {
+                        this.creating_folder = false;
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.create_folder_failed")),
+                            cx,
+                        );
+                        cx.notify();
+                    });
+                    return;
+                }
+            };
+
+            if clash {
+                let Ok(answer) = this.update_in(cx, |_this, window, cx| {
+                    conflict::ask(
+                        &t!("browse.name_taken", name = name),
+                        &t!("browse.name_taken_detail"),
+                        &[
+                            (ConflictChoice::Rename, t!("browse.rename")),
+                            (ConflictChoice::Proceed, t!("browse.create_anyway")),
+                        ],
+                        window,
+                        cx,
+                    )
+                }) else {
+                    return;
+                };
+
+                let answer = answer.await;
+                if answer != ConflictChoice::Proceed {
+                    let _ = this.update_in(cx, |this, window, cx| {
+                        this.creating_folder = false;
+                        // Rename keeps the dialog open to edit the name
+                        if answer == ConflictChoice::Cancel {
+                            window.close_dialog(cx);
+                        }
+                        cx.notify();
+                    });
+                    return;
+                }
+            }
+
+            let Ok(task) = this.update(cx, |_this, cx| {
+                rt::spawn(cx, async move {
+                    balti_s3::create_folder(remote, key.as_str()).await
+                })
+            }) else {
+                return;
+            };
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.creating_folder = false;
+                let access = Access::learned(&result);
+                RemoteCapabilities::learn_write(&this.s3_remote.remote_name, access, cx);
+                let entry = this.journal_entry(
+                    Action::CreateFolder,
+                    [(marker_key.as_str(), result.as_ref().err())],
+                );
+                journal::record(entry, cx);
+
+                match result {
+                    Ok(_) => {
+                        let added = reconcile::listed_entry(this.listed_prefix(), &marker_key, 0);
+                        let change = Expected {
+                            added: added.into_iter().collect(),
+                            ..Default::default()
+                        };
+                        this.reconcile(change, window, cx);
+                        window.close_dialog(cx);
+
+                        let undo = Undo::CreateFolder {
+                            remote: undo_remote,
+                            marker_key: marker_key.into(),
+                        };
+                        let id = UndoStack::push(undo, cx);
+                        let notification =
+                            Notification::success(t!("browse.folder_created", name = name));
+                        window.push_notification(this.with_undo(notification, id, cx), cx);
+                    }
+                    Err(err) => window.push_notification(
+                        error_notification(&err).title(t!("browse.create_folder_failed")),
+                        cx,
+                    ),
+                };
+
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn is_creating(&self) -> bool {
+        self.creating_folder
+    }
+}
+
+impl upload_options_dialog::UploadOptionsDialog for BrowseUi {
+    fn upload_with(
+        &mut self,
+        options: UploadDefaults,
+        folder: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if folder {
+            self.open_upload_folder_prompt(Some(options), window, cx);
+        } else {
+            self.open_upload_prompt(Some(options), window, cx);
+        }
+    }
+}
+
+impl object_properties_dialog::ObjectPropertiesDialog for BrowseUi {
+    fn properties(&self, key: &str) -> Option<AppResult<ObjectMeta>> {
+        self.properties
+            .as_ref()
+            .filter(|(shown, _)| shown.as_ref() == key)
+            .and_then(|(_, properties)| properties.clone())
+    }
+}
+
+impl rename_dialog::RenameDialog for BrowseUi {
+    fn rename(
+        &mut self,
+        object: S3Object,
+        name: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(to_key) = balti_s3::renamed_key(object.key(), &name) else {
+            return;
+        };
+
+        match object.as_ref() {
+            __S3Object::File { key, size, .. } => {
+                self.rename_file(key.clone(), to_key, *size, window, cx)
+            }
+            __S3Object::Folder(_) => self.rename_folder(object.clone(), to_key, window, cx),
+        }
+    }
+
+    fn is_renaming(&self) -> bool {
+        self.renaming
+    }
+}
+
+impl presign_dialog::PresignDialog for BrowseUi {
+    fn copy_presigned_url(
+        &mut self,
+        key: Arc<str>,
+        expires_in: Duration,
+        force_download: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote.clone();
+        let name = key.rsplit('/').next().unwrap_or_default().to_owned();
+        let disposition = force_download.then(|| balti_s3::attachment_disposition(&name));
+        let task = rt::spawn(cx, async move {
+            balti_s3::presign_get(remote, &key, expires_in, disposition.as_deref()).await
+        });
+
+        cx.spawn_in(window, async move |_this, cx| {
+            let result = task.await.flatten();
+
+            let _ = cx.update(|window, cx| {
+                let notification = match result {
+                    Ok(url) => {
+                        cx.write_to_clipboard(ClipboardItem::new_string(url));
+                        Notification::success(t!(
+                            "presign.copied",
+                            name = name,
+                            expiry = rate::format_duration(expires_in)
+                        ))
+                    }
+                    Err(err) => error_notification(&err).title(t!("presign.failed")),
+                };
+                window.push_notification(notification, cx);
+            });
+        })
+        .detach();
+    }
+}
+
+impl verify_dialog::VerifyDialog for BrowseUi {
+    fn verify(
+        &mut self,
+        prefix: Arc<str>,
+        tier: VerifyTier,
+        full_limit: u64,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.verifying {
+            window.push_notification(Notification::warning(t!("browse.verify_in_progress")), cx);
+            return;
+        }
+
+        let remote = self.s3_remote.clone();
+        let transfer = Transfers::start(remote.remote_name.clone(), TransferKind::Verify, cx);
+        let _prefix = prefix.clone();
+        let task =
+            rt::spawn(cx, async move {
+                let mut objects = Vec::new();
+                let mut token: Option<Arc<str>> = None;
+                loop {
+                    let page = balti_s3::list_objects_recursive(
+                        remote.clone(),
+                        &_prefix,
+                        MAX_PAGE_SIZE,
+                        token.as_deref(),
+                    )
+                    .await?;
+                    objects.extend(page.objects.iter().filter_map(
+                        |object| match object.as_ref() {
+                            __S3Object::File { key, size, .. } => Some((key.clone(), *size)),
+                            __S3Object::Folder(_) => None,
+                        },
+                    ));
+
+                    match page.next_token {
+                        Some(next) => token = Some(next),
+                        None => break,
+                    }
+                }
+                AppResult::Ok(objects)
+            });
+
+        self.verifying = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+            drop(transfer);
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.verifying = false;
+                match result {
+                    Ok(objects) if objects.is_empty() => {
+                        window.push_notification(
+                            Notification::info(t!("browse.verify_empty", prefix = prefix)),
+                            cx,
+                        );
+                    }
+                    Ok(objects) => {
+                        this.verify_batch = Some(VerifyBatch {
+                            tier,
+                            full_limit,
+                            sizes: objects.iter().cloned().collect(),
+                        });
+                        this.run_verify(objects, window, cx);
+                    }
+                    Err(err) => {
+                        this.note_error(&err, cx);
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.verify_failed")),
+                            cx,
+                        );
+                    }
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn is_verifying(&self) -> bool {
+        self.verifying
+    }
+}
+
+impl delete_object_dialog::DeleteObjectDialog for BrowseUi {
+    fn delete_objects(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let objects = self.selected_objects();
+        let threshold = bulk_confirm::threshold(cx);
+        let has_folders = objects
+            .iter()
+            .any(|object| matches!(object.as_ref(), __S3Object::Folder(_)));
+        if threshold == 0 || (!has_folders && objects.len() <= threshold) {
+            self.delete(objects, None, window, cx);
+            return;
+        }
+
+        // folders are counted first, a big one asks before anything is gone
+        let task = rt::spawn(
+            cx,
+            bulk::count_objects(
+                self.s3_remote.clone(),
+                objects.clone(),
+                bulk::count_limit(threshold),
+            ),
+        );
+        self.deleting_objects = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let counted = task.await.flatten();
+            let Ok(confirm) = this.update_in(cx, |this, window, cx| {
+                this.deleting_objects = false;
+                cx.notify();
+                match counted {
+                    Ok(count) if bulk::needs_confirmation(count, threshold) => Some((
+                        count,
+                        bulk_confirm::ask(BulkOperation::Delete, count, true, window, cx),
+                    )),
+                    Ok(count) => {
+                        this.delete(objects.clone(), Some(count.objects), window, cx);
+                        None
+                    }
+                    Err(err) => {
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.count_failed")),
+                            cx,
+                        );
+                        None
+                    }
+                }
+            }) else {
+                return;
+            };
+            let Some((count, answer)) = confirm else {
+                return;
+            };
+
+            let choice = answer.await;
+            let _ = this.update_in(cx, |this, window, cx| match choice {
+                BulkChoice::Proceed => {
+                    let total = count.complete.then_some(count.objects);
+                    this.delete(objects, total, window, cx);
+                }
+                BulkChoice::Narrow => {
+                    window.close_all_dialogs(cx);
+                    select_pattern_dialog::open_dialog(cx.weak_entity(), window, cx);
+                }
+                BulkChoice::Cancel => window.close_dialog(cx),
+            });
+        })
+        .detach();
+    }
+
+    fn is_deleting(&self) -> bool {
+        self.deleting_objects
+    }
+}
+
+impl select_pattern_dialog::SelectPatternDialog for BrowseUi {
+    fn count_matches(&self, pattern: &NamePattern) -> usize {
+        self.rows
+            .iter()
+            .filter(|row| pattern.matches(&row.name))
+            .count()
+    }
+
+    fn select_matching(&mut self, pattern: &NamePattern, replace: bool, _cx: &mut Context<Self>) {
+        if replace {
+            self.selection.clear();
+        }
+        for (object, row) in self.listing.objects.iter().zip(self.rows.iter()) {
+            if pattern.matches(&row.name) {
+                self.selection.set(object.key().clone(), true);
+            }
+        }
+    }
+}
+
+impl batch_report_dialog::BatchReportDialog for BrowseUi {
+    fn retry_failed(
+        &mut self,
+        operation: BatchOperation,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match operation {
+            BatchOperation::Delete => {
+                let objects = keys
+                    .iter()
+                    .filter_map(|key| self.delete_batch.get(key).cloned())
+                    .collect();
+                self.delete(objects, None, window, cx);
+            }
+            BatchOperation::Copy | BatchOperation::Move => {
+                let Some(batch) = self.paste_batch.as_ref() else {
+                    return;
+                };
+                let (from, mode) = (batch.from.clone(), batch.mode);
+                let items = keys
+                    .iter()
+                    .filter_map(|key| batch.items.get(key).cloned())
+                    .collect();
+                self.run_paste(from, mode, items, HashMap::new(), window, cx);
+            }
+            BatchOperation::Verify => {
+                let Some(batch) = self.verify_batch.as_ref() else {
+                    return;
+                };
+                let objects = keys
+                    .iter()
+                    .filter_map(|key| batch.sizes.get(key).map(|size| (key.clone(), *size)))
+                    .collect();
+                self.run_verify(objects, window, cx);
+            }
+            BatchOperation::Upload => {
+                let items = keys
+                    .iter()
+                    .filter_map(|key| {
+                        let path = self.upload_batch.get(key)?;
+                        Some((key.clone(), path.clone()))
+                    })
+                    .collect();
+                self.upload(items, self.upload_options.clone(), window, cx);
+            }
+        }
+    }
+
+    fn resume_paused(
+        &mut self,
+        operation: BatchOperation,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        QuotaGates::resume(&self.s3_remote.remote_name, cx);
+        if let (BatchOperation::Copy | BatchOperation::Move, Some(batch)) =
+            (operation, self.paste_batch.as_ref())
+        {
+            QuotaGates::resume(&batch.from.remote_name, cx);
+        }
+        self.retry_failed(operation, keys, window, cx);
+    }
+
+    fn is_retrying(&self, operation: BatchOperation) -> bool {
+        match operation {
+            BatchOperation::Delete => self.deleting_objects,
+            BatchOperation::Copy | BatchOperation::Move => self.pasting,
+            BatchOperation::Verify => self.verifying,
+            BatchOperation::Upload => self.uploading,
+        }
+    }
 
-        let task = rt::spawn(cx, async move {
-            balti_s3::list_objects(remote, prefix.trim_start_matches('/')).await
-        });
+    fn undo(&mut self, id: Option<u64>, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(undo) = UndoStack::take(id, cx) else {
+            window.push_notification(Notification::info(t!("browse.nothing_to_undo")), cx);
+            return;
+        };
+        // the keys put back, on the remote they went back into
+        let (remote, from_remote, keys) = match &undo {
+            Undo::Move { from, to, objects } => (
+                from.remote_name.clone(),
+                Some(to.remote_name.clone()),
+                objects.iter().map(|object| object.to_key.clone()).collect(),
+            ),
+            Undo::CreateFolder { remote, marker_key } => {
+                (remote.remote_name.clone(), None, vec![marker_key.clone()])
+            }
+        };
+        let task = rt::spawn(cx, undo::revert(undo));
 
         cx.spawn_in(window, async move |this, cx| {
-            let _ = this.update(cx, |this, cx| {
-                this.loading = true;
-                cx.notify();
-            });
-
             let result = task.await.flatten();
 
             let _ = this.update_in(cx, |this, window, cx| {
-                this.loading = false;
+                // conflicts touch nothing
+                let reverted = match &result {
+                    Ok(Reverted::Conflict(_)) => None,
+                    Ok(Reverted::Done(_)) => Some(None),
+                    Err(err) => Some(Some(err)),
+                };
+                if let Some(err) = reverted {
+                    let results = keys.iter().map(|key| (key.as_ref(), err));
+                    let mut entry = JournalEntry::new(&remote, Action::Undo, results, Utc::now());
+                    if let Some(from_remote) = &from_remote {
+                        entry = entry.source_remote(from_remote);
+                    }
+                    journal::record(entry, cx);
+                }
 
                 match result {
-                    Ok(objects) => {
-                        this.checked_objects.clear();
-
-                        let item_sizes = objects.iter().map(|_| size(px(256.), px(40.))).collect();
-                        this.item_sizes = Rc::new(item_sizes);
-                        this.objects = objects
+                    Ok(Reverted::Done(count)) => {
+                        window.push_notification(
+                            Notification::success(t!("browse.undone", count = count)),
+                            cx,
+                        );
+                        this.list_objects(window, cx);
                     }
-                    Err(err) => {
+                    Ok(Reverted::Conflict(conflict)) => {
+                        let message = match conflict {
+                            UndoConflict::Gone(key) => t!("browse.undo_gone", key = key),
+                            UndoConflict::Modified(key) => t!("browse.undo_modified", key = key),
+                            UndoConflict::Taken(key) => t!("browse.undo_taken", key = key),
+                            UndoConflict::NotEmpty(key) => t!("browse.undo_not_empty", key = key),
+                        };
+                        tracing::warn!(op = "undo", %remote, "Undo stopped: {message}");
                         window.push_notification(
-                            Notification::error(&err.message).title("Failed to fetch objects"),
+                            Notification::warning(message).title(t!("browse.undo_conflict")),
                             cx,
                         );
-                        this.error = Some(err);
                     }
+                    Err(err) => window.push_notification(
+                        error_notification(&err).title(t!("browse.undo_failed")),
+                        cx,
+                    ),
                 };
 
                 cx.notify();
@@ -121,72 +3239,245 @@ impl BrowseUi {
         })
         .detach();
     }
+}
 
-    fn new_folder_dialog(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let folder_name_input_state =
-            cx.new(|cx| InputState::new(window, cx).placeholder("CoolFolder"));
+impl BrowseUi {
+    /// Deletes `objects`, `total` counts the keys below them when it's known
+    fn delete(
+        &mut self,
+        objects: Vec<S3Object>,
+        total: Option<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let total = total.or_else(|| {
+            objects
+                .iter()
+                .all(|object| object.kind() != ObjectKind::FolderPrefix)
+                .then_some(objects.len())
+        });
+        let started = Instant::now();
+        let remote = self.s3_remote.clone();
+        let transfer = Transfers::start(remote.remote_name.clone(), TransferKind::Delete, cx);
+        let gate = QuotaGates::gate(&remote.remote_name, cx);
+        self.delete_batch = objects
+            .iter()
+            .map(|obj| (obj.key().clone(), obj.clone()))
+            .collect();
 
-        let prefix = self.prefix.clone();
-        let entity = cx.weak_entity();
+        let progress = BatchProgress::new(objects.iter().map(|obj| obj.key().clone()).collect());
+        let (outcomes_tx, outcomes) = mpsc::unbounded::<Outcome>();
+        let task = rt::spawn_operation(cx, progress.operation(), async move {
+            let gates = [gate];
+            let (folders, files): (Vec<_>, Vec<_>) =
+                objects.into_iter().partition(|obj| obj.is_folder());
 
-        window.open_dialog(cx, move |dialog, _window, _cx| {
-            create_folder_dialog::dialog(
-                dialog,
-                entity.clone(),
-                prefix.clone(),
-                folder_name_input_state.clone(),
-            )
+            // files go a request per batch, folders a listing and requests each
+            let keys = files
+                .iter()
+                .map(|obj| obj.key().clone())
+                .collect::<Vec<_>>();
+            for batch in keys.chunks(MAX_DELETE_BATCH) {
+                let deleted = balti_s3::delete_many(remote.clone(), batch.to_vec());
+                for outcome in quota::gated_batch(&gates, batch, deleted)
+                    .await
+                    .into_items()
+                {
+                    let _ = outcomes_tx.unbounded_send(outcome);
+                }
+            }
+
+            let tasks = folders.into_iter().map(|obj| {
+                let remote = remote.clone();
+                let gates = &gates;
+                async move {
+                    let result = quota::gated(gates, async {
+                        balti_s3::delete_folder(remote, obj.key().as_ref()).await
+                    })
+                    .await;
+                    (obj.key().clone(), result)
+                }
+            });
+
+            futures::stream::iter(tasks)
+                .buffer_unordered(8)
+                .for_each(|outcome| {
+                    let _ = outcomes_tx.unbounded_send(outcome);
+                    futures::future::ready(())
+                })
+                .await
         });
-    }
 
-    fn open_upload_prompt(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        window.push_notification(Notification::info("Coming soon (TM)"), cx);
+        cx.spawn_in(window, async move |this, cx| {
+            let _ = this.update(cx, |this, cx| {
+                this.deleting_objects = true;
+                cx.notify();
+            });
+
+            let (report, progress) = coalesce::follow(
+                progress,
+                outcomes,
+                task,
+                &transfer,
+                |progress| {
+                    batch_report_dialog::progress_notification(BatchOperation::Delete, progress)
+                },
+                cx,
+            )
+            .await;
+            drop(transfer);
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.deleting_objects = false;
+                if let Some(total) = total {
+                    Throughput::note(BulkOperation::Delete, total, started.elapsed(), cx);
+                }
+
+                // refresh even on partial failure, the selection gets
+                // reconciled against whatever still exists
+                this.check_folder_gone = true;
+                let change = Expected {
+                    removed: report
+                        .items
+                        .iter()
+                        .filter(|(_, result)| result.is_ok())
+                        .map(|(key, _)| key.clone())
+                        .collect(),
+                    ..Default::default()
+                };
+                this.reconcile(change, window, cx);
+                window.close_all_dialogs(cx);
+
+                this.note_report(&report, cx);
+                let access = Access::learned_from(report.items.iter().map(|(_, result)| result));
+                RemoteCapabilities::learn_delete(&this.s3_remote.remote_name, access, cx);
+                let entry = JournalEntry::from_report(
+                    &this.s3_remote.remote_name,
+                    Action::Delete,
+                    &report,
+                    Utc::now(),
+                );
+                journal::record(entry.operation(progress.operation()), cx);
+                let pending = PendingReport::delete(
+                    this.s3_remote.remote_name.clone(),
+                    &report,
+                    &this.delete_batch,
+                );
+                PendingReports::note(pending, cx);
+                batch_report_dialog::show(
+                    BatchOperation::Delete,
+                    report,
+                    progress,
+                    None,
+                    cx.weak_entity(),
+                    window,
+                    cx,
+                );
+
+                cx.notify();
+            });
+        })
+        .detach();
     }
-}
 
-impl create_folder_dialog::CreateFolderDialog for BrowseUi {
-    fn create_folder(
+    /// Uploads each local file to its key with `options`, or the remote's upload defaults
+    /// when `None`, large ones in parts. A failed upload only fails its own key.
+    fn upload(
         &mut self,
-        folder_name: SharedString,
+        items: Vec<(Arc<str>, PathBuf)>,
+        options: Option<UploadDefaults>,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
         let remote = self.s3_remote.clone();
-        let key = format!(
-            "{}/{}",
-            self.prefix.trim_matches('/'),
-            folder_name
-                .trim()
-                .trim_matches('/')
-                .replace(".", "")
-                .replace("..", "")
-        );
+        let transfer = Transfers::start(remote.remote_name.clone(), TransferKind::Upload, cx);
+        let gate = QuotaGates::gate(&remote.remote_name, cx);
+        self.upload_batch = items.iter().cloned().collect();
+        self.upload_options = options.clone();
 
-        let task = rt::spawn(cx, async move {
-            balti_s3::create_folder(remote, key.as_str()).await
+        let progress = BatchProgress::new(items.iter().map(|(key, _)| key.clone()).collect());
+        let (outcomes_tx, outcomes) = mpsc::unbounded::<Outcome>();
+        let task = rt::spawn_operation(cx, progress.operation(), async move {
+            let gates = [gate];
+            for batch in items.chunks(UPLOAD_BATCH) {
+                let keys = batch.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+                let uploaded = balti_s3::upload_many(
+                    remote.clone(),
+                    batch.to_vec(),
+                    UPLOAD_CONCURRENCY,
+                    options.as_ref(),
+                    None,
+                );
+                for outcome in quota::gated_batch(&gates, &keys, uploaded)
+                    .await
+                    .into_items()
+                {
+                    let _ = outcomes_tx.unbounded_send(outcome);
+                }
+            }
         });
 
         cx.spawn_in(window, async move |this, cx| {
             let _ = this.update(cx, |this, cx| {
-                this.creating_folder = true;
+                this.uploading = true;
                 cx.notify();
             });
 
-            let result = task.await.flatten();
+            let (report, progress) = coalesce::follow(
+                progress,
+                outcomes,
+                task,
+                &transfer,
+                |progress| {
+                    batch_report_dialog::progress_notification(BatchOperation::Upload, progress)
+                },
+                cx,
+            )
+            .await;
+            drop(transfer);
 
             let _ = this.update_in(cx, |this, window, cx| {
-                this.creating_folder = false;
+                this.uploading = false;
 
-                match result {
-                    Ok(_) => {
-                        this.list_objects(window, cx);
-                        window.close_dialog(cx);
-                    }
-                    Err(err) => window.push_notification(
-                        Notification::error(err.message).title("Error creating folder"),
-                        cx,
-                    ),
+                let prefix = this.listed_prefix();
+                let added = report
+                    .items
+                    .iter()
+                    .filter(|(_, result)| result.is_ok())
+                    .filter_map(|(key, _)| {
+                        let size = this
+                            .upload_batch
+                            .get(key)
+                            .and_then(|path| path.metadata().ok())
+                            .map_or(0, |meta| meta.len() as i64);
+                        reconcile::listed_entry(prefix, key, size)
+                    })
+                    .collect();
+                let change = Expected {
+                    added,
+                    ..Default::default()
                 };
+                this.reconcile(change, window, cx);
+
+                this.note_report(&report, cx);
+                let access = Access::learned_from(report.items.iter().map(|(_, result)| result));
+                RemoteCapabilities::learn_write(&this.s3_remote.remote_name, access, cx);
+                let entry = JournalEntry::from_report(
+                    &this.s3_remote.remote_name,
+                    Action::Upload,
+                    &report,
+                    Utc::now(),
+                );
+                journal::record(entry.operation(progress.operation()), cx);
+                batch_report_dialog::show(
+                    BatchOperation::Upload,
+                    report,
+                    progress,
+                    None,
+                    cx.weak_entity(),
+                    window,
+                    cx,
+                );
 
                 cx.notify();
             });
@@ -194,107 +3485,355 @@ impl create_folder_dialog::CreateFolderDialog for BrowseUi {
         .detach();
     }
 
-    fn is_creating(&self) -> bool {
-        self.creating_folder
+    /// Puts the checked objects on the clipboard, or just `object` when it's outside the selection
+    pub fn copy_to_clipboard(
+        &mut self,
+        mode: ClipboardMode,
+        object: Option<S3Object>,
+        cx: &mut Context<Self>,
+    ) {
+        let objects = match object {
+            Some(object) if !self.selection.contains(object.key()) => vec![object],
+            _ => self.selected_objects(),
+        };
+        if objects.is_empty() {
+            return;
+        }
+
+        ObjectClipboard::set(
+            Some(ClipboardEntry {
+                remote: self.s3_remote.clone(),
+                prefix: self.prefix.as_ref().into(),
+                objects,
+                mode,
+            }),
+            cx,
+        );
     }
-}
 
-impl delete_object_dialog::DeleteObjectDialog for BrowseUi {
-    fn delete_objects(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let remote = self.s3_remote.clone();
+    pub fn paste_here(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.paste(self.prefix.clone(), window, cx);
+    }
 
-        let objects = self
-            .checked_objects
-            .iter()
-            .map(|(_, obj)| obj.clone())
-            .collect::<Vec<_>>();
+    /// Pastes the clipboard into `dest`, asking first when some destinations are taken
+    fn paste(&mut self, dest: SharedString, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(entry) = ObjectClipboard::entry(cx).cloned() else {
+            return;
+        };
+        if self.pasting {
+            window.push_notification(Notification::warning(t!("browse.paste_in_progress")), cx);
+            return;
+        }
+        match entry.check(&self.s3_remote.remote_name, &dest) {
+            PasteCheck::Ok => {}
+            PasteCheck::SameFolder => {
+                window.push_notification(Notification::info(t!("browse.paste_same_folder")), cx);
+                return;
+            }
+            PasteCheck::IntoItself => {
+                window.push_notification(Notification::warning(t!("browse.paste_into_itself")), cx);
+                return;
+            }
+        }
 
+        let to = self.s3_remote.clone();
+        let to_name = to.remote_name.clone();
+        let transfer = Transfers::start(to.remote_name.clone(), TransferKind::Copy, cx);
+        let from = entry.remote.clone();
+        let mode = entry.mode;
         let task = rt::spawn(cx, async move {
-            let tasks = objects.into_iter().map(|obj| {
-                let remote = remote.clone();
-                async move {
-                    match obj.as_ref() {
-                        __S3Object::Folder(key) => {
-                            balti_s3::delete_folder(remote, key.as_ref()).await
-                        }
-                        __S3Object::File { key, .. } => {
-                            balti_s3::delete_file(remote, key.as_ref()).await
-                        }
+            let items = clipboard::plan(entry, dest.as_ref().into()).await?;
+            let taken = clipboard::conflicts(to, items.clone()).await?;
+            AppResult::Ok((items, taken))
+        });
+
+        self.pasting = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let (items, taken) = match task.await.flatten() {
+                Ok(plan) => plan,
+                Err(err) => {
+                    let _ = this.update_in(cx, |this, window, cx| {
+                        this.pasting = false;
+                        window.push_notification(
+                            error_notification(&err).title(t!("browse.paste_failed")),
+                            cx,
+                        );
+                        cx.notify();
+                    });
+                    return;
+                }
+            };
+
+            let count = ObjectCount::exact(items.len());
+            let Ok(confirm) = this.update_in(cx, |_this, window, cx| {
+                bulk_confirm::needs_confirmation(count, cx)
+                    .then(|| bulk_confirm::ask(BulkOperation::from(mode), count, false, window, cx))
+            }) else {
+                return;
+            };
+            if let Some(answer) = confirm
+                && answer.await != BulkChoice::Proceed
+            {
+                let _ = this.update(cx, |this, cx| {
+                    this.pasting = false;
+                    cx.notify();
+                });
+                return;
+            }
+
+            // a single new object pastes right away, anything more is reviewed first
+            let mut plan = PastePlan::new(from.remote_name.clone(), to_name, mode, items, &taken);
+            if plan.items.len() > 1 || !taken.is_empty() {
+                let Ok(review) = this.update_in(cx, |_this, window, cx| {
+                    paste_plan_dialog::review(plan, window, cx)
+                }) else {
+                    return;
+                };
+
+                match review.await {
+                    Some(reviewed) => plan = reviewed,
+                    None => {
+                        let _ = this.update(cx, |this, cx| {
+                            this.pasting = false;
+                            cx.notify();
+                        });
+                        return;
                     }
                 }
+            }
+
+            let (items, skip) = plan.into_batch();
+            let _ = this.update_in(cx, |this, window, cx| {
+                // the sources are moving away, pasting them again can only fail
+                if mode == ClipboardMode::Cut {
+                    ObjectClipboard::set(None, cx);
+                }
+                this.run_paste(from, mode, items, skip, window, cx);
             });
+            drop(transfer);
+        })
+        .detach();
+    }
 
-            let results = futures::stream::iter(tasks)
-                .buffer_unordered(8)
-                .collect::<Vec<_>>()
-                .await;
+    /// Checks `objects` with their listed sizes the way the last verification did. The
+    /// progress toast cancels it, what's left unchecked comes back paused to resume.
+    fn run_verify(
+        &mut self,
+        objects: Vec<(Arc<str>, i64)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(batch) = self.verify_batch.as_ref() else {
+            return;
+        };
+        let (tier, full_limit) = (batch.tier, batch.full_limit);
+        let remote = self.s3_remote.clone();
+        let transfer = Transfers::start(remote.remote_name.clone(), TransferKind::Verify, cx);
+        let cancelled = Arc::new(AtomicBool::new(false));
 
-            let mut err_message = String::from("");
-            for result in results.into_iter() {
-                if let Err(err) = result {
-                    err_message.push_str(&err.message);
-                    err_message.push_str(";\n");
+        let progress = BatchProgress::new(objects.iter().map(|(key, _)| key.clone()).collect());
+        let (outcomes_tx, outcomes) = mpsc::unbounded::<Outcome>();
+        let _cancelled = cancelled.clone();
+        let task = rt::spawn_operation(cx, progress.operation(), async move {
+            let checks = objects.into_iter().map(|(key, size)| {
+                let remote = remote.clone();
+                let cancelled = _cancelled.clone();
+                async move {
+                    let result = if cancelled.load(Ordering::Relaxed) {
+                        Err(AppError::message("Cancelled before it was checked")
+                            .with_kind(ErrorKind::Paused))
+                    } else {
+                        balti_s3::verify_object(remote, &key, size, tier, full_limit).await
+                    };
+                    (key, result)
                 }
-            }
+            });
 
-            if err_message.is_empty() {
-                Ok(())
-            } else {
-                Err(AppError::message(err_message))
-            }
+            futures::stream::iter(checks)
+                .buffer_unordered(VERIFY_CONCURRENCY)
+                .for_each(|outcome| {
+                    let _ = outcomes_tx.unbounded_send(outcome);
+                    futures::future::ready(())
+                })
+                .await
         });
 
+        self.verifying = true;
+        cx.notify();
+
         cx.spawn_in(window, async move |this, cx| {
-            let _ = this.update(cx, |this, cx| {
-                this.deleting_objects = true;
+            let (report, progress) = coalesce::follow(
+                progress,
+                outcomes,
+                task,
+                &transfer,
+                |progress| {
+                    let cancelled = cancelled.clone();
+                    batch_report_dialog::progress_notification(BatchOperation::Verify, progress)
+                        .action(move |_this, _window, cx| {
+                            let cancelled = cancelled.clone();
+                            Button::new("cancel_verify")
+                                .small()
+                                .label(t!("common.cancel"))
+                                .on_click(cx.listener(move |_this, _ev, _window, _cx| {
+                                    cancelled.store(true, Ordering::Relaxed);
+                                }))
+                        })
+                },
+                cx,
+            )
+            .await;
+            drop(transfer);
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.verifying = false;
+                this.note_report(&report, cx);
+                batch_report_dialog::show(
+                    BatchOperation::Verify,
+                    report,
+                    progress,
+                    None,
+                    cx.weak_entity(),
+                    window,
+                    cx,
+                );
                 cx.notify();
             });
+        })
+        .detach();
+    }
 
-            let result = task.await.flatten();
+    fn run_paste(
+        &mut self,
+        from: S3Remote,
+        mode: ClipboardMode,
+        items: Vec<PasteItem>,
+        skip: HashMap<Arc<str>, PasteReason>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let to = self.s3_remote.clone();
+        let transfer = Transfers::start(to.remote_name.clone(), TransferKind::Copy, cx);
+        let gates = vec![
+            QuotaGates::gate(&from.remote_name, cx),
+            QuotaGates::gate(&to.remote_name, cx),
+        ];
+        self.paste_batch = Some(PasteBatch {
+            from: from.clone(),
+            mode,
+            items: items
+                .iter()
+                .map(|item| (item.from_key.clone(), item.clone()))
+                .collect(),
+        });
 
-            let _ = this.update_in(cx, |this, window, cx| {
-                this.deleting_objects = false;
+        let operation = match mode {
+            ClipboardMode::Copy => BatchOperation::Copy,
+            ClipboardMode::Cut => BatchOperation::Move,
+        };
+        let objects = items.len().saturating_sub(skip.len());
+        let started = Instant::now();
+        let progress = BatchProgress::new(items.iter().map(|item| item.from_key.clone()).collect());
+        let (outcomes_tx, outcomes) = mpsc::unbounded::<Outcome>();
+        let (moved_tx, moved_rx) = mpsc::unbounded::<MovedObject>();
+        let (undo_from, undo_to) = (from.clone(), to.clone());
+        let action = match mode {
+            ClipboardMode::Copy => Action::Copy,
+            ClipboardMode::Cut => Action::Move,
+        };
+        let (from_name, dest) = (from.remote_name.clone(), self.prefix.clone());
+        let task = rt::spawn_operation(
+            cx,
+            progress.operation(),
+            clipboard::paste(from, to, items, mode, skip, gates, outcomes_tx, moved_tx),
+        );
 
-                match result {
-                    Ok(_) => {
-                        this.list_objects(window, cx);
+        self.pasting = true;
+        cx.notify();
 
-                        window.close_all_dialogs(cx);
-                        window.push_notification(
-                            Notification::success("Objects deleted")
-                                .icon(Icon::new(IconName::CircleCheck).text_color(green())),
-                            cx,
-                        );
-                    }
-                    Err(err) => window.push_notification(
-                        Notification::error(err.message).title("Failed to delete object(s)"),
-                        cx,
-                    ),
+        cx.spawn_in(window, async move |this, cx| {
+            let (report, progress) = coalesce::follow(
+                progress,
+                outcomes,
+                task,
+                &transfer,
+                |progress| batch_report_dialog::progress_notification(operation, progress),
+                cx,
+            )
+            .await;
+            drop(transfer);
+            let moved = moved_rx.collect::<Vec<_>>().await;
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.pasting = false;
+                Throughput::note(BulkOperation::from(mode), objects, started.elapsed(), cx);
+                let added = this.paste_batch.as_ref().map(|batch| {
+                    report
+                        .items
+                        .iter()
+                        .filter(|(_, result)| result.is_ok())
+                        .filter_map(|(key, _)| batch.items.get(key))
+                        .filter_map(|item| {
+                            reconcile::listed_entry(this.listed_prefix(), &item.to_key, item.size)
+                        })
+                        .collect()
+                });
+                let change = Expected {
+                    added: added.unwrap_or_default(),
+                    ..Default::default()
                 };
+                this.reconcile(change, window, cx);
+
+                let undo = (!moved.is_empty()).then(|| {
+                    let undo = Undo::Move {
+                        from: undo_from,
+                        to: undo_to,
+                        objects: moved,
+                    };
+                    UndoStack::push(undo, cx)
+                });
+
+                this.note_report(&report, cx);
+                // keys are the sources, on the remote they were pasted from
+                let entry = JournalEntry::from_report(
+                    &this.s3_remote.remote_name,
+                    action,
+                    &report,
+                    Utc::now(),
+                );
+                let entry = entry.source_remote(&from_name).to(&dest);
+                journal::record(entry.operation(progress.operation()), cx);
+                if let Some(batch) = this.paste_batch.as_ref() {
+                    let pending =
+                        PendingReport::paste(this.s3_remote.remote_name.clone(), &report, batch);
+                    PendingReports::note(pending, cx);
+                }
+                batch_report_dialog::show(
+                    operation,
+                    report,
+                    progress,
+                    undo,
+                    cx.weak_entity(),
+                    window,
+                    cx,
+                );
 
                 cx.notify();
             });
         })
         .detach();
     }
-
-    fn is_deleting(&self) -> bool {
-        self.deleting_objects
-    }
 }
 
 impl BrowsePrefix for BrowseUi {
     fn name(&self) -> SharedString {
-        if self.prefix == "/" {
+        if self.prefix == "/" || self.prefix.as_ref() == self.s3_remote.root().as_ref() {
             SharedString::from(self.s3_remote.bucket_name.clone())
         } else {
-            self.prefix
-                .trim_matches('/')
-                .split('/')
-                .last()
-                .map(|s| s.to_owned())
-                .unwrap_or_default()
-                .into()
+            let path = self.prefix.strip_suffix('/').unwrap_or(&self.prefix);
+            display_name(path.rsplit('/').next().unwrap_or_default()).into()
         }
     }
 
@@ -304,43 +3843,200 @@ impl BrowsePrefix for BrowseUi {
 }
 
 impl Render for BrowseUi {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let row_height = Settings::get(cx).row_height(ROW_HEIGHT);
+        // the view this one replaced was released since it got created
+        if std::mem::take(&mut self.restore_scroll)
+            && let Some(listing) =
+                ListingMemory::recall(&self.s3_remote.remote_name, &self.prefix, cx)
+            && !self.listing.objects.is_empty()
+        {
+            self.objects_scroll_handle.set_offset(listing.scroll_offset);
+        }
+        let viewport_rows = (window.viewport_size().height / row_height).ceil() as usize;
+        let skeleton_rows = listing_memory::skeleton_rows(self.remembered_count, viewport_rows);
+
+        let mut key_context = KeyContext::new_with_defaults();
+        key_context.add(BROWSE_CONTEXT);
+        if !self.selection.is_empty() {
+            key_context.add("selection");
+        }
+
         div()
             .id(self.prefix.clone())
+            .key_context(key_context)
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(|this, _: &DeleteSelection, window, cx| {
+                this.confirm_delete(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &ClearSelection, _window, cx| {
+                this.selection.clear();
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, _: &DownloadSelection, window, cx| {
+                this.download_selection(window, cx);
+            }))
             .size_full()
             .mt_11()
             .overflow_scroll()
             .child(deferred(self.render_browse_status(cx)).with_priority(999))
-            .when_some(self.error.clone(), |this, error| {
-                this.child(self.render_error(error.message, cx))
+            .when(self.is_degraded(cx), |this| {
+                this.child(self.render_degraded(cx))
             })
-            .when_none(&self.error.clone(), |this| {
-                this.when_else(
-                    self.loading,
-                    |this| {
-                        this.child(
-                            div()
-                                .p_2()
-                                .pb_10()
-                                .flex()
-                                .flex_col()
-                                .size_full()
-                                .gap_0p5()
-                                .children(
-                                    (0..7)
-                                        .map(|_| Skeleton::new().w_full().h(px(40.)).rounded_md()),
-                                ),
-                        )
-                    },
-                    |this| this.child(self.render_object_list(cx)),
-                )
-                .vertical_scrollbar(&self.objects_scroll_handle)
-                .horizontal_scrollbar(&self.objects_scroll_handle)
+            .when_some(self.search.as_ref(), |this, search| {
+                this.child(self.render_search(search, cx))
+            })
+            .when(self.search.is_none(), |this| {
+                this.when_some(self.listing.error.clone(), |this, error| {
+                    this.child(self.render_error(error.message, cx))
+                })
+                .when_none(&self.listing.error.clone(), |this| {
+                    // auto refresh re-lists in place, keep showing the current objects meanwhile
+                    this.when_else(
+                        self.listing.loading && self.listing.objects.is_empty(),
+                        |this| {
+                            this.child(
+                                div()
+                                    .p_2()
+                                    .pb_10()
+                                    .flex()
+                                    .flex_col()
+                                    .size_full()
+                                    .gap_0p5()
+                                    .children(
+                                        (0..skeleton_rows)
+                                            .map(|_| loading::skeleton(row_height, cx)),
+                                    ),
+                            )
+                        },
+                        |this| {
+                            let stale = self.listing.stale && self.listing.loading;
+                            this.when(stale, |this| this.child(self.render_refreshing(cx)))
+                                .child(
+                                    div()
+                                        .size_full()
+                                        .when(stale, |this| this.opacity(0.5))
+                                        .child(self.render_object_list(cx)),
+                                )
+                        },
+                    )
+                    .vertical_scrollbar(&self.objects_scroll_handle)
+                    .horizontal_scrollbar(&self.objects_scroll_handle)
+                })
             })
     }
 }
 
 impl BrowseUi {
+    /// Shown over the greyed out listing the prefix had last, until the fresh one lands
+    fn render_refreshing(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .absolute()
+            .top_2()
+            .right_4()
+            .gap_1()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .bg(cx.theme().background)
+            .border_1()
+            .border_color(cx.theme().sidebar_border)
+            .text_xs()
+            .text_color(cx.theme().muted_foreground)
+            .child(loading::loading_indicator(
+                "browse-refreshing",
+                Icon::new(IconName::LoaderCircle).xsmall(),
+                cx,
+            ))
+            .child(t!("browse.refreshing"))
+    }
+
+    /// Hits of the search with their path below the prefix, each opens the folder it's in
+    fn render_search(&self, search: &Search, cx: &mut Context<Self>) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+        let status = if search.task.is_some() {
+            t!("browse.searching", count = search.hits.len())
+        } else if search.hits.len() >= balti_s3::MAX_SEARCH_MATCHES {
+            t!("browse.search_capped", count = search.hits.len())
+        } else if search.complete && search.hits.is_empty() {
+            t!("browse.no_search_hits")
+        } else if search.complete {
+            t!("browse.search_hits", count = search.hits.len())
+        } else {
+            t!("browse.search_stopped", count = search.hits.len())
+        };
+
+        div()
+            .p_2()
+            .pb_10()
+            .flex()
+            .flex_col()
+            .w_full()
+            .gap_0p5()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .px_2()
+                    .py_1()
+                    .text_xs()
+                    .text_color(muted)
+                    .when(search.task.is_some(), |this| {
+                        this.child(loading::loading_indicator(
+                            "browse-searching",
+                            Icon::new(IconName::LoaderCircle).xsmall(),
+                            cx,
+                        ))
+                    })
+                    .child(status),
+            )
+            .when_some(search.error.as_ref(), |this, err| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .text_sm()
+                        .text_color(cx.theme().danger)
+                        .child(t!("browse.search_failed", error = err.message)),
+                )
+            })
+            .children(search.hits.iter().enumerate().map(|(i, hit)| {
+                let key = hit.key().clone();
+                let path = key.trim_key_prefix(self.prefix.as_str());
+                let size = match hit.as_ref() {
+                    __S3Object::File { size, .. } => Some(util::human_readable_size(*size)),
+                    __S3Object::Folder(_) => None,
+                };
+
+                h_flex()
+                    .id(("search-hit", i))
+                    .w_full()
+                    .justify_between()
+                    .gap_4()
+                    .p_2()
+                    .rounded_md()
+                    .text_sm()
+                    .border_b_1()
+                    .border_color(cx.theme().sidebar_border)
+                    .hover(|this| this.bg(cx.theme().secondary_hover.opacity(0.4)))
+                    .child(
+                        h_flex()
+                            .min_w_0()
+                            .gap_4()
+                            .child(self.render_object_icon(hit, false, cx))
+                            .child(div().min_w_0().truncate().child(path.to_string())),
+                    )
+                    .when_some(size, |this, size| {
+                        this.child(div().flex_shrink_0().text_color(muted).child(size))
+                    })
+                    .on_click(cx.listener(move |this, _ev, _window, cx| {
+                        let folder = containing_folder(&key);
+                        this.browse_nav.update(cx, |_nav, cx| {
+                            cx.emit(BrowseRevealEvent(folder, key.clone()));
+                        });
+                    }))
+            }))
+    }
+
     fn render_error(&mut self, message: String, cx: &mut Context<Self>) -> impl IntoElement {
         div().p_2().child(
             div()
@@ -362,12 +4058,58 @@ impl BrowseUi {
                             .text_color(cx.theme().danger_foreground),
                     ),
                 )
-                .child(div().text_lg().child("Failed to fetch objects"))
+                .child(div().text_lg().child(t!("browse.fetch_failed")))
                 .child(div().child(message)),
         )
     }
 
+    /// Banner of a remote whose endpoint stopped answering. Retries wait out a cooldown,
+    /// the remote is probed in the background meanwhile.
+    fn render_degraded(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let remote_name = self.s3_remote.remote_name.clone();
+        let retry_in = RemoteHealth::retry_in(&remote_name, cx);
+
+        h_flex()
+            .m_2()
+            .p_2()
+            .gap_2()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().warning)
+            .bg(cx.theme().warning.opacity(0.1))
+            .text_sm()
+            .child(
+                Icon::new(IconName::TriangleAlert)
+                    .small()
+                    .text_color(cx.theme().warning),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .child(t!("browse.degraded", remote = remote_name)),
+            )
+            .child(
+                Button::new("retry_degraded")
+                    .small()
+                    .label(match retry_in {
+                        Some(left) => {
+                            t!("browse.retry_in", seconds = left.as_millis().div_ceil(1000))
+                        }
+                        None => t!("browse.retry"),
+                    })
+                    .disabled(retry_in.is_some())
+                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                        RemoteHealth::note_retry(&remote_name, cx);
+                        this.list_objects(window, cx);
+                    })),
+            )
+    }
+
     fn render_browse_status(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let allowed = RemoteCapabilities::get(&self.s3_remote.remote_name, cx);
+        let no_write = capabilities::missing(allowed.write, "s3:PutObject");
+        let no_delete = capabilities::missing(allowed.delete, "s3:DeleteObject");
+
         div()
             .absolute()
             .bottom_0()
@@ -378,17 +4120,102 @@ impl BrowseUi {
             .border_t_1()
             .border_color(cx.theme().sidebar_border)
             .px_2()
-            .py_1p5()
+            .map(|this| {
+                if Settings::get(cx).is_compact() {
+                    this.py_0p5()
+                } else {
+                    this.py_1p5()
+                }
+            })
             .w_full()
             .justify_between()
+            // groups that don't fit a narrow window go on a line of their own rather
+            // than over each other
+            .flex_wrap()
+            .gap_x_4()
+            .gap_y_1()
             .text_sm()
             .map(|this| {
-                if self.checked_objects.is_empty() {
-                    this.child(div().map(|this| {
-                        if self.loading {
+                if self.selection.is_empty() {
+                    this.child(div().min_w_0().map(|this| {
+                        if self.zip_job.is_some() || !self.downloads.is_empty() {
+                            this.flex()
+                                .items_center()
+                                .gap_4()
+                                .when_some(self.zip_job.as_ref(), |this, job| {
+                                    this.child(self.render_zip_job(job, cx))
+                                })
+                                .children(
+                                    self.downloads
+                                        .iter()
+                                        .map(|download| self.render_download(download, cx)),
+                                )
+                        } else if self.listing.loading && self.listing.objects.is_empty() {
                             this.child("...")
                         } else {
-                            this.child(format!("Total: {} item(s)", self.objects.len()))
+                            this.flex()
+                                .items_center()
+                                .gap_3()
+                                .child(if self.listing.next_token.is_some() {
+                                    t!("browse.total_more", count = self.listing.objects.len())
+                                } else {
+                                    t!("browse.total", count = self.listing.objects.len())
+                                })
+                                .when_some(self.last_refreshed_label(cx), |this, label| {
+                                    this.child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(label),
+                                    )
+                                })
+                                .when(self.listing.next_token.is_some(), |this| {
+                                    this.child(
+                                        Button::new("load_more")
+                                            .label(t!("browse.load_more"))
+                                            .small()
+                                            .ghost()
+                                            .loading_state(self.listing.loading_more, cx)
+                                            .disabled(self.listing.loading_more)
+                                            .on_click(cx.listener(|this, _ev, window, cx| {
+                                                this.load_more(window, cx);
+                                            })),
+                                    )
+                                })
+                                .when_some(
+                                    ObjectClipboard::entry(cx).map(|entry| entry.hint()),
+                                    |this, hint| {
+                                        this.child(
+                                            h_flex()
+                                                .gap_1()
+                                                .child(
+                                                    div()
+                                                        .text_xs()
+                                                        .text_color(cx.theme().muted_foreground)
+                                                        .child(hint),
+                                                )
+                                                .child(
+                                                    Button::new("paste")
+                                                        .label(t!("browse.paste"))
+                                                        .small()
+                                                        .ghost()
+                                                        .loading_state(self.pasting, cx)
+                                                        .disabled(
+                                                            self.pasting || no_write.is_some(),
+                                                        )
+                                                        .when_some(
+                                                            no_write.clone(),
+                                                            |this, reason| this.tooltip(reason),
+                                                        )
+                                                        .on_click(cx.listener(
+                                                            |this, _ev, window, cx| {
+                                                                this.paste_here(window, cx);
+                                                            },
+                                                        )),
+                                                ),
+                                        )
+                                    },
+                                )
                         }
                     }))
                     .child(
@@ -396,18 +4223,84 @@ impl BrowseUi {
                             .flex()
                             .items_center()
                             .gap_3()
+                            .child(
+                                Button::new("thumbnails")
+                                    .icon(Icon::empty().path("icons/image.svg"))
+                                    .small()
+                                    .ghost()
+                                    .selected(self.view_prefs.read(cx).show_thumbnails)
+                                    .tooltip(t!("remote.show_thumbnails"))
+                                    .on_click(cx.listener(|this, _ev, _window, cx| {
+                                        this.view_prefs.update(cx, |prefs, cx| {
+                                            prefs.show_thumbnails = !prefs.show_thumbnails;
+                                            cx.notify();
+                                        });
+                                    })),
+                            )
+                            .child(
+                                Button::new("folder_counts")
+                                    .icon(Icon::empty().path("icons/chart-pie.svg"))
+                                    .small()
+                                    .ghost()
+                                    .selected(self.view_prefs.read(cx).show_folder_counts)
+                                    .tooltip(t!("remote.show_folder_counts"))
+                                    .on_click(cx.listener(|this, _ev, _window, cx| {
+                                        this.view_prefs.update(cx, |prefs, cx| {
+                                            prefs.show_folder_counts = !prefs.show_folder_counts;
+                                            cx.notify();
+                                        });
+                                    })),
+                            )
+                            .child(
+                                Button::new("folders_first")
+                                    .icon(Icon::empty().path("icons/sort-ascending.svg"))
+                                    .small()
+                                    .ghost()
+                                    .selected(
+                                        self.view_prefs.read(cx).order == ListingOrder::Natural,
+                                    )
+                                    .tooltip(t!("remote.folders_first"))
+                                    .on_click(cx.listener(|this, _ev, _window, cx| {
+                                        this.view_prefs.update(cx, |prefs, cx| {
+                                            prefs.order = match prefs.order {
+                                                ListingOrder::Natural => ListingOrder::Raw,
+                                                ListingOrder::Raw => ListingOrder::Natural,
+                                            };
+                                            cx.notify();
+                                        });
+                                    })),
+                            )
+                            .child(
+                                Button::new("select_pattern")
+                                    .icon(Icon::empty().path("icons/search.svg"))
+                                    .small()
+                                    .ghost()
+                                    .disabled(self.listing.objects.is_empty())
+                                    .tooltip(t!("browse.select_by_pattern"))
+                                    .on_click(cx.listener(|_this, _ev, window, cx| {
+                                        select_pattern_dialog::open_dialog(
+                                            cx.weak_entity(),
+                                            window,
+                                            cx,
+                                        );
+                                    })),
+                            )
                             .child(
                                 Button::new("new_folder")
                                     .icon(Icon::empty().path("icons/folder-plus.svg"))
-                                    .label("Folder")
+                                    .label(t!("browse.folder"))
                                     .small()
                                     .border_color(cx.theme().sidebar_border)
                                     .outline()
                                     .disabled(
-                                        self.loading
+                                        self.listing.loading
                                             || self.creating_folder
-                                            || self.deleting_objects,
+                                            || self.deleting_objects
+                                            || no_write.is_some(),
                                     )
+                                    .when_some(no_write.clone(), |this, reason| {
+                                        this.tooltip(reason)
+                                    })
                                     .on_click(cx.listener(move |this, _ev, window, cx| {
                                         this.new_folder_dialog(window, cx);
                                     })),
@@ -415,12 +4308,59 @@ impl BrowseUi {
                             .child(
                                 Button::new("upload")
                                     .icon(Icon::empty().path("icons/upload.svg"))
-                                    .label("Upload")
+                                    .label(t!("browse.upload"))
                                     .small()
                                     .primary()
-                                    .disabled(self.loading || self.deleting_objects)
+                                    .disabled(
+                                        self.listing.loading
+                                            || self.deleting_objects
+                                            || self.uploading
+                                            || no_write.is_some(),
+                                    )
+                                    .when_some(no_write.clone(), |this, reason| {
+                                        this.tooltip(reason)
+                                    })
+                                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                                        this.open_upload_prompt(None, window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("upload_folder")
+                                    .icon(Icon::empty().path("icons/upload.svg"))
+                                    .label(t!("browse.upload_folder"))
+                                    .small()
+                                    .disabled(
+                                        self.listing.loading
+                                            || self.deleting_objects
+                                            || self.uploading
+                                            || no_write.is_some(),
+                                    )
+                                    .when_some(no_write.clone(), |this, reason| {
+                                        this.tooltip(reason)
+                                    })
+                                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                                        this.open_upload_folder_prompt(None, window, cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("upload_options")
+                                    .icon(Icon::empty().path("icons/settings-2.svg"))
+                                    .small()
+                                    .ghost()
+                                    .tooltip(t!("browse.upload_with_options"))
+                                    .disabled(
+                                        self.listing.loading
+                                            || self.deleting_objects
+                                            || self.uploading
+                                            || no_write.is_some(),
+                                    )
                                     .on_click(cx.listener(move |this, _ev, window, cx| {
-                                        this.open_upload_prompt(window, cx);
+                                        upload_options_dialog::open_dialog(
+                                            cx.weak_entity(),
+                                            &this.s3_remote.config.upload_defaults,
+                                            window,
+                                            cx,
+                                        );
                                     })),
                             ),
                     )
@@ -428,34 +4368,48 @@ impl BrowseUi {
                     this.bg(cx.theme().primary)
                         .text_color(cx.theme().primary_foreground)
                         .child(
-                            Button::new("select-all")
-                                .small()
-                                .outline()
-                                .icon(IconName::Asterisk)
-                                .label("Select all")
-                                .on_click(cx.listener(|this, _ev, _window, cx| {
-                                    this.objects.iter().for_each(|remote| {
-                                        this.checked_objects
-                                            .insert(remote.key().clone(), remote.clone());
-                                    });
-                                    cx.notify();
-                                })),
+                            h_flex()
+                                .gap_2()
+                                .child(
+                                    Button::new("select-all")
+                                        .small()
+                                        .outline()
+                                        .icon(IconName::Asterisk)
+                                        .label(t!("browse.select_all"))
+                                        .on_click(cx.listener(|this, _ev, _window, cx| {
+                                            this.selection.select_all(&this.prefix);
+                                            cx.notify();
+                                        })),
+                                )
+                                .child(
+                                    Button::new("select-pattern")
+                                        .small()
+                                        .outline()
+                                        .label(t!("browse.select_by_pattern"))
+                                        .on_click(cx.listener(|_this, _ev, window, cx| {
+                                            select_pattern_dialog::open_dialog(
+                                                cx.weak_entity(),
+                                                window,
+                                                cx,
+                                            );
+                                        })),
+                                ),
                         )
                         .child(
                             h_flex()
                                 .gap_4()
-                                .child(div().text_sm().font_medium().child(format!(
-                                    "{} item(s) selected",
-                                    self.checked_objects.len()
+                                .child(div().text_sm().font_medium().child(t!(
+                                    "browse.items_selected",
+                                    count = self.selected_count()
                                 )))
                                 .child(
                                     Button::new("clear")
                                         .small()
                                         .outline()
                                         .icon(IconName::Close)
-                                        .label("Clear all")
+                                        .label(t!("browse.clear_all"))
                                         .on_click(cx.listener(|this, _ev, _window, cx| {
-                                            this.checked_objects.clear();
+                                            this.selection.clear();
                                             cx.notify();
                                         })),
                                 ),
@@ -465,39 +4419,269 @@ impl BrowseUi {
                                 .small()
                                 .danger()
                                 .icon(IconName::Delete)
-                                .label("Delete items")
+                                .label(t!("browse.delete_items"))
+                                .disabled(no_delete.is_some())
+                                .when_some(no_delete, |this, reason| this.tooltip(reason))
                                 .on_click(cx.listener(|this, _ev, window, cx| {
-                                    let count = this.checked_objects.len();
-                                    let entity = cx.weak_entity();
-
-                                    window.open_dialog(cx, move |dialog, _window, _cx| {
-                                        delete_object_dialog::dialog(dialog, count, entity.clone())
-                                    });
+                                    this.confirm_delete(window, cx);
                                 })),
                         )
                 }
             })
     }
 
+    /// Like "42.3 MB/s · 2m 10s left", or "stalled" once progress stops. Without the
+    /// `remaining` bytes only the speed shows.
+    fn transfer_rate_label(rate: &TransferRate, remaining: Option<u64>) -> String {
+        if rate.is_stalled(Instant::now()) {
+            return t!("browse.stalled");
+        }
+
+        match (
+            rate.bytes_per_sec(),
+            remaining.and_then(|remaining| rate.eta(remaining)),
+        ) {
+            (Some(speed), Some(eta)) => t!(
+                "browse.rate_eta",
+                speed = util::human_readable_size(speed as i64),
+                eta = rate::format_duration(eta)
+            ),
+            (Some(speed), None) => t!(
+                "browse.rate",
+                speed = util::human_readable_size(speed as i64)
+            ),
+            _ => t!("browse.starting"),
+        }
+    }
+
+    fn render_zip_job(&self, job: &ZipJob, cx: &mut Context<Self>) -> impl IntoElement {
+        let status = match job.phase {
+            ZipPhase::Listing => t!("browse.zip_listing", name = job.name),
+            ZipPhase::Zipping(progress) => t!(
+                "browse.zip_progress",
+                name = job.name,
+                done = progress.files_done,
+                count = progress.files_total,
+                bytes_done = util::human_readable_size(progress.bytes_done as i64),
+                bytes_total = util::human_readable_size(progress.bytes_total as i64),
+                rate = Self::transfer_rate_label(
+                    &job.rate,
+                    Some(progress.bytes_total.saturating_sub(progress.bytes_done))
+                ),
+            ),
+        };
+
+        h_flex()
+            .gap_2()
+            .child(loading::loading_indicator(
+                "zip-loading",
+                Icon::new(IconName::LoaderCircle).small(),
+                cx,
+            ))
+            .child(status)
+            .child(
+                Button::new("cancel_zip")
+                    .icon(IconName::Close)
+                    .xsmall()
+                    .ghost()
+                    .tooltip(t!("browse.cancel_download"))
+                    .on_click(cx.listener(|this, _ev, _window, cx| {
+                        // dropping the task aborts the download, the partial archive goes
+                        // along with its checkpoint
+                        if let Some(checkpoint) = this.zip_job.take().and_then(|job| job.checkpoint)
+                        {
+                            checkpoint::discard(&config::checkpoints_dir(), &checkpoint);
+                        }
+                        cx.notify();
+                    })),
+            )
+    }
+
+    /// Name, a bar and the bytes so far, only the bytes when the total isn't known
+    fn render_download(&self, download: &FileDownload, cx: &Context<Self>) -> impl IntoElement {
+        let id = download.id;
+        let bytes_done = util::human_readable_size(download.bytes_done as i64);
+        let status = match download.total {
+            Some(total) => t!(
+                "browse.download_progress",
+                name = download.name,
+                bytes_done = bytes_done,
+                bytes_total = util::human_readable_size(total as i64),
+                rate = Self::transfer_rate_label(
+                    &download.rate,
+                    Some(total.saturating_sub(download.bytes_done))
+                ),
+            ),
+            None => t!(
+                "browse.download_progress_unknown",
+                name = download.name,
+                bytes_done = bytes_done,
+                rate = Self::transfer_rate_label(&download.rate, None),
+            ),
+        };
+        let fraction = download
+            .total
+            .filter(|total| *total > 0)
+            .map(|total| (download.bytes_done as f32 / total as f32).min(1.));
+
+        h_flex()
+            .gap_2()
+            .when_some(fraction, |this, fraction| {
+                this.child(
+                    div()
+                        .w(px(80.))
+                        .h_1p5()
+                        .flex_shrink_0()
+                        .rounded_full()
+                        .bg(cx.theme().muted)
+                        .child(
+                            div()
+                                .h_full()
+                                .w(relative(fraction))
+                                .rounded_full()
+                                .bg(cx.theme().primary),
+                        ),
+                )
+            })
+            .when(fraction.is_none(), |this| {
+                this.child(loading::loading_indicator(
+                    ("download-loading", id),
+                    Icon::new(IconName::LoaderCircle).small(),
+                    cx,
+                ))
+            })
+            .child(status)
+            .child(
+                Button::new(("cancel-download", id))
+                    .icon(IconName::Close)
+                    .xsmall()
+                    .ghost()
+                    .tooltip(t!("browse.cancel_download"))
+                    .on_click(cx.listener(move |this, _ev, _window, cx| {
+                        this.cancel_download(id, cx);
+                    })),
+            )
+    }
+
     fn render_object_list(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
         div()
             .id(self.prefix.clone())
+            .on_drop(cx.listener(|this, _: &DraggedObject, _window, cx| {
+                this.cancel_drag_out(cx);
+            }))
+            .drag_over::<ExternalPaths>(|style, _, _, cx| style.bg(cx.theme().accent))
+            .on_drop(cx.listener(|this, paths: &ExternalPaths, window, cx| {
+                this.drop_files(paths, window, cx);
+            }))
             .p_2()
             .pb_10()
             .flex()
             .flex_col()
             .size_full()
             .gap_0p5()
+            .when(
+                self.listing.objects.is_empty()
+                    && self.listing.has_marker
+                    && !self.listing.loading_more,
+                |this| {
+                    this.child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .p_2()
+                            .rounded_md()
+                            .text_sm()
+                            .bg(cx.theme().muted)
+                            .text_color(cx.theme().muted_foreground)
+                            .child(Icon::new(IconName::Info).small())
+                            .child(t!("browse.placeholder_only")),
+                    )
+                },
+            )
+            .map(|this| match self.view_prefs.read(cx).view_mode {
+                ViewMode::List => this.child(self.render_list(cx)),
+                ViewMode::Grid => this.child(self.render_grid(cx)),
+            })
+            .when(self.listing.loading_more, |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(t!("browse.loading_more")),
+                )
+            })
+    }
+
+    fn render_list(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_virtual_list(
+            cx.entity().clone(),
+            "browse-list",
+            self.item_sizes.clone(),
+            |this, range, _window, cx| {
+                this.request_folder_counts(range.clone(), cx);
+                this.request_thumbnails(range.clone(), cx);
+
+                range
+                    .map(
+                        |i| match this.listing.objects.get(i).zip(this.rows.get(i)) {
+                            Some((object, row)) => this.render_object_item(i, object, row, cx),
+                            None => div().id("i").child("whoops ??").into_any_element(),
+                        },
+                    )
+                    .collect()
+            },
+        )
+        .w_full()
+        .track_scroll(&self.objects_scroll_handle)
+    }
+
+    fn render_grid(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let entity = cx.entity();
+        let columns = self.grid_columns.max(1);
+
+        div()
+            .relative()
+            .size_full()
+            .child(
+                // tiles per row follow the width the grid gets
+                canvas(
+                    move |bounds, _window, cx| {
+                        entity.update(cx, |this, cx| this.set_grid_width(bounds.size.width, cx));
+                    },
+                    |_, _, _, _| {},
+                )
+                .absolute()
+                .size_full(),
+            )
             .child(
                 v_virtual_list(
                     cx.entity().clone(),
-                    "browse-list",
-                    self.item_sizes.clone(),
-                    |this, range, _window, cx| {
+                    "browse-grid",
+                    self.grid_row_sizes.clone(),
+                    move |this, range, _window, cx| {
+                        let objects = range.start * columns..range.end * columns;
+                        this.request_folder_counts(objects.clone(), cx);
+                        this.request_thumbnails(objects, cx);
+
                         range
-                            .map(|i| match this.objects.get(i) {
-                                Some(object) => this.render_object_item(i, object.clone(), cx),
-                                None => div().id("i").child("whoops ??"),
+                            .map(|row| {
+                                let start = row * columns;
+                                let end = (start + columns).min(this.listing.objects.len());
+
+                                h_flex()
+                                    .gap(Settings::get(cx).scaled(TILE_GAP))
+                                    .children((start..end).map(|i| {
+                                        this.render_object_tile(
+                                            i,
+                                            &this.listing.objects[i],
+                                            &this.rows[i],
+                                            cx,
+                                        )
+                                    }))
+                                    .into_any_element()
                             })
                             .collect()
                     },
@@ -507,74 +4691,162 @@ impl BrowseUi {
             )
     }
 
-    fn render_object_item(
+    fn render_object_tile(
         &self,
         i: usize,
-        object: S3Object,
+        object: &S3Object,
+        row: &RowModel,
         cx: &mut Context<Self>,
-    ) -> Stateful<Div> {
-        let _object = object.clone();
+    ) -> AnyElement {
+        let settings = Settings::get(cx);
+        let checked = self.selection.contains(object.key());
+        let detail = match object.as_ref() {
+            __S3Object::Folder(key) => self.folder_count_label(key, cx),
+            __S3Object::File { .. } => row.size.clone(),
+        };
 
-        div()
-            .id(SharedString::new(i.to_string()))
+        let tile = div()
+            .id(row.id.clone())
+            .relative()
+            .flex()
+            .flex_col()
+            .flex_shrink_0()
+            .items_center()
+            .justify_center()
+            .gap_1()
+            .w(settings.scaled(TILE_WIDTH))
+            .h(settings.scaled(TILE_HEIGHT))
+            .p_2()
+            .rounded_md()
+            .border_1()
+            .border_color(if checked {
+                cx.theme().primary
+            } else {
+                cx.theme().sidebar_border
+            })
+            .group(row.id.clone())
+            .hover(|this| this.bg(cx.theme().secondary_hover.opacity(0.4)))
+            .when(self.is_flashed(object), |this| {
+                this.bg(cx.theme().primary.opacity(0.15))
+            })
+            .child(
+                div()
+                    .absolute()
+                    .top_1()
+                    .left_1()
+                    .child(self.render_object_checkbox(i, object, row.id.clone(), cx)),
+            )
+            .when_some(row.quirk, |this, quirk| {
+                this.child(
+                    div()
+                        .absolute()
+                        .top_1()
+                        .right_1()
+                        .child(quirk_badge(&row.id, quirk, cx)),
+                )
+            })
+            .child(self.render_object_icon(object, true, cx))
+            .child(
+                div()
+                    .w_full()
+                    .text_xs()
+                    .text_center()
+                    .truncate()
+                    .child(row.name.clone()),
+            )
+            .when_some(detail, |this, detail| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .opacity(0.)
+                        .group_hover(row.id.clone(), |el| el.opacity(100.))
+                        .child(detail),
+                )
+            });
+
+        self.with_object_actions(tile, object, cx)
+    }
+
+    /// Checkbox that shows on hover of `group`, shift-click checks the whole range
+    /// from the previously toggled object
+    fn render_object_checkbox(
+        &self,
+        i: usize,
+        object: &S3Object,
+        group: SharedString,
+        cx: &mut Context<Self>,
+    ) -> Checkbox {
+        let checked = self.selection.contains(object.key());
+
+        // scoped by the row's id
+        Checkbox::new("check")
+            .checked(checked)
+            .map(|this| {
+                if !checked {
+                    this.opacity(0.).group_hover(group, |el| el.opacity(100.))
+                } else {
+                    this
+                }
+            })
+            .on_click(cx.listener(move |this, checked, window, cx| {
+                cx.stop_propagation();
+
+                this.toggle_checked(i, *checked, window.modifiers().shift);
+                cx.notify();
+            }))
+    }
+
+    fn render_object_item(
+        &self,
+        i: usize,
+        object: &S3Object,
+        row: &RowModel,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let settings = Settings::get(cx);
+        let element = div()
+            .id(row.id.clone())
             .flex()
             .w_full()
-            .h(px(40.))
-            .gap_4()
+            .h(settings.row_height(ROW_HEIGHT))
             .items_center()
             .justify_between()
             .rounded_md()
-            .p_2()
-            .pr_8()
+            .map(|this| {
+                if settings.is_compact() {
+                    this.gap_2().px_2().py_0p5().pr_4()
+                } else {
+                    this.gap_4().p_2().pr_8()
+                }
+            })
             .text_sm()
             .map(|this| {
-                if self.checked_objects.contains_key(object.key()) {
+                if self.selection.contains(object.key()) {
                     this.border_1().border_color(cx.theme().primary)
                 } else {
                     this.border_b_1().border_color(cx.theme().sidebar_border)
                 }
             })
-            .group(i.to_string())
+            .group(row.id.clone())
             .hover(|this| this.bg(cx.theme().secondary_hover.opacity(0.4)))
+            .when(self.is_flashed(object), |this| {
+                this.bg(cx.theme().primary.opacity(0.15))
+            })
             .child(
                 div()
                     .flex()
+                    .min_w_0()
                     .items_center()
                     .gap_4()
-                    .child(
-                        Checkbox::new(SharedString::new(format!("chk-{i}")))
-                            .checked(self.checked_objects.contains_key(object.key()))
-                            .map(|this| {
-                                if !self.checked_objects.contains_key(object.key()) {
-                                    this.opacity(0.)
-                                        .group_hover(SharedString::new(i.to_string()), |el| {
-                                            el.opacity(100.)
-                                        })
-                                } else {
-                                    this
-                                }
-                            })
-                            .on_click(cx.listener(move |this, checked, _window, cx| {
-                                cx.stop_propagation();
-
-                                let object = _object.clone();
-                                let key = object.key();
-                                if *checked {
-                                    this.checked_objects.insert(key.clone(), object.clone());
-                                } else {
-                                    this.checked_objects.remove(key);
-                                }
-                                cx.notify();
-                            })),
-                    )
-                    .child(match object.as_ref() {
-                        __S3Object::Folder(_) => Icon::new(IconName::Folder),
-                        __S3Object::File { .. } => Icon::empty().path("icons/file-digit.svg"),
-                    })
+                    .child(self.render_object_checkbox(i, object, row.id.clone(), cx))
+                    .child(self.render_object_icon(object, false, cx))
                     .text_sm()
-                    .child(SharedString::new(
-                        object.key().trim_key_prefix(self.prefix.as_str()),
-                    )),
+                    // long names give way to the size and date on narrow windows
+                    .child(div().min_w_0().truncate().child(row.name.clone()))
+                    .when_some(row.quirk, |this, quirk| {
+                        this.child(quirk_badge(&row.id, quirk, cx))
+                    }),
             )
             .child(
                 div()
@@ -583,32 +4855,355 @@ impl BrowseUi {
                     .gap_4()
                     .items_center()
                     .map(|this| match object.as_ref() {
-                        __S3Object::Folder(_) => this,
-                        __S3Object::File {
-                            size,
-                            last_modified,
-                            ..
-                        } => this
+                        __S3Object::Folder(key) => this
+                            .when_some(self.folder_count_label(key, cx), |this, label| {
+                                this.text_color(cx.theme().muted_foreground).child(label)
+                            }),
+                        __S3Object::File { key, .. } => this
                             .text_color(cx.theme().muted_foreground)
-                            .child(
-                                div()
-                                    // i know this font won't exist for everyone
-                                    .font_family("JetBrains Mono")
-                                    .child(util::human_readable_size(*size)),
-                            )
-                            .child(SharedString::new(last_modified.clone().unwrap_or_default())),
+                            .when_some(self.drag_out_label(key), |this, label| {
+                                this.child(div().text_color(cx.theme().primary).child(label))
+                            })
+                            .when_some(row.size.clone(), |this, size| {
+                                this.child(
+                                    div()
+                                        // i know this font won't exist for everyone
+                                        .font_family("JetBrains Mono")
+                                        .child(size),
+                                )
+                            })
+                            .child(row.last_modified.clone()),
                     }),
-            )
-            .map(|this| match object.as_ref() {
-                __S3Object::Folder(key) => {
-                    let prefix = SharedString::new(key.clone());
-                    this.on_click(cx.listener(move |this, _ev, _window, cx| {
+            );
+
+        self.with_object_actions(element, object, cx)
+    }
+
+    /// Click and context menu behaviour shared by list rows and grid tiles
+    fn with_object_actions(
+        &self,
+        element: Stateful<Div>,
+        object: &S3Object,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let object = object.clone();
+        let entity = cx.weak_entity();
+        let can_read =
+            RemoteCapabilities::get(&self.s3_remote.remote_name, cx).read != Access::Denied;
+
+        match object.as_ref() {
+            __S3Object::Folder(key) => {
+                let prefix = SharedString::new(balti_s3::folder_path(key));
+                let _prefix = prefix.clone();
+                let object = object.clone();
+
+                element
+                    .on_click(cx.listener(move |this, ev: &ClickEvent, _window, cx| {
+                        if ev.modifiers().secondary() {
+                            this.open_in_new_tab(prefix.clone(), cx);
+                            return;
+                        }
+
                         this.browse_nav.update(cx, |_nav, cx| {
                             cx.emit(BrowseRefreshEvent(prefix.clone()));
                         });
                     }))
-                }
-                __S3Object::File { .. } => this,
+                    .context_menu(move |menu, _window, cx| {
+                        let prefix = _prefix.clone();
+                        let entity = entity.clone();
+
+                        let _prefix = prefix.clone();
+                        let _entity = entity.clone();
+                        let paste_prefix = prefix.clone();
+                        let paste_entity = entity.clone();
+                        let clipboard_entity = entity.clone();
+                        let has_clipboard = ObjectClipboard::entry(cx).is_some();
+
+                        let menu = menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                            let prefix = _prefix.clone();
+                            let entity = _entity.clone();
+
+                            div()
+                                .id("open-in-new-tab")
+                                .flex()
+                                .gap_2()
+                                .items_center()
+                                .child(Icon::new(IconName::ExternalLink).small())
+                                .child(div().child(t!("browse.open_in_new_tab")).text_sm())
+                                .on_click(move |_ev, _window, cx| {
+                                    let _ = entity.update(cx, |this, cx| {
+                                        this.open_in_new_tab(prefix.clone(), cx);
+                                    });
+                                })
+                        });
+                        // zipping and verifying read every object, pointless without the
+                        // permission
+                        let verify_prefix = Arc::<str>::from(prefix.as_ref());
+                        let verify_entity = entity.clone();
+                        let (junk_prefix, junk_entity) = (verify_prefix.clone(), entity.clone());
+                        let menu = if can_read {
+                            menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                                let prefix = prefix.clone();
+                                let entity = entity.clone();
+
+                                div()
+                                    .id("download-zip")
+                                    .flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(Icon::new(IconName::ArrowDown).small())
+                                    .child(div().child(t!("browse.download_zip")).text_sm())
+                                    .on_click(move |_ev, window, cx| {
+                                        let _ = entity.update(cx, |this, cx| {
+                                            this.download_zip(prefix.clone(), window, cx);
+                                        });
+                                    })
+                            })
+                            .menu_element(
+                                Box::new(EmptyAction),
+                                move |_window, _cx| {
+                                    let prefix = verify_prefix.clone();
+                                    let entity = verify_entity.clone();
+
+                                    div()
+                                        .id("verify-prefix")
+                                        .flex()
+                                        .gap_2()
+                                        .items_center()
+                                        .child(Icon::new(IconName::CircleCheck).small())
+                                        .child(div().child(t!("browse.verify")).text_sm())
+                                        .on_click(move |_ev, window, cx| {
+                                            let _ = entity.update(cx, |this, cx| {
+                                                this.verify_dialog(prefix.clone(), window, cx);
+                                            });
+                                        })
+                                },
+                            )
+                        } else {
+                            menu
+                        }
+                        .menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                            let prefix = junk_prefix.clone();
+                            let entity = junk_entity.clone();
+
+                            div()
+                                .id("find-junk")
+                                .flex()
+                                .gap_2()
+                                .items_center()
+                                .child(Icon::new(IconName::Search).small())
+                                .child(div().child(t!("browse.find_junk")).text_sm())
+                                .on_click(move |_ev, window, cx| {
+                                    let _ = entity.update(cx, |this, cx| {
+                                        this.find_junk(prefix.clone(), window, cx);
+                                    });
+                                })
+                        })
+                        .separator();
+
+                        let menu =
+                            Self::clipboard_menu(menu, object.clone(), clipboard_entity.clone());
+                        let menu = Self::rename_menu(menu, object.clone(), clipboard_entity);
+                        if !has_clipboard {
+                            return menu;
+                        }
+
+                        menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                            let prefix = paste_prefix.clone();
+                            let entity = paste_entity.clone();
+
+                            div()
+                                .id("paste-into")
+                                .flex()
+                                .gap_2()
+                                .items_center()
+                                .child(Icon::empty().path("icons/clipboard-paste.svg").small())
+                                .child(div().child(t!("browse.paste_into_folder")).text_sm())
+                                .on_click(move |_ev, window, cx| {
+                                    let _ = entity.update(cx, |this, cx| {
+                                        this.paste(prefix.clone(), window, cx);
+                                    });
+                                })
+                        })
+                    })
+                    .into_any_element()
+            }
+            __S3Object::File { key, size, .. } => {
+                let dragged = DraggedObject {
+                    key: key.clone(),
+                    size: (*size).max(0) as u64,
+                    name: SharedString::new(key.rsplit('/').next().unwrap_or_default().to_owned()),
+                };
+                let drag_entity = entity.clone();
+                let (duplicate_key, duplicate_size) = (key.clone(), *size);
+
+                element
+                    .when(can_read, |this| {
+                        this.on_drag(dragged, move |dragged, _offset, window, cx| {
+                            let preview = cx.new(|_cx| dragged.clone());
+                            let _ = drag_entity.update(cx, |this, cx| {
+                                this.start_drag_out(dragged, &preview, window, cx);
+                            });
+                            preview
+                        })
+                    })
+                    .context_menu(move |menu, _window, _cx| {
+                        let download_key = duplicate_key.clone();
+                        let download_entity = entity.clone();
+                        let presign_key = duplicate_key.clone();
+                        let presign_entity = entity.clone();
+                        let menu = if can_read {
+                            menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                                let key = download_key.clone();
+                                let entity = download_entity.clone();
+
+                                div()
+                                    .id("download-file")
+                                    .flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(Icon::new(IconName::ArrowDown).small())
+                                    .child(div().child(t!("browse.download")).text_sm())
+                                    .on_click(move |_ev, window, cx| {
+                                        let _ = entity.update(cx, |this, cx| {
+                                            this.download_file(key.clone(), window, cx);
+                                        });
+                                    })
+                            })
+                            .menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                                let key = presign_key.clone();
+                                let entity = presign_entity.clone();
+
+                                div()
+                                    .id("copy-presigned-url")
+                                    .flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(Icon::new(IconName::ExternalLink).small())
+                                    .child(div().child(t!("presign.menu")).text_sm())
+                                    .on_click(move |_ev, window, cx| {
+                                        presign_dialog::open_dialog(
+                                            entity.clone(),
+                                            key.clone(),
+                                            window,
+                                            cx,
+                                        );
+                                    })
+                            })
+                            .separator()
+                        } else {
+                            menu
+                        };
+                        let menu = Self::clipboard_menu(menu, object.clone(), entity.clone());
+                        let menu = Self::rename_menu(menu, object.clone(), entity.clone());
+                        let (key, size) = (duplicate_key.clone(), duplicate_size);
+                        let properties_key = key.clone();
+                        let properties_entity = entity.clone();
+                        let entity = entity.clone();
+
+                        menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                            let key = key.clone();
+                            let entity = entity.clone();
+
+                            div()
+                                .id("duplicate")
+                                .flex()
+                                .gap_2()
+                                .items_center()
+                                .child(Icon::new(IconName::Copy).small())
+                                .child(div().child(t!("browse.duplicate")).text_sm())
+                                .on_click(move |_ev, window, cx| {
+                                    let _ = entity.update(cx, |this, cx| {
+                                        this.duplicate(key.clone(), size, window, cx);
+                                    });
+                                })
+                        })
+                        .separator()
+                        .menu_element(
+                            Box::new(EmptyAction),
+                            move |_window, _cx| {
+                                let key = properties_key.clone();
+                                let entity = properties_entity.clone();
+
+                                div()
+                                    .id("properties")
+                                    .flex()
+                                    .gap_2()
+                                    .items_center()
+                                    .child(Icon::new(IconName::Info).small())
+                                    .child(div().child(t!("browse.properties")).text_sm())
+                                    .on_click(move |_ev, window, cx| {
+                                        let _ = entity.update(cx, |this, cx| {
+                                            this.open_properties(key.clone(), window, cx);
+                                        });
+                                    })
+                            },
+                        )
+                    })
+                    .into_any_element()
+            }
+        }
+    }
+
+    /// Copy and cut entries for an object's context menu
+    fn clipboard_menu(menu: PopupMenu, object: S3Object, entity: WeakEntity<Self>) -> PopupMenu {
+        [
+            (
+                ClipboardMode::Copy,
+                "copy",
+                "icons/copy.svg",
+                t!("browse.copy"),
+            ),
+            (
+                ClipboardMode::Cut,
+                "cut",
+                "icons/scissors.svg",
+                t!("browse.cut"),
+            ),
+        ]
+        .into_iter()
+        .fold(menu, |menu, (mode, id, icon, label)| {
+            let object = object.clone();
+            let entity = entity.clone();
+
+            menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                let object = object.clone();
+                let entity = entity.clone();
+
+                div()
+                    .id(id)
+                    .flex()
+                    .gap_2()
+                    .items_center()
+                    .child(Icon::empty().path(icon).small())
+                    .child(div().child(label.clone()).text_sm())
+                    .on_click(move |_ev, _window, cx| {
+                        let _ = entity.update(cx, |this, cx| {
+                            this.copy_to_clipboard(mode, Some(object.clone()), cx);
+                        });
+                    })
             })
+        })
+    }
+
+    /// Rename entry for an object's context menu
+    fn rename_menu(menu: PopupMenu, object: S3Object, entity: WeakEntity<Self>) -> PopupMenu {
+        menu.menu_element(Box::new(EmptyAction), move |_window, _cx| {
+            let object = object.clone();
+            let entity = entity.clone();
+
+            div()
+                .id("rename")
+                .flex()
+                .gap_2()
+                .items_center()
+                .child(Icon::empty().path("icons/pencil.svg").small())
+                .child(div().child(t!("browse.rename")).text_sm())
+                .on_click(move |_ev, window, cx| {
+                    let _ = entity.update(cx, |this, cx| {
+                        this.rename_dialog(object.clone(), window, cx);
+                    });
+                })
+        })
     }
 }
@@ -0,0 +1,436 @@
+use balti_err::{AppResult, ErrorKind};
+use balti_s3::{LifecycleRuleSummary, PublicAccess, PublicAccessBlock, S3Remote};
+use chrono::Utc;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    tab::{Tab, TabBar},
+    v_flex,
+};
+
+use crate::{
+    credentials::{self, ExpiredRemotes},
+    rt,
+    strings::t,
+    ui::loading,
+};
+
+const OVERVIEW_TAB: usize = 0;
+const LIFECYCLE_TAB: usize = 1;
+
+/// Details of the bucket behind a remote, fetched as the dialog opens
+struct BucketInfo {
+    remote: S3Remote,
+    tab: usize,
+    /// `None` while loading
+    lifecycle: Option<AppResult<Vec<LifecycleRuleSummary>>>,
+    /// `None` while loading, the inner `None` when the provider doesn't support it
+    access_block: Option<AppResult<Option<PublicAccessBlock>>>,
+    /// Like `access_block`, whether the bucket policy is public
+    policy_is_public: Option<AppResult<Option<bool>>>,
+    show_access_details: bool,
+    _lifecycle_task: Task<()>,
+    _public_access_task: Task<()>,
+}
+
+impl BucketInfo {
+    fn new(remote: S3Remote, cx: &mut Context<Self>) -> Self {
+        let remote_name = remote.remote_name.clone();
+        let task = rt::spawn(cx, balti_s3::get_lifecycle_configuration(remote.clone()));
+        let lifecycle_task = cx.spawn(async move |this, cx| {
+            let result = task.await.flatten();
+            let _ = this.update(cx, |this, cx| {
+                if let Err(err) = &result {
+                    err.log();
+                    ExpiredRemotes::note_error(&remote_name, err, cx);
+                }
+                this.lifecycle = Some(result);
+                cx.notify();
+            });
+        });
+
+        let remote_name = remote.remote_name.clone();
+        let _remote = remote.clone();
+        let task = rt::spawn(cx, async move {
+            futures::join!(
+                balti_s3::get_public_access_block(_remote.clone()),
+                balti_s3::get_bucket_policy_status(_remote),
+            )
+        });
+        let public_access_task = cx.spawn(async move |this, cx| {
+            let (access_block, policy_is_public) = match task.await {
+                Ok(results) => results,
+                Err(err) => (Err(err.clone()), Err(err)),
+            };
+            let _ = this.update(cx, |this, cx| {
+                for err in [access_block.as_ref().err(), policy_is_public.as_ref().err()]
+                    .into_iter()
+                    .flatten()
+                {
+                    err.log();
+                    ExpiredRemotes::note_error(&remote_name, err, cx);
+                }
+                this.access_block = Some(access_block);
+                this.policy_is_public = Some(policy_is_public);
+                cx.notify();
+            });
+        });
+
+        Self {
+            remote,
+            tab: OVERVIEW_TAB,
+            lifecycle: None,
+            access_block: None,
+            policy_is_public: None,
+            show_access_details: false,
+            _lifecycle_task: lifecycle_task,
+            _public_access_task: public_access_task,
+        }
+    }
+
+    fn select_tab(&mut self, index: &usize, _window: &mut Window, cx: &mut Context<Self>) {
+        self.tab = *index;
+        cx.notify();
+    }
+
+    fn toggle_access_details(
+        &mut self,
+        _ev: &ClickEvent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_access_details = !self.show_access_details;
+        cx.notify();
+    }
+
+    /// Badge answering whether the bucket is public, with its details below when expanded
+    fn render_public_access(&self, cx: &mut Context<Self>) -> AnyElement {
+        let (Some(access_block), Some(policy_is_public)) =
+            (self.access_block.as_ref(), self.policy_is_public.as_ref())
+        else {
+            return div()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child(t!("bucket_info.checking"))
+                .into_any_element();
+        };
+
+        let access = PublicAccess::assess(
+            access_block.as_ref().ok().and_then(Option::as_ref),
+            policy_is_public.as_ref().ok().copied().flatten(),
+        );
+        let (label, color) = match access {
+            PublicAccess::Blocked => (t!("bucket_info.blocked"), green()),
+            PublicAccess::PotentiallyPublic => {
+                (t!("bucket_info.potentially_public"), cx.theme().danger)
+            }
+            PublicAccess::Unknown => (t!("bucket_info.unknown"), cx.theme().muted_foreground),
+        };
+
+        let block_details = match access_block {
+            Ok(Some(block)) => block
+                .settings()
+                .iter()
+                .map(|(name, on)| {
+                    if *on {
+                        t!("bucket_info.setting_on", name = name)
+                    } else {
+                        t!("bucket_info.setting_off", name = name)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            Ok(None) => t!("bucket_info.unsupported"),
+            Err(err) => check_error(&err.message, err.kind),
+        };
+        let policy_details = match policy_is_public {
+            Ok(Some(true)) => t!("bucket_info.policy_public"),
+            Ok(Some(false)) => t!("bucket_info.policy_not_public"),
+            Ok(None) => t!("bucket_info.unsupported"),
+            Err(err) => check_error(&err.message, err.kind),
+        };
+
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .px_1()
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(color)
+                            .text_xs()
+                            .text_color(color)
+                            .child(label),
+                    )
+                    .child(
+                        Button::new("toggle_access_details")
+                            .xsmall()
+                            .ghost()
+                            .icon(if self.show_access_details {
+                                IconName::ChevronUp
+                            } else {
+                                IconName::ChevronDown
+                            })
+                            .label(if self.show_access_details {
+                                t!("common.hide_details")
+                            } else {
+                                t!("common.show_details")
+                            })
+                            .on_click(cx.listener(Self::toggle_access_details)),
+                    ),
+            )
+            .when(self.show_access_details, |this| {
+                this.child(
+                    v_flex()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(t!(
+                            "bucket_info.access_block_details",
+                            details = block_details
+                        ))
+                        .child(t!("bucket_info.policy_details", details = policy_details)),
+                )
+            })
+            .into_any_element()
+    }
+
+    fn render_overview(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let config = &self.remote.config;
+
+        v_flex()
+            .gap_1()
+            .child(info_row(
+                t!("bucket_info.remote"),
+                self.remote.remote_name.to_string(),
+                cx,
+            ))
+            .child(info_row(
+                t!("bucket_info.bucket"),
+                config.bucket_name.to_string(),
+                cx,
+            ))
+            .child(info_row(
+                t!("bucket_info.region"),
+                config.region.to_string(),
+                cx,
+            ))
+            .child(info_row(
+                t!("bucket_info.endpoint"),
+                config.endpoint.to_string(),
+                cx,
+            ))
+            .child(info_row(
+                t!("bucket_info.root_prefix"),
+                config
+                    .root_prefix
+                    .as_deref()
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| t!("bucket_info.no_root_prefix")),
+                cx,
+            ))
+            .child(info_row(
+                t!("bucket_info.credentials"),
+                credentials::describe(config, Utc::now()),
+                cx,
+            ))
+            .child(info_row(
+                t!("bucket_info.public_access"),
+                self.render_public_access(cx),
+                cx,
+            ))
+    }
+
+    fn render_lifecycle(&self, cx: &App) -> AnyElement {
+        let muted = cx.theme().muted_foreground;
+
+        let rules = match &self.lifecycle {
+            None => {
+                return v_flex()
+                    .gap_2()
+                    .children((0..3).map(|_| loading::skeleton(px(24.), cx)))
+                    .into_any_element();
+            }
+            Some(Err(err)) if err.kind == ErrorKind::Unsupported => {
+                return note(t!("bucket_info.lifecycle_unsupported"), muted);
+            }
+            Some(Err(err)) => {
+                return note(
+                    t!("bucket_info.lifecycle_failed", error = err.message),
+                    cx.theme().danger,
+                );
+            }
+            Some(Ok(rules)) if rules.is_empty() => {
+                return note(t!("bucket_info.no_lifecycle_rules"), muted);
+            }
+            Some(Ok(rules)) => rules,
+        };
+
+        let border = cx.theme().sidebar_border;
+        let header = h_flex()
+            .gap_3()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(border)
+            .text_xs()
+            .text_color(muted)
+            .child(cell(px(120.)).child(t!("bucket_info.rule")))
+            .child(cell(px(160.)).child(t!("bucket_info.applies_to")))
+            .child(cell(px(64.)).child(t!("bucket_info.status")))
+            .child(cell(px(120.)).child(t!("bucket_info.expiration")))
+            .child(div().flex_1().child(t!("bucket_info.transitions")));
+
+        div()
+            .id("lifecycle-rules")
+            .flex()
+            .flex_col()
+            .max_h(px(360.))
+            .overflow_y_scroll()
+            .border_1()
+            .border_color(border)
+            .rounded_md()
+            .child(header)
+            .children(rules.iter().map(|rule| {
+                let mut expiration = vec![match rule.expiration {
+                    Some(at) => at.to_string(),
+                    None => t!("bucket_info.never"),
+                }];
+                if let Some(days) = rule.noncurrent_expiration_days {
+                    expiration.push(t!("bucket_info.noncurrent_after", count = days));
+                }
+                if let Some(days) = rule.abort_multipart_days {
+                    expiration.push(t!("bucket_info.incomplete_uploads_after", count = days));
+                }
+
+                let transitions = rule
+                    .transitions
+                    .iter()
+                    .map(|transition| match transition.at {
+                        Some(at) => format!("{} {at}", transition.storage_class),
+                        None => transition.storage_class.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+
+                h_flex()
+                    .items_start()
+                    .gap_3()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(border)
+                    .text_sm()
+                    .child(
+                        cell(px(120.)).font_medium().child(
+                            rule.id
+                                .as_deref()
+                                .map(str::to_owned)
+                                .unwrap_or_else(|| t!("bucket_info.unnamed")),
+                        ),
+                    )
+                    .child(cell(px(160.)).child(SharedString::new(rule.filter.clone())))
+                    .child(
+                        cell(px(64.))
+                            .when(!rule.enabled, |this| this.text_color(muted))
+                            .child(if rule.enabled {
+                                t!("bucket_info.enabled")
+                            } else {
+                                t!("bucket_info.disabled")
+                            }),
+                    )
+                    .child(cell(px(120.)).flex().flex_col().children(expiration))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .flex_1()
+                            .min_w_0()
+                            .when(transitions.is_empty(), |this| {
+                                this.text_color(muted).child(t!("bucket_info.none"))
+                            })
+                            .children(transitions),
+                    )
+            }))
+            .into_any_element()
+    }
+}
+
+impl Render for BucketInfo {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .gap_3()
+            .child(
+                TabBar::new("bucket_info_tabs")
+                    .selected_index(self.tab)
+                    .on_click(cx.listener(Self::select_tab))
+                    .child(Tab::new().label(t!("bucket_info.overview")))
+                    .child(Tab::new().label(t!("bucket_info.lifecycle"))),
+            )
+            .map(|this| match self.tab {
+                LIFECYCLE_TAB => this.child(self.render_lifecycle(cx)),
+                _ => this.child(self.render_overview(cx)),
+            })
+    }
+}
+
+/// Detail of a public access check that failed, lacking permissions isn't an error
+fn check_error(message: &str, kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::AccessDenied => t!("bucket_info.check_denied"),
+        _ => t!("bucket_info.check_failed", error = message),
+    }
+}
+
+fn info_row(label: String, value: impl IntoElement, cx: &App) -> impl IntoElement {
+    h_flex()
+        .items_start()
+        .gap_3()
+        .text_sm()
+        .child(
+            div()
+                .w(px(96.))
+                .flex_shrink_0()
+                .text_color(cx.theme().muted_foreground)
+                .child(label),
+        )
+        .child(div().min_w_0().child(value))
+}
+
+fn cell(width: Pixels) -> Div {
+    div().w(width).flex_shrink_0().min_w_0()
+}
+
+fn note(message: String, color: Hsla) -> AnyElement {
+    div()
+        .py_2()
+        .text_sm()
+        .text_color(color)
+        .child(message)
+        .into_any_element()
+}
+
+pub fn open_dialog(remote: S3Remote, window: &mut Window, cx: &mut App) {
+    let title = SharedString::new(t!("bucket_info.title", bucket = remote.bucket_name));
+    let info = cx.new(|cx| BucketInfo::new(remote, cx));
+
+    window.open_dialog(cx, move |dialog, _window, _cx| {
+        dialog
+            .rounded_lg()
+            .w(px(720.))
+            .title(title.clone())
+            .child(info.clone())
+            .footer(|_, _, _, _cx| {
+                vec![
+                    Button::new("close_dialog")
+                        .label(t!("common.close"))
+                        .on_click(|_, window, cx| {
+                            window.close_dialog(cx);
+                        }),
+                ]
+            })
+    });
+}
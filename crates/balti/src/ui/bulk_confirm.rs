@@ -0,0 +1,78 @@
+use gpui::*;
+
+use crate::{
+    bulk::{self, BulkOperation, ObjectCount, Throughput},
+    settings::Settings,
+    strings::t,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkChoice {
+    Cancel,
+    /// Go back to pick fewer objects
+    Narrow,
+    Proceed,
+}
+
+/// Objects past which bulk operations ask once more, `0` when they never do
+pub fn threshold(cx: &App) -> usize {
+    Settings::get(cx).confirm_objects_above
+}
+
+/// Whether `operation` on `count` objects should ask first, see [`ask`]
+pub fn needs_confirmation(count: ObjectCount, cx: &App) -> bool {
+    bulk::needs_confirmation(count, threshold(cx))
+}
+
+/// Asks before `operation` touches `count` objects, stating how long that would take
+/// at recent speed. `narrow` offers going back to the selection. Dismissing counts as
+/// Cancel.
+pub fn ask(
+    operation: BulkOperation,
+    count: ObjectCount,
+    narrow: bool,
+    window: &mut Window,
+    cx: &mut App,
+) -> impl Future<Output = BulkChoice> + use<> {
+    let estimate = cx.global::<Throughput>().estimate(operation, count.objects);
+    let message = match operation {
+        BulkOperation::Delete => t!("bulk.delete_objects", objects = count.label()),
+        BulkOperation::Copy => t!("bulk.copy_objects", objects = count.label()),
+        BulkOperation::Move => t!("bulk.move_objects", objects = count.label()),
+        BulkOperation::Download => t!("bulk.download_objects", objects = count.label()),
+    };
+    let detail = bulk::confirmation_detail(operation, threshold(cx), estimate);
+
+    let mut choices = vec![(BulkChoice::Cancel, t!("common.cancel"))];
+    if narrow {
+        choices.push((BulkChoice::Narrow, t!("bulk.narrow_selection")));
+    }
+    choices.push((
+        BulkChoice::Proceed,
+        match operation {
+            BulkOperation::Delete => t!("bulk.delete_all"),
+            BulkOperation::Copy => t!("bulk.copy_all"),
+            BulkOperation::Move => t!("bulk.move_all"),
+            BulkOperation::Download => t!("bulk.download_all"),
+        },
+    ));
+
+    let buttons = choices
+        .iter()
+        .map(|(choice, label)| match choice {
+            BulkChoice::Cancel => PromptButton::Cancel(label.clone().into()),
+            _ => PromptButton::Ok(label.clone().into()),
+        })
+        .collect::<Vec<_>>();
+
+    let answer = window.prompt(PromptLevel::Warning, &message, Some(&detail), &buttons, cx);
+    async move {
+        match answer.await {
+            Ok(index) => choices
+                .get(index)
+                .map(|(choice, _)| *choice)
+                .unwrap_or(BulkChoice::Cancel),
+            Err(_) => BulkChoice::Cancel,
+        }
+    }
+}
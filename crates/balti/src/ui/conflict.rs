@@ -0,0 +1,44 @@
+use gpui::*;
+
+use crate::strings::t;
+
+/// How to go on when the destination of an operation is taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    Cancel,
+    /// Leave the taken destinations alone and do the rest
+    Skip,
+    /// Pick another name
+    Rename,
+    /// Go ahead regardless, replacing or sitting next to what's there
+    Proceed,
+}
+
+/// Asks how to handle a conflict, offering Cancel followed by `choices` with their labels.
+/// Dismissing the prompt counts as Cancel.
+pub fn ask(
+    message: &str,
+    detail: &str,
+    choices: &[(ConflictChoice, String)],
+    window: &mut Window,
+    cx: &mut App,
+) -> impl Future<Output = ConflictChoice> + use<> {
+    let mut buttons = vec![PromptButton::Cancel(t!("common.cancel").into())];
+    buttons.extend(
+        choices
+            .iter()
+            .map(|(_, label)| PromptButton::Ok(label.clone().into())),
+    );
+    let choices = choices.to_vec();
+
+    let answer = window.prompt(PromptLevel::Warning, message, Some(detail), &buttons, cx);
+    async move {
+        match answer.await {
+            Ok(index) if index > 0 => choices
+                .get(index - 1)
+                .map(|(choice, _)| *choice)
+                .unwrap_or(ConflictChoice::Cancel),
+            _ => ConflictChoice::Cancel,
+        }
+    }
+}
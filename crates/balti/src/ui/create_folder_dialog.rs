@@ -1,12 +1,23 @@
+use std::rc::Rc;
+
 use gpui::*;
 use gpui_component::{
-    Disableable, IconName, StyledExt, WindowExt,
+    Disableable, StyledExt, WindowExt,
     button::{Button, ButtonVariants},
     dialog::Dialog,
     form::{field, v_form},
     input::{Input, InputState},
 };
 
+use crate::{
+    strings::t,
+    ui::{
+        dialog_form::{self, DialogFormExt, Submit},
+        dismiss::DismissExt,
+        loading::LoadingExt,
+    },
+};
+
 pub trait CreateFolderDialog: Render {
     fn create_folder(
         &mut self,
@@ -18,39 +29,93 @@ pub trait CreateFolderDialog: Render {
     fn is_creating(&self) -> bool;
 }
 
+pub fn open_dialog<T: CreateFolderDialog>(
+    entity: WeakEntity<T>,
+    prefix: SharedString,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let folder_name_input_state =
+        cx.new(|cx| InputState::new(window, cx).placeholder("CoolFolder"));
+
+    let submit = submit(entity.clone(), folder_name_input_state.clone());
+    dialog_form::bind_fields(
+        &[folder_name_input_state.clone()],
+        submit.clone(),
+        window,
+        cx,
+    );
+
+    window.open_dialog(cx, move |dialog, _window, _cx| {
+        self::dialog(
+            dialog,
+            entity.clone(),
+            prefix.clone(),
+            folder_name_input_state.clone(),
+            submit.clone(),
+        )
+    });
+}
+
+/// Creates the folder unless the name is blank or one is being created already
+fn submit<T: CreateFolderDialog>(
+    entity: WeakEntity<T>,
+    folder_name_input_state: Entity<InputState>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let folder_name = folder_name_input_state.read(cx).value();
+        let is_creating = entity
+            .read_with(cx, |this, _cx| this.is_creating())
+            .unwrap_or(true);
+        if folder_name.trim().is_empty() || is_creating {
+            return;
+        }
+
+        let _ = entity.update(cx, |this, cx| {
+            this.create_folder(folder_name, window, cx);
+            cx.notify();
+        });
+    })
+}
+
 pub fn dialog<T: CreateFolderDialog>(
     dialog: Dialog,
     entity: WeakEntity<T>,
     prefix: SharedString,
     folder_name_input_state: Entity<InputState>,
+    submit: Submit,
 ) -> Dialog {
+    let _entity = entity.clone();
+
     dialog
         .alert()
-        .keyboard(false)
-        .overlay_closable(false)
+        .cancel_on_escape(false, move |cx| {
+            _entity
+                .read_with(cx, |this, _cx| this.is_creating())
+                .unwrap_or_default()
+        })
+        .submit_on_enter(submit.clone())
         .rounded_lg()
-        .title("Create new folder")
+        .title(t!("create_folder.title"))
         .v_flex()
         .child(
             v_form().child(
                 field()
-                    .label("Folder Name")
+                    .label(t!("create_folder.name"))
                     .child(Input::new(&folder_name_input_state).cleanable(true))
-                    .description(format!("Path: {prefix}")),
+                    .description(t!("create_folder.path", prefix = prefix)),
             ),
         )
         .footer(move |_, _, _, cx| {
-            let folder_name_input_state = folder_name_input_state.clone();
-
-            let entity = entity.clone();
-            let _entity = entity.clone();
+            let submit = submit.clone();
 
             let is_creating = entity
                 .read_with(cx, |this, _cx| this.is_creating())
                 .unwrap_or_default();
+            let is_blank = folder_name_input_state.read(cx).value().trim().is_empty();
 
             let cancel = Button::new("cancel_dialog")
-                .label("Cancel")
+                .label(t!("common.cancel"))
                 .disabled(is_creating)
                 .on_click(|_, window, cx| {
                     window.close_dialog(cx);
@@ -58,18 +123,10 @@ pub fn dialog<T: CreateFolderDialog>(
 
             let ok = Button::new("ok_dialog")
                 .primary()
-                .label("Create")
-                .disabled(is_creating)
-                .loading(is_creating)
-                .loading_icon(IconName::LoaderCircle)
-                .on_click(move |_ev, window, cx| {
-                    let folder_name = folder_name_input_state.read(cx).value();
-
-                    let _ = entity.update(cx, |this, cx| {
-                        this.create_folder(folder_name, window, cx);
-                        cx.notify();
-                    });
-                });
+                .label(t!("create_folder.create"))
+                .disabled(is_creating || is_blank)
+                .loading_state(is_creating, cx)
+                .on_click(move |_ev, window, cx| submit(window, cx));
 
             vec![cancel, ok]
         })
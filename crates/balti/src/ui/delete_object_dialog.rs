@@ -1,10 +1,15 @@
 use gpui::*;
 use gpui_component::{
-    Disableable, IconName, StyledExt, WindowExt,
+    Disableable, StyledExt, WindowExt,
     button::{Button, ButtonVariants},
     dialog::Dialog,
 };
 
+use crate::{
+    strings::t,
+    ui::{dialog_form::DialogFormExt, dismiss::DismissExt, loading::LoadingExt},
+};
+
 pub trait DeleteObjectDialog: Render {
     fn delete_objects(&mut self, window: &mut Window, cx: &mut Context<Self>);
 
@@ -16,16 +21,24 @@ pub fn dialog<T: DeleteObjectDialog>(
     selected_objects_count: usize,
     entity: WeakEntity<T>,
 ) -> Dialog {
+    let is_deleting = {
+        let entity = entity.clone();
+        move |cx: &App| {
+            entity
+                .read_with(cx, |this, _cx| this.is_deleting())
+                .unwrap_or_default()
+        }
+    };
+
     dialog
         .alert()
-        .keyboard(false)
-        .overlay_closable(false)
+        // destructive, clicking outside doesn't dismiss it and Enter cancels rather than deletes
+        .cancel_on_escape(true, is_deleting.clone())
+        .enter_cancels(is_deleting)
         .rounded_lg()
-        .title("Delete object(s)")
+        .title(t!("delete.title", count = selected_objects_count))
         .v_flex()
-        .child(format!(
-            "Delete selected {selected_objects_count} items ? This action cannot be UNDONE."
-        ))
+        .child(t!("delete.message", count = selected_objects_count))
         .footer(move |_, _, _, cx| {
             let entity = entity.clone();
 
@@ -34,7 +47,7 @@ pub fn dialog<T: DeleteObjectDialog>(
                 .unwrap_or_default();
 
             let cancel = Button::new("cancel_dialog")
-                .label("Cancel")
+                .label(t!("common.cancel"))
                 .disabled(is_deleting)
                 .on_click(|_, window, cx| {
                     window.close_dialog(cx);
@@ -42,10 +55,9 @@ pub fn dialog<T: DeleteObjectDialog>(
 
             let ok = Button::new("ok_dialog")
                 .danger()
-                .label("Delete")
+                .label(t!("common.delete"))
                 .disabled(is_deleting)
-                .loading(is_deleting)
-                .loading_icon(IconName::LoaderCircle)
+                .loading_state(is_deleting, cx)
                 .on_click(move |_ev, window, cx| {
                     let _ = entity.update(cx, |this, cx| {
                         this.delete_objects(window, cx);
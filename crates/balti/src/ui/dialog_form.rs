@@ -0,0 +1,61 @@
+use std::rc::Rc;
+
+use gpui::*;
+use gpui_component::{
+    dialog::Dialog,
+    input::{InputEvent, InputState},
+};
+
+/// Primary action of a dialog, run by its confirm button and by Enter. It checks the
+/// inputs itself and does nothing while they're invalid.
+pub type Submit = Rc<dyn Fn(&mut Window, &mut App)>;
+
+/// Focuses the first of `fields` once the dialog is open and submits on Enter in any of
+/// them. Tab already moves through the fields in the order they're laid out.
+pub fn bind_fields(
+    fields: &[Entity<InputState>],
+    submit: Submit,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    for field in fields {
+        let submit = submit.clone();
+        // dropped along with the field when the dialog closes
+        window
+            .subscribe(field, cx, move |_field, event: &InputEvent, window, cx| {
+                if let InputEvent::PressEnter { .. } = event {
+                    submit(window, cx);
+                }
+            })
+            .detach();
+    }
+
+    if let Some(first) = fields.first().cloned() {
+        // opening the dialog moves focus to it, take it back afterwards
+        window.defer(cx, move |window, cx| {
+            first.update(cx, |input, cx| input.focus(window, cx));
+        });
+    }
+}
+
+/// What Enter does when focus isn't in a text field
+pub trait DialogFormExt {
+    /// Enter submits, the dialog closes on success only
+    fn submit_on_enter(self, submit: Submit) -> Self;
+
+    /// Enter cancels unless `busy`, as if Cancel had focus. For destructive confirmations.
+    fn enter_cancels(self, busy: impl Fn(&App) -> bool + 'static) -> Self;
+}
+
+impl DialogFormExt for Dialog {
+    fn submit_on_enter(self, submit: Submit) -> Self {
+        self.on_ok(move |_ev, window, cx| {
+            submit(window, cx);
+            false
+        })
+    }
+
+    fn enter_cancels(self, busy: impl Fn(&App) -> bool + 'static) -> Self {
+        self.on_ok(move |_ev, _window, cx| !busy(cx))
+    }
+}
@@ -0,0 +1,149 @@
+use std::{cell::RefCell, rc::Rc};
+
+use futures::channel::oneshot;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    dialog::Dialog,
+    h_flex,
+};
+
+use crate::{
+    diff::{self, Change, DiffRow, Overwrite},
+    strings::t,
+};
+
+/// Unchanged lines kept around each change, the rest fold away
+const CONTEXT_LINES: usize = 3;
+
+/// Shows what writing `overwrite` changes line by line, with the entries it adds and
+/// removes up top. Resolves to `true` once confirmed, `false` when cancelled or dismissed.
+pub fn confirm(
+    title: String,
+    detail: String,
+    overwrite: Overwrite,
+    window: &mut Window,
+    cx: &mut App,
+) -> impl Future<Output = bool> + use<> {
+    let (answer_tx, answer) = oneshot::channel();
+    let answer_tx = Rc::new(RefCell::new(Some(answer_tx)));
+    let rows = Rc::new(diff::fold(
+        diff::diff_lines(&overwrite.old, &overwrite.new),
+        CONTEXT_LINES,
+    ));
+    let overwrite = Rc::new(overwrite);
+
+    // dismissing drops the dialog along with the sender, which answers `false`
+    window.open_dialog(cx, move |dialog_, _window, cx| {
+        dialog(
+            dialog_,
+            title.clone(),
+            detail.clone(),
+            overwrite.clone(),
+            rows.clone(),
+            answer_tx.clone(),
+            cx,
+        )
+    });
+
+    async move { answer.await.is_ok() }
+}
+
+fn dialog(
+    dialog: Dialog,
+    title: String,
+    detail: String,
+    overwrite: Rc<Overwrite>,
+    rows: Rc<Vec<DiffRow>>,
+    answer_tx: Rc<RefCell<Option<oneshot::Sender<()>>>>,
+    cx: &mut App,
+) -> Dialog {
+    let muted = cx.theme().muted_foreground;
+    let border = cx.theme().sidebar_border;
+    let (added, removed) = (green(), cx.theme().danger);
+
+    dialog
+        .rounded_lg()
+        .w(px(720.))
+        .title(title)
+        .v_flex()
+        .gap_2()
+        .child(div().text_sm().text_color(muted).child(detail))
+        .when(!overwrite.added.is_empty(), |this| {
+            this.child(div().text_sm().text_color(added).child(t!(
+                "diff.added_names",
+                count = overwrite.added.len(),
+                names = overwrite.added.join(", ")
+            )))
+        })
+        .when(!overwrite.removed.is_empty(), |this| {
+            this.child(div().text_sm().text_color(removed).child(t!(
+                "diff.removed_names",
+                count = overwrite.removed.len(),
+                names = overwrite.removed.join(", ")
+            )))
+        })
+        .child(
+            div()
+                .id("diff-lines")
+                .flex()
+                .flex_col()
+                .max_h(px(400.))
+                .overflow_y_scroll()
+                .border_1()
+                .border_color(border)
+                .rounded_md()
+                .py_1()
+                .text_xs()
+                // i know this font won't exist for everyone
+                .font_family("JetBrains Mono")
+                .children(rows.iter().map(|row| {
+                    match row {
+                        DiffRow::Folded(count) => div()
+                            .px_2()
+                            .text_color(muted)
+                            .child(t!("diff.unchanged", count = count)),
+                        DiffRow::Line(line) => {
+                            let (sign, color) = match line.change {
+                                Change::Same => (" ", None),
+                                Change::Added => ("+", Some(added)),
+                                Change::Removed => ("-", Some(removed)),
+                            };
+                            div()
+                                .px_2()
+                                .when_some(color, |this, color| {
+                                    this.text_color(color).bg(color.opacity(0.1))
+                                })
+                                .child(
+                                    h_flex()
+                                        .gap_2()
+                                        .child(sign)
+                                        .child(SharedString::new(line.text.clone())),
+                                )
+                        }
+                    }
+                })),
+        )
+        .footer(move |_, _, _, _cx| {
+            let answer_tx = answer_tx.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .danger()
+                .label(t!("diff.overwrite"))
+                .on_click(move |_ev, window, cx| {
+                    if let Some(answer) = answer_tx.borrow_mut().take() {
+                        let _ = answer.send(());
+                    }
+                    window.close_dialog(cx);
+                });
+
+            vec![cancel, ok]
+        })
+}
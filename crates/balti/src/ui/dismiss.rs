@@ -0,0 +1,62 @@
+use gpui::*;
+use gpui_component::{WindowExt, dialog::Dialog};
+
+use crate::strings::t;
+
+/// Escape handling shared by the app's dialogs, Escape always acts as their Cancel button
+pub trait DismissExt {
+    /// Escape cancels unless `busy`, while Cancel is disabled too. Clicking outside
+    /// also closes the dialog, except for `destructive` confirmations.
+    fn cancel_on_escape(self, destructive: bool, busy: impl Fn(&App) -> bool + 'static) -> Self;
+
+    /// Like [`DismissExt::cancel_on_escape`], asking before discarding input while `is_dirty`
+    fn confirm_discard(
+        self,
+        busy: impl Fn(&App) -> bool + 'static,
+        is_dirty: impl Fn(&App) -> bool + 'static,
+    ) -> Self;
+}
+
+impl DismissExt for Dialog {
+    fn cancel_on_escape(self, destructive: bool, busy: impl Fn(&App) -> bool + 'static) -> Self {
+        self.keyboard(true)
+            .overlay_closable(!destructive)
+            .on_cancel(move |_ev, _window, cx| !busy(cx))
+    }
+
+    fn confirm_discard(
+        self,
+        busy: impl Fn(&App) -> bool + 'static,
+        is_dirty: impl Fn(&App) -> bool + 'static,
+    ) -> Self {
+        self.keyboard(true)
+            .overlay_closable(false)
+            .on_cancel(move |_ev, window, cx| {
+                if busy(cx) {
+                    return false;
+                }
+                if !is_dirty(cx) {
+                    return true;
+                }
+
+                let answer = window.prompt(
+                    PromptLevel::Warning,
+                    &t!("dismiss.discard_changes"),
+                    None,
+                    &[
+                        PromptButton::Cancel(t!("dismiss.keep_editing").into()),
+                        PromptButton::Ok(t!("dismiss.discard").into()),
+                    ],
+                    cx,
+                );
+                window
+                    .spawn(cx, async move |cx| {
+                        if let Ok(1) = answer.await {
+                            let _ = cx.update(|window, cx| window.close_dialog(cx));
+                        }
+                    })
+                    .detach();
+                false
+            })
+    }
+}
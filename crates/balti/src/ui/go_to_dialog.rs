@@ -0,0 +1,269 @@
+use std::{rc::Rc, sync::Arc};
+
+use balti_s3::{RemoteMatch, S3Remote};
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    dialog::Dialog,
+    form::{field, v_form},
+    input::{Input, InputState},
+    v_flex,
+};
+
+use crate::{
+    strings::t,
+    ui::{
+        dialog_form::{self, DialogFormExt, Submit},
+        dismiss::DismissExt,
+        path_breadcrumb::{self, PathBreadcrumb},
+    },
+};
+
+pub trait GoToDialog: Render {
+    /// Shows `prefix` of the remote, in the active tab when it browses there, and
+    /// scrolls to the object at `reveal`
+    fn go_to(
+        &mut self,
+        remote: S3Remote,
+        prefix: SharedString,
+        reveal: Option<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+}
+
+/// Opens a location the dialog resolved to
+type Jump = Rc<dyn Fn(S3Remote, SharedString, Option<Arc<str>>, &mut Window, &mut App)>;
+
+/// Asks for a location to open: an `s3://` URI, an object or console URL, or a key of
+/// the remote browsed in the active tab. Pasted URLs on the clipboard are filled in.
+pub fn open_dialog<T: GoToDialog>(
+    entity: WeakEntity<T>,
+    remotes: Vec<S3Remote>,
+    current: Option<S3Remote>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let pasted = cx
+        .read_from_clipboard()
+        .and_then(|item| item.text())
+        .filter(|text| text.contains("://") && resolve(text, &remotes, None).is_ok())
+        .unwrap_or_default();
+    let input = cx.new(|cx| {
+        InputState::new(window, cx)
+            .placeholder(t!("go_to.placeholder"))
+            .default_value(pasted)
+    });
+
+    let jump: Jump = Rc::new(move |remote, prefix, reveal, window, cx| {
+        window.close_dialog(cx);
+        let _ = entity.update(cx, |this, cx| {
+            this.go_to(remote, prefix, reveal, window, cx)
+        });
+    });
+    let remotes = Rc::new(remotes);
+    let submit = submit(
+        jump.clone(),
+        input.clone(),
+        remotes.clone(),
+        current.clone(),
+    );
+    dialog_form::bind_fields(&[input.clone()], submit.clone(), window, cx);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(
+            dialog,
+            input.clone(),
+            &remotes,
+            current.as_ref(),
+            jump.clone(),
+            submit.clone(),
+            cx,
+        )
+    });
+}
+
+/// Where the input leads
+#[derive(Clone)]
+struct Location {
+    remote: S3Remote,
+    folder: SharedString,
+    /// Key of the file it names, shown in `folder`
+    object: Option<Arc<str>>,
+}
+
+/// Remote and folder the input leads to, the folder of keys naming a file. Otherwise
+/// why it leads nowhere.
+fn resolve(
+    input: &str,
+    remotes: &[S3Remote],
+    current: Option<&S3Remote>,
+) -> Result<Location, SharedString> {
+    let configs = remotes
+        .iter()
+        .map(|remote| (&remote.remote_name, &remote.config));
+    let Some((matched, key)) = balti_s3::parse_object_locator(input, configs) else {
+        return Err(t!("go_to.unrecognized").into());
+    };
+
+    let remote = match matched {
+        RemoteMatch::Remote(remote_name) => remotes
+            .iter()
+            .find(|remote| remote.remote_name == remote_name)
+            .cloned()
+            .ok_or_else(|| SharedString::new(t!("go_to.remote_gone", remote = remote_name)))?,
+        RemoteMatch::Current => {
+            let remote = current.ok_or_else(|| SharedString::new(t!("go_to.no_current_remote")))?;
+            remote
+                .check_scope(&key)
+                .map_err(|err| SharedString::new(err.message))?;
+            remote.clone()
+        }
+        RemoteMatch::Bucket { bucket, .. } => {
+            return Err(t!("go_to.unknown_bucket", bucket = bucket).into());
+        }
+    };
+
+    let folder = match key.rfind('/') {
+        Some(at) => SharedString::new(&key[..=at]),
+        None => SharedString::new_static("/"),
+    };
+    let object = (!key.is_empty() && !key.ends_with('/')).then(|| Arc::from(key));
+    Ok(Location {
+        remote,
+        folder,
+        object,
+    })
+}
+
+fn submit(
+    jump: Jump,
+    input: Entity<InputState>,
+    remotes: Rc<Vec<S3Remote>>,
+    current: Option<S3Remote>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let Ok(location) = resolve(&input.read(cx).value(), &remotes, current.as_ref()) else {
+            return;
+        };
+
+        jump(
+            location.remote,
+            location.folder,
+            location.object,
+            window,
+            cx,
+        );
+    })
+}
+
+fn dialog(
+    dialog: Dialog,
+    input: Entity<InputState>,
+    remotes: &[S3Remote],
+    current: Option<&S3Remote>,
+    jump: Jump,
+    submit: Submit,
+    cx: &mut App,
+) -> Dialog {
+    let value = input.read(cx).value();
+    let resolved = (!value.trim().is_empty()).then(|| resolve(&value, remotes, current));
+    let is_valid = matches!(resolved, Some(Ok(_)));
+
+    // every folder on the way is a click away
+    let breadcrumb = match &resolved {
+        Some(Ok(location)) if location.folder != "/" || location.object.is_some() => {
+            let key = location
+                .object
+                .as_deref()
+                .unwrap_or(&location.folder)
+                .to_owned();
+            let Location {
+                remote,
+                folder,
+                object,
+            } = location.clone();
+            let breadcrumb = PathBreadcrumb::key("go-to-path", &key)
+                .flex_wrap()
+                .on_click(move |depth, window, cx| {
+                    match path_breadcrumb::key_prefix_at(&key, depth) {
+                        prefix if prefix.ends_with('/') => {
+                            jump(remote.clone(), prefix.to_owned().into(), None, window, cx)
+                        }
+                        _ => jump(remote.clone(), folder.clone(), object.clone(), window, cx),
+                    }
+                });
+            Some(breadcrumb)
+        }
+        _ => None,
+    };
+
+    dialog
+        .alert()
+        .cancel_on_escape(false, |_cx| false)
+        .submit_on_enter(submit.clone())
+        .rounded_lg()
+        .title(t!("go_to.title"))
+        .v_flex()
+        .child(
+            v_form().child(
+                field()
+                    .label(t!("go_to.location"))
+                    .child(Input::new(&input).cleanable(true))
+                    .map(|this| match resolved {
+                        Some(Err(message)) => {
+                            this.child(div().text_sm().text_color(cx.theme().danger).child(message))
+                        }
+                        Some(Ok(Location {
+                            remote,
+                            object: Some(key),
+                            ..
+                        })) => this.description(t!(
+                            "go_to.reveals",
+                            key = key,
+                            remote = remote.remote_name
+                        )),
+                        Some(Ok(Location { remote, folder, .. })) if folder == "/" => {
+                            this.description(t!("go_to.opens_remote", remote = remote.remote_name))
+                        }
+                        Some(Ok(Location { remote, folder, .. })) => this.description(t!(
+                            "go_to.opens_prefix",
+                            prefix = folder,
+                            remote = remote.remote_name
+                        )),
+                        None => this.description(t!("go_to.hint")),
+                    }),
+            ),
+        )
+        .when_some(breadcrumb, |this, breadcrumb| {
+            this.child(
+                v_flex()
+                    .gap_1()
+                    .text_sm()
+                    .child(
+                        div()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(t!("go_to.jump_to")),
+                    )
+                    .child(breadcrumb),
+            )
+        })
+        .footer(move |_, _, _, _cx| {
+            let submit = submit.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("go_to.go"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
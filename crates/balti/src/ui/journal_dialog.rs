@@ -0,0 +1,325 @@
+use std::{cell::RefCell, rc::Rc};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, Selectable, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    dialog::Dialog,
+    form::{field, v_form},
+    h_flex,
+    input::{Input, InputState},
+    notification::Notification,
+    v_flex,
+};
+
+use crate::{
+    config,
+    journal::{self, ACTIONS, Action, ExportFormat, JournalFilter},
+    rt,
+    strings::t,
+    ui::{
+        dialog_form::{self, DialogFormExt, Submit},
+        error_notification,
+    },
+};
+
+/// Name the export is saved under unless changed, before its extension
+const EXPORT_FILE_STEM: &str = "balti-journal";
+
+/// Picks of the dialog, kept across renders
+struct Choice {
+    /// Remote the entries are narrowed to, all of them for `None`
+    remote: Option<String>,
+    action: Option<Action>,
+    format: ExportFormat,
+}
+
+/// Fields of the dialog
+#[derive(Clone)]
+struct Inputs {
+    since: Entity<InputState>,
+    until: Entity<InputState>,
+    /// Id of a bulk operation, as its report names it
+    operation: Entity<InputState>,
+}
+
+/// Asks which journal entries to export and where to, `remotes` are offered as filters
+pub fn open_dialog(remotes: Vec<String>, window: &mut Window, cx: &mut App) {
+    let since_input = cx.new(|cx| InputState::new(window, cx).placeholder("YYYY-MM-DD"));
+    let until_input = cx.new(|cx| InputState::new(window, cx).placeholder("YYYY-MM-DD"));
+    let operation_input = cx.new(|cx| InputState::new(window, cx));
+    let choice = Rc::new(RefCell::new(Choice {
+        remote: None,
+        action: None,
+        format: ExportFormat::Jsonl,
+    }));
+
+    let inputs = Inputs {
+        since: since_input,
+        until: until_input,
+        operation: operation_input,
+    };
+    let submit = submit(choice.clone(), inputs.clone());
+    dialog_form::bind_fields(
+        &[
+            inputs.since.clone(),
+            inputs.until.clone(),
+            inputs.operation.clone(),
+        ],
+        submit.clone(),
+        window,
+        cx,
+    );
+
+    let remotes = Rc::new(remotes);
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(
+            dialog,
+            &remotes,
+            choice.clone(),
+            inputs.clone(),
+            submit.clone(),
+            cx,
+        )
+    });
+}
+
+/// Day typed into `input`, `None` when left empty and an error when it isn't a date
+fn entered_day(input: &Entity<InputState>, cx: &App) -> Result<Option<NaiveDate>, ()> {
+    let value = input.read(cx).value();
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| ())
+}
+
+fn start_of(day: NaiveDate) -> DateTime<Utc> {
+    day.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// Filter the dialog describes, `None` while a date is invalid or the range is empty.
+/// Both days are included.
+fn entered_filter(choice: &Choice, inputs: &Inputs, cx: &App) -> Option<JournalFilter> {
+    let since = entered_day(&inputs.since, cx).ok()?;
+    let until = entered_day(&inputs.until, cx).ok()?;
+    if let (Some(since), Some(until)) = (since, until)
+        && since > until
+    {
+        return None;
+    }
+    let operation = inputs.operation.read(cx).value();
+    let operation = operation.trim();
+
+    Some(JournalFilter {
+        remote: choice.remote.clone(),
+        action: choice.action,
+        operation: (!operation.is_empty()).then(|| operation.to_owned()),
+        since: since.map(start_of),
+        until: until.and_then(|until| until.succ_opt()).map(start_of),
+    })
+}
+
+/// Asks where to save the entries the dialog describes and writes them there
+fn submit(choice: Rc<RefCell<Choice>>, inputs: Inputs) -> Submit {
+    Rc::new(move |window, cx| {
+        let Some(filter) = entered_filter(&choice.borrow(), &inputs, cx) else {
+            return;
+        };
+        let format = choice.borrow().format;
+        window.close_dialog(cx);
+
+        let directory = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let name = format!("{EXPORT_FILE_STEM}.{}", format.extension());
+        let path_prompt = cx.prompt_for_new_path(&directory, Some(&name));
+
+        window
+            .spawn(cx, async move |cx| {
+                let Ok(Ok(Some(path))) = path_prompt.await else {
+                    return;
+                };
+                let export_path = path.clone();
+                // multi-month journals take a while to read through
+                let Ok(task) = cx.update(|_window, cx| {
+                    rt::spawn_blocking(cx, move || {
+                        journal::export_to(&config::journal_dir(), &export_path, &filter, format)
+                    })
+                }) else {
+                    return;
+                };
+                let result = task.await.flatten();
+
+                let _ = cx.update(|window, cx| {
+                    let notification = match result {
+                        Ok(count) => Notification::success(t!(
+                            "journal.exported",
+                            count = count,
+                            path = path.display()
+                        )),
+                        Err(err) => error_notification(&err).title(t!("journal.export_failed")),
+                    };
+                    window.push_notification(notification, cx);
+                });
+            })
+            .detach();
+    })
+}
+
+fn action_label(action: Option<Action>) -> String {
+    match action {
+        None => t!("journal.all"),
+        Some(Action::Delete) => t!("journal.delete"),
+        Some(Action::Rename) => t!("journal.rename"),
+        Some(Action::Copy) => t!("journal.copy"),
+        Some(Action::Move) => t!("journal.move"),
+        Some(Action::Duplicate) => t!("journal.duplicate"),
+        Some(Action::CreateFolder) => t!("journal.create_folder"),
+        Some(Action::Upload) => t!("journal.upload"),
+        Some(Action::Undo) => t!("journal.undo"),
+    }
+}
+
+fn dialog(
+    dialog: Dialog,
+    remotes: &[String],
+    choice: Rc<RefCell<Choice>>,
+    inputs: Inputs,
+    submit: Submit,
+    cx: &mut App,
+) -> Dialog {
+    let muted = cx.theme().muted_foreground;
+    let current = choice.borrow();
+    let since_valid = entered_day(&inputs.since, cx).is_ok();
+    let until_valid = entered_day(&inputs.until, cx).is_ok();
+    let is_valid = entered_filter(&current, &inputs, cx).is_some();
+
+    let remote_buttons = std::iter::once(None)
+        .chain(remotes.iter().cloned().map(Some))
+        .enumerate()
+        .map(|(i, remote)| {
+            let choice = choice.clone();
+            Button::new(("journal-remote", i))
+                .small()
+                .outline()
+                .label(remote.clone().unwrap_or_else(|| t!("journal.all")))
+                .selected(current.remote == remote)
+                .on_click(move |_ev, window, _cx| {
+                    choice.borrow_mut().remote = remote.clone();
+                    window.refresh();
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let action_buttons = std::iter::once(None)
+        .chain(ACTIONS.into_iter().map(Some))
+        .enumerate()
+        .map(|(i, action)| {
+            let choice = choice.clone();
+            Button::new(("journal-action", i))
+                .small()
+                .outline()
+                .label(action_label(action))
+                .selected(current.action == action)
+                .on_click(move |_ev, window, _cx| {
+                    choice.borrow_mut().action = action;
+                    window.refresh();
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let format_buttons = [(ExportFormat::Jsonl, "JSONL"), (ExportFormat::Csv, "CSV")]
+        .into_iter()
+        .map(|(format, label)| {
+            let choice = choice.clone();
+            Button::new(label)
+                .small()
+                .outline()
+                .label(label)
+                .selected(current.format == format)
+                .on_click(move |_ev, window, _cx| {
+                    choice.borrow_mut().format = format;
+                    window.refresh();
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let date_field = |label: String, input: &Entity<InputState>, valid: bool| {
+        field()
+            .label(label)
+            .child(Input::new(input).cleanable(true))
+            .when(!valid, |this| this.description(t!("journal.invalid_date")))
+    };
+
+    dialog
+        .submit_on_enter(submit.clone())
+        .rounded_lg()
+        .w(px(520.))
+        .title(t!("journal.title"))
+        .v_flex()
+        .gap_3()
+        .child(
+            div()
+                .text_sm()
+                .text_color(muted)
+                .child(t!("journal.detail")),
+        )
+        .child(
+            v_flex()
+                .gap_1()
+                .child(div().text_sm().font_medium().child(t!("journal.remote")))
+                .child(h_flex().flex_wrap().gap_2().children(remote_buttons)),
+        )
+        .child(
+            v_flex()
+                .gap_1()
+                .child(div().text_sm().font_medium().child(t!("journal.action")))
+                .child(h_flex().flex_wrap().gap_2().children(action_buttons)),
+        )
+        .child(
+            v_form()
+                .child(date_field(t!("journal.since"), &inputs.since, since_valid))
+                .child(date_field(t!("journal.until"), &inputs.until, until_valid))
+                .child(
+                    field()
+                        .label(t!("journal.operation"))
+                        .child(Input::new(&inputs.operation).cleanable(true))
+                        .description(t!("journal.operation_hint")),
+                ),
+        )
+        .when(since_valid && until_valid && !is_valid, |this| {
+            this.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().danger)
+                    .child(t!("journal.empty_range")),
+            )
+        })
+        .child(
+            v_flex()
+                .gap_1()
+                .child(div().text_sm().font_medium().child(t!("journal.format")))
+                .child(h_flex().gap_2().children(format_buttons)),
+        )
+        .footer(move |_, _, _, _cx| {
+            let submit = submit.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("journal.export"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
@@ -0,0 +1,231 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use balti_s3::{__S3Object, S3Object};
+use futures::channel::oneshot;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    dialog::Dialog,
+    h_flex,
+};
+
+use crate::{junk::JunkReason, strings::t, util};
+
+/// Matches show in a list this tall
+const LIST_HEIGHT: Pixels = px(320.);
+const ITEM_HEIGHT: Pixels = px(44.);
+
+/// Object the scan matched, deleted while `included`
+struct Found {
+    object: S3Object,
+    reason: JunkReason,
+    size: i64,
+    included: bool,
+}
+
+/// Matches under review, the dialog keeps them across renders
+struct Review {
+    prefix: Arc<str>,
+    found: Vec<Found>,
+    /// Answers [`review`] once the delete goes ahead
+    answer: Option<oneshot::Sender<Vec<S3Object>>>,
+}
+
+impl Review {
+    /// Objects left in and the bytes deleting them frees
+    fn included(&self) -> (usize, i64) {
+        self.found
+            .iter()
+            .filter(|found| found.included)
+            .fold((0, 0), |(count, bytes), found| (count + 1, bytes + found.size))
+    }
+}
+
+/// Lists the junk found below `prefix`, every match starts out included and can be left
+/// out. Resolves to the objects to delete, `None` when cancelled or dismissed.
+pub fn review(
+    prefix: Arc<str>,
+    found: Vec<(S3Object, JunkReason)>,
+    window: &mut Window,
+    cx: &mut App,
+) -> impl Future<Output = Option<Vec<S3Object>>> + use<> {
+    let (answer_tx, answer) = oneshot::channel();
+    let found = found
+        .into_iter()
+        .map(|(object, reason)| {
+            let size = match object.as_ref() {
+                __S3Object::File { size, .. } => *size,
+                __S3Object::Folder(_) => 0,
+            };
+            Found {
+                object,
+                reason,
+                size,
+                included: true,
+            }
+        })
+        .collect();
+    let review = Rc::new(RefCell::new(Review {
+        prefix,
+        found,
+        answer: Some(answer_tx),
+    }));
+    // dismissing drops the dialog along with the sender, which answers `None`
+    window.open_dialog(cx, move |dialog_, _window, cx| {
+        dialog(dialog_, review.clone(), cx)
+    });
+
+    async move { answer.await.ok() }
+}
+
+fn reason_label(reason: &JunkReason) -> String {
+    match reason {
+        JunkReason::Pattern(glob) => t!("junk.reason_pattern", pattern = glob),
+        JunkReason::Empty => t!("junk.reason_empty"),
+    }
+}
+
+fn dialog(dialog: Dialog, review: Rc<RefCell<Review>>, cx: &mut App) -> Dialog {
+    let border = cx.theme().sidebar_border;
+    let muted = cx.theme().muted_foreground;
+
+    let state = review.borrow();
+    let total = state.found.len();
+    let (count, bytes) = state.included();
+    let title = t!("junk.title", count = total);
+    let prefix = match state.prefix.as_ref() {
+        "" => "/".to_owned(),
+        prefix => prefix.to_owned(),
+    };
+    drop(state);
+
+    let _review = review.clone();
+    let toggle_all = Checkbox::new("junk-toggle-all")
+        .label(t!("junk.include_all"))
+        .checked(count == total)
+        .on_click(move |checked, window, _cx| {
+            for found in _review.borrow_mut().found.iter_mut() {
+                found.included = *checked;
+            }
+            window.refresh();
+        });
+
+    let _review = review.clone();
+    let items = uniform_list("junk-items", total, move |range, _window, cx| {
+        let review = _review.clone();
+        let state = _review.borrow();
+
+        range
+            .map(|index| {
+                let found = &state.found[index];
+                let review = review.clone();
+                let key = found.object.key();
+                let path = key.strip_prefix(state.prefix.as_ref()).unwrap_or(key);
+
+                h_flex()
+                    .h(ITEM_HEIGHT)
+                    .gap_3()
+                    .px_2()
+                    .border_b_1()
+                    .border_color(cx.theme().sidebar_border)
+                    .text_sm()
+                    .child(
+                        Checkbox::new(("junk-item", index))
+                            .checked(found.included)
+                            .on_click(move |checked, window, _cx| {
+                                review.borrow_mut().found[index].included = *checked;
+                                window.refresh();
+                            }),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .flex_1()
+                            .min_w_0()
+                            .child(
+                                div()
+                                    .font_medium()
+                                    .truncate()
+                                    .child(SharedString::new(path)),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(reason_label(&found.reason)),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(util::human_readable_size(found.size)),
+                    )
+            })
+            .collect()
+    })
+    .h(LIST_HEIGHT);
+
+    dialog
+        .rounded_lg()
+        .w(px(640.))
+        .title(title)
+        .v_flex()
+        .gap_2()
+        .child(
+            div()
+                .text_sm()
+                .text_color(muted)
+                .child(t!("junk.detail", prefix = prefix)),
+        )
+        .child(
+            h_flex()
+                .justify_between()
+                .child(toggle_all)
+                .child(div().text_sm().child(t!(
+                    "junk.reclaimable",
+                    count = count,
+                    size = util::human_readable_size(bytes)
+                ))),
+        )
+        .child(
+            div()
+                .border_1()
+                .border_color(border)
+                .rounded_md()
+                .child(items),
+        )
+        .footer(move |_, _, _, _cx| {
+            let review = review.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .danger()
+                .label(t!("junk.delete", count = count))
+                .disabled(count == 0)
+                .on_click(move |_ev, window, cx| {
+                    let mut review = review.borrow_mut();
+                    let approved = review
+                        .found
+                        .iter()
+                        .filter(|found| found.included)
+                        .map(|found| found.object.clone())
+                        .collect();
+                    if let Some(answer) = review.answer.take() {
+                        let _ = answer.send(approved);
+                    }
+                    drop(review);
+                    window.close_dialog(cx);
+                });
+
+            vec![cancel, ok]
+        })
+}
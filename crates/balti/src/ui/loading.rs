@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{ActiveTheme, Icon, IconName, button::Button, skeleton::Skeleton};
+
+use crate::settings::Settings;
+
+/// Spinning `icon`, left still when motion is reduced
+pub fn loading_indicator(id: impl Into<ElementId>, icon: Icon, cx: &App) -> AnyElement {
+    if Settings::get(cx).reduce_motion {
+        return icon.into_any_element();
+    }
+
+    icon.with_animation(
+        id,
+        Animation::new(Duration::from_secs(1)).repeat(),
+        |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
+    )
+    .into_any_element()
+}
+
+/// Placeholder row while a listing loads, without the pulse when motion is reduced
+pub fn skeleton(height: Pixels, cx: &App) -> AnyElement {
+    if Settings::get(cx).reduce_motion {
+        div()
+            .w_full()
+            .h(height)
+            .rounded_md()
+            .bg(cx.theme().skeleton)
+            .into_any_element()
+    } else {
+        Skeleton::new()
+            .w_full()
+            .h(height)
+            .rounded_md()
+            .into_any_element()
+    }
+}
+
+pub trait LoadingExt {
+    /// Shows the loader while `loading`, callers still disable the button themselves
+    fn loading_state(self, loading: bool, cx: &App) -> Self;
+}
+
+impl LoadingExt for Button {
+    fn loading_state(self, loading: bool, cx: &App) -> Self {
+        if Settings::get(cx).reduce_motion {
+            // the button's own loading state always spins
+            self.when(loading, |this| this.icon(IconName::LoaderCircle))
+        } else {
+            self.loading_icon(IconName::LoaderCircle).loading(loading)
+        }
+    }
+}
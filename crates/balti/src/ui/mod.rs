@@ -1,7 +1,16 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
-use balti_err::AppError;
-use balti_s3::{S3Config, S3Remote};
+use balti_err::{AppError, ErrorKind};
+use balti_s3::{EndpointUrl, RemoteTarget, S3Remote};
+use chrono::{DateTime, Utc};
 use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
     ActiveTheme, Icon, IconName, Root, Side, Sizable, ThemeMode, WindowExt,
@@ -11,21 +20,113 @@ use gpui_component::{
     notification::Notification,
     sidebar::{Sidebar, SidebarGroup, SidebarHeader, SidebarMenu, SidebarMenuItem},
     tab::{Tab, TabBar},
+    tooltip::Tooltip,
+    v_flex,
 };
 
-use crate::{config, nav::TabNav, rt, s3::S3RemoteManager, ui::remote::RemoteUi};
+use crate::{
+    about::Diagnostics,
+    capabilities::RemoteCapabilities,
+    changelog,
+    checkpoint::{self, Checkpoint},
+    clipboard::ClipboardMode,
+    config::{self, RemoteConfig},
+    credentials::{self, EXPIRY_WARNING, ExpiredRemotes},
+    health::{HealthEvent, RemoteHealth},
+    nav::{BrowsePrefix, PendingState, TabNav, TabStatus},
+    pending_reports::{PendingReport, PendingReports},
+    rt,
+    s3::S3RemoteManager,
+    settings::{Density, Settings, SettingsExport},
+    strings::t,
+    transfers::{self, Transfers},
+    ui::{
+        batch_report_dialog::{self, BatchOperation, BatchReportDialog},
+        browse::{self, BrowseUi, ClearSelection, DeleteSelection, DownloadSelection},
+        diff_dialog,
+        remote::{NavChangedEvent, OpenTabEvent, RemoteUi, SwitchRegionEvent},
+    },
+    update::{self, Release},
+    util,
+};
 
+mod about_dialog;
+mod batch_report_dialog;
 mod browse;
+mod bucket_info_dialog;
+mod bulk_confirm;
+mod conflict;
 mod create_folder_dialog;
 mod delete_object_dialog;
+mod dialog_form;
+mod diff_dialog;
+mod dismiss;
+mod go_to_dialog;
+mod journal_dialog;
+mod loading;
+mod object_properties_dialog;
+mod paste_plan_dialog;
+mod path_breadcrumb;
+mod presign_dialog;
 mod remote;
 mod remote_dialog;
+mod remote_snippet_dialog;
+mod rename_dialog;
+mod select_pattern_dialog;
+mod upload_options_dialog;
+mod usage_dialog;
+mod verify_dialog;
+mod whats_new_dialog;
 
 actions!([EmptyAction]);
 
-actions!(window, [CloseWindow, Quit, About, CheckForUpdates]);
+actions!(
+    window,
+    [
+        CloseWindow,
+        Quit,
+        About,
+        WhatsNew,
+        CheckForUpdates,
+        ExportSettings,
+        ImportSettings,
+        ExportJournal,
+        ZoomIn,
+        ZoomOut,
+        ResetZoom,
+        Minimize,
+        ZoomWindow,
+        ToggleFullScreen,
+        ToggleReduceMotion,
+        ToggleCompactDensity,
+        ShowUsage,
+        GoToLocation,
+        CopyObjects,
+        CutObjects,
+        PasteObjects,
+        UndoLast
+    ]
+);
 pub const APP_CONTEXT: &str = "Rooter";
 
+/// Longest location shown in the window title before its middle is cut out
+const TITLE_LOCATION_CHARS: usize = 64;
+
+/// Name settings are exported under unless changed
+const SETTINGS_EXPORT_FILE_NAME: &str = "balti-settings.toml";
+
+/// How often credential expiries are checked, also keeps the sidebar countdowns current
+const EXPIRY_TICK: Duration = Duration::from_secs(30);
+
+/// How often degraded remotes are checked for a due probe, also ticks retry countdowns
+const HEALTH_TICK: Duration = Duration::from_secs(1);
+
+/// Interrupted downloads are offered once per launch, not again by every new window
+static RESUME_OFFERED: AtomicBool = AtomicBool::new(false);
+
+/// Reports the last session left are offered once per launch too
+static REPORTS_OFFERED: AtomicBool = AtomicBool::new(false);
+
 fn init_kb(cx: &mut App) {
     #[cfg(target_os = "macos")]
     cx.bind_keys([KeyBinding::new("cmd-w", CloseWindow, Some(APP_CONTEXT))]);
@@ -38,24 +139,131 @@ fn init_kb(cx: &mut App) {
 
     #[cfg(not(target_os = "macos"))]
     cx.bind_keys([KeyBinding::new("alt-f4", Quit, Some(APP_CONTEXT))]);
+
+    #[cfg(target_os = "macos")]
+    cx.bind_keys([
+        KeyBinding::new("cmd-=", ZoomIn, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-+", ZoomIn, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd--", ZoomOut, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-0", ResetZoom, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-c", CopyObjects, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-x", CutObjects, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-v", PasteObjects, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-z", UndoLast, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-l", GoToLocation, Some(APP_CONTEXT)),
+        KeyBinding::new("cmd-m", Minimize, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-cmd-f", ToggleFullScreen, Some(APP_CONTEXT)),
+        KeyBinding::new(
+            "cmd-backspace",
+            DeleteSelection,
+            Some(browse::SELECTION_CONTEXT),
+        ),
+        KeyBinding::new("cmd-d", DownloadSelection, Some(browse::SELECTION_CONTEXT)),
+    ]);
+
+    #[cfg(not(target_os = "macos"))]
+    cx.bind_keys([
+        KeyBinding::new("ctrl-=", ZoomIn, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-+", ZoomIn, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl--", ZoomOut, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-0", ResetZoom, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-c", CopyObjects, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-x", CutObjects, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-v", PasteObjects, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-z", UndoLast, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-l", GoToLocation, Some(APP_CONTEXT)),
+        KeyBinding::new("f11", ToggleFullScreen, Some(APP_CONTEXT)),
+        KeyBinding::new("ctrl-d", DownloadSelection, Some(browse::SELECTION_CONTEXT)),
+    ]);
+
+    // only bound while objects are checked in the focused listing. Text fields and
+    // dialogs hold the focus themselves, so their Delete and Escape still come first.
+    cx.bind_keys([
+        KeyBinding::new("delete", DeleteSelection, Some(browse::SELECTION_CONTEXT)),
+        KeyBinding::new("escape", ClearSelection, Some(browse::SELECTION_CONTEXT)),
+    ]);
+}
+
+/// Notification of an error reaching the user, which is where it gets logged
+fn error_notification(err: &AppError) -> Notification {
+    err.log();
+    Notification::error(err.message.clone())
+}
+
+/// [`error_notification`] for errors the user can work around, logged the same way
+fn warning_notification(err: &AppError) -> Notification {
+    err.log();
+    Notification::warning(err.message.clone())
+}
+
+/// Error for a bucket in another region, with an action moving the remote to `region`
+fn wrong_region_notification(
+    err: &AppError,
+    region: Arc<str>,
+    on_switch: impl Fn(&mut Window, &mut App) + 'static,
+) -> Notification {
+    err.log();
+    let on_switch = Rc::new(on_switch);
+
+    Notification::warning(err.message.clone())
+        .title(t!("rooter.wrong_region"))
+        .autohide(false)
+        .action(move |_this, _window, cx| {
+            let on_switch = on_switch.clone();
+            Button::new("switch_region")
+                .primary()
+                .small()
+                .label(t!("rooter.switch_region", region = region))
+                .on_click(cx.listener(move |this, _ev, window, cx| {
+                    on_switch(window, cx);
+                    this.dismiss(window, cx);
+                }))
+        })
 }
 
 pub struct Rooter {
     s3_remote_manager: Entity<S3RemoteManager>,
     tab_nav: TabNav,
+    /// Remotes already warned about their credentials running out
+    expiry_warned: HashSet<Arc<str>>,
+    /// Remotes whose unencrypted connection badge was dismissed
+    plain_http_dismissed: HashSet<Arc<str>>,
+    /// Last title set on the OS window
+    window_title: String,
 
     focus_handle: FocusHandle,
     is_testing: bool,
+    is_fetching_buckets: bool,
+    /// Restored report the batch dialog shows
+    reviewing_report: Option<PendingReport>,
+    /// Newer release the banner suggests, until dismissed
+    update_banner: Option<Release>,
+    _expiry_task: Task<()>,
+    _health_task: Task<()>,
 }
 
 impl Rooter {
-    fn new(focus_handle: FocusHandle, _window: &mut Window, cx: &mut Context<Self>) -> Self {
-        let s3_remote_manager = cx.new(|_cx| S3RemoteManager::empty());
+    fn new(focus_handle: FocusHandle, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let s3_remote_manager =
+            cx.new(|cx| S3RemoteManager::empty(Settings::get(cx).proxy.clone()));
         let tab_nav = TabNav::new();
 
         let win_s3 = s3_remote_manager.clone();
         cx.on_window_closed(move |cx| {
-            win_s3.read(cx).save_remotes();
+            win_s3.update(cx, |s3, _cx| match s3.review_save() {
+                // nobody's left to review it, the config stays as it is
+                Ok(Some(_)) => {
+                    tracing::warn!("Left remotes config as is, saving would overwrite edits");
+                }
+                Ok(None) => match s3.save_remotes() {
+                    Ok(conflicts) if !conflicts.is_empty() => {
+                        tracing::warn!("Kept this window's version of {}", conflicts.join(", "));
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::error!("Failed to save remotes: {}", err.message),
+                },
+                Err(err) => tracing::error!("Failed to save remotes: {}", err.message),
+            });
 
             if cx.windows().is_empty() {
                 cx.quit();
@@ -63,11 +271,74 @@ impl Rooter {
         })
         .detach();
 
+        // the sidebar shows what runs against each remote
+        cx.observe(&Transfers::entity(cx), |_this, _transfers, cx| cx.notify())
+            .detach();
+        cx.observe(&ExpiredRemotes::entity(cx), |_this, _expired, cx| {
+            cx.notify()
+        })
+        .detach();
+        cx.observe(&RemoteHealth::entity(cx), |_this, _health, cx| cx.notify())
+            .detach();
+        cx.subscribe_in(
+            &RemoteHealth::entity(cx),
+            window,
+            |_this, _health, event: &HealthEvent, window, cx| {
+                if let HealthEvent::Recovered(remote) = event {
+                    window.push_notification(
+                        Notification::success(t!("rooter.back_online_detail"))
+                            .title(t!("rooter.back_online", remote = remote)),
+                        cx,
+                    );
+                }
+            },
+        )
+        .detach();
+
+        let expiry_task = cx.spawn_in(window, async move |this, cx| {
+            loop {
+                cx.background_executor().timer(EXPIRY_TICK).await;
+                let checked = this.update_in(cx, |this, window, cx| {
+                    this.check_credentials_expiry(window, cx);
+                });
+                if checked.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let health_task = cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(HEALTH_TICK).await;
+                let probed = this.update(cx, |this, cx| {
+                    let remotes = this
+                        .s3_remote_manager
+                        .read(cx)
+                        .remotes()
+                        .values()
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    RemoteHealth::probe_degraded(&remotes, cx);
+                });
+                if probed.is_err() {
+                    break;
+                }
+            }
+        });
+
         Self {
             s3_remote_manager,
             tab_nav,
+            expiry_warned: HashSet::new(),
+            plain_http_dismissed: HashSet::new(),
+            window_title: String::new(),
             focus_handle,
             is_testing: false,
+            is_fetching_buckets: false,
+            reviewing_report: None,
+            update_banner: None,
+            _expiry_task: expiry_task,
+            _health_task: health_task,
         }
     }
 
@@ -90,50 +361,598 @@ impl Rooter {
                 this.s3_remote_manager.update(cx, |s3, cx| {
                     if let Err(err) = s3.parse() {
                         window.push_notification(
-                            Notification::error(err.message)
-                                .title("Failed to init s3 remotes")
+                            error_notification(&err)
+                                .title(t!("rooter.init_remotes_failed"))
                                 .autohide(false),
                             cx,
                         );
                     }
                     cx.notify();
                 });
+                this.check_credentials_expiry(window, cx);
+                this.show_whats_new_after_update(window, cx);
+                this.check_for_update_on_start(cx);
+                this.offer_resume_downloads(window, cx);
+                if !REPORTS_OFFERED.swap(true, Ordering::Relaxed) {
+                    this.offer_pending_reports(window, cx);
+                }
                 cx.notify();
             });
         })
         .detach();
     }
 
+    /// Offers to continue the folder downloads a previous run left unfinished
+    fn offer_resume_downloads(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if RESUME_OFFERED.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let entity = cx.weak_entity();
+        for checkpoint in checkpoint::load_all(&config::checkpoints_dir()) {
+            let detail = if checkpoint.listed_all {
+                t!(
+                    "rooter.download_interrupted_detail",
+                    name = checkpoint.name(),
+                    remote = checkpoint.remote,
+                    done = checkpoint.completed,
+                    count = checkpoint.listed
+                )
+            } else {
+                t!(
+                    "rooter.download_interrupted_listing",
+                    name = checkpoint.name(),
+                    remote = checkpoint.remote
+                )
+            };
+            let entity = entity.clone();
+
+            let notification = Notification::info(detail)
+                .id1::<Checkpoint>(SharedString::new(checkpoint.id.clone()))
+                .title(t!("rooter.download_interrupted"))
+                .autohide(false)
+                .action(move |_this, _window, cx| {
+                    let checkpoint = checkpoint.clone();
+                    let entity = entity.clone();
+                    Button::new("resume_download")
+                        .primary()
+                        .small()
+                        .label(t!("browse.resume_download"))
+                        .on_click(cx.listener(move |this, _ev, window, cx| {
+                            let _ = entity.update(cx, |rooter, cx| {
+                                rooter.resume_download(checkpoint.clone(), window, cx)
+                            });
+                            this.dismiss(window, cx);
+                        }))
+                });
+            window.push_notification(notification, cx);
+        }
+    }
+
+    /// Opens the folder of an interrupted download in a new tab and continues it there
+    fn resume_download(
+        &mut self,
+        checkpoint: Checkpoint,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let s3_remote = self
+            .s3_remote_manager
+            .read(cx)
+            .remotes()
+            .get(checkpoint.remote.as_str())
+            .cloned();
+        let Some(s3_remote) = s3_remote else {
+            window.push_notification(
+                Notification::error(t!("rooter.resume_remote_gone", remote = checkpoint.remote)),
+                cx,
+            );
+            return;
+        };
+
+        self.new_tab(
+            s3_remote,
+            SharedString::new(checkpoint.prefix.clone()),
+            window,
+            cx,
+        );
+        if let Some(browse) = self.active_browse(cx) {
+            browse.update(cx, |browse, cx| browse.resume_zip(checkpoint, window, cx));
+        }
+    }
+
+    /// Offers the reports the last session quit with, one at a time
+    fn offer_pending_reports(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let count = PendingReports::restored(cx).len();
+        if count == 0 {
+            return;
+        }
+
+        let entity = cx.weak_entity();
+        let notification =
+            Notification::warning(t!("rooter.pending_reports_detail", count = count))
+                .id::<PendingReports>()
+                .title(t!("rooter.pending_reports"))
+                .autohide(false)
+                .action(move |_this, _window, cx| {
+                    let entity = entity.clone();
+                    Button::new("review_report")
+                        .primary()
+                        .small()
+                        .label(t!("rooter.review_report"))
+                        .on_click(cx.listener(move |this, _ev, window, cx| {
+                            let _ =
+                                entity.update(cx, |rooter, cx| rooter.review_report(window, cx));
+                            this.dismiss(window, cx);
+                        }))
+                });
+        window.push_notification(notification, cx);
+    }
+
+    /// Opens the batch dialog of the oldest restored report
+    fn review_report(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(report) = PendingReports::restored(cx).first().cloned() else {
+            return;
+        };
+        let operation = match report.mode() {
+            None => BatchOperation::Delete,
+            Some(ClipboardMode::Copy) => BatchOperation::Copy,
+            Some(ClipboardMode::Cut) => BatchOperation::Move,
+        };
+
+        let entity = cx.weak_entity();
+        let _report = report.clone();
+        let dismiss = Rc::new(move |window: &mut Window, cx: &mut App| {
+            PendingReports::dismiss(&_report, cx);
+            let _ = entity.update(cx, |this, cx| {
+                this.reviewing_report = None;
+                this.offer_pending_reports(window, cx);
+            });
+        });
+
+        batch_report_dialog::show_restored(
+            operation,
+            report.report(),
+            dismiss,
+            cx.weak_entity(),
+            window,
+            cx,
+        );
+        self.reviewing_report = Some(report);
+    }
+
+    /// Opens a tab of the report's remote and queues the `keys` again there
+    fn retry_report(
+        &mut self,
+        report: PendingReport,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remotes = self.s3_remote_manager.read(cx).remotes();
+        let from_name = report.paste.as_ref().map(|paste| paste.from.clone());
+        let gone = std::iter::once(&report.remote)
+            .chain(from_name.as_ref())
+            .find(|name| !remotes.contains_key(*name));
+        if let Some(remote) = gone {
+            window.push_notification(
+                Notification::error(t!("rooter.retry_remote_gone", remote = remote)),
+                cx,
+            );
+            return;
+        }
+        let s3_remote = remotes[&report.remote].clone();
+        let from = from_name.map(|name| remotes[&name].clone());
+
+        let root = SharedString::new(s3_remote.root());
+        self.new_tab(s3_remote, root, window, cx);
+        if let Some(browse) = self.active_browse(cx) {
+            browse.update(cx, |browse, cx| {
+                browse.retry_restored(&report, from, keys, window, cx)
+            });
+        }
+    }
+
+    /// Shows the bundled notes once after an update and remembers this version
+    fn show_whats_new_after_update(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let last_seen = config::parse_last_seen_version().unwrap_or_else(|err| {
+            tracing::warn!("Failed to read the last launched version: {}", err.message);
+            None
+        });
+
+        if changelog::is_update(last_seen.as_deref(), config::BALTI_VERSION)
+            && let Some(notes) = changelog::notes(config::BALTI_VERSION)
+        {
+            whats_new_dialog::open_dialog(config::BALTI_VERSION, notes, window, cx);
+        }
+
+        if last_seen.as_deref() != Some(config::BALTI_VERSION)
+            && let Err(err) = config::save_last_seen_version(config::BALTI_VERSION)
+        {
+            tracing::warn!("Failed to save the launched version: {}", err.message);
+        }
+    }
+
+    /// Looks for a newer release in the background, the banner suggests it once the app
+    /// is far enough behind. Failed checks, like when offline, stay quiet.
+    fn check_for_update_on_start(&mut self, cx: &mut Context<Self>) {
+        let settings = Settings::get(cx);
+        if !settings.check_for_updates {
+            return;
+        }
+        let threshold = settings.update_banner_minor_versions;
+        let proxy = settings.proxy.clone();
+        let task = rt::spawn(cx, update::latest_release(proxy));
+
+        cx.spawn(async move |this, cx| {
+            let release = match task.await.flatten() {
+                Ok(release) => release,
+                Err(err) => {
+                    tracing::debug!("Skipped the update banner: {}", err.message);
+                    return;
+                }
+            };
+            let dismissed = config::parse_dismissed_update().unwrap_or_else(|err| {
+                tracing::warn!("Failed to read the dismissed update: {}", err.message);
+                None
+            });
+            if update::should_nudge(
+                config::BALTI_VERSION,
+                &release,
+                threshold,
+                dismissed.as_deref(),
+            ) {
+                let _ = this.update(cx, |this, cx| {
+                    this.update_banner = Some(release);
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Degrades remotes whose credentials ran out and warns shortly before they do
+    fn check_credentials_expiry(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let now = Utc::now();
+        let remotes = self
+            .s3_remote_manager
+            .read(cx)
+            .remotes()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for remote in remotes {
+            let Some(expiry) = remote.config.credentials_expiry else {
+                continue;
+            };
+            if expiry <= now {
+                ExpiredRemotes::mark(remote.remote_name.clone(), cx);
+            } else if expiry - now <= EXPIRY_WARNING
+                && self.expiry_warned.insert(remote.remote_name.clone())
+            {
+                let notification = self.expiry_notification(remote, expiry, now, cx);
+                window.push_notification(notification, cx);
+            }
+        }
+
+        // the sidebar tooltips count down
+        cx.notify();
+    }
+
+    fn expiry_notification(
+        &self,
+        remote: S3Remote,
+        expiry: DateTime<Utc>,
+        now: DateTime<Utc>,
+        cx: &mut Context<Self>,
+    ) -> Notification {
+        let entity = cx.weak_entity();
+
+        Notification::warning(credentials::countdown(expiry, now))
+            .title(t!("rooter.remote_title", remote = remote.remote_name))
+            .autohide(false)
+            .action(move |_this, _window, cx| {
+                let remote = remote.clone();
+                let entity = entity.clone();
+                Button::new("update_credentials")
+                    .primary()
+                    .small()
+                    .label(t!("rooter.update_credentials"))
+                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                        remote_dialog::open_dialog(
+                            Some(remote.clone()),
+                            entity.clone(),
+                            window,
+                            cx,
+                        );
+                        this.dismiss(window, cx);
+                    }))
+            })
+    }
+
+    /// Forgets the warnings and what's known about the credentials of a remote once
+    /// it's replaced or deleted
+    fn reset_remote_state(&mut self, remote: &str, cx: &mut Context<Self>) {
+        self.expiry_warned.remove(remote);
+        self.plain_http_dismissed.remove(remote);
+        ExpiredRemotes::clear(remote, cx);
+        RemoteCapabilities::forget(remote, cx);
+        RemoteHealth::forget(remote, cx);
+    }
+
+    /// Rebuilds the remote's client from its saved config without editing it, for
+    /// temporary credentials renewed outside the app. Its tabs close like on an edit.
+    fn reload_credentials(
+        &mut self,
+        remote_name: Arc<str>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.tab_nav
+            .close_tab_by_remote(SharedString::new(remote_name.clone()), cx);
+        self.reset_remote_state(&remote_name, cx);
+        let reloaded = self
+            .s3_remote_manager
+            .update(cx, |s3, _cx| s3.reload_remote(remote_name.clone()));
+
+        let notification = match reloaded {
+            Ok(()) => {
+                Notification::success(t!("rooter.credentials_reloaded", remote = remote_name))
+            }
+            Err(err) => error_notification(&err).title(t!("rooter.reload_credentials_failed")),
+        };
+        window.push_notification(notification, cx);
+        self.check_credentials_expiry(window, cx);
+        cx.notify();
+    }
+
     fn open_about_dialog(&mut self, _: &About, window: &mut Window, cx: &mut Context<Self>) {
-        let message = format!("Balti {}", config::BALTI_VERSION);
-        let detail = config::BALTI_COMMIT_SHA;
+        // reading the OS version may block
+        let diagnostics = cx.background_spawn(async { Diagnostics::collect() });
 
-        let task = window.prompt(
+        cx.spawn_in(window, async move |_this, cx| {
+            let diagnostics = diagnostics.await;
+            let _ = cx.update(|window, cx| about_dialog::open_dialog(diagnostics, window, cx));
+        })
+        .detach();
+    }
+
+    /// Saves the settings where the user picks, along with the remotes' view
+    /// preferences if they want
+    fn export_settings(&mut self, _: &ExportSettings, window: &mut Window, cx: &mut Context<Self>) {
+        let answer = window.prompt(
             PromptLevel::Info,
-            message.as_str(),
-            Some(detail),
-            &[PromptButton::Ok(SharedString::new_static("Ok"))],
+            &t!("rooter.export_settings"),
+            Some(&t!("rooter.export_settings_detail")),
+            &[
+                PromptButton::Cancel(t!("common.cancel").into()),
+                PromptButton::Other(t!("rooter.settings_only").into()),
+                PromptButton::Ok(t!("rooter.include_view_prefs").into()),
+            ],
             cx,
         );
+        let directory = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+
+        cx.spawn_in(window, async move |_this, cx| {
+            let include_view_prefs = match answer.await {
+                Ok(1) => false,
+                Ok(2) => true,
+                _ => return,
+            };
+            let Ok(path_prompt) = cx.update(|_window, cx| {
+                cx.prompt_for_new_path(&directory, Some(SETTINGS_EXPORT_FILE_NAME))
+            }) else {
+                return;
+            };
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+            let Ok(settings) = cx.update(|_window, cx| Settings::get(cx).clone()) else {
+                return;
+            };
+
+            let view_prefs = if include_view_prefs {
+                config::parse_all_view_prefs()
+            } else {
+                Ok(BTreeMap::new())
+            };
+            let result = view_prefs.and_then(|view_prefs| {
+                let export = SettingsExport {
+                    settings,
+                    view_prefs,
+                    ..Default::default()
+                };
+                config::export_settings(&path, &export)
+            });
 
-        cx.spawn(async move |_this, _cx| {
-            let _ = task.await;
+            let _ = cx.update(|window, cx| {
+                let notification = match result {
+                    Ok(_) => {
+                        Notification::success(t!("rooter.settings_exported", path = path.display()))
+                    }
+                    Err(err) => error_notification(&err).title(t!("rooter.export_settings_failed")),
+                };
+                window.push_notification(notification, cx);
+            });
         })
         .detach();
     }
 
+    /// Loads settings exported on another machine and applies them
+    fn import_settings(&mut self, _: &ImportSettings, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: None,
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let result = config::import_settings(&path).and_then(|import| {
+                let overwrite = config::review_settings(&import.settings)?;
+                Ok((import, overwrite))
+            });
+            let (import, overwrite) = match result {
+                Ok(imported) => imported,
+                Err(err) => {
+                    let _ = cx.update(|window, cx| {
+                        window.push_notification(
+                            error_notification(&err).title(t!("rooter.import_settings_failed")),
+                            cx,
+                        );
+                    });
+                    return;
+                }
+            };
+
+            // the imported settings get shown against the file they replace
+            if let Some(overwrite) = overwrite {
+                let Ok(confirmed) = cx.update(|window, cx| {
+                    diff_dialog::confirm(
+                        t!("rooter.import_settings_review"),
+                        t!("rooter.import_settings_review_detail"),
+                        overwrite,
+                        window,
+                        cx,
+                    )
+                }) else {
+                    return;
+                };
+                if !confirmed.await {
+                    return;
+                }
+            }
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.apply_settings(import, window, cx);
+                window.push_notification(
+                    Notification::success(t!("rooter.settings_imported_detail"))
+                        .title(t!("rooter.settings_imported")),
+                    cx,
+                );
+            });
+        })
+        .detach();
+    }
+
+    /// Replaces the settings and view preferences in `import`, open tabs follow. The
+    /// ones read at startup apply from the next launch.
+    fn apply_settings(
+        &mut self,
+        import: SettingsExport,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        Settings::update(cx, |settings| *settings = import.settings);
+        crate::theme::apply_ui_scale(cx);
+
+        if !import.view_prefs.is_empty() {
+            if let Err(err) = config::merge_view_prefs(import.view_prefs.clone()) {
+                tracing::error!("Failed to save imported view preferences: {}", err.message);
+            }
+            let remote_uis = self
+                .tab_nav
+                .views()
+                .filter_map(|view| view.clone().downcast::<RemoteUi>().ok())
+                .collect::<Vec<_>>();
+            for remote_ui in remote_uis {
+                remote_ui.update(cx, |remote_ui, cx| {
+                    let remote_name = remote_ui.remote().remote_name.clone();
+                    if let Some(prefs) = import.view_prefs.get(remote_name.as_ref()) {
+                        remote_ui.set_view_prefs(prefs.clone(), cx);
+                    }
+                });
+            }
+        }
+        window.refresh();
+    }
+
+    fn open_whats_new(&mut self, _: &WhatsNew, window: &mut Window, cx: &mut Context<Self>) {
+        match changelog::notes(config::BALTI_VERSION) {
+            Some(notes) => whats_new_dialog::open_dialog(config::BALTI_VERSION, notes, window, cx),
+            None => window.push_notification(
+                Notification::info(t!(
+                    "rooter.no_release_notes",
+                    version = config::BALTI_VERSION
+                )),
+                cx,
+            ),
+        }
+    }
+
+    fn active_remote_ui(&self) -> Option<Entity<RemoteUi>> {
+        self.tab_nav
+            .active_view()?
+            .clone()
+            .downcast::<RemoteUi>()
+            .ok()
+    }
+
+    /// Listing shown in the active tab, clipboard shortcuts act on it
+    fn active_browse(&self, cx: &App) -> Option<Entity<BrowseUi>> {
+        self.active_remote_ui()?.read(cx).current_browse(cx)
+    }
+
+    /// Names the active tab's location in the OS window title, like
+    /// "prod-eu / exports/2024 — Balti", for window switchers
+    fn sync_window_title(&mut self, window: &mut Window, cx: &App) {
+        let title = match self.active_remote_ui() {
+            Some(remote_ui) => format!(
+                "{} — Balti",
+                util::truncate_middle(&remote_ui.read(cx).location(cx), TITLE_LOCATION_CHARS)
+            ),
+            None => "Balti".to_owned(),
+        };
+        if title != self.window_title {
+            window.set_window_title(&title);
+            self.window_title = title;
+        }
+    }
+
+    fn zoom(&mut self, steps: Option<i32>, window: &mut Window, cx: &mut Context<Self>) {
+        Settings::update(cx, |settings| settings.zoom(steps));
+        crate::theme::apply_ui_scale(cx);
+        window.refresh();
+    }
+
     fn check_for_updates(
         &mut self,
         _: &CheckForUpdates,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let task = rt::spawn(cx, async move {
-            println!("todo: {}", config::BALTI_COMMIT_SHA);
-        });
+        let proxy = Settings::get(cx).proxy.clone();
+        let task = rt::spawn(cx, update::latest_release(proxy));
 
-        cx.spawn_in(window, async move |_this, _cx| {
-            let _result = task.await;
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                // asked for, so any newer release shows regardless of the threshold
+                Ok(release)
+                    if update::parse_version(config::BALTI_VERSION)
+                        .is_some_and(|current| release.version > current) =>
+                {
+                    this.update_banner = Some(release);
+                    cx.notify();
+                }
+                Ok(_) => window.push_notification(
+                    Notification::success(t!("update.up_to_date", version = config::BALTI_VERSION)),
+                    cx,
+                ),
+                Err(err) => window.push_notification(
+                    error_notification(&err).title(t!("update.check_failed")),
+                    cx,
+                ),
+            });
         })
         .detach();
     }
@@ -155,51 +974,192 @@ impl Rooter {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        // its tabs close along with it
+        let pending = self.tab_nav.remote_pending_state(&remote_name, cx);
+        let detail = pending.has_pending_state().then(|| {
+            t!(
+                "rooter.delete_remote_pending",
+                state = describe_pending(pending)
+            )
+        });
         let task = window.prompt(
             PromptLevel::Critical,
-            format!("Delete '{}' remote ?", remote_name).as_str(),
-            None,
+            &t!("rooter.delete_remote_prompt", remote = remote_name),
+            detail.as_deref(),
             &[
-                PromptButton::Cancel(SharedString::new_static("Cancel")),
-                PromptButton::Ok(SharedString::new_static("Delete")),
+                PromptButton::Cancel(t!("common.cancel").into()),
+                PromptButton::Ok(t!("common.delete").into()),
             ],
             cx,
         );
 
-        cx.spawn(async move |this, cx| {
+        cx.spawn_in(window, async move |this, cx| {
             let result = task.await;
             match result {
                 Ok(index) => {
                     if index == 1 {
-                        let _ = this.update(cx, |this, cx| {
+                        let _ = this.update_in(cx, |this, window, cx| {
                             this.tab_nav
                                 .close_tab_by_remote(SharedString::new(remote_name.clone()), cx);
-                            this.s3_remote_manager.update(cx, |s3, cx| {
+                            this.reset_remote_state(&remote_name, cx);
+                            this.s3_remote_manager.update(cx, |s3, _cx| {
                                 s3.remove_remote(remote_name.into());
-                                s3.save_remotes();
-                                cx.notify();
                             });
+                            this.save_remotes(window, cx);
 
                             cx.notify();
                         });
                     }
                 }
-                Err(err) => {
-                    let _ = AppError::err(err);
-                }
+                Err(err) => AppError::err(err).log(),
             };
         })
         .detach();
     }
 
-    fn new_tab(&mut self, s3_remote: S3Remote, window: &mut Window, cx: &mut Context<Self>) {
+    /// Saves the remotes config, telling about remotes another instance changed as well.
+    /// A save that would overwrite edits made elsewhere, or a config that didn't parse,
+    /// shows what it changes first. Cancelling leaves the file as it is.
+    fn save_remotes(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let s3 = self.s3_remote_manager.read(cx);
+        let (review, had_parse_error) = (s3.review_save(), s3.had_parse_error());
+        let overwrite = match review {
+            Ok(None) => return self.write_remotes(false, window, cx),
+            Ok(Some(overwrite)) => overwrite,
+            Err(err) => {
+                window.push_notification(
+                    error_notification(&err).title(t!("rooter.save_remotes_failed")),
+                    cx,
+                );
+                return;
+            }
+        };
+
+        let detail = if had_parse_error {
+            t!("rooter.replace_remotes_detail")
+        } else {
+            t!("rooter.overwrite_remotes_detail")
+        };
+        let confirmed = diff_dialog::confirm(
+            t!("rooter.overwrite_remotes"),
+            detail,
+            overwrite,
+            window,
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            if confirmed.await {
+                let _ = this.update_in(cx, |this, window, cx| {
+                    this.write_remotes(true, window, cx);
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Writes the remotes config, with `overwrite` over what the user saw it would change
+    fn write_remotes(&mut self, overwrite: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let saved = self.s3_remote_manager.update(cx, |s3, cx| {
+            let saved = if overwrite {
+                s3.overwrite_remotes()
+            } else {
+                s3.save_remotes()
+            };
+            cx.notify();
+            saved
+        });
+
+        match saved {
+            Ok(conflicts) if conflicts.is_empty() => {}
+            Ok(conflicts) => window.push_notification(
+                Notification::warning(t!(
+                    "rooter.remotes_conflict",
+                    count = conflicts.len(),
+                    remotes = conflicts.join(", ")
+                ))
+                .title(t!("rooter.remotes_changed_elsewhere"))
+                .autohide(false),
+                cx,
+            ),
+            Err(err) => window.push_notification(
+                error_notification(&err).title(t!("rooter.save_remotes_failed")),
+                cx,
+            ),
+        }
+    }
+
+    fn new_tab(
+        &mut self,
+        s3_remote: S3Remote,
+        prefix: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
         cx.stop_propagation();
 
-        let view = RemoteUi::view(s3_remote, window, cx);
-        self.tab_nav.new_tab(view, cx);
+        let view = RemoteUi::view(s3_remote, prefix, window, cx);
+        let sub = cx.subscribe_in(
+            &view,
+            window,
+            |this, _view, event: &OpenTabEvent, window, cx| {
+                this.new_tab(event.remote.clone(), event.prefix.clone(), window, cx);
+            },
+        );
+        let region_sub = cx.subscribe_in(
+            &view,
+            window,
+            |this, _view, event: &SwitchRegionEvent, window, cx| {
+                this.switch_region(
+                    event.remote.clone(),
+                    event.region.clone(),
+                    event.prefix.clone(),
+                    window,
+                    cx,
+                );
+            },
+        );
+        let nav_sub = cx.subscribe_in(
+            &view,
+            window,
+            |this, _view, _event: &NavChangedEvent, window, cx| {
+                this.sync_window_title(window, cx);
+            },
+        );
+        self.tab_nav
+            .new_tab(view, vec![sub, region_sub, nav_sub], cx);
         cx.notify();
     }
 
+    /// Saves the remote with the bucket's region, its tabs are replaced by one at `prefix`
+    fn switch_region(
+        &mut self,
+        s3_remote: S3Remote,
+        region: Arc<str>,
+        prefix: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote_name = s3_remote.remote_name.clone();
+        let config = RemoteConfig::S3(s3_remote.config.with_region(&region));
+
+        self.tab_nav
+            .close_tab_by_remote(SharedString::new(remote_name.clone()), cx);
+        self.s3_remote_manager.update(cx, |s3, _cx| {
+            s3.add_remote(remote_name.clone(), config);
+        });
+        self.save_remotes(window, cx);
+
+        let s3_remote = self
+            .s3_remote_manager
+            .read(cx)
+            .remotes()
+            .get(&remote_name)
+            .cloned();
+        if let Some(s3_remote) = s3_remote {
+            self.new_tab(s3_remote, prefix, window, cx);
+        }
+    }
+
     fn select_tab(&mut self, index: &usize, _window: &mut Window, cx: &mut Context<Self>) {
         cx.stop_propagation();
         self.tab_nav.select_tab(*index);
@@ -211,16 +1171,60 @@ impl Rooter {
         cx.notify();
     }
 
-    fn close_active_tab(&mut self) -> bool {
-        self.tab_nav.close_active_tab()
+    /// Closes the tab, asking first when that drops a selection or running operations
+    fn request_close_tab(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let pending = self.tab_nav.pending_state(index, cx);
+        if !pending.has_pending_state() {
+            self.close_tab(index, cx);
+            return;
+        }
+        let Some(id) = self.tab_nav.tabs().get(index).cloned() else {
+            return;
+        };
+
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            &t!("rooter.close_tab_prompt", state = describe_pending(pending)),
+            None,
+            &[
+                PromptButton::Cancel(t!("common.cancel").into()),
+                PromptButton::Ok(t!("rooter.close_anyway").into()),
+            ],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            if answer.await != Ok(1) {
+                return;
+            }
+            let _ = this.update(cx, |this, cx| {
+                // other tabs may have closed while asking
+                if let Some(index) = this.tab_nav.tabs().iter().position(|tab| tab == &id) {
+                    this.close_tab(index, cx);
+                }
+            });
+        })
+        .detach();
     }
 }
 
+/// Like "214 items selected, 2 operations running"
+fn describe_pending(pending: PendingState) -> String {
+    let selected =
+        (pending.selected > 0).then(|| t!("rooter.pending_selected", count = pending.selected));
+    let running =
+        (pending.running > 0).then(|| t!("rooter.pending_running", count = pending.running));
+    [selected, running]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl remote_dialog::RemoteDialog for Rooter {
     fn create_remote(
         &mut self,
         name: SharedString,
-        config: S3Config,
+        config: RemoteConfig,
         old_remote: Option<Arc<str>>,
         window: &mut Window,
         cx: &mut Context<Self>,
@@ -231,18 +1235,16 @@ impl remote_dialog::RemoteDialog for Rooter {
             Some(old_remote) => {
                 self.tab_nav
                     .close_tab_by_remote(old_remote.clone().into(), cx);
-                self.s3_remote_manager.update(cx, |s3, cx| {
+                self.reset_remote_state(&old_remote, cx);
+                self.s3_remote_manager.update(cx, |s3, _cx| {
                     s3.remove_remote(old_remote);
-                    s3.save_remotes();
-                    cx.notify();
                 });
+                self.save_remotes(window, cx);
             }
             None => {
                 if self.s3_remote_manager.read(cx).has_remote(name.clone()) {
                     window.push_notification(
-                        Notification::warning(format!(
-                            "Remote with name \"{name}\" already exists"
-                        )),
+                        Notification::warning(t!("rooter.remote_exists", remote = name)),
                         cx,
                     );
                     return;
@@ -250,17 +1252,44 @@ impl remote_dialog::RemoteDialog for Rooter {
             }
         };
 
-        self.s3_remote_manager.update(cx, |s3, cx| {
+        self.reset_remote_state(&name, cx);
+        self.s3_remote_manager.update(cx, |s3, _cx| {
             s3.add_remote(name, config);
-            s3.save_remotes();
-            cx.notify();
         });
+        self.save_remotes(window, cx);
         window.close_all_dialogs(cx);
+        self.check_credentials_expiry(window, cx);
     }
 
-    fn test_config(&mut self, config: S3Config, window: &mut Window, cx: &mut Context<Self>) {
-        let remote = self.s3_remote_manager.read(cx).dummy_remote(config);
-        let task = rt::spawn(cx, async move { balti_s3::list_objects(remote, "").await });
+    fn test_config(
+        &mut self,
+        config: RemoteConfig,
+        on_switch_region: remote_dialog::ApplyConfig,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote_manager.read(cx).dummy_remote(config.clone());
+        let endpoint = EndpointUrl::parse(&remote.config.endpoint)
+            .map(|endpoint| endpoint.authority())
+            .unwrap_or_else(|_| remote.config.endpoint.to_string());
+        let page_size = Settings::get(cx).page_size(&remote);
+        let task = rt::spawn(cx, async move {
+            let root = remote.root();
+            let result = balti_s3::list_objects(
+                remote.clone(),
+                root.trim_start_matches('/'),
+                page_size,
+                None,
+            )
+            .await;
+            let region = match &result {
+                Err(err) if err.kind == ErrorKind::WrongRegion => {
+                    balti_s3::bucket_region(remote).await.ok()
+                }
+                _ => None,
+            };
+            (result, region)
+        });
 
         cx.spawn_in(window, async move |this, cx| {
             let _ = this.update(cx, |this, cx| {
@@ -268,21 +1297,47 @@ impl remote_dialog::RemoteDialog for Rooter {
                 cx.notify();
             });
 
-            let result = task.await.flatten();
+            let (result, region) = match task.await {
+                Ok(tested) => tested,
+                Err(err) => (Err(err), None),
+            };
 
             let _ = this.update_in(cx, |this, window, cx| {
                 this.is_testing = false;
 
-                match result {
-                    Ok(objects) => window.push_notification(
+                match (result, region) {
+                    (Ok(page), _) => window.push_notification(
                         Notification::new()
-                            .message(format!("Listed {} objects at root", objects.len()))
-                            .title("Test success")
+                            .message(t!(
+                                "rooter.test_listed",
+                                endpoint = endpoint,
+                                count = page.objects.len()
+                            ))
+                            .title(t!("rooter.test_success"))
                             .icon(Icon::new(IconName::CircleCheck).text_color(green())),
                         cx,
                     ),
-                    Err(err) => window.push_notification(
-                        Notification::error(err.message).title("Test failed"),
+                    (Err(err), Some(region)) => {
+                        let config = config.with_region(&region);
+                        let entity = cx.weak_entity();
+                        window.push_notification(
+                            wrong_region_notification(&err, region, move |window, cx| {
+                                on_switch_region(&config, window, cx);
+                                let _ = entity.update(cx, |this, cx| {
+                                    this.test_config(
+                                        config.clone(),
+                                        on_switch_region.clone(),
+                                        window,
+                                        cx,
+                                    );
+                                    cx.notify();
+                                });
+                            }),
+                            cx,
+                        );
+                    }
+                    (Err(err), None) => window.push_notification(
+                        error_notification(&err).title(t!("rooter.test_failed")),
                         cx,
                     ),
                 };
@@ -296,10 +1351,143 @@ impl remote_dialog::RemoteDialog for Rooter {
     fn is_testing(&self) -> bool {
         self.is_testing
     }
+
+    fn fetch_buckets(
+        &mut self,
+        config: RemoteConfig,
+        on_buckets: remote_dialog::ApplyBuckets,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let remote = self.s3_remote_manager.read(cx).dummy_remote(config);
+        let task = rt::spawn(cx, balti_s3::list_buckets(remote));
+
+        self.is_fetching_buckets = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await.flatten();
+
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.is_fetching_buckets = false;
+
+                match result {
+                    Ok(buckets) if buckets.is_empty() => {
+                        window.push_notification(Notification::info(t!("rooter.no_buckets")), cx)
+                    }
+                    Ok(buckets) => on_buckets(buckets, window, cx),
+                    // keys scoped to one bucket usually can't list them, it's typed in then
+                    Err(err) if err.kind == ErrorKind::AccessDenied => {
+                        err.log();
+                        window.push_notification(
+                            Notification::warning(t!("rooter.buckets_denied"))
+                                .title(t!("rooter.buckets_unavailable")),
+                            cx,
+                        )
+                    }
+                    Err(err) => window.push_notification(
+                        warning_notification(&err).title(t!("rooter.buckets_unavailable")),
+                        cx,
+                    ),
+                };
+
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn is_fetching_buckets(&self) -> bool {
+        self.is_fetching_buckets
+    }
+
+    fn duplicate_of(
+        &self,
+        target: &RemoteTarget,
+        old_remote: Option<&str>,
+        cx: &App,
+    ) -> Option<Arc<str>> {
+        self.s3_remote_manager
+            .read(cx)
+            .duplicate_of(target, old_remote)
+    }
+}
+
+/// The dialog of a restored report hands its retry to a tab of the remote, which
+/// reports on it like any batch
+impl BatchReportDialog for Rooter {
+    fn retry_failed(
+        &mut self,
+        _operation: BatchOperation,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(report) = self.reviewing_report.take() else {
+            return;
+        };
+        PendingReports::dismiss(&report, cx);
+        window.close_dialog(cx);
+        self.retry_report(report, keys, window, cx);
+        self.offer_pending_reports(window, cx);
+    }
+
+    /// Restored reports have nothing paused, a quota pause ends with the session
+    fn resume_paused(
+        &mut self,
+        operation: BatchOperation,
+        keys: Vec<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.retry_failed(operation, keys, window, cx);
+    }
+
+    fn is_retrying(&self, _operation: BatchOperation) -> bool {
+        false
+    }
+
+    fn undo(&mut self, id: Option<u64>, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(browse) = self.active_browse(cx) {
+            browse.update(cx, |browse, cx| browse.undo(id, window, cx));
+        }
+    }
+}
+
+impl go_to_dialog::GoToDialog for Rooter {
+    fn go_to(
+        &mut self,
+        remote: S3Remote,
+        prefix: SharedString,
+        reveal: Option<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(remote_ui) = self.active_remote_ui()
+            && remote_ui.read(cx).remote().remote_name == remote.remote_name
+            && remote_ui.update(cx, |remote_ui, cx| {
+                remote_ui.go_to(prefix.clone(), reveal.clone(), window, cx)
+            })
+        {
+            return;
+        }
+        self.new_tab(remote, prefix.clone(), window, cx);
+
+        // an open tab of the location may have moved on to another prefix
+        if let Some(key) = reveal
+            && let Some(browse) = self.active_browse(cx)
+            && browse.read(cx).prefix() == prefix
+        {
+            browse.update(cx, |browse, cx| browse.reveal(key, window, cx));
+        }
+    }
 }
 
 impl Render for Rooter {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // tabs opening, closing and switching all re-render
+        self.sync_window_title(window, cx);
+
         let dialog_layer = Root::render_dialog_layer(window, cx);
         let notification_layer = Root::render_notification_layer(window, cx);
 
@@ -308,11 +1496,15 @@ impl Render for Rooter {
             .key_context(APP_CONTEXT)
             .track_focus(&self.focus_handle)
             .on_action(cx.listener(Self::open_about_dialog))
+            .on_action(cx.listener(Self::open_whats_new))
             .on_action(cx.listener(Self::check_for_updates))
+            .on_action(cx.listener(Self::export_settings))
+            .on_action(cx.listener(Self::import_settings))
             .on_action(cx.listener(|this, _: &CloseWindow, window, cx| {
-                let closed = this.close_active_tab();
-                cx.notify();
-                if !closed {
+                let index = *this.tab_nav.active_index();
+                if index < this.tab_nav.tabs().len() {
+                    this.request_close_tab(index, window, cx);
+                } else {
                     window.remove_window();
                 }
             }))
@@ -320,23 +1512,161 @@ impl Render for Rooter {
                 window.remove_window();
                 cx.quit();
             }))
+            .on_action(cx.listener(|this, _: &ZoomIn, window, cx| {
+                this.zoom(Some(1), window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &ZoomOut, window, cx| {
+                this.zoom(Some(-1), window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &ResetZoom, window, cx| {
+                this.zoom(None, window, cx);
+            }))
+            .on_action(cx.listener(|_this, _: &Minimize, window, _cx| {
+                window.minimize_window();
+            }))
+            .on_action(cx.listener(|_this, _: &ZoomWindow, window, _cx| {
+                window.zoom_window();
+            }))
+            .on_action(cx.listener(|_this, _: &ToggleFullScreen, window, _cx| {
+                window.toggle_fullscreen();
+            }))
+            .on_action(cx.listener(|this, _: &ShowUsage, window, cx| {
+                usage_dialog::open_dialog(this.s3_remote_manager.clone(), window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &ExportJournal, window, cx| {
+                let remotes = this
+                    .s3_remote_manager
+                    .read(cx)
+                    .remotes()
+                    .keys()
+                    .map(|name| name.to_string())
+                    .collect();
+                journal_dialog::open_dialog(remotes, window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &GoToLocation, window, cx| {
+                let remotes = this
+                    .s3_remote_manager
+                    .read(cx)
+                    .remotes()
+                    .values()
+                    .cloned()
+                    .collect();
+                let current = this
+                    .active_remote_ui()
+                    .map(|remote_ui| remote_ui.read(cx).remote().clone());
+                go_to_dialog::open_dialog(cx.weak_entity(), remotes, current, window, cx);
+            }))
+            .on_action(cx.listener(|_this, _: &ToggleReduceMotion, window, cx| {
+                Settings::update(cx, |settings| {
+                    settings.reduce_motion = !settings.reduce_motion
+                });
+                window.refresh();
+            }))
+            .on_action(cx.listener(|_this, _: &ToggleCompactDensity, window, cx| {
+                // listings resize their rows when the settings change
+                Settings::update(cx, |settings| {
+                    settings.density = match settings.density {
+                        Density::Comfortable => Density::Compact,
+                        Density::Compact => Density::Comfortable,
+                    }
+                });
+                window.refresh();
+            }))
+            .on_action(cx.listener(|this, _: &CopyObjects, window, cx| {
+                if let Some(browse) = this.active_browse(cx) {
+                    browse.update(cx, |browse, cx| {
+                        browse.copy_to_clipboard(ClipboardMode::Copy, None, cx)
+                    });
+                }
+            }))
+            .on_action(cx.listener(|this, _: &CutObjects, window, cx| {
+                if let Some(browse) = this.active_browse(cx) {
+                    browse.update(cx, |browse, cx| {
+                        browse.copy_to_clipboard(ClipboardMode::Cut, None, cx)
+                    });
+                }
+            }))
+            .on_action(cx.listener(|this, _: &PasteObjects, window, cx| {
+                if let Some(browse) = this.active_browse(cx) {
+                    browse.update(cx, |browse, cx| browse.paste_here(window, cx));
+                }
+            }))
+            .on_action(cx.listener(|this, _: &UndoLast, window, cx| {
+                if let Some(browse) = this.active_browse(cx) {
+                    browse.update(cx, |browse, cx| browse.undo(None, window, cx));
+                }
+            }))
             .flex()
             .size_full()
             .child(self.render_sidebar(cx))
-            .child(div().size_full().map(|this| {
-                if self.tab_nav.tabs().is_empty() {
-                    this.child(self.render_empty_tab(cx))
-                } else {
-                    this.child(self.render_tabs(cx))
-                }
-            }))
+            .child(
+                v_flex()
+                    .size_full()
+                    .when_some(self.update_banner.clone(), |this, release| {
+                        this.child(self.render_update_banner(release, cx))
+                    })
+                    .child(div().size_full().map(|this| {
+                        if self.tab_nav.tabs().is_empty() {
+                            this.child(self.render_empty_tab(cx))
+                        } else {
+                            this.child(self.render_tabs(cx))
+                        }
+                    })),
+            )
             .when_some(notification_layer, |d, layer| d.child(layer))
             .when_some(dialog_layer, |d, layer| d.child(layer))
     }
 }
 
 impl Rooter {
+    /// Suggests the newer release, dismissing it keeps quiet until the next one
+    fn render_update_banner(&self, release: Release, cx: &mut Context<Self>) -> impl IntoElement {
+        let version = release.version.to_string();
+        let url = release.url;
+
+        h_flex()
+            .m_2()
+            .p_2()
+            .gap_2()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().info)
+            .bg(cx.theme().info.opacity(0.1))
+            .text_sm()
+            .child(
+                Icon::new(IconName::Info)
+                    .small()
+                    .text_color(cx.theme().info),
+            )
+            .child(div().flex_1().child(t!(
+                "update.banner",
+                latest = version,
+                current = config::BALTI_VERSION
+            )))
+            .child(
+                Button::new("view_release")
+                    .small()
+                    .label(t!("update.view_release"))
+                    .on_click(move |_ev, _window, cx| cx.open_url(&url)),
+            )
+            .child(
+                Button::new("dismiss_update")
+                    .small()
+                    .ghost()
+                    .icon(IconName::Close)
+                    .on_click(cx.listener(move |this, _ev, _window, cx| {
+                        if let Err(err) = config::save_dismissed_update(&version) {
+                            tracing::warn!("Failed to save the dismissed update: {}", err.message);
+                        }
+                        this.update_banner = None;
+                        cx.notify();
+                    })),
+            )
+    }
+
     fn render_sidebar(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let duplicates = self.s3_remote_manager.read(cx).duplicates();
+
         Sidebar::new(Side::Left)
             .header(
                 SidebarHeader::new()
@@ -360,93 +1690,474 @@ impl Rooter {
                     )
                     .mt(px(32.)),
             )
-            .child(SidebarGroup::new("Remotes").child(
+            .child(SidebarGroup::new(t!("rooter.remotes")).child(
                 SidebarMenu::new().children(
                     self.s3_remote_manager.read(cx).remotes().into_iter().map(
                         |(remote, s3_remote)| {
                             let entity = cx.weak_entity();
                             let _s3_remote = s3_remote.clone();
                             let s3_remote = s3_remote.clone();
+                            let duplicate_of = duplicates.get(remote).map(|others| {
+                                let others = others
+                                    .iter()
+                                    .map(|name| format!("'{name}'"))
+                                    .collect::<Vec<_>>()
+                                    .join(", ");
+                                SharedString::new(t!("rooter.duplicate_of", remotes = others))
+                            });
+                            let counts = Transfers::entity(cx).read(cx).counts(remote);
+                            let running = counts.iter().map(|(_, count)| count).sum::<usize>();
+                            let activity = transfers::summary(&counts).map(SharedString::new);
+                            let expiry = s3_remote.config.credentials_expiry;
+                            let can_reload = s3_remote.config.credentials_source().can_reload();
+                            let expired = ExpiredRemotes::entity(cx).read(cx).contains(remote);
+                            let degraded = RemoteHealth::is_degraded(remote, cx);
+                            let expiring_soon =
+                                expiry.is_some_and(|expiry| expiry - Utc::now() <= EXPIRY_WARNING);
+                            let plain_http = EndpointUrl::parse(&s3_remote.config.endpoint)
+                                .ok()
+                                .filter(|endpoint| endpoint.is_plain_http())
+                                .filter(|_| !self.plain_http_dismissed.contains(remote));
 
                             SidebarMenuItem::new(remote)
                                 .icon(Icon::empty().path("icons/server.svg"))
                                 .suffix(
-                                    Button::new(SharedString::new(format!("btn-{remote}")))
-                                        .icon(IconName::EllipsisVertical)
-                                        .small()
-                                        .ghost()
-                                        .on_click(move |_ev, _window, cx| {
-                                            cx.stop_propagation();
+                                    h_flex()
+                                        .gap_1()
+                                        .when_some(duplicate_of, |this, tooltip| {
+                                            this.child(
+                                                div()
+                                                    .id(SharedString::new(format!("dup-{remote}")))
+                                                    .px_1()
+                                                    .rounded_sm()
+                                                    .border_1()
+                                                    .border_color(cx.theme().warning)
+                                                    .text_xs()
+                                                    .text_color(cx.theme().warning)
+                                                    .child(t!("rooter.duplicate_badge"))
+                                                    .tooltip(move |window, cx| {
+                                                        Tooltip::new(tooltip.clone())
+                                                            .build(window, cx)
+                                                    }),
+                                            )
                                         })
-                                        .dropdown_menu(move |menu, _window, _cx| {
-                                            let s3_remote = _s3_remote.clone();
-                                            let _s3_remote = _s3_remote.clone();
-                                            let entity = entity.clone();
-                                            let _entity = entity.clone();
-
-                                            menu.menu_element(
-                                                Box::new(EmptyAction),
-                                                move |_window, _cx| {
-                                                    let s3_remote = _s3_remote.clone();
-                                                    let entity = _entity.clone();
-
-                                                    div()
-                                                        .id("")
-                                                        .flex()
-                                                        .gap_2()
-                                                        .items_center()
-                                                        .child(
-                                                            Icon::empty()
-                                                                .path("icons/pencil.svg")
-                                                                .small(),
-                                                        )
-                                                        .child(div().child("Edit remote").text_sm())
-                                                        .on_click(move |_ev, window, cx| {
-                                                            remote_dialog::open_dialog(
-                                                                Some(s3_remote.clone()),
-                                                                entity.clone(),
-                                                                window,
-                                                                cx,
-                                                            );
-                                                        })
-                                                },
+                                        .when_some(plain_http, |this, endpoint| {
+                                            let tooltip = SharedString::new(t!(
+                                                "remote.plain_http",
+                                                host = endpoint.authority()
+                                            ));
+                                            let remote = remote.clone();
+                                            this.child(
+                                                h_flex()
+                                                    .id(SharedString::new(format!("http-{remote}")))
+                                                    .gap_0p5()
+                                                    .px_1()
+                                                    .rounded_sm()
+                                                    .border_1()
+                                                    .border_color(cx.theme().warning)
+                                                    .text_xs()
+                                                    .text_color(cx.theme().warning)
+                                                    .child("http")
+                                                    .child(
+                                                        Button::new(SharedString::new(format!(
+                                                            "dismiss-http-{remote}"
+                                                        )))
+                                                        .icon(IconName::Close)
+                                                        .xsmall()
+                                                        .ghost()
+                                                        .on_click(cx.listener(
+                                                            move |this, _ev, _window, cx| {
+                                                                cx.stop_propagation();
+                                                                this.plain_http_dismissed
+                                                                    .insert(remote.clone());
+                                                                cx.notify();
+                                                            },
+                                                        )),
+                                                    )
+                                                    .tooltip(move |window, cx| {
+                                                        Tooltip::new(tooltip.clone())
+                                                            .build(window, cx)
+                                                    }),
+                                            )
+                                        })
+                                        .when(expired, |this| {
+                                            let expiry = match expiry {
+                                                Some(expiry) => {
+                                                    credentials::countdown(expiry, Utc::now())
+                                                }
+                                                None => t!("credentials.expired"),
+                                            };
+                                            let tooltip = SharedString::new(if can_reload {
+                                                t!("rooter.reload_expired", expiry = expiry)
+                                            } else {
+                                                t!("rooter.update_expired", expiry = expiry)
+                                            });
+                                            this.child(
+                                                div()
+                                                    .id(SharedString::new(format!(
+                                                        "expired-{remote}"
+                                                    )))
+                                                    .px_1()
+                                                    .rounded_sm()
+                                                    .border_1()
+                                                    .border_color(cx.theme().danger)
+                                                    .text_xs()
+                                                    .text_color(cx.theme().danger)
+                                                    .child(t!("rooter.expired_badge"))
+                                                    .tooltip(move |window, cx| {
+                                                        Tooltip::new(tooltip.clone())
+                                                            .build(window, cx)
+                                                    }),
+                                            )
+                                        })
+                                        .when(degraded, |this| {
+                                            let tooltip = SharedString::new(t!(
+                                                "rooter.offline_detail",
+                                                endpoint = s3_remote.config.endpoint
+                                            ));
+                                            this.child(
+                                                div()
+                                                    .id(SharedString::new(format!(
+                                                        "offline-{remote}"
+                                                    )))
+                                                    .px_1()
+                                                    .rounded_sm()
+                                                    .border_1()
+                                                    .border_color(cx.theme().warning)
+                                                    .text_xs()
+                                                    .text_color(cx.theme().warning)
+                                                    .child(t!("rooter.offline_badge"))
+                                                    .tooltip(move |window, cx| {
+                                                        Tooltip::new(tooltip.clone())
+                                                            .build(window, cx)
+                                                    }),
                                             )
-                                            .separator()
-                                            .menu_element(
-                                                Box::new(EmptyAction),
-                                                move |_window, cx| {
-                                                    let remote_name = s3_remote.remote_name.clone();
+                                        })
+                                        .when(expiry.is_some() && !expired, |this| {
+                                            let config = s3_remote.config.clone();
+                                            this.child(
+                                                div()
+                                                    .id(SharedString::new(format!(
+                                                        "expiry-{remote}"
+                                                    )))
+                                                    .child(
+                                                        Icon::empty()
+                                                            .path("icons/timer.svg")
+                                                            .xsmall()
+                                                            .text_color(if expiring_soon {
+                                                                cx.theme().warning
+                                                            } else {
+                                                                cx.theme().muted_foreground
+                                                            }),
+                                                    )
+                                                    .tooltip(move |window, cx| {
+                                                        Tooltip::new(credentials::describe(
+                                                            &config,
+                                                            Utc::now(),
+                                                        ))
+                                                        .build(window, cx)
+                                                    }),
+                                            )
+                                        })
+                                        .when_some(activity, |this, tooltip| {
+                                            this.child(
+                                                h_flex()
+                                                    .id(SharedString::new(format!(
+                                                        "activity-{remote}"
+                                                    )))
+                                                    .gap_0p5()
+                                                    .text_xs()
+                                                    .text_color(cx.theme().muted_foreground)
+                                                    .child(loading::loading_indicator(
+                                                        SharedString::new(format!(
+                                                            "activity-spin-{remote}"
+                                                        )),
+                                                        Icon::new(IconName::LoaderCircle).xsmall(),
+                                                        cx,
+                                                    ))
+                                                    .child(running.to_string())
+                                                    .tooltip(move |window, cx| {
+                                                        Tooltip::new(tooltip.clone())
+                                                            .build(window, cx)
+                                                    }),
+                                            )
+                                        })
+                                        .child(
+                                            Button::new(SharedString::new(format!("btn-{remote}")))
+                                                .icon(IconName::EllipsisVertical)
+                                                .small()
+                                                .ghost()
+                                                .on_click(move |_ev, _window, cx| {
+                                                    cx.stop_propagation();
+                                                })
+                                                .dropdown_menu(move |menu, _window, _cx| {
+                                                    let s3_remote = _s3_remote.clone();
+                                                    let info_remote = _s3_remote.clone();
+                                                    let probe_remote = _s3_remote.clone();
+                                                    let reload_remote = _s3_remote.remote_name.clone();
+                                                    let reload_entity = entity.clone();
+                                                    let snippet_remote = _s3_remote.clone();
+                                                    let snippet_entity = entity.clone();
+                                                    let _s3_remote = _s3_remote.clone();
                                                     let entity = entity.clone();
+                                                    let _entity = entity.clone();
+
+                                                    let menu = menu.menu_element(
+                                                        Box::new(EmptyAction),
+                                                        move |_window, _cx| {
+                                                            let s3_remote = _s3_remote.clone();
+                                                            let entity = _entity.clone();
 
-                                                    div()
-                                                        .id("")
-                                                        .flex()
-                                                        .gap_2()
-                                                        .items_center()
-                                                        .text_color(cx.theme().danger)
-                                                        .child(Icon::new(IconName::Delete).small())
-                                                        .child(
-                                                            div().child("Delete remote").text_sm(),
+                                                            div()
+                                                                .id("")
+                                                                .flex()
+                                                                .gap_2()
+                                                                .items_center()
+                                                                .child(
+                                                                    Icon::empty()
+                                                                        .path("icons/pencil.svg")
+                                                                        .small(),
+                                                                )
+                                                                .child(
+                                                                    div()
+                                                                        .child(t!("rooter.edit_remote"))
+                                                                        .text_sm(),
+                                                                )
+                                                                .on_click(move |_ev, window, cx| {
+                                                                    remote_dialog::open_dialog(
+                                                                        Some(s3_remote.clone()),
+                                                                        entity.clone(),
+                                                                        window,
+                                                                        cx,
+                                                                    );
+                                                                })
+                                                        },
+                                                    )
+                                                    .menu_element(
+                                                        Box::new(EmptyAction),
+                                                        move |_window, _cx| {
+                                                            let s3_remote = info_remote.clone();
+
+                                                            div()
+                                                                .id("")
+                                                                .flex()
+                                                                .gap_2()
+                                                                .items_center()
+                                                                .child(
+                                                                    Icon::new(IconName::Info)
+                                                                        .small(),
+                                                                )
+                                                                .child(
+                                                                    div()
+                                                                        .child(t!("rooter.bucket_info"))
+                                                                        .text_sm(),
+                                                                )
+                                                                .on_click(move |_ev, window, cx| {
+                                                                    bucket_info_dialog::open_dialog(
+                                                                        s3_remote.clone(),
+                                                                        window,
+                                                                        cx,
+                                                                    );
+                                                                })
+                                                        },
+                                                    )
+                                                    .menu_element(
+                                                        Box::new(EmptyAction),
+                                                        move |_window, _cx| {
+                                                            let s3_remote = probe_remote.clone();
+
+                                                            div()
+                                                                .id("")
+                                                                .flex()
+                                                                .gap_2()
+                                                                .items_center()
+                                                                .child(
+                                                                    Icon::new(IconName::CircleCheck)
+                                                                        .small(),
+                                                                )
+                                                                .child(
+                                                                    div()
+                                                                        .child(t!("rooter.recheck_permissions"))
+                                                                        .text_sm(),
+                                                                )
+                                                                .on_click(move |_ev, window, cx| {
+                                                                    RemoteCapabilities::reprobe(
+                                                                        &s3_remote,
+                                                                        cx,
+                                                                    );
+                                                                    window.push_notification(
+                                                                        Notification::info(t!(
+                                                                            "rooter.checking_permissions"
+                                                                        )),
+                                                                        cx,
+                                                                    );
+                                                                })
+                                                        },
+                                                    );
+                                                    let menu = if can_reload {
+                                                        menu.menu_element(
+                                                            Box::new(EmptyAction),
+                                                            move |_window, _cx| {
+                                                                let remote_name = reload_remote.clone();
+                                                                let entity = reload_entity.clone();
+
+                                                                div()
+                                                                    .id("")
+                                                                    .flex()
+                                                                    .gap_2()
+                                                                    .items_center()
+                                                                    .child(
+                                                                        Icon::empty()
+                                                                            .path("icons/rotate-ccw.svg")
+                                                                            .small(),
+                                                                    )
+                                                                    .child(
+                                                                        div()
+                                                                            .child(t!("rooter.reload_credentials"))
+                                                                            .text_sm(),
+                                                                    )
+                                                                    .on_click(move |_ev, window, cx| {
+                                                                        let _ = entity.update(cx, |this, cx| {
+                                                                            this.reload_credentials(
+                                                                                remote_name.clone(),
+                                                                                window,
+                                                                                cx,
+                                                                            );
+                                                                        });
+                                                                    })
+                                                            },
                                                         )
-                                                        .on_click(move |_ev, window, cx| {
-                                                            let _ = entity.clone().update(
-                                                                cx,
-                                                                |this, cx| {
-                                                                    this.delete_remote(
-                                                                        remote_name.clone(),
+                                                    } else {
+                                                        menu
+                                                    };
+
+                                                    menu.separator()
+                                                    .menu_element(
+                                                        Box::new(EmptyAction),
+                                                        move |_window, _cx| {
+                                                            let s3_remote = snippet_remote.clone();
+
+                                                            div()
+                                                                .id("")
+                                                                .flex()
+                                                                .gap_2()
+                                                                .items_center()
+                                                                .child(
+                                                                    Icon::new(IconName::Copy)
+                                                                        .small(),
+                                                                )
+                                                                .child(
+                                                                    div()
+                                                                        .child(t!("rooter.copy_snippet"))
+                                                                        .text_sm(),
+                                                                )
+                                                                .on_click(move |_ev, window, cx| {
+                                                                    cx.write_to_clipboard(
+                                                                        ClipboardItem::new_string(
+                                                                            config::remote_snippet(
+                                                                                &s3_remote.remote_name,
+                                                                                &RemoteConfig::S3(
+                                                                                    s3_remote.config.clone(),
+                                                                                ),
+                                                                            ),
+                                                                        ),
+                                                                    );
+                                                                    window.push_notification(
+                                                                        Notification::info(t!(
+                                                                            "rooter.snippet_copied"
+                                                                        )),
+                                                                        cx,
+                                                                    );
+                                                                })
+                                                        },
+                                                    )
+                                                    .menu_element(
+                                                        Box::new(EmptyAction),
+                                                        move |_window, _cx| {
+                                                            let entity = snippet_entity.clone();
+
+                                                            div()
+                                                                .id("")
+                                                                .flex()
+                                                                .gap_2()
+                                                                .items_center()
+                                                                .child(
+                                                                    Icon::new(IconName::Plus)
+                                                                        .small(),
+                                                                )
+                                                                .child(
+                                                                    div()
+                                                                        .child(t!("rooter.add_from_snippet"))
+                                                                        .text_sm(),
+                                                                )
+                                                                .on_click(move |_ev, window, cx| {
+                                                                    remote_snippet_dialog::open_dialog(
+                                                                        entity.clone(),
                                                                         window,
                                                                         cx,
                                                                     );
-                                                                    cx.notify();
-                                                                },
-                                                            );
-                                                        })
-                                                },
-                                            )
-                                        }),
+                                                                })
+                                                        },
+                                                    )
+                                                    .separator()
+                                                    .menu_element(
+                                                        Box::new(EmptyAction),
+                                                        move |_window, cx| {
+                                                            let remote_name =
+                                                                s3_remote.remote_name.clone();
+                                                            let entity = entity.clone();
+
+                                                            div()
+                                                                .id("")
+                                                                .flex()
+                                                                .gap_2()
+                                                                .items_center()
+                                                                .text_color(cx.theme().danger)
+                                                                .child(
+                                                                    Icon::new(IconName::Delete)
+                                                                        .small(),
+                                                                )
+                                                                .child(
+                                                                    div()
+                                                                        .child(t!("rooter.delete_remote"))
+                                                                        .text_sm(),
+                                                                )
+                                                                .on_click(move |_ev, window, cx| {
+                                                                    let _ = entity.clone().update(
+                                                                        cx,
+                                                                        |this, cx| {
+                                                                            this.delete_remote(
+                                                                                remote_name.clone(),
+                                                                                window,
+                                                                                cx,
+                                                                            );
+                                                                            cx.notify();
+                                                                        },
+                                                                    );
+                                                                })
+                                                        },
+                                                    )
+                                                }),
+                                        ),
                                 )
                                 .on_click(cx.listener(move |this, _ev, window, cx| {
-                                    this.new_tab(s3_remote.clone(), window, cx);
+                                    // browsing would only fail, offer to fix the credentials
+                                    if expired {
+                                        cx.stop_propagation();
+                                        remote_dialog::open_dialog(
+                                            Some(s3_remote.clone()),
+                                            cx.weak_entity(),
+                                            window,
+                                            cx,
+                                        );
+                                        return;
+                                    }
+                                    this.new_tab(
+                                        s3_remote.clone(),
+                                        SharedString::new_static("/"),
+                                        window,
+                                        cx,
+                                    );
                                 }))
                         },
                     ),
@@ -493,8 +2204,8 @@ impl Rooter {
                         .bg(cx.theme().muted)
                         .child(Icon::empty().path("icons/server.svg").size_5()),
                 )
-                .child(div().text_lg().child("Select remote"))
-                .child(div().child("Select or create remote to start browsing"))
+                .child(div().text_lg().child(t!("rooter.select_remote")))
+                .child(div().child(t!("rooter.select_remote_hint")))
                 .child(remote_dialog::trigger(cx.weak_entity(), None)),
         )
     }
@@ -518,17 +2229,21 @@ impl Rooter {
                             .enumerate()
                             .map(|(index, remote)| {
                                 let remote = remote.clone();
+                                let status = self.tab_nav.tab_status(&remote, cx);
 
-                                Tab::new().label(remote.clone()).suffix(
-                                    Button::new(remote.clone())
-                                        .mr_2()
-                                        .icon(IconName::Close)
-                                        .xsmall()
-                                        .ghost()
-                                        .on_click(cx.listener(move |this, _ev, _window, cx| {
-                                            this.close_tab(index, cx);
-                                        })),
-                                )
+                                Tab::new()
+                                    .label(remote.clone())
+                                    .prefix(self.render_tab_status(index, status, cx))
+                                    .suffix(
+                                        Button::new(remote.clone())
+                                            .mr_2()
+                                            .icon(IconName::Close)
+                                            .xsmall()
+                                            .ghost()
+                                            .on_click(cx.listener(move |this, _ev, window, cx| {
+                                                this.request_close_tab(index, window, cx);
+                                            })),
+                                    )
                             }),
                     ),
             )
@@ -536,4 +2251,23 @@ impl Rooter {
                 this.child(view)
             })
     }
+
+    fn render_tab_status(
+        &self,
+        index: usize,
+        status: TabStatus,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        div().flex().items_center().ml_2().map(|this| match status {
+            TabStatus::Ok => this,
+            TabStatus::Loading => this.child(loading::loading_indicator(
+                SharedString::new(format!("tab-loading-{index}")),
+                Icon::new(IconName::LoaderCircle)
+                    .xsmall()
+                    .text_color(cx.theme().muted_foreground),
+                cx,
+            )),
+            TabStatus::Error => this.child(div().size_2().rounded_full().bg(cx.theme().danger)),
+        })
+    }
 }
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use balti_err::{AppResult, ErrorKind};
+use balti_s3::ObjectMeta;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, StyledExt, WindowExt, button::Button, dialog::Dialog, h_flex, v_flex,
+};
+
+use crate::{strings::t, ui::loading, util};
+
+/// Storage class S3 leaves out of the response
+const DEFAULT_STORAGE_CLASS: &str = "STANDARD";
+
+pub trait ObjectPropertiesDialog: Render {
+    /// Metadata of the object at `key`, `None` while it loads
+    fn properties(&self, key: &str) -> Option<AppResult<ObjectMeta>>;
+}
+
+pub fn dialog<T: ObjectPropertiesDialog>(
+    dialog: Dialog,
+    key: Arc<str>,
+    entity: WeakEntity<T>,
+    cx: &App,
+) -> Dialog {
+    let properties = entity
+        .read_with(cx, |this, _cx| this.properties(&key))
+        .ok()
+        .flatten();
+    let name = key.rsplit('/').next().unwrap_or_default().to_owned();
+
+    let content = match properties {
+        None => v_flex()
+            .gap_2()
+            .children((0..5).map(|_| loading::skeleton(px(20.), cx)))
+            .into_any_element(),
+        Some(Err(err)) if err.kind == ErrorKind::NotFound => {
+            note(t!("properties.gone"), cx.theme().muted_foreground)
+        }
+        Some(Err(err)) => note(
+            t!("properties.failed", error = err.message),
+            cx.theme().danger,
+        ),
+        Some(Ok(meta)) => render_meta(&key, meta, cx),
+    };
+
+    dialog
+        .rounded_lg()
+        .w(px(560.))
+        .title(t!("properties.title", name = name))
+        .v_flex()
+        .child(content)
+        .footer(|_, _, _, _cx| {
+            vec![
+                Button::new("close_dialog")
+                    .label(t!("common.close"))
+                    .on_click(|_, window, cx| {
+                        window.close_dialog(cx);
+                    }),
+            ]
+        })
+}
+
+fn render_meta(key: &str, meta: ObjectMeta, cx: &App) -> AnyElement {
+    let unknown = || t!("properties.unknown");
+    let metadata = if meta.metadata.is_empty() {
+        div()
+            .text_color(cx.theme().muted_foreground)
+            .child(t!("properties.no_metadata"))
+            .into_any_element()
+    } else {
+        v_flex()
+            .children(
+                meta.metadata
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}")),
+            )
+            .into_any_element()
+    };
+
+    v_flex()
+        .gap_1()
+        .child(info_row(t!("properties.key"), key.to_owned(), cx))
+        .child(info_row(
+            t!("properties.size"),
+            util::human_readable_size(meta.size),
+            cx,
+        ))
+        .child(info_row(
+            t!("properties.last_modified"),
+            meta.last_modified
+                .as_deref()
+                .map(str::to_owned)
+                .unwrap_or_else(unknown),
+            cx,
+        ))
+        .child(info_row(
+            t!("properties.content_type"),
+            meta.content_type
+                .as_deref()
+                .map(str::to_owned)
+                .unwrap_or_else(unknown),
+            cx,
+        ))
+        .child(info_row(
+            t!("properties.etag"),
+            meta.etag
+                .as_deref()
+                .map(str::to_owned)
+                .unwrap_or_else(unknown),
+            cx,
+        ))
+        .child(info_row(
+            t!("properties.storage_class"),
+            meta.storage_class
+                .as_deref()
+                .unwrap_or(DEFAULT_STORAGE_CLASS)
+                .to_owned(),
+            cx,
+        ))
+        .child(info_row(t!("properties.metadata"), metadata, cx))
+        .into_any_element()
+}
+
+fn info_row(label: String, value: impl IntoElement, cx: &App) -> impl IntoElement {
+    h_flex()
+        .items_start()
+        .gap_3()
+        .text_sm()
+        .child(
+            div()
+                .w(px(112.))
+                .flex_shrink_0()
+                .text_color(cx.theme().muted_foreground)
+                .child(label),
+        )
+        .child(div().min_w_0().child(value))
+}
+
+fn note(message: String, color: Hsla) -> AnyElement {
+    div()
+        .py_2()
+        .text_sm()
+        .text_color(color)
+        .child(message)
+        .into_any_element()
+}
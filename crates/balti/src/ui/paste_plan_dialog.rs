@@ -0,0 +1,298 @@
+use std::{cell::RefCell, rc::Rc};
+
+use balti_err::AppError;
+use futures::channel::oneshot;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, Selectable, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    dialog::Dialog,
+    h_flex,
+    notification::Notification,
+};
+
+use crate::{
+    clipboard::{ClipboardMode, PasteAction, PastePlan, PasteReason},
+    strings::t,
+    ui::error_notification,
+};
+
+/// Planned items show in a list this tall
+const LIST_HEIGHT: Pixels = px(320.);
+const ITEM_HEIGHT: Pixels = px(44.);
+
+/// Name the plan is exported under unless changed
+const EXPORT_FILE_NAME: &str = "paste-plan.json";
+
+/// Plan under review, the dialog keeps it across renders
+struct Review {
+    plan: PastePlan,
+    /// Action the list is narrowed to, everything for `None`
+    filter: Option<PasteAction>,
+    /// Answers [`review`] once the paste goes ahead
+    answer: Option<oneshot::Sender<PastePlan>>,
+}
+
+/// Lists what the paste does with every item, they can be left out before it runs.
+/// Resolves to the reviewed plan, `None` when cancelled or dismissed.
+pub fn review(
+    plan: PastePlan,
+    window: &mut Window,
+    cx: &mut App,
+) -> impl Future<Output = Option<PastePlan>> + use<> {
+    let (answer_tx, answer) = oneshot::channel();
+    let review = Rc::new(RefCell::new(Review {
+        plan,
+        filter: None,
+        answer: Some(answer_tx),
+    }));
+    // dismissing drops the dialog along with the sender, which answers `None`
+    window.open_dialog(cx, move |dialog_, _window, cx| {
+        dialog(dialog_, review.clone(), cx)
+    });
+
+    async move { answer.await.ok() }
+}
+
+fn action_label(action: Option<PasteAction>, mode: ClipboardMode) -> String {
+    match (action, mode) {
+        (None, _) => t!("paste_plan.all"),
+        (Some(PasteAction::Copy), ClipboardMode::Copy) => t!("paste_plan.copy"),
+        (Some(PasteAction::Copy), ClipboardMode::Cut) => t!("paste_plan.move"),
+        (Some(PasteAction::Replace), _) => t!("paste_plan.replace"),
+        (Some(PasteAction::Skip), _) => t!("paste_plan.skip"),
+    }
+}
+
+fn reason_label(reason: PasteReason) -> String {
+    match reason {
+        PasteReason::New => t!("paste_plan.reason_new"),
+        PasteReason::Exists => t!("paste_plan.reason_exists"),
+        PasteReason::FolderMarker => t!("paste_plan.reason_folder_marker"),
+        PasteReason::Excluded => t!("paste_plan.reason_excluded"),
+    }
+}
+
+fn dialog(dialog: Dialog, review: Rc<RefCell<Review>>, cx: &mut App) -> Dialog {
+    let muted = cx.theme().muted_foreground;
+    let border = cx.theme().sidebar_border;
+
+    let state = review.borrow();
+    let plan = &state.plan;
+    let filter = state.filter;
+    let shown = Rc::new(
+        plan.filtered(filter)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>(),
+    );
+    let all_shown_included = shown.iter().all(|index| plan.items[*index].included);
+
+    let tabs = [
+        None,
+        Some(PasteAction::Copy),
+        Some(PasteAction::Replace),
+        Some(PasteAction::Skip),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, action)| {
+        let review = review.clone();
+
+        Button::new(("paste-plan-filter", i))
+            .small()
+            .ghost()
+            .label(format!(
+                "{} ({})",
+                action_label(action, plan.mode),
+                plan.count(action)
+            ))
+            .selected(filter == action)
+            .on_click(move |_ev, window, _cx| {
+                review.borrow_mut().filter = action;
+                window.refresh();
+            })
+    })
+    .collect::<Vec<_>>();
+
+    let _review = review.clone();
+    let toggle_shown = Checkbox::new("paste-plan-toggle-shown")
+        .label(t!("paste_plan.include_shown"))
+        .checked(!shown.is_empty() && all_shown_included)
+        .disabled(shown.is_empty())
+        .on_click(move |checked, window, _cx| {
+            let mut review = _review.borrow_mut();
+            let filter = review.filter;
+            review.plan.set_included(filter, *checked);
+            window.refresh();
+        });
+
+    let _review = review.clone();
+    let _shown = shown.clone();
+    let items = uniform_list(
+        "paste-plan-items",
+        shown.len(),
+        move |range, _window, cx| {
+            let review = _review.clone();
+            let state = _review.borrow();
+
+            _shown[range]
+                .iter()
+                .map(|&index| {
+                    let planned = &state.plan.items[index];
+                    let review = review.clone();
+
+                    h_flex()
+                        .h(ITEM_HEIGHT)
+                        .gap_3()
+                        .px_2()
+                        .border_b_1()
+                        .border_color(cx.theme().sidebar_border)
+                        .text_sm()
+                        .child(
+                            Checkbox::new(("paste-plan-item", index))
+                                .checked(planned.included)
+                                .on_click(move |checked, window, _cx| {
+                                    review.borrow_mut().plan.items[index].included = *checked;
+                                    window.refresh();
+                                }),
+                        )
+                        .child(
+                            div()
+                                .w(px(64.))
+                                .flex_shrink_0()
+                                .map(|this| match planned.action() {
+                                    PasteAction::Replace => this.text_color(cx.theme().warning),
+                                    PasteAction::Skip => {
+                                        this.text_color(cx.theme().muted_foreground)
+                                    }
+                                    PasteAction::Copy => this,
+                                })
+                                .child(action_label(Some(planned.action()), state.plan.mode)),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .flex_col()
+                                .min_w_0()
+                                .child(
+                                    div()
+                                        .font_medium()
+                                        .truncate()
+                                        .child(SharedString::new(planned.item.to_key.clone())),
+                                )
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(cx.theme().muted_foreground)
+                                        .child(reason_label(planned.reason())),
+                                ),
+                        )
+                })
+                .collect()
+        },
+    )
+    .h(LIST_HEIGHT);
+
+    let title = t!(
+        "paste_plan.title",
+        count = plan.items.len(),
+        from = plan.from,
+        to = plan.to
+    );
+    drop(state);
+
+    dialog
+        .rounded_lg()
+        .w(px(640.))
+        .title(title)
+        .v_flex()
+        .gap_2()
+        .child(h_flex().gap_1().children(tabs))
+        .child(toggle_shown)
+        .child(
+            div()
+                .border_1()
+                .border_color(border)
+                .rounded_md()
+                .map(|this| {
+                    if shown.is_empty() {
+                        this.p_2()
+                            .text_sm()
+                            .text_color(muted)
+                            .child(t!("paste_plan.nothing_shown"))
+                    } else {
+                        this.child(items)
+                    }
+                }),
+        )
+        .footer(move |_, _, _, _cx| {
+            let _review = review.clone();
+            let review = review.clone();
+            let pasted = {
+                let plan = &review.borrow().plan;
+                plan.items.len() - plan.count(Some(PasteAction::Skip))
+            };
+
+            let export = Button::new("export_plan")
+                .label(t!("paste_plan.export"))
+                .on_click(move |_ev, window, cx| save_plan(&_review.borrow().plan, window, cx));
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("paste_plan.paste", count = pasted))
+                .disabled(pasted == 0)
+                .on_click(move |_ev, window, cx| {
+                    let mut review = review.borrow_mut();
+                    if let Some(answer) = review.answer.take() {
+                        let _ = answer.send(review.plan.clone());
+                    }
+                    drop(review);
+                    window.close_dialog(cx);
+                });
+
+            vec![export, cancel, ok]
+        })
+}
+
+/// Saves the plan as JSON where the user picks
+fn save_plan(plan: &PastePlan, window: &mut Window, cx: &mut App) {
+    let content = match plan.export() {
+        Ok(content) => content,
+        Err(err) => {
+            window.push_notification(
+                error_notification(&err).title(t!("paste_plan.export_failed")),
+                cx,
+            );
+            return;
+        }
+    };
+    let directory = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default();
+    let path_prompt = cx.prompt_for_new_path(&directory, Some(EXPORT_FILE_NAME));
+
+    window
+        .spawn(cx, async move |cx| {
+            let Ok(Ok(Some(path))) = path_prompt.await else {
+                return;
+            };
+            let result = std::fs::write(&path, content).map_err(|err| AppError::err(err));
+            let _ = cx.update(|window, cx| {
+                let notification = match result {
+                    Ok(_) => {
+                        Notification::success(t!("paste_plan.exported", path = path.display()))
+                    }
+                    Err(err) => error_notification(&err).title(t!("paste_plan.export_failed")),
+                };
+                window.push_notification(notification, cx);
+            });
+        })
+        .detach();
+}
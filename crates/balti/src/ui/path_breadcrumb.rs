@@ -0,0 +1,140 @@
+use std::rc::Rc;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Sizable, StyledExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+};
+
+/// Called with the index of the clicked segment, `0` being the first
+type OnSegmentClick = Rc<dyn Fn(usize, &mut Window, &mut App)>;
+
+/// Path as clickable segments separated by slashes, like "bucket / logs / 2024 /", with
+/// the last segment highlighted as the current one
+#[derive(IntoElement)]
+pub struct PathBreadcrumb {
+    id: ElementId,
+    style: StyleRefinement,
+    segments: Vec<SharedString>,
+    highlight_last: bool,
+    /// Segments shown before the middle ones fold into "…"
+    max_segments: Option<usize>,
+    on_click: Option<OnSegmentClick>,
+}
+
+impl PathBreadcrumb {
+    pub fn new(
+        id: impl Into<ElementId>,
+        segments: impl IntoIterator<Item = impl Into<SharedString>>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            style: StyleRefinement::default(),
+            segments: segments.into_iter().map(Into::into).collect(),
+            highlight_last: true,
+            max_segments: None,
+            on_click: None,
+        }
+    }
+
+    /// Segments of an object key, [`key_prefix_at`] tells where each one leads
+    pub fn key(id: impl Into<ElementId>, key: &str) -> Self {
+        Self::new(id, key.split('/').filter(|segment| !segment.is_empty())).highlight_last(false)
+    }
+
+    pub fn highlight_last(mut self, highlight: bool) -> Self {
+        self.highlight_last = highlight;
+        self
+    }
+
+    /// Past `max` segments the ones after the first fold into "…", leading to the
+    /// deepest of them, so the current one stays in view on narrow windows
+    pub fn max_segments(mut self, max: usize) -> Self {
+        self.max_segments = Some(max.max(2));
+        self
+    }
+
+    pub fn on_click(mut self, handler: impl Fn(usize, &mut Window, &mut App) + 'static) -> Self {
+        self.on_click = Some(Rc::new(handler));
+        self
+    }
+}
+
+impl Styled for PathBreadcrumb {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl RenderOnce for PathBreadcrumb {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let last = self.segments.len().saturating_sub(1);
+        let highlight_last = self.highlight_last;
+        // the first segment, then the last `max - 1`
+        let folded = match self.max_segments {
+            Some(max) if self.segments.len() > max => 1..self.segments.len() + 1 - max,
+            _ => 0..0,
+        };
+        let folded_path = self.segments[folded.clone()]
+            .iter()
+            .map(|segment| segment.as_str())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        h_flex()
+            .id(self.id)
+            .gap_1()
+            .refine_style(&self.style)
+            .children(
+                self.segments
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, name)| {
+                        if folded.contains(&i) && i + 1 != folded.end {
+                            return None;
+                        }
+                        let on_click = self.on_click.clone();
+                        let (name, tooltip) = if folded.contains(&i) {
+                            (SharedString::new_static("…"), Some(folded_path.clone()))
+                        } else {
+                            (name, None)
+                        };
+
+                        Some(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Button::new(SharedString::new(i.to_string()))
+                                        .label(name)
+                                        .ghost()
+                                        .small()
+                                        .px_1()
+                                        .when(highlight_last && i == last, |this| {
+                                            this.bg(cx.theme().primary.opacity(0.2))
+                                                .border_1()
+                                                .border_color(cx.theme().primary)
+                                                .font_medium()
+                                        })
+                                        .when_some(tooltip, |this, tooltip| this.tooltip(tooltip))
+                                        .when_some(on_click, |this, on_click| {
+                                            this.on_click(move |_ev, window, cx| {
+                                                on_click(i, window, cx)
+                                            })
+                                        }),
+                                )
+                                .child(div().text_color(cx.theme().muted_foreground).child("/")),
+                        )
+                    }),
+            )
+    }
+}
+
+/// Where segment `depth` of `key` leads: its folder prefix like `a/b/` for depth 1 of
+/// `a/b/c.txt`, or the key itself for the last segment
+pub fn key_prefix_at(key: &str, depth: usize) -> &str {
+    key.match_indices('/')
+        .filter(|(at, _)| *at > 0)
+        .nth(depth)
+        .map_or(key, |(at, _)| &key[..=at])
+}
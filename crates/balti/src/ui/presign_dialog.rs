@@ -0,0 +1,265 @@
+use std::{rc::Rc, sync::Arc, time::Duration};
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, IconName, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    dialog::Dialog,
+    form::{field, v_form},
+    h_flex,
+    input::{Input, InputState},
+    v_flex,
+};
+
+use crate::{
+    settings::{PresignPreset, Settings},
+    strings::t,
+    ui::dialog_form::{self, DialogFormExt, Submit},
+};
+
+/// Expiry the picker starts at
+const DEFAULT_EXPIRY: &str = "1h";
+
+pub trait PresignDialog: Render {
+    /// Copies a link to `key` that works without credentials for `expires_in`, one that
+    /// saves the file rather than opening it with `force_download`
+    fn copy_presigned_url(
+        &mut self,
+        key: Arc<str>,
+        expires_in: Duration,
+        force_download: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+}
+
+/// What the inputs describe, as a preset named after `name_input`
+fn entered(
+    name_input: &Entity<InputState>,
+    expiry_input: &Entity<InputState>,
+    force_download: &Entity<bool>,
+    cx: &App,
+) -> PresignPreset {
+    PresignPreset {
+        name: name_input.read(cx).value().trim().to_owned(),
+        expiry: expiry_input.read(cx).value().trim().to_owned(),
+        force_download: *force_download.read(cx),
+    }
+}
+
+pub fn open_dialog<T: PresignDialog>(
+    entity: WeakEntity<T>,
+    key: Arc<str>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let expiry_input = cx.new(|cx| InputState::new(window, cx).default_value(DEFAULT_EXPIRY));
+    let name_input =
+        cx.new(|cx| InputState::new(window, cx).placeholder(t!("presign.preset_name_hint")));
+    let force_download = cx.new(|_cx| false);
+
+    let submit = submit(
+        entity.clone(),
+        key.clone(),
+        name_input.clone(),
+        expiry_input.clone(),
+        force_download.clone(),
+    );
+    dialog_form::bind_fields(&[expiry_input.clone()], submit.clone(), window, cx);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(
+            dialog,
+            entity.clone(),
+            key.clone(),
+            expiry_input.clone(),
+            name_input.clone(),
+            force_download.clone(),
+            submit.clone(),
+            cx,
+        )
+    });
+}
+
+/// Copies the link the inputs describe
+fn submit<T: PresignDialog>(
+    entity: WeakEntity<T>,
+    key: Arc<str>,
+    name_input: Entity<InputState>,
+    expiry_input: Entity<InputState>,
+    force_download: Entity<bool>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let preset = entered(&name_input, &expiry_input, &force_download, cx);
+        copy(entity.clone(), key.clone(), &preset, window, cx);
+    })
+}
+
+/// Copies the link `preset` describes and closes the dialog, unless its expiry is invalid
+fn copy<T: PresignDialog>(
+    entity: WeakEntity<T>,
+    key: Arc<str>,
+    preset: &PresignPreset,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let Some(expires_in) = preset.expires_in() else {
+        return;
+    };
+
+    let force_download = preset.force_download;
+    let _ = entity.update(cx, |this, cx| {
+        this.copy_presigned_url(key, expires_in, force_download, window, cx);
+    });
+    window.close_dialog(cx);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dialog<T: PresignDialog>(
+    dialog: Dialog,
+    entity: WeakEntity<T>,
+    key: Arc<str>,
+    expiry_input: Entity<InputState>,
+    name_input: Entity<InputState>,
+    force_download: Entity<bool>,
+    submit: Submit,
+    cx: &mut App,
+) -> Dialog {
+    let muted = cx.theme().muted_foreground;
+    let presets = Settings::get(cx).presign_presets.clone();
+    let current = entered(&name_input, &expiry_input, &force_download, cx);
+    let is_valid = current.expires_in().is_some();
+    let can_save = is_valid && !current.name.is_empty();
+
+    dialog
+        .submit_on_enter(submit.clone())
+        .rounded_lg()
+        .w(px(480.))
+        .title(t!("presign.title"))
+        .v_flex()
+        .gap_3()
+        .child(div().text_sm().text_color(muted).child(key.to_string()))
+        .child(
+            v_flex()
+                .gap_1()
+                .child(div().text_sm().font_medium().child(t!("presign.presets")))
+                .when(presets.is_empty(), |this| {
+                    this.child(
+                        div()
+                            .text_sm()
+                            .text_color(muted)
+                            .child(t!("presign.no_presets")),
+                    )
+                })
+                .child(
+                    h_flex()
+                        .flex_wrap()
+                        .gap_2()
+                        .children(presets.into_iter().enumerate().map(|(i, preset)| {
+                            let (entity, key) = (entity.clone(), key.clone());
+                            let remove_name = preset.name.clone();
+                            let label = if preset.force_download {
+                                t!(
+                                    "presign.preset_download",
+                                    name = preset.name,
+                                    expiry = preset.expiry
+                                )
+                            } else {
+                                t!("presign.preset", name = preset.name, expiry = preset.expiry)
+                            };
+
+                            h_flex()
+                                .child(
+                                    Button::new(("presign-preset", i))
+                                        .small()
+                                        .outline()
+                                        .label(label)
+                                        .disabled(preset.expires_in().is_none())
+                                        .on_click(move |_ev, window, cx| {
+                                            copy(entity.clone(), key.clone(), &preset, window, cx);
+                                        }),
+                                )
+                                .child(
+                                    Button::new(("remove-presign-preset", i))
+                                        .xsmall()
+                                        .ghost()
+                                        .icon(IconName::Close)
+                                        .tooltip(t!("presign.remove_preset"))
+                                        .on_click(move |_ev, window, cx| {
+                                            Settings::update(cx, |settings| {
+                                                settings
+                                                    .presign_presets
+                                                    .retain(|preset| preset.name != remove_name);
+                                            });
+                                            window.refresh();
+                                        }),
+                                )
+                        })),
+                ),
+        )
+        .child(
+            v_form()
+                .child(
+                    field()
+                        .label(t!("presign.expires_in"))
+                        .child(Input::new(&expiry_input))
+                        .description(if is_valid {
+                            t!("presign.expires_in_hint")
+                        } else {
+                            t!("presign.invalid_expiry")
+                        }),
+                )
+                .child(
+                    field().child(
+                        Checkbox::new("force_download")
+                            .label(t!("presign.force_download"))
+                            .checked(current.force_download)
+                            .on_click(move |checked, window, cx| {
+                                force_download.update(cx, |this, _cx| *this = *checked);
+                                window.refresh();
+                            }),
+                    ),
+                )
+                .child(
+                    field().label(t!("presign.save_as")).child(
+                        h_flex()
+                            .gap_2()
+                            .child(div().flex_1().child(Input::new(&name_input)))
+                            .child(
+                                Button::new("save_presign_preset")
+                                    .label(t!("presign.save_preset"))
+                                    .disabled(!can_save)
+                                    .on_click(move |_ev, window, cx| {
+                                        let preset = current.clone();
+                                        // saving under a taken name replaces that preset
+                                        Settings::update(cx, |settings| {
+                                            settings
+                                                .presign_presets
+                                                .retain(|saved| saved.name != preset.name);
+                                            settings.presign_presets.push(preset);
+                                        });
+                                        window.refresh();
+                                    }),
+                            ),
+                    ),
+                ),
+        )
+        .footer(move |_, _, _, _cx| {
+            let submit = submit.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("presign.copy"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
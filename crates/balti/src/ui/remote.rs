@@ -1,45 +1,202 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
 use balti_s3::S3Remote;
 use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    ActiveTheme, Icon, Sizable, StyledExt,
+    ActiveTheme, Disableable, Icon, IconName, Selectable, Sizable, WindowExt,
     button::{Button, ButtonVariants},
     h_flex,
+    input::Input,
+    menu::DropdownMenu,
+    notification::Notification,
 };
 
 use crate::{
-    nav::{BucketNav, TabId},
-    ui::browse::BrowseUi,
+    config,
+    health::RemoteHealth,
+    nav::{BucketNav, PendingState, TabId, TabStatus},
+    settings::{Settings, ViewMode, ViewPrefs},
+    strings::t,
+    ui::{
+        EmptyAction, browse::BrowseUi, error_notification, loading, path_breadcrumb::PathBreadcrumb,
+    },
 };
 
+/// Intervals offered for refreshing the current view automatically
+const AUTO_REFRESH_INTERVALS: [(u64, &str); 4] =
+    [(15, "15s"), (30, "30s"), (60, "1m"), (300, "5m")];
+/// How often the auto refresh checks in, also keeps the "last refreshed" label current
+const AUTO_REFRESH_TICK: Duration = Duration::from_secs(1);
+/// Breadcrumb segments shown before the middle ones fold away
+const MAX_BREADCRUMB_SEGMENTS: usize = 6;
+
 pub struct BrowseRefreshEvent(pub SharedString);
+/// Emitted by a [`BrowseUi`] whenever its listing state changes
+pub struct BrowseStatusEvent(pub SharedString, pub TabStatus);
+/// Emitted by a [`BrowseUi`] to open the prefix in a separate tab
+pub struct BrowseOpenTabEvent(pub SharedString);
+/// Emitted by a [`BrowseUi`] when its prefix no longer has any object under it
+pub struct BrowseFolderGoneEvent(pub SharedString);
+/// Emitted by a [`BrowseUi`] to move the remote to the bucket's region and retry the prefix
+pub struct BrowseSwitchRegionEvent(pub SharedString, pub Arc<str>);
+/// Emitted by a [`BrowseUi`] to open the prefix and scroll to the object at the key
+pub struct BrowseRevealEvent(pub SharedString, pub Arc<str>);
 pub struct BrowseNav;
 impl EventEmitter<BrowseRefreshEvent> for BrowseNav {}
+impl EventEmitter<BrowseStatusEvent> for BrowseNav {}
+impl EventEmitter<BrowseOpenTabEvent> for BrowseNav {}
+impl EventEmitter<BrowseFolderGoneEvent> for BrowseNav {}
+impl EventEmitter<BrowseSwitchRegionEvent> for BrowseNav {}
+impl EventEmitter<BrowseRevealEvent> for BrowseNav {}
+
+/// Asks the tab owner to open a new tab for the remote at the prefix
+pub struct OpenTabEvent {
+    pub remote: S3Remote,
+    pub prefix: SharedString,
+}
+
+/// Emitted whenever the tab moves to another prefix
+pub struct NavChangedEvent;
+
+/// Asks the remote owner to persist the remote's new region, then reopen the prefix
+pub struct SwitchRegionEvent {
+    pub remote: S3Remote,
+    pub region: Arc<str>,
+    pub prefix: SharedString,
+}
+
+/// View a refresh replaced, put back if the refresh gets cancelled before it lists
+struct Refresh {
+    prefix: SharedString,
+    view: Entity<BrowseUi>,
+    previous: Entity<BrowseUi>,
+}
+
+/// What cancelling the listing shown goes back to
+enum CancelListing {
+    /// Stops refreshing what's shown, which stays
+    Stop,
+    /// Puts back the view a refresh replaced
+    Restore(Entity<BrowseUi>),
+    /// Steps back to the history entry at the index
+    Back(usize),
+}
 
 pub struct RemoteUi {
     s3_remote: S3Remote,
+    root_prefix: SharedString,
     nav: Entity<BucketNav>,
     browse_nav: Entity<BrowseNav>,
     header_scroll_handle: ScrollHandle,
+    status: Entity<TabStatus>,
+    prefix_statuses: HashMap<SharedString, TabStatus>,
+    view_prefs: Entity<ViewPrefs>,
+    auto_refresh: Option<Duration>,
+    refresh: Option<Refresh>,
+    /// Set on every draw, tabs in the background aren't drawn and skip auto refresh
+    rendered: bool,
+    _auto_refresh_task: Option<Task<()>>,
     _subcriptions: Vec<Subscription>,
 }
 
 impl RemoteUi {
-    fn new(s3_remote: S3Remote, window: &mut Window, cx: &mut Context<Self>) -> Self {
+    fn new(
+        s3_remote: S3Remote,
+        root_prefix: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        // scoped remotes can't browse above their root prefix
+        let root_prefix = if root_prefix == "/" {
+            SharedString::new(s3_remote.root())
+        } else {
+            root_prefix
+        };
         let browse_nav = cx.new(|_| BrowseNav {});
 
-        let nav_sub = cx.subscribe_in(&browse_nav, window, |this, _entity, event, window, cx| {
-            this.nav.update(cx, |nav, cx| {
-                nav.push(
-                    BrowseUi::view(
-                        this.browse_nav.clone(),
-                        this.s3_remote.clone(),
-                        event.0.clone(),
-                        window,
-                        cx,
-                    ),
+        let nav_sub = cx.subscribe_in(
+            &browse_nav,
+            window,
+            |this, _entity, event: &BrowseRefreshEvent, window, cx| {
+                this.open_prefix(event.0.clone(), window, cx);
+            },
+        );
+
+        let status_sub = cx.subscribe(
+            &browse_nav,
+            |this, _entity, event: &BrowseStatusEvent, cx| {
+                this.prefix_statuses.insert(event.0.clone(), event.1);
+                // the refresh listed, there's nothing to put back anymore
+                if event.1 != TabStatus::Loading
+                    && this
+                        .refresh
+                        .as_ref()
+                        .is_some_and(|refresh| refresh.prefix == event.0)
+                {
+                    this.refresh = None;
+                }
+                this.sync_status(cx);
+                cx.notify();
+            },
+        );
+
+        let open_tab_sub = cx.subscribe(
+            &browse_nav,
+            |this, _entity, event: &BrowseOpenTabEvent, cx| {
+                cx.emit(OpenTabEvent {
+                    remote: this.s3_remote.clone(),
+                    prefix: event.0.clone(),
+                });
+            },
+        );
+
+        let switch_region_sub = cx.subscribe(
+            &browse_nav,
+            |this, _entity, event: &BrowseSwitchRegionEvent, cx| {
+                cx.emit(SwitchRegionEvent {
+                    remote: this.s3_remote.clone(),
+                    region: event.1.clone(),
+                    prefix: event.0.clone(),
+                });
+            },
+        );
+
+        let folder_gone_sub = cx.subscribe_in(
+            &browse_nav,
+            window,
+            |this, _entity, event: &BrowseFolderGoneEvent, window, cx| {
+                this.on_folder_gone(event.0.clone(), window, cx);
+            },
+        );
+
+        let reveal_sub = cx.subscribe_in(
+            &browse_nav,
+            window,
+            |this, _entity, event: &BrowseRevealEvent, window, cx| {
+                this.go_to(event.0.clone(), Some(event.1.clone()), window, cx);
+            },
+        );
+
+        let view_prefs = cx.new(|_| {
+            config::parse_view_prefs(&s3_remote.remote_name).unwrap_or_else(|err| {
+                tracing::warn!("Using default view preferences: {}", err.message);
+                ViewPrefs::default()
+            })
+        });
+        let prefs_sub = cx.observe_in(&view_prefs, window, |this, prefs, window, cx| {
+            let prefs = prefs.read(cx).clone();
+            if let Err(err) = config::save_view_prefs(&this.s3_remote.remote_name, &prefs) {
+                window.push_notification(
+                    error_notification(&err).title(t!("remote.save_view_failed")),
                     cx,
                 );
-            });
+            }
+
+            if this.auto_refresh != prefs.auto_refresh() {
+                this.auto_refresh = prefs.auto_refresh();
+                this.start_auto_refresh(window, cx);
+            }
+            cx.notify();
         });
 
         let nav = cx.new(|cx| {
@@ -47,7 +204,8 @@ impl RemoteUi {
                 BrowseUi::view(
                     browse_nav.clone(),
                     s3_remote.clone(),
-                    SharedString::new_static("/"),
+                    view_prefs.clone(),
+                    root_prefix.clone(),
                     window,
                     cx,
                 ),
@@ -55,30 +213,384 @@ impl RemoteUi {
             )
         });
 
-        Self {
+        let nav_changed_sub = cx.observe(&nav, |_this, _nav, cx| cx.emit(NavChangedEvent));
+        // refreshing waits out the retry cooldown of a degraded remote
+        let health_sub = cx.observe(&RemoteHealth::entity(cx), |_this, _health, cx| cx.notify());
+
+        let auto_refresh = view_prefs.read(cx).auto_refresh();
+
+        let mut this = Self {
             s3_remote,
+            root_prefix,
             nav,
             browse_nav,
             header_scroll_handle: ScrollHandle::new(),
-            _subcriptions: vec![nav_sub],
+            status: cx.new(|_| TabStatus::default()),
+            prefix_statuses: HashMap::new(),
+            view_prefs,
+            auto_refresh,
+            refresh: None,
+            rendered: false,
+            _auto_refresh_task: None,
+            _subcriptions: vec![
+                nav_sub,
+                status_sub,
+                open_tab_sub,
+                switch_region_sub,
+                folder_gone_sub,
+                reveal_sub,
+                prefs_sub,
+                nav_changed_sub,
+                health_sub,
+            ],
+        };
+        this.start_auto_refresh(window, cx);
+        this
+    }
+
+    fn set_auto_refresh(&mut self, interval: Option<Duration>, cx: &mut Context<Self>) {
+        self.view_prefs.update(cx, |prefs, cx| {
+            prefs.auto_refresh = interval.map(|interval| interval.as_secs());
+            cx.notify();
+        });
+    }
+
+    /// Replaces the remote's view preferences, the observer saves them
+    pub fn set_view_prefs(&mut self, prefs: ViewPrefs, cx: &mut Context<Self>) {
+        self.view_prefs.update(cx, |current, cx| {
+            *current = prefs;
+            cx.notify();
+        });
+    }
+
+    /// Puts the remote's view preferences back to the defaults
+    fn reset_view_prefs(&mut self, cx: &mut Context<Self>) {
+        self.set_view_prefs(ViewPrefs::default(), cx);
+    }
+
+    fn start_auto_refresh(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self._auto_refresh_task = self.auto_refresh.map(|interval| {
+            cx.spawn_in(window, async move |this, cx| {
+                loop {
+                    cx.background_executor().timer(AUTO_REFRESH_TICK).await;
+
+                    let result = this.update_in(cx, |this, window, cx| {
+                        this.auto_refresh_tick(interval, window, cx);
+                    });
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            })
+        });
+    }
+
+    fn auto_refresh_tick(
+        &mut self,
+        interval: Duration,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !std::mem::take(&mut self.rendered) {
+            return;
+        }
+
+        if let Some(view) = self.current_browse(cx) {
+            view.update(cx, |view, cx| view.auto_refresh(interval, window, cx));
+        }
+        cx.notify();
+    }
+
+    /// Remote name and the prefix shown, like "prod-eu / exports/2024"
+    pub fn location(&self, cx: &App) -> String {
+        let prefix = self
+            .nav
+            .read(cx)
+            .active_view()
+            .map(|prefix| prefix.trim_matches('/').to_owned())
+            .unwrap_or_default();
+        if prefix.is_empty() {
+            return self.s3_remote.remote_name.to_string();
+        }
+        format!("{} / {prefix}", self.s3_remote.remote_name)
+    }
+
+    pub fn remote(&self) -> &S3Remote {
+        &self.s3_remote
+    }
+
+    /// Shows `prefix` in this tab and scrolls to the object at `reveal`, `false` when
+    /// it's above the prefix the tab was opened at
+    pub fn go_to(
+        &mut self,
+        prefix: SharedString,
+        reveal: Option<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if self.root_prefix != "/" && !prefix.starts_with(self.root_prefix.as_ref()) {
+            return false;
+        }
+        let browse = self.open_prefix(prefix, window, cx);
+        if let Some(key) = reveal {
+            browse.update(cx, |browse, cx| browse.reveal(key, window, cx));
+        }
+        true
+    }
+
+    /// Pushes a fresh listing of `prefix` onto the history
+    fn open_prefix(
+        &mut self,
+        prefix: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Entity<BrowseUi> {
+        // the folder left behind stays in history, stop filling it in
+        if let Some(browse) = self.current_browse(cx) {
+            browse.update(cx, |browse, cx| browse.stop_listing(cx));
+        }
+        let browse = BrowseUi::view(
+            self.browse_nav.clone(),
+            self.s3_remote.clone(),
+            self.view_prefs.clone(),
+            prefix,
+            window,
+            cx,
+        );
+        self.nav.update(cx, |nav, cx| nav.push(browse.clone(), cx));
+        self.sync_status(cx);
+        browse
+    }
+
+    /// Replaces the view shown with a fresh listing of its prefix. The view replaced
+    /// comes back when the listing gets cancelled before its first page.
+    fn refresh(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        RemoteHealth::note_retry(&self.s3_remote.remote_name, cx);
+        let previous = self.current_browse(cx);
+        if let Some(previous) = &previous {
+            previous.update(cx, |browse, cx| browse.stop_listing(cx));
+        }
+        let query = previous
+            .as_ref()
+            .map(|previous| previous.read(cx).search_query(cx))
+            .unwrap_or_default();
+
+        let mut fresh = None;
+        self.nav.update(cx, |nav, cx| {
+            nav.refresh_active_view(|prefix| {
+                let view = BrowseUi::view(
+                    self.browse_nav.clone(),
+                    self.s3_remote.clone(),
+                    self.view_prefs.clone(),
+                    prefix.clone(),
+                    window,
+                    cx,
+                );
+                fresh = Some((prefix.clone(), view.clone()));
+                view
+            });
+            cx.notify();
+        });
+        // the fresh view searches again
+        if let Some((_, view)) = &fresh
+            && !query.trim().is_empty()
+        {
+            view.update(cx, |browse, cx| browse.search_for(query, window, cx));
+        }
+        self.refresh = previous
+            .zip(fresh)
+            .map(|(previous, (prefix, view))| Refresh {
+                prefix,
+                view,
+                previous,
+            });
+    }
+
+    /// `None` while nothing lists from the start, or when the first listing of the
+    /// tab has nothing to go back to
+    fn cancel_listing_target(&self, cx: &App) -> Option<CancelListing> {
+        let browse = self.current_browse(cx)?;
+        if !browse.read(cx).is_listing() {
+            return None;
+        }
+        if browse.read(cx).has_listed() {
+            return Some(CancelListing::Stop);
+        }
+        if let Some(refresh) = self
+            .refresh
+            .as_ref()
+            .filter(|refresh| refresh.view == browse)
+        {
+            return Some(CancelListing::Restore(refresh.previous.clone()));
+        }
+
+        let nav = self.nav.read(cx);
+        let active = nav.active_view()?;
+        let index = nav
+            .stack()
+            .iter()
+            .position(|(_, prefix)| prefix == active)?;
+        index.checked_sub(1).map(CancelListing::Back)
+    }
+
+    /// Stops the listing shown and goes back to what was shown before it. Does nothing
+    /// when the listing landed in the meantime.
+    fn cancel_listing(&mut self, cx: &mut Context<Self>) {
+        let Some(target) = self.cancel_listing_target(cx) else {
+            return;
+        };
+        if let Some(browse) = self.current_browse(cx) {
+            browse.update(cx, |browse, cx| browse.stop_listing(cx));
+        }
+
+        match target {
+            CancelListing::Stop => {}
+            CancelListing::Restore(previous) => {
+                self.refresh = None;
+                self.nav.update(cx, |nav, cx| {
+                    nav.refresh_active_view(|_prefix| previous.clone());
+                    cx.notify();
+                });
+            }
+            CancelListing::Back(index) => {
+                let popped = self.nav.update(cx, |nav, cx| {
+                    cx.notify();
+                    nav.trim(index)
+                });
+                stop_listings(popped, cx);
+            }
         }
+        self.sync_status(cx);
+        cx.notify();
     }
 
-    pub fn view(s3_remote: S3Remote, window: &mut Window, cx: &mut App) -> Entity<Self> {
-        cx.new(|cx| Self::new(s3_remote, window, cx))
+    /// Listing of the prefix currently shown
+    pub fn current_browse(&self, cx: &App) -> Option<Entity<BrowseUi>> {
+        self.nav
+            .read(cx)
+            .current_view()
+            .cloned()
+            .and_then(|view| view.downcast::<BrowseUi>().ok())
+    }
+
+    /// Steps back to the parent of a folder that vanished, with a fresh listing
+    fn on_folder_gone(
+        &mut self,
+        prefix: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(index) = self
+            .nav
+            .read(cx)
+            .stack()
+            .iter()
+            .position(|(_, p)| p == &prefix)
+        else {
+            return;
+        };
+        if index == 0 {
+            return;
+        }
+
+        let popped = self.nav.update(cx, |nav, cx| {
+            let popped = nav.trim(index - 1);
+            nav.refresh_active_view(|prefix| {
+                BrowseUi::view(
+                    self.browse_nav.clone(),
+                    self.s3_remote.clone(),
+                    self.view_prefs.clone(),
+                    prefix.clone(),
+                    window,
+                    cx,
+                )
+            });
+            cx.notify();
+            popped
+        });
+        stop_listings(popped, cx);
+        self.sync_status(cx);
+
+        window.push_notification(
+            Notification::info(format!(
+                "\"{}\" no longer exists, it was removed along with its last object",
+                prefix.trim_matches('/')
+            )),
+            cx,
+        );
+    }
+
+    /// Mirror the status of the currently shown prefix into the tab status
+    fn sync_status(&mut self, cx: &mut Context<Self>) {
+        let status = self
+            .nav
+            .read(cx)
+            .active_view()
+            .and_then(|prefix| self.prefix_statuses.get(prefix))
+            .copied()
+            .unwrap_or_default();
+
+        self.status.update(cx, |this, cx| {
+            if *this != status {
+                *this = status;
+                cx.notify();
+            }
+        });
+    }
+
+    pub fn view(
+        s3_remote: S3Remote,
+        root_prefix: SharedString,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        cx.new(|cx| Self::new(s3_remote, root_prefix, window, cx))
     }
 }
 
+impl EventEmitter<OpenTabEvent> for RemoteUi {}
+impl EventEmitter<SwitchRegionEvent> for RemoteUi {}
+impl EventEmitter<NavChangedEvent> for RemoteUi {}
+
 impl TabId for RemoteUi {
     fn id(&self) -> SharedString {
+        if self.root_prefix.as_ref() == self.s3_remote.root().as_ref() {
+            self.group()
+        } else {
+            SharedString::new(format!(
+                "{} — {}",
+                self.s3_remote.remote_name,
+                self.root_prefix.trim_matches('/')
+            ))
+        }
+    }
+
+    fn group(&self) -> SharedString {
         SharedString::new(self.s3_remote.remote_name.clone())
     }
+
+    fn status(&self) -> Entity<TabStatus> {
+        self.status.clone()
+    }
+
+    /// Listings left behind in history may still hold a selection or run operations
+    fn pending_state(&self, cx: &App) -> PendingState {
+        self.nav
+            .read(cx)
+            .views()
+            .filter_map(|view| view.clone().downcast::<BrowseUi>().ok())
+            .map(|browse| browse.read(cx).pending_state())
+            .sum()
+    }
 }
 
 impl Render for RemoteUi {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        self.rendered = true;
+
         let stack = self.nav.read(cx).stack();
-        let len = stack.len();
+        let entity = cx.weak_entity();
+        let retry_in = RemoteHealth::retry_in(&self.s3_remote.remote_name, cx);
 
         div()
             .flex()
@@ -98,70 +610,55 @@ impl Render for RemoteUi {
                     .text_sm()
                     .border_color(cx.theme().sidebar_border)
                     .child(
-                        Button::new("refresh")
-                            .icon(Icon::empty().path("icons/rotate-ccw.svg"))
-                            .small()
-                            .ghost()
-                            .on_click(cx.listener(move |this, _ev, window, cx| {
-                                this.nav.update(cx, |nav, cx| {
-                                    nav.refresh_active_view(|prefix| {
-                                        BrowseUi::view(
-                                            this.browse_nav.clone(),
-                                            this.s3_remote.clone(),
-                                            prefix.clone(),
-                                            window,
-                                            cx,
-                                        )
-                                    });
-                                    cx.notify();
-                                });
-                            })),
+                        // the breadcrumb scrolls rather than squeezing these
+                        h_flex()
+                            .flex_shrink_0()
+                            .gap_1()
+                            .child(
+                                Button::new("refresh")
+                                    .icon(Icon::empty().path("icons/rotate-ccw.svg"))
+                                    .small()
+                                    .ghost()
+                                    .disabled(retry_in.is_some())
+                                    .on_click(cx.listener(move |this, _ev, window, cx| {
+                                        this.refresh(window, cx);
+                                    })),
+                            )
+                            .child(self.render_listing(cx))
+                            .child(self.render_auto_refresh(cx))
+                            .child(self.render_view_mode(cx))
+                            .child(self.render_view_menu(cx))
+                            .child(self.render_search(cx)),
                     )
                     .child(
                         div()
                             .id("header")
                             .flex()
-                            .w_full()
+                            .flex_1()
+                            .min_w_0()
                             .overflow_x_scroll()
-                            .pr(px(56.))
+                            .pr(Settings::get(cx).scaled(px(56.)))
                             .track_scroll(&self.header_scroll_handle)
                             .gap_1()
-                            .children(stack.into_iter().cloned().enumerate().map(
-                                |(i, (name, _))| {
-                                    h_flex()
-                                        .gap_1()
-                                        .child(
-                                            Button::new(SharedString::new(i.to_string()))
-                                                .label(name.trim_matches('/').to_owned())
-                                                .ghost()
-                                                .small()
-                                                .px_1()
-                                                .map(|this| {
-                                                    if i == len - 1 {
-                                                        this.bg(cx.theme().primary.opacity(0.2))
-                                                            .border_1()
-                                                            .border_color(cx.theme().primary)
-                                                            .font_medium()
-                                                    } else {
-                                                        this
-                                                    }
-                                                })
-                                                .on_click(cx.listener(
-                                                    move |this, _ev, _window, cx| {
-                                                        this.nav.update(cx, |nav, cx| {
-                                                            nav.trim(i);
-                                                            cx.notify();
-                                                        });
-                                                    },
-                                                )),
-                                        )
-                                        .child(
-                                            div()
-                                                .text_color(cx.theme().muted_foreground)
-                                                .child("/"),
-                                        )
-                                },
-                            )),
+                            .child(
+                                PathBreadcrumb::new(
+                                    "breadcrumb",
+                                    stack
+                                        .iter()
+                                        .map(|(name, _)| name.trim_matches('/').to_owned()),
+                                )
+                                .max_segments(MAX_BREADCRUMB_SEGMENTS)
+                                .on_click(move |i, _window, cx| {
+                                    let _ = entity.update(cx, |this, cx| {
+                                        let popped = this.nav.update(cx, |nav, cx| {
+                                            cx.notify();
+                                            nav.trim(i)
+                                        });
+                                        stop_listings(popped, cx);
+                                        this.sync_status(cx);
+                                    });
+                                }),
+                            ),
                     ),
             )
             .when_some(self.nav.read(cx).current_view().cloned(), |this, view| {
@@ -169,3 +666,242 @@ impl Render for RemoteUi {
             })
     }
 }
+
+/// Listings of views popped off the history may still be running, nothing shows them
+fn stop_listings(views: Vec<AnyView>, cx: &mut App) {
+    for view in views {
+        if let Ok(browse) = view.downcast::<BrowseUi>() {
+            browse.update(cx, |browse, cx| browse.stop_listing(cx));
+        }
+    }
+}
+
+impl RemoteUi {
+    /// Spinner while the view shown lists from the start, with a button to cancel
+    fn render_listing(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let listing = self
+            .current_browse(cx)
+            .is_some_and(|browse| browse.read(cx).is_listing());
+        let cancel = self.cancel_listing_target(cx);
+
+        h_flex()
+            .when(listing, |this| {
+                this.gap_1()
+                    .px_1()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(loading::loading_indicator(
+                        "remote-listing",
+                        Icon::new(IconName::LoaderCircle).xsmall(),
+                        cx,
+                    ))
+                    .child(t!("remote.listing"))
+            })
+            .when_some(cancel, |this, cancel| {
+                this.child(
+                    Button::new("cancel_listing")
+                        .icon(IconName::Close)
+                        .xsmall()
+                        .ghost()
+                        .tooltip(match cancel {
+                            CancelListing::Stop | CancelListing::Restore(_) => {
+                                t!("remote.stop_refresh")
+                            }
+                            CancelListing::Back(_) => t!("remote.cancel_listing"),
+                        })
+                        .on_click(cx.listener(|this, _ev, _window, cx| this.cancel_listing(cx))),
+                )
+            })
+    }
+
+    /// Search input of the view shown, its hits replace the listing
+    fn render_search(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex().w(Settings::get(cx).scaled(px(200.))).when_some(
+            self.current_browse(cx),
+            |this, browse| {
+                this.child(
+                    Input::new(browse.read(cx).search_input())
+                        .small()
+                        .prefix(Icon::new(IconName::Search).xsmall())
+                        .cleanable(true),
+                )
+            },
+        )
+    }
+
+    fn render_auto_refresh(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let entity = cx.weak_entity();
+        let current = self.auto_refresh;
+
+        Button::new("auto_refresh")
+            .icon(Icon::empty().path("icons/timer.svg"))
+            .small()
+            .ghost()
+            .selected(current.is_some())
+            .when_some(current, |this, interval| {
+                this.label(SharedString::new(
+                    AUTO_REFRESH_INTERVALS
+                        .iter()
+                        .find(|(secs, _)| *secs == interval.as_secs())
+                        .map(|(_, label)| label.to_string())
+                        .unwrap_or_else(|| format!("{}s", interval.as_secs())),
+                ))
+            })
+            .dropdown_menu(move |menu, _window, _cx| {
+                let entity = entity.clone();
+                let _entity = entity.clone();
+
+                AUTO_REFRESH_INTERVALS
+                    .iter()
+                    .map(|(secs, label)| (Some(Duration::from_secs(*secs)), *label))
+                    .fold(menu, |menu, (interval, label)| {
+                        let entity = entity.clone();
+                        menu.menu_element_with_check(
+                            current == interval,
+                            Box::new(EmptyAction),
+                            move |_window, _cx| {
+                                let entity = entity.clone();
+
+                                div()
+                                    .id(label)
+                                    .text_sm()
+                                    .child(t!("remote.refresh_every", interval = label))
+                                    .on_click(move |_ev, _window, cx| {
+                                        let _ = entity.update(cx, |this, cx| {
+                                            this.set_auto_refresh(interval, cx);
+                                        });
+                                    })
+                            },
+                        )
+                    })
+                    .separator()
+                    .menu_element_with_check(
+                        current.is_none(),
+                        Box::new(EmptyAction),
+                        move |_window, _cx| {
+                            let entity = _entity.clone();
+
+                            div()
+                                .id("off")
+                                .text_sm()
+                                .child(t!("remote.auto_refresh_off"))
+                                .on_click(move |_ev, _window, cx| {
+                                    let _ = entity.update(cx, |this, cx| {
+                                        this.set_auto_refresh(None, cx);
+                                    });
+                                })
+                        },
+                    )
+            })
+    }
+
+    /// Switches the listing between rows and tiles
+    fn render_view_mode(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let prefs = self.view_prefs.clone();
+        let (icon, next) = match prefs.read(cx).view_mode {
+            ViewMode::List => ("icons/layout-dashboard.svg", ViewMode::Grid),
+            ViewMode::Grid => ("icons/menu.svg", ViewMode::List),
+        };
+
+        Button::new("view_mode")
+            .icon(Icon::empty().path(icon))
+            .small()
+            .ghost()
+            .tooltip(match next {
+                ViewMode::List => t!("remote.show_as_list"),
+                ViewMode::Grid => t!("remote.show_as_grid"),
+            })
+            .on_click(move |_ev, _window, cx| {
+                prefs.update(cx, |prefs, cx| {
+                    prefs.view_mode = next;
+                    cx.notify();
+                });
+            })
+    }
+
+    fn render_view_menu(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let entity = cx.weak_entity();
+        let prefs = self.view_prefs.clone();
+        let show_folder_counts = prefs.read(cx).show_folder_counts;
+        let show_thumbnails = prefs.read(cx).show_thumbnails;
+
+        Button::new("view_menu")
+            .icon(Icon::empty().path("icons/settings-2.svg"))
+            .small()
+            .ghost()
+            .dropdown_menu(move |menu, _window, _cx| {
+                let prefs = prefs.clone();
+                let _prefs = prefs.clone();
+                let entity = entity.clone();
+                let _entity = entity.clone();
+
+                menu.menu_element_with_check(
+                    show_folder_counts,
+                    Box::new(EmptyAction),
+                    move |_window, _cx| {
+                        let prefs = prefs.clone();
+
+                        div()
+                            .id("folder_counts")
+                            .text_sm()
+                            .child(t!("remote.show_folder_counts"))
+                            .on_click(move |_ev, _window, cx| {
+                                prefs.update(cx, |prefs, cx| {
+                                    prefs.show_folder_counts = !prefs.show_folder_counts;
+                                    cx.notify();
+                                });
+                            })
+                    },
+                )
+                .menu_element_with_check(
+                    show_thumbnails,
+                    Box::new(EmptyAction),
+                    move |_window, _cx| {
+                        let prefs = _prefs.clone();
+
+                        div()
+                            .id("thumbnails")
+                            .text_sm()
+                            .child(t!("remote.show_thumbnails"))
+                            .on_click(move |_ev, _window, cx| {
+                                prefs.update(cx, |prefs, cx| {
+                                    prefs.show_thumbnails = !prefs.show_thumbnails;
+                                    cx.notify();
+                                });
+                            })
+                    },
+                )
+                .separator()
+                .menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                    let entity = _entity.clone();
+
+                    div()
+                        .id("find_junk")
+                        .text_sm()
+                        .child(t!("remote.find_junk"))
+                        .on_click(move |_ev, window, cx| {
+                            let _ = entity.update(cx, |this, cx| {
+                                if let Some(browse) = this.current_browse(cx) {
+                                    browse
+                                        .update(cx, |browse, cx| browse.find_junk_here(window, cx));
+                                }
+                            });
+                        })
+                })
+                .separator()
+                .menu_element(Box::new(EmptyAction), move |_window, _cx| {
+                    let entity = entity.clone();
+
+                    div()
+                        .id("reset_view")
+                        .text_sm()
+                        .child(t!("remote.reset_view"))
+                        .on_click(move |_ev, _window, cx| {
+                            let _ = entity.update(cx, |this, cx| {
+                                this.reset_view_prefs(cx);
+                            });
+                        })
+                })
+            })
+    }
+}
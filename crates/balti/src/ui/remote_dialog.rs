@@ -1,182 +1,659 @@
-use std::sync::Arc;
+use std::{rc::Rc, sync::Arc};
 
-use balti_s3::{S3Config, S3Remote};
-use gpui::*;
+use balti_s3::{
+    CustomHeader, DEFAULT_TIMEOUT_SECS, MAX_PAGE_SIZE, RemoteTarget, S3Config, S3Remote,
+};
+use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    Disableable, IconName, StyledExt, WindowExt,
+    ActiveTheme, Disableable, IconName, Sizable, StyledExt, WindowExt,
     button::{Button, ButtonVariants},
+    checkbox::Checkbox,
     dialog::Dialog,
     form::{field, v_form},
-    input::{Input, InputState},
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    menu::DropdownMenu,
+    v_flex,
+};
+
+use crate::{
+    config::RemoteConfig,
+    remote_form::{Field, FieldError, FormValues, HeaderValues, PageSize, PastedLocation},
+    settings::Settings,
+    strings::t,
+    ui::{
+        EmptyAction,
+        dialog_form::{self, DialogFormExt, Submit},
+        dismiss::DismissExt,
+        loading::LoadingExt,
+    },
 };
 
+/// Custom header row of the advanced section
+#[derive(Clone)]
+struct HeaderField {
+    name: Entity<InputState>,
+    value: Entity<InputState>,
+    secret: bool,
+}
+
+impl HeaderField {
+    fn new(header: Option<&CustomHeader>, window: &mut Window, cx: &mut App) -> Self {
+        let secret = header.is_some_and(|h| h.secret);
+        let name = cx.new(|cx| InputState::new(window, cx).placeholder("X-Org-Token"));
+        let value = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("value")
+                .masked(secret)
+        });
+
+        if let Some(header) = header {
+            name.update(cx, |input, cx| input.set_value(&header.name, window, cx));
+            value.update(cx, |input, cx| input.set_value(&header.value, window, cx));
+        }
+
+        Self {
+            name,
+            value,
+            secret,
+        }
+    }
+
+    fn values(&self, cx: &App) -> HeaderValues {
+        HeaderValues {
+            name: self.name.read(cx).value().to_string(),
+            value: self.value.read(cx).value().to_string(),
+            secret: self.secret,
+        }
+    }
+}
+
+/// Writes a corrected config back into the dialog's fields
+pub type ApplyConfig = Rc<dyn Fn(&RemoteConfig, &mut Window, &mut App)>;
+
+/// Offers the buckets found for the dialog's keys in the bucket field
+pub type ApplyBuckets = Rc<dyn Fn(Vec<Arc<str>>, &mut Window, &mut App)>;
+
 pub trait RemoteDialog: Render {
     fn create_remote(
         &mut self,
         name: SharedString,
-        config: S3Config,
+        config: RemoteConfig,
         old_remote: Option<Arc<str>>,
         window: &mut Window,
         cx: &mut Context<Self>,
     );
 
-    fn test_config(&mut self, config: S3Config, window: &mut Window, cx: &mut Context<Self>);
+    /// `on_switch_region` applies the config moved to the bucket's region when
+    /// the test finds it in another one
+    fn test_config(
+        &mut self,
+        config: RemoteConfig,
+        on_switch_region: ApplyConfig,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
 
     fn is_testing(&self) -> bool;
+
+    /// Lists the buckets the keys of `config` can see, `on_buckets` gets them unless
+    /// the listing fails. The bucket name stays free to type either way.
+    fn fetch_buckets(
+        &mut self,
+        config: RemoteConfig,
+        on_buckets: ApplyBuckets,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+
+    fn is_fetching_buckets(&self) -> bool;
+
+    /// Existing remote, other than the one being edited, with the same target
+    fn duplicate_of(
+        &self,
+        target: &RemoteTarget,
+        old_remote: Option<&str>,
+        cx: &App,
+    ) -> Option<Arc<str>>;
 }
 
 pub fn trigger<T: RemoteDialog>(entity: WeakEntity<T>, remote: Option<S3Remote>) -> Button {
     Button::new("create_remote")
         .primary()
         .icon(IconName::Plus)
-        .label("Create remote")
+        .label(t!("remote_dialog.create_remote"))
         .on_click(move |_ev, window, cx| {
             open_dialog(remote.clone(), entity.clone(), window, cx);
         })
 }
 
+/// Inputs of the dialog, the main form in the order it's laid out. Both the Test and
+/// Save buttons go through [`RemoteForm::read_config`].
+struct RemoteForm {
+    /// `s3://` or `https://` URL a new remote's bucket fields are filled in from
+    uri: Entity<InputState>,
+    remote_name: Entity<InputState>,
+    access_key_id: Entity<InputState>,
+    secret_access_key: Entity<InputState>,
+    /// Only temporary (STS) credentials come with one
+    session_token: Entity<InputState>,
+    credentials_expiry: Entity<InputState>,
+    region: Entity<InputState>,
+    endpoint: Entity<InputState>,
+    bucket_name: Entity<InputState>,
+    /// Found for the keys with "Fetch buckets", picked from next to the bucket name
+    buckets: Vec<Arc<str>>,
+    root_prefix: Entity<InputState>,
+    page_size: Entity<InputState>,
+    /// Rarely needed settings, collapsed unless the remote already uses them
+    advanced_expanded: bool,
+    headers: Vec<HeaderField>,
+    proxy_url: Entity<InputState>,
+    proxy_username: Entity<InputState>,
+    proxy_password: Entity<InputState>,
+    no_proxy: Entity<InputState>,
+    /// Seconds, blank for [`DEFAULT_TIMEOUT_SECS`]
+    timeout_secs: Entity<InputState>,
+    ca_bundle: Entity<InputState>,
+    insecure_tls: bool,
+    /// Collapsed unless the remote already sets some
+    upload_expanded: bool,
+    storage_class: Entity<InputState>,
+    encryption: Entity<InputState>,
+    kms_key_id: Entity<InputState>,
+    acl: Entity<InputState>,
+    detect_content_type: bool,
+    /// Filled in from a shared snippet, which leaves the secret key out
+    from_snippet: bool,
+    /// Values the dialog opened with, edits are told apart from them
+    initial: FormValues,
+}
+
+impl RemoteForm {
+    /// Filled in with the name and config of `remote` when given
+    fn new(remote: Option<(&str, &S3Config)>, window: &mut Window, cx: &mut App) -> Self {
+        let input = |placeholder: &str, window: &mut Window, cx: &mut App| {
+            let placeholder = SharedString::new(placeholder);
+            cx.new(|cx| InputState::new(window, cx).placeholder(placeholder))
+        };
+
+        let page_size_placeholder = Settings::get(cx).page_size.to_string();
+        let mut form = Self {
+            uri: input("s3://acme-bucket/team/shared/", window, cx),
+            remote_name: input("cooler_remote", window, cx),
+            access_key_id: input("ABCD1234", window, cx),
+            secret_access_key: input("secret-abcd-xyz-123", window, cx),
+            session_token: cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("FwoGZXIvYXdzE...")
+                    .masked(true)
+            }),
+            credentials_expiry: input("2026-01-01T12:00:00Z", window, cx),
+            region: input("auto", window, cx),
+            endpoint: input("https://endpoint.com", window, cx),
+            bucket_name: input("acme-bucket", window, cx),
+            buckets: Vec::new(),
+            root_prefix: input("team/shared", window, cx),
+            page_size: input(&page_size_placeholder, window, cx),
+            advanced_expanded: false,
+            headers: remote
+                .map(|(_, config)| {
+                    config
+                        .headers
+                        .iter()
+                        .map(|h| HeaderField::new(Some(h), window, cx))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            proxy_url: input("http://proxy.corp:3128 (default: app proxy)", window, cx),
+            proxy_username: input("username", window, cx),
+            proxy_password: cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("password")
+                    .masked(true)
+            }),
+            no_proxy: input("localhost,*.internal", window, cx),
+            timeout_secs: input(&DEFAULT_TIMEOUT_SECS.to_string(), window, cx),
+            ca_bundle: input("/etc/ssl/internal-ca.pem", window, cx),
+            insecure_tls: false,
+            upload_expanded: false,
+            storage_class: input("STANDARD_IA (default: bucket's)", window, cx),
+            encryption: input("AES256 or aws:kms (default: bucket's)", window, cx),
+            kms_key_id: input("alias/archive-key", window, cx),
+            acl: input("public-read (default: bucket's)", window, cx),
+            detect_content_type: false,
+            from_snippet: false,
+            initial: FormValues::default(),
+        };
+
+        if let Some((name, config)) = remote {
+            let values = FormValues::from_config(name, config);
+            for (input, value) in [
+                (&form.remote_name, &values.remote_name),
+                (&form.access_key_id, &values.access_key_id),
+                (&form.secret_access_key, &values.secret_access_key),
+                (&form.session_token, &values.session_token),
+                (&form.credentials_expiry, &values.credentials_expiry),
+                (&form.region, &values.region),
+                (&form.endpoint, &values.endpoint),
+                (&form.bucket_name, &values.bucket_name),
+                (&form.root_prefix, &values.root_prefix),
+                (&form.page_size, &values.page_size),
+                (&form.proxy_url, &values.proxy_url),
+                (&form.proxy_username, &values.proxy_username),
+                (&form.proxy_password, &values.proxy_password),
+                (&form.no_proxy, &values.no_proxy),
+                (&form.timeout_secs, &values.timeout_secs),
+                (&form.ca_bundle, &values.ca_bundle),
+                (&form.storage_class, &values.storage_class),
+                (&form.encryption, &values.encryption),
+                (&form.kms_key_id, &values.kms_key_id),
+                (&form.acl, &values.acl),
+            ] {
+                if !value.is_empty() {
+                    input.update(cx, |input, cx| input.set_value(value.clone(), window, cx));
+                }
+            }
+            form.insecure_tls = values.insecure_tls;
+            form.advanced_expanded = values.uses_advanced();
+            form.detect_content_type = values.detect_content_type;
+            form.upload_expanded = values.uses_upload_defaults();
+        }
+        form.initial = form.values(cx);
+        form
+    }
+
+    /// Text inputs of the main form, in tab order
+    fn inputs(&self) -> [Entity<InputState>; 10] {
+        [
+            self.remote_name.clone(),
+            self.access_key_id.clone(),
+            self.secret_access_key.clone(),
+            self.session_token.clone(),
+            self.credentials_expiry.clone(),
+            self.region.clone(),
+            self.endpoint.clone(),
+            self.bucket_name.clone(),
+            self.root_prefix.clone(),
+            self.page_size.clone(),
+        ]
+    }
+
+    /// Current value of every field, blank header rows left out
+    fn values(&self, cx: &App) -> FormValues {
+        let text = |input: &Entity<InputState>| input.read(cx).value().to_string();
+        FormValues {
+            remote_name: text(&self.remote_name),
+            access_key_id: text(&self.access_key_id),
+            secret_access_key: text(&self.secret_access_key),
+            session_token: text(&self.session_token),
+            credentials_expiry: text(&self.credentials_expiry),
+            region: text(&self.region),
+            endpoint: text(&self.endpoint),
+            bucket_name: text(&self.bucket_name),
+            root_prefix: text(&self.root_prefix),
+            page_size: text(&self.page_size),
+            headers: self
+                .headers
+                .iter()
+                .map(|header| header.values(cx))
+                .filter(|header| !header.is_blank())
+                .collect(),
+            proxy_url: text(&self.proxy_url),
+            proxy_username: text(&self.proxy_username),
+            proxy_password: text(&self.proxy_password),
+            no_proxy: text(&self.no_proxy),
+            timeout_secs: text(&self.timeout_secs),
+            ca_bundle: text(&self.ca_bundle),
+            insecure_tls: self.insecure_tls,
+            storage_class: text(&self.storage_class),
+            encryption: text(&self.encryption),
+            kms_key_id: text(&self.kms_key_id),
+            acl: text(&self.acl),
+            detect_content_type: self.detect_content_type,
+        }
+    }
+
+    fn read_config(&self, cx: &App) -> Result<S3Config, Vec<FieldError>> {
+        self.values(cx).read_config()
+    }
+
+    fn is_dirty(&self, cx: &App) -> bool {
+        self.values(cx) != self.initial
+    }
+
+    /// Fills in the bucket fields from a pasted URL, leaving the ones it doesn't name
+    fn apply_location(
+        &self,
+        location: &PastedLocation,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let fields = [
+            (&self.bucket_name, Some(&location.bucket_name)),
+            (&self.root_prefix, Some(&location.root_prefix)),
+            (&self.region, location.region.as_ref()),
+            (&self.endpoint, location.endpoint.as_ref()),
+        ];
+        for (input, value) in fields {
+            if let Some(value) = value {
+                input.update(cx, |input, cx| input.set_value(value.clone(), window, cx));
+            }
+        }
+    }
+
+    /// Shows the region and endpoint of `config`, after the test moved it
+    fn apply_region(&self, config: &S3Config, window: &mut Window, cx: &mut Context<Self>) {
+        self.region.update(cx, |input, cx| {
+            input.set_value(&config.region, window, cx);
+        });
+        self.endpoint.update(cx, |input, cx| {
+            input.set_value(&config.endpoint, window, cx);
+        });
+    }
+}
+
 pub fn open_dialog<T: RemoteDialog>(
     remote: Option<S3Remote>,
     entity: WeakEntity<T>,
     window: &mut Window,
     cx: &mut App,
 ) {
-    let remote = remote.clone();
-    let entity = entity.clone();
-
-    let remote_name_input_state =
-        cx.new(|cx| InputState::new(window, cx).placeholder("cooler_remote"));
-    let access_key_id_input_state =
-        cx.new(|cx| InputState::new(window, cx).placeholder("ABCD1234"));
-    let secret_access_key_input_state =
-        cx.new(|cx| InputState::new(window, cx).placeholder("secret-abcd-xyz-123"));
-    let region_input_state = cx.new(|cx| InputState::new(window, cx).placeholder("auto"));
-    let endpoint_input_state =
-        cx.new(|cx| InputState::new(window, cx).placeholder("https://endpoint.com"));
-    let bucket_name_input_state =
-        cx.new(|cx| InputState::new(window, cx).placeholder("acme-bucket"));
-
-    remote.as_ref().map(|r| {
-        remote_name_input_state.update(cx, |input, cx| {
-            input.set_value(&r.remote_name, window, cx);
-        });
-        access_key_id_input_state.update(cx, |input, cx| {
-            input.set_value(&r.config.access_key_id, window, cx);
-        });
-        secret_access_key_input_state.update(cx, |input, cx| {
-            input.set_value(&r.config.secret_access_key, window, cx);
-        });
-        if r.config.region.as_ref() != "auto" {
-            region_input_state.update(cx, |input, cx| {
-                input.set_value(&r.config.region, window, cx);
-            });
-        }
-        endpoint_input_state.update(cx, |input, cx| {
-            input.set_value(&r.config.endpoint, window, cx);
-        });
-        bucket_name_input_state.update(cx, |input, cx| {
-            input.set_value(&r.config.bucket_name, window, cx);
-        });
-    });
+    let form = RemoteForm::new(
+        remote.as_ref().map(|r| (r.remote_name.as_ref(), &r.config)),
+        window,
+        cx,
+    );
+    let old_remote = remote.map(|r| r.remote_name);
+    show(form, old_remote, entity, window, cx);
+}
+
+/// Opens the dialog for a new remote filled in from a shared snippet, the secret key
+/// is left to enter
+pub fn open_from_snippet<T: RemoteDialog>(
+    remote_name: &str,
+    config: &RemoteConfig,
+    entity: WeakEntity<T>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let mut form = match config {
+        RemoteConfig::S3(config) => RemoteForm::new(Some((remote_name, config)), window, cx),
+    };
+    form.from_snippet = true;
+    show(form, None, entity, window, cx);
+}
+
+fn show<T: RemoteDialog>(
+    form: RemoteForm,
+    old_remote: Option<Arc<str>>,
+    entity: WeakEntity<T>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let mut inputs = form.inputs().to_vec();
+    if old_remote.is_none() && !form.from_snippet {
+        // pasting the URL comes first, it fills in most of the rest
+        inputs.insert(0, form.uri.clone());
+    }
+    let uri = form.uri.clone();
+    let form = cx.new(|_cx| form);
+
+    let submit = save(entity.clone(), old_remote.clone(), form.clone());
+    dialog_form::bind_fields(&inputs, submit.clone(), window, cx);
+
+    let _form = form.clone();
+    window
+        .subscribe(&uri, cx, move |uri, event: &InputEvent, window, cx| {
+            if let InputEvent::Change = event
+                && let Some(location) = PastedLocation::parse(&uri.read(cx).value())
+            {
+                _form.update(cx, |form, cx| form.apply_location(&location, window, cx));
+            }
+        })
+        .detach();
 
     window.open_dialog(cx, move |dialog, _window, cx| {
         comp(
             dialog,
             entity.clone(),
-            remote.as_ref().map(|r| r.remote_name.clone()),
-            remote_name_input_state.clone(),
-            access_key_id_input_state.clone(),
-            secret_access_key_input_state.clone(),
-            region_input_state.clone(),
-            endpoint_input_state.clone(),
-            bucket_name_input_state.clone(),
+            old_remote.clone(),
+            form.clone(),
+            submit.clone(),
             cx,
         )
     });
 }
 
+/// Saves the remote unless a field is invalid
+fn save<T: RemoteDialog>(
+    entity: WeakEntity<T>,
+    old_remote: Option<Arc<str>>,
+    form: Entity<RemoteForm>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let Ok(config) = form.read(cx).read_config(cx) else {
+            return;
+        };
+
+        let remote_name = form.read(cx).remote_name.read(cx).value();
+        let _ = entity.update(cx, |this, cx| {
+            this.create_remote(
+                remote_name,
+                RemoteConfig::S3(config),
+                old_remote.clone(),
+                window,
+                cx,
+            );
+            cx.notify();
+        });
+    })
+}
+
+/// Why `field` doesn't parse, when there's more to say than that
+fn error_message(errors: &[FieldError], field: Field) -> Option<String> {
+    errors
+        .iter()
+        .find(|error| error.field() == field)
+        .and_then(FieldError::message)
+        .map(str::to_owned)
+}
+
 fn comp<T: RemoteDialog>(
     dialog: Dialog,
     entity: WeakEntity<T>,
     old_remote: Option<Arc<str>>,
-    remote_name_input_state: Entity<InputState>,
-    access_key_id_input_state: Entity<InputState>,
-    secret_access_key_input_state: Entity<InputState>,
-    region_input_state: Entity<InputState>,
-    endpoint_input_state: Entity<InputState>,
-    bucket_name_input_state: Entity<InputState>,
+    form: Entity<RemoteForm>,
+    submit: Submit,
     cx: &mut App,
 ) -> Dialog {
-    let invalid_fields = remote_name_input_state.read(cx).value().is_empty()
-        || access_key_id_input_state.read(cx).value().is_empty()
-        || secret_access_key_input_state.read(cx).value().is_empty()
-        || bucket_name_input_state.read(cx).value().is_empty()
-        || endpoint_input_state.read(cx).value().is_empty();
+    let values = form.read(cx).values(cx);
+    let errors = values.errors();
+    let invalid_fields = !errors.is_empty();
+    let page_size = values.page_size();
+    let endpoint = values.endpoint();
+    let expiry_error = error_message(&errors, Field::CredentialsExpiry);
+    let missing_secret = form.read(cx).from_snippet && values.secret_access_key.is_empty();
+    let uri_input = form.read(cx).uri.clone();
+    let shows_uri = old_remote.is_none() && !form.read(cx).from_snippet;
+    let uri = uri_input.read(cx).value();
+    let uri_hint = if uri.trim().is_empty() {
+        t!("remote_dialog.paste_uri_hint")
+    } else {
+        match PastedLocation::parse(&uri) {
+            Some(location) => t!(
+                "remote_dialog.paste_uri_filled",
+                bucket = location.bucket_name
+            ),
+            None => t!("remote_dialog.paste_uri_invalid"),
+        }
+    };
+    let [
+        remote_name_input,
+        access_key_id_input,
+        secret_access_key_input,
+        session_token_input,
+        credentials_expiry_input,
+        region_input,
+        endpoint_input,
+        bucket_name_input,
+        root_prefix_input,
+        page_size_input,
+    ] = form.read(cx).inputs();
+
+    let bucket_lookup = values.bucket_lookup_config();
+    let buckets = form.read(cx).buckets.clone();
+    let is_fetching_buckets = entity
+        .read_with(cx, |this, _cx| this.is_fetching_buckets())
+        .unwrap_or_default();
 
-    let _entity = entity.clone();
+    let target = values.target();
+    let duplicate = entity
+        .read_with(cx, |this, cx| {
+            this.duplicate_of(&target, old_remote.as_deref(), cx)
+        })
+        .ok()
+        .flatten();
+
+    let testing_entity = entity.clone();
+    let dirty_form = form.clone();
 
     dialog
         .alert()
-        .keyboard(false)
-        .overlay_closable(false)
+        .confirm_discard(
+            move |cx| {
+                testing_entity
+                    .read_with(cx, |this, _cx| this.is_testing())
+                    .unwrap_or_default()
+            },
+            move |cx| dirty_form.read(cx).is_dirty(cx),
+        )
+        .submit_on_enter(submit.clone())
         .rounded_lg()
         .title(if old_remote.is_some() {
-            "Edit remote"
+            t!("remote_dialog.edit_title")
         } else {
-            "Create new remote"
+            t!("remote_dialog.create_title")
         })
         .v_flex()
         .child(
             v_form()
+                .when(shows_uri, |this| {
+                    this.child(
+                        field()
+                            .label(t!("remote_dialog.paste_uri"))
+                            .child(Input::new(&uri_input).cleanable(true))
+                            .description(uri_hint),
+                    )
+                })
+                .child(
+                    field()
+                        .label(t!("remote_dialog.remote_name"))
+                        .child(Input::new(&remote_name_input).cleanable(true)),
+                )
+                .child(
+                    field()
+                        .label(t!("remote_dialog.access_key"))
+                        .child(Input::new(&access_key_id_input).cleanable(true)),
+                )
                 .child(
                     field()
-                        .label("Remote Name")
-                        .child(Input::new(&remote_name_input_state).cleanable(true)),
+                        .label(t!("remote_dialog.secret_key"))
+                        .child(Input::new(&secret_access_key_input).cleanable(true))
+                        .when(missing_secret, |this| {
+                            this.description(t!("remote_dialog.secret_from_snippet"))
+                        }),
                 )
                 .child(
                     field()
-                        .label("Access Key")
-                        .child(Input::new(&access_key_id_input_state).cleanable(true)),
+                        .label(t!("remote_dialog.session_token"))
+                        .child(Input::new(&session_token_input).cleanable(true))
+                        .description(t!("remote_dialog.session_token_hint")),
                 )
                 .child(
                     field()
-                        .label("Secret Access Key")
-                        .child(Input::new(&secret_access_key_input_state).cleanable(true)),
+                        .label(t!("remote_dialog.expiry"))
+                        .child(Input::new(&credentials_expiry_input).cleanable(true))
+                        .description(
+                            expiry_error.unwrap_or_else(|| t!("remote_dialog.expiry_hint")),
+                        ),
                 )
                 .child(
                     field()
-                        .label("Region (default: auto)")
-                        .child(Input::new(&region_input_state).cleanable(true)),
+                        .label(t!("remote_dialog.region"))
+                        .child(Input::new(&region_input).cleanable(true)),
                 )
                 .child(
                     field()
-                        .label("Endpoint")
-                        .child(Input::new(&endpoint_input_state).cleanable(true)),
+                        .label(t!("remote_dialog.endpoint"))
+                        .child(Input::new(&endpoint_input).cleanable(true))
+                        .map(|this| match endpoint {
+                            Some(Err(err)) => this.description(err.message),
+                            Some(Ok(endpoint)) if endpoint.is_plain_http() => this.child(
+                                div()
+                                    .text_sm()
+                                    .text_color(cx.theme().warning)
+                                    .child(t!("remote.plain_http", host = endpoint.authority())),
+                            ),
+                            _ => this,
+                        }),
                 )
                 .child(
                     field()
-                        .label("Bucket name")
-                        .child(Input::new(&bucket_name_input_state).cleanable(true)),
+                        .label(t!("remote_dialog.bucket_name"))
+                        .child(render_bucket_name(
+                            entity.clone(),
+                            form.clone(),
+                            bucket_name_input,
+                            buckets,
+                            bucket_lookup,
+                            is_fetching_buckets,
+                            cx,
+                        )),
+                )
+                .child(
+                    field()
+                        .label(t!("remote_dialog.root_prefix"))
+                        .child(Input::new(&root_prefix_input).cleanable(true))
+                        .description(t!("remote_dialog.root_prefix_hint")),
+                )
+                .child(
+                    field()
+                        .label(t!(
+                            "remote_dialog.page_size",
+                            default = Settings::get(cx).page_size
+                        ))
+                        .child(Input::new(&page_size_input).cleanable(true))
+                        .map(|this| match page_size {
+                            PageSize::Invalid => {
+                                this.description(t!("remote_dialog.page_size_invalid"))
+                            }
+                            PageSize::Value {
+                                value,
+                                clamped: true,
+                            } => this.description(t!(
+                                "remote_dialog.page_size_clamped",
+                                max = MAX_PAGE_SIZE,
+                                value = value
+                            )),
+                            _ => this,
+                        }),
                 ),
         )
+        .child(render_advanced(form.clone(), &errors, cx))
+        .child(render_upload_defaults(form.clone(), &errors, cx))
+        .when_some(duplicate.clone(), |this, duplicate| {
+            this.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().warning)
+                    .child(t!("remote_dialog.duplicate", remote = duplicate)),
+            )
+        })
         .footer(move |_, _, _, cx| {
-            let _remote_name_input_state = remote_name_input_state.clone();
-            let _access_key_id_input_state = access_key_id_input_state.clone();
-            let _secret_access_key_input_state = secret_access_key_input_state.clone();
-            let _region_input_state = region_input_state.clone();
-            let _endpoint_input_state = endpoint_input_state.clone();
-            let _bucket_name_input_state = bucket_name_input_state.clone();
-
-            let remote_name_input_state = remote_name_input_state.clone();
-            let access_key_id_input_state = access_key_id_input_state.clone();
-            let secret_access_key_input_state = secret_access_key_input_state.clone();
-            let region_input_state = region_input_state.clone();
-            let endpoint_input_state = endpoint_input_state.clone();
-            let bucket_name_input_state = bucket_name_input_state.clone();
-
-            let old_remote = old_remote.clone();
-            let entity = _entity.clone();
-            let _entity = _entity.clone();
+            let form = form.clone();
+            let entity = entity.clone();
+            let submit = submit.clone();
 
             let is_testing = entity
                 .read_with(cx, |this, _cx| this.is_testing())
@@ -184,38 +661,28 @@ fn comp<T: RemoteDialog>(
 
             let test = Button::new("test_dialog")
                 .ghost()
-                .label("Test")
+                .label(t!("remote_dialog.test"))
                 .disabled(invalid_fields || is_testing)
-                .loading_icon(IconName::LoaderCircle)
-                .loading(is_testing)
+                .loading_state(is_testing, cx)
                 .on_click(move |_, window, cx| {
-                    let access_key_id = _access_key_id_input_state.read(cx).value();
-                    let secret_access_key = _secret_access_key_input_state.read(cx).value();
-                    let region = _region_input_state.read(cx).value();
-                    let region = if region.trim().is_empty() {
-                        SharedString::new_static("auto")
-                    } else {
-                        region
-                    };
-                    let endpoint = _endpoint_input_state.read(cx).value();
-                    let bucket_name = _bucket_name_input_state.read(cx).value();
-
-                    let config = S3Config {
-                        access_key_id: access_key_id.into(),
-                        secret_access_key: secret_access_key.into(),
-                        region: region.into(),
-                        endpoint: endpoint.into(),
-                        bucket_name: bucket_name.into(),
+                    let Ok(config) = form.read(cx).read_config(cx) else {
+                        return;
                     };
 
-                    let _ = _entity.update(cx, |this, cx| {
-                        this.test_config(config, window, cx);
+                    let form = form.clone();
+                    let on_switch_region: ApplyConfig = Rc::new(move |config, window, cx| {
+                        let RemoteConfig::S3(config) = config;
+                        form.update(cx, |form, cx| form.apply_region(config, window, cx));
+                    });
+
+                    let _ = entity.update(cx, |this, cx| {
+                        this.test_config(RemoteConfig::S3(config), on_switch_region, window, cx);
                         cx.notify();
                     });
                 });
 
             let cancel = Button::new("cancel_dialog")
-                .label("Cancel")
+                .label(t!("common.cancel"))
                 .disabled(is_testing)
                 .on_click(|_, window, cx| {
                     window.close_dialog(cx);
@@ -223,35 +690,346 @@ fn comp<T: RemoteDialog>(
 
             let ok = Button::new("ok_dialog")
                 .primary()
-                .label("Save")
+                .label(if duplicate.is_some() {
+                    t!("remote_dialog.save_anyway")
+                } else {
+                    t!("remote_dialog.save")
+                })
                 .disabled(invalid_fields)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![test, cancel, ok]
+        })
+}
+
+/// Bucket name input, with the buckets the keys can see to pick from once fetched
+fn render_bucket_name<T: RemoteDialog>(
+    entity: WeakEntity<T>,
+    form: Entity<RemoteForm>,
+    bucket_name_input: Entity<InputState>,
+    buckets: Vec<Arc<str>>,
+    bucket_lookup: Option<S3Config>,
+    is_fetching: bool,
+    cx: &mut App,
+) -> impl IntoElement {
+    let current = bucket_name_input.read(cx).value();
+    let pick_input = bucket_name_input.clone();
+
+    h_flex()
+        .gap_2()
+        .child(
+            div()
+                .flex_1()
+                .child(Input::new(&bucket_name_input).cleanable(true)),
+        )
+        .when(!buckets.is_empty(), |this| {
+            this.child(
+                Button::new("pick_bucket")
+                    .ghost()
+                    .icon(IconName::ChevronDown)
+                    .label(t!("remote_dialog.pick_bucket", count = buckets.len()))
+                    .dropdown_menu(move |menu, _window, _cx| {
+                        buckets.iter().fold(menu, |menu, bucket| {
+                            let bucket = bucket.clone();
+                            let input = pick_input.clone();
+
+                            menu.menu_element_with_check(
+                                *bucket == *current,
+                                Box::new(EmptyAction),
+                                move |_window, _cx| {
+                                    let bucket = bucket.clone();
+                                    let input = input.clone();
+
+                                    div()
+                                        .id(SharedString::new(format!("bucket-{bucket}")))
+                                        .text_sm()
+                                        .child(SharedString::new(bucket.to_string()))
+                                        .on_click(move |_ev, window, cx| {
+                                            input.update(cx, |input, cx| {
+                                                input.set_value(bucket.to_string(), window, cx)
+                                            });
+                                        })
+                                },
+                            )
+                        })
+                    }),
+            )
+        })
+        .child(
+            Button::new("fetch_buckets")
+                .ghost()
+                .label(t!("remote_dialog.fetch_buckets"))
+                .disabled(bucket_lookup.is_none() || is_fetching)
+                .loading_state(is_fetching, cx)
                 .on_click(move |_ev, window, cx| {
-                    let remote_name = remote_name_input_state.read(cx).value();
-                    let access_key_id = access_key_id_input_state.read(cx).value();
-                    let secret_access_key = secret_access_key_input_state.read(cx).value();
-                    let region = region_input_state.read(cx).value();
-                    let region = if region.trim().is_empty() {
-                        SharedString::new_static("auto")
-                    } else {
-                        region
-                    };
-                    let endpoint = endpoint_input_state.read(cx).value();
-                    let bucket_name = bucket_name_input_state.read(cx).value();
-
-                    let config = S3Config {
-                        access_key_id: access_key_id.into(),
-                        secret_access_key: secret_access_key.into(),
-                        region: region.into(),
-                        endpoint: endpoint.into(),
-                        bucket_name: bucket_name.into(),
+                    let Some(config) = bucket_lookup.clone() else {
+                        return;
                     };
 
+                    let form = form.clone();
+                    let on_buckets: ApplyBuckets = Rc::new(move |buckets, window, cx| {
+                        form.update(cx, |form, _cx| form.buckets = buckets);
+                        window.refresh();
+                    });
+
                     let _ = entity.update(cx, |this, cx| {
-                        this.create_remote(remote_name, config, old_remote.clone(), window, cx);
+                        this.fetch_buckets(RemoteConfig::S3(config), on_buckets, window, cx);
                         cx.notify();
                     });
-                });
+                }),
+        )
+}
 
-            vec![test, cancel, ok]
+fn render_advanced(
+    form: Entity<RemoteForm>,
+    errors: &[FieldError],
+    cx: &mut App,
+) -> impl IntoElement {
+    let headers_error = error_message(errors, Field::Headers);
+    let proxy_error = error_message(errors, Field::Proxy);
+    let ca_bundle_error = error_message(errors, Field::CaBundle);
+    let timeout_invalid = errors.iter().any(|error| error.field() == Field::Timeout);
+    let state = form.read(cx);
+    let insecure_tls = state.insecure_tls;
+    let expanded = state.advanced_expanded;
+    let rows = state.headers.clone();
+    let ca_bundle = state.ca_bundle.clone();
+    let proxy_url = state.proxy_url.clone();
+    let proxy_username = state.proxy_username.clone();
+    let proxy_password = state.proxy_password.clone();
+    let no_proxy = state.no_proxy.clone();
+    let timeout_secs = state.timeout_secs.clone();
+    let insecure_form = form.clone();
+    let _form = form.clone();
+
+    v_flex()
+        .gap_2()
+        .child(
+            Button::new("toggle_advanced")
+                .ghost()
+                .small()
+                .icon(if expanded {
+                    IconName::ChevronDown
+                } else {
+                    IconName::ChevronRight
+                })
+                .label(t!("remote_dialog.advanced"))
+                .on_click(move |_ev, window, cx| {
+                    _form.update(cx, |this, _cx| {
+                        this.advanced_expanded = !this.advanced_expanded
+                    });
+                    window.refresh();
+                }),
+        )
+        .when(expanded, |this| {
+            this.child(
+                v_form()
+                    .child(
+                        field()
+                            .label(t!("remote_dialog.proxy"))
+                            .child(Input::new(&proxy_url).cleanable(true))
+                            .description(
+                                proxy_error.unwrap_or_else(|| t!("remote_dialog.proxy_hint")),
+                            ),
+                    )
+                    .child(
+                        field().label(t!("remote_dialog.proxy_credentials")).child(
+                            h_flex()
+                                .gap_2()
+                                .child(div().flex_1().child(Input::new(&proxy_username)))
+                                .child(div().flex_1().child(Input::new(&proxy_password))),
+                        ),
+                    )
+                    .child(
+                        field()
+                            .label(t!("remote_dialog.no_proxy"))
+                            .child(Input::new(&no_proxy).cleanable(true)),
+                    )
+                    .child(
+                        field()
+                            .label(t!("remote_dialog.timeout", default = DEFAULT_TIMEOUT_SECS))
+                            .child(Input::new(&timeout_secs).cleanable(true))
+                            .description(if timeout_invalid {
+                                t!("remote_dialog.timeout_invalid")
+                            } else {
+                                t!("remote_dialog.timeout_hint")
+                            }),
+                    )
+                    .child(
+                        field()
+                            .label(t!("remote_dialog.ca_bundle"))
+                            .child(Input::new(&ca_bundle).cleanable(true))
+                            .description(
+                                ca_bundle_error
+                                    .unwrap_or_else(|| t!("remote_dialog.ca_bundle_hint")),
+                            ),
+                    )
+                    .child(
+                        field().child(
+                            v_flex()
+                                .gap_1()
+                                .child(
+                                    Checkbox::new("insecure_tls")
+                                        .label(t!("remote_dialog.insecure_tls"))
+                                        .checked(insecure_tls)
+                                        .on_click(move |checked, window, cx| {
+                                            let checked = *checked;
+                                            insecure_form.update(cx, |this, _cx| {
+                                                this.insecure_tls = checked;
+                                            });
+                                            window.refresh();
+                                        }),
+                                )
+                                .when(insecure_tls, |this| {
+                                    this.child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().danger)
+                                            .child(t!("remote_dialog.insecure_tls_warning")),
+                                    )
+                                }),
+                        ),
+                    ),
+            )
+            .child(
+                v_form().child(
+                    field()
+                        .label(t!("remote_dialog.custom_headers"))
+                        .description(
+                            headers_error.unwrap_or_else(|| t!("remote_dialog.headers_hint")),
+                        )
+                        .child(
+                            v_flex()
+                                .gap_2()
+                                .children(rows.into_iter().enumerate().map(|(i, row)| {
+                                    let secret_form = form.clone();
+                                    let remove_form = form.clone();
+                                    let value = row.value.clone();
+
+                                    h_flex()
+                                        .gap_2()
+                                        .child(div().flex_1().child(Input::new(&row.name)))
+                                        .child(div().flex_1().child(Input::new(&row.value)))
+                                        .child(
+                                            Checkbox::new(SharedString::new(format!("secret-{i}")))
+                                                .label(t!("remote_dialog.secret_header"))
+                                                .checked(row.secret)
+                                                .on_click(move |checked, window, cx| {
+                                                    let secret = *checked;
+                                                    secret_form.update(cx, |this, _cx| {
+                                                        if let Some(row) = this.headers.get_mut(i) {
+                                                            row.secret = secret;
+                                                        }
+                                                    });
+                                                    value.update(cx, |input, cx| {
+                                                        input.set_masked(secret, window, cx)
+                                                    });
+                                                    window.refresh();
+                                                }),
+                                        )
+                                        .child(
+                                            Button::new(SharedString::new(format!("remove-{i}")))
+                                                .ghost()
+                                                .xsmall()
+                                                .icon(IconName::Delete)
+                                                .on_click(move |_ev, window, cx| {
+                                                    remove_form.update(cx, |this, _cx| {
+                                                        this.headers.remove(i);
+                                                    });
+                                                    window.refresh();
+                                                }),
+                                        )
+                                }))
+                                .child(
+                                    Button::new("add_header")
+                                        .ghost()
+                                        .xsmall()
+                                        .icon(IconName::Plus)
+                                        .label(t!("remote_dialog.add_header"))
+                                        .on_click(move |_ev, window, cx| {
+                                            let row = HeaderField::new(None, window, cx);
+                                            form.update(cx, |this, _cx| this.headers.push(row));
+                                            window.refresh();
+                                        }),
+                                ),
+                        ),
+                ),
+            )
+        })
+}
+
+/// Options every upload to the remote starts from, unless the upload sets its own
+fn render_upload_defaults(
+    form: Entity<RemoteForm>,
+    errors: &[FieldError],
+    cx: &mut App,
+) -> impl IntoElement {
+    let upload_error = error_message(errors, Field::UploadDefaults);
+    let state = form.read(cx);
+    let expanded = state.upload_expanded;
+    let detect_content_type = state.detect_content_type;
+    let storage_class = state.storage_class.clone();
+    let encryption = state.encryption.clone();
+    let kms_key_id = state.kms_key_id.clone();
+    let acl = state.acl.clone();
+    let detect_form = form.clone();
+
+    v_flex()
+        .gap_2()
+        .child(
+            Button::new("toggle_upload_defaults")
+                .ghost()
+                .small()
+                .icon(if expanded {
+                    IconName::ChevronDown
+                } else {
+                    IconName::ChevronRight
+                })
+                .label(t!("remote_dialog.upload_defaults"))
+                .on_click(move |_ev, window, cx| {
+                    form.update(cx, |this, _cx| this.upload_expanded = !this.upload_expanded);
+                    window.refresh();
+                }),
+        )
+        .when(expanded, |this| {
+            this.child(
+                v_form()
+                    .child(
+                        field()
+                            .label(t!("remote_dialog.storage_class"))
+                            .child(Input::new(&storage_class).cleanable(true)),
+                    )
+                    .child(
+                        field().label(t!("remote_dialog.encryption")).child(
+                            h_flex()
+                                .gap_2()
+                                .child(div().flex_1().child(Input::new(&encryption)))
+                                .child(div().flex_1().child(Input::new(&kms_key_id))),
+                        ),
+                    )
+                    .child(
+                        field()
+                            .label(t!("remote_dialog.acl"))
+                            .child(Input::new(&acl).cleanable(true))
+                            .description(
+                                upload_error
+                                    .unwrap_or_else(|| t!("remote_dialog.upload_defaults_hint")),
+                            ),
+                    )
+                    .child(
+                        field().child(
+                            Checkbox::new("detect_content_type")
+                                .label(t!("remote_dialog.detect_content_type"))
+                                .checked(detect_content_type)
+                                .on_click(move |checked, window, cx| {
+                                    let checked = *checked;
+                                    detect_form.update(cx, |this, _cx| {
+                                        this.detect_content_type = checked;
+                                    });
+                                    window.refresh();
+                                }),
+                        ),
+                    ),
+            )
         })
 }
@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    dialog::Dialog,
+    form::{field, v_form},
+    input::{Input, InputState},
+};
+
+use crate::{
+    config,
+    strings::t,
+    ui::{
+        dialog_form::Submit,
+        dismiss::DismissExt,
+        remote_dialog::{self, RemoteDialog},
+    },
+};
+
+/// Asks for a snippet made by "Copy remote as snippet", then opens the remote dialog
+/// filled in from it
+pub fn open_dialog<T: RemoteDialog>(entity: WeakEntity<T>, window: &mut Window, cx: &mut App) {
+    let input = cx.new(|cx| {
+        InputState::new(window, cx)
+            .multi_line(true)
+            .rows(10)
+            .placeholder("[remote-name]\naccess_key_id = \"…\"\nendpoint = \"https://…\"")
+    });
+
+    let submit = submit(entity, input.clone());
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(dialog, input.clone(), submit.clone(), cx)
+    });
+}
+
+/// Moves on to the remote dialog, unless the snippet doesn't parse
+fn submit<T: RemoteDialog>(entity: WeakEntity<T>, input: Entity<InputState>) -> Submit {
+    Rc::new(move |window, cx| {
+        let Ok((remote_name, config)) = config::parse_remote_snippet(&input.read(cx).value())
+        else {
+            return;
+        };
+
+        window.close_dialog(cx);
+        remote_dialog::open_from_snippet(&remote_name, &config, entity.clone(), window, cx);
+    })
+}
+
+fn dialog(dialog: Dialog, input: Entity<InputState>, submit: Submit, cx: &mut App) -> Dialog {
+    let value = input.read(cx).value();
+    let parsed = (!value.trim().is_empty()).then(|| config::parse_remote_snippet(&value));
+    let is_valid = matches!(parsed, Some(Ok(_)));
+
+    dialog
+        .alert()
+        .cancel_on_escape(false, |_cx| false)
+        .rounded_lg()
+        .title(t!("snippet.title"))
+        .v_flex()
+        .child(
+            v_form().child(
+                field()
+                    .label(t!("snippet.snippet"))
+                    .child(Input::new(&input))
+                    .map(|this| match parsed {
+                        Some(Err(err)) => this.child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().danger)
+                                .child(err.message),
+                        ),
+                        Some(Ok((remote_name, _))) => {
+                            this.description(t!("snippet.adds_remote", remote = remote_name))
+                        }
+                        None => this.description(t!("snippet.hint")),
+                    }),
+            ),
+        )
+        .footer(move |_, _, _, _cx| {
+            let submit = submit.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("snippet.continue"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
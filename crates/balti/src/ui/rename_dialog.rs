@@ -0,0 +1,156 @@
+use std::rc::Rc;
+
+use balti_s3::S3Object;
+use gpui::*;
+use gpui_component::{
+    Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    dialog::Dialog,
+    form::{field, v_form},
+    input::{Input, InputState},
+};
+
+use crate::{
+    strings::t,
+    ui::{
+        dialog_form::{self, DialogFormExt, Submit},
+        dismiss::DismissExt,
+        loading::LoadingExt,
+    },
+};
+
+pub trait RenameDialog: Render {
+    fn rename(
+        &mut self,
+        object: S3Object,
+        name: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+
+    fn is_renaming(&self) -> bool;
+}
+
+/// Last segment of the object's key, what the user edits
+fn current_name(object: &S3Object) -> SharedString {
+    let key = object.key().trim_end_matches('/');
+    SharedString::new(key.rsplit('/').next().unwrap_or_default().to_owned())
+}
+
+/// Blank names and ones with a `/` can't be a single segment of the key
+fn is_valid(name: &str) -> bool {
+    !name.trim().is_empty() && !name.contains('/')
+}
+
+pub fn open_dialog<T: RenameDialog>(
+    entity: WeakEntity<T>,
+    object: S3Object,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let name = current_name(&object);
+    let name_input_state = cx.new(|cx| InputState::new(window, cx).default_value(name.clone()));
+
+    let submit = submit(
+        entity.clone(),
+        object.clone(),
+        name,
+        name_input_state.clone(),
+    );
+    dialog_form::bind_fields(&[name_input_state.clone()], submit.clone(), window, cx);
+
+    window.open_dialog(cx, move |dialog, _window, _cx| {
+        self::dialog(
+            dialog,
+            entity.clone(),
+            object.clone(),
+            name_input_state.clone(),
+            submit.clone(),
+        )
+    });
+}
+
+/// Renames unless the name is invalid, unchanged or a rename is running already
+fn submit<T: RenameDialog>(
+    entity: WeakEntity<T>,
+    object: S3Object,
+    current: SharedString,
+    name_input_state: Entity<InputState>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let name = name_input_state.read(cx).value();
+        let is_renaming = entity
+            .read_with(cx, |this, _cx| this.is_renaming())
+            .unwrap_or(true);
+        if !is_valid(&name) || is_renaming {
+            return;
+        }
+        if name.trim() == current.as_ref() {
+            window.close_dialog(cx);
+            return;
+        }
+
+        let _ = entity.update(cx, |this, cx| {
+            this.rename(object.clone(), name, window, cx);
+            cx.notify();
+        });
+    })
+}
+
+pub fn dialog<T: RenameDialog>(
+    dialog: Dialog,
+    entity: WeakEntity<T>,
+    object: S3Object,
+    name_input_state: Entity<InputState>,
+    submit: Submit,
+) -> Dialog {
+    let _entity = entity.clone();
+
+    dialog
+        .alert()
+        .cancel_on_escape(false, move |cx| {
+            _entity
+                .read_with(cx, |this, _cx| this.is_renaming())
+                .unwrap_or_default()
+        })
+        .submit_on_enter(submit.clone())
+        .rounded_lg()
+        .title(if object.is_folder() {
+            t!("rename.title_folder")
+        } else {
+            t!("rename.title_file")
+        })
+        .v_flex()
+        .child(
+            v_form().child(
+                field()
+                    .label(t!("rename.name"))
+                    .child(Input::new(&name_input_state).cleanable(true))
+                    .description(t!("rename.path", key = object.key())),
+            ),
+        )
+        .footer(move |_, _, _, cx| {
+            let submit = submit.clone();
+
+            let is_renaming = entity
+                .read_with(cx, |this, _cx| this.is_renaming())
+                .unwrap_or_default();
+            let is_valid = is_valid(&name_input_state.read(cx).value());
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .disabled(is_renaming)
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("rename.rename"))
+                .disabled(is_renaming || !is_valid)
+                .loading_state(is_renaming, cx)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
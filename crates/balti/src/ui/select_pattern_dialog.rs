@@ -0,0 +1,189 @@
+use std::rc::Rc;
+
+use balti_err::AppResult;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    dialog::Dialog,
+    form::{field, v_form},
+    input::{Input, InputState},
+    v_flex,
+};
+
+use crate::{
+    pattern::{NamePattern, PatternKind},
+    strings::t,
+    ui::{
+        dialog_form::{self, DialogFormExt, Submit},
+        dismiss::DismissExt,
+    },
+};
+
+pub trait SelectPatternDialog: Render {
+    /// Loaded objects whose name matches
+    fn count_matches(&self, pattern: &NamePattern) -> usize;
+
+    /// Checks the loaded objects whose name matches, unchecking the rest when `replace`
+    fn select_matching(&mut self, pattern: &NamePattern, replace: bool, cx: &mut Context<Self>);
+}
+
+struct PatternOptions {
+    kind: PatternKind,
+    replace: bool,
+}
+
+impl PatternOptions {
+    /// `None` while the input is blank
+    fn pattern(&self, input: &Entity<InputState>, cx: &App) -> Option<AppResult<NamePattern>> {
+        let value = input.read(cx).value();
+        (!value.is_empty()).then(|| NamePattern::parse(&value, self.kind))
+    }
+}
+
+pub fn open_dialog<T: SelectPatternDialog>(
+    entity: WeakEntity<T>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let input = cx.new(|cx| InputState::new(window, cx).placeholder("*.tmp"));
+    let options = cx.new(|_cx| PatternOptions {
+        kind: PatternKind::Glob,
+        replace: false,
+    });
+
+    let submit = submit(entity.clone(), input.clone(), options.clone());
+    dialog_form::bind_fields(&[input.clone()], submit.clone(), window, cx);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(
+            dialog,
+            entity.clone(),
+            input.clone(),
+            options.clone(),
+            submit.clone(),
+            cx,
+        )
+    });
+}
+
+/// Selects the matches and closes the dialog, unless the pattern is blank or invalid
+fn submit<T: SelectPatternDialog>(
+    entity: WeakEntity<T>,
+    input: Entity<InputState>,
+    options: Entity<PatternOptions>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let options = options.read(cx);
+        let replace = options.replace;
+        let Some(Ok(pattern)) = options.pattern(&input, cx) else {
+            return;
+        };
+
+        let _ = entity.update(cx, |this, cx| {
+            this.select_matching(&pattern, replace, cx);
+            cx.notify();
+        });
+        window.close_dialog(cx);
+    })
+}
+
+fn dialog<T: SelectPatternDialog>(
+    dialog: Dialog,
+    entity: WeakEntity<T>,
+    input: Entity<InputState>,
+    options: Entity<PatternOptions>,
+    submit: Submit,
+    cx: &mut App,
+) -> Dialog {
+    let (kind, replace) = {
+        let options = options.read(cx);
+        (options.kind, options.replace)
+    };
+    let pattern = options.read(cx).pattern(&input, cx);
+    let is_valid = matches!(pattern, Some(Ok(_)));
+
+    let description = match &pattern {
+        None => t!("select_pattern.hint"),
+        Some(Err(err)) => err.message.clone(),
+        Some(Ok(pattern)) => {
+            let count = entity
+                .read_with(cx, |this, _cx| this.count_matches(pattern))
+                .unwrap_or_default();
+            t!("select_pattern.matches", count = count)
+        }
+    };
+    let is_error = matches!(pattern, Some(Err(_)));
+    let kind_options = options.clone();
+    let replace_options = options.clone();
+
+    dialog
+        .alert()
+        .cancel_on_escape(false, |_cx| false)
+        .submit_on_enter(submit.clone())
+        .rounded_lg()
+        .title(t!("select_pattern.title"))
+        .v_flex()
+        .child(
+            v_form().child(
+                field()
+                    .label(match kind {
+                        PatternKind::Glob => t!("select_pattern.glob"),
+                        PatternKind::Regex => t!("select_pattern.regex"),
+                    })
+                    .child(Input::new(&input).cleanable(true))
+                    .child(
+                        div()
+                            .text_sm()
+                            .when(is_error, |this| this.text_color(cx.theme().danger))
+                            .child(description),
+                    ),
+            ),
+        )
+        .child(
+            v_flex()
+                .gap_2()
+                .child(
+                    Checkbox::new("pattern_regex")
+                        .label(t!("select_pattern.regex"))
+                        .checked(kind == PatternKind::Regex)
+                        .on_click(move |checked, window, cx| {
+                            let kind = if *checked {
+                                PatternKind::Regex
+                            } else {
+                                PatternKind::Glob
+                            };
+                            kind_options.update(cx, |this, _cx| this.kind = kind);
+                            window.refresh();
+                        }),
+                )
+                .child(
+                    Checkbox::new("pattern_replace")
+                        .label(t!("select_pattern.replace"))
+                        .checked(replace)
+                        .on_click(move |checked, window, cx| {
+                            let checked = *checked;
+                            replace_options.update(cx, |this, _cx| this.replace = checked);
+                            window.refresh();
+                        }),
+                ),
+        )
+        .footer(move |_, _, _, _cx| {
+            let submit = submit.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("select_pattern.select"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
@@ -0,0 +1,207 @@
+use std::{rc::Rc, sync::Arc};
+
+use balti_s3::UploadDefaults;
+use gpui::*;
+use gpui_component::{
+    Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    dialog::Dialog,
+    form::{field, v_form},
+    h_flex,
+    input::{Input, InputState},
+};
+
+use crate::{
+    strings::t,
+    ui::dialog_form::{self, DialogFormExt, Submit},
+};
+
+pub trait UploadOptionsDialog: Render {
+    /// Asks for the files to upload with `options`, or for a folder with `folder`
+    fn upload_with(
+        &mut self,
+        options: UploadDefaults,
+        folder: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+}
+
+/// Fields of the dialog
+#[derive(Clone)]
+struct Inputs {
+    storage_class: Entity<InputState>,
+    encryption: Entity<InputState>,
+    kms_key_id: Entity<InputState>,
+    acl: Entity<InputState>,
+    detect_content_type: Entity<bool>,
+}
+
+/// Options the inputs describe, blank ones left to the bucket
+fn entered(inputs: &Inputs, cx: &App) -> UploadDefaults {
+    let optional = |input: &Entity<InputState>| {
+        let value = input.read(cx).value();
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.into())
+    };
+    UploadDefaults {
+        storage_class: optional(&inputs.storage_class),
+        encryption: optional(&inputs.encryption),
+        kms_key_id: optional(&inputs.kms_key_id),
+        acl: optional(&inputs.acl),
+        detect_content_type: *inputs.detect_content_type.read(cx),
+    }
+}
+
+/// Asks how the next upload is written, starting from the remote's upload `defaults`
+pub fn open_dialog<T: UploadOptionsDialog>(
+    entity: WeakEntity<T>,
+    defaults: &UploadDefaults,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let input = |placeholder: &str, value: &Option<Arc<str>>, window: &mut Window, cx: &mut App| {
+        let placeholder = SharedString::new(placeholder);
+        let value = SharedString::new(value.as_deref().unwrap_or_default());
+        cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder(placeholder)
+                .default_value(value)
+        })
+    };
+    let inputs = Inputs {
+        storage_class: input(
+            "STANDARD_IA (default: bucket's)",
+            &defaults.storage_class,
+            window,
+            cx,
+        ),
+        encryption: input(
+            "AES256 or aws:kms (default: bucket's)",
+            &defaults.encryption,
+            window,
+            cx,
+        ),
+        kms_key_id: input("alias/archive-key", &defaults.kms_key_id, window, cx),
+        acl: input("public-read (default: bucket's)", &defaults.acl, window, cx),
+        detect_content_type: cx.new(|_cx| defaults.detect_content_type),
+    };
+
+    let upload_files = submit(entity.clone(), inputs.clone(), false);
+    let upload_folder = submit(entity, inputs.clone(), true);
+    dialog_form::bind_fields(
+        &[
+            inputs.storage_class.clone(),
+            inputs.encryption.clone(),
+            inputs.kms_key_id.clone(),
+            inputs.acl.clone(),
+        ],
+        upload_files.clone(),
+        window,
+        cx,
+    );
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(
+            dialog,
+            inputs.clone(),
+            upload_files.clone(),
+            upload_folder.clone(),
+            cx,
+        )
+    });
+}
+
+/// Closes the dialog and asks for what to upload with the options, unless they're invalid
+fn submit<T: UploadOptionsDialog>(entity: WeakEntity<T>, inputs: Inputs, folder: bool) -> Submit {
+    Rc::new(move |window, cx| {
+        let options = entered(&inputs, cx);
+        if options.validate().is_err() {
+            return;
+        }
+        window.close_dialog(cx);
+
+        let _ = entity.update(cx, |this, cx| {
+            this.upload_with(options, folder, window, cx);
+        });
+    })
+}
+
+fn dialog(
+    dialog: Dialog,
+    inputs: Inputs,
+    upload_files: Submit,
+    upload_folder: Submit,
+    cx: &mut App,
+) -> Dialog {
+    let error = entered(&inputs, cx).validate().err();
+    let is_valid = error.is_none();
+    let detect_content_type = inputs.detect_content_type.clone();
+    let checked = *detect_content_type.read(cx);
+
+    dialog
+        .submit_on_enter(upload_files.clone())
+        .rounded_lg()
+        .title(t!("upload_options.title"))
+        .v_flex()
+        .child(
+            v_form()
+                .child(
+                    field()
+                        .label(t!("remote_dialog.storage_class"))
+                        .child(Input::new(&inputs.storage_class).cleanable(true)),
+                )
+                .child(
+                    field().label(t!("remote_dialog.encryption")).child(
+                        h_flex()
+                            .gap_2()
+                            .child(div().flex_1().child(Input::new(&inputs.encryption)))
+                            .child(div().flex_1().child(Input::new(&inputs.kms_key_id))),
+                    ),
+                )
+                .child(
+                    field()
+                        .label(t!("remote_dialog.acl"))
+                        .child(Input::new(&inputs.acl).cleanable(true))
+                        .description(
+                            error
+                                .map(|err| err.message)
+                                .unwrap_or_else(|| t!("upload_options.hint")),
+                        ),
+                )
+                .child(
+                    field().child(
+                        Checkbox::new("upload_detect_content_type")
+                            .label(t!("remote_dialog.detect_content_type"))
+                            .checked(checked)
+                            .on_click(move |checked, window, cx| {
+                                detect_content_type.update(cx, |this, _cx| *this = *checked);
+                                window.refresh();
+                            }),
+                    ),
+                ),
+        )
+        .footer(move |_, _, _, _cx| {
+            let (upload_files, upload_folder) = (upload_files.clone(), upload_folder.clone());
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let folder = Button::new("upload_folder_dialog")
+                .label(t!("upload_options.choose_folder"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| upload_folder(window, cx));
+
+            let files = Button::new("ok_dialog")
+                .primary()
+                .label(t!("upload_options.choose_files"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| upload_files(window, cx));
+
+            vec![cancel, folder, files]
+        })
+}
@@ -0,0 +1,350 @@
+use std::{sync::Arc, time::Duration};
+
+use balti_s3::Usage;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, IconName, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    form::{field, h_form},
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    v_flex,
+};
+
+use crate::{
+    cache::CacheUsage,
+    listing_memory::ListingMemory,
+    s3::S3RemoteManager,
+    settings::{Settings, UnitPrices},
+    strings::t,
+    thumbnail::ThumbnailMemory,
+    util,
+};
+
+/// How often the totals are read again while the dialog is up
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Session totals of billed requests per remote, with a cost estimate once unit
+/// prices are entered, and what the in-memory caches take
+struct UsagePanel {
+    manager: Entity<S3RemoteManager>,
+    /// Price inputs in [`UnitPrices`] field order
+    prices: [Entity<InputState>; 3],
+    _subscriptions: Vec<Subscription>,
+    _refresh_task: Task<()>,
+}
+
+impl UsagePanel {
+    fn new(manager: Entity<S3RemoteManager>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let current = Settings::get(cx).unit_prices.unwrap_or_default();
+        let prices = [
+            current.class_a_per_1k,
+            current.class_b_per_1k,
+            current.egress_per_gb,
+        ]
+        .map(|price| {
+            cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("0.00")
+                    .default_value(if price > 0. {
+                        price.to_string()
+                    } else {
+                        String::new()
+                    })
+            })
+        });
+
+        let subscriptions = prices
+            .iter()
+            .map(|input| {
+                cx.subscribe_in(input, window, |this, _input, event, _window, cx| {
+                    if let InputEvent::Blur | InputEvent::PressEnter { .. } = event {
+                        this.save_prices(cx);
+                    }
+                })
+            })
+            .collect();
+
+        let refresh_task = cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(REFRESH_INTERVAL).await;
+                if this.update(cx, |_this, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            manager,
+            prices,
+            _subscriptions: subscriptions,
+            _refresh_task: refresh_task,
+        }
+    }
+
+    /// Prices as entered, `None` while one doesn't parse
+    fn entered_prices(&self, cx: &App) -> Option<UnitPrices> {
+        let [class_a, class_b, egress] = self
+            .prices
+            .each_ref()
+            .map(|input| parse_price(&input.read(cx).value()));
+
+        Some(UnitPrices {
+            class_a_per_1k: class_a?,
+            class_b_per_1k: class_b?,
+            egress_per_gb: egress?,
+        })
+    }
+
+    /// Persists valid prices, clearing them all turns the estimate off
+    fn save_prices(&mut self, cx: &mut Context<Self>) {
+        let Some(prices) = self.entered_prices(cx) else {
+            return;
+        };
+        let prices = (prices != UnitPrices::default()).then_some(prices);
+        if Settings::get(cx).unit_prices != prices {
+            Settings::update(cx, |settings| settings.unit_prices = prices);
+        }
+        cx.notify();
+    }
+
+    fn reset(&mut self, remote_name: Option<&str>, cx: &mut Context<Self>) {
+        self.manager.read(cx).reset_usage(remote_name);
+        cx.notify();
+    }
+
+    fn clear_caches(&mut self, cx: &mut Context<Self>) {
+        ListingMemory::clear(cx);
+        ThumbnailMemory::clear(cx);
+        // open views let go of the thumbnails they show
+        cx.refresh_windows();
+    }
+
+    fn render_caches(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let cache_row = |label: String, usage: CacheUsage| {
+            h_flex()
+                .gap_3()
+                .text_sm()
+                .child(cell(px(160.)).flex_1().child(label))
+                .child(t!(
+                    "usage.cache_usage",
+                    count = usage.entries,
+                    used = util::human_readable_size(usage.bytes as i64),
+                    max = util::human_readable_size(usage.max_bytes as i64)
+                ))
+        };
+
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(div().text_sm().font_medium().child(t!("usage.caches")))
+                    .child(
+                        Button::new("clear_caches")
+                            .xsmall()
+                            .label(t!("usage.clear_caches"))
+                            .on_click(cx.listener(|this, _ev, _window, cx| this.clear_caches(cx))),
+                    ),
+            )
+            .child(cache_row(
+                t!("usage.listing_cache"),
+                ListingMemory::usage(cx),
+            ))
+            .child(cache_row(
+                t!("usage.thumbnail_cache"),
+                ThumbnailMemory::usage(cx),
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(t!("usage.caches_hint")),
+            )
+    }
+
+    fn render_row(
+        &self,
+        remote_name: Option<Arc<str>>,
+        usage: Usage,
+        prices: Option<UnitPrices>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        h_flex()
+            .gap_3()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().sidebar_border)
+            .text_sm()
+            .when(remote_name.is_none(), |this| this.font_medium())
+            .child(
+                cell(px(160.)).flex_1().child(
+                    remote_name
+                        .as_deref()
+                        .map(str::to_owned)
+                        .unwrap_or_else(|| t!("usage.total")),
+                ),
+            )
+            .child(cell(px(72.)).child(usage.class_a.to_string()))
+            .child(cell(px(72.)).child(usage.class_b.to_string()))
+            .child(cell(px(80.)).child(util::human_readable_size(usage.bytes_out as i64)))
+            .when_some(prices, |this, prices| {
+                this.child(cell(px(80.)).child(format!("≈ {:.4}", prices.estimate(&usage))))
+            })
+            .child(cell(px(24.)).when_some(remote_name, |this, remote_name| {
+                this.child(
+                    Button::new(SharedString::new(format!("reset-usage-{remote_name}")))
+                        .xsmall()
+                        .ghost()
+                        .icon(IconName::Close)
+                        .tooltip(t!("usage.reset_remote"))
+                        .on_click(cx.listener(move |this, _ev, _window, cx| {
+                            this.reset(Some(&remote_name), cx);
+                        })),
+                )
+            }))
+    }
+}
+
+impl Render for UsagePanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let usage = self.manager.read(cx).usage();
+        let prices = Settings::get(cx).unit_prices;
+        let muted = cx.theme().muted_foreground;
+
+        let totals = if usage.is_empty() {
+            div()
+                .py_2()
+                .text_sm()
+                .text_color(muted)
+                .child(t!("usage.no_requests"))
+                .into_any_element()
+        } else {
+            let total = usage
+                .iter()
+                .fold(Usage::default(), |total, (_, usage)| Usage {
+                    class_a: total.class_a + usage.class_a,
+                    class_b: total.class_b + usage.class_b,
+                    bytes_out: total.bytes_out + usage.bytes_out,
+                });
+            let show_total = usage.len() > 1;
+
+            v_flex()
+                .border_1()
+                .border_color(cx.theme().sidebar_border)
+                .rounded_md()
+                .child(
+                    h_flex()
+                        .gap_3()
+                        .px_2()
+                        .py_1()
+                        .border_b_1()
+                        .border_color(cx.theme().sidebar_border)
+                        .text_xs()
+                        .text_color(muted)
+                        .child(cell(px(160.)).flex_1().child(t!("usage.remote")))
+                        .child(cell(px(72.)).child(t!("usage.class_a")))
+                        .child(cell(px(72.)).child(t!("usage.class_b")))
+                        .child(cell(px(80.)).child(t!("usage.egress")))
+                        .when(prices.is_some(), |this| {
+                            this.child(cell(px(80.)).child(t!("usage.estimated_cost")))
+                        })
+                        .child(cell(px(24.))),
+                )
+                .children(
+                    usage
+                        .into_iter()
+                        .map(|(name, usage)| self.render_row(Some(name), usage, prices, cx))
+                        .collect::<Vec<_>>(),
+                )
+                .when(show_total, |this| {
+                    this.child(self.render_row(None, total, prices, cx))
+                })
+                .into_any_element()
+        };
+
+        let invalid = self.entered_prices(cx).is_none();
+
+        v_flex()
+            .gap_3()
+            .child(div().text_xs().text_color(muted).child(t!("usage.classes")))
+            .child(totals)
+            .child(
+                h_form()
+                    .child(
+                        field()
+                            .label(t!("usage.class_a_price"))
+                            .child(Input::new(&self.prices[0])),
+                    )
+                    .child(
+                        field()
+                            .label(t!("usage.class_b_price"))
+                            .child(Input::new(&self.prices[1])),
+                    )
+                    .child(
+                        field()
+                            .label(t!("usage.egress_price"))
+                            .child(Input::new(&self.prices[2])),
+                    ),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(if invalid { cx.theme().danger } else { muted })
+                    .child(if invalid {
+                        t!("usage.invalid_prices")
+                    } else {
+                        t!("usage.prices_hint")
+                    }),
+            )
+            .child(self.render_caches(cx))
+    }
+}
+
+/// Empty input counts as free
+fn parse_price(input: &str) -> Option<f64> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Some(0.);
+    }
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|price| price.is_finite() && *price >= 0.)
+}
+
+fn cell(width: Pixels) -> Div {
+    div().w(width).flex_shrink_0().min_w_0()
+}
+
+pub fn open_dialog(manager: Entity<S3RemoteManager>, window: &mut Window, cx: &mut App) {
+    let panel = cx.new(|cx| UsagePanel::new(manager, window, cx));
+
+    window.open_dialog(cx, move |dialog, _window, _cx| {
+        let panel = panel.clone();
+
+        dialog
+            .rounded_lg()
+            .w(px(640.))
+            .title(t!("usage.title"))
+            .child(panel.clone())
+            .footer(move |_, _, _, _cx| {
+                let panel = panel.clone();
+
+                vec![
+                    Button::new("reset_usage")
+                        .label(t!("usage.reset_all"))
+                        .on_click(move |_, _window, cx| {
+                            panel.update(cx, |panel, cx| panel.reset(None, cx));
+                        }),
+                    Button::new("close_dialog")
+                        .primary()
+                        .label(t!("common.close"))
+                        .on_click(|_, window, cx| {
+                            window.close_dialog(cx);
+                        }),
+                ]
+            })
+    });
+}
@@ -0,0 +1,210 @@
+use std::{rc::Rc, sync::Arc};
+
+use balti_s3::{DEFAULT_FULL_LIMIT, SAMPLE_BYTES, VerifyTier};
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    dialog::Dialog,
+    form::{field, v_form},
+    input::{Input, InputState},
+    v_flex,
+};
+
+use crate::{
+    strings::t,
+    ui::{
+        dialog_form::{self, DialogFormExt, Submit},
+        dismiss::DismissExt,
+    },
+    util,
+};
+
+const MB: u64 = 1024 * 1024;
+
+pub trait VerifyDialog: Render {
+    /// Checks the objects below `prefix` as far as `tier` goes, reading whole ones up to
+    /// `full_limit` bytes
+    fn verify(
+        &mut self,
+        prefix: Arc<str>,
+        tier: VerifyTier,
+        full_limit: u64,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    );
+
+    fn is_verifying(&self) -> bool;
+}
+
+/// Largest object a full check reads whole, in bytes. `None` unless it's a whole
+/// number of MB above zero.
+fn full_limit(input: &Entity<InputState>, cx: &App) -> Option<u64> {
+    input
+        .read(cx)
+        .value()
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|mb| *mb > 0)
+        .map(|mb| mb * MB)
+}
+
+pub fn open_dialog<T: VerifyDialog>(
+    entity: WeakEntity<T>,
+    prefix: Arc<str>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let limit_input = cx
+        .new(|cx| InputState::new(window, cx).default_value((DEFAULT_FULL_LIMIT / MB).to_string()));
+    let tier = cx.new(|_cx| VerifyTier::default());
+
+    let submit = submit(
+        entity.clone(),
+        prefix.clone(),
+        tier.clone(),
+        limit_input.clone(),
+    );
+    dialog_form::bind_fields(&[limit_input.clone()], submit.clone(), window, cx);
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        self::dialog(
+            dialog,
+            prefix.clone(),
+            tier.clone(),
+            limit_input.clone(),
+            submit.clone(),
+            cx,
+        )
+    });
+}
+
+/// Starts the verification and closes the dialog, unless the size limit is invalid or
+/// one is running already
+fn submit<T: VerifyDialog>(
+    entity: WeakEntity<T>,
+    prefix: Arc<str>,
+    tier: Entity<VerifyTier>,
+    limit_input: Entity<InputState>,
+) -> Submit {
+    Rc::new(move |window, cx| {
+        let tier = *tier.read(cx);
+        // only a full check goes by the limit
+        let limit = match full_limit(&limit_input, cx) {
+            Some(limit) => limit,
+            None if tier != VerifyTier::Full => DEFAULT_FULL_LIMIT,
+            None => return,
+        };
+        let is_verifying = entity
+            .read_with(cx, |this, _cx| this.is_verifying())
+            .unwrap_or(true);
+        if is_verifying {
+            return;
+        }
+
+        let _ = entity.update(cx, |this, cx| {
+            this.verify(prefix.clone(), tier, limit, window, cx);
+            cx.notify();
+        });
+        window.close_dialog(cx);
+    })
+}
+
+fn dialog(
+    dialog: Dialog,
+    prefix: Arc<str>,
+    tier: Entity<VerifyTier>,
+    limit_input: Entity<InputState>,
+    submit: Submit,
+    cx: &mut App,
+) -> Dialog {
+    let selected = *tier.read(cx);
+    let is_valid = selected != VerifyTier::Full || full_limit(&limit_input, cx).is_some();
+    let muted = cx.theme().muted_foreground;
+
+    let tiers = [
+        (
+            VerifyTier::Head,
+            "verify_head",
+            t!("verify.head"),
+            t!("verify.head_hint"),
+        ),
+        (
+            VerifyTier::Sampled,
+            "verify_sampled",
+            t!("verify.sampled"),
+            t!(
+                "verify.sampled_hint",
+                size = util::human_readable_size(SAMPLE_BYTES as i64)
+            ),
+        ),
+        (
+            VerifyTier::Full,
+            "verify_full",
+            t!("verify.full"),
+            t!("verify.full_hint"),
+        ),
+    ];
+
+    dialog
+        .alert()
+        .cancel_on_escape(false, |_cx| false)
+        .submit_on_enter(submit.clone())
+        .rounded_lg()
+        .title(t!("verify.title", prefix = prefix))
+        .v_flex()
+        .gap_3()
+        .child(div().text_sm().text_color(muted).child(t!("verify.hint")))
+        .child(
+            v_flex()
+                .gap_2()
+                .children(tiers.into_iter().map(|(value, id, label, hint)| {
+                    let tier = tier.clone();
+
+                    v_flex()
+                        .child(
+                            Checkbox::new(id)
+                                .label(label)
+                                .checked(selected == value)
+                                .on_click(move |_checked, window, cx| {
+                                    tier.update(cx, |this, _cx| *this = value);
+                                    window.refresh();
+                                }),
+                        )
+                        .child(div().pl_6().text_sm().text_color(muted).child(hint))
+                })),
+        )
+        .when(selected == VerifyTier::Full, |this| {
+            this.child(
+                v_form().child(
+                    field()
+                        .label(t!("verify.full_limit"))
+                        .child(Input::new(&limit_input))
+                        .description(if is_valid {
+                            t!("verify.full_limit_hint")
+                        } else {
+                            t!("verify.invalid_limit")
+                        }),
+                ),
+            )
+        })
+        .footer(move |_, _, _, _cx| {
+            let submit = submit.clone();
+
+            let cancel = Button::new("cancel_dialog")
+                .label(t!("common.cancel"))
+                .on_click(|_, window, cx| {
+                    window.close_dialog(cx);
+                });
+
+            let ok = Button::new("ok_dialog")
+                .primary()
+                .label(t!("verify.verify"))
+                .disabled(!is_valid)
+                .on_click(move |_ev, window, cx| submit(window, cx));
+
+            vec![cancel, ok]
+        })
+}
@@ -0,0 +1,47 @@
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+
+use crate::strings::t;
+
+/// Release notes of `version`, list items as bullets and other lines as paragraphs
+pub fn open_dialog(version: &str, notes: &'static str, window: &mut Window, cx: &mut App) {
+    let title = SharedString::new(t!("whats_new.title", version = version));
+
+    window.open_dialog(cx, move |dialog, _window, cx| {
+        let muted = cx.theme().muted_foreground;
+        let lines = notes
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.strip_prefix("- ") {
+                Some(item) => h_flex()
+                    .items_start()
+                    .gap_2()
+                    .child(div().text_color(muted).child("•"))
+                    .child(div().flex_1().child(item))
+                    .into_any_element(),
+                None => div().child(line).into_any_element(),
+            })
+            .collect::<Vec<_>>();
+
+        dialog
+            .rounded_lg()
+            .w(px(520.))
+            .title(title.clone())
+            .child(v_flex().gap_1().text_sm().children(lines))
+            .footer(|_, _, _, _cx| {
+                vec![
+                    Button::new("close_dialog")
+                        .primary()
+                        .label(t!("common.close"))
+                        .on_click(|_, window, cx| {
+                            window.close_dialog(cx);
+                        }),
+                ]
+            })
+    });
+}
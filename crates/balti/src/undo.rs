@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use balti_err::{AppError, AppResult};
+use balti_s3::{FOLDER_MARKER, S3Remote};
+use futures::StreamExt;
+use gpui::{App, Global};
+
+/// Operations the session keeps to undo, the oldest drop off past this
+const MAX_UNDO: usize = 20;
+/// Objects checked or moved back at once
+const UNDO_CONCURRENCY: usize = 8;
+
+pub fn init(cx: &mut App) {
+    cx.set_global(UndoStack::default());
+}
+
+/// Operation the session can reverse while it remembers the keys. Deletes aren't one of
+/// them, without a trash there's nothing left to restore.
+#[derive(Clone)]
+pub enum Undo {
+    /// Moved back from `to` into `from`
+    Move {
+        from: S3Remote,
+        to: S3Remote,
+        objects: Vec<MovedObject>,
+    },
+    /// Removed again by its marker, while the folder holds nothing else
+    CreateFolder {
+        remote: S3Remote,
+        marker_key: Arc<str>,
+    },
+}
+
+/// Object a move put at `to_key`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovedObject {
+    pub from_key: Arc<str>,
+    pub to_key: Arc<str>,
+    /// Of the copy the move wrote, `None` when the provider didn't tell
+    pub etag: Option<Arc<str>>,
+}
+
+/// Change since the operation that an undo would overwrite or lose
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoConflict {
+    /// The moved object isn't at its destination anymore
+    Gone(Arc<str>),
+    /// The moved object was overwritten at its destination
+    Modified(Arc<str>),
+    /// Another object took the key the move came from
+    Taken(Arc<str>),
+    /// The created folder got objects of its own
+    NotEmpty(Arc<str>),
+}
+
+/// How an undo went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reverted {
+    /// Objects put back, or the folder removed
+    Done(usize),
+    /// Nothing was touched
+    Conflict(UndoConflict),
+}
+
+/// Whether `object` can move back, given the ETag at its destination now (`None` when
+/// it's missing) and whether its old key got taken since
+pub fn check_moved(
+    object: &MovedObject,
+    etag_now: Option<&str>,
+    source_taken: bool,
+) -> Option<UndoConflict> {
+    let Some(etag_now) = etag_now else {
+        return Some(UndoConflict::Gone(object.to_key.clone()));
+    };
+    if object.etag.as_deref().is_some_and(|etag| etag != etag_now) {
+        return Some(UndoConflict::Modified(object.to_key.clone()));
+    }
+    source_taken.then(|| UndoConflict::Taken(object.from_key.clone()))
+}
+
+/// Undoable operations of the session, latest last
+#[derive(Default)]
+pub struct UndoStack {
+    entries: Vec<(u64, Undo)>,
+    next_id: u64,
+}
+
+impl Global for UndoStack {}
+
+impl UndoStack {
+    /// Remembers `undo`, the id takes it back out with [`UndoStack::take`]
+    pub fn push(undo: Undo, cx: &mut App) -> u64 {
+        cx.global_mut::<Self>().push_entry(undo)
+    }
+
+    /// Takes the undo `id` off the stack, or the latest one. An undo runs once,
+    /// conflicting or not.
+    pub fn take(id: Option<u64>, cx: &mut App) -> Option<Undo> {
+        cx.global_mut::<Self>().take_entry(id)
+    }
+
+    fn push_entry(&mut self, undo: Undo) -> u64 {
+        self.next_id += 1;
+        self.entries.push((self.next_id, undo));
+        if self.entries.len() > MAX_UNDO {
+            self.entries.remove(0);
+        }
+        self.next_id
+    }
+
+    fn take_entry(&mut self, id: Option<u64>) -> Option<Undo> {
+        let index = match id {
+            Some(id) => self.entries.iter().position(|(entry, _)| *entry == id)?,
+            None => self.entries.len().checked_sub(1)?,
+        };
+        Some(self.entries.remove(index).1)
+    }
+}
+
+/// Checks nothing changed since the operation, then reverses it
+pub async fn revert(undo: Undo) -> AppResult<Reverted> {
+    match undo {
+        Undo::Move { from, to, objects } => move_back(from, to, objects).await,
+        Undo::CreateFolder { remote, marker_key } => {
+            let folder = marker_key
+                .strip_suffix(FOLDER_MARKER)
+                .unwrap_or(&marker_key);
+            let page = balti_s3::list_objects_recursive(remote.clone(), folder, 2, None).await?;
+            if page
+                .objects
+                .iter()
+                .any(|object| *object.key() != marker_key)
+            {
+                return Ok(Reverted::Conflict(UndoConflict::NotEmpty(folder.into())));
+            }
+
+            balti_s3::delete_file(remote, &marker_key).await?;
+            Ok(Reverted::Done(1))
+        }
+    }
+}
+
+async fn move_back(from: S3Remote, to: S3Remote, objects: Vec<MovedObject>) -> AppResult<Reverted> {
+    let checks = objects.iter().map(|object| {
+        let (from, to) = (from.clone(), to.clone());
+        async move {
+            let etag_now = balti_s3::object_etag(to, &object.to_key).await?;
+            let taken = balti_s3::object_exists(from, &object.from_key).await?;
+            AppResult::Ok(check_moved(object, etag_now.as_deref(), taken))
+        }
+    });
+    let mut checks = futures::stream::iter(checks).buffer_unordered(UNDO_CONCURRENCY);
+    while let Some(conflict) = checks.next().await {
+        if let Some(conflict) = conflict? {
+            return Ok(Reverted::Conflict(conflict));
+        }
+    }
+
+    let same_remote = from.remote_name == to.remote_name;
+    let moves = objects.iter().map(|object| {
+        let (from, to) = (from.clone(), to.clone());
+        async move {
+            if same_remote {
+                balti_s3::copy_object(to.clone(), &object.to_key, &object.from_key, false).await?;
+            } else {
+                balti_s3::copy_between(to.clone(), &object.to_key, from, &object.from_key).await?;
+            }
+            balti_s3::delete_file(to, &object.to_key).await
+        }
+    });
+    let failed = futures::stream::iter(moves)
+        .buffer_unordered(UNDO_CONCURRENCY)
+        .filter_map(|result| futures::future::ready(result.err()))
+        .collect::<Vec<_>>()
+        .await;
+
+    match failed.first() {
+        None => Ok(Reverted::Done(objects.len())),
+        Some(err) => Err(AppError::message(format!(
+            "{} of {} objects couldn't be moved back: {}",
+            failed.len(),
+            objects.len(),
+            err.message
+        ))
+        .with_kind(err.kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(etag: Option<&str>) -> MovedObject {
+        MovedObject {
+            from_key: "a/report.csv".into(),
+            to_key: "b/report.csv".into(),
+            etag: etag.map(Arc::from),
+        }
+    }
+
+    #[test]
+    fn detects_changes_since_the_move() {
+        let object = moved(Some("\"e1\""));
+        assert_eq!(check_moved(&object, Some("\"e1\""), false), None);
+        assert_eq!(
+            check_moved(&object, Some("\"e2\""), false),
+            Some(UndoConflict::Modified("b/report.csv".into()))
+        );
+        assert_eq!(
+            check_moved(&object, None, false),
+            Some(UndoConflict::Gone("b/report.csv".into()))
+        );
+        assert_eq!(
+            check_moved(&object, Some("\"e1\""), true),
+            Some(UndoConflict::Taken("a/report.csv".into()))
+        );
+
+        // without a recorded tag only a missing object or a taken key stop it
+        assert_eq!(check_moved(&moved(None), Some("\"e9\""), false), None);
+    }
+
+    fn folder(key: &str) -> Undo {
+        let remote = balti_s3::__S3Remote::new(
+            "prod".into(),
+            balti_s3::S3Config {
+                endpoint: "http://localhost:9000".into(),
+                bucket_name: "bucket".into(),
+                ..Default::default()
+            },
+            None,
+            Arc::default(),
+        );
+        Undo::CreateFolder {
+            remote,
+            marker_key: key.into(),
+        }
+    }
+
+    fn marker(undo: Option<Undo>) -> Option<String> {
+        match undo? {
+            Undo::CreateFolder { marker_key, .. } => Some(marker_key.to_string()),
+            Undo::Move { .. } => None,
+        }
+    }
+
+    #[test]
+    fn takes_undos_once_latest_first() {
+        let mut stack = UndoStack::default();
+        let first = stack.push_entry(folder("a/__fd.dat"));
+        stack.push_entry(folder("b/__fd.dat"));
+        stack.push_entry(folder("c/__fd.dat"));
+
+        assert_eq!(marker(stack.take_entry(Some(first))).unwrap(), "a/__fd.dat");
+        assert!(stack.take_entry(Some(first)).is_none());
+        assert_eq!(marker(stack.take_entry(None)).unwrap(), "c/__fd.dat");
+        assert_eq!(marker(stack.take_entry(None)).unwrap(), "b/__fd.dat");
+        assert!(stack.take_entry(None).is_none());
+
+        for i in 0..MAX_UNDO + 5 {
+            stack.push_entry(folder(&format!("{i}/__fd.dat")));
+        }
+        assert_eq!(stack.entries.len(), MAX_UNDO);
+        assert_eq!(
+            marker(stack.take_entry(Some(first + 3))),
+            None,
+            "the oldest drop off"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use balti_err::{AppError, AppResult};
+use balti_s3::ProxySettings;
+use semver::Version;
+use serde::Deserialize;
+
+use crate::config;
+
+/// Newest release of the app, GitHub leaves drafts and pre-releases out of it
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/shank03/balti/releases/latest";
+
+/// An update check gives up after this long, offline it shouldn't linger
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Release an update check found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: Version,
+    /// Page of the release to download it from
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+/// `1.2.3`, `v1.2.3` or `1.2.3-beta.1` as a version, `None` when it isn't semver
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+/// Minor versions `current` lags behind `latest`, `0` when it's up to date or ahead.
+/// A newer major version is as far behind as it gets.
+pub fn minor_versions_behind(current: &Version, latest: &Version) -> u64 {
+    if latest <= current {
+        0
+    } else if latest.major > current.major {
+        u64::MAX
+    } else {
+        latest.minor - current.minor
+    }
+}
+
+/// Whether the app on `current` should nudge about `latest`: it's more than `threshold`
+/// minor versions behind and the nudge about that release wasn't dismissed yet. Builds
+/// whose version doesn't parse never are.
+pub fn should_nudge(
+    current: &str,
+    latest: &Release,
+    threshold: u32,
+    dismissed: Option<&str>,
+) -> bool {
+    let Some(current) = parse_version(current) else {
+        return false;
+    };
+    let dismissed = dismissed.and_then(parse_version);
+    minor_versions_behind(&current, &latest.version) > threshold as u64
+        && dismissed.as_ref() != Some(&latest.version)
+}
+
+/// Asks GitHub for the latest release, through `proxy` when given
+pub async fn latest_release(proxy: Option<ProxySettings>) -> AppResult<Release> {
+    let failed = |err: reqwest::Error| AppError::message(format!("Update check failed: {err}"));
+
+    let mut client = reqwest::Client::builder()
+        .user_agent(format!("Balti/{}", config::BALTI_VERSION))
+        .timeout(CHECK_TIMEOUT);
+    if let Some(settings) = proxy {
+        let mut proxy = reqwest::Proxy::all(settings.url.as_ref()).map_err(failed)?;
+        if let Some(username) = settings.username.as_deref() {
+            proxy = proxy.basic_auth(username, settings.password.as_deref().unwrap_or_default());
+        }
+        client = client.proxy(proxy);
+    }
+
+    let release = client
+        .build()
+        .map_err(failed)?
+        .get(LATEST_RELEASE_URL)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(failed)?
+        .json::<GithubRelease>()
+        .await
+        .map_err(failed)?;
+
+    let version = parse_version(&release.tag_name).ok_or_else(|| {
+        AppError::message(format!(
+            "Latest release isn't a version: {}",
+            release.tag_name
+        ))
+    })?;
+    Ok(Release {
+        version,
+        url: release.html_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> Version {
+        parse_version(version).unwrap()
+    }
+
+    fn release(version: &str) -> Release {
+        Release {
+            version: self::version(version),
+            url: format!("https://github.com/shank03/balti/releases/tag/v{version}"),
+        }
+    }
+
+    #[test]
+    fn parses_release_tags() {
+        assert_eq!(version("v0.9.0"), Version::new(0, 9, 0));
+        assert_eq!(version(" 1.2.3 "), Version::new(1, 2, 3));
+        assert_eq!(version("0.4.0-beta.2").pre.as_str(), "beta.2");
+        assert_eq!(parse_version("0.9"), None);
+        assert_eq!(parse_version("nightly"), None);
+    }
+
+    #[test]
+    fn counts_minor_versions_behind() {
+        let behind = |current: &str, latest: &str| {
+            minor_versions_behind(&version(current), &version(latest))
+        };
+        assert_eq!(behind("0.4.2", "0.9.0"), 5);
+        assert_eq!(behind("0.9.0", "0.9.3"), 0);
+        assert_eq!(behind("0.9.0", "0.9.0"), 0);
+        assert_eq!(behind("0.10.0", "0.9.0"), 0);
+        // a pre-release comes before its release
+        assert_eq!(behind("0.9.0-beta.1", "0.9.0"), 0);
+        assert_eq!(behind("0.8.0-beta.1", "0.9.0"), 1);
+        assert_eq!(behind("0.9.4", "1.0.0"), u64::MAX);
+    }
+
+    #[test]
+    fn nudges_once_per_release_past_the_threshold() {
+        let latest = release("0.9.0");
+        assert!(should_nudge("0.4.0", &latest, 3, None));
+        assert!(!should_nudge("0.6.0", &latest, 3, None));
+        assert!(should_nudge("0.5.1", &latest, 3, Some("0.8.0")));
+        // dismissed for this release, the next one nudges again
+        assert!(!should_nudge("0.4.0", &latest, 3, Some("0.9.0")));
+        assert!(should_nudge("0.4.0", &release("0.10.0"), 3, Some("0.9.0")));
+        assert!(!should_nudge("dev", &latest, 0, None));
+    }
+}
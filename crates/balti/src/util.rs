@@ -1,4 +1,4 @@
-use std::sync::LazyLock;
+use std::{cmp::Ordering, sync::LazyLock};
 
 use regex::Regex;
 
@@ -30,6 +30,65 @@ pub fn human_readable_size(bytes: i64) -> gpui::SharedString {
 /// ------- Yanked from https://github.com/zed-industries/zed/blob/main/crates/client/src/telemetry.rs
 ///
 
+/// Shortens `text` to `max_chars` by cutting out its middle, both ends stay readable
+pub fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_owned();
+    }
+
+    let keep = max_chars.saturating_sub(1);
+    let head = text.chars().take(keep.div_ceil(2)).collect::<String>();
+    let tail = text.chars().skip(len - keep / 2).collect::<String>();
+    format!("{head}…{tail}")
+}
+
+/// Orders names the way people read them: case-insensitive, with runs of digits compared
+/// by value so `file2.txt` comes before `file10.txt`. Names equal that way, like `a` and
+/// `A`, fall back to their raw order so no two keys compare equal.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    natural_order(a, b).then_with(|| a.cmp(b))
+}
+
+fn natural_order(mut a: &str, mut b: &str) -> Ordering {
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return b.is_empty().cmp(&a.is_empty());
+        };
+
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (number_a, rest_a) = split_digits(a);
+            let (number_b, rest_b) = split_digits(b);
+            let (number_a, number_b) = (
+                number_a.trim_start_matches('0'),
+                number_b.trim_start_matches('0'),
+            );
+            let ordering = number_a
+                .len()
+                .cmp(&number_b.len())
+                .then_with(|| number_a.cmp(number_b));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            let ordering = x.to_lowercase().cmp(y.to_lowercase());
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+/// Leading ASCII digits of `text` and what follows them
+fn split_digits(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
 pub fn os_name() -> String {
     #[cfg(target_os = "macos")]
     {
@@ -50,6 +109,36 @@ pub fn os_name() -> String {
     }
 }
 
+/// Login the app runs under
+pub fn user_name() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok().filter(|name| !name.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Note: This might do blocking IO! Only call from background threads
+pub fn host_name() -> String {
+    #[cfg(target_os = "windows")]
+    let name = std::env::var("COMPUTERNAME").ok();
+
+    #[cfg(not(target_os = "windows"))]
+    let name = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        });
+
+    name.map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
 #[cfg(target_os = "macos")]
 static MACOS_VERSION_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(\s*\(Build [^)]*[0-9]\))").unwrap());
@@ -74,14 +163,14 @@ pub fn os_version() -> String {
     {
         use std::path::Path;
 
-        let content = if let Ok(file) = std::fs::read_to_string(&Path::new("/etc/os-release")) {
+        let content = if let Ok(file) = std::fs::read_to_string(Path::new("/etc/os-release")) {
             file
-        } else if let Ok(file) = std::fs::read_to_string(&Path::new("/usr/lib/os-release")) {
+        } else if let Ok(file) = std::fs::read_to_string(Path::new("/usr/lib/os-release")) {
             file
-        } else if let Ok(file) = std::fs::read_to_string(&Path::new("/var/run/os-release")) {
+        } else if let Ok(file) = std::fs::read_to_string(Path::new("/var/run/os-release")) {
             file
         } else {
-            log::error!(
+            tracing::error!(
                 "Failed to load /etc/os-release, /usr/lib/os-release, or /var/run/os-release"
             );
             "".to_string()
@@ -105,14 +194,64 @@ pub fn os_version() -> String {
         let mut info = unsafe { std::mem::zeroed() };
         let status = unsafe { windows::Wdk::System::SystemServices::RtlGetVersion(&mut info) };
         if status.is_ok() {
-            semver::Version::new(
-                info.dwMajorVersion as _,
-                info.dwMinorVersion as _,
-                info.dwBuildNumber as _,
+            format!(
+                "{}.{}.{}",
+                info.dwMajorVersion, info.dwMinorVersion, info.dwBuildNumber
             )
-            .to_string()
         } else {
             "unknown".to_string()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&'static str]) -> Vec<&'static str> {
+        let mut names = names.to_vec();
+        names.sort_by(|a, b| natural_cmp(a, b));
+        names
+    }
+
+    #[test]
+    fn compares_numbers_by_value() {
+        assert_eq!(
+            sorted(&["file10.txt", "file2.txt", "file1.txt", "file02.txt"]),
+            ["file1.txt", "file02.txt", "file2.txt", "file10.txt"]
+        );
+        assert_eq!(
+            sorted(&["v1.10.0", "v1.9.2", "v1.9.10"]),
+            ["v1.9.2", "v1.9.10", "v1.10.0"]
+        );
+        assert_eq!(
+            sorted(&["99999999999999999999999", "100000000000000000000000", "7"]),
+            ["7", "99999999999999999999999", "100000000000000000000000"]
+        );
+        assert_eq!(sorted(&["a1b", "a", "a1", "1"]), ["1", "a", "a1", "a1b"]);
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert_eq!(
+            sorted(&["banana", "Apple", "cherry", "apple"]),
+            ["Apple", "apple", "banana", "cherry"]
+        );
+        assert_eq!(natural_cmp("README", "readme"), "README".cmp("readme"));
+        assert_eq!(natural_cmp("b", "b"), Ordering::Equal);
+    }
+
+    #[test]
+    fn handles_unicode() {
+        assert_eq!(
+            sorted(&["Éclair", "zebra", "éclair", "Ärger", "apfel"]),
+            ["apfel", "zebra", "Ärger", "Éclair", "éclair"]
+        );
+        // digits other than ASCII ones compare as text
+        assert_eq!(sorted(&["٣", "2", "10"]), ["2", "10", "٣"]);
+        assert_eq!(
+            sorted(&["日本2", "日本10", "日本1"]),
+            ["日本1", "日本2", "日本10"]
+        );
+    }
+}
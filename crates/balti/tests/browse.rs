@@ -0,0 +1,207 @@
+//! Browse flows against a bucket in memory: listing a prefix page by page, creating a
+//! folder and deleting, through the listing state the browse view keeps
+
+use std::{collections::BTreeSet, sync::Arc};
+
+use balti::{listing::Listing, selection::Selection};
+use balti_s3::{__S3Object, FOLDER_MARKER, ObjectPage, S3Object};
+
+/// Entries a page of the fake store holds
+const PAGE_SIZE: usize = 2;
+/// Pages a listing streams before waiting for "Load more"
+const STREAMED_PAGES: usize = 2;
+
+/// Bucket in memory answering shallow listings a page at a time, like S3 with `/`
+/// as the delimiter
+#[derive(Default)]
+struct FakeStore {
+    keys: BTreeSet<String>,
+}
+
+impl FakeStore {
+    fn new(keys: &[&str]) -> Self {
+        Self {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    fn create_folder(&mut self, key: &str) {
+        self.keys
+            .insert(format!("{}/{FOLDER_MARKER}", key.trim_matches('/')));
+    }
+
+    fn delete(&mut self, key: &str) {
+        self.keys.remove(key);
+    }
+
+    fn delete_folder(&mut self, prefix: &str) {
+        self.keys.retain(|key| !key.starts_with(prefix));
+    }
+
+    /// Files and folders right below `prefix` in key order, after `token`
+    fn list(&self, prefix: &str, token: Option<&str>) -> ObjectPage {
+        let entries = self
+            .keys
+            .iter()
+            .filter_map(|key| {
+                let name = key.strip_prefix(prefix)?;
+                Some(match name.split_once('/') {
+                    Some((folder, _)) => format!("{prefix}{folder}/"),
+                    None => key.clone(),
+                })
+            })
+            .collect::<BTreeSet<_>>();
+
+        let mut page = entries
+            .into_iter()
+            .filter(|entry| token.is_none_or(|token| entry.as_str() > token))
+            .take(PAGE_SIZE + 1)
+            .collect::<Vec<_>>();
+        let more = page.len() > PAGE_SIZE;
+        page.truncate(PAGE_SIZE);
+
+        ObjectPage {
+            next_token: more.then(|| page.last().unwrap().as_str().into()),
+            objects: page.into_iter().map(|entry| self.object(entry)).collect(),
+        }
+    }
+
+    fn object(&self, entry: String) -> S3Object {
+        if self.keys.contains(&entry) {
+            Arc::new(__S3Object::file(entry.into(), 2, None, None))
+        } else {
+            Arc::new(__S3Object::Folder(entry.into()))
+        }
+    }
+}
+
+/// Streams pages of `prefix` into `listing` like the browse view does, from the
+/// start or after what's shown
+fn stream(listing: &mut Listing, store: &FakeStore, prefix: &str, restart: bool) {
+    let mut token = match restart {
+        true => None,
+        false => listing.next_token.clone(),
+    };
+    let generation = listing.start(restart);
+
+    let mut replace = restart;
+    for _ in 0..STREAMED_PAGES {
+        let page = store.list(prefix, token.as_deref());
+        token = page.next_token.clone();
+        assert!(listing.apply_page(generation, page, replace));
+        replace = false;
+        if token.is_none() {
+            break;
+        }
+    }
+    assert!(listing.finish(generation));
+}
+
+fn keys(listing: &Listing) -> Vec<&str> {
+    listing
+        .objects
+        .iter()
+        .map(|obj| obj.key().as_ref())
+        .collect()
+}
+
+#[test]
+fn lists_a_prefix_page_by_page() {
+    let store = FakeStore::new(&[
+        "logs/a.txt",
+        "logs/b.txt",
+        "logs/c.txt",
+        "logs/d.txt",
+        "logs/2024/e.txt",
+        "other.txt",
+    ]);
+    let mut listing = Listing::default();
+
+    stream(&mut listing, &store, "logs/", true);
+    assert_eq!(
+        keys(&listing),
+        ["logs/2024/", "logs/a.txt", "logs/b.txt", "logs/c.txt"]
+    );
+    assert!(listing.next_token.is_some());
+    assert!(!listing.loading && !listing.loading_more);
+
+    // "Load more" picks up after what's shown
+    stream(&mut listing, &store, "logs/", false);
+    assert_eq!(
+        keys(&listing),
+        [
+            "logs/2024/",
+            "logs/a.txt",
+            "logs/b.txt",
+            "logs/c.txt",
+            "logs/d.txt"
+        ]
+    );
+    assert!(listing.next_token.is_none());
+}
+
+#[test]
+fn creates_a_folder_then_deletes_it() {
+    let mut store = FakeStore::new(&["readme.md"]);
+    let mut root = Listing::default();
+    stream(&mut root, &store, "", true);
+    assert_eq!(keys(&root), ["readme.md"]);
+
+    store.create_folder("photos");
+    stream(&mut root, &store, "", true);
+    assert_eq!(keys(&root), ["photos/", "readme.md"]);
+
+    // the new folder is empty, its marker keeps it around
+    let mut photos = Listing::default();
+    stream(&mut photos, &store, "photos/", true);
+    assert!(photos.objects.is_empty());
+    assert!(photos.has_marker);
+    assert!(!photos.is_gone());
+
+    let mut selection = Selection::default();
+    selection.set("photos/".into(), true);
+    store.delete_folder("photos/");
+    stream(&mut root, &store, "", true);
+    root.prune(&mut selection);
+    assert_eq!(keys(&root), ["readme.md"]);
+    assert!(selection.is_empty());
+
+    // a view still open on the folder finds it gone once it refreshes
+    stream(&mut photos, &store, "photos/", true);
+    assert!(photos.is_gone());
+}
+
+#[test]
+fn deleting_checked_files_unchecks_only_them() {
+    let mut store = FakeStore::new(&["a.txt", "b.txt", "c.txt"]);
+    let mut listing = Listing::default();
+    stream(&mut listing, &store, "", true);
+
+    let mut selection = Selection::default();
+    selection.set("a.txt".into(), true);
+    selection.set("c.txt".into(), true);
+
+    store.delete("a.txt");
+    stream(&mut listing, &store, "", true);
+    listing.prune(&mut selection);
+
+    assert_eq!(keys(&listing), ["b.txt", "c.txt"]);
+    assert!(!selection.contains("a.txt"));
+    assert!(selection.contains("c.txt"));
+}
+
+#[test]
+fn a_refresh_started_before_a_change_doesnt_undo_it() {
+    let mut store = FakeStore::new(&["a.txt"]);
+    let mut listing = Listing::default();
+
+    let before = listing.start(true);
+    let old_page = store.list("", None);
+    store.create_folder("new");
+    stream(&mut listing, &store, "", true);
+
+    // the answer to the refresh from before lands last
+    assert!(!listing.apply_page(before, old_page, true));
+    assert!(!listing.finish(before));
+    assert_eq!(keys(&listing), ["a.txt", "new/"]);
+}